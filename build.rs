@@ -0,0 +1,25 @@
+//! Build script embedding the git commit hash for the `server_info` tool.
+
+#![allow(
+    clippy::print_stdout,
+    reason = "cargo reads build script directives from stdout"
+)]
+
+use std::process::Command;
+
+/// Exposes the short commit hash as `ZENMONEY_MCP_GIT_HASH` when built from a git checkout.
+fn main() {
+    let hash = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|stdout| stdout.trim().to_owned())
+        .filter(|stdout| !stdout.is_empty());
+    if let Some(commit) = hash {
+        println!("cargo:rustc-env=ZENMONEY_MCP_GIT_HASH={commit}");
+    }
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+}
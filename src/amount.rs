@@ -0,0 +1,222 @@
+//! Amounts given as localized strings.
+//!
+//! Models echo the user's number formatting, so an amount may arrive as
+//! `"1 234,56"` or `"1,234.56"` instead of a JSON number. The create and
+//! update parameters accept both: spaces and apostrophes group thousands,
+//! and of `.` and `,` the last one is the decimal separator. A lone
+//! separator followed by exactly three digits groups thousands when the
+//! integer part is one to three digits other than a lone zero (`"1,234"`,
+//! `"1.234"`), since money rarely has three decimals; otherwise it marks the
+//! decimals (`"0.500"`, `"1234,567"`). Groups after the first always have
+//! three digits, so dates such as `"15.06.2024"` are rejected.
+
+use core::fmt;
+
+use schemars::JsonSchema;
+use serde::de::{self, Unexpected, Visitor};
+use serde::{Deserialize, Deserializer};
+
+/// What an amount may look like, as worded in deserialization errors.
+const EXPECTED_AMOUNT: &str =
+    "an amount as a number or a string such as \"1234.56\", \"1 234,56\" or \"1,234.56\"";
+
+/// An amount as a JSON number or a localized string.
+#[derive(Debug, Clone, JsonSchema)]
+#[serde(untagged)]
+pub(crate) enum RawAmount {
+    /// A plain number.
+    Number(f64),
+    /// A string such as `"1 234,56"` or `"1,234.56"`.
+    Text(String),
+}
+
+impl RawAmount {
+    /// Returns the amount as a number.
+    fn value<E: de::Error>(self) -> Result<f64, E> {
+        match self {
+            Self::Number(value) => Ok(value),
+            Self::Text(text) => parse_amount(&text)
+                .map_err(|_err| E::invalid_value(Unexpected::Str(&text), &AmountVisitor)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for RawAmount {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(AmountVisitor)
+    }
+}
+
+/// Reads a [`RawAmount`], naming the accepted formats when the value is
+/// neither a number nor a string.
+#[derive(Debug, Clone, Copy)]
+struct AmountVisitor;
+
+impl Visitor<'_> for AmountVisitor {
+    type Value = RawAmount;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(EXPECTED_AMOUNT)
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<RawAmount, E> {
+        Ok(RawAmount::Number(value))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<RawAmount, E> {
+        Ok(RawAmount::Text(value.to_string()))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<RawAmount, E> {
+        Ok(RawAmount::Text(value.to_string()))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<RawAmount, E> {
+        Ok(RawAmount::Text(value.to_owned()))
+    }
+
+    fn visit_string<E: de::Error>(self, value: String) -> Result<RawAmount, E> {
+        Ok(RawAmount::Text(value))
+    }
+}
+
+/// Returns whether `separator` at byte `index` of `digits` groups thousands
+/// rather than marking the decimals.
+fn groups_thousands(digits: &str, separator: char, index: usize) -> bool {
+    if digits.matches(separator).count() > 1 {
+        return true;
+    }
+    let before = digits
+        .get(..index)
+        .unwrap_or_default()
+        .trim_start_matches(['-', '+']);
+    let after = digits.get(index.saturating_add(1)..).unwrap_or_default();
+    after.len() == 3 && (1..=3).contains(&before.len()) && before != "0"
+}
+
+/// Returns whether the thousands groups of `integer`, the part of an amount
+/// before its decimal separator, have one to three digits and then three.
+fn valid_groups(integer: &str) -> bool {
+    let mut groups = integer.trim_start_matches(['-', '+']).split(['.', ',']);
+    let first = groups.next().unwrap_or_default();
+    let mut rest = groups.peekable();
+    rest.peek().is_none() || ((1..=3).contains(&first.len()) && rest.all(|group| group.len() == 3))
+}
+
+/// Parses a localized amount such as `"1 234,56"`, `"1,234.56"` or `"-50"`.
+///
+/// # Errors
+///
+/// Returns a message if `text` is not a number in a recognized format.
+pub(crate) fn parse_amount(text: &str) -> Result<f64, String> {
+    let digits: String = text
+        .trim()
+        .chars()
+        .filter(|ch| !ch.is_whitespace() && !matches!(ch, '\'' | '\u{2019}'))
+        .collect();
+    let invalid = || format!("invalid amount '{text}'; expected a number such as 1234.56");
+    if digits.is_empty()
+        || !digits
+            .chars()
+            .all(|ch| ch.is_ascii_digit() || matches!(ch, '.' | ',' | '-' | '+'))
+    {
+        return Err(invalid());
+    }
+    let decimal = match (digits.rfind('.'), digits.rfind(',')) {
+        (Some(dot), Some(comma)) => {
+            let separator = if dot > comma { '.' } else { ',' };
+            if digits.matches(separator).count() > 1 {
+                return Err(invalid());
+            }
+            Some(dot.max(comma))
+        }
+        (Some(dot), None) => (!groups_thousands(&digits, '.', dot)).then_some(dot),
+        (None, Some(comma)) => (!groups_thousands(&digits, ',', comma)).then_some(comma),
+        (None, None) => None,
+    };
+    let integer = decimal.map_or(digits.as_str(), |index| {
+        digits.get(..index).unwrap_or_default()
+    });
+    if !valid_groups(integer) {
+        return Err(invalid());
+    }
+    let normalized: String = digits
+        .char_indices()
+        .filter_map(|(index, ch)| match ch {
+            '.' | ',' if Some(index) == decimal => Some('.'),
+            '.' | ',' => None,
+            other => Some(other),
+        })
+        .collect();
+    normalized.parse().map_err(|_err| invalid())
+}
+
+/// Deserializes an amount given as a number or a localized string.
+///
+/// # Errors
+///
+/// Returns an error if the value is neither a number nor a parsable string.
+pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    RawAmount::deserialize(deserializer)?.value()
+}
+
+/// Deserializes an optional amount given as a number or a localized string.
+///
+/// # Errors
+///
+/// Returns an error if the value is neither null, a number nor a parsable
+/// string.
+pub(crate) fn deserialize_option<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<f64>, D::Error> {
+    Option::<RawAmount>::deserialize(deserializer)?
+        .map(RawAmount::value)
+        .transpose()
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect for readability"
+)]
+mod tests {
+    use super::parse_amount;
+
+    #[test]
+    fn parses_localized_amounts() {
+        let cases = [
+            ("1 234,56", 1234.56),
+            ("1,234.56", 1234.56),
+            ("1.234,56", 1234.56),
+            ("1\u{a0}234", 1234.0),
+            ("1'234.5", 1234.5),
+            ("1,234", 1234.0),
+            ("1,234,567", 1_234_567.0),
+            ("0.500", 0.5),
+            ("-0,500", -0.5),
+            ("1234,567", 1234.567),
+            ("12,5", 12.5),
+            ("300", 300.0),
+            ("-50,00", -50.0),
+        ];
+        for (text, expected) in cases {
+            let parsed = parse_amount(text).expect("valid amount");
+            assert!(
+                (parsed - expected).abs() < 1e-9,
+                "{text} parsed as {parsed}"
+            );
+        }
+        for text in [
+            "",
+            "12 usd",
+            "1,2,3.4.5",
+            "inf",
+            "1.2.3",
+            "15.06.2024",
+            "1,2,3.4",
+        ] {
+            assert!(parse_amount(text).is_err(), "{text} should be rejected");
+        }
+    }
+}
@@ -0,0 +1,2409 @@
+//! Aggregation helpers for transaction analytics.
+//!
+//! These functions operate on already-filtered transactions and the
+//! [`LookupMaps`] used for display, so they stay independent of the
+//! client and storage layers.
+
+extern crate alloc;
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use core::ops::Bound;
+use std::collections::{HashMap, HashSet};
+
+use chrono::{Datelike as _, Months, NaiveDate, Weekday};
+use zenmoney_rs::models::{
+    Account, Budget, Reminder, ReminderMarker, ReminderMarkerState, TagId, Transaction,
+};
+
+use crate::envelopes::Envelope;
+use crate::loans::add_intervals;
+use crate::mcc::mcc_label_or_code;
+use crate::params::{
+    BudgetBasis, Granularity, GroupBy, NearFilter, PayeeDimension, RankBy, StatementCycle,
+    TransactionType,
+};
+use crate::response::{
+    AccountActivity, AccountChange, AccountTransfers, BalanceCheck, BalancePoint, BillTotals,
+    BudgetAdherence, BudgetMonth, BudgetOverrun, BudgetProgress, BudgetResponse, BudgetSuggestion,
+    CategoryAverage, CategoryDelta, CategorySpend, CurrencyTotals, DebtCounterparty,
+    EnvelopeStatus, LookupMaps, PatternBucket, PayeeSpend, ReminderTotals, SavingsMonth, TagUsage,
+    TransactionGroup, TransactionSummary, TransferFlow, UpcomingBill,
+};
+use crate::server::classify_transaction;
+
+/// Label used for transactions without a category tag.
+const UNCATEGORIZED_LABEL: &str = "Uncategorized";
+
+/// Label used for transactions without a payee.
+const NO_PAYEE_LABEL: &str = "(no payee)";
+
+/// Label used for transactions without a merchant.
+const NO_MERCHANT_LABEL: &str = "(no merchant)";
+
+/// Label used for transactions without a merchant category code.
+const NO_MCC_LABEL: &str = "(no MCC)";
+
+/// Minimum number of samples before an anomaly baseline is trusted.
+const MIN_BASELINE_SAMPLES: usize = 5;
+
+/// Scales the median absolute deviation to a standard-deviation estimate.
+const MAD_SCALE: f64 = 1.4826;
+
+/// Scales the mean absolute deviation when the median absolute deviation is zero.
+const MEAN_AD_SCALE: f64 = 1.253_314;
+
+/// Debt balances closer to zero than this are reported as settled.
+const DEBT_SETTLED_EPSILON: f64 = 0.005;
+
+/// Mean Earth radius in kilometers, used for great-circle distances.
+const EARTH_RADIUS_KM: f64 = 6_371.0;
+
+/// Upper bound on reminder intervals stepped through when projecting due dates.
+const MAX_REMINDER_STEPS: u32 = 20_000;
+
+/// Returns the `(key, label)` pair a transaction belongs to for a grouping.
+fn group_key(tx: &Transaction, group_by: GroupBy, maps: &LookupMaps) -> (String, String) {
+    match group_by {
+        GroupBy::Tag => tx.tag.as_deref().and_then(<[_]>::first).map_or_else(
+            || (String::new(), UNCATEGORIZED_LABEL.to_owned()),
+            |tag_id| (tag_id.to_string(), maps.tag_name(tag_id.as_inner())),
+        ),
+        GroupBy::Payee => tx
+            .payee
+            .as_deref()
+            .filter(|payee| !payee.is_empty())
+            .map_or_else(
+                || (String::new(), NO_PAYEE_LABEL.to_owned()),
+                |payee| (payee.to_owned(), payee.to_owned()),
+            ),
+        GroupBy::Account => {
+            let account = match classify_transaction(tx) {
+                TransactionType::Income => &tx.income_account,
+                TransactionType::Expense | TransactionType::Transfer => &tx.outcome_account,
+            };
+            (account.to_string(), maps.account_name(account.as_inner()))
+        }
+        GroupBy::Merchant => tx.merchant.as_ref().map_or_else(
+            || (String::new(), NO_MERCHANT_LABEL.to_owned()),
+            |merchant_id| {
+                (
+                    merchant_id.to_string(),
+                    maps.merchant_name(merchant_id.as_inner()),
+                )
+            },
+        ),
+        GroupBy::Month => {
+            let month = tx.date.format("%Y-%m").to_string();
+            (month.clone(), month)
+        }
+        GroupBy::Weekday => {
+            let weekday = tx.date.weekday();
+            (
+                weekday.number_from_monday().to_string(),
+                weekday.to_string(),
+            )
+        }
+        GroupBy::Mcc => tx.mcc.map_or_else(
+            || (String::new(), NO_MCC_LABEL.to_owned()),
+            |code| (code.to_string(), mcc_label_or_code(code)),
+        ),
+    }
+}
+
+/// Replaces each transaction's tags with their top-level parents.
+///
+/// Duplicate parents are collapsed, so a transaction tagged with two
+/// children of the same parent counts once for that parent.
+pub(crate) fn roll_up_tags(transactions: &mut [Transaction], maps: &LookupMaps) {
+    for tx in transactions {
+        let Some(tags) = tx.tag.as_mut() else {
+            continue;
+        };
+        let mut rolled: Vec<TagId> = Vec::with_capacity(tags.len());
+        for tag in tags.iter() {
+            let root = maps.root_tag(tag.as_inner());
+            if !rolled.iter().any(|existing| existing.as_inner() == root) {
+                rolled.push(TagId::new(root.to_owned()));
+            }
+        }
+        *tags = rolled;
+    }
+}
+
+/// Returns `true` if the transaction carries `tag_id` or one of its children.
+pub(crate) fn has_tag_or_child(tx: &Transaction, tag_id: &str, maps: &LookupMaps) -> bool {
+    tx.tag
+        .as_deref()
+        .unwrap_or_default()
+        .iter()
+        .any(|tag| maps.is_tag_within(tag.as_inner(), tag_id))
+}
+
+/// Great-circle (haversine) distance between two points, in kilometers.
+fn distance_km(lat_a: f64, lon_a: f64, lat_b: f64, lon_b: f64) -> f64 {
+    let half_lat = (lat_b - lat_a).to_radians() / 2.0_f64;
+    let half_lon = (lon_b - lon_a).to_radians() / 2.0_f64;
+    let hav = lat_a.to_radians().cos().mul_add(
+        lat_b.to_radians().cos() * half_lon.sin().powi(2),
+        half_lat.sin().powi(2),
+    );
+    2.0_f64 * EARTH_RADIUS_KM * hav.sqrt().asin()
+}
+
+/// Returns `true` if the transaction has a location inside the `near` circle.
+pub(crate) fn is_near(tx: &Transaction, near: NearFilter) -> bool {
+    match (tx.latitude, tx.longitude) {
+        (Some(latitude), Some(longitude)) => {
+            distance_km(latitude, longitude, near.latitude, near.longitude) <= near.radius_km
+        }
+        _ => false,
+    }
+}
+
+/// Converts an amount to the requested base currency, keeping it as-is
+/// when no conversion is requested or the source rate is unknown.
+///
+/// Totals built from these amounts list the currencies kept as-is with
+/// [`unconverted_currencies`].
+fn maybe_convert(maps: &LookupMaps, amount: f64, from: i32, convert_to: Option<i32>) -> f64 {
+    convert_to
+        .and_then(|to| maps.convert(amount, from, to))
+        .unwrap_or(amount)
+}
+
+/// Returns the symbols of the currencies that income and expenses among
+/// `transactions` cannot be converted from into `convert_to`, for lack of a
+/// rate. Converted totals count these amounts at face value.
+pub(crate) fn unconverted_currencies(
+    transactions: &[Transaction],
+    maps: &LookupMaps,
+    convert_to: Option<i32>,
+) -> Vec<String> {
+    let Some(to) = convert_to else {
+        return Vec::new();
+    };
+    let mut symbols = BTreeSet::new();
+    for tx in transactions {
+        let (amount, from) = match classify_transaction(tx) {
+            TransactionType::Income => (tx.income, tx.income_instrument.into_inner()),
+            TransactionType::Expense => (tx.outcome, tx.outcome_instrument.into_inner()),
+            TransactionType::Transfer => continue,
+        };
+        if maps.convert(amount, from, to).is_none() {
+            let _added = symbols.insert(maps.instrument_symbol(from));
+        }
+    }
+    symbols.into_iter().collect()
+}
+
+/// Aggregates transactions into groups with counts and income/outcome sums.
+///
+/// Month and weekday groups are ordered chronologically; all other groups
+/// are ordered by total turnover, largest first.
+pub(crate) fn group_transactions(
+    transactions: &[Transaction],
+    group_by: GroupBy,
+    maps: &LookupMaps,
+    convert_to: Option<i32>,
+) -> Vec<TransactionGroup> {
+    let mut groups: HashMap<String, TransactionGroup> = HashMap::new();
+    for tx in transactions {
+        let (key, label) = group_key(tx, group_by, maps);
+        let group = groups
+            .entry(key.clone())
+            .or_insert_with(|| TransactionGroup {
+                key,
+                label,
+                count: 0,
+                income: 0.0,
+                outcome: 0.0,
+            });
+        group.count = group.count.saturating_add(1);
+        group.income += maybe_convert(
+            maps,
+            tx.income,
+            tx.income_instrument.into_inner(),
+            convert_to,
+        );
+        group.outcome += maybe_convert(
+            maps,
+            tx.outcome,
+            tx.outcome_instrument.into_inner(),
+            convert_to,
+        );
+    }
+
+    let mut result: Vec<TransactionGroup> = groups.into_values().collect();
+    match group_by {
+        GroupBy::Month => result.sort_by(|a, b| a.key.cmp(&b.key)),
+        GroupBy::Weekday => result.sort_by_key(|group| group.key.parse::<u32>().unwrap_or(0)),
+        GroupBy::Tag | GroupBy::Payee | GroupBy::Account | GroupBy::Merchant | GroupBy::Mcc => {
+            result.sort_by(|a, b| (b.income + b.outcome).total_cmp(&(a.income + a.outcome)));
+        }
+    }
+    result
+}
+
+/// Totals income and outcome over all `transactions`.
+///
+/// Transfers are counted but left out of the totals. Per-currency totals
+/// use native amounts; the overall totals are converted to `convert_to`, or
+/// given only when every amount shares one currency.
+/// Amounts without a rate to `convert_to` are counted at face value and
+/// their currencies listed as unconverted.
+pub(crate) fn summarize_transactions(
+    transactions: &[Transaction],
+    maps: &LookupMaps,
+    convert_to: Option<i32>,
+) -> TransactionSummary {
+    let mut native: BTreeMap<i32, (f64, f64)> = BTreeMap::new();
+    let (mut income, mut outcome) = (0.0_f64, 0.0_f64);
+    let mut transfer_count: usize = 0;
+    for tx in transactions {
+        match classify_transaction(tx) {
+            TransactionType::Transfer => transfer_count = transfer_count.saturating_add(1),
+            TransactionType::Income => {
+                native
+                    .entry(tx.income_instrument.into_inner())
+                    .or_default()
+                    .0 += tx.income;
+                income += income_amount(tx, maps, convert_to);
+            }
+            TransactionType::Expense => {
+                native
+                    .entry(tx.outcome_instrument.into_inner())
+                    .or_default()
+                    .1 += tx.outcome;
+                outcome += expense_amount(tx, maps, convert_to);
+            }
+        }
+    }
+    let single = native.keys().next().copied().filter(|_| native.len() == 1);
+    let currency = convert_to.or(single).map(|id| maps.instrument_symbol(id));
+    let has_totals = convert_to.is_some() || native.len() <= 1;
+    TransactionSummary {
+        count: transactions.len(),
+        transfer_count,
+        currency,
+        income: has_totals.then(|| round_cents(income)),
+        outcome: has_totals.then(|| round_cents(outcome)),
+        net: has_totals.then(|| round_cents(income - outcome)),
+        by_currency: native
+            .into_iter()
+            .map(|(id, (native_income, native_outcome))| CurrencyTotals {
+                currency: maps.instrument_symbol(id),
+                income: round_cents(native_income),
+                outcome: round_cents(native_outcome),
+                net: round_cents(native_income - native_outcome),
+            })
+            .collect(),
+        unconverted: unconverted_currencies(transactions, maps, convert_to),
+    }
+}
+
+/// Returns the expense amount of a transaction, converted if requested.
+pub(crate) fn expense_amount(tx: &Transaction, maps: &LookupMaps, convert_to: Option<i32>) -> f64 {
+    maybe_convert(
+        maps,
+        tx.outcome,
+        tx.outcome_instrument.into_inner(),
+        convert_to,
+    )
+}
+
+/// Returns the income amount of a transaction, converted if requested.
+pub(crate) fn income_amount(tx: &Transaction, maps: &LookupMaps, convert_to: Option<i32>) -> f64 {
+    maybe_convert(
+        maps,
+        tx.income,
+        tx.income_instrument.into_inner(),
+        convert_to,
+    )
+}
+
+/// Computes envelope balances for `month` (`YYYY-MM`) with rollover.
+///
+/// `expenses` must cover every month since the first allocation up to the
+/// end of `month`. Each expense is charged to the first envelope whose tags
+/// (or their children) it carries; spending before `month` reduces the
+/// carried-over balance.
+pub(crate) fn envelope_balances(
+    envelopes: &[Envelope],
+    month: &str,
+    expenses: &[Transaction],
+    maps: &LookupMaps,
+    convert_to: Option<i32>,
+) -> Vec<EnvelopeStatus> {
+    let mut spent_before = vec![0.0_f64; envelopes.len()];
+    let mut spent_in = vec![0.0_f64; envelopes.len()];
+    for tx in expenses {
+        let Some(index) = envelopes.iter().position(|envelope| {
+            envelope
+                .tag_ids
+                .iter()
+                .any(|tag_id| has_tag_or_child(tx, tag_id, maps))
+        }) else {
+            continue;
+        };
+        let tx_month = tx.date.format("%Y-%m").to_string();
+        let bucket = if tx_month.as_str() < month {
+            spent_before.get_mut(index)
+        } else {
+            spent_in.get_mut(index)
+        };
+        if let Some(total) = bucket {
+            *total += expense_amount(tx, maps, convert_to);
+        }
+    }
+
+    envelopes
+        .iter()
+        .zip(spent_before)
+        .zip(spent_in)
+        .map(|((envelope, before), spent)| {
+            let carried_over = envelope.allocated_before(month) - before;
+            let allocated = envelope.allocated_in(month);
+            let available = carried_over + allocated - spent;
+            EnvelopeStatus {
+                name: envelope.name.clone(),
+                tags: envelope
+                    .tag_ids
+                    .iter()
+                    .map(|tag_id| maps.tag_name(tag_id))
+                    .collect(),
+                carried_over,
+                allocated,
+                spent,
+                available,
+                overspent: available < 0.0_f64,
+            }
+        })
+        .collect()
+}
+
+/// Rounds an amount to two decimals for compact output.
+pub(crate) fn round_cents(amount: f64) -> f64 {
+    (amount * 100.0_f64).round() / 100.0_f64
+}
+
+/// Returns a short description of a transaction: payee, merchant, or category.
+pub(crate) fn transaction_label(tx: &Transaction, maps: &LookupMaps) -> String {
+    if let Some(payee) = tx.payee.as_deref().filter(|payee| !payee.is_empty()) {
+        return payee.to_owned();
+    }
+    if let Some(merchant_id) = tx.merchant.as_ref() {
+        return maps.merchant_name(merchant_id.as_inner());
+    }
+    group_key(tx, GroupBy::Tag, maps).1
+}
+
+/// Compares expense budgets with actual spending in their categories.
+///
+/// `budgets` and `expenses` should cover the same month. Spending includes
+/// child tags of the budget tag. Only exceeded budgets are returned,
+/// largest overrun first.
+pub(crate) fn budget_overruns(
+    budgets: &[Budget],
+    expenses: &[Transaction],
+    maps: &LookupMaps,
+    convert_to: Option<i32>,
+) -> Vec<BudgetOverrun> {
+    let mut overruns: Vec<BudgetOverrun> = budgets
+        .iter()
+        .filter(|budget| budget.outcome > 0.0_f64)
+        .filter_map(|budget| {
+            let tag_id = budget.tag.as_ref()?.as_inner();
+            let spent: f64 = expenses
+                .iter()
+                .filter(|tx| has_tag_or_child(tx, tag_id, maps))
+                .map(|tx| expense_amount(tx, maps, convert_to))
+                .sum();
+            (spent > budget.outcome).then(|| BudgetOverrun {
+                category: maps.tag_name(tag_id),
+                budget: budget.outcome,
+                spent,
+                over: spent - budget.outcome,
+            })
+        })
+        .collect();
+    overruns.sort_by(|a, b| b.over.total_cmp(&a.over));
+    overruns
+}
+
+/// Sums the net balance change of each account, in the account's currency.
+///
+/// Accounts whose change rounds to zero are skipped; the rest are ordered
+/// by the size of the change, largest first.
+pub(crate) fn account_changes(
+    transactions: &[Transaction],
+    maps: &LookupMaps,
+) -> Vec<AccountChange> {
+    let mut totals: HashMap<&str, (f64, i32)> = HashMap::new();
+    for tx in transactions {
+        let income = totals
+            .entry(tx.income_account.as_inner())
+            .or_insert((0.0_f64, tx.income_instrument.into_inner()));
+        income.0 += tx.income;
+        let outcome = totals
+            .entry(tx.outcome_account.as_inner())
+            .or_insert((0.0_f64, tx.outcome_instrument.into_inner()));
+        outcome.0 -= tx.outcome;
+    }
+
+    let mut changes: Vec<AccountChange> = totals
+        .into_iter()
+        .filter(|&(_, (change, _))| round_cents(change).abs() > 0.0_f64)
+        .map(|(account_id, (change, instrument))| AccountChange {
+            account: maps.account_name(account_id),
+            currency: maps.instrument_symbol(instrument),
+            change,
+        })
+        .collect();
+    changes.sort_by(|a, b| b.change.abs().total_cmp(&a.change.abs()));
+    changes
+}
+
+/// Number of day-of-month buckets in spending pattern reports.
+const DAY_OF_MONTH_BUCKETS: u32 = 10;
+
+/// Returns the day-of-month bucket (0-9) a day of the month falls into.
+const fn day_bucket(day: u32) -> u32 {
+    day.saturating_sub(1) * DAY_OF_MONTH_BUCKETS / 31
+}
+
+/// Running totals for one weekday or day-of-month bucket.
+#[derive(Default)]
+struct PatternAccumulator {
+    /// Calendar days in the range that fall into the bucket.
+    days: u32,
+    /// Total spending.
+    total: f64,
+    /// Category name → total spending.
+    categories: HashMap<String, f64>,
+}
+
+impl PatternAccumulator {
+    /// Adds an expense to the bucket.
+    fn add(&mut self, category: String, amount: f64) {
+        self.total += amount;
+        *self.categories.entry(category).or_insert(0.0_f64) += amount;
+    }
+
+    /// Converts the totals into per-day averages.
+    fn finish(self, label: String) -> PatternBucket {
+        let days = f64::from(self.days.max(1));
+        let mut categories: Vec<CategoryAverage> = self
+            .categories
+            .into_iter()
+            .map(|(category, total)| CategoryAverage {
+                category,
+                daily_average: total / days,
+            })
+            .collect();
+        categories.sort_by(|a, b| b.daily_average.total_cmp(&a.daily_average));
+        PatternBucket {
+            label,
+            days: self.days,
+            total: self.total,
+            daily_average: self.total / days,
+            categories,
+        }
+    }
+}
+
+/// Averages daily spending by weekday and by day-of-month bucket.
+///
+/// Averages divide by every calendar day of the range in the bucket, so
+/// days without spending count as zero. Returns seven weekday buckets
+/// (Monday first) and ten day-of-month buckets, each with a per-category
+/// breakdown.
+pub(crate) fn spending_patterns(
+    expenses: &[Transaction],
+    from: NaiveDate,
+    to: NaiveDate,
+    maps: &LookupMaps,
+    convert_to: Option<i32>,
+) -> (Vec<PatternBucket>, Vec<PatternBucket>) {
+    let mut weekdays: Vec<PatternAccumulator> =
+        core::iter::repeat_with(PatternAccumulator::default)
+            .take(7)
+            .collect();
+    let mut month_days: Vec<PatternAccumulator> = (0..DAY_OF_MONTH_BUCKETS)
+        .map(|_| PatternAccumulator::default())
+        .collect();
+    let buckets_of = |date: NaiveDate| {
+        (
+            usize::try_from(date.weekday().num_days_from_monday()).unwrap_or_default(),
+            usize::try_from(day_bucket(date.day())).unwrap_or_default(),
+        )
+    };
+
+    for day in from.iter_days().take_while(|day| *day <= to) {
+        let (weekday, month_day) = buckets_of(day);
+        if let Some(bucket) = weekdays.get_mut(weekday) {
+            bucket.days = bucket.days.saturating_add(1);
+        }
+        if let Some(bucket) = month_days.get_mut(month_day) {
+            bucket.days = bucket.days.saturating_add(1);
+        }
+    }
+    for tx in expenses
+        .iter()
+        .filter(|tx| tx.date >= from && tx.date <= to)
+    {
+        let (weekday, month_day) = buckets_of(tx.date);
+        let category = group_key(tx, GroupBy::Tag, maps).1;
+        let amount = expense_amount(tx, maps, convert_to);
+        if let Some(bucket) = weekdays.get_mut(weekday) {
+            bucket.add(category.clone(), amount);
+        }
+        if let Some(bucket) = month_days.get_mut(month_day) {
+            bucket.add(category, amount);
+        }
+    }
+
+    let weekday_buckets = weekdays
+        .into_iter()
+        .zip(core::iter::successors(Some(Weekday::Mon), |day| {
+            Some(day.succ())
+        }))
+        .map(|(bucket, weekday)| bucket.finish(weekday.to_string()))
+        .collect();
+    let month_day_buckets = month_days
+        .into_iter()
+        .zip(0_u32..)
+        .map(|(bucket, index)| {
+            let days: Vec<u32> = (1..=31).filter(|day| day_bucket(*day) == index).collect();
+            let label = format!(
+                "{}-{}",
+                days.first().copied().unwrap_or_default(),
+                days.last().copied().unwrap_or_default()
+            );
+            bucket.finish(label)
+        })
+        .collect();
+    (weekday_buckets, month_day_buckets)
+}
+
+/// Returns the average daily spending across several buckets.
+pub(crate) fn pooled_daily_average<'bucket, I>(buckets: I) -> f64
+where
+    I: IntoIterator<Item = &'bucket PatternBucket>,
+{
+    let (total, days) = buckets
+        .into_iter()
+        .fold((0.0_f64, 0_u32), |(total, days), bucket| {
+            (total + bucket.total, days.saturating_add(bucket.days))
+        });
+    total / f64::from(days.max(1))
+}
+
+/// Returns the running transfer totals of an account, creating them on first use.
+fn account_transfers<'map, 'id>(
+    accounts: &'map mut HashMap<&'id str, AccountTransfers>,
+    account_id: &'id str,
+    instrument: i32,
+    maps: &LookupMaps,
+) -> &'map mut AccountTransfers {
+    accounts
+        .entry(account_id)
+        .or_insert_with(|| AccountTransfers {
+            account: maps.account_name(account_id),
+            currency: maps.instrument_symbol(instrument),
+            transferred_in: 0.0,
+            transferred_out: 0.0,
+            net: 0.0,
+        })
+}
+
+/// Aggregates transfers into account-to-account flows and per-account totals.
+///
+/// Flows are keyed by source and destination account and ordered by
+/// number of transfers, busiest first. Amounts stay in each side's own
+/// currency unless `convert_to` is set. Non-transfer transactions are
+/// ignored.
+pub(crate) fn transfer_matrix(
+    transactions: &[Transaction],
+    maps: &LookupMaps,
+    convert_to: Option<i32>,
+) -> (Vec<TransferFlow>, Vec<AccountTransfers>) {
+    let mut flows: HashMap<(&str, &str), TransferFlow> = HashMap::new();
+    let mut accounts: HashMap<&str, AccountTransfers> = HashMap::new();
+    for tx in transactions
+        .iter()
+        .filter(|tx| matches!(classify_transaction(tx), TransactionType::Transfer))
+    {
+        let from = tx.outcome_account.as_inner();
+        let to = tx.income_account.as_inner();
+        let from_instrument = convert_to.unwrap_or_else(|| tx.outcome_instrument.into_inner());
+        let to_instrument = convert_to.unwrap_or_else(|| tx.income_instrument.into_inner());
+        let sent = expense_amount(tx, maps, convert_to);
+        let received = income_amount(tx, maps, convert_to);
+
+        let flow = flows.entry((from, to)).or_insert_with(|| TransferFlow {
+            from_account: maps.account_name(from),
+            to_account: maps.account_name(to),
+            count: 0,
+            sent: 0.0,
+            sent_currency: maps.instrument_symbol(from_instrument),
+            received: 0.0,
+            received_currency: maps.instrument_symbol(to_instrument),
+        });
+        flow.count = flow.count.saturating_add(1);
+        flow.sent += sent;
+        flow.received += received;
+
+        let source = account_transfers(&mut accounts, from, from_instrument, maps);
+        source.transferred_out += sent;
+        source.net -= sent;
+        let destination = account_transfers(&mut accounts, to, to_instrument, maps);
+        destination.transferred_in += received;
+        destination.net += received;
+    }
+
+    let mut flow_list: Vec<TransferFlow> = flows.into_values().collect();
+    flow_list.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.from_account.cmp(&b.from_account))
+            .then_with(|| a.to_account.cmp(&b.to_account))
+    });
+    let mut account_list: Vec<AccountTransfers> = accounts.into_values().collect();
+    account_list.sort_by(|a, b| a.account.cmp(&b.account));
+    (flow_list, account_list)
+}
+
+/// Net amount a transfer moves into savings accounts (negative when withdrawn).
+///
+/// Transfers between two savings accounts, or between two other accounts,
+/// do not change savings.
+fn savings_transfer(tx: &Transaction, maps: &LookupMaps, convert_to: Option<i32>) -> f64 {
+    let into_savings = maps.is_savings(tx.income_account.as_inner());
+    let out_of_savings = maps.is_savings(tx.outcome_account.as_inner());
+    match (out_of_savings, into_savings) {
+        (false, true) => income_amount(tx, maps, convert_to),
+        (true, false) => -expense_amount(tx, maps, convert_to),
+        (true, true) | (false, false) => 0.0,
+    }
+}
+
+/// Fills in the net saved amount and the saved share of income.
+///
+/// The share is based on savings transfers when they are tracked and on
+/// income minus expenses otherwise.
+fn settle_savings(month: SavingsMonth) -> SavingsMonth {
+    let net_saved = month.income - month.expense;
+    let saved = month.to_savings.unwrap_or(net_saved);
+    SavingsMonth {
+        net_saved,
+        savings_percent: percent_change(month.income, saved),
+        ..month
+    }
+}
+
+/// Sums income, expenses and optionally savings transfers per calendar month.
+///
+/// Returns the months oldest first together with the totals for all of them.
+pub(crate) fn savings_by_month(
+    transactions: &[Transaction],
+    maps: &LookupMaps,
+    convert_to: Option<i32>,
+    savings_transfers: bool,
+) -> (Vec<SavingsMonth>, SavingsMonth) {
+    let empty = |month: String| SavingsMonth {
+        month,
+        income: 0.0,
+        expense: 0.0,
+        net_saved: 0.0,
+        to_savings: savings_transfers.then_some(0.0),
+        savings_percent: None,
+    };
+    let mut months: BTreeMap<String, SavingsMonth> = BTreeMap::new();
+    for tx in transactions {
+        let key = tx.date.format("%Y-%m").to_string();
+        let month = months.entry(key.clone()).or_insert_with(|| empty(key));
+        match classify_transaction(tx) {
+            TransactionType::Income => month.income += income_amount(tx, maps, convert_to),
+            TransactionType::Expense => month.expense += expense_amount(tx, maps, convert_to),
+            TransactionType::Transfer => {
+                if let Some(saved) = month.to_savings.as_mut() {
+                    *saved += savings_transfer(tx, maps, convert_to);
+                }
+            }
+        }
+    }
+
+    let mut total = empty("total".to_owned());
+    for month in months.values() {
+        total.income += month.income;
+        total.expense += month.expense;
+        if let (Some(sum), Some(saved)) = (total.to_savings.as_mut(), month.to_savings) {
+            *sum += saved;
+        }
+    }
+    let month_list = months.into_values().map(settle_savings).collect();
+    (month_list, settle_savings(total))
+}
+
+/// Checks monthly category budgets against actual spending.
+///
+/// Each budget covers the expenses of its tag (and child tags) in the
+/// budget's month. Overruns are labelled with the month, largest first.
+pub(crate) fn budget_adherence(
+    budgets: &[Budget],
+    expenses: &[Transaction],
+    maps: &LookupMaps,
+    convert_to: Option<i32>,
+) -> BudgetAdherence {
+    let mut adherence = BudgetAdherence {
+        budgets: 0,
+        kept: 0,
+        kept_percent: None,
+        budgeted: 0.0,
+        spent: 0.0,
+        overruns: Vec::new(),
+    };
+    for budget in budgets.iter().filter(|budget| budget.outcome > 0.0_f64) {
+        let Some(tag) = budget.tag.as_ref() else {
+            continue;
+        };
+        let tag_id = tag.as_inner();
+        let spent: f64 = expenses
+            .iter()
+            .filter(|tx| {
+                tx.date.year() == budget.date.year() && tx.date.month() == budget.date.month()
+            })
+            .filter(|tx| has_tag_or_child(tx, tag_id, maps))
+            .map(|tx| expense_amount(tx, maps, convert_to))
+            .sum();
+        adherence.budgets = adherence.budgets.saturating_add(1);
+        adherence.budgeted += budget.outcome;
+        adherence.spent += spent;
+        if spent > budget.outcome {
+            adherence.overruns.push(BudgetOverrun {
+                category: format!(
+                    "{} ({})",
+                    maps.tag_name(tag_id),
+                    budget.date.format("%Y-%m")
+                ),
+                budget: budget.outcome,
+                spent,
+                over: spent - budget.outcome,
+            });
+        } else {
+            adherence.kept = adherence.kept.saturating_add(1);
+        }
+    }
+    adherence.kept_percent =
+        percent_change(f64::from(adherence.budgets), f64::from(adherence.kept));
+    adherence.overruns.sort_by(|a, b| b.over.total_cmp(&a.over));
+    adherence
+}
+
+/// Running totals for one payee while building the `top_payees` report.
+struct PayeeAccumulator {
+    /// Entry being accumulated (categories filled in at the end).
+    spend: PayeeSpend,
+    /// Category name → running spend.
+    categories: HashMap<String, CategorySpend>,
+}
+
+/// Sums expense transactions per payee or merchant with a category breakdown.
+///
+/// Transactions without a payee (or merchant) are skipped. Entries are
+/// returned unordered; use [`rank_payees`] to sort them.
+pub(crate) fn payee_spend(
+    transactions: &[Transaction],
+    dimension: PayeeDimension,
+    maps: &LookupMaps,
+    convert_to: Option<i32>,
+) -> Vec<PayeeSpend> {
+    let group_by = match dimension {
+        PayeeDimension::Payee => GroupBy::Payee,
+        PayeeDimension::Merchant => GroupBy::Merchant,
+    };
+    let mut entries: HashMap<String, PayeeAccumulator> = HashMap::new();
+    for tx in transactions {
+        let (key, label) = group_key(tx, group_by, maps);
+        if key.is_empty() {
+            continue;
+        }
+        let amount = expense_amount(tx, maps, convert_to);
+        let (_tag_key, category) = group_key(tx, GroupBy::Tag, maps);
+        let acc = entries
+            .entry(key.clone())
+            .or_insert_with(|| PayeeAccumulator {
+                spend: PayeeSpend {
+                    key,
+                    label,
+                    count: 0,
+                    total: 0.0,
+                    categories: Vec::new(),
+                },
+                categories: HashMap::new(),
+            });
+        acc.spend.count = acc.spend.count.saturating_add(1);
+        acc.spend.total += amount;
+        let category_spend =
+            acc.categories
+                .entry(category.clone())
+                .or_insert_with(|| CategorySpend {
+                    category,
+                    count: 0,
+                    amount: 0.0,
+                });
+        category_spend.count = category_spend.count.saturating_add(1);
+        category_spend.amount += amount;
+    }
+
+    entries
+        .into_values()
+        .map(|acc| {
+            let mut entry = acc.spend;
+            entry.categories = acc.categories.into_values().collect();
+            entry
+                .categories
+                .sort_by(|a, b| b.amount.total_cmp(&a.amount));
+            entry
+        })
+        .collect()
+}
+
+/// Sorts report entries by the requested metric, largest first.
+pub(crate) fn rank_payees(entries: &mut [PayeeSpend], rank_by: RankBy) {
+    match rank_by {
+        RankBy::Amount => entries.sort_by(|a, b| b.total.total_cmp(&a.total)),
+        RankBy::Count => entries.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| b.total.total_cmp(&a.total))
+        }),
+    }
+}
+
+/// Returns `delta` as a percentage of `base`, or `None` when `base` is zero.
+pub(crate) fn percent_change(base: f64, delta: f64) -> Option<f64> {
+    (base.abs() > f64::EPSILON).then(|| delta / base * 100.0_f64)
+}
+
+/// Picks the amount a group contributes for the compared transaction type.
+const fn group_amount(group: &TransactionGroup, kind: &TransactionType) -> f64 {
+    match *kind {
+        TransactionType::Income => group.income,
+        TransactionType::Expense | TransactionType::Transfer => group.outcome,
+    }
+}
+
+/// Compares per-category totals of two periods.
+///
+/// Categories present in only one period get zero in the other. The result
+/// is ordered by delta, largest increase first.
+pub(crate) fn compare_categories(
+    base: &[TransactionGroup],
+    compare: &[TransactionGroup],
+    kind: &TransactionType,
+) -> Vec<CategoryDelta> {
+    let mut totals: HashMap<&str, (String, f64, f64)> = HashMap::new();
+    for group in base {
+        let entry = totals
+            .entry(group.key.as_str())
+            .or_insert_with(|| (group.label.clone(), 0.0_f64, 0.0_f64));
+        entry.1 += group_amount(group, kind);
+    }
+    for group in compare {
+        let entry = totals
+            .entry(group.key.as_str())
+            .or_insert_with(|| (group.label.clone(), 0.0_f64, 0.0_f64));
+        entry.2 += group_amount(group, kind);
+    }
+
+    let mut deltas: Vec<CategoryDelta> = totals
+        .into_values()
+        .map(|(category, base_total, compare_total)| {
+            let delta = compare_total - base_total;
+            CategoryDelta {
+                category,
+                base: base_total,
+                compare: compare_total,
+                delta,
+                delta_percent: percent_change(base_total, delta),
+            }
+        })
+        .collect();
+    deltas.sort_by(|a, b| b.delta.total_cmp(&a.delta));
+    deltas
+}
+
+/// Returns the closing date in the month of `month_start`, clamped to its length.
+fn closing_date(month_start: NaiveDate, closing_day: u32) -> Option<NaiveDate> {
+    let month_end = month_start.checked_add_months(Months::new(1))?.pred_opt()?;
+    month_start.with_day(closing_day.min(month_end.day()))
+}
+
+/// Returns the first and last day of a credit card statement cycle.
+///
+/// A cycle ends on `closing_day` (clamped to short months) and starts the
+/// day after the previous closing date. The current cycle is the one that
+/// contains `today`.
+pub(crate) fn statement_period(
+    closing_day: u32,
+    today: NaiveDate,
+    cycle: StatementCycle,
+) -> Option<(NaiveDate, NaiveDate)> {
+    let this_month = today.with_day(1)?;
+    let current_end_month = if today <= closing_date(this_month, closing_day)? {
+        this_month
+    } else {
+        this_month.checked_add_months(Months::new(1))?
+    };
+    let end_month = match cycle {
+        StatementCycle::Current => current_end_month,
+        StatementCycle::Previous => current_end_month.checked_sub_months(Months::new(1))?,
+    };
+    let previous_month = end_month.checked_sub_months(Months::new(1))?;
+    let start = closing_date(previous_month, closing_day)?.succ_opt()?;
+    Some((start, closing_date(end_month, closing_day)?))
+}
+
+/// Sums the net effect of transactions on an account per day.
+///
+/// Income into the account adds to the balance, outcome from it subtracts.
+pub(crate) fn daily_net_changes(
+    transactions: &[Transaction],
+    account_id: &str,
+) -> BTreeMap<NaiveDate, f64> {
+    let mut net: BTreeMap<NaiveDate, f64> = BTreeMap::new();
+    for tx in transactions {
+        let mut change = 0.0_f64;
+        if tx.income_account.as_inner() == account_id {
+            change += tx.income;
+        }
+        if tx.outcome_account.as_inner() == account_id {
+            change -= tx.outcome;
+        }
+        *net.entry(tx.date).or_insert(0.0_f64) += change;
+    }
+    net
+}
+
+/// Returns `true` if the date is the last day of its month.
+fn is_month_end(date: NaiveDate) -> bool {
+    date.succ_opt()
+        .is_none_or(|next| next.month() != date.month())
+}
+
+/// Reconstructs end-of-day balances by walking back from the current balance.
+///
+/// All changes dated after `to` are undone first, so `current_balance` must
+/// reflect every transaction in `net`. With [`Granularity::Month`] only
+/// month ends (and `to` itself) are returned. Points are chronological.
+pub(crate) fn balance_history(
+    net: &BTreeMap<NaiveDate, f64>,
+    current_balance: f64,
+    from: NaiveDate,
+    to: NaiveDate,
+    granularity: Granularity,
+) -> Vec<BalancePoint> {
+    let later_changes: f64 = net
+        .range((Bound::Excluded(to), Bound::Unbounded))
+        .map(|(_date, change)| change)
+        .sum();
+    let mut balance = current_balance - later_changes;
+    let mut points: Vec<BalancePoint> = Vec::new();
+    let mut day = to;
+    while day >= from {
+        if granularity == Granularity::Day || day == to || is_month_end(day) {
+            points.push(BalancePoint {
+                date: day.to_string(),
+                balance,
+            });
+        }
+        balance -= net.get(&day).copied().unwrap_or(0.0_f64);
+        match day.pred_opt() {
+            Some(previous) => day = previous,
+            None => break,
+        }
+    }
+    points.reverse();
+    points
+}
+
+/// Returns the median of a sample, sorting it in place.
+fn median(values: &mut [f64]) -> Option<f64> {
+    values.sort_by(f64::total_cmp);
+    let mid = values.len().div_euclid(2);
+    let upper = *values.get(mid)?;
+    if values.len().is_multiple_of(2) {
+        let lower = *values.get(mid.checked_sub(1)?)?;
+        Some(f64::midpoint(lower, upper))
+    } else {
+        Some(upper)
+    }
+}
+
+/// Robust location and spread of a sample of amounts.
+struct RobustStats {
+    /// Median amount.
+    median: f64,
+    /// Standard-deviation estimate derived from absolute deviations.
+    scale: f64,
+    /// Number of samples.
+    samples: usize,
+}
+
+impl RobustStats {
+    /// Computes robust statistics, or `None` for small or constant samples.
+    ///
+    /// Uses the median absolute deviation, falling back to the mean absolute
+    /// deviation when more than half of the samples are identical.
+    fn from_samples(samples: &[f64]) -> Option<Self> {
+        if samples.len() < MIN_BASELINE_SAMPLES {
+            return None;
+        }
+        let median = median(&mut samples.to_vec())?;
+        let mut deviations: Vec<f64> = samples.iter().map(|value| (value - median).abs()).collect();
+        let mean_deviation =
+            deviations.iter().sum::<f64>() / f64::from(u32::try_from(deviations.len()).ok()?);
+        let mad = self::median(&mut deviations)?;
+        let scale = if mad > f64::EPSILON {
+            mad * MAD_SCALE
+        } else {
+            mean_deviation * MEAN_AD_SCALE
+        };
+        (scale > f64::EPSILON).then_some(Self {
+            median,
+            scale,
+            samples: samples.len(),
+        })
+    }
+
+    /// Returns the modified z-score of an amount.
+    fn score(&self, amount: f64) -> f64 {
+        (amount - self.median) / self.scale
+    }
+}
+
+/// Transaction flagged by [`detect_anomalies`].
+pub(crate) struct Anomaly<'tx> {
+    /// Flagged transaction.
+    pub(crate) tx: &'tx Transaction,
+    /// Highest score across the baselines that flagged it.
+    pub(crate) score: f64,
+    /// Human-readable reasons.
+    pub(crate) reasons: Vec<String>,
+}
+
+/// Returns the `(baseline key, description)` pairs a transaction is compared against.
+fn baseline_keys(tx: &Transaction, maps: &LookupMaps) -> Vec<(String, String)> {
+    let mut keys = Vec::with_capacity(2);
+    let (tag_key, tag_label) = group_key(tx, GroupBy::Tag, maps);
+    keys.push((format!("tag:{tag_key}"), format!("category '{tag_label}'")));
+    let (payee_key, payee_label) = group_key(tx, GroupBy::Payee, maps);
+    if !payee_key.is_empty() {
+        keys.push((
+            format!("payee:{}", payee_key.to_lowercase()),
+            format!("payee '{payee_label}'"),
+        ));
+    }
+    keys
+}
+
+/// Flags candidate expenses that are much larger than usual for their
+/// category or payee.
+///
+/// Baselines are built from `history` per category and per payee; a
+/// candidate is flagged when its modified z-score against any baseline with
+/// enough samples exceeds `threshold`. Results are ordered by score, highest first.
+pub(crate) fn detect_anomalies<'tx>(
+    history: &[Transaction],
+    candidates: &'tx [Transaction],
+    maps: &LookupMaps,
+    threshold: f64,
+    convert_to: Option<i32>,
+) -> Vec<Anomaly<'tx>> {
+    let mut samples: HashMap<String, Vec<f64>> = HashMap::new();
+    for tx in history {
+        let amount = expense_amount(tx, maps, convert_to);
+        for (key, _description) in baseline_keys(tx, maps) {
+            samples.entry(key).or_default().push(amount);
+        }
+    }
+    let baselines: HashMap<String, RobustStats> = samples
+        .into_iter()
+        .filter_map(|(key, values)| RobustStats::from_samples(&values).map(|stats| (key, stats)))
+        .collect();
+
+    let mut anomalies: Vec<Anomaly<'tx>> = Vec::new();
+    for tx in candidates {
+        let amount = expense_amount(tx, maps, convert_to);
+        let mut score = f64::NEG_INFINITY;
+        let mut reasons: Vec<String> = Vec::new();
+        for (key, description) in baseline_keys(tx, maps) {
+            let Some(stats) = baselines.get(&key) else {
+                continue;
+            };
+            let z = stats.score(amount);
+            if z > threshold {
+                score = score.max(z);
+                reasons.push(format!(
+                    "{amount:.2} vs median {:.2} for {description} (n={}, score {z:.1})",
+                    stats.median, stats.samples
+                ));
+            }
+        }
+        if !reasons.is_empty() {
+            anomalies.push(Anomaly { tx, score, reasons });
+        }
+    }
+    anomalies.sort_by(|a, b| b.score.total_cmp(&a.score));
+    anomalies
+}
+
+/// Computes usage statistics for the transactions carrying one tag.
+///
+/// Spend windows are counted back from `today` and include only expenses.
+pub(crate) fn tag_usage(
+    transactions: &[Transaction],
+    today: NaiveDate,
+    maps: &LookupMaps,
+    convert_to: Option<i32>,
+) -> TagUsage {
+    let three_months_ago = today
+        .checked_sub_months(Months::new(3))
+        .unwrap_or(NaiveDate::MIN);
+    let year_ago = today
+        .checked_sub_months(Months::new(12))
+        .unwrap_or(NaiveDate::MIN);
+    let mut spend_last_3_months = 0.0_f64;
+    let mut spend_last_12_months = 0.0_f64;
+    for tx in transactions {
+        if !matches!(classify_transaction(tx), TransactionType::Expense) || tx.date <= year_ago {
+            continue;
+        }
+        let amount = expense_amount(tx, maps, convert_to);
+        spend_last_12_months += amount;
+        if tx.date > three_months_ago {
+            spend_last_3_months += amount;
+        }
+    }
+    TagUsage {
+        transaction_count: transactions.len(),
+        last_used: transactions
+            .iter()
+            .map(|tx| tx.date)
+            .max()
+            .map(|date| date.to_string()),
+        spend_last_3_months,
+        spend_last_12_months,
+    }
+}
+
+/// Computes the activity of one account from its transactions and reminders.
+///
+/// Flows are in the account's currency and cover the calendar month of `today`.
+pub(crate) fn account_activity(
+    account_id: &str,
+    transactions: &[Transaction],
+    reminders: &[Reminder],
+    today: NaiveDate,
+) -> AccountActivity {
+    let month_start = today.with_day(1).unwrap_or(today);
+    let mut month_inflow = 0.0_f64;
+    let mut month_outflow = 0.0_f64;
+    for tx in transactions.iter().filter(|tx| tx.date >= month_start) {
+        if tx.income_account.as_inner() == account_id {
+            month_inflow += tx.income;
+        }
+        if tx.outcome_account.as_inner() == account_id {
+            month_outflow += tx.outcome;
+        }
+    }
+    let mut pending_reminders = ReminderTotals::default();
+    for reminder in reminders
+        .iter()
+        .filter(|reminder| reminder.end_date.is_none_or(|end| end >= today))
+    {
+        let into = reminder.income_account.as_inner() == account_id;
+        let from = reminder.outcome_account.as_inner() == account_id;
+        if into {
+            pending_reminders.income += reminder.income;
+        }
+        if from {
+            pending_reminders.outcome += reminder.outcome;
+        }
+        if into || from {
+            pending_reminders.count = pending_reminders.count.saturating_add(1);
+        }
+    }
+    AccountActivity {
+        month_inflow: round_cents(month_inflow),
+        month_outflow: round_cents(month_outflow),
+        last_transaction: transactions
+            .iter()
+            .map(|tx| tx.date)
+            .max()
+            .map(|date| date.to_string()),
+        pending_reminders,
+    }
+}
+
+/// Recomputes the balance of each account that has one stored.
+///
+/// The computed balance is the start balance (0 when unset) plus the income
+/// and minus the outcome of every non-deleted transaction on the account.
+pub(crate) fn check_balances(
+    accounts: &[Account],
+    transactions: &[Transaction],
+    maps: &LookupMaps,
+) -> Vec<BalanceCheck> {
+    let mut totals: HashMap<&str, (f64, usize)> = HashMap::new();
+    for tx in transactions.iter().filter(|tx| !tx.deleted) {
+        let income = totals.entry(tx.income_account.as_inner()).or_default();
+        income.0 += tx.income;
+        income.1 = income.1.saturating_add(1);
+        let outcome = totals.entry(tx.outcome_account.as_inner()).or_default();
+        outcome.0 -= tx.outcome;
+        if tx.outcome_account.as_inner() != tx.income_account.as_inner() {
+            outcome.1 = outcome.1.saturating_add(1);
+        }
+    }
+    accounts
+        .iter()
+        .filter_map(|account| {
+            let stored_balance = account.balance?;
+            let start_balance = account.start_balance.unwrap_or_default();
+            let (change, transaction_count) = totals
+                .get(account.id.as_inner())
+                .copied()
+                .unwrap_or_default();
+            let computed_balance = round_cents(start_balance + change);
+            Some(BalanceCheck {
+                account_id: account.id.to_string(),
+                account: account.title.clone(),
+                currency: account
+                    .instrument
+                    .map(|id| maps.instrument_symbol(id.into_inner()))
+                    .unwrap_or_default(),
+                start_balance,
+                transaction_count,
+                computed_balance,
+                stored_balance,
+                difference: round_cents(stored_balance - computed_balance),
+            })
+        })
+        .collect()
+}
+
+/// Groups budgets by month, oldest first, with the target totals of each.
+pub(crate) fn budget_months(budgets: &[Budget], maps: &LookupMaps) -> Vec<BudgetMonth> {
+    let mut by_month: BTreeMap<NaiveDate, Vec<&Budget>> = BTreeMap::new();
+    for budget in budgets {
+        by_month.entry(budget.date).or_default().push(budget);
+    }
+    by_month
+        .into_iter()
+        .map(|(month, entries)| BudgetMonth {
+            month: month.format("%Y-%m").to_string(),
+            income: round_cents(entries.iter().map(|budget| budget.income).sum()),
+            outcome: round_cents(entries.iter().map(|budget| budget.outcome).sum()),
+            budgets: entries
+                .into_iter()
+                .map(|budget| BudgetResponse::from_budget(budget, maps))
+                .collect(),
+        })
+        .collect()
+}
+
+/// Tracks spending against the outcome budgets of the last month in `months`.
+///
+/// `months` lists the first days of the reported month and, before it, the
+/// earlier months to roll over, oldest first. Every earlier month in which a
+/// category had a budget carries `budget - spent` forward. Spending includes
+/// child tags of the budget tag. Categories are ordered least remaining first.
+pub(crate) fn budget_progress(
+    budgets: &[Budget],
+    expenses: &[Transaction],
+    months: &[NaiveDate],
+    maps: &LookupMaps,
+    convert_to: Option<i32>,
+) -> Vec<BudgetProgress> {
+    let Some((&month, earlier)) = months.split_last() else {
+        return Vec::new();
+    };
+    let spent_in = |tag_id: &str, first_day: NaiveDate| -> f64 {
+        expenses
+            .iter()
+            .filter(|tx| tx.date.year() == first_day.year() && tx.date.month() == first_day.month())
+            .filter(|tx| has_tag_or_child(tx, tag_id, maps))
+            .map(|tx| expense_amount(tx, maps, convert_to))
+            .sum()
+    };
+    let mut progress: Vec<BudgetProgress> = budgets
+        .iter()
+        .filter(|budget| budget.date == month && budget.outcome > 0.0_f64)
+        .filter_map(|budget| {
+            let tag_id = budget.tag.as_ref()?.as_inner();
+            let carried_over: f64 = budgets
+                .iter()
+                .filter(|earlier_budget| earlier.contains(&earlier_budget.date))
+                .filter(|earlier_budget| {
+                    earlier_budget.tag.as_ref().map(TagId::as_inner) == Some(tag_id)
+                })
+                .map(|earlier_budget| {
+                    earlier_budget.outcome - spent_in(tag_id, earlier_budget.date)
+                })
+                .sum();
+            let available = budget.outcome + carried_over;
+            let spent = spent_in(tag_id, month);
+            Some(BudgetProgress {
+                tag_id: tag_id.to_owned(),
+                tag: maps.tag_name(tag_id),
+                budget: round_cents(budget.outcome),
+                carried_over: round_cents(carried_over),
+                available: round_cents(available),
+                spent: round_cents(spent),
+                remaining: round_cents(available - spent),
+                percent_used: (available > 0.0_f64)
+                    .then(|| round_cents(spent / available * 100.0_f64)),
+            })
+        })
+        .collect();
+    progress.sort_by(|a, b| a.remaining.total_cmp(&b.remaining));
+    progress
+}
+
+/// Proposes a monthly expense budget per category from past spending.
+///
+/// Each expense counts toward its first tag in the month it falls in, and
+/// months of `history` without spending count as zero. The proposed amount
+/// is the median or average of the monthly totals, largest first.
+pub(crate) fn suggest_budget_amounts(
+    expenses: &[Transaction],
+    history: &[NaiveDate],
+    maps: &LookupMaps,
+    basis: BudgetBasis,
+    convert_to: Option<i32>,
+) -> Vec<BudgetSuggestion> {
+    let mut by_tag: BTreeMap<&str, BTreeMap<NaiveDate, f64>> = BTreeMap::new();
+    for tx in expenses {
+        let Some(tag) = tx.tag.as_deref().and_then(<[_]>::first) else {
+            continue;
+        };
+        let month = tx.date.with_day(1).unwrap_or(tx.date);
+        *by_tag
+            .entry(tag.as_inner())
+            .or_default()
+            .entry(month)
+            .or_default() += expense_amount(tx, maps, convert_to);
+    }
+    let month_count = f64::from(u32::try_from(history.len()).unwrap_or(u32::MAX).max(1));
+    let mut suggestions: Vec<BudgetSuggestion> = by_tag
+        .into_iter()
+        .map(|(tag_id, spent)| {
+            let mut totals: Vec<f64> = history
+                .iter()
+                .map(|month| spent.get(month).copied().unwrap_or_default())
+                .collect();
+            let average = totals.iter().sum::<f64>() / month_count;
+            let middle = median(&mut totals).unwrap_or_default();
+            let amount = match basis {
+                BudgetBasis::Median => middle,
+                BudgetBasis::Average => average,
+            };
+            BudgetSuggestion {
+                tag_id: tag_id.to_owned(),
+                tag: maps.tag_name(tag_id),
+                months_with_spending: spent.len(),
+                median: round_cents(middle),
+                average: round_cents(average),
+                amount: round_cents(amount),
+                current: None,
+            }
+        })
+        .filter(|suggestion| suggestion.amount > 0.0_f64)
+        .collect();
+    suggestions.sort_by(|a, b| b.amount.total_cmp(&a.amount));
+    suggestions
+}
+
+/// Maps each account ID to the date of its latest non-deleted transaction.
+pub(crate) fn last_activity_by_account(transactions: &[Transaction]) -> HashMap<String, NaiveDate> {
+    let mut last: HashMap<String, NaiveDate> = HashMap::new();
+    for tx in transactions.iter().filter(|tx| !tx.deleted) {
+        for account in [&tx.income_account, &tx.outcome_account] {
+            let latest = last.entry(account.as_inner().to_owned()).or_insert(tx.date);
+            *latest = (*latest).max(tx.date);
+        }
+    }
+    last
+}
+
+/// Returns the status label for a counterparty's net debt balance.
+const fn debt_status(balance: f64) -> &'static str {
+    if balance > DEBT_SETTLED_EPSILON {
+        "owes_you"
+    } else if balance < -DEBT_SETTLED_EPSILON {
+        "you_owe"
+    } else {
+        "settled"
+    }
+}
+
+/// Nets transactions against Debt accounts into per-counterparty balances.
+///
+/// Money moved into a debt account counts as lent (the counterparty owes
+/// more); money moved out counts as received. Counterparties are keyed by
+/// payee and currency, largest outstanding balance first.
+pub(crate) fn debt_balances(
+    transactions: &[Transaction],
+    debt_accounts: &HashSet<String>,
+    maps: &LookupMaps,
+    convert_to: Option<i32>,
+) -> Vec<DebtCounterparty> {
+    let mut balances: HashMap<(String, i32), DebtCounterparty> = HashMap::new();
+    for tx in transactions {
+        let into_debt = debt_accounts.contains(tx.income_account.as_inner());
+        let out_of_debt = debt_accounts.contains(tx.outcome_account.as_inner());
+        let (raw_lent, raw_received, instrument) = match (into_debt, out_of_debt) {
+            (true, false) => (tx.income, 0.0_f64, tx.income_instrument.into_inner()),
+            (false, true) => (0.0_f64, tx.outcome, tx.outcome_instrument.into_inner()),
+            (true, true) | (false, false) => continue,
+        };
+        let lent = maybe_convert(maps, raw_lent, instrument, convert_to);
+        let received = maybe_convert(maps, raw_received, instrument, convert_to);
+        let currency = convert_to.unwrap_or(instrument);
+        let payee = tx
+            .payee
+            .as_deref()
+            .filter(|payee| !payee.is_empty())
+            .map(str::to_owned)
+            .or_else(|| {
+                tx.merchant
+                    .as_ref()
+                    .map(|id| maps.merchant_name(id.as_inner()))
+            })
+            .unwrap_or_else(|| NO_PAYEE_LABEL.to_owned());
+        let date = tx.date.to_string();
+
+        let entry = balances
+            .entry((payee.clone(), currency))
+            .or_insert_with(|| DebtCounterparty {
+                payee,
+                currency: maps.instrument_symbol(currency),
+                balance: 0.0,
+                lent: 0.0,
+                received: 0.0,
+                count: 0,
+                last_date: String::new(),
+                status: String::new(),
+            });
+        entry.lent += lent;
+        entry.received += received;
+        entry.balance += lent - received;
+        entry.count = entry.count.saturating_add(1);
+        if date > entry.last_date {
+            entry.last_date = date;
+        }
+    }
+
+    let mut result: Vec<DebtCounterparty> = balances
+        .into_values()
+        .map(|mut counterparty| {
+            counterparty.status = debt_status(counterparty.balance).to_owned();
+            counterparty
+        })
+        .collect();
+    result.sort_by(|a, b| b.balance.abs().total_cmp(&a.balance.abs()));
+    result
+}
+
+/// Lists the dates in `from..=to` on which a reminder falls due.
+///
+/// A reminder without an interval falls due once, on its start date; one
+/// with an interval repeats every `step` intervals (1 when unset) until its
+/// end date.
+pub(crate) fn reminder_due_dates(
+    reminder: &Reminder,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Vec<NaiveDate> {
+    let last = reminder.end_date.map_or(to, |end| end.min(to));
+    let Some(interval) = reminder.interval else {
+        return core::iter::once(reminder.start_date)
+            .filter(|date| *date >= from && *date <= last)
+            .collect();
+    };
+    let every = reminder
+        .step
+        .and_then(|step| u32::try_from(step).ok())
+        .filter(|&step| step > 0)
+        .unwrap_or(1);
+    let mut dates = Vec::new();
+    for index in 0..MAX_REMINDER_STEPS {
+        let Some(date) = index
+            .checked_mul(every)
+            .and_then(|count| add_intervals(reminder.start_date, interval, count))
+        else {
+            break;
+        };
+        if date > last {
+            break;
+        }
+        if date >= from {
+            dates.push(date);
+        }
+    }
+    dates
+}
+
+/// Period and matching rules for [`upcoming_bills`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BillWindow {
+    /// First day of the period.
+    pub(crate) from: NaiveDate,
+    /// Last day of the period.
+    pub(crate) to: NaiveDate,
+    /// Unpaid occurrences before this date are overdue.
+    pub(crate) today: NaiveDate,
+    /// Days around the due date within which an unlinked payment matches.
+    pub(crate) match_days: u64,
+}
+
+/// Finds the unused transaction closest to `due` that looks like a payment
+/// of `reminder`: it leaves the reminder's account within `match_days` of
+/// the due date and shares its payee (case-insensitive) or one of its tags.
+fn closest_payment<'tx>(
+    reminder: &Reminder,
+    due: NaiveDate,
+    transactions: &'tx [Transaction],
+    used: &HashSet<&str>,
+    match_days: u64,
+) -> Option<&'tx Transaction> {
+    let tags: HashSet<&str> = reminder.tag.iter().flatten().map(TagId::as_inner).collect();
+    let payee = reminder.payee.as_deref().map(str::to_lowercase);
+    let distance = |tx: &Transaction| tx.date.signed_duration_since(due).num_days().unsigned_abs();
+    transactions
+        .iter()
+        .filter(|tx| {
+            !tx.deleted
+                && tx.reminder_marker.is_none()
+                && tx.outcome > 0.0
+                && tx.outcome_account.as_inner() == reminder.outcome_account.as_inner()
+                && !used.contains(tx.id.as_inner())
+                && distance(tx) <= match_days
+        })
+        .filter(|tx| {
+            let same_payee = payee.as_deref().is_some_and(|wanted| {
+                tx.payee
+                    .as_deref()
+                    .is_some_and(|found| found.to_lowercase() == wanted)
+            });
+            same_payee
+                || tx
+                    .tag
+                    .iter()
+                    .flatten()
+                    .any(|tag| tags.contains(tag.as_inner()))
+        })
+        .min_by_key(|tx| distance(tx))
+}
+
+/// Reconciles the bill occurrences in a period with recorded payments.
+///
+/// Bills are reminders with an outcome. A transaction linked to an
+/// occurrence through its reminder marker pays it; otherwise the closest
+/// matching transaction does (see [`closest_payment`]), each transaction
+/// paying at most one occurrence. Occurrences whose marker was processed
+/// count as paid and deleted ones as skipped; the rest are overdue before
+/// `today` and due from then on.
+pub(crate) fn upcoming_bills(
+    reminders: &[Reminder],
+    markers: &[ReminderMarker],
+    transactions: &[Transaction],
+    window: &BillWindow,
+    maps: &LookupMaps,
+) -> Vec<UpcomingBill> {
+    let marker_keys: HashMap<&str, (&str, NaiveDate)> = markers
+        .iter()
+        .map(|marker| {
+            (
+                marker.id.as_inner(),
+                (marker.reminder.as_inner(), marker.date),
+            )
+        })
+        .collect();
+    let states: HashMap<(&str, NaiveDate), ReminderMarkerState> = markers
+        .iter()
+        .map(|marker| ((marker.reminder.as_inner(), marker.date), marker.state))
+        .collect();
+    let linked: HashMap<(&str, NaiveDate), &Transaction> = transactions
+        .iter()
+        .filter(|tx| !tx.deleted)
+        .filter_map(|tx| {
+            let marker = tx.reminder_marker.as_ref()?;
+            marker_keys.get(marker.as_inner()).map(|key| (*key, tx))
+        })
+        .collect();
+    let mut used: HashSet<&str> = linked.values().map(|tx| tx.id.as_inner()).collect();
+
+    let mut occurrences: Vec<(&Reminder, NaiveDate)> = reminders
+        .iter()
+        .filter(|reminder| reminder.outcome > 0.0)
+        .flat_map(|reminder| {
+            reminder_due_dates(reminder, window.from, window.to)
+                .into_iter()
+                .map(move |due| (reminder, due))
+        })
+        .collect();
+    occurrences.sort_by_key(|occurrence| occurrence.1);
+
+    let mut bills = Vec::with_capacity(occurrences.len());
+    for (reminder, due) in occurrences {
+        let key = (reminder.id.as_inner(), due);
+        let state = states.get(&key).copied();
+        let skipped = state == Some(ReminderMarkerState::Deleted);
+        let payment = linked.get(&key).copied().or_else(|| {
+            (!skipped)
+                .then(|| closest_payment(reminder, due, transactions, &used, window.match_days))
+                .flatten()
+        });
+        if let Some(tx) = payment {
+            let _inserted = used.insert(tx.id.as_inner());
+        }
+        let status = if payment.is_some() || state == Some(ReminderMarkerState::Processed) {
+            "paid"
+        } else if skipped {
+            "skipped"
+        } else if due < window.today {
+            "overdue"
+        } else {
+            "due"
+        };
+        bills.push(UpcomingBill::new(reminder, due, status, payment, maps));
+    }
+    bills
+}
+
+/// Sums paid and outstanding bill amounts per currency.
+pub(crate) fn bill_totals(bills: &[UpcomingBill]) -> Vec<BillTotals> {
+    let mut totals: BTreeMap<&str, BillTotals> = BTreeMap::new();
+    for bill in bills {
+        let entry = totals
+            .entry(bill.currency.as_str())
+            .or_insert_with(|| BillTotals {
+                currency: bill.currency.clone(),
+                ..BillTotals::default()
+            });
+        match bill.status.as_str() {
+            "paid" => entry.paid += bill.paid_amount.unwrap_or(bill.amount),
+            "due" | "overdue" => entry.remaining += bill.amount,
+            _ => {}
+        }
+    }
+    totals
+        .into_values()
+        .map(|total| BillTotals {
+            paid: round_cents(total.paid),
+            remaining: round_cents(total.remaining),
+            ..total
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::indexing_slicing,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect and indexing for readability"
+)]
+mod tests {
+    use std::collections::HashSet;
+
+    use chrono::{DateTime, NaiveDate};
+    use zenmoney_rs::models::{
+        Account, AccountId, AccountType, Budget, Instrument, InstrumentId, Interval, Reminder,
+        ReminderId, Tag, TagId, Transaction, TransactionId, UserId,
+    };
+
+    use super::{
+        account_changes, balance_history, budget_adherence, budget_overruns, compare_categories,
+        daily_net_changes, debt_balances, detect_anomalies, envelope_balances, group_transactions,
+        has_tag_or_child, is_near, payee_spend, pooled_daily_average, rank_payees,
+        reminder_due_dates, roll_up_tags, round_cents, spending_patterns, statement_period,
+        summarize_transactions, tag_usage, transaction_label, transfer_matrix,
+    };
+    use crate::envelopes::Envelope;
+    use crate::params::{
+        Granularity, GroupBy, NearFilter, PayeeDimension, RankBy, StatementCycle, TransactionType,
+    };
+    use crate::response::{LookupMaps, build_lookup_maps};
+
+    fn sample_maps() -> LookupMaps {
+        let changed = DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp");
+        let accounts = vec![Account {
+            id: AccountId::new("acc-1".to_owned()),
+            changed,
+            user: UserId::new(1),
+            role: None,
+            instrument: Some(InstrumentId::new(1)),
+            company: None,
+            kind: AccountType::Checking,
+            title: "Main Account".to_owned(),
+            sync_id: None,
+            balance: Some(0.0),
+            start_balance: None,
+            credit_limit: None,
+            in_balance: true,
+            savings: None,
+            enable_correction: false,
+            enable_sms: false,
+            archive: false,
+            capitalization: None,
+            percent: None,
+            start_date: None,
+            end_date_offset: None,
+            end_date_offset_interval: None,
+            payoff_step: None,
+            payoff_interval: None,
+            balance_correction_type: None,
+            private: None,
+        }];
+        let groceries = Tag {
+            id: TagId::new("tag-1".to_owned()),
+            changed,
+            user: UserId::new(1),
+            title: "Groceries".to_owned(),
+            parent: None,
+            icon: None,
+            picture: None,
+            color: None,
+            show_income: false,
+            show_outcome: true,
+            budget_income: false,
+            budget_outcome: true,
+            required: None,
+            static_id: None,
+            archive: None,
+        };
+        let vegetables = Tag {
+            id: TagId::new("tag-2".to_owned()),
+            title: "Vegetables".to_owned(),
+            parent: Some(TagId::new("tag-1".to_owned())),
+            ..groceries.clone()
+        };
+        let tags = vec![groceries, vegetables];
+        let instruments = vec![
+            Instrument {
+                id: InstrumentId::new(1),
+                changed,
+                title: "Russian Ruble".to_owned(),
+                short_title: "RUB".to_owned(),
+                symbol: "₽".to_owned(),
+                rate: 1.0,
+            },
+            Instrument {
+                id: InstrumentId::new(2),
+                changed,
+                title: "US Dollar".to_owned(),
+                short_title: "USD".to_owned(),
+                symbol: "$".to_owned(),
+                rate: 90.0,
+            },
+        ];
+        build_lookup_maps(&accounts, &tags, &instruments)
+    }
+
+    fn expense(id: &str, outcome: f64, date: NaiveDate, tag: Option<&str>) -> Transaction {
+        let changed = DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp");
+        Transaction {
+            id: TransactionId::new(id.to_owned()),
+            changed,
+            created: changed,
+            user: UserId::new(1),
+            deleted: false,
+            hold: None,
+            income_instrument: InstrumentId::new(1),
+            income_account: AccountId::new("acc-1".to_owned()),
+            income: 0.0,
+            outcome_instrument: InstrumentId::new(1),
+            outcome_account: AccountId::new("acc-1".to_owned()),
+            outcome,
+            tag: tag.map(|tag_id| vec![TagId::new(tag_id.to_owned())]),
+            merchant: None,
+            payee: None,
+            original_payee: None,
+            comment: None,
+            date,
+            mcc: None,
+            reminder_marker: None,
+            op_income: None,
+            op_income_instrument: None,
+            op_outcome: None,
+            op_outcome_instrument: None,
+            latitude: None,
+            longitude: None,
+            income_bank_id: None,
+            outcome_bank_id: None,
+            qr_code: None,
+            source: None,
+            viewed: None,
+        }
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).expect("valid date")
+    }
+
+    #[test]
+    fn group_by_tag_sums_and_orders_by_turnover() {
+        let maps = sample_maps();
+        let txs = vec![
+            expense("a", 100.0, date(2024, 6, 1), Some("tag-1")),
+            expense("b", 200.0, date(2024, 6, 2), Some("tag-1")),
+            expense("c", 50.0, date(2024, 6, 3), None),
+        ];
+        let groups = group_transactions(&txs, GroupBy::Tag, &maps, None);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].label, "Groceries");
+        assert_eq!(groups[0].count, 2);
+        assert!((groups[0].outcome - 300.0).abs() < f64::EPSILON);
+        assert_eq!(groups[1].label, "Uncategorized");
+    }
+
+    #[test]
+    fn group_by_mcc_labels_codes() {
+        let maps = sample_maps();
+        let mut grocery = expense("a", 100.0, date(2024, 6, 1), None);
+        grocery.mcc = Some(5411);
+        let txs = vec![grocery, expense("b", 50.0, date(2024, 6, 2), None)];
+        let groups = group_transactions(&txs, GroupBy::Mcc, &maps, None);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].key, "5411");
+        assert_eq!(groups[0].label, "Grocery stores and supermarkets");
+        assert_eq!(groups[1].label, "(no MCC)");
+    }
+
+    #[test]
+    fn is_near_uses_great_circle_distance() {
+        let mut tx = expense("a", 100.0, date(2024, 6, 1), None);
+        // Moscow center, Red Square.
+        let near = NearFilter {
+            latitude: 55.7539,
+            longitude: 37.6208,
+            radius_km: 5.0,
+        };
+        assert!(!is_near(&tx, near));
+        // Bolshoi Theatre, about 0.5 km away.
+        tx.latitude = Some(55.7601);
+        tx.longitude = Some(37.6186);
+        assert!(is_near(&tx, near));
+        // Saint Petersburg, about 630 km away.
+        tx.latitude = Some(59.9343);
+        tx.longitude = Some(30.3351);
+        assert!(!is_near(&tx, near));
+    }
+
+    #[test]
+    fn group_by_month_is_chronological() {
+        let maps = sample_maps();
+        let txs = vec![
+            expense("a", 100.0, date(2024, 7, 1), None),
+            expense("b", 900.0, date(2024, 5, 1), None),
+        ];
+        let groups = group_transactions(&txs, GroupBy::Month, &maps, None);
+        assert_eq!(groups[0].key, "2024-05");
+        assert_eq!(groups[1].key, "2024-07");
+    }
+
+    #[test]
+    fn group_by_weekday_uses_iso_numbers() {
+        let maps = sample_maps();
+        // 2024-06-03 is a Monday, 2024-06-09 is a Sunday.
+        let txs = vec![
+            expense("a", 100.0, date(2024, 6, 9), None),
+            expense("b", 100.0, date(2024, 6, 3), None),
+        ];
+        let groups = group_transactions(&txs, GroupBy::Weekday, &maps, None);
+        assert_eq!(groups[0].key, "1");
+        assert_eq!(groups[0].label, "Mon");
+        assert_eq!(groups[1].key, "7");
+    }
+
+    #[test]
+    fn group_converts_amounts() {
+        let maps = sample_maps();
+        let txs = vec![expense("a", 900.0, date(2024, 6, 1), None)];
+        let groups = group_transactions(&txs, GroupBy::Account, &maps, Some(2));
+        assert_eq!(groups[0].label, "Main Account");
+        assert!((groups[0].outcome - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn summary_lists_unconverted_currencies() {
+        let maps = sample_maps();
+        let mut unknown = expense("b", 50.0, date(2024, 6, 2), None);
+        unknown.outcome_instrument = InstrumentId::new(99);
+        let txs = vec![expense("a", 900.0, date(2024, 6, 1), None), unknown];
+
+        let summary = summarize_transactions(&txs, &maps, Some(2));
+        assert_eq!(summary.unconverted, vec!["99".to_owned()]);
+        assert!((summary.outcome.unwrap_or_default() - 60.0).abs() < 1e-9);
+        assert!(
+            summarize_transactions(&txs, &maps, None)
+                .unconverted
+                .is_empty()
+        );
+    }
+
+    fn paid_to(id: &str, payee: &str, outcome: f64, tag: Option<&str>) -> Transaction {
+        let mut tx = expense(id, outcome, date(2024, 6, 1), tag);
+        tx.payee = Some(payee.to_owned());
+        tx
+    }
+
+    #[test]
+    fn payee_spend_breaks_down_categories() {
+        let maps = sample_maps();
+        let txs = vec![
+            paid_to("a", "Coffee", 300.0, Some("tag-1")),
+            paid_to("b", "Coffee", 100.0, None),
+            paid_to("c", "Bakery", 50.0, None),
+            paid_to("d", "Bakery", 50.0, None),
+            paid_to("e", "Bakery", 50.0, None),
+            expense("f", 1000.0, date(2024, 6, 1), None),
+        ];
+
+        let mut entries = payee_spend(&txs, PayeeDimension::Payee, &maps, None);
+        assert_eq!(entries.len(), 2);
+
+        rank_payees(&mut entries, RankBy::Amount);
+        assert_eq!(entries[0].label, "Coffee");
+        assert!((entries[0].total - 400.0).abs() < f64::EPSILON);
+        assert_eq!(entries[0].categories.len(), 2);
+        assert_eq!(entries[0].categories[0].category, "Groceries");
+
+        rank_payees(&mut entries, RankBy::Count);
+        assert_eq!(entries[0].label, "Bakery");
+        assert_eq!(entries[0].count, 3);
+    }
+
+    #[test]
+    fn compare_categories_computes_deltas() {
+        let maps = sample_maps();
+        let base = group_transactions(
+            &[
+                expense("a", 100.0, date(2024, 5, 1), Some("tag-1")),
+                expense("b", 40.0, date(2024, 5, 2), None),
+            ],
+            GroupBy::Tag,
+            &maps,
+            None,
+        );
+        let compare = group_transactions(
+            &[expense("c", 250.0, date(2024, 6, 1), Some("tag-1"))],
+            GroupBy::Tag,
+            &maps,
+            None,
+        );
+        let deltas = compare_categories(&base, &compare, &TransactionType::Expense);
+        assert_eq!(deltas.len(), 2);
+        assert_eq!(deltas[0].category, "Groceries");
+        assert!((deltas[0].delta - 150.0).abs() < f64::EPSILON);
+        assert_eq!(deltas[0].delta_percent.map(f64::round), Some(150.0));
+        assert_eq!(deltas[1].category, "Uncategorized");
+        assert!((deltas[1].compare).abs() < f64::EPSILON);
+        assert_eq!(deltas[1].delta_percent.map(f64::round), Some(-100.0));
+    }
+
+    #[test]
+    fn balance_history_walks_back_from_current_balance() {
+        let mut income = expense("in", 0.0, date(2024, 6, 2), None);
+        income.income = 1000.0;
+        let txs = vec![
+            expense("a", 100.0, date(2024, 5, 31), None),
+            income,
+            expense("b", 50.0, date(2024, 6, 5), None),
+        ];
+        let net = daily_net_changes(&txs, "acc-1");
+        let points = balance_history(
+            &net,
+            5_000.0,
+            date(2024, 5, 30),
+            date(2024, 6, 3),
+            Granularity::Day,
+        );
+        let balances: Vec<(String, f64)> = points
+            .into_iter()
+            .map(|point| (point.date, point.balance))
+            .collect();
+        assert_eq!(
+            balances,
+            vec![
+                ("2024-05-30".to_owned(), 4_150.0),
+                ("2024-05-31".to_owned(), 4_050.0),
+                ("2024-06-01".to_owned(), 4_050.0),
+                ("2024-06-02".to_owned(), 5_050.0),
+                ("2024-06-03".to_owned(), 5_050.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn balance_history_monthly_keeps_month_ends() {
+        let net = daily_net_changes(&[expense("a", 100.0, date(2024, 5, 15), None)], "acc-1");
+        let points = balance_history(
+            &net,
+            1_000.0,
+            date(2024, 4, 1),
+            date(2024, 6, 10),
+            Granularity::Month,
+        );
+        let dates: Vec<&str> = points.iter().map(|point| point.date.as_str()).collect();
+        assert_eq!(dates, vec!["2024-04-30", "2024-05-31", "2024-06-10"]);
+        assert!((points[0].balance - 1_100.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn envelope_balances_roll_over() {
+        let maps = sample_maps();
+        let envelopes = vec![Envelope {
+            name: "Food".to_owned(),
+            tag_ids: vec!["tag-1".to_owned()],
+            allocations: [("2024-05".to_owned(), 500.0), ("2024-06".to_owned(), 300.0)]
+                .into_iter()
+                .collect(),
+        }];
+        let expenses = vec![
+            expense("may", 400.0, date(2024, 5, 10), Some("tag-1")),
+            expense("june", 450.0, date(2024, 6, 5), Some("tag-1")),
+            expense("other", 1_000.0, date(2024, 6, 5), None),
+        ];
+        let status = envelope_balances(&envelopes, "2024-06", &expenses, &maps, None);
+        assert_eq!(status.len(), 1);
+        assert_eq!(status[0].tags, vec!["Groceries".to_owned()]);
+        assert!((status[0].carried_over - 100.0).abs() < f64::EPSILON);
+        assert!((status[0].spent - 450.0).abs() < f64::EPSILON);
+        assert!((status[0].available + 50.0).abs() < f64::EPSILON);
+        assert!(status[0].overspent);
+    }
+
+    fn budget(tag: &str, outcome: f64) -> Budget {
+        Budget {
+            changed: DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp"),
+            user: UserId::new(1),
+            tag: Some(TagId::new(tag.to_owned())),
+            date: date(2024, 6, 1),
+            income: 0.0,
+            income_lock: false,
+            outcome,
+            outcome_lock: false,
+            is_income_forecast: None,
+            is_outcome_forecast: None,
+        }
+    }
+
+    #[test]
+    fn budget_overruns_include_child_tags() {
+        let maps = sample_maps();
+        let expenses = vec![
+            expense("a", 300.0, date(2024, 6, 2), Some("tag-1")),
+            expense("b", 250.0, date(2024, 6, 3), Some("tag-2")),
+        ];
+        let overruns = budget_overruns(
+            &[budget("tag-1", 500.0), budget("tag-2", 1_000.0)],
+            &expenses,
+            &maps,
+            None,
+        );
+        assert_eq!(overruns.len(), 1);
+        assert_eq!(overruns[0].category, "Groceries");
+        assert!((overruns[0].over - 50.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn account_changes_skip_unchanged_accounts() {
+        let maps = sample_maps();
+        let mut refund = expense("refund", 0.0, date(2024, 6, 2), None);
+        refund.income = 100.0;
+        let txs = vec![expense("a", 300.0, date(2024, 6, 1), None), refund];
+        let changes = account_changes(&txs, &maps);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].account, "Main Account");
+        assert!((changes[0].change + 200.0).abs() < f64::EPSILON);
+        assert!(account_changes(&[], &maps).is_empty());
+    }
+
+    #[test]
+    fn transfer_matrix_groups_by_direction() {
+        let maps = sample_maps();
+        let transfer = |id: &str, outcome: f64| {
+            let mut tx = expense(id, outcome, date(2024, 6, 1), None);
+            tx.income_account = AccountId::new("acc-2".to_owned());
+            tx.income_instrument = InstrumentId::new(2);
+            tx.income = outcome / 90.0;
+            tx
+        };
+        let mut back = transfer("c", 900.0);
+        back.outcome_account = AccountId::new("acc-2".to_owned());
+        back.income_account = AccountId::new("acc-1".to_owned());
+        let txs = vec![
+            transfer("a", 9_000.0),
+            transfer("b", 4_500.0),
+            back,
+            expense("d", 50.0, date(2024, 6, 2), None),
+        ];
+        let (flows, accounts) = transfer_matrix(&txs, &maps, None);
+        assert_eq!(flows.len(), 2);
+        assert_eq!(flows[0].from_account, "Main Account");
+        assert_eq!(flows[0].to_account, "acc-2");
+        assert_eq!(flows[0].count, 2);
+        assert!((flows[0].sent - 13_500.0).abs() < f64::EPSILON);
+        assert!((flows[0].received - 150.0).abs() < 1e-9);
+        assert_eq!(flows[0].received_currency, "$");
+
+        let (converted, _) = transfer_matrix(&txs, &maps, Some(1));
+        assert_eq!(converted[0].received_currency, "₽");
+        assert!((converted[0].received - 13_500.0).abs() < 1e-6);
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(accounts[0].account, "Main Account");
+        assert!((accounts[0].transferred_out - 13_500.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn budget_adherence_counts_kept_months() {
+        let maps = sample_maps();
+        let mut july = budget("tag-1", 100.0);
+        july.date = date(2024, 7, 1);
+        let expenses = vec![
+            expense("a", 300.0, date(2024, 6, 2), Some("tag-2")),
+            expense("b", 80.0, date(2024, 7, 9), Some("tag-1")),
+        ];
+        let adherence = budget_adherence(
+            &[budget("tag-1", 200.0), july, budget("tag-2", 0.0)],
+            &expenses,
+            &maps,
+            None,
+        );
+        assert_eq!(adherence.budgets, 2);
+        assert_eq!(adherence.kept, 1);
+        assert_eq!(adherence.kept_percent, Some(50.0));
+        assert!((adherence.spent - 380.0).abs() < f64::EPSILON);
+        assert_eq!(adherence.overruns.len(), 1);
+        assert_eq!(adherence.overruns[0].category, "Groceries (2024-06)");
+    }
+
+    #[test]
+    fn transaction_label_prefers_payee() {
+        let maps = sample_maps();
+        assert_eq!(
+            transaction_label(&paid_to("a", "Coffee", 1.0, None), &maps),
+            "Coffee"
+        );
+        let tagged = expense("b", 1.0, date(2024, 6, 1), Some("tag-1"));
+        assert_eq!(transaction_label(&tagged, &maps), "Groceries");
+        assert!((round_cents(2.345_678) - 2.35).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn spending_patterns_average_over_calendar_days() {
+        let maps = sample_maps();
+        let expenses = vec![
+            expense("sat", 100.0, date(2024, 6, 1), Some("tag-1")),
+            expense("mon", 70.0, date(2024, 6, 3), None),
+        ];
+        let (weekdays, month_days) =
+            spending_patterns(&expenses, date(2024, 6, 1), date(2024, 6, 14), &maps, None);
+        assert_eq!(weekdays.len(), 7);
+        assert_eq!(weekdays[0].label, "Mon");
+        assert_eq!(weekdays[5].days, 2);
+        assert!((weekdays[5].daily_average - 50.0).abs() < f64::EPSILON);
+        assert_eq!(weekdays[5].categories[0].category, "Groceries");
+        assert!((pooled_daily_average(&weekdays[5..]) - 25.0).abs() < f64::EPSILON);
+
+        assert_eq!(month_days.len(), 10);
+        assert_eq!(month_days[0].label, "1-4");
+        assert_eq!(month_days[9].label, "29-31");
+        assert!((month_days[0].daily_average - 42.5).abs() < f64::EPSILON);
+        assert_eq!(month_days[9].days, 0);
+    }
+
+    #[test]
+    fn statement_period_current_and_previous() {
+        let period = |today, cycle| statement_period(25, today, cycle).expect("valid period");
+        assert_eq!(
+            period(date(2024, 3, 10), StatementCycle::Current),
+            (date(2024, 2, 26), date(2024, 3, 25))
+        );
+        assert_eq!(
+            period(date(2024, 3, 26), StatementCycle::Current),
+            (date(2024, 3, 26), date(2024, 4, 25))
+        );
+        assert_eq!(
+            period(date(2024, 3, 10), StatementCycle::Previous),
+            (date(2024, 1, 26), date(2024, 2, 25))
+        );
+    }
+
+    #[test]
+    fn statement_period_clamps_short_months() {
+        let (start, end) =
+            statement_period(31, date(2024, 2, 15), StatementCycle::Current).expect("valid period");
+        assert_eq!((start, end), (date(2024, 2, 1), date(2024, 2, 29)));
+    }
+
+    #[test]
+    fn detect_anomalies_flags_outliers() {
+        let maps = sample_maps();
+        let mut history: Vec<Transaction> = [90.0, 100.0, 110.0, 95.0, 105.0, 100.0]
+            .iter()
+            .enumerate()
+            .map(|(idx, amount)| paid_to(&format!("h{idx}"), "Coffee", *amount, Some("tag-1")))
+            .collect();
+        let candidates = vec![
+            paid_to("big", "Coffee", 1_000.0, Some("tag-1")),
+            paid_to("normal", "Coffee", 102.0, Some("tag-1")),
+        ];
+        history.extend(candidates.iter().cloned());
+
+        let anomalies = detect_anomalies(&history, &candidates, &maps, 3.5, None);
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(anomalies[0].tx.id.as_inner(), "big");
+        assert_eq!(anomalies[0].reasons.len(), 2);
+        assert!(anomalies[0].reasons[0].contains("category 'Groceries'"));
+    }
+
+    #[test]
+    fn detect_anomalies_needs_enough_samples() {
+        let maps = sample_maps();
+        let history = vec![
+            paid_to("a", "Coffee", 100.0, None),
+            paid_to("b", "Coffee", 5_000.0, None),
+        ];
+        assert!(detect_anomalies(&history, &history, &maps, 3.5, None).is_empty());
+    }
+
+    #[test]
+    fn detect_anomalies_falls_back_when_mad_is_zero() {
+        let maps = sample_maps();
+        let mut history: Vec<Transaction> = (0..6)
+            .map(|idx| paid_to(&format!("s{idx}"), "Streaming", 500.0, None))
+            .collect();
+        let candidates = vec![paid_to("hike", "Streaming", 1_500.0, None)];
+        history.extend(candidates.iter().cloned());
+        let anomalies = detect_anomalies(&history, &candidates, &maps, 3.5, None);
+        assert_eq!(anomalies.len(), 1);
+    }
+
+    #[test]
+    fn tag_usage_counts_spend_windows() {
+        let maps = sample_maps();
+        let mut income = expense("in", 0.0, date(2024, 6, 1), Some("tag-1"));
+        income.income = 5_000.0;
+        let txs = vec![
+            expense("recent", 100.0, date(2024, 6, 10), Some("tag-1")),
+            expense("older", 200.0, date(2024, 1, 10), Some("tag-1")),
+            expense("ancient", 400.0, date(2022, 1, 10), Some("tag-1")),
+            income,
+        ];
+        let usage = tag_usage(&txs, date(2024, 6, 15), &maps, None);
+        assert_eq!(usage.transaction_count, 4);
+        assert_eq!(usage.last_used.as_deref(), Some("2024-06-10"));
+        assert!((usage.spend_last_3_months - 100.0).abs() < f64::EPSILON);
+        assert!((usage.spend_last_12_months - 300.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn debt_balances_net_per_counterparty() {
+        let maps = sample_maps();
+        let debt_tx = |id: &str, payee: &str, lent: f64, received: f64| {
+            let mut tx = expense(id, received, date(2024, 6, 1), None);
+            tx.income = lent;
+            tx.payee = Some(payee.to_owned());
+            if lent > 0.0 {
+                tx.income_account = AccountId::new("debt".to_owned());
+            } else {
+                tx.outcome_account = AccountId::new("debt".to_owned());
+            }
+            tx
+        };
+        let mut repaid = debt_tx("c", "Bob", 0.0, 300.0);
+        repaid.date = date(2024, 6, 20);
+        let txs = vec![
+            debt_tx("a", "Alice", 1_000.0, 0.0),
+            debt_tx("b", "Bob", 300.0, 0.0),
+            repaid,
+            expense("d", 50.0, date(2024, 6, 1), None),
+        ];
+        let debt_accounts = HashSet::from(["debt".to_owned()]);
+        let balances = debt_balances(&txs, &debt_accounts, &maps, None);
+        assert_eq!(balances.len(), 2);
+        assert_eq!(balances[0].payee, "Alice");
+        assert!((balances[0].balance - 1_000.0).abs() < f64::EPSILON);
+        assert_eq!(balances[0].status, "owes_you");
+        assert_eq!(balances[1].payee, "Bob");
+        assert_eq!(balances[1].count, 2);
+        assert_eq!(balances[1].last_date, "2024-06-20");
+        assert_eq!(balances[1].status, "settled");
+    }
+
+    #[test]
+    fn roll_up_tags_merges_children_into_parent() {
+        let maps = sample_maps();
+        let mut both = expense("both", 100.0, date(2024, 6, 1), Some("tag-2"));
+        both.tag = Some(vec![
+            TagId::new("tag-2".to_owned()),
+            TagId::new("tag-1".to_owned()),
+        ]);
+        let mut txs = vec![
+            expense("child", 50.0, date(2024, 6, 1), Some("tag-2")),
+            both,
+            expense("untagged", 10.0, date(2024, 6, 1), None),
+        ];
+        assert!(has_tag_or_child(&txs[0], "tag-1", &maps));
+        assert!(!has_tag_or_child(&txs[2], "tag-1", &maps));
+
+        roll_up_tags(&mut txs, &maps);
+        let groups = group_transactions(&txs, GroupBy::Tag, &maps, None);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].label, "Groceries");
+        assert_eq!(groups[0].count, 2);
+        assert!((groups[0].outcome - 150.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn reminder_due_dates_step_from_start_until_end() {
+        let date = |month, day| NaiveDate::from_ymd_opt(2024, month, day).expect("valid date");
+        let mut reminder = Reminder {
+            id: ReminderId::new("rem-1".to_owned()),
+            changed: DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp"),
+            user: UserId::new(1),
+            income_instrument: InstrumentId::new(1),
+            income_account: AccountId::new("acc-1".to_owned()),
+            income: 0.0,
+            outcome_instrument: InstrumentId::new(1),
+            outcome_account: AccountId::new("acc-1".to_owned()),
+            outcome: 1_200.0,
+            tag: None,
+            merchant: None,
+            payee: Some("Rent".to_owned()),
+            comment: None,
+            interval: Some(Interval::Month),
+            step: Some(1),
+            points: None,
+            start_date: date(1, 31),
+            end_date: Some(date(5, 15)),
+            notify: false,
+        };
+        assert_eq!(
+            reminder_due_dates(&reminder, date(3, 1), date(6, 30)),
+            vec![date(3, 31), date(4, 30)]
+        );
+        reminder.interval = None;
+        assert!(reminder_due_dates(&reminder, date(3, 1), date(6, 30)).is_empty());
+        assert_eq!(
+            reminder_due_dates(&reminder, date(1, 1), date(1, 31)),
+            vec![date(1, 31)]
+        );
+    }
+}
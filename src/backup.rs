@@ -0,0 +1,343 @@
+//! Backups of locally stored entities and local-only state.
+//!
+//! A backup is one JSON file holding every synced entity plus saved
+//! templates and envelopes, with a format version, per-collection counts and
+//! a checksum of the entity data. Reading verifies all three, so a truncated
+//! or hand-edited backup is rejected instead of being restored.
+//!
+//! Snapshots hold the same entities in a plain document meant for other
+//! programs: no checksum, optionally date-bounded transactions, and a schema
+//! name and version so that future readers can tell what they were given.
+
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use zenmoney_rs::models::{
+    Account, Budget, Company, Instrument, Merchant, Reminder, Tag, Transaction, User,
+};
+
+use crate::envelopes::Envelope;
+use crate::templates::TransactionTemplate;
+
+/// Version of the backup file layout written by this server.
+const BACKUP_FORMAT_VERSION: u32 = 1;
+
+/// Schema name identifying snapshot documents.
+const SNAPSHOT_SCHEMA: &str = "zenmoney-mcp/snapshot";
+
+/// Version of the snapshot document layout, raised on incompatible changes.
+pub(crate) const SNAPSHOT_SCHEMA_VERSION: u32 = 1;
+
+/// FNV-1a 64-bit offset basis.
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// FNV-1a 64-bit prime.
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// Everything stored locally, as saved in a backup.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct BackupEntities {
+    /// Financial accounts.
+    pub(crate) accounts: Vec<Account>,
+    /// Category tags.
+    pub(crate) tags: Vec<Tag>,
+    /// Currency instruments.
+    pub(crate) instruments: Vec<Instrument>,
+    /// Merchants.
+    pub(crate) merchants: Vec<Merchant>,
+    /// Companies (banks).
+    pub(crate) companies: Vec<Company>,
+    /// Users of the account.
+    pub(crate) users: Vec<User>,
+    /// Category budgets.
+    pub(crate) budgets: Vec<Budget>,
+    /// Recurring transaction reminders.
+    pub(crate) reminders: Vec<Reminder>,
+    /// Transactions, including deleted ones.
+    pub(crate) transactions: Vec<Transaction>,
+    /// Saved transaction templates.
+    pub(crate) templates: Vec<TransactionTemplate>,
+    /// Budgeting envelopes.
+    pub(crate) envelopes: Vec<Envelope>,
+}
+
+impl BackupEntities {
+    /// Counts the entities of each collection.
+    pub(crate) fn counts(&self) -> BTreeMap<String, usize> {
+        [
+            ("accounts", self.accounts.len()),
+            ("tags", self.tags.len()),
+            ("instruments", self.instruments.len()),
+            ("merchants", self.merchants.len()),
+            ("companies", self.companies.len()),
+            ("users", self.users.len()),
+            ("budgets", self.budgets.len()),
+            ("reminders", self.reminders.len()),
+            ("transactions", self.transactions.len()),
+            ("templates", self.templates.len()),
+            ("envelopes", self.envelopes.len()),
+        ]
+        .into_iter()
+        .map(|entry| (entry.0.to_owned(), entry.1))
+        .collect()
+    }
+}
+
+/// On-disk layout of a backup.
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupFile {
+    /// Layout version, see [`BACKUP_FORMAT_VERSION`].
+    format_version: u32,
+    /// When the backup was taken.
+    created_at: DateTime<Utc>,
+    /// Number of entities per collection.
+    counts: BTreeMap<String, usize>,
+    /// Checksum of the serialized `entities`.
+    checksum: String,
+    /// The [`BackupEntities`], kept as a JSON value so the checksum is
+    /// computed over exactly what was read.
+    entities: Value,
+}
+
+/// Transactions kept in a snapshot.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub(crate) struct SnapshotRange {
+    /// First transaction date kept, if bounded.
+    pub(crate) date_from: Option<NaiveDate>,
+    /// Last transaction date kept, if bounded.
+    pub(crate) date_to: Option<NaiveDate>,
+    /// Whether deleted transactions are kept.
+    pub(crate) include_deleted: bool,
+}
+
+impl SnapshotRange {
+    /// Returns whether `tx` belongs in the snapshot.
+    pub(crate) fn contains(&self, tx: &Transaction) -> bool {
+        (self.include_deleted || !tx.deleted)
+            && self.date_from.is_none_or(|from| tx.date >= from)
+            && self.date_to.is_none_or(|to| tx.date <= to)
+    }
+}
+
+/// Layout of a snapshot document.
+#[derive(Debug, Serialize)]
+struct SnapshotFile<'entities> {
+    /// Always [`SNAPSHOT_SCHEMA`].
+    schema: &'static str,
+    /// See [`SNAPSHOT_SCHEMA_VERSION`].
+    schema_version: u32,
+    /// Server name and version that wrote the snapshot.
+    generator: String,
+    /// When the snapshot was taken.
+    created_at: DateTime<Utc>,
+    /// Which transactions were kept.
+    transactions: SnapshotRange,
+    /// Number of entities per collection.
+    counts: BTreeMap<String, usize>,
+    /// The exported entities.
+    entities: &'entities BackupEntities,
+}
+
+/// Builds an `InvalidData` error for a rejected backup.
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Computes the FNV-1a checksum of the compact JSON form of `entities`.
+fn checksum(entities: &Value) -> io::Result<String> {
+    let bytes = serde_json::to_vec(entities).map_err(io::Error::other)?;
+    let hash = bytes.iter().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+    });
+    Ok(format!("fnv1a64:{hash:016x}"))
+}
+
+/// Resolves where a file goes: `<prefix>-YYYYMMDD-HHMMSS.json` inside `path`
+/// if it is a directory, otherwise `path` itself.
+fn timestamped_path(path: &Path, prefix: &str, created_at: DateTime<Utc>) -> PathBuf {
+    if path.is_dir() {
+        path.join(format!(
+            "{prefix}-{}.json",
+            created_at.format("%Y%m%d-%H%M%S")
+        ))
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// Resolves where a backup goes: a timestamped file inside `path` if it is a
+/// directory, otherwise `path` itself.
+pub(crate) fn backup_path(path: &Path, created_at: DateTime<Utc>) -> PathBuf {
+    timestamped_path(path, "zenmoney-backup", created_at)
+}
+
+/// Resolves where a snapshot goes, like [`backup_path`].
+pub(crate) fn snapshot_path(path: &Path, created_at: DateTime<Utc>) -> PathBuf {
+    timestamped_path(path, "zenmoney-snapshot", created_at)
+}
+
+/// Writes `json` to `path` via a temporary file, returning its size.
+fn write_atomically(path: &Path, json: &[u8]) -> io::Result<u64> {
+    let tmp_path = path.with_extension("json.tmp");
+    std::fs::write(&tmp_path, json)?;
+    std::fs::rename(&tmp_path, path)?;
+    Ok(u64::try_from(json.len()).unwrap_or(u64::MAX))
+}
+
+/// Writes `entities` to `path` via a temporary file, returning the file size.
+///
+/// # Errors
+///
+/// Returns an error if the entities cannot be serialized or the file cannot be written.
+pub(crate) fn write_backup(
+    path: &Path,
+    entities: &BackupEntities,
+    created_at: DateTime<Utc>,
+) -> io::Result<u64> {
+    let data = serde_json::to_value(entities).map_err(io::Error::other)?;
+    let file = BackupFile {
+        format_version: BACKUP_FORMAT_VERSION,
+        created_at,
+        counts: entities.counts(),
+        checksum: checksum(&data)?,
+        entities: data,
+    };
+    let json = serde_json::to_vec_pretty(&file).map_err(io::Error::other)?;
+    write_atomically(path, &json)
+}
+
+/// Writes `entities` to `path` as a snapshot document, returning the file size.
+///
+/// `range` only describes the transactions; the caller filters them.
+///
+/// # Errors
+///
+/// Returns an error if the entities cannot be serialized or the file cannot be written.
+pub(crate) fn write_snapshot(
+    path: &Path,
+    entities: &BackupEntities,
+    range: SnapshotRange,
+    created_at: DateTime<Utc>,
+) -> io::Result<u64> {
+    let file = SnapshotFile {
+        schema: SNAPSHOT_SCHEMA,
+        schema_version: SNAPSHOT_SCHEMA_VERSION,
+        generator: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+        created_at,
+        transactions: range,
+        counts: entities.counts(),
+        entities,
+    };
+    let json = serde_json::to_vec_pretty(&file).map_err(io::Error::other)?;
+    write_atomically(path, &json)
+}
+
+/// Reads a backup from `path` and verifies its integrity.
+///
+/// Returns when the backup was taken and its entities.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read, or an `InvalidData` error
+/// if it is not a backup, has an unsupported version, or its checksum or
+/// counts do not match the contents.
+pub(crate) fn read_backup(path: &Path) -> io::Result<(DateTime<Utc>, BackupEntities)> {
+    let bytes = std::fs::read(path)?;
+    let file: BackupFile = serde_json::from_slice(&bytes)
+        .map_err(|err| invalid(format!("not a backup file: {err}")))?;
+    if file.format_version != BACKUP_FORMAT_VERSION {
+        return Err(invalid(format!(
+            "unsupported backup format version {} (expected {BACKUP_FORMAT_VERSION})",
+            file.format_version
+        )));
+    }
+    if checksum(&file.entities)? != file.checksum {
+        return Err(invalid(
+            "checksum mismatch: the backup is corrupted or was edited".to_owned(),
+        ));
+    }
+    let entities: BackupEntities = serde_json::from_value(file.entities)
+        .map_err(|err| invalid(format!("backup entities are malformed: {err}")))?;
+    if entities.counts() != file.counts {
+        return Err(invalid(
+            "entity counts do not match the backup header".to_owned(),
+        ));
+    }
+    Ok((file.created_at, entities))
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::indexing_slicing,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect and indexing for readability"
+)]
+mod tests {
+    use std::io;
+
+    use chrono::DateTime;
+
+    use super::{
+        BTreeMap, BackupEntities, SnapshotRange, backup_path, read_backup, snapshot_path,
+        write_backup, write_snapshot,
+    };
+    use crate::envelopes::Envelope;
+    use crate::persist::TempDir;
+
+    fn entities() -> BackupEntities {
+        BackupEntities {
+            envelopes: vec![Envelope {
+                name: "Food".to_owned(),
+                tag_ids: vec!["tag-1".to_owned()],
+                allocations: BTreeMap::from([("2024-06".to_owned(), 12_000.5)]),
+            }],
+            ..BackupEntities::default()
+        }
+    }
+
+    #[test]
+    fn round_trip_and_tamper_detection() {
+        let dir = TempDir::new();
+        let created_at = DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp");
+        let path = backup_path(&dir, created_at);
+        assert!(path.ends_with("zenmoney-backup-20231114-221320.json"));
+        let bytes = write_backup(&path, &entities(), created_at).expect("write backup");
+        assert!(bytes > 0);
+
+        let (restored_at, restored) = read_backup(&path).expect("read backup");
+        assert_eq!(restored_at, created_at);
+        assert_eq!(restored.counts(), entities().counts());
+        assert!((restored.envelopes[0].allocated_in("2024-06") - 12_000.5).abs() < f64::EPSILON);
+
+        let text = std::fs::read_to_string(&path).expect("read text");
+        std::fs::write(&path, text.replace("12000.5", "99000.0")).expect("tamper");
+        let err = read_backup(&path).expect_err("tampered backup must be rejected");
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn snapshot_carries_schema_metadata() {
+        let dir = TempDir::new();
+        let created_at = DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp");
+        let path = snapshot_path(&dir, created_at);
+        assert!(path.ends_with("zenmoney-snapshot-20231114-221320.json"));
+        write_snapshot(&path, &entities(), SnapshotRange::default(), created_at)
+            .expect("write snapshot");
+
+        let text = std::fs::read_to_string(&path).expect("read text");
+        let snapshot: serde_json::Value = serde_json::from_str(&text).expect("valid json");
+        assert_eq!(snapshot["schema"], "zenmoney-mcp/snapshot");
+        assert_eq!(snapshot["schema_version"], 1);
+        assert_eq!(snapshot["counts"]["envelopes"], 1);
+        assert_eq!(snapshot["transactions"]["include_deleted"], false);
+        assert_eq!(snapshot["entities"]["envelopes"][0]["name"], "Food");
+        assert!(read_backup(&path).is_err());
+    }
+}
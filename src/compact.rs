@@ -0,0 +1,151 @@
+//! Compact tool output to reduce token usage.
+//!
+//! Pretty-printed responses with long field names more than double the
+//! tokens spent on large listings. In compact mode, turned on for every call
+//! with `ZENMONEY_COMPACT_OUTPUT` or per call with `compact: true`, the
+//! JSON text of a result is minified, the most frequent field names are
+//! shortened and null values and empty arrays are left out. Structured
+//! content keeps the declared output schema and is not changed.
+
+use rmcp::model::{CallToolResult, Content, JsonObject, Tool};
+use serde_json::Value;
+
+use crate::lenient::{add_bool_param, flag_argument};
+
+/// Environment variable that turns compact output on for every call.
+pub const COMPACT_OUTPUT_ENV: &str = "ZENMONEY_COMPACT_OUTPUT";
+
+/// Tool argument that turns compact output on or off for a single call.
+const COMPACT_ARG: &str = "compact";
+
+/// Field names shortened in compact output.
+const SHORT_NAMES: &[(&str, &str)] = &[
+    ("income_account_id", "in_acc_id"),
+    ("outcome_account_id", "out_acc_id"),
+    ("income_account", "in_acc"),
+    ("outcome_account", "out_acc"),
+    ("income_currency", "in_cur"),
+    ("outcome_currency", "out_cur"),
+    ("income", "in"),
+    ("outcome", "out"),
+    ("merchant_id", "merch_id"),
+    ("merchant", "merch"),
+    ("comment", "cmt"),
+    ("changed", "chg"),
+    ("currency", "cur"),
+    ("balance", "bal"),
+    ("converted_amount", "conv"),
+    ("converted_balance", "conv_bal"),
+    ("mcc_label", "mcc_lbl"),
+    ("transaction_count", "tx_count"),
+];
+
+/// Returns whether a call should get compact output: its `compact`
+/// argument if given, otherwise the server-wide `default`.
+pub(crate) fn requested(arguments: Option<&JsonObject>, default: bool) -> bool {
+    flag_argument(arguments, COMPACT_ARG).unwrap_or(default)
+}
+
+/// Adds the `compact` argument to the input schema of a tool.
+pub(crate) fn add_compact_param(tool: &mut Tool) {
+    add_bool_param(
+        tool,
+        COMPACT_ARG,
+        "Return minified JSON without nulls or empty arrays, with short field names (in/out for income/outcome, in_acc/out_acc for accounts, cur for currency, cmt for comment, chg for changed, merch for merchant, bal for balance)",
+    );
+}
+
+/// Rewrites the JSON text content of `result` in compact form.
+///
+/// Text that is not JSON, such as notes appended to a result, is kept.
+pub(crate) fn compact_result(mut result: CallToolResult) -> CallToolResult {
+    for content in &mut result.content {
+        let compacted = content
+            .raw
+            .as_text()
+            .and_then(|text| serde_json::from_str::<Value>(&text.text).ok())
+            .map(|value| compact_value(value).to_string());
+        if let Some(text) = compacted {
+            *content = Content::text(text);
+        }
+    }
+    result
+}
+
+/// Shortens field names and drops nulls and empty arrays, recursively.
+fn compact_value(value: Value) -> Value {
+    match value {
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .filter(|entry| !is_empty(&entry.1))
+                .map(|(name, field)| (short_name(name), compact_value(field)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(items.into_iter().map(compact_value).collect()),
+        Value::Null | Value::Bool(_) | Value::Number(_) | Value::String(_) => value,
+    }
+}
+
+/// Returns whether a field carries no information.
+fn is_empty(value: &Value) -> bool {
+    value.is_null() || value.as_array().is_some_and(Vec::is_empty)
+}
+
+/// Returns the compact name of a field.
+fn short_name(name: String) -> String {
+    SHORT_NAMES
+        .iter()
+        .find(|&&(long, _short)| long == name)
+        .map_or(name, |&(_long, short)| short.to_owned())
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect for readability"
+)]
+mod tests {
+    use rmcp::model::{CallToolResult, Content, JsonObject};
+    use serde_json::json;
+
+    use super::{compact_result, requested};
+
+    #[test]
+    fn minifies_and_shortens_json_text() {
+        let pretty = serde_json::to_string_pretty(&json!({
+            "items": [{"income": 0.0, "outcome_account": "Card", "payee": null, "tags": []}],
+            "total": 1,
+        }))
+        .expect("serialize");
+        let result =
+            CallToolResult::success(vec![Content::text(pretty), Content::text("saved locally")]);
+        let compacted = compact_result(result);
+        let text = |index: usize| {
+            compacted
+                .content
+                .get(index)
+                .and_then(|content| content.raw.as_text())
+                .map(|content| content.text.clone())
+                .expect("text content")
+        };
+        assert_eq!(
+            text(0),
+            r#"{"items":[{"in":0.0,"out_acc":"Card"}],"total":1}"#
+        );
+        assert_eq!(text(1), "saved locally");
+    }
+
+    #[test]
+    fn call_flag_overrides_default() {
+        let args: JsonObject =
+            serde_json::from_value(json!({ "compact": false })).expect("valid arguments");
+        assert!(!requested(Some(&args), true));
+        let args: JsonObject =
+            serde_json::from_value(json!({ "compact": "true" })).expect("valid arguments");
+        assert!(requested(Some(&args), false));
+        assert!(requested(None, true));
+        assert!(!requested(None, false));
+    }
+}
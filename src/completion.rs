@@ -0,0 +1,115 @@
+//! Argument completion for entity IDs.
+//!
+//! Clients call `completion/complete` while the user types an argument.
+//! Arguments named after an entity ID (`account_id`, `tag_id`,
+//! `merchant_id`) are completed from local data, matching the typed text
+//! against the entity title or ID prefix, case-insensitively.
+
+use rmcp::model::CompletionInfo;
+
+/// Maximum number of values in one completion response (MCP limit).
+const MAX_COMPLETION_VALUES: usize = 100;
+
+/// Entity collections that can complete an argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CompletionSource {
+    /// Account IDs.
+    Account,
+    /// Category tag IDs.
+    Tag,
+    /// Merchant IDs.
+    Merchant,
+}
+
+impl CompletionSource {
+    /// Picks the collection completing an argument, if any.
+    pub(crate) fn for_argument(name: &str) -> Option<Self> {
+        match name {
+            "account_id" | "to_account_id" => Some(Self::Account),
+            "tag_id" | "tag_ids" | "parent_tag_id" => Some(Self::Tag),
+            "merchant_id" => Some(Self::Merchant),
+            _ => None,
+        }
+    }
+}
+
+/// Completes `typed` against `(id, title)` candidates.
+///
+/// Title prefix matches come first, then ID prefix matches, each sorted by
+/// title. Only IDs are returned since they are what the argument expects.
+pub(crate) fn complete_ids(candidates: &[(String, String)], typed: &str) -> CompletionInfo {
+    let needle = typed.trim().to_lowercase();
+    let mut matches: Vec<(bool, &str, &str)> = candidates
+        .iter()
+        .filter_map(|candidate| {
+            let id = candidate.0.as_str();
+            let title = candidate.1.as_str();
+            if title.to_lowercase().starts_with(&needle) {
+                Some((false, title, id))
+            } else if id.to_lowercase().starts_with(&needle) {
+                Some((true, title, id))
+            } else {
+                None
+            }
+        })
+        .collect();
+    matches.sort_unstable();
+    let total = matches.len();
+    let values: Vec<String> = matches
+        .into_iter()
+        .take(MAX_COMPLETION_VALUES)
+        .map(|(_by_id, _title, id)| id.to_owned())
+        .collect();
+    CompletionInfo {
+        has_more: Some(total > values.len()),
+        total: u32::try_from(total).ok(),
+        values,
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::indexing_slicing,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses indexing for readability"
+)]
+mod tests {
+    use super::{CompletionSource, complete_ids};
+
+    fn candidates() -> Vec<(String, String)> {
+        [
+            ("acc-2", "Savings"),
+            ("acc-1", "Main Account"),
+            ("sa-3", "Cash"),
+        ]
+        .into_iter()
+        .map(|(id, title)| (id.to_owned(), title.to_owned()))
+        .collect()
+    }
+
+    #[test]
+    fn argument_names_pick_sources() {
+        assert_eq!(
+            CompletionSource::for_argument("account_id"),
+            Some(CompletionSource::Account)
+        );
+        assert_eq!(
+            CompletionSource::for_argument("tag_id"),
+            Some(CompletionSource::Tag)
+        );
+        assert_eq!(CompletionSource::for_argument("date_from"), None);
+    }
+
+    #[test]
+    fn title_matches_come_before_id_matches() {
+        let completion = complete_ids(&candidates(), "SA");
+        assert_eq!(
+            completion.values,
+            vec!["acc-2".to_owned(), "sa-3".to_owned()]
+        );
+        assert_eq!(completion.total, Some(2));
+        assert_eq!(completion.has_more, Some(false));
+        let everything = complete_ids(&candidates(), "");
+        assert_eq!(everything.values[0], "sa-3");
+    }
+}
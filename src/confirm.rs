@@ -0,0 +1,130 @@
+//! User confirmation of destructive operations via MCP elicitation.
+//!
+//! When enabled with `ZENMONEY_CONFIRM_DESTRUCTIVE`, deletions and bulk
+//! commits ask the user to accept a summary of the change before anything
+//! is sent to ZenMoney. Clients without elicitation support are not asked,
+//! so the setting never blocks them.
+
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+
+use rmcp::model::{CreateElicitationRequestParam, ElicitationAction, ElicitationSchema};
+use rmcp::service::RequestContext;
+use rmcp::{ErrorData as McpError, Peer, RoleServer};
+
+/// Environment variable that turns confirmation prompts on.
+pub const CONFIRM_DESTRUCTIVE_ENV: &str = "ZENMONEY_CONFIRM_DESTRUCTIVE";
+
+/// Returns whether a setting value switches confirmation on.
+#[inline]
+#[must_use]
+pub fn is_enabled(value: &str) -> bool {
+    matches!(
+        value.trim().to_lowercase().as_str(),
+        "1" | "true" | "yes" | "on"
+    )
+}
+
+/// Asks the user to confirm a destructive change.
+#[derive(Clone, Default)]
+pub(crate) struct Confirmation {
+    /// Connection to ask through; `None` skips the question.
+    peer: Option<Peer<RoleServer>>,
+    /// Answer given without asking, so tests can decline.
+    #[cfg(test)]
+    answer: Option<bool>,
+}
+
+impl core::fmt::Debug for Confirmation {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Confirmation")
+            .field("enabled", &self.peer.is_some())
+            .finish()
+    }
+}
+
+impl Confirmation {
+    /// Creates a confirmation for the request in `context`, asking only when `enabled`.
+    pub(crate) fn new(enabled: bool, context: &RequestContext<RoleServer>) -> Self {
+        Self {
+            peer: enabled.then(|| context.peer.clone()),
+            #[cfg(test)]
+            answer: None,
+        }
+    }
+
+    /// Returns a confirmation the user always declines.
+    #[cfg(test)]
+    pub(crate) fn declined() -> Self {
+        Self {
+            peer: None,
+            answer: Some(false),
+        }
+    }
+
+    /// Shows `message` to the user and returns whether they accepted.
+    ///
+    /// Returns `true` without asking when confirmation is disabled or the
+    /// client does not support elicitation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the elicitation request fails.
+    pub(crate) async fn ask(&self, message: String) -> Result<bool, McpError> {
+        #[cfg(test)]
+        if let Some(answer) = self.answer {
+            return Ok(answer);
+        }
+        let Some(peer) = self.peer.as_ref() else {
+            return Ok(true);
+        };
+        let supported = peer
+            .peer_info()
+            .is_some_and(|info| info.capabilities.elicitation.is_some());
+        if !supported {
+            return Ok(true);
+        }
+        let response = peer
+            .create_elicitation(CreateElicitationRequestParam {
+                message,
+                requested_schema: ElicitationSchema::new(BTreeMap::new()),
+            })
+            .await
+            .map_err(|err| {
+                McpError::internal_error(format!("confirmation request failed: {err}"), None)
+            })?;
+        Ok(match response.action {
+            ElicitationAction::Accept => true,
+            ElicitationAction::Decline | ElicitationAction::Cancel => false,
+        })
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect for readability"
+)]
+mod tests {
+    use super::{Confirmation, is_enabled};
+
+    #[test]
+    fn setting_values() {
+        assert!(is_enabled("1"));
+        assert!(is_enabled(" TRUE "));
+        assert!(is_enabled("on"));
+        assert!(!is_enabled("0"));
+        assert!(!is_enabled(""));
+    }
+
+    #[tokio::test]
+    async fn disabled_confirmation_accepts() {
+        let accepted = Confirmation::default()
+            .ask("Delete everything?".to_owned())
+            .await
+            .expect("no request is sent");
+        assert!(accepted);
+    }
+}
@@ -0,0 +1,239 @@
+//! Conflicts between queued edits and changes made on ZenMoney meanwhile.
+//!
+//! Queued writes are sent after the next successful sync. If that sync
+//! brought a newer version of a transaction the queue was about to
+//! overwrite, the edit is held back instead of silently replacing the other
+//! change: both versions are kept here until `resolve_conflict` keeps one
+//! or merges them field by field. Conflicts are stored as a JSON file next
+//! to the ZenMoney storage data.
+//!
+//! Only queued edits are checked. Edits sent while ZenMoney is reachable go
+//! out at once and overwrite the server version, as ZenMoney itself does.
+
+use std::io;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use zenmoney_rs::models::{MerchantId, Transaction};
+
+use crate::params::TransactionField;
+use crate::persist::{Keyed, KeyedList};
+
+/// File name used for conflict storage inside the data directory.
+const CONFLICTS_FILE: &str = "conflicts.json";
+
+/// A queued edit held back because ZenMoney changed the same transaction.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Conflict {
+    /// Conflict ID, used to resolve it.
+    pub(crate) id: String,
+    /// When the conflict was found.
+    pub(crate) detected_at: DateTime<Utc>,
+    /// The queued local edit.
+    pub(crate) local: Transaction,
+    /// The version synced from ZenMoney.
+    pub(crate) remote: Transaction,
+}
+
+impl Keyed for Conflict {
+    fn key(&self) -> &str {
+        &self.id
+    }
+}
+
+/// Unresolved conflicts, optionally backed by a JSON file.
+#[derive(Debug, Default)]
+pub struct ConflictStore {
+    /// Conflicts, oldest first.
+    conflicts: KeyedList<Conflict>,
+}
+
+impl ConflictStore {
+    /// Opens the conflict store inside `dir`, loading unresolved conflicts.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read or parsed.
+    #[inline]
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        Ok(Self {
+            conflicts: KeyedList::open(dir.join(CONFLICTS_FILE))?,
+        })
+    }
+
+    /// Returns the unresolved conflicts, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned.
+    pub(crate) fn list(&self) -> io::Result<Vec<Conflict>> {
+        self.conflicts.list()
+    }
+
+    /// Records new conflicts.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned or the file cannot be written.
+    pub(crate) fn record(&self, conflicts: Vec<Conflict>) -> io::Result<()> {
+        self.conflicts.extend(conflicts)
+    }
+
+    /// Returns the conflict with `id`, if it is unresolved.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned.
+    pub(crate) fn get(&self, id: &str) -> io::Result<Option<Conflict>> {
+        Ok(self
+            .conflicts
+            .list()?
+            .into_iter()
+            .find(|conflict| conflict.id == id))
+    }
+
+    /// Removes the conflict with `id`, returning it if it was unresolved.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned or the file cannot be written.
+    pub(crate) fn remove(&self, id: &str) -> io::Result<Option<Conflict>> {
+        self.conflicts.remove(id)
+    }
+
+    /// Drops all unresolved conflicts, returning how many there were.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned or the file cannot be written.
+    pub(crate) fn clear(&self) -> io::Result<usize> {
+        self.conflicts.clear()
+    }
+}
+
+/// Returns whether two amounts differ.
+fn amounts_differ(left: f64, right: f64) -> bool {
+    (left - right).abs() > f64::EPSILON
+}
+
+/// Returns whether `field` differs between two versions of a transaction.
+fn field_differs(field: TransactionField, left: &Transaction, right: &Transaction) -> bool {
+    match field {
+        TransactionField::Date => left.date != right.date,
+        TransactionField::Amounts => {
+            amounts_differ(left.income, right.income) || amounts_differ(left.outcome, right.outcome)
+        }
+        TransactionField::Accounts => {
+            left.income_account.as_inner() != right.income_account.as_inner()
+                || left.outcome_account.as_inner() != right.outcome_account.as_inner()
+        }
+        TransactionField::Tags => {
+            let ids = |tx: &Transaction| -> Vec<String> {
+                tx.tag
+                    .iter()
+                    .flatten()
+                    .map(|tag| tag.as_inner().to_owned())
+                    .collect()
+            };
+            ids(left) != ids(right)
+        }
+        TransactionField::Merchant => {
+            left.merchant.as_ref().map(MerchantId::as_inner)
+                != right.merchant.as_ref().map(MerchantId::as_inner)
+        }
+        TransactionField::Payee => left.payee != right.payee,
+        TransactionField::Comment => left.comment != right.comment,
+        TransactionField::Deleted => left.deleted != right.deleted,
+    }
+}
+
+/// Copies `field` from `source` into `target`.
+fn copy_field(field: TransactionField, target: &mut Transaction, source: &Transaction) {
+    match field {
+        TransactionField::Date => target.date = source.date,
+        TransactionField::Amounts => {
+            target.income = source.income;
+            target.outcome = source.outcome;
+        }
+        TransactionField::Accounts => {
+            target.income_account = source.income_account.clone();
+            target.income_instrument = source.income_instrument;
+            target.outcome_account = source.outcome_account.clone();
+            target.outcome_instrument = source.outcome_instrument;
+        }
+        TransactionField::Tags => target.tag.clone_from(&source.tag),
+        TransactionField::Merchant => target.merchant.clone_from(&source.merchant),
+        TransactionField::Payee => target.payee.clone_from(&source.payee),
+        TransactionField::Comment => target.comment.clone_from(&source.comment),
+        TransactionField::Deleted => target.deleted = source.deleted,
+    }
+}
+
+/// Lists the field groups in which the two versions differ.
+pub(crate) fn differing_fields(local: &Transaction, remote: &Transaction) -> Vec<TransactionField> {
+    TransactionField::ALL
+        .into_iter()
+        .filter(|field| field_differs(*field, local, remote))
+        .collect()
+}
+
+/// Builds the merged version: `remote` with `local_fields` taken from `local`.
+pub(crate) fn merge(
+    remote: &Transaction,
+    local: &Transaction,
+    local_fields: &[TransactionField],
+) -> Transaction {
+    let mut merged = remote.clone();
+    for field in local_fields {
+        copy_field(*field, &mut merged, local);
+    }
+    merged.changed = Utc::now();
+    merged
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect for readability"
+)]
+mod tests {
+    use chrono::NaiveDate;
+    use zenmoney_rs::models::Transaction;
+
+    use super::{differing_fields, merge};
+    use crate::params::TransactionField;
+
+    fn transaction() -> Transaction {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 15).expect("valid date");
+        crate::demo::dataset(today)
+            .transactions
+            .into_iter()
+            .next()
+            .expect("demo transactions")
+    }
+
+    #[test]
+    fn finds_and_merges_differing_fields() {
+        let base = transaction();
+        let mut local = base.clone();
+        local.comment = Some("split with Anna".to_owned());
+        local.outcome += 100.0;
+        let mut remote = base;
+        remote.payee = Some("Corner Shop #24".to_owned());
+
+        assert_eq!(
+            differing_fields(&local, &remote),
+            vec![
+                TransactionField::Amounts,
+                TransactionField::Payee,
+                TransactionField::Comment
+            ]
+        );
+        let merged = merge(&remote, &local, &[TransactionField::Comment]);
+        assert_eq!(merged.comment, local.comment);
+        assert_eq!(merged.payee, remote.payee);
+        assert!((merged.outcome - remote.outcome).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,225 @@
+//! Guard against transaction dates far from today.
+//!
+//! Assistants often get the year wrong, and a transaction dated a year off
+//! silently lands in the wrong month of every report. Created and updated
+//! transactions are therefore checked against a window around today (by
+//! default at most 90 days ahead, with no limit into the past) and must not
+//! be dated before the start date of their account. The window and whether
+//! a date outside it is rejected or only logged are read from environment
+//! variables; a single call opts out with `allow_out_of_range`.
+
+use chrono::NaiveDate;
+
+/// Environment variable choosing what happens to dates out of range.
+pub const DATE_GUARD_ENV: &str = "ZENMONEY_DATE_GUARD";
+
+/// Environment variable with the most days ahead of today a date may be.
+pub const MAX_FUTURE_DAYS_ENV: &str = "ZENMONEY_MAX_FUTURE_DAYS";
+
+/// Environment variable with the most days before today a date may be.
+pub const MAX_PAST_DAYS_ENV: &str = "ZENMONEY_MAX_PAST_DAYS";
+
+/// Days ahead of today allowed when [`MAX_FUTURE_DAYS_ENV`] is unset.
+const DEFAULT_MAX_FUTURE_DAYS: u32 = 90;
+
+/// What happens to a date out of range.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GuardMode {
+    /// Refuse the create or update.
+    #[default]
+    Reject,
+    /// Log a warning and go ahead.
+    Warn,
+    /// Do not check dates.
+    Off,
+}
+
+/// Allowed transaction dates relative to today.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateWindow {
+    /// What happens to a date out of range.
+    mode: GuardMode,
+    /// Most days ahead of today (`None` for no limit).
+    max_future_days: Option<u32>,
+    /// Most days before today (`None` for no limit).
+    max_past_days: Option<u32>,
+}
+
+impl Default for DateWindow {
+    fn default() -> Self {
+        Self {
+            mode: GuardMode::Reject,
+            max_future_days: Some(DEFAULT_MAX_FUTURE_DAYS),
+            max_past_days: None,
+        }
+    }
+}
+
+/// Parses a day limit; blank keeps `default` and `0` removes the limit.
+fn parse_days(
+    name: &str,
+    value: Option<&str>,
+    default: Option<u32>,
+) -> Result<Option<u32>, String> {
+    let Some(raw) = value.map(str::trim).filter(|raw| !raw.is_empty()) else {
+        return Ok(default);
+    };
+    let days: u32 = raw
+        .parse()
+        .map_err(|err| format!("invalid {name} value '{raw}': {err}; expected whole days"))?;
+    Ok((days > 0).then_some(days))
+}
+
+impl DateWindow {
+    /// Builds the window from the values of [`DATE_GUARD_ENV`],
+    /// [`MAX_FUTURE_DAYS_ENV`] and [`MAX_PAST_DAYS_ENV`].
+    ///
+    /// The mode is `reject` (default), `warn` or `off`. A day limit of `0`
+    /// removes that limit.
+    ///
+    /// # Errors
+    ///
+    /// Returns a message if a value is not recognized.
+    #[inline]
+    pub fn from_settings(
+        mode: Option<&str>,
+        max_future_days: Option<&str>,
+        max_past_days: Option<&str>,
+    ) -> Result<Self, String> {
+        let guard_mode = match mode.map(|raw| raw.trim().to_lowercase()).as_deref() {
+            None | Some("" | "reject") => GuardMode::Reject,
+            Some("warn") => GuardMode::Warn,
+            Some("off") => GuardMode::Off,
+            Some(other) => {
+                return Err(format!(
+                    "invalid {DATE_GUARD_ENV} value '{other}'; expected reject, warn or off"
+                ));
+            }
+        };
+        Ok(Self {
+            mode: guard_mode,
+            max_future_days: parse_days(
+                MAX_FUTURE_DAYS_ENV,
+                max_future_days,
+                Some(DEFAULT_MAX_FUTURE_DAYS),
+            )?,
+            max_past_days: parse_days(MAX_PAST_DAYS_ENV, max_past_days, None)?,
+        })
+    }
+
+    /// Describes the window for the `doctor` settings report.
+    #[inline]
+    #[must_use]
+    pub fn describe(&self) -> String {
+        let action = match self.mode {
+            GuardMode::Off => return "date guard: off".to_owned(),
+            GuardMode::Warn => "warn on",
+            GuardMode::Reject => "reject",
+        };
+        let limit = |days: Option<u32>| {
+            days.map_or_else(|| "no limit".to_owned(), |count| format!("{count} days"))
+        };
+        format!(
+            "date guard: {action} dates over {} ahead or {} back, or before the account start",
+            limit(self.max_future_days),
+            limit(self.max_past_days)
+        )
+    }
+
+    /// Returns why `date` is out of range, if it is.
+    fn violation(
+        &self,
+        date: NaiveDate,
+        today: NaiveDate,
+        account_start: Option<NaiveDate>,
+    ) -> Option<String> {
+        let offset = date.signed_duration_since(today).num_days();
+        if let Some(start) = account_start.filter(|start| date < *start) {
+            return Some(format!(
+                "date {date} is before the account start date {start}"
+            ));
+        }
+        if self
+            .max_future_days
+            .is_some_and(|days| offset > i64::from(days))
+        {
+            return Some(format!(
+                "date {date} is {offset} days after today ({today})"
+            ));
+        }
+        if self
+            .max_past_days
+            .is_some_and(|days| -offset > i64::from(days))
+        {
+            return Some(format!(
+                "date {date} is {} days before today ({today})",
+                -offset
+            ));
+        }
+        None
+    }
+
+    /// Checks a transaction date, honoring the mode.
+    ///
+    /// # Errors
+    ///
+    /// Returns a message in `reject` mode if `date` is out of range.
+    pub(crate) fn check(
+        &self,
+        date: NaiveDate,
+        today: NaiveDate,
+        account_start: Option<NaiveDate>,
+    ) -> Result<(), String> {
+        if self.mode == GuardMode::Off {
+            return Ok(());
+        }
+        let Some(problem) = self.violation(date, today, account_start) else {
+            return Ok(());
+        };
+        if self.mode == GuardMode::Warn {
+            tracing::warn!(%date, "{problem}");
+            return Ok(());
+        }
+        Err(format!(
+            "{problem}; check the year, or pass allow_out_of_range=true if the date is intended"
+        ))
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect for readability"
+)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::DateWindow;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).expect("valid date")
+    }
+
+    #[test]
+    fn rejects_dates_out_of_window() {
+        let today = date(2024, 6, 15);
+        let window = DateWindow::from_settings(None, None, Some("365")).expect("valid settings");
+        assert!(window.check(date(2024, 8, 1), today, None).is_ok());
+        assert!(window.check(date(2025, 6, 15), today, None).is_err());
+        assert!(window.check(date(2023, 6, 1), today, None).is_err());
+        assert!(
+            window
+                .check(date(2024, 1, 1), today, Some(date(2024, 2, 1)))
+                .is_err()
+        );
+
+        let warn = DateWindow::from_settings(Some("warn"), Some("0"), None).expect("valid");
+        assert!(
+            warn.check(date(2030, 1, 1), today, Some(date(2031, 1, 1)))
+                .is_ok()
+        );
+        assert!(DateWindow::from_settings(Some("loud"), None, None).is_err());
+        assert!(DateWindow::from_settings(None, Some("soon"), None).is_err());
+    }
+}
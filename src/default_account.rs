@@ -0,0 +1,131 @@
+//! Default account for quick creates.
+//!
+//! Most expenses logged from a chat ("I spent 300 on coffee") come from the
+//! same card, so `create_transaction` may omit `account_id` once a default
+//! account is known. It is chosen with the `set_default_account` tool, which
+//! saves it as a JSON file next to the ZenMoney storage data, or with
+//! `ZENMONEY_DEFAULT_ACCOUNT`, which applies while nothing was saved.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::persist::{lock_err, read_json_list, write_json_list};
+
+/// Environment variable with the default account ID.
+pub const DEFAULT_ACCOUNT_ENV: &str = "ZENMONEY_DEFAULT_ACCOUNT";
+
+/// File name used for the saved default account inside the data directory.
+const DEFAULT_ACCOUNT_FILE: &str = "default_account.json";
+
+/// Default account, optionally backed by a JSON file.
+#[derive(Debug, Default)]
+pub struct DefaultAccountStore {
+    /// Path of the backing file (`None` keeps the choice in memory only).
+    path: Option<PathBuf>,
+    /// Account used while none is saved, from [`DEFAULT_ACCOUNT_ENV`].
+    fallback: Option<String>,
+    /// Account ID saved by `set_default_account`.
+    saved: Mutex<Option<String>>,
+}
+
+impl DefaultAccountStore {
+    /// Opens the default account store inside `dir`, loading the saved choice.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read or parsed.
+    #[inline]
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        let path = dir.join(DEFAULT_ACCOUNT_FILE);
+        let saved: Vec<String> = read_json_list(&path)?;
+        Ok(Self {
+            path: Some(path),
+            fallback: None,
+            saved: Mutex::new(saved.last().cloned()),
+        })
+    }
+
+    /// Sets the account used while none is saved; blank values are ignored.
+    #[inline]
+    #[must_use]
+    pub fn with_fallback(mut self, account_id: Option<String>) -> Self {
+        self.fallback = account_id
+            .map(|id| id.trim().to_owned())
+            .filter(|id| !id.is_empty());
+        self
+    }
+
+    /// Returns the default account ID and whether it was saved (rather than
+    /// taken from the environment).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned.
+    pub(crate) fn account(&self) -> io::Result<Option<(String, bool)>> {
+        let saved = self.saved.lock().map_err(|err| lock_err(&err))?.clone();
+        Ok(saved
+            .map(|id| (id, true))
+            .or_else(|| self.fallback.clone().map(|id| (id, false))))
+    }
+
+    /// Saves `account_id` as the default, or clears the saved one with `None`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned or the file cannot be written.
+    pub(crate) fn set(&self, account_id: Option<String>) -> io::Result<()> {
+        let mut saved = self.saved.lock().map_err(|err| lock_err(&err))?;
+        saved.clone_from(&account_id);
+        // Keep the lock while writing so concurrent changes cannot interleave.
+        let persisted = self.path.as_deref().map_or(Ok(()), |path| {
+            write_json_list(path, &account_id.iter().collect::<Vec<_>>())
+        });
+        drop(saved);
+        persisted
+    }
+
+    /// Forgets the saved default account, returning whether one was saved.
+    ///
+    /// The fallback from [`DEFAULT_ACCOUNT_ENV`] still applies afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned or the file cannot be written.
+    pub(crate) fn clear(&self) -> io::Result<bool> {
+        let cleared = self.saved.lock().map_err(|err| lock_err(&err))?.is_some();
+        self.set(None).map(|()| cleared)
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect for readability"
+)]
+mod tests {
+    use super::DefaultAccountStore;
+    use crate::persist::TempDir;
+
+    #[test]
+    fn saved_account_overrides_fallback_until_cleared() {
+        let dir = TempDir::new();
+        let store = DefaultAccountStore::open(&dir)
+            .expect("open empty store")
+            .with_fallback(Some(" acc-env ".to_owned()));
+        assert_eq!(
+            store.account().expect("account"),
+            Some(("acc-env".to_owned(), false))
+        );
+        store.set(Some("acc-card".to_owned())).expect("save");
+        let reopened = DefaultAccountStore::open(&dir).expect("reopen store");
+        assert_eq!(
+            reopened.account().expect("account"),
+            Some(("acc-card".to_owned(), true))
+        );
+        reopened.set(None).expect("clear");
+        let cleared = DefaultAccountStore::open(&dir).expect("reopen store");
+        assert_eq!(cleared.account().expect("account"), None);
+    }
+}
@@ -0,0 +1,468 @@
+//! Demo mode with a generated dataset.
+//!
+//! With `ZENMONEY_DEMO=1` the binary never contacts ZenMoney: the server
+//! runs on in-memory storage seeded by [`dataset`] with a few accounts,
+//! categories, merchants, budgets and six months of everyday transactions.
+//! Tools that would call the API (syncs and writes) are refused, so people
+//! can evaluate the server or record demos without a real token.
+
+use chrono::{DateTime, Datelike as _, Days, NaiveDate, Utc};
+use zenmoney_rs::models::{
+    Account, AccountId, AccountType, Budget, Instrument, InstrumentId, Merchant, MerchantId, Tag,
+    TagId, Transaction, TransactionId, User, UserId,
+};
+
+use crate::backup::BackupEntities;
+
+/// Environment variable that turns demo mode on.
+pub const DEMO_ENV: &str = "ZENMONEY_DEMO";
+
+/// Sentence appended to the instructions in demo mode.
+pub const DEMO_NOTICE: &str = "Demo mode: all data is generated and ZenMoney is never \
+     contacted; sync and write tools are disabled.";
+
+/// Tools that call the ZenMoney API besides the write tools.
+pub(crate) const ONLINE_TOOLS: &[&str] = &["sync", "full_sync", "sync_entities"];
+
+/// Number of days of generated transactions.
+const HISTORY_DAYS: u64 = 180;
+
+/// ID of the demo user.
+const USER_ID: i64 = 1;
+
+/// Ruble instrument, the base currency.
+const RUB: i32 = 1;
+
+/// US dollar instrument.
+const USD: i32 = 2;
+
+/// Main debit card account.
+const CARD: &str = "demo-card";
+
+/// Cash wallet account.
+const CASH: &str = "demo-cash";
+
+/// Credit card account.
+const CREDIT: &str = "demo-credit";
+
+/// Dollar savings account.
+const SAVINGS: &str = "demo-savings";
+
+/// Deterministic pseudo-random numbers, so every demo session looks the same.
+struct Lcg {
+    /// Generator state.
+    state: u64,
+}
+
+impl Lcg {
+    /// Returns an amount between `min` and `max`, rounded to ten units.
+    fn amount(&mut self, min: u32, max: u32) -> f64 {
+        self.state = self
+            .state
+            .wrapping_mul(0x5851_f42d_4c95_7f2d)
+            .wrapping_add(0x1405_7b7e_f767_814f);
+        let span = u64::from(max.saturating_sub(min).max(1));
+        let offset = u32::try_from((self.state >> 33) % span).unwrap_or_default();
+        f64::from(min.saturating_add(offset).div_euclid(10).saturating_mul(10))
+    }
+}
+
+/// Noon UTC of `date`, used as the creation and change time of entities.
+fn stamp(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(12, 0, 0).unwrap_or_default().and_utc()
+}
+
+/// Builds an account.
+fn account(
+    id: &str,
+    title: &str,
+    kind: AccountType,
+    instrument: i32,
+    balance: f64,
+    now: DateTime<Utc>,
+) -> Account {
+    Account {
+        id: AccountId::new(id.to_owned()),
+        changed: now,
+        user: UserId::new(USER_ID),
+        role: None,
+        instrument: Some(InstrumentId::new(instrument)),
+        company: None,
+        kind,
+        title: title.to_owned(),
+        sync_id: None,
+        balance: Some(balance),
+        start_balance: None,
+        credit_limit: None,
+        in_balance: true,
+        savings: None,
+        enable_correction: false,
+        enable_sms: false,
+        archive: false,
+        capitalization: None,
+        percent: None,
+        start_date: None,
+        end_date_offset: None,
+        end_date_offset_interval: None,
+        payoff_step: None,
+        payoff_interval: None,
+        balance_correction_type: None,
+        private: None,
+    }
+}
+
+/// Builds a category tag; `income` marks categories shown for income.
+fn tag(id: &str, title: &str, income: bool, now: DateTime<Utc>) -> Tag {
+    Tag {
+        id: TagId::new(id.to_owned()),
+        changed: now,
+        user: UserId::new(USER_ID),
+        title: title.to_owned(),
+        parent: None,
+        icon: None,
+        picture: None,
+        color: None,
+        show_income: income,
+        show_outcome: !income,
+        budget_income: income,
+        budget_outcome: !income,
+        required: None,
+        static_id: None,
+        archive: None,
+    }
+}
+
+/// Builds a currency instrument.
+fn instrument(id: i32, title: &str, short_title: &str, symbol: &str, rate: f64) -> Instrument {
+    Instrument {
+        id: InstrumentId::new(id),
+        changed: DateTime::UNIX_EPOCH,
+        title: title.to_owned(),
+        short_title: short_title.to_owned(),
+        symbol: symbol.to_owned(),
+        rate,
+    }
+}
+
+/// A generated transaction before it gets an ID.
+struct Entry<'entry> {
+    /// Account money leaves, if any.
+    from: Option<&'entry str>,
+    /// Account money arrives on, if any.
+    to: Option<&'entry str>,
+    /// Amount in the account currency.
+    amount: f64,
+    /// Category tag ID.
+    tag: Option<&'entry str>,
+    /// Merchant ID, whose title is also used as the payee.
+    merchant: Option<(&'entry str, &'entry str)>,
+}
+
+/// Builds a transaction from `entry`; transfers between a ruble and a dollar
+/// account are converted at the demo rate.
+fn transaction(id: usize, date: NaiveDate, entry: &Entry<'_>) -> Transaction {
+    let from = entry.from.or(entry.to).unwrap_or(CARD);
+    let to = entry.to.or(entry.from).unwrap_or(CARD);
+    let outcome = if entry.from.is_some() {
+        entry.amount
+    } else {
+        0.0
+    };
+    let income = match (entry.from, entry.to) {
+        (Some(_), Some(SAVINGS)) => (entry.amount / 90.0).round(),
+        (_, Some(_)) => entry.amount,
+        (_, None) => 0.0,
+    };
+    Transaction {
+        id: TransactionId::new(format!("demo-tx-{id}")),
+        changed: stamp(date),
+        created: stamp(date),
+        user: UserId::new(USER_ID),
+        deleted: false,
+        hold: None,
+        income_instrument: InstrumentId::new(if to == SAVINGS { USD } else { RUB }),
+        income_account: AccountId::new(to.to_owned()),
+        income,
+        outcome_instrument: InstrumentId::new(RUB),
+        outcome_account: AccountId::new(from.to_owned()),
+        outcome,
+        tag: entry.tag.map(|tag_id| vec![TagId::new(tag_id.to_owned())]),
+        merchant: entry
+            .merchant
+            .map(|merchant| MerchantId::new(merchant.0.to_owned())),
+        payee: entry.merchant.map(|merchant| merchant.1.to_owned()),
+        original_payee: None,
+        comment: None,
+        date,
+        mcc: None,
+        reminder_marker: None,
+        op_income: None,
+        op_income_instrument: None,
+        op_outcome: None,
+        op_outcome_instrument: None,
+        latitude: None,
+        longitude: None,
+        income_bank_id: None,
+        outcome_bank_id: None,
+        qr_code: None,
+        source: None,
+        viewed: Some(true),
+    }
+}
+
+/// Generates the entries of one day: salary and rent at the start of the
+/// month, groceries, coffee, transport and the occasional evening out.
+fn day_entries(date: NaiveDate, rng: &mut Lcg) -> Vec<Entry<'static>> {
+    let day = date.day();
+    let mut entries = Vec::new();
+    let mut spend = |from, amount, tag, merchant| {
+        entries.push(Entry {
+            from: Some(from),
+            to: None,
+            amount,
+            tag: Some(tag),
+            merchant,
+        });
+    };
+    if day == 1 {
+        spend(CARD, 45_000.0, "demo-housing", None);
+    }
+    if day % 3 == 0 {
+        let amount = rng.amount(900, 4_500);
+        spend(
+            CARD,
+            amount,
+            "demo-groceries",
+            Some(("demo-m-market", "Green Market")),
+        );
+    }
+    if day % 2 == 1 {
+        let amount = rng.amount(250, 450);
+        spend(
+            CASH,
+            amount,
+            "demo-cafe",
+            Some(("demo-m-coffee", "Corner Coffee")),
+        );
+    }
+    if date.weekday().num_days_from_monday() < 5 && day % 4 == 2 {
+        let amount = rng.amount(300, 900);
+        spend(
+            CREDIT,
+            amount,
+            "demo-transport",
+            Some(("demo-m-taxi", "City Taxi")),
+        );
+    }
+    if day == 12 || day == 26 {
+        let amount = rng.amount(1_500, 6_000);
+        spend(
+            CREDIT,
+            amount,
+            "demo-fun",
+            Some(("demo-m-cinema", "Star Cinema")),
+        );
+    }
+    if day == 18 {
+        let amount = rng.amount(600, 2_500);
+        spend(
+            CARD,
+            amount,
+            "demo-health",
+            Some(("demo-m-pharmacy", "Pharmacy No. 1")),
+        );
+    }
+    let mut move_money = |from, to, amount, tag| {
+        entries.push(Entry {
+            from,
+            to: Some(to),
+            amount,
+            tag,
+            merchant: None,
+        });
+    };
+    if day == 5 {
+        move_money(None, CARD, 150_000.0, Some("demo-salary"));
+    }
+    if day == 7 {
+        move_money(Some(CARD), CASH, 8_000.0, None);
+        move_money(Some(CARD), SAVINGS, 18_000.0, None);
+    }
+    entries
+}
+
+/// Generates six months of transactions ending on `today`.
+fn transactions(today: NaiveDate) -> Vec<Transaction> {
+    let mut rng = Lcg { state: 0x5eed };
+    let mut result = Vec::new();
+    for offset in (0..HISTORY_DAYS).rev() {
+        let Some(date) = today.checked_sub_days(Days::new(offset)) else {
+            continue;
+        };
+        for entry in day_entries(date, &mut rng) {
+            result.push(transaction(result.len(), date, &entry));
+        }
+    }
+    result
+}
+
+/// Category budgets for the current and the previous month.
+fn budgets(today: NaiveDate, now: DateTime<Utc>) -> Vec<Budget> {
+    let this_month = today.with_day(1).unwrap_or(today);
+    let months = [
+        this_month
+            .checked_sub_days(Days::new(1))
+            .and_then(|date| date.with_day(1)),
+        Some(this_month),
+    ];
+    let limits = [
+        ("demo-groceries", 25_000.0),
+        ("demo-cafe", 6_000.0),
+        ("demo-transport", 7_000.0),
+        ("demo-fun", 8_000.0),
+    ];
+    months
+        .into_iter()
+        .flatten()
+        .flat_map(|month| {
+            limits.into_iter().map(move |limit| Budget {
+                changed: now,
+                user: UserId::new(USER_ID),
+                tag: Some(TagId::new(limit.0.to_owned())),
+                date: month,
+                income: 0.0,
+                income_lock: false,
+                outcome: limit.1,
+                outcome_lock: false,
+                is_income_forecast: None,
+                is_outcome_forecast: None,
+            })
+        })
+        .collect()
+}
+
+/// The demo user, keeping books in rubles.
+fn user(now: DateTime<Utc>) -> User {
+    User {
+        id: UserId::new(USER_ID),
+        changed: now,
+        login: Some("demo".to_owned()),
+        currency: InstrumentId::new(RUB),
+        parent: None,
+        country: None,
+        country_code: None,
+        email: None,
+        is_forecast_enabled: None,
+        month_start_day: None,
+        paid_till: None,
+        plan_balance_mode: None,
+        plan_settings: None,
+        subscription: None,
+        subscription_renewal_date: None,
+    }
+}
+
+/// Merchants the generated transactions are paid to.
+fn merchants(now: DateTime<Utc>) -> Vec<Merchant> {
+    [
+        ("demo-m-market", "Green Market"),
+        ("demo-m-coffee", "Corner Coffee"),
+        ("demo-m-taxi", "City Taxi"),
+        ("demo-m-cinema", "Star Cinema"),
+        ("demo-m-pharmacy", "Pharmacy No. 1"),
+    ]
+    .into_iter()
+    .map(|merchant| Merchant {
+        id: MerchantId::new(merchant.0.to_owned()),
+        changed: now,
+        user: UserId::new(USER_ID),
+        title: merchant.1.to_owned(),
+    })
+    .collect()
+}
+
+/// Generates the demo dataset with transactions up to `today`.
+pub(crate) fn dataset(today: NaiveDate) -> BackupEntities {
+    let now = stamp(today);
+    let mut credit = account(
+        CREDIT,
+        "Credit Card",
+        AccountType::CreditCard,
+        RUB,
+        -12_400.0,
+        now,
+    );
+    credit.credit_limit = Some(100_000.0);
+    BackupEntities {
+        users: vec![user(now)],
+        instruments: vec![
+            instrument(RUB, "Russian Ruble", "RUB", "\u{20bd}", 1.0),
+            instrument(USD, "US Dollar", "USD", "$", 90.0),
+        ],
+        accounts: vec![
+            account(
+                CARD,
+                "Debit Card",
+                AccountType::Checking,
+                RUB,
+                84_250.0,
+                now,
+            ),
+            account(CASH, "Wallet", AccountType::Cash, RUB, 3_600.0, now),
+            credit,
+            account(
+                SAVINGS,
+                "Dollar Savings",
+                AccountType::Deposit,
+                USD,
+                1_200.0,
+                now,
+            ),
+        ],
+        tags: vec![
+            tag("demo-groceries", "Groceries", false, now),
+            tag("demo-cafe", "Cafes", false, now),
+            tag("demo-transport", "Transport", false, now),
+            tag("demo-housing", "Housing", false, now),
+            tag("demo-fun", "Entertainment", false, now),
+            tag("demo-health", "Health", false, now),
+            tag("demo-salary", "Salary", true, now),
+        ],
+        merchants: merchants(now),
+        budgets: budgets(today, now),
+        transactions: transactions(today),
+        ..BackupEntities::default()
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect for readability"
+)]
+mod tests {
+    use std::collections::HashSet;
+
+    use chrono::NaiveDate;
+
+    use super::dataset;
+
+    #[test]
+    fn dataset_is_consistent_and_deterministic() {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 15).expect("valid date");
+        let data = dataset(today);
+        assert!(data.transactions.len() > 150);
+        assert!(data.transactions.iter().all(|tx| tx.date <= today));
+        let accounts: HashSet<&str> = data.accounts.iter().map(|acc| acc.id.as_inner()).collect();
+        let tags: HashSet<&str> = data.tags.iter().map(|tag| tag.id.as_inner()).collect();
+        for tx in &data.transactions {
+            assert!(accounts.contains(tx.income_account.as_inner()));
+            assert!(accounts.contains(tx.outcome_account.as_inner()));
+            let mut tx_tags = tx.tag.iter().flatten();
+            assert!(tx_tags.all(|tag| tags.contains(tag.as_inner())));
+        }
+        assert_eq!(data.budgets.len(), 8);
+        assert_eq!(dataset(today).counts(), data.counts());
+    }
+}
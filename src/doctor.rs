@@ -0,0 +1,249 @@
+//! Startup diagnostics for `zenmoney-mcp doctor`.
+//!
+//! A misconfigured server shows up in the MCP client only as a disconnect
+//! during the handshake. The doctor runs the setup steps one by one and
+//! reports each with an actionable message instead.
+
+use std::io::Write as _;
+use std::path::Path;
+
+use zenmoney_rs::models::SuggestRequest;
+use zenmoney_rs::storage::{FileStorage, InMemoryStorage, Storage};
+use zenmoney_rs::zen_money::ZenMoney;
+
+use crate::confirm::{self, CONFIRM_DESTRUCTIVE_ENV};
+use crate::default_account::DEFAULT_ACCOUNT_ENV;
+use crate::freshness::{self, MAX_DATA_AGE_ENV};
+use crate::server::BASE_CURRENCY_ENV;
+use crate::settings::{date_window, scheduled_jobs};
+use crate::storage::{STORAGE_ENV, StorageKind};
+use crate::throttle::{self, MIN_REQUEST_INTERVAL_ENV};
+
+/// File written and removed to test that the storage directory is writable.
+const PROBE_FILE: &str = ".zenmoney-doctor-probe";
+
+/// Outcome of one diagnostic check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckResult {
+    /// What was checked.
+    pub name: &'static str,
+    /// Whether the check passed.
+    pub passed: bool,
+    /// What was found, or how to fix the problem.
+    pub detail: String,
+}
+
+impl CheckResult {
+    /// Creates a passed check.
+    #[inline]
+    #[must_use]
+    pub const fn pass(name: &'static str, detail: String) -> Self {
+        Self {
+            name,
+            passed: true,
+            detail,
+        }
+    }
+
+    /// Creates a failed check.
+    #[inline]
+    #[must_use]
+    pub const fn fail(name: &'static str, detail: String) -> Self {
+        Self {
+            name,
+            passed: false,
+            detail,
+        }
+    }
+}
+
+impl core::fmt::Display for CheckResult {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let status = if self.passed { "ok" } else { "FAIL" };
+        write!(f, "[{status}] {}: {}", self.name, self.detail)
+    }
+}
+
+/// Checks that `dir` exists, creating it if needed, and accepts new files.
+#[inline]
+#[must_use]
+pub fn check_storage_dir(dir: &Path) -> CheckResult {
+    let probe = dir.join(PROBE_FILE);
+    let outcome = std::fs::create_dir_all(dir)
+        .and_then(|()| std::fs::write(&probe, b"ok"))
+        .and_then(|()| std::fs::remove_file(&probe));
+    match outcome {
+        Ok(()) => CheckResult::pass("storage", format!("{} is writable", dir.display())),
+        Err(err) => CheckResult::fail(
+            "storage",
+            format!(
+                "cannot write to {}: {err}; fix the directory permissions or set \
+                 ZENMONEY_STORAGE=memory",
+                dir.display()
+            ),
+        ),
+    }
+}
+
+/// Checks that ZenMoney accepts the client's token, using a lightweight
+/// category suggestion request instead of a sync.
+#[inline]
+pub async fn check_token<S: Storage + 'static>(client: &ZenMoney<S>) -> CheckResult {
+    let request = SuggestRequest {
+        payee: Some("doctor".to_owned()),
+        comment: None,
+    };
+    match client.suggest(&request).await {
+        Ok(_response) => CheckResult::pass("token", "ZenMoney accepted the token".to_owned()),
+        Err(err) => CheckResult::fail(
+            "token",
+            format!(
+                "ZenMoney request failed: {err}; check that ZENMONEY_TOKEN is a valid API \
+                 token and that api.zenmoney.ru is reachable"
+            ),
+        ),
+    }
+}
+
+/// Describes the storage backend and checks that its directory is writable.
+fn storage_report(config: &mut Vec<String>, checks: &mut Vec<CheckResult>) {
+    match StorageKind::from_setting(std::env::var(STORAGE_ENV).ok().as_deref()) {
+        Ok(StorageKind::File) => match FileStorage::default_dir() {
+            Ok(dir) => {
+                config.push(format!("storage: file, data dir {}", dir.display()));
+                checks.push(check_storage_dir(&dir));
+            }
+            Err(err) => checks.push(CheckResult::fail(
+                "storage",
+                format!(
+                    "cannot locate the data directory: {err}; set XDG_DATA_HOME or \
+                     ZENMONEY_STORAGE=memory"
+                ),
+            )),
+        },
+        Ok(StorageKind::Memory) => {
+            config.push("storage: memory (nothing written to disk)".to_owned());
+        }
+        Err(message) => checks.push(CheckResult::fail("storage", message)),
+    }
+}
+
+/// Describes the tunable settings, failing a check for each invalid one.
+fn settings_report(config: &mut Vec<String>, checks: &mut Vec<CheckResult>) {
+    match freshness::parse_max_data_age(std::env::var(MAX_DATA_AGE_ENV).ok().as_deref()) {
+        Ok(Some(max_age)) => config.push(format!(
+            "auto-sync before reads: after {} minutes",
+            max_age.as_secs().div_euclid(60)
+        )),
+        Ok(None) => config.push("auto-sync before reads: off".to_owned()),
+        Err(message) => checks.push(CheckResult::fail("settings", message)),
+    }
+    match throttle::parse_min_request_interval(
+        std::env::var(MIN_REQUEST_INTERVAL_ENV).ok().as_deref(),
+    ) {
+        Ok(interval) => config.push(format!("min request interval: {} ms", interval.as_millis())),
+        Err(message) => checks.push(CheckResult::fail("settings", message)),
+    }
+    let confirm_destructive =
+        std::env::var(CONFIRM_DESTRUCTIVE_ENV).is_ok_and(|value| confirm::is_enabled(&value));
+    config.push(format!("confirm destructive: {confirm_destructive}"));
+    let base_currency = std::env::var(BASE_CURRENCY_ENV).ok();
+    config.push(format!(
+        "base currency: {}",
+        base_currency
+            .as_deref()
+            .unwrap_or("account owner's currency")
+    ));
+    config.push(format!(
+        "default account: {}",
+        std::env::var(DEFAULT_ACCOUNT_ENV).unwrap_or_else(|_| "none".to_owned())
+    ));
+    match date_window() {
+        Ok(window) => config.push(window.describe()),
+        Err(message) => checks.push(CheckResult::fail("settings", message)),
+    }
+    match scheduled_jobs() {
+        Ok(Some(jobs)) => config.push(format!("scheduled reports: {} jobs", jobs.len())),
+        Ok(None) => config.push("scheduled reports: none".to_owned()),
+        Err(message) => checks.push(CheckResult::fail("settings", message)),
+    }
+}
+
+/// Checks that `ZENMONEY_TOKEN` is set and accepted by ZenMoney.
+async fn token_check() -> CheckResult {
+    let Ok(token) = std::env::var("ZENMONEY_TOKEN") else {
+        return CheckResult::fail(
+            "token",
+            "ZENMONEY_TOKEN is not set; export your ZenMoney API token (see the README)".to_owned(),
+        );
+    };
+    match ZenMoney::builder()
+        .token(token)
+        .storage(InMemoryStorage::new())
+        .build()
+    {
+        Ok(client) => check_token(&client).await,
+        Err(err) => CheckResult::fail("token", format!("cannot build the ZenMoney client: {err}")),
+    }
+}
+
+/// Checks the configuration and the token, printing a report to stderr.
+///
+/// # Errors
+///
+/// Returns an error naming the number of failed checks, or if the report
+/// cannot be written.
+#[inline]
+pub async fn run() -> Result<(), Box<dyn core::error::Error>> {
+    let mut config = vec![
+        format!("version: {}", env!("CARGO_PKG_VERSION")),
+        "transport: stdio".to_owned(),
+        "timezone: UTC (\"today\" and the current month are computed in UTC)".to_owned(),
+    ];
+    let mut checks = Vec::new();
+    storage_report(&mut config, &mut checks);
+    settings_report(&mut config, &mut checks);
+    checks.push(token_check().await);
+
+    let mut stderr = std::io::stderr().lock();
+    writeln!(stderr, "Configuration:")?;
+    for line in &config {
+        writeln!(stderr, "  {line}")?;
+    }
+    writeln!(stderr, "Checks:")?;
+    for check in &checks {
+        writeln!(stderr, "  {check}")?;
+    }
+    let failed = checks.iter().filter(|check| !check.passed).count();
+    if failed > 0 {
+        return Err(format!("doctor found {failed} problem(s)").into());
+    }
+    writeln!(stderr, "All checks passed.")?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect for readability"
+)]
+mod tests {
+    use super::{CheckResult, check_storage_dir};
+    use crate::persist::TempDir;
+
+    #[test]
+    fn storage_dir_is_created_and_probed() {
+        let dir = TempDir::new();
+        let check = check_storage_dir(&dir.join("nested"));
+        assert!(check.passed, "{check}");
+        assert!(!dir.join("nested").join(super::PROBE_FILE).exists());
+
+        let failed = CheckResult::fail("token", "ZENMONEY_TOKEN is not set".to_owned());
+        assert_eq!(
+            failed.to_string(),
+            "[FAIL] token: ZENMONEY_TOKEN is not set"
+        );
+    }
+}
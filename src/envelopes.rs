@@ -0,0 +1,200 @@
+//! Persistent envelope budgets.
+//!
+//! Envelopes split monthly income into named pots, each covering one or
+//! more category tags. Allocations are recorded per month and unspent money
+//! rolls over, so an envelope's balance is everything allocated so far minus
+//! everything spent in its categories. Envelopes exist only locally and are
+//! stored as a JSON file next to the ZenMoney storage data.
+
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use core::ops::Bound;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::persist::{lock_err, name_key, read_json_list, write_json_list};
+
+/// File name used for envelope storage inside the data directory.
+const ENVELOPES_FILE: &str = "envelopes.json";
+
+/// A named envelope mapped to category tags.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct Envelope {
+    /// Envelope name as entered by the user.
+    pub(crate) name: String,
+    /// Category tag IDs whose expenses are paid from this envelope.
+    pub(crate) tag_ids: Vec<String>,
+    /// Allocated amounts keyed by month (`YYYY-MM`).
+    pub(crate) allocations: BTreeMap<String, f64>,
+}
+
+impl Envelope {
+    /// Returns the amount allocated in `month` (`YYYY-MM`).
+    pub(crate) fn allocated_in(&self, month: &str) -> f64 {
+        self.allocations.get(month).copied().unwrap_or_default()
+    }
+
+    /// Returns the total allocated in all months before `month`.
+    pub(crate) fn allocated_before(&self, month: &str) -> f64 {
+        self.allocations
+            .range::<str, _>((Bound::Unbounded, Bound::Excluded(month)))
+            .map(|(_month, amount)| amount)
+            .sum()
+    }
+
+    /// Adds `amount` (possibly negative) to the allocation for `month`.
+    pub(crate) fn allocate(&mut self, month: &str, amount: f64) {
+        *self.allocations.entry(month.to_owned()).or_insert(0.0) += amount;
+    }
+}
+
+/// Envelope collection, optionally backed by a JSON file.
+#[derive(Debug, Default)]
+pub struct EnvelopeStore {
+    /// Path of the backing file (`None` keeps envelopes in memory only).
+    path: Option<PathBuf>,
+    /// Envelopes keyed by lowercased name.
+    envelopes: Mutex<BTreeMap<String, Envelope>>,
+}
+
+impl EnvelopeStore {
+    /// Opens the envelope store inside `dir`, loading any saved envelopes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read or parsed.
+    #[inline]
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        let path = dir.join(ENVELOPES_FILE);
+        let envelopes: Vec<Envelope> = read_json_list(&path)?;
+        let map = envelopes
+            .into_iter()
+            .map(|envelope| (name_key(&envelope.name), envelope))
+            .collect();
+        Ok(Self {
+            path: Some(path),
+            envelopes: Mutex::new(map),
+        })
+    }
+
+    /// Returns all envelopes sorted by name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned.
+    pub(crate) fn list(&self) -> io::Result<Vec<Envelope>> {
+        let envelopes = self.envelopes.lock().map_err(|err| lock_err(&err))?;
+        Ok(envelopes.values().cloned().collect())
+    }
+
+    /// Looks up an envelope by name (case-insensitive).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned.
+    pub(crate) fn get(&self, name: &str) -> io::Result<Option<Envelope>> {
+        let envelopes = self.envelopes.lock().map_err(|err| lock_err(&err))?;
+        Ok(envelopes.get(&name_key(name)).cloned())
+    }
+
+    /// Saves envelopes in one write, replacing any with the same names.
+    ///
+    /// Saving several envelopes together keeps a move between two of them
+    /// from being half-applied.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned or the file cannot be written.
+    pub(crate) fn save(&self, changed: Vec<Envelope>) -> io::Result<()> {
+        let mut envelopes = self.envelopes.lock().map_err(|err| lock_err(&err))?;
+        for envelope in changed {
+            let _previous = envelopes.insert(name_key(&envelope.name), envelope);
+        }
+        // Keep the lock while writing so concurrent saves cannot interleave.
+        let persisted = self.persist(&envelopes);
+        drop(envelopes);
+        persisted
+    }
+
+    /// Deletes all envelopes, returning how many there were.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned or the file cannot be written.
+    pub(crate) fn clear(&self) -> io::Result<usize> {
+        let mut envelopes = self.envelopes.lock().map_err(|err| lock_err(&err))?;
+        let count = envelopes.len();
+        envelopes.clear();
+        let persisted = self.persist(&envelopes);
+        drop(envelopes);
+        persisted.map(|()| count)
+    }
+
+    /// Writes all envelopes to the backing file, if any.
+    fn persist(&self, envelopes: &BTreeMap<String, Envelope>) -> io::Result<()> {
+        let Some(path) = self.path.as_ref() else {
+            return Ok(());
+        };
+        let values: Vec<&Envelope> = envelopes.values().collect();
+        write_json_list(path, &values)
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::indexing_slicing,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect and indexing for readability"
+)]
+mod tests {
+    use super::{Envelope, EnvelopeStore};
+    use crate::persist::TempDir;
+
+    fn groceries() -> Envelope {
+        Envelope {
+            name: "Groceries".to_owned(),
+            tag_ids: vec!["tag-1".to_owned()],
+            allocations: [("2024-05".to_owned(), 300.0), ("2024-06".to_owned(), 400.0)]
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn allocation_totals_by_month() {
+        let mut envelope = groceries();
+        envelope.allocate("2024-06", -50.0);
+        envelope.allocate("2024-07", 100.0);
+        assert!((envelope.allocated_in("2024-06") - 350.0).abs() < f64::EPSILON);
+        assert!((envelope.allocated_before("2024-07") - 650.0).abs() < f64::EPSILON);
+        assert!(envelope.allocated_before("2024-05").abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn in_memory_save_and_get_case_insensitive() {
+        let store = EnvelopeStore::default();
+        store.save(vec![groceries()]).expect("should save");
+        let found = store.get(" groceries ").expect("lock").expect("found");
+        assert_eq!(found.tag_ids, vec!["tag-1".to_owned()]);
+        assert!(store.get("Rent").expect("lock").is_none());
+    }
+
+    #[test]
+    fn persists_to_file() {
+        let dir = TempDir::new();
+        let store = EnvelopeStore::open(&dir).expect("open empty");
+        assert!(store.list().expect("lock").is_empty());
+        store.save(vec![groceries()]).expect("should save");
+
+        let reopened = EnvelopeStore::open(&dir).expect("reopen");
+        let envelopes = reopened.list().expect("lock");
+        assert_eq!(envelopes.len(), 1);
+        assert_eq!(envelopes[0].allocations.len(), 2);
+    }
+}
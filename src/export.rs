@@ -0,0 +1,420 @@
+//! QIF and OFX export of transactions, one file per account, and CSV
+//! rendering for scheduled reports.
+//!
+//! Both formats describe a single account's statement, so every exported
+//! account gets its own file. Amounts are signed from that account's point of
+//! view: money coming in is positive and money going out is negative, and a
+//! transfer appears in the files of both of its accounts. QIF files record
+//! the first tag as the category, or `[Other account]` for transfers, so
+//! GnuCash and similar tools rebuild the transfer links on import. OFX files
+//! use the OFX 2 XML layout with a bank or credit card statement. CSV
+//! covers every account in one table with both sides of each transaction.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use zenmoney_rs::models::{Account, AccountType, Transaction};
+
+use crate::params::TransactionType;
+use crate::response::LookupMaps;
+use crate::server::classify_transaction;
+
+/// Longest payee OFX allows in a transaction's `NAME`.
+const OFX_NAME_MAX_CHARS: usize = 32;
+
+/// Header row of CSV reports.
+pub(crate) const CSV_HEADER: &str = "date,id,type,outcome,outcome_currency,outcome_account,\
+    income,income_currency,income_account,tags,payee,comment";
+
+/// Interchange format written by an export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ExportFormat {
+    /// Quicken Interchange Format.
+    Qif,
+    /// Open Financial Exchange 2 (XML).
+    Ofx,
+}
+
+impl ExportFormat {
+    /// File extension, also used as the format name in responses.
+    pub(crate) const fn extension(self) -> &'static str {
+        match self {
+            Self::Qif => "qif",
+            Self::Ofx => "ofx",
+        }
+    }
+}
+
+/// Returns the amount of `tx` for `account_id`: income into it minus outcome from it.
+pub(crate) fn signed_amount(tx: &Transaction, account_id: &str) -> f64 {
+    let mut amount = 0.0_f64;
+    if tx.income_account.as_inner() == account_id {
+        amount += tx.income;
+    }
+    if tx.outcome_account.as_inner() == account_id {
+        amount -= tx.outcome;
+    }
+    amount
+}
+
+/// Returns the other account of a transfer, or `None` for other transactions.
+fn transfer_counterpart<'tx>(tx: &'tx Transaction, account_id: &str) -> Option<&'tx str> {
+    let income = tx.income_account.as_inner();
+    let outcome = tx.outcome_account.as_inner();
+    if income == outcome {
+        return None;
+    }
+    Some(if income == account_id {
+        outcome
+    } else {
+        income
+    })
+}
+
+/// Builds a file name from the account title and the start of its ID.
+pub(crate) fn export_file_name(account: &Account, format: ExportFormat) -> String {
+    let title: String = account
+        .title
+        .chars()
+        .map(|ch| if ch.is_alphanumeric() { ch } else { '_' })
+        .collect();
+    let id: String = account.id.as_inner().chars().take(8).collect();
+    format!("{title}-{id}.{}", format.extension())
+}
+
+/// QIF account type header for an account.
+const fn qif_type(kind: AccountType) -> &'static str {
+    match kind {
+        AccountType::Cash => "Cash",
+        AccountType::CreditCard => "CCard",
+        AccountType::Loan => "Oth L",
+        AccountType::Checking | AccountType::Deposit | AccountType::EMoney | AccountType::Debt => {
+            "Bank"
+        }
+    }
+}
+
+/// Renders the transactions of one account as a QIF file.
+pub(crate) fn render_qif(
+    account: &Account,
+    transactions: &[&Transaction],
+    maps: &LookupMaps,
+) -> String {
+    let account_id = account.id.as_inner();
+    let mut lines = vec![format!("!Type:{}", qif_type(account.kind))];
+    for tx in transactions {
+        lines.push(format!("D{}", tx.date.format("%m/%d/%Y")));
+        lines.push(format!("T{:.2}", signed_amount(tx, account_id)));
+        if let Some(payee) = tx.payee.as_deref().filter(|payee| !payee.is_empty()) {
+            lines.push(format!("P{payee}"));
+        }
+        if let Some(comment) = tx.comment.as_deref().filter(|comment| !comment.is_empty()) {
+            lines.push(format!("M{}", comment.replace('\n', " ")));
+        }
+        let category = transfer_counterpart(tx, account_id)
+            .map(|other| format!("[{}]", maps.account_name(other)))
+            .or_else(|| {
+                tx.tag
+                    .iter()
+                    .flatten()
+                    .next()
+                    .map(|tag| maps.tag_name(tag.as_inner()))
+            });
+        if let Some(category) = category {
+            lines.push(format!("L{category}"));
+        }
+        lines.push("^".to_owned());
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// Escapes text for an OFX XML element.
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Renders one `STMTTRN` element.
+fn ofx_transaction(tx: &Transaction, account_id: &str, maps: &LookupMaps) -> String {
+    let amount = signed_amount(tx, account_id);
+    let kind = if transfer_counterpart(tx, account_id).is_some() {
+        "XFER"
+    } else if amount < 0.0 {
+        "DEBIT"
+    } else {
+        "CREDIT"
+    };
+    let name = tx
+        .payee
+        .as_deref()
+        .filter(|payee| !payee.is_empty())
+        .map(str::to_owned)
+        .or_else(|| {
+            tx.merchant
+                .as_ref()
+                .map(|id| maps.merchant_name(id.as_inner()))
+        })
+        .map(|name| {
+            let short: String = name.chars().take(OFX_NAME_MAX_CHARS).collect();
+            format!("<NAME>{}</NAME>", xml_escape(&short))
+        })
+        .unwrap_or_default();
+    let memo = tx
+        .comment
+        .as_deref()
+        .filter(|comment| !comment.is_empty())
+        .map(|comment| format!("<MEMO>{}</MEMO>", xml_escape(comment)))
+        .unwrap_or_default();
+    format!(
+        "<STMTTRN><TRNTYPE>{kind}</TRNTYPE><DTPOSTED>{}</DTPOSTED><TRNAMT>{amount:.2}</TRNAMT>\
+         <FITID>{}</FITID>{name}{memo}</STMTTRN>",
+        tx.date.format("%Y%m%d"),
+        xml_escape(tx.id.as_inner()),
+    )
+}
+
+/// OFX `ACCTTYPE` of a bank account, or `None` for credit cards.
+const fn ofx_account_type(kind: AccountType) -> Option<&'static str> {
+    match kind {
+        AccountType::CreditCard => None,
+        AccountType::Deposit => Some("SAVINGS"),
+        AccountType::Loan => Some("CREDITLINE"),
+        AccountType::Cash | AccountType::Checking | AccountType::EMoney | AccountType::Debt => {
+            Some("CHECKING")
+        }
+    }
+}
+
+/// Renders the `*ACCTFROM` element identifying the account.
+fn ofx_account_from(account: &Account) -> String {
+    let id = xml_escape(account.id.as_inner());
+    ofx_account_type(account.kind).map_or_else(
+        || format!("<CCACCTFROM><ACCTID>{id}</ACCTID></CCACCTFROM>"),
+        |kind| {
+            format!(
+                "<BANKACCTFROM><BANKID>ZENMONEY</BANKID><ACCTID>{id}</ACCTID>\
+                 <ACCTTYPE>{kind}</ACCTTYPE></BANKACCTFROM>"
+            )
+        },
+    )
+}
+
+/// Renders the `BANKTRANLIST` element with the date range of `transactions`.
+fn ofx_transaction_list(
+    account_id: &str,
+    transactions: &[&Transaction],
+    maps: &LookupMaps,
+) -> String {
+    let start = transactions.iter().map(|tx| tx.date).min();
+    let end = transactions.iter().map(|tx| tx.date).max();
+    let range = start
+        .zip(end)
+        .map(|(first, last)| {
+            format!(
+                "<DTSTART>{}</DTSTART><DTEND>{}</DTEND>",
+                first.format("%Y%m%d"),
+                last.format("%Y%m%d")
+            )
+        })
+        .unwrap_or_default();
+    let mut lines = vec![format!("<BANKTRANLIST>{range}")];
+    lines.extend(
+        transactions
+            .iter()
+            .map(|tx| ofx_transaction(tx, account_id, maps)),
+    );
+    lines.push("</BANKTRANLIST>".to_owned());
+    lines.join("\n")
+}
+
+/// Renders the transactions of one account as an OFX 2 statement.
+///
+/// Credit cards get a credit card statement; other accounts a bank
+/// statement. The ledger balance is the account's stored balance.
+pub(crate) fn render_ofx(
+    account: &Account,
+    currency: &str,
+    transactions: &[&Transaction],
+    maps: &LookupMaps,
+    generated: DateTime<Utc>,
+) -> String {
+    let now = generated.format("%Y%m%d%H%M%S");
+    let (messages, response, statement) = if ofx_account_type(account.kind).is_some() {
+        ("BANKMSGSRSV1", "STMTTRNRS", "STMTRS")
+    } else {
+        ("CREDITCARDMSGSRSV1", "CCSTMTTRNRS", "CCSTMTRS")
+    };
+    let status = "<STATUS><CODE>0</CODE><SEVERITY>INFO</SEVERITY></STATUS>";
+    [
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>".to_owned(),
+        "<?OFX OFXHEADER=\"200\" VERSION=\"220\" SECURITY=\"NONE\" OLDFILEUID=\"NONE\" \
+         NEWFILEUID=\"NONE\"?>"
+            .to_owned(),
+        "<OFX>".to_owned(),
+        format!(
+            "<SIGNONMSGSRSV1><SONRS>{status}<DTSERVER>{now}</DTSERVER>\
+             <LANGUAGE>ENG</LANGUAGE></SONRS></SIGNONMSGSRSV1>"
+        ),
+        format!("<{messages}><{response}><TRNUID>1</TRNUID>{status}<{statement}>"),
+        format!("<CURDEF>{}</CURDEF>", xml_escape(currency)),
+        ofx_account_from(account),
+        ofx_transaction_list(account.id.as_inner(), transactions, maps),
+        format!(
+            "<LEDGERBAL><BALAMT>{:.2}</BALAMT><DTASOF>{now}</DTASOF></LEDGERBAL>",
+            account.balance.unwrap_or_default()
+        ),
+        format!("</{statement}></{response}></{messages}>"),
+        "</OFX>".to_owned(),
+        String::new(),
+    ]
+    .join("\n")
+}
+
+/// Quotes a CSV field when it contains a separator, quote or line break.
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_owned()
+    }
+}
+
+/// Renders one CSV row; sides without an amount are left empty.
+fn csv_row(tx: &Transaction, currencies: &HashMap<i32, String>, maps: &LookupMaps) -> String {
+    let kind = match classify_transaction(tx) {
+        TransactionType::Expense => "expense",
+        TransactionType::Income => "income",
+        TransactionType::Transfer => "transfer",
+    };
+    let side = |amount: f64, instrument: i32, account: &str| {
+        if amount > 0.0 {
+            [
+                format!("{amount:.2}"),
+                currencies.get(&instrument).cloned().unwrap_or_default(),
+                maps.account_name(account),
+            ]
+        } else {
+            [String::new(), String::new(), String::new()]
+        }
+    };
+    let tags: Vec<String> = tx
+        .tag
+        .iter()
+        .flatten()
+        .map(|tag| maps.tag_name(tag.as_inner()))
+        .collect();
+    let mut fields = vec![
+        tx.date.to_string(),
+        tx.id.as_inner().to_owned(),
+        kind.to_owned(),
+    ];
+    fields.extend(side(
+        tx.outcome,
+        tx.outcome_instrument.into_inner(),
+        tx.outcome_account.as_inner(),
+    ));
+    fields.extend(side(
+        tx.income,
+        tx.income_instrument.into_inner(),
+        tx.income_account.as_inner(),
+    ));
+    fields.push(tags.join("; "));
+    fields.push(tx.payee.clone().unwrap_or_default());
+    fields.push(tx.comment.clone().unwrap_or_default());
+    fields
+        .iter()
+        .map(|field| csv_field(field))
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Renders transactions as CSV with a header row.
+pub(crate) fn render_csv(
+    transactions: &[Transaction],
+    currencies: &HashMap<i32, String>,
+    maps: &LookupMaps,
+) -> String {
+    let mut lines = vec![CSV_HEADER.to_owned()];
+    lines.extend(transactions.iter().map(|tx| csv_row(tx, currencies, maps)));
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect for readability"
+)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::{DateTime, NaiveDate};
+    use zenmoney_rs::models::{Account, AccountId, Transaction};
+
+    use super::{
+        CSV_HEADER, ExportFormat, export_file_name, render_csv, render_ofx, render_qif,
+        signed_amount,
+    };
+    use crate::response::{LookupMaps, build_lookup_maps};
+
+    fn dataset() -> (Vec<Account>, Vec<Transaction>, LookupMaps) {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 15).expect("valid date");
+        let data = crate::demo::dataset(today);
+        let maps = build_lookup_maps(&data.accounts, &data.tags, &data.instruments);
+        (data.accounts, data.transactions, maps)
+    }
+
+    #[test]
+    fn signs_amounts_from_the_account_side() {
+        let (_accounts, transactions, _maps) = dataset();
+        let mut transfer = transactions.first().expect("demo transactions").clone();
+        transfer.outcome_account = AccountId::new("from".to_owned());
+        transfer.income_account = AccountId::new("to".to_owned());
+        transfer.outcome = 100.0;
+        transfer.income = 90.0;
+        assert!((signed_amount(&transfer, "from") + 100.0).abs() < f64::EPSILON);
+        assert!((signed_amount(&transfer, "to") - 90.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn renders_qif_and_ofx_statements() {
+        let (accounts, transactions, maps) = dataset();
+        let account = accounts.first().expect("demo accounts");
+        let own: Vec<&Transaction> = transactions
+            .iter()
+            .filter(|tx| signed_amount(tx, account.id.as_inner()).abs() > 0.0)
+            .collect();
+        assert!(!own.is_empty());
+
+        let qif = render_qif(account, &own, &maps);
+        assert!(qif.starts_with("!Type:"));
+        assert_eq!(qif.matches("\n^").count(), own.len());
+
+        let generated = DateTime::from_timestamp(1_718_409_600, 0).expect("valid timestamp");
+        let ofx = render_ofx(account, "RUB", &own, &maps, generated);
+        assert!(ofx.contains("<CURDEF>RUB</CURDEF>"));
+        assert_eq!(ofx.matches("<STMTTRN>").count(), own.len());
+        assert!(ofx.trim_end().ends_with("</OFX>"));
+
+        let name = export_file_name(account, ExportFormat::Ofx);
+        assert!(name.ends_with(".ofx"));
+        assert!(!name.contains(' '));
+    }
+
+    #[test]
+    fn renders_csv_with_quoted_fields() {
+        let (_accounts, transactions, maps) = dataset();
+        let mut tx = transactions.first().expect("demo transactions").clone();
+        tx.comment = Some("coffee, \"large\"".to_owned());
+        let currencies = HashMap::from([(tx.outcome_instrument.into_inner(), "RUB".to_owned())]);
+        let csv = render_csv(&[tx], &currencies, &maps);
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some(CSV_HEADER));
+        let row = lines.next().expect("one row");
+        assert!(row.ends_with(",\"coffee, \"\"large\"\"\""));
+        assert!(lines.next().is_none());
+    }
+}
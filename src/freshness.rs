@@ -0,0 +1,130 @@
+//! Automatic sync before reads when local data is stale.
+//!
+//! Read tools answer from local storage, which silently drifts from ZenMoney
+//! between syncs. With a maximum data age configured (`ZENMONEY_MAX_DATA_AGE`
+//! minutes, 30 by default), a read tool called more than that long after the
+//! last sync first runs an incremental sync. Passing `skip_sync: true`
+//! answers from local data as is.
+
+use core::time::Duration;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use rmcp::model::{JsonObject, Tool};
+
+/// Environment variable setting the maximum data age in minutes (`0` disables).
+pub const MAX_DATA_AGE_ENV: &str = "ZENMONEY_MAX_DATA_AGE";
+
+/// Maximum data age used when `ZENMONEY_MAX_DATA_AGE` is not set.
+pub const DEFAULT_MAX_DATA_AGE: Duration = Duration::from_secs(30 * 60);
+
+/// Tool argument that opts a single call out of the automatic sync.
+const SKIP_SYNC_ARG: &str = "skip_sync";
+
+/// Parses a maximum data age in whole minutes.
+///
+/// An unset or blank value selects [`DEFAULT_MAX_DATA_AGE`]; `0` turns the
+/// automatic sync off and returns `None`.
+///
+/// # Errors
+///
+/// Returns a message if `value` is not a whole number of minutes.
+#[inline]
+pub fn parse_max_data_age(value: Option<&str>) -> Result<Option<Duration>, String> {
+    let Some(raw) = value.map(str::trim).filter(|raw| !raw.is_empty()) else {
+        return Ok(Some(DEFAULT_MAX_DATA_AGE));
+    };
+    let minutes: u64 = raw.parse().map_err(|err| {
+        format!("invalid {MAX_DATA_AGE_ENV} value '{raw}': {err}; expected whole minutes")
+    })?;
+    Ok((minutes > 0).then(|| Duration::from_secs(minutes.saturating_mul(60))))
+}
+
+/// Time of the last successful sync.
+///
+/// A new clock has never seen a sync, so data counts as stale until the
+/// first one is marked.
+#[derive(Debug, Default)]
+pub(crate) struct SyncClock {
+    /// When local data was last synced, if ever.
+    last: Mutex<Option<Instant>>,
+}
+
+impl SyncClock {
+    /// Records a successful sync.
+    pub(crate) fn mark(&self) {
+        if let Ok(mut last) = self.last.lock() {
+            *last = Some(Instant::now());
+        }
+    }
+
+    /// Returns whether the last sync happened more than `max_age` ago, or
+    /// none happened yet.
+    pub(crate) fn is_older_than(&self, max_age: Duration) -> bool {
+        self.last
+            .lock()
+            .is_ok_and(|last| last.is_none_or(|at| at.elapsed() > max_age))
+    }
+}
+
+/// Returns whether the tool call arguments ask to skip the automatic sync.
+pub(crate) fn skip_requested(arguments: Option<&JsonObject>) -> bool {
+    flag_argument(arguments, SKIP_SYNC_ARG).unwrap_or(false)
+}
+
+/// Adds the `skip_sync` argument to the input schema of a read tool.
+pub(crate) fn add_skip_sync_param(tool: &mut Tool) {
+    add_bool_param(
+        tool,
+        SKIP_SYNC_ARG,
+        "Answer from local data without the automatic sync that runs when it is stale",
+    );
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect for readability"
+)]
+mod tests {
+    use core::time::Duration;
+
+    use rmcp::model::JsonObject;
+    use serde_json::json;
+
+    use super::{DEFAULT_MAX_DATA_AGE, SyncClock, parse_max_data_age, skip_requested};
+
+    #[test]
+    fn parses_max_data_age() {
+        assert_eq!(parse_max_data_age(None), Ok(Some(DEFAULT_MAX_DATA_AGE)));
+        assert_eq!(
+            parse_max_data_age(Some(" ")),
+            Ok(Some(DEFAULT_MAX_DATA_AGE))
+        );
+        assert_eq!(
+            parse_max_data_age(Some("5")),
+            Ok(Some(Duration::from_secs(300)))
+        );
+        assert_eq!(parse_max_data_age(Some("0")), Ok(None));
+        assert!(parse_max_data_age(Some("soon")).is_err());
+    }
+
+    #[test]
+    fn clock_and_skip_flag() {
+        let clock = SyncClock::default();
+        assert!(clock.is_older_than(Duration::from_secs(60)));
+        clock.mark();
+        assert!(!clock.is_older_than(Duration::from_secs(60)));
+        std::thread::sleep(Duration::from_millis(2));
+        assert!(clock.is_older_than(Duration::ZERO));
+
+        let args: JsonObject =
+            serde_json::from_value(json!({ "skip_sync": true })).expect("valid arguments");
+        assert!(skip_requested(Some(&args)));
+        let args: JsonObject =
+            serde_json::from_value(json!({ "skip_sync": "yes" })).expect("valid arguments");
+        assert!(skip_requested(Some(&args)));
+        assert!(!skip_requested(None));
+    }
+}
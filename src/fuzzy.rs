@@ -0,0 +1,114 @@
+//! Fuzzy title matching for entity search.
+//!
+//! Exact case-insensitive lookups fail on the typos and partial names that
+//! users and models produce. Titles are scored against a query by edit
+//! distance over the whole title and each of its words, with a bonus when
+//! one starts with or contains the other, so "tinkof" still finds
+//! "Tinkoff Black" and "Sber" finds "Sberbank Card".
+
+/// Candidates scoring below this are not returned.
+pub(crate) const MIN_SCORE: f64 = 0.5;
+
+/// Lowercases and trims a title for comparison.
+fn normalize(text: &str) -> Vec<char> {
+    text.trim().to_lowercase().chars().collect()
+}
+
+/// Number of single-character edits turning `left` into `right`.
+fn levenshtein(left: &[char], right: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=right.len()).collect();
+    for (row, left_char) in left.iter().enumerate() {
+        let mut current = Vec::with_capacity(previous.len());
+        current.push(row.saturating_add(1));
+        for (column, right_char) in right.iter().enumerate() {
+            let changed = usize::from(left_char != right_char);
+            let substitution = previous
+                .get(column)
+                .map_or(usize::MAX, |cost| cost.saturating_add(changed));
+            let deletion = previous
+                .get(column.saturating_add(1))
+                .map_or(usize::MAX, |cost| cost.saturating_add(1));
+            let insertion = current
+                .last()
+                .map_or(usize::MAX, |cost| cost.saturating_add(1));
+            current.push(substitution.min(deletion).min(insertion));
+        }
+        previous = current;
+    }
+    previous.last().copied().unwrap_or_default()
+}
+
+/// Returns `part / whole` for two lengths, or 1 when `whole` is zero.
+fn length_ratio(part: usize, whole: usize) -> f64 {
+    let as_float = |len: usize| f64::from(u32::try_from(len).unwrap_or(u32::MAX));
+    if whole == 0 {
+        return 1.0;
+    }
+    as_float(part) / as_float(whole)
+}
+
+/// Similarity of two normalized strings from 0 (unrelated) to 1 (equal).
+fn edit_similarity(left: &[char], right: &[char]) -> f64 {
+    1.0 - length_ratio(levenshtein(left, right), left.len().max(right.len()))
+}
+
+/// Scores how well `title` matches `query`, from 0 to 1.
+///
+/// An exact match scores 1. A title starting with the query scores at
+/// least 0.9, one with a word starting with it at least 0.85 and one
+/// containing it (or contained in it) at least 0.8, more the closer the
+/// lengths. Anything else gets the best edit similarity between the query
+/// and the whole title or any of its words.
+pub(crate) fn score(query: &str, title: &str) -> f64 {
+    let wanted = normalize(query);
+    let candidate = normalize(title);
+    if wanted.is_empty() || candidate.is_empty() {
+        return 0.0;
+    }
+    if wanted == candidate {
+        return 1.0;
+    }
+    let wanted_text: String = wanted.iter().collect();
+    let candidate_text: String = candidate.iter().collect();
+    let closeness = length_ratio(
+        wanted.len().min(candidate.len()),
+        wanted.len().max(candidate.len()),
+    );
+    if candidate_text.starts_with(&wanted_text) {
+        return 0.1_f64.mul_add(closeness, 0.9);
+    }
+    if candidate_text
+        .split_whitespace()
+        .any(|word| word.starts_with(&wanted_text))
+    {
+        return 0.05_f64.mul_add(closeness, 0.85);
+    }
+    if candidate_text.contains(&wanted_text) || wanted_text.contains(&candidate_text) {
+        return 0.05_f64.mul_add(closeness, 0.8);
+    }
+    candidate_text
+        .split_whitespace()
+        .map(|word| edit_similarity(&wanted, &word.chars().collect::<Vec<char>>()))
+        .fold(edit_similarity(&wanted, &candidate), f64::max)
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::missing_docs_in_private_items,
+    reason = "test code does not need docs"
+)]
+mod tests {
+    use super::{MIN_SCORE, score};
+
+    #[test]
+    fn ranks_exact_partial_and_misspelled_titles() {
+        assert!((score("Groceries", "groceries ") - 1.0).abs() < f64::EPSILON);
+        assert!(score("tinkoff", "Tinkoff Black") >= 0.9);
+        assert!(score("sber", "Sberbank Card") > score("card", "Sberbank Card"));
+        assert!(score("card", "Sberbank Card") > score("bank", "Sberbank Card"));
+        assert!(score("Grocereis", "Groceries") >= MIN_SCORE);
+        assert!(score("tinkof", "Tinkoff Black") >= MIN_SCORE);
+        assert!(score("Groceries", "Salary") < MIN_SCORE);
+        assert!(score("", "Salary") < MIN_SCORE);
+    }
+}
@@ -0,0 +1,109 @@
+//! Server instructions sent to the client on initialization.
+//!
+//! The base text can be replaced with `ZENMONEY_INSTRUCTIONS`. A block
+//! generated at startup follows it, listing the main accounts, the base
+//! currency and the current month, so the model starts each session knowing
+//! the essentials without a tool call.
+
+use chrono::NaiveDate;
+
+/// Environment variable replacing the default instructions text.
+pub const INSTRUCTIONS_ENV: &str = "ZENMONEY_INSTRUCTIONS";
+
+/// Instructions used when `ZENMONEY_INSTRUCTIONS` is not set.
+pub const DEFAULT_INSTRUCTIONS: &str = "ZenMoney personal finance MCP server. \
+     Local data is synced at startup; call sync before answering questions about \
+     recent activity. Tools take IDs, so look up accounts, tags and merchants first \
+     (find_account, find_tag) and pass convert_to to compare amounts across \
+     currencies. Write tools that stage changes return a preview; nothing is saved \
+     until execute_bulk_operations is called. Write tools called in parallel are \
+     applied one at a time in the order they arrive.";
+
+/// Maximum number of accounts named in the generated block.
+const MAX_LISTED_ACCOUNTS: usize = 10;
+
+/// Picks the base instructions: a non-blank override, or the default text.
+#[inline]
+#[must_use]
+pub fn base_instructions(custom: Option<String>) -> String {
+    custom
+        .filter(|text| !text.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_INSTRUCTIONS.to_owned())
+}
+
+/// Renders the session context block.
+///
+/// `accounts` are display labels, most important first; only the first few
+/// are listed.
+pub(crate) fn session_block(
+    accounts: &[String],
+    base_currency: Option<&str>,
+    today: NaiveDate,
+) -> String {
+    let mut lines = vec!["Session context:".to_owned()];
+    if let Some(currency) = base_currency {
+        lines.push(format!("- Base currency: {currency}"));
+    }
+    lines.push(format!(
+        "- Current month: {} (today is {today})",
+        today.format("%Y-%m")
+    ));
+    if !accounts.is_empty() {
+        lines.push("- Main accounts:".to_owned());
+        lines.extend(
+            accounts
+                .iter()
+                .take(MAX_LISTED_ACCOUNTS)
+                .map(|label| format!("  - {label}")),
+        );
+        let hidden = accounts.len().saturating_sub(MAX_LISTED_ACCOUNTS);
+        if hidden > 0 {
+            lines.push(format!("  - …and {hidden} more (see list_accounts)"));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Joins the base instructions and the session context block.
+pub(crate) fn compose(base: &str, session: Option<&str>) -> String {
+    match session {
+        Some(block) => format!("{base}\n\n{block}"),
+        None => base.to_owned(),
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect for readability"
+)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{DEFAULT_INSTRUCTIONS, base_instructions, compose, session_block};
+
+    #[test]
+    fn blank_override_keeps_default() {
+        assert_eq!(base_instructions(None), DEFAULT_INSTRUCTIONS);
+        assert_eq!(
+            base_instructions(Some("  ".to_owned())),
+            DEFAULT_INSTRUCTIONS
+        );
+        assert_eq!(base_instructions(Some("Be terse.".to_owned())), "Be terse.");
+    }
+
+    #[test]
+    fn session_block_lists_context() {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 15).expect("valid date");
+        let accounts: Vec<String> = (1..=12_u32).map(|idx| format!("Account {idx}")).collect();
+        let block = session_block(&accounts, Some("₽ (instrument 1)"), today);
+        assert!(block.contains("Base currency: ₽ (instrument 1)"));
+        assert!(block.contains("Current month: 2024-06 (today is 2024-06-15)"));
+        assert!(block.contains("  - Account 10"));
+        assert!(!block.contains("Account 11"));
+        assert!(block.contains("…and 2 more"));
+        assert_eq!(compose("Base.", None), "Base.");
+        assert!(compose("Base.", Some(&block)).starts_with("Base.\n\nSession context:"));
+    }
+}
@@ -0,0 +1,442 @@
+//! Plain-text accounting journals in hledger or beancount syntax.
+//!
+//! ZenMoney accounts become `Assets:` or `Liabilities:` accounts named after
+//! their titles, and the first tag of an expense or income becomes an
+//! `Expenses:` or `Income:` account following the tag hierarchy
+//! (`Uncategorized` when there is none). Instruments are declared as
+//! commodities by their currency code. A transfer between currencies
+//! records the outcome amount as the total cost (`@@`) of the income side,
+//! so every entry balances in both tools. Each entry carries the ZenMoney
+//! transaction ID as `zenmoney_id` metadata.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use zenmoney_rs::models::{Account, AccountType, Transaction};
+
+use crate::params::{LedgerDialect, TransactionType};
+use crate::response::LookupMaps;
+use crate::server::classify_transaction;
+
+/// Indentation of posting and metadata lines.
+const INDENT: &str = "    ";
+
+/// Category account name for transactions without a tag.
+const UNCATEGORIZED: &str = "Uncategorized";
+
+/// Account taking the difference of a same-currency transfer whose sides differ.
+const TRANSFER_FEES: &str = "Expenses:Transfer-Fees";
+
+/// Smallest transfer difference worth a fee posting.
+const MIN_FEE: f64 = 0.005;
+
+/// A rendered journal.
+#[derive(Debug, Clone)]
+pub(crate) struct Journal {
+    /// Journal text.
+    pub(crate) text: String,
+    /// Ledger accounts used by the entries, sorted.
+    pub(crate) accounts: Vec<String>,
+    /// Commodities used by the entries, sorted.
+    pub(crate) commodities: Vec<String>,
+}
+
+/// One posting line of a journal entry.
+#[derive(Debug, Clone)]
+struct Posting {
+    /// Ledger account name.
+    account: String,
+    /// Signed amount.
+    amount: f64,
+    /// Commodity of the amount.
+    commodity: String,
+    /// Total cost in another commodity, for transfers between currencies.
+    cost: Option<(f64, String)>,
+}
+
+impl Posting {
+    /// Creates a posting without a cost.
+    const fn new(account: String, amount: f64, commodity: String) -> Self {
+        Self {
+            account,
+            amount,
+            commodity,
+            cost: None,
+        }
+    }
+}
+
+/// Resolves where a journal goes: `zenmoney.<extension>` inside `path` if
+/// it is a directory, otherwise `path` itself.
+pub(crate) fn journal_path(path: &Path, dialect: LedgerDialect) -> PathBuf {
+    if path.is_dir() {
+        path.join(format!("zenmoney.{}", dialect.extension()))
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// Turns a title into an account name component: letters and digits are
+/// kept, other runs become one dash and the first letter is uppercased.
+fn component(title: &str) -> String {
+    let mut text = String::new();
+    for ch in title.chars() {
+        if ch.is_alphanumeric() {
+            text.push(ch);
+        } else if !text.is_empty() && !text.ends_with('-') {
+            text.push('-');
+        }
+    }
+    let mut chars = text.trim_end_matches('-').chars();
+    chars.next().map_or_else(
+        || "Unnamed".to_owned(),
+        |first| first.to_uppercase().chain(chars).collect(),
+    )
+}
+
+/// Turns a currency code into a commodity name both tools accept.
+fn commodity(code: &str) -> String {
+    let name = code
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .collect::<String>()
+        .to_ascii_uppercase();
+    if name.starts_with(|ch: char| ch.is_ascii_alphabetic()) {
+        name
+    } else {
+        format!("X{name}")
+    }
+}
+
+/// Top-level ledger account for a ZenMoney account type.
+const fn account_root(kind: AccountType) -> &'static str {
+    match kind {
+        AccountType::CreditCard | AccountType::Loan => "Liabilities",
+        AccountType::Cash
+        | AccountType::Checking
+        | AccountType::Deposit
+        | AccountType::EMoney
+        | AccountType::Debt => "Assets",
+    }
+}
+
+/// Assigns a unique ledger name to every account in title order; a name
+/// already taken gets the start of the account ID appended.
+fn account_names(accounts: &[Account]) -> HashMap<String, String> {
+    let mut sorted: Vec<&Account> = accounts.iter().collect();
+    sorted.sort_by(|left, right| left.title.cmp(&right.title));
+    let mut taken = HashSet::new();
+    let mut names = HashMap::new();
+    for account in sorted {
+        let base = format!(
+            "{}:{}",
+            account_root(account.kind),
+            component(&account.title)
+        );
+        let name = if taken.contains(&base) {
+            let id: String = account
+                .id
+                .as_inner()
+                .chars()
+                .filter(char::is_ascii_alphanumeric)
+                .take(8)
+                .collect();
+            format!("{base}-{id}")
+        } else {
+            base
+        };
+        let _new = taken.insert(name.clone());
+        let _previous = names.insert(account.id.as_inner().to_owned(), name);
+    }
+    names
+}
+
+/// Expense or income account for the first tag of a transaction.
+fn category_account(root: &str, tx: &Transaction, maps: &LookupMaps) -> String {
+    let path = tx.tag.iter().flatten().next().map_or_else(
+        || UNCATEGORIZED.to_owned(),
+        |tag| {
+            maps.tag_path(tag.as_inner())
+                .into_iter()
+                .map(|title| component(&title))
+                .collect::<Vec<String>>()
+                .join(":")
+        },
+    );
+    format!("{root}:{path}")
+}
+
+/// Builds the balanced postings of a transaction.
+fn postings(
+    tx: &Transaction,
+    names: &HashMap<String, String>,
+    currencies: &HashMap<i32, String>,
+    maps: &LookupMaps,
+) -> Vec<Posting> {
+    let account = |id: &str| {
+        names
+            .get(id)
+            .cloned()
+            .unwrap_or_else(|| format!("Assets:{}", component(&maps.account_name(id))))
+    };
+    let code =
+        |instrument: i32| commodity(currencies.get(&instrument).map_or("XXX", String::as_str));
+    let income_commodity = code(tx.income_instrument.into_inner());
+    let outcome_commodity = code(tx.outcome_instrument.into_inner());
+    let incoming = account(tx.income_account.as_inner());
+    let outgoing = account(tx.outcome_account.as_inner());
+    match classify_transaction(tx) {
+        TransactionType::Expense => vec![
+            Posting::new(
+                category_account("Expenses", tx, maps),
+                tx.outcome,
+                outcome_commodity.clone(),
+            ),
+            Posting::new(outgoing, -tx.outcome, outcome_commodity),
+        ],
+        TransactionType::Income => vec![
+            Posting::new(incoming, tx.income, income_commodity.clone()),
+            Posting::new(
+                category_account("Income", tx, maps),
+                -tx.income,
+                income_commodity,
+            ),
+        ],
+        TransactionType::Transfer => {
+            let mut income = Posting::new(incoming, tx.income, income_commodity.clone());
+            let outcome = Posting::new(outgoing, -tx.outcome, outcome_commodity.clone());
+            if income_commodity != outcome_commodity {
+                income.cost = Some((tx.outcome, outcome_commodity));
+                vec![income, outcome]
+            } else if (tx.outcome - tx.income).abs() >= MIN_FEE {
+                let fee = tx.outcome - tx.income;
+                vec![
+                    income,
+                    outcome,
+                    Posting::new(TRANSFER_FEES.to_owned(), fee, income_commodity),
+                ]
+            } else {
+                vec![income, outcome]
+            }
+        }
+    }
+}
+
+/// Quotes text as a beancount string.
+fn quoted(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Renders a posting line.
+fn render_posting(posting: &Posting) -> String {
+    let cost = posting
+        .cost
+        .as_ref()
+        .map(|cost| format!(" @@ {:.2} {}", cost.0, cost.1))
+        .unwrap_or_default();
+    format!(
+        "{INDENT}{}  {:.2} {}{cost}",
+        posting.account, posting.amount, posting.commodity
+    )
+}
+
+/// Renders a transaction with its postings as a journal entry.
+///
+/// The payee (or merchant) and comment become the hledger description
+/// `payee | note` or the beancount payee and narration strings.
+fn render_entry(
+    dialect: LedgerDialect,
+    tx: &Transaction,
+    postings: &[Posting],
+    maps: &LookupMaps,
+) -> String {
+    let payee = tx
+        .payee
+        .as_deref()
+        .filter(|payee| !payee.is_empty())
+        .map(str::to_owned)
+        .or_else(|| {
+            tx.merchant
+                .as_ref()
+                .map(|id| maps.merchant_name(id.as_inner()))
+        });
+    let note = tx
+        .comment
+        .as_deref()
+        .filter(|comment| !comment.is_empty())
+        .map(|comment| comment.replace('\n', " "));
+    let date = tx.date.format("%Y-%m-%d");
+    let id = tx.id.as_inner();
+    let (header, metadata) = match dialect {
+        LedgerDialect::Hledger => {
+            let description = [payee, note].into_iter().flatten().collect::<Vec<String>>();
+            (
+                format!("{date} {}", description.join(" | "))
+                    .trim_end()
+                    .to_owned(),
+                format!("{INDENT}; zenmoney_id: {id}"),
+            )
+        }
+        LedgerDialect::Beancount => {
+            let narration = quoted(note.as_deref().unwrap_or_default());
+            (
+                payee.map_or_else(
+                    || format!("{date} * {narration}"),
+                    |name| format!("{date} * {} {narration}", quoted(&name)),
+                ),
+                format!("{INDENT}zenmoney_id: {}", quoted(id)),
+            )
+        }
+    };
+    let mut lines = vec![header, metadata];
+    lines.extend(postings.iter().map(render_posting));
+    lines.join("\n")
+}
+
+/// Renders the commodity and account declarations opening a journal.
+///
+/// Beancount dates its declarations; they use the first entry's date.
+fn render_declarations(
+    dialect: LedgerDialect,
+    commodities: &BTreeSet<String>,
+    accounts: &BTreeSet<String>,
+    opened: &str,
+) -> String {
+    let lines: Vec<String> = match dialect {
+        LedgerDialect::Hledger => commodities
+            .iter()
+            .map(|name| format!("commodity 1000.00 {name}"))
+            .chain(accounts.iter().map(|name| format!("account {name}")))
+            .collect(),
+        LedgerDialect::Beancount => commodities
+            .iter()
+            .map(|name| format!("{opened} commodity {name}"))
+            .chain(accounts.iter().map(|name| format!("{opened} open {name}")))
+            .collect(),
+    };
+    lines.join("\n")
+}
+
+/// Renders `transactions`, oldest first, as a journal.
+///
+/// `currencies` maps instrument IDs to currency codes; accounts of
+/// unknown instruments use `XXX`.
+pub(crate) fn render_journal(
+    dialect: LedgerDialect,
+    accounts: &[Account],
+    transactions: &[Transaction],
+    currencies: &HashMap<i32, String>,
+    maps: &LookupMaps,
+) -> Journal {
+    let names = account_names(accounts);
+    let mut used_accounts = BTreeSet::new();
+    let mut used_commodities = BTreeSet::new();
+    let mut entries = Vec::new();
+    for tx in transactions {
+        let lines = postings(tx, &names, currencies, maps);
+        for posting in &lines {
+            let _new_account = used_accounts.insert(posting.account.clone());
+            let _new_commodity = used_commodities.insert(posting.commodity.clone());
+            if let Some(cost) = posting.cost.as_ref() {
+                let _new_cost = used_commodities.insert(cost.1.clone());
+            }
+        }
+        entries.push(render_entry(dialect, tx, &lines, maps));
+    }
+    let opened = transactions
+        .iter()
+        .map(|tx| tx.date)
+        .min()
+        .unwrap_or_default()
+        .format("%Y-%m-%d")
+        .to_string();
+    let text = format!(
+        "; Exported from ZenMoney\n\n{}\n\n{}\n",
+        render_declarations(dialect, &used_commodities, &used_accounts, &opened),
+        entries.join("\n\n")
+    );
+    Journal {
+        text,
+        accounts: used_accounts.into_iter().collect(),
+        commodities: used_commodities.into_iter().collect(),
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect for readability"
+)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::NaiveDate;
+    use zenmoney_rs::models::{AccountId, InstrumentId};
+
+    use super::{commodity, component, render_journal};
+    use crate::params::LedgerDialect;
+    use crate::response::build_lookup_maps;
+
+    #[test]
+    fn names_accounts_and_commodities() {
+        assert_eq!(component("tinkoff black (RUB)"), "Tinkoff-black-RUB");
+        assert_eq!(component("  "), "Unnamed");
+        assert_eq!(commodity("usd"), "USD");
+        assert_eq!(commodity("1INCH"), "X1INCH");
+    }
+
+    #[test]
+    fn renders_balanced_entries_in_both_dialects() {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 15).expect("valid date");
+        let data = crate::demo::dataset(today);
+        let maps = build_lookup_maps(&data.accounts, &data.tags, &data.instruments);
+        let currencies: HashMap<i32, String> = data
+            .instruments
+            .iter()
+            .map(|instrument| (instrument.id.into_inner(), instrument.short_title.clone()))
+            .collect();
+        let mut transactions = data.transactions.clone();
+        let mut exchange = transactions.first().expect("demo transactions").clone();
+        exchange.outcome_account = AccountId::new("from".to_owned());
+        exchange.income_account = AccountId::new("to".to_owned());
+        exchange.outcome_instrument = InstrumentId::new(2);
+        exchange.income_instrument = InstrumentId::new(1);
+        exchange.outcome = 100.0;
+        exchange.income = 9000.0;
+        transactions.push(exchange);
+
+        let hledger = render_journal(
+            LedgerDialect::Hledger,
+            &data.accounts,
+            &transactions,
+            &currencies,
+            &maps,
+        );
+        assert!(hledger.text.contains("\ncommodity 1000.00 "));
+        assert!(hledger.text.contains(" @@ 100.00 "));
+        assert!(
+            hledger
+                .accounts
+                .iter()
+                .any(|name| name.starts_with("Expenses:"))
+        );
+        assert_eq!(
+            hledger.text.matches("; zenmoney_id: ").count(),
+            transactions.len()
+        );
+
+        let beancount = render_journal(
+            LedgerDialect::Beancount,
+            &data.accounts,
+            &transactions,
+            &currencies,
+            &maps,
+        );
+        assert_eq!(
+            beancount.text.matches(" open ").count(),
+            beancount.accounts.len()
+        );
+        assert!(beancount.text.contains(" * "));
+    }
+}
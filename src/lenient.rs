@@ -0,0 +1,520 @@
+//! Lenient tool arguments and readable parameter errors.
+//!
+//! Agents often send `"10"` for a number, `"true"` for a flag or an array
+//! encoded as a JSON string, and serde's message for the failure ("invalid
+//! type: string \"10\", expected usize") names neither the field nor a value
+//! that would work. Tools take their arguments through [`Parameters`]: when
+//! the arguments do not deserialize, they are coerced towards the tool's
+//! input schema and read once more; if that still fails, the error names
+//! each offending field with the type it expects and an example value.
+
+use std::collections::BTreeSet;
+
+use rmcp::ErrorData as McpError;
+use rmcp::handler::server::common::{FromContextPart, cached_schema_for_type};
+use rmcp::handler::server::tool::ToolCallContext;
+use rmcp::model::{JsonObject, Tool};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::amount::parse_amount;
+
+/// Start of the error for arguments that do not deserialize, as rmcp words
+/// it.
+const DESERIALIZE_ERROR: &str = "failed to deserialize parameters";
+
+/// Longest rendering of a rejected value in an error message.
+const MAX_VALUE_CHARS: usize = 40;
+
+/// What an input schema accepts for one argument.
+#[derive(Debug, Default)]
+struct Expected {
+    /// JSON types allowed, such as `integer` or `string`.
+    types: BTreeSet<String>,
+    /// Allowed values, when the schema lists them.
+    values: Vec<Value>,
+    /// What array items accept, when an array is allowed.
+    items: Option<Box<Expected>>,
+    /// Description of the argument, used to pick an example.
+    description: Option<String>,
+}
+
+/// Tool parameters, read leniently.
+///
+/// Stands in for rmcp's wrapper of the same name, which the `#[tool]` macro
+/// recognizes by name to derive the input schema from `P`.
+#[derive(Debug, Clone)]
+pub(crate) struct Parameters<P>(
+    /// The deserialized arguments.
+    pub(crate) P,
+);
+
+impl<S, P> FromContextPart<ToolCallContext<'_, S>> for Parameters<P>
+where
+    P: DeserializeOwned + JsonSchema + 'static,
+{
+    fn from_context_part(context: &mut ToolCallContext<'_, S>) -> Result<Self, McpError> {
+        let arguments = context.arguments.take().unwrap_or_default();
+        read_arguments(context.name(), arguments).map(Self)
+    }
+}
+
+/// Reads the `arguments` of a call to `tool`. If they do not deserialize,
+/// reads them once more coerced to the input schema of `P`, and otherwise
+/// returns an error naming the offending arguments.
+///
+/// # Errors
+///
+/// Returns an invalid-params error if the arguments do not fit `P`.
+pub(crate) fn read_arguments<P>(tool: &str, mut arguments: JsonObject) -> Result<P, McpError>
+where
+    P: DeserializeOwned + JsonSchema + 'static,
+{
+    let err = match serde_json::from_value(Value::Object(arguments.clone())) {
+        Ok(params) => return Ok(params),
+        Err(err) => err,
+    };
+    let schema = cached_schema_for_type::<P>();
+    coerce_arguments(&schema, &mut arguments)
+        .then(|| serde_json::from_value(Value::Object(arguments.clone())).ok())
+        .flatten()
+        .ok_or_else(|| explain(&err, tool, &schema, &arguments))
+}
+
+/// Follows a local reference (`#/$defs/Name`) of `node` within `schema`.
+fn resolve<'schema>(schema: &'schema JsonObject, node: &'schema Value) -> &'schema Value {
+    node.get("$ref")
+        .and_then(Value::as_str)
+        .and_then(|reference| {
+            let name = reference.rsplit('/').next()?;
+            schema
+                .get("$defs")
+                .or_else(|| schema.get("definitions"))?
+                .get(name)
+        })
+        .unwrap_or(node)
+}
+
+/// Returns the JSON type name of `value`.
+fn json_type(value: &Value) -> &'static str {
+    match *value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(ref number) if number.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Renders a rejected value for an error message, shortened if long.
+fn describe_value(value: &Value) -> String {
+    let text = value.to_string();
+    let mut short: String = text.chars().take(MAX_VALUE_CHARS).collect();
+    if short.len() < text.len() {
+        short.push('…');
+    }
+    format!("{} {short}", json_type(value))
+}
+
+impl Expected {
+    /// Reads what `node` accepts, following references and the branches of
+    /// `anyOf`, `oneOf` and `allOf`.
+    fn of(schema: &JsonObject, node: &Value) -> Self {
+        let mut expected = Self::default();
+        expected.collect(schema, node);
+        expected
+    }
+
+    /// Reads what the property `name` of a tool's input schema accepts.
+    fn of_property(schema: &JsonObject, name: &str) -> Option<Self> {
+        let node = schema.get("properties")?.get(name)?;
+        Some(Self::of(schema, node))
+    }
+
+    /// Adds what `node` accepts.
+    fn collect(&mut self, schema: &JsonObject, node: &Value) {
+        let resolved = resolve(schema, node);
+        if self.description.is_none() {
+            self.description = resolved
+                .get("description")
+                .and_then(Value::as_str)
+                .map(str::to_owned);
+        }
+        match resolved.get("type") {
+            Some(&Value::String(ref kind)) => {
+                let _added = self.types.insert(kind.clone());
+            }
+            Some(&Value::Array(ref kinds)) => self
+                .types
+                .extend(kinds.iter().filter_map(Value::as_str).map(str::to_owned)),
+            _ => {}
+        }
+        if let Some(values) = resolved.get("enum").and_then(Value::as_array) {
+            self.values.extend(values.iter().cloned());
+        }
+        if let Some(value) = resolved.get("const") {
+            self.values.push(value.clone());
+        }
+        if self.items.is_none() {
+            self.items = resolved
+                .get("items")
+                .map(|items| Box::new(Self::of(schema, items)));
+        }
+        for key in ["anyOf", "oneOf", "allOf"] {
+            for branch in resolved
+                .get(key)
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+            {
+                self.collect(schema, branch);
+            }
+        }
+    }
+
+    /// Returns whether `value` is acceptable.
+    fn accepts(&self, value: &Value) -> bool {
+        if value.is_null() && self.types.contains("null") {
+            return true;
+        }
+        if !self.values.is_empty() {
+            return self.values.contains(value);
+        }
+        let kind = json_type(value);
+        self.types.is_empty()
+            || self.types.contains(kind)
+            || (kind == "integer" && self.types.contains("number"))
+    }
+
+    /// Values `text` plainly stands for, most specific first.
+    fn readings_of_text(&self, text: &str) -> Vec<Value> {
+        let trimmed = text.trim();
+        let mut readings: Vec<Value> = self
+            .values
+            .iter()
+            .filter(|value| {
+                value
+                    .as_str()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(trimmed))
+            })
+            .cloned()
+            .collect();
+        if let Ok(integer) = trimmed.parse::<i64>() {
+            readings.push(Value::from(integer));
+        }
+        if let Some(number) = parse_amount(trimmed)
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+        {
+            readings.push(Value::Number(number));
+        }
+        if let Some(flag) = parse_flag(trimmed) {
+            readings.push(Value::Bool(flag));
+        }
+        if let Ok(parsed) = serde_json::from_str::<Value>(trimmed) {
+            readings.push(parsed);
+        }
+        readings
+    }
+
+    /// Converts `value` to something the schema accepts, if it plainly means
+    /// one: a number or flag sent as text, JSON sent as a string, a whole
+    /// number sent as a float, a scalar where a list is expected, or a number
+    /// where text is expected.
+    fn coerce(&self, value: &Value) -> Option<Value> {
+        let mut readings = match *value {
+            Value::String(ref text) => self.readings_of_text(text),
+            Value::Number(ref number) => {
+                let text = number.to_string();
+                let whole = text
+                    .strip_suffix(".0")
+                    .and_then(|digits| digits.parse::<i64>().ok());
+                whole
+                    .map(Value::from)
+                    .into_iter()
+                    .chain([Value::String(text)])
+                    .collect()
+            }
+            Value::Bool(flag) => vec![Value::String(flag.to_string())],
+            Value::Null | Value::Array(_) | Value::Object(_) => Vec::new(),
+        };
+        if self
+            .items
+            .as_ref()
+            .is_some_and(|items| !value.is_array() && items.accepts(value))
+        {
+            readings.push(Value::Array(vec![value.clone()]));
+        }
+        readings.into_iter().find(|reading| self.accepts(reading))
+    }
+
+    /// Returns an example of an acceptable value.
+    fn example(&self) -> String {
+        if let Some(value) = self.values.first() {
+            return value.to_string();
+        }
+        let description = self.description.as_deref().unwrap_or_default();
+        match self
+            .types
+            .iter()
+            .find(|kind| *kind != "null")
+            .map(String::as_str)
+        {
+            Some("array") => format!(
+                "[{}]",
+                self.items
+                    .as_ref()
+                    .map_or_else(String::new, |items| items.example())
+            ),
+            Some("boolean") => "true".to_owned(),
+            Some("integer") => "10".to_owned(),
+            Some("number") => "12.5".to_owned(),
+            Some("object") => "{}".to_owned(),
+            _ if description.contains("YYYY-MM-DD") => "\"2024-01-31\"".to_owned(),
+            _ if description.contains("YYYY-MM") => "\"2024-01\"".to_owned(),
+            _ => "\"text\"".to_owned(),
+        }
+    }
+
+    /// Describes what is expected, such as `: expected integer, for example 10`.
+    fn hint(&self) -> String {
+        if !self.values.is_empty() {
+            let choices: Vec<String> = self.values.iter().map(Value::to_string).collect();
+            return format!(": expected one of {}", choices.join(", "));
+        }
+        let types: Vec<&str> = self
+            .types
+            .iter()
+            .map(String::as_str)
+            .filter(|kind| *kind != "null")
+            .collect();
+        if types.is_empty() {
+            return String::new();
+        }
+        format!(
+            ": expected {}, for example {}",
+            types.join(" or "),
+            self.example()
+        )
+    }
+}
+
+/// Reads a flag sent as text, such as `"true"` or `"no"`.
+fn parse_flag(text: &str) -> Option<bool> {
+    match text.trim().to_lowercase().as_str() {
+        "true" | "yes" => Some(true),
+        "false" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// Reads the boolean argument `name` of a call, accepting the flags as text
+/// that tool arguments accept.
+pub(crate) fn flag_argument(arguments: Option<&JsonObject>, name: &str) -> Option<bool> {
+    match *arguments?.get(name)? {
+        Value::Bool(flag) => Some(flag),
+        Value::String(ref text) => parse_flag(text),
+        Value::Null | Value::Number(_) | Value::Array(_) | Value::Object(_) => None,
+    }
+}
+
+/// Adds the boolean argument `name` to the input schema of `tool`.
+pub(crate) fn add_bool_param(tool: &mut Tool, name: &str, description: &str) {
+    let mut schema = (*tool.input_schema).clone();
+    let properties = schema
+        .entry("properties")
+        .or_insert_with(|| Value::Object(JsonObject::new()));
+    if let Some(properties) = properties.as_object_mut() {
+        let _previous = properties.insert(
+            name.to_owned(),
+            serde_json::json!({ "type": "boolean", "description": description }),
+        );
+    }
+    tool.input_schema = schema.into();
+}
+
+/// Coerces the top-level `arguments` of a call towards `schema`, returning
+/// whether anything changed.
+fn coerce_arguments(schema: &JsonObject, arguments: &mut JsonObject) -> bool {
+    let mut changed = false;
+    for (name, value) in arguments.iter_mut() {
+        let Some(expected) = Expected::of_property(schema, name) else {
+            continue;
+        };
+        if expected.accepts(value) {
+            continue;
+        }
+        if let Some(coerced) = expected.coerce(value) {
+            *value = coerced;
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Lists the arguments that do not fit `schema`: missing required ones and
+/// those of the wrong type.
+fn problems(schema: &JsonObject, arguments: &JsonObject) -> Vec<String> {
+    let required = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str);
+    let missing = required
+        .filter(|name| !arguments.contains_key(*name))
+        .map(|name| {
+            let hint = Expected::of_property(schema, name).map(|expected| expected.hint());
+            format!("`{name}` is required{}", hint.unwrap_or_default())
+        });
+    let invalid = arguments.iter().filter_map(|(name, value)| {
+        let expected = Expected::of_property(schema, name)?;
+        (!expected.accepts(value))
+            .then(|| format!("`{name}` got {}{}", describe_value(value), expected.hint()))
+    });
+    missing.chain(invalid).collect()
+}
+
+/// Returns the argument whose text value serde quotes in `err`, for errors
+/// raised by a field's own parser, which do not name the field.
+fn quoted_argument<'args>(
+    err: &serde_json::Error,
+    arguments: &'args JsonObject,
+) -> Option<&'args str> {
+    let message = err.to_string();
+    arguments.iter().find_map(|(name, value)| {
+        let text = value.as_str()?;
+        message
+            .contains(&format!("string {text:?}"))
+            .then_some(name.as_str())
+    })
+}
+
+/// Turns a deserialization error of `tool` into one naming the offending
+/// arguments, keeping serde's message as error data. Falls back to serde's
+/// message, naming the argument it quotes, when the schema does not explain
+/// it.
+fn explain(
+    err: &serde_json::Error,
+    tool: &str,
+    schema: &JsonObject,
+    arguments: &JsonObject,
+) -> McpError {
+    let mut found = problems(schema, arguments);
+    if found.is_empty() {
+        let Some(name) = quoted_argument(err, arguments) else {
+            return McpError::invalid_params(format!("{DESERIALIZE_ERROR}: {err}"), None);
+        };
+        found.push(format!("`{name}`: {err}"));
+    }
+    McpError::invalid_params(
+        format!("invalid arguments for {tool}: {}", found.join("; ")),
+        Some(serde_json::json!({ "details": format!("{DESERIALIZE_ERROR}: {err}") })),
+    )
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::indexing_slicing,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect and indexing for readability"
+)]
+mod tests {
+    use rmcp::handler::server::common::cached_schema_for_type;
+    use rmcp::model::JsonObject;
+    use serde_json::{Value, json};
+
+    use super::{coerce_arguments, problems, read_arguments};
+    use crate::params::{
+        GetInstrumentParams, ListTransactionsParams, LogExpenseParams, TransactionType,
+    };
+
+    fn arguments(value: Value) -> JsonObject {
+        serde_json::from_value(value).expect("arguments object")
+    }
+
+    #[test]
+    fn coerces_text_to_schema_types() {
+        let schema = cached_schema_for_type::<ListTransactionsParams>();
+        let mut args = arguments(json!({
+            "limit": "20",
+            "hold": "true",
+            "transaction_type": "Expense",
+            "min_amount": "1 000,50",
+            "max_amount": "0.500",
+            "date_from": "2024-01-01"
+        }));
+        assert!(coerce_arguments(&schema, &mut args));
+        let params: ListTransactionsParams =
+            serde_json::from_value(Value::Object(args)).expect("coerced arguments deserialize");
+        assert_eq!(params.limit, Some(20));
+        assert_eq!(params.hold, Some(true));
+        assert!(matches!(
+            params.transaction_type,
+            Some(TransactionType::Expense)
+        ));
+        assert!((params.min_amount.unwrap_or_default() - 1000.5).abs() < f64::EPSILON);
+        assert!((params.max_amount.unwrap_or_default() - 0.5).abs() < f64::EPSILON);
+        assert_eq!(params.date_from.as_deref(), Some("2024-01-01"));
+
+        let mut valid = arguments(json!({ "limit": 5 }));
+        assert!(!coerce_arguments(&schema, &mut valid));
+    }
+
+    #[test]
+    fn names_offending_fields() {
+        let schema = cached_schema_for_type::<ListTransactionsParams>();
+        let found = problems(&schema, &arguments(json!({ "limit": "many" })));
+        assert_eq!(found.len(), 1);
+        assert!(
+            found[0].starts_with("`limit` got string \"many\""),
+            "{}",
+            found[0]
+        );
+        assert!(found[0].contains("for example 10"), "{}", found[0]);
+
+        let err = read_arguments::<GetInstrumentParams>("get_instrument", JsonObject::new())
+            .expect_err("missing id");
+        assert!(
+            err.message
+                .starts_with("invalid arguments for get_instrument: ")
+        );
+        assert!(
+            err.message
+                .ends_with("`id` is required: expected integer, for example 10")
+        );
+        assert_eq!(
+            err.data,
+            Some(json!({ "details": "failed to deserialize parameters: missing field `id`" }))
+        );
+    }
+
+    #[test]
+    fn names_the_field_of_an_unparsable_amount() {
+        let err = read_arguments::<LogExpenseParams>(
+            "log_expense",
+            arguments(json!({ "amount": "15.06.2024" })),
+        )
+        .expect_err("a date is not an amount");
+        assert!(
+            err.message.starts_with(
+                "invalid arguments for log_expense: `amount`: invalid value: string \"15.06.2024\""
+            ),
+            "{}",
+            err.message
+        );
+        assert!(err.message.contains("\"1 234,56\""), "{}", err.message);
+    }
+
+    #[test]
+    fn reads_coerced_arguments() {
+        let params: ListTransactionsParams =
+            read_arguments("list_transactions", arguments(json!({ "limit": "20" })))
+                .expect("coerced arguments");
+        assert_eq!(params.limit, Some(20));
+    }
+}
@@ -0,0 +1,69 @@
+//! MCP server wrapping the ZenMoney personal finance API.
+//!
+//! [`ZenMoneyMcpServer`] implements `rmcp`'s `ServerHandler`, so it can be
+//! served over any `rmcp` transport or embedded in another application. A
+//! custom handler can dispatch to [`ZenMoneyMcpServer::tools`] alongside
+//! tools of its own. The `zenmoney-mcp` binary is a thin stdio
+//! wrapper around this crate.
+//!
+//! ```no_run
+//! use rmcp::ServiceExt as _;
+//! use zenmoney_mcp::ZenMoneyMcpServer;
+//! use zenmoney_rs::storage::FileStorage;
+//! use zenmoney_rs::zen_money::ZenMoney;
+//!
+//! # async fn run() -> Result<(), Box<dyn core::error::Error>> {
+//! let storage = FileStorage::new(FileStorage::default_dir()?)?;
+//! let token = std::env::var("ZENMONEY_TOKEN")?;
+//! let client = ZenMoney::builder().token(token).storage(storage).build()?;
+//! let server = ZenMoneyMcpServer::new(client);
+//! let service = server.serve((tokio::io::stdin(), tokio::io::stdout())).await?;
+//! let _quit_reason = service.waiting().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod amount;
+mod analytics;
+mod backup;
+pub mod compact;
+mod completion;
+pub mod confirm;
+pub mod conflicts;
+pub mod date_guard;
+pub mod default_account;
+pub mod demo;
+pub mod doctor;
+pub mod envelopes;
+mod export;
+pub mod freshness;
+mod fuzzy;
+pub mod instructions;
+mod ledger;
+mod lenient;
+mod loans;
+pub mod logging;
+mod mcc;
+mod merge;
+mod ofx;
+mod params;
+pub mod pending;
+mod persist;
+pub mod planned;
+mod progress;
+mod receipt;
+pub mod replay;
+mod resources;
+mod response;
+pub mod review;
+pub mod schedule;
+mod server;
+pub mod settings;
+pub mod shutdown;
+mod stats;
+pub mod storage;
+mod sync_diff;
+pub mod templates;
+pub mod throttle;
+
+pub use crate::server::{BASE_CURRENCY_ENV, INCLUDE_IDS_ENV, ZenMoneyMcpServer};
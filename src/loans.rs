@@ -0,0 +1,223 @@
+//! Loan and deposit projections.
+//!
+//! ZenMoney stores the terms of `Loan` and `Deposit` accounts: the annual
+//! rate, capitalization, start date, term and payoff period. These helpers
+//! turn those terms into period dates and an amortization or maturity
+//! schedule. Rates are applied per period, so the result is a projection
+//! rather than the bank's exact day-count calculation.
+
+use chrono::{Days, Months, NaiveDate};
+use zenmoney_rs::models::Interval;
+
+use crate::response::ScheduleRow;
+
+/// Upper bound on generated periods, guarding against runaway terms.
+const MAX_PERIODS: u32 = 1_200;
+
+/// Returns `date` moved forward by `count` intervals.
+pub(crate) fn add_intervals(date: NaiveDate, interval: Interval, count: u32) -> Option<NaiveDate> {
+    match interval {
+        Interval::Day => date.checked_add_days(Days::new(u64::from(count))),
+        Interval::Week => date.checked_add_days(Days::new(u64::from(count).saturating_mul(7))),
+        Interval::Month => date.checked_add_months(Months::new(count)),
+        Interval::Year => date.checked_add_months(Months::new(count.checked_mul(12)?)),
+    }
+}
+
+/// Interest rate for `count` intervals at an annual percentage.
+pub(crate) fn period_rate(annual_percent: f64, interval: Interval, count: u32) -> f64 {
+    let intervals = f64::from(count);
+    let years = match interval {
+        Interval::Day => intervals / 365.0,
+        Interval::Week => intervals * 7.0 / 365.0,
+        Interval::Month => intervals / 12.0,
+        Interval::Year => intervals,
+    };
+    annual_percent / 100.0 * years
+}
+
+/// Lists period end dates after `after`, stepping from `start` until `end`.
+///
+/// `end` is always the final date when it falls after `after`, so a term
+/// that is not a whole number of steps ends with a shorter period.
+pub(crate) fn period_dates(
+    start: NaiveDate,
+    end: NaiveDate,
+    step: Option<(Interval, u32)>,
+    after: NaiveDate,
+) -> Vec<NaiveDate> {
+    let mut dates = Vec::new();
+    if let Some((interval, every)) = step.filter(|&(_, every)| every > 0) {
+        for index in 1..=MAX_PERIODS {
+            let Some(date) = index
+                .checked_mul(every)
+                .and_then(|count| add_intervals(start, interval, count))
+            else {
+                break;
+            };
+            if date >= end {
+                break;
+            }
+            if date > after {
+                dates.push(date);
+            }
+        }
+    }
+    if end > after {
+        dates.push(end);
+    }
+    dates
+}
+
+/// Builds an equal-payment (annuity) schedule repaying `principal` on `dates`.
+///
+/// The last payment settles whatever principal remains.
+pub(crate) fn amortization_schedule(
+    principal: f64,
+    rate: f64,
+    dates: &[NaiveDate],
+) -> Vec<ScheduleRow> {
+    let periods = i32::try_from(dates.len()).unwrap_or(i32::MAX);
+    let payment = if rate.abs() < f64::EPSILON {
+        principal / f64::from(periods.max(1))
+    } else {
+        principal * rate / (1.0 - (1.0 + rate).powi(-periods))
+    };
+
+    let mut remaining = principal;
+    let mut rows = Vec::with_capacity(dates.len());
+    for (index, date) in dates.iter().enumerate() {
+        let interest = remaining * rate;
+        let repaid = if index + 1 == dates.len() {
+            remaining
+        } else {
+            (payment - interest).clamp(0.0, remaining)
+        };
+        remaining -= repaid;
+        rows.push(ScheduleRow {
+            date: date.to_string(),
+            payment: repaid + interest,
+            interest,
+            principal: repaid,
+            balance: remaining,
+        });
+    }
+    rows
+}
+
+/// Projects deposit growth on `dates`.
+///
+/// With capitalization interest is added to the balance; otherwise it is
+/// paid out each period and reported as the payment.
+pub(crate) fn deposit_schedule(
+    balance: f64,
+    rate: f64,
+    capitalization: bool,
+    dates: &[NaiveDate],
+) -> Vec<ScheduleRow> {
+    let mut current = balance;
+    dates
+        .iter()
+        .map(|date| {
+            let interest = current * rate;
+            let payout = if capitalization {
+                current += interest;
+                0.0
+            } else {
+                interest
+            };
+            ScheduleRow {
+                date: date.to_string(),
+                payment: payout,
+                interest,
+                principal: 0.0,
+                balance: current,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::indexing_slicing,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect and indexing for readability"
+)]
+mod tests {
+    use chrono::NaiveDate;
+    use zenmoney_rs::models::Interval;
+
+    use super::{
+        add_intervals, amortization_schedule, deposit_schedule, period_dates, period_rate,
+    };
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).expect("valid date")
+    }
+
+    #[test]
+    fn add_intervals_by_unit() {
+        let start = date(2024, 1, 31);
+        assert_eq!(
+            add_intervals(start, Interval::Day, 1),
+            Some(date(2024, 2, 1))
+        );
+        assert_eq!(
+            add_intervals(start, Interval::Week, 2),
+            Some(date(2024, 2, 14))
+        );
+        assert_eq!(
+            add_intervals(start, Interval::Month, 1),
+            Some(date(2024, 2, 29))
+        );
+        assert_eq!(
+            add_intervals(start, Interval::Year, 1),
+            Some(date(2025, 1, 31))
+        );
+    }
+
+    #[test]
+    fn period_dates_skip_past_and_end_on_term() {
+        let dates = period_dates(
+            date(2024, 1, 15),
+            date(2024, 6, 1),
+            Some((Interval::Month, 1)),
+            date(2024, 3, 1),
+        );
+        assert_eq!(
+            dates,
+            vec![
+                date(2024, 3, 15),
+                date(2024, 4, 15),
+                date(2024, 5, 15),
+                date(2024, 6, 1)
+            ]
+        );
+        let ended = period_dates(date(2024, 1, 1), date(2024, 2, 1), None, date(2024, 3, 1));
+        assert!(ended.is_empty());
+    }
+
+    #[test]
+    fn amortization_repays_principal() {
+        let dates: Vec<NaiveDate> = (1..=12).map(|month| date(2024, month, 1)).collect();
+        let rate = period_rate(12.0, Interval::Month, 1);
+        let rows = amortization_schedule(12_000.0, rate, &dates);
+        assert_eq!(rows.len(), 12);
+        // 1% monthly annuity on 12 000 over 12 months is about 1 066.19.
+        assert!((rows[0].payment - 1_066.19).abs() < 0.01);
+        assert!((rows[0].interest - 120.0).abs() < 1e-9);
+        assert!(rows[11].balance.abs() < 1e-9);
+    }
+
+    #[test]
+    fn deposit_with_and_without_capitalization() {
+        let dates = vec![date(2024, 2, 1), date(2024, 3, 1)];
+        let rate = period_rate(12.0, Interval::Month, 1);
+        let compounded = deposit_schedule(1_000.0, rate, true, &dates);
+        assert!((compounded[1].balance - 1_020.1).abs() < 1e-9);
+        let simple = deposit_schedule(1_000.0, rate, false, &dates);
+        assert!((simple[1].balance - 1_000.0).abs() < f64::EPSILON);
+        assert!((simple[1].payment - 10.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,194 @@
+//! Forwarding of `tracing` events to the MCP client as log notifications.
+//!
+//! Once the client picks a level with `logging/setLevel`, events from this
+//! server and the `zenmoney-rs` client at or above that level are sent as
+//! `notifications/message`, in addition to the usual stderr output. Events
+//! from other crates (HTTP stack, MCP transport) are never forwarded, which
+//! also keeps failed notifications from producing more notifications.
+
+extern crate alloc;
+
+use alloc::sync::Arc;
+use std::sync::Mutex;
+
+use rmcp::model::{LoggingLevel, LoggingMessageNotificationParam};
+use rmcp::{Peer, RoleServer};
+use serde_json::{Map, Value};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::Layer;
+use tracing_subscriber::layer::Context;
+
+/// Target prefix of events that may be forwarded to the client.
+const FORWARDED_TARGET_PREFIX: &str = "zenmoney";
+
+/// Client connection and minimum level requested by the client.
+#[derive(Clone)]
+struct ClientSink {
+    /// Connection to the client that set the level.
+    peer: Peer<RoleServer>,
+    /// Least severe level the client wants to receive.
+    level: LoggingLevel,
+}
+
+/// Logging destination shared by the MCP server and the tracing layer.
+#[derive(Clone, Default)]
+pub struct ClientLog {
+    /// Current destination; `None` until the client sets a level.
+    sink: Arc<Mutex<Option<ClientSink>>>,
+}
+
+impl core::fmt::Debug for ClientLog {
+    #[inline]
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ClientLog").finish_non_exhaustive()
+    }
+}
+
+impl ClientLog {
+    /// Starts forwarding events at `level` or above to `peer`.
+    pub(crate) fn set_level(&self, peer: Peer<RoleServer>, level: LoggingLevel) {
+        if let Ok(mut sink) = self.sink.lock() {
+            *sink = Some(ClientSink { peer, level });
+        }
+    }
+
+    /// Returns the peer to notify about an event at `level`, if the client wants it.
+    fn peer_for(&self, level: LoggingLevel) -> Option<Peer<RoleServer>> {
+        let sink = self.sink.lock().ok()?;
+        sink.as_ref()
+            .filter(|current| severity(level) >= severity(current.level))
+            .map(|current| current.peer.clone())
+    }
+}
+
+/// Ranks MCP logging levels from least to most severe.
+const fn severity(level: LoggingLevel) -> u8 {
+    match level {
+        LoggingLevel::Debug => 0,
+        LoggingLevel::Info => 1,
+        LoggingLevel::Notice => 2,
+        LoggingLevel::Warning => 3,
+        LoggingLevel::Error => 4,
+        LoggingLevel::Critical => 5,
+        LoggingLevel::Alert => 6,
+        LoggingLevel::Emergency => 7,
+    }
+}
+
+/// Maps a tracing level to the closest MCP logging level.
+fn mcp_level(level: Level) -> LoggingLevel {
+    if level == Level::ERROR {
+        LoggingLevel::Error
+    } else if level == Level::WARN {
+        LoggingLevel::Warning
+    } else if level == Level::INFO {
+        LoggingLevel::Info
+    } else {
+        LoggingLevel::Debug
+    }
+}
+
+/// Collects event fields into a JSON object.
+#[derive(Debug, Default)]
+struct JsonFields {
+    /// Field name → value.
+    fields: Map<String, Value>,
+}
+
+impl Visit for JsonFields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        let _previous = self
+            .fields
+            .insert(field.name().to_owned(), Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        let _previous = self
+            .fields
+            .insert(field.name().to_owned(), Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        let _previous = self
+            .fields
+            .insert(field.name().to_owned(), Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        let _previous = self
+            .fields
+            .insert(field.name().to_owned(), Value::from(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn core::fmt::Debug) {
+        let _previous = self
+            .fields
+            .insert(field.name().to_owned(), Value::from(format!("{value:?}")));
+    }
+}
+
+/// Tracing layer that sends events to the MCP client.
+#[derive(Debug)]
+pub struct ClientLogLayer {
+    /// Destination shared with the server.
+    log: ClientLog,
+}
+
+impl ClientLogLayer {
+    /// Creates a layer forwarding to `log`.
+    #[inline]
+    #[must_use]
+    pub const fn new(log: ClientLog) -> Self {
+        Self { log }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for ClientLogLayer {
+    #[inline]
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        if !metadata.target().starts_with(FORWARDED_TARGET_PREFIX) {
+            return;
+        }
+        let level = mcp_level(*metadata.level());
+        let Some(peer) = self.log.peer_for(level) else {
+            return;
+        };
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let mut visitor = JsonFields::default();
+        event.record(&mut visitor);
+        let notification = LoggingMessageNotificationParam {
+            level,
+            logger: Some(metadata.target().to_owned()),
+            data: Value::Object(visitor.fields),
+        };
+        drop(runtime.spawn(async move {
+            // A failed log notification has nowhere better to be reported.
+            let _sent = peer.notify_logging_message(notification).await;
+        }));
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::missing_docs_in_private_items,
+    reason = "test code does not need docs"
+)]
+mod tests {
+    use rmcp::model::LoggingLevel;
+    use tracing::Level;
+
+    use super::{mcp_level, severity};
+
+    #[test]
+    fn tracing_levels_map_to_mcp_levels() {
+        assert!(matches!(mcp_level(Level::ERROR), LoggingLevel::Error));
+        assert!(matches!(mcp_level(Level::WARN), LoggingLevel::Warning));
+        assert!(matches!(mcp_level(Level::INFO), LoggingLevel::Info));
+        assert!(matches!(mcp_level(Level::TRACE), LoggingLevel::Debug));
+        assert!(severity(LoggingLevel::Warning) > severity(LoggingLevel::Info));
+    }
+}
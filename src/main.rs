@@ -1,59 +1,298 @@
 //! ZenMoney MCP server entry point.
 //!
 //! Reads `ZENMONEY_TOKEN` from the environment, creates a [`ZenMoney`]
-//! client backed by [`FileStorage`], performs an initial sync, then
-//! serves MCP tools over stdio.
+//! client backed by [`FileStorage`] (or [`InMemoryStorage`] when
+//! `ZENMONEY_STORAGE=memory`), performs an initial sync, then serves the
+//! [`ZenMoneyMcpServer`] tools over stdio. With `ZENMONEY_DEMO=1` it serves
+//! generated data instead and needs no token, and `ZENMONEY_REPLAY` records
+//! the synced data or serves a recording offline. `ZENMONEY_SCHEDULE` names
+//! a file of report jobs run on cron schedules while serving.
+//!
+//! `zenmoney-mcp --reset` instead wipes the local storage directory and exits,
+//! `zenmoney-mcp doctor` checks the configuration and exits, and
+//! `zenmoney-mcp --run-schedule` runs the scheduled jobs without serving
+//! MCP, for a service manager to keep running. SIGINT and SIGTERM let
+//! in-flight writes finish before the transport is closed.
 
-mod params;
-mod response;
-mod server;
+use std::io::Write as _;
+use std::path::Path;
 
-use rmcp::ServiceExt;
-use tracing_subscriber::EnvFilter;
-use zenmoney_rs::storage::FileStorage;
+use rmcp::service::RunningService;
+use rmcp::{RoleServer, ServiceExt};
+use tracing_subscriber::layer::SubscriberExt as _;
+use tracing_subscriber::util::SubscriberInitExt as _;
+use tracing_subscriber::{EnvFilter, Layer as _};
+use zenmoney_rs::storage::{FileStorage, InMemoryStorage, Storage};
 use zenmoney_rs::zen_money::ZenMoney;
 
-use crate::server::ZenMoneyMcpServer;
+use zenmoney_mcp::ZenMoneyMcpServer;
+use zenmoney_mcp::confirm;
+use zenmoney_mcp::demo::{DEMO_ENV, DEMO_NOTICE};
+use zenmoney_mcp::doctor;
+use zenmoney_mcp::logging::{ClientLog, ClientLogLayer};
+use zenmoney_mcp::pending;
+use zenmoney_mcp::replay::{self, ReplayMode};
+use zenmoney_mcp::schedule::SCHEDULE_ENV;
+use zenmoney_mcp::settings::{Settings, scheduled_jobs};
+use zenmoney_mcp::shutdown::{self, ShutdownHandle};
+use zenmoney_mcp::storage::{LocalState, STORAGE_ENV, StorageKind, purge_dir};
+
+/// Command-line flag that wipes local data instead of serving.
+const RESET_FLAG: &str = "--reset";
+
+/// Subcommand that checks the configuration instead of serving.
+const DOCTOR_COMMAND: &str = "doctor";
+
+/// Command-line flag that runs the scheduled jobs instead of serving MCP.
+const RUN_SCHEDULE_FLAG: &str = "--run-schedule";
+
+/// Securely deletes the file storage directory contents and reports the result.
+///
+/// # Errors
+///
+/// Returns an error if the directory cannot be located or purged.
+fn reset() -> Result<(), Box<dyn core::error::Error>> {
+    let storage_dir = FileStorage::default_dir()?;
+    let stats = purge_dir(&storage_dir)?;
+    writeln!(
+        std::io::stderr(),
+        "Deleted {} files ({} bytes overwritten) from {}",
+        stats.files,
+        stats.bytes,
+        storage_dir.display()
+    )?;
+    Ok(())
+}
 
 /// Runs the MCP server.
 ///
 /// # Errors
 ///
-/// Returns an error if the token is missing, the client cannot be built,
-/// the initial sync fails, or the stdio transport encounters an error.
+/// Returns an error if the token is missing, `ZENMONEY_STORAGE` is not a
+/// supported backend, the client cannot be built, the initial sync fails,
+/// the stdio transport encounters an error, `--reset` cannot purge the
+/// storage directory, or a `doctor` check fails.
 async fn run() -> Result<(), Box<dyn core::error::Error>> {
-    // Initialise tracing to stderr (stdout is used for MCP stdio transport).
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env())
-        .with_writer(std::io::stderr)
+    // Initialise tracing to stderr (stdout is used for MCP stdio transport),
+    // also forwarding events to the client once it sets a logging level.
+    let client_log = ClientLog::default();
+    tracing_subscriber::registry()
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(std::io::stderr)
+                .with_filter(EnvFilter::from_default_env()),
+        )
+        .with(ClientLogLayer::new(client_log.clone()))
         .init();
 
+    if std::env::args().skip(1).any(|arg| arg == RESET_FLAG) {
+        return reset();
+    }
+    if std::env::args()
+        .nth(1)
+        .is_some_and(|arg| arg == DOCTOR_COMMAND)
+    {
+        return doctor::run().await;
+    }
+
+    let schedule_only = std::env::args().skip(1).any(|arg| arg == RUN_SCHEDULE_FLAG);
     tracing::info!("starting ZenMoney MCP server");
+    if std::env::var(DEMO_ENV).is_ok_and(|value| confirm::is_enabled(&value)) {
+        return serve_demo(client_log).await;
+    }
+    if let Some(path) = replay::configured_file(ReplayMode::Replay)? {
+        return serve_replay(&path, client_log).await;
+    }
 
     // Read token from environment.
     let token: String = std::env::var("ZENMONEY_TOKEN")
         .map_err(|_err| "ZENMONEY_TOKEN environment variable is required")?;
 
-    // Create file storage at default XDG location.
-    let storage_dir = FileStorage::default_dir()?;
-    let storage = FileStorage::new(storage_dir)?;
+    match StorageKind::from_setting(std::env::var(STORAGE_ENV).ok().as_deref())? {
+        StorageKind::File => {
+            // Create file storage at default XDG location.
+            let storage_dir = FileStorage::default_dir()?;
+            let local = LocalState::open(&storage_dir)?;
+            let storage = FileStorage::new(storage_dir)?;
+            let client = ZenMoney::builder().token(token).storage(storage).build()?;
+            serve(client, local, client_log, schedule_only).await
+        }
+        StorageKind::Memory => {
+            tracing::info!("using in-memory storage; nothing is written to disk");
+            let client = ZenMoney::builder()
+                .token(token)
+                .storage(InMemoryStorage::new())
+                .build()?;
+            serve(client, LocalState::default(), client_log, schedule_only).await
+        }
+    }
+}
 
-    // Build the ZenMoney client.
-    let client = ZenMoney::builder().token(token).storage(storage).build()?;
+/// Syncs the client, then serves MCP tools over stdio until the client
+/// disconnects, or with `schedule_only` runs the scheduled jobs until a
+/// shutdown signal arrives.
+///
+/// # Errors
+///
+/// Returns an error if `ZENMONEY_MAX_DATA_AGE` or
+/// `ZENMONEY_MIN_REQUEST_INTERVAL_MS` is not a whole number, the schedule
+/// file is invalid or missing in `schedule_only` mode, ZenMoney rejects the
+/// initial sync or the stdio transport encounters an error. If ZenMoney
+/// cannot be reached at all, the server starts on local data.
+async fn serve<S: Storage + 'static>(
+    client: ZenMoney<S>,
+    local: LocalState,
+    client_log: ClientLog,
+    schedule_only: bool,
+) -> Result<(), Box<dyn core::error::Error>> {
+    let settings = Settings::from_env()?;
+    let jobs = scheduled_jobs()?;
+    if schedule_only && jobs.is_none() {
+        return Err(
+            format!("{RUN_SCHEDULE_FLAG} needs {SCHEDULE_ENV} to name a schedule file").into(),
+        );
+    }
 
     // Perform initial sync.
     tracing::info!("performing initial sync");
-    let _sync_response = client.sync().await?;
-    tracing::info!("initial sync complete");
+    let synced = match client.sync().await {
+        Ok(_sync_response) => {
+            tracing::info!("initial sync complete");
+            true
+        }
+        Err(err) if pending::is_unreachable(&err) => {
+            tracing::warn!(%err, "ZenMoney is unreachable, serving local data");
+            false
+        }
+        Err(err) => return Err(err.into()),
+    };
 
     // Create MCP server and serve over stdio.
-    let mcp_server = ZenMoneyMcpServer::new(client);
+    let mcp_server = settings
+        .apply(ZenMoneyMcpServer::new(client), local)
+        .await
+        .with_client_log(client_log);
+    if synced {
+        mcp_server.mark_synced();
+    }
+    if let Some(path) = replay::configured_file(ReplayMode::Record)? {
+        mcp_server.record_replay(&path).await?;
+    }
+    if let Some(jobs) = jobs {
+        mcp_server.spawn_scheduled_jobs(jobs);
+    }
+    if schedule_only {
+        return run_schedule(&mcp_server).await;
+    }
+    serve_stdio(mcp_server).await
+}
+
+/// Lets the scheduled jobs of `mcp_server` run without serving MCP until
+/// SIGINT or SIGTERM arrives, then waits for the job in flight to finish
+/// its write or sync.
+///
+/// # Errors
+///
+/// Returns an error if the signal handlers cannot be installed.
+async fn run_schedule<S: Storage + 'static>(
+    mcp_server: &ZenMoneyMcpServer<S>,
+) -> Result<(), Box<dyn core::error::Error>> {
+    tracing::info!("running scheduled reports without serving MCP");
+    shutdown::wait_for_signal().await?;
+    tracing::info!("shutdown requested; finishing in-flight work");
+    mcp_server.shutdown_handle().drain().await;
+    tracing::info!("shutdown complete");
+    Ok(())
+}
+
+/// Serves data recorded with `ZENMONEY_REPLAY=record` without contacting ZenMoney.
+///
+/// # Errors
+///
+/// Returns an error if the client cannot be built, the replay file cannot be
+/// read or stored, or the stdio transport encounters an error.
+async fn serve_replay(
+    path: &Path,
+    client_log: ClientLog,
+) -> Result<(), Box<dyn core::error::Error>> {
+    let client = ZenMoney::builder()
+        .token("replay")
+        .storage(InMemoryStorage::new())
+        .build()?;
+    let server = ZenMoneyMcpServer::new(client).with_replay(path).await?;
+    let mcp_server = Settings::from_env()?
+        .apply(server, LocalState::default())
+        .await
+        .with_client_log(client_log);
+    serve_stdio(mcp_server).await
+}
+
+/// Serves generated demo data without contacting ZenMoney.
+///
+/// # Errors
+///
+/// Returns an error if the client cannot be built, the demo data cannot be
+/// stored or the stdio transport encounters an error.
+async fn serve_demo(client_log: ClientLog) -> Result<(), Box<dyn core::error::Error>> {
+    tracing::info!("demo mode: serving generated data, ZenMoney is never contacted");
+    let client = ZenMoney::builder()
+        .token("demo")
+        .storage(InMemoryStorage::new())
+        .build()?;
+    let server = ZenMoneyMcpServer::new(client).with_demo_data().await?;
+    let mcp_server = Settings::from_env()?
+        .with_notice(DEMO_NOTICE)
+        .apply(server, LocalState::default())
+        .await
+        .with_client_log(client_log);
+    serve_stdio(mcp_server).await
+}
+
+/// Serves `mcp_server` over stdio until the client disconnects or a
+/// shutdown signal arrives.
+///
+/// # Errors
+///
+/// Returns an error if the stdio transport encounters an error.
+async fn serve_stdio<S: Storage + 'static>(
+    mcp_server: ZenMoneyMcpServer<S>,
+) -> Result<(), Box<dyn core::error::Error>> {
+    let shutdown = mcp_server.shutdown_handle();
     let transport = (tokio::io::stdin(), tokio::io::stdout());
     let service = mcp_server.serve(transport).await?;
 
     tracing::info!("MCP server running on stdio");
-    let _quit_reason = service.waiting().await?;
+    run_until_shutdown(service, &shutdown).await
+}
 
+/// Serves until the client disconnects or SIGINT/SIGTERM arrives.
+///
+/// On a signal, in-flight writes and syncs finish before the transport is
+/// closed.
+///
+/// # Errors
+///
+/// Returns an error if the signal handlers cannot be installed or the
+/// service task fails.
+async fn run_until_shutdown<S: Storage + 'static>(
+    service: RunningService<RoleServer, ZenMoneyMcpServer<S>>,
+    shutdown: &ShutdownHandle,
+) -> Result<(), Box<dyn core::error::Error>> {
+    let cancel = service.cancellation_token();
+    let mut waiting = core::pin::pin!(service.waiting());
+    tokio::select! {
+        quit = &mut waiting => {
+            let _quit_reason = quit?;
+        }
+        signal = shutdown::wait_for_signal() => {
+            signal?;
+            tracing::info!("shutdown requested; finishing in-flight work");
+            shutdown.drain().await;
+            cancel.cancel();
+            let _quit_reason = waiting.await?;
+            tracing::info!("shutdown complete");
+        }
+    }
     Ok(())
 }
 
@@ -0,0 +1,233 @@
+//! Merchant category codes (MCC).
+//!
+//! Bank-imported transactions carry the ISO 18245 merchant category code
+//! of the card terminal. This module bundles a table of the most common
+//! codes so responses can show a readable category next to the number.
+
+/// Known MCC codes with their descriptions, sorted by code.
+const MCC_TABLE: &[(i32, &str)] = &[
+    (742, "Veterinary services"),
+    (1520, "General contractors"),
+    (1711, "Heating, plumbing and air conditioning contractors"),
+    (1799, "Special trade contractors"),
+    (2741, "Publishing and printing"),
+    (4111, "Commuter transport"),
+    (4112, "Passenger railways"),
+    (4121, "Taxicabs and limousines"),
+    (4131, "Bus lines"),
+    (4214, "Freight carriers and trucking"),
+    (4215, "Courier services"),
+    (4411, "Cruise lines"),
+    (4511, "Airlines"),
+    (4582, "Airports and airport terminals"),
+    (4722, "Travel agencies and tour operators"),
+    (4784, "Tolls and bridge fees"),
+    (4789, "Transportation services"),
+    (4812, "Telecommunication equipment and phone sales"),
+    (4814, "Telecommunication services"),
+    (4816, "Computer network and information services"),
+    (4829, "Money transfers"),
+    (4899, "Cable, satellite and pay television"),
+    (4900, "Utilities"),
+    (5013, "Motor vehicle supplies and parts"),
+    (5045, "Computers and software"),
+    (5094, "Jewelry and precious stones"),
+    (5111, "Stationery and office supplies"),
+    (5122, "Drugs and druggist sundries"),
+    (5200, "Home supply warehouse stores"),
+    (5211, "Building materials stores"),
+    (5251, "Hardware stores"),
+    (5261, "Garden supply stores"),
+    (5300, "Wholesale clubs"),
+    (5309, "Duty-free stores"),
+    (5310, "Discount stores"),
+    (5311, "Department stores"),
+    (5331, "Variety stores"),
+    (5399, "General merchandise"),
+    (5411, "Grocery stores and supermarkets"),
+    (5422, "Meat provisioners"),
+    (5441, "Candy and confectionery stores"),
+    (5451, "Dairy products stores"),
+    (5462, "Bakeries"),
+    (5499, "Convenience and specialty food stores"),
+    (5511, "Car dealers (new and used)"),
+    (5532, "Automotive tire stores"),
+    (5533, "Automotive parts and accessories stores"),
+    (5541, "Service stations"),
+    (5542, "Automated fuel dispensers"),
+    (5611, "Men's clothing stores"),
+    (5621, "Women's clothing stores"),
+    (5631, "Women's accessory stores"),
+    (5641, "Children's and infants' wear stores"),
+    (5651, "Family clothing stores"),
+    (5655, "Sports and riding apparel stores"),
+    (5661, "Shoe stores"),
+    (5691, "Clothing stores"),
+    (5699, "Apparel and accessory stores"),
+    (5712, "Furniture and home furnishings stores"),
+    (5714, "Drapery and upholstery stores"),
+    (5719, "Home furnishing specialty stores"),
+    (5722, "Household appliance stores"),
+    (5732, "Electronics stores"),
+    (5733, "Music stores"),
+    (5734, "Computer software stores"),
+    (5735, "Record stores"),
+    (5811, "Caterers"),
+    (5812, "Restaurants"),
+    (5813, "Bars and nightclubs"),
+    (5814, "Fast food restaurants"),
+    (5815, "Digital media: books, movies, music"),
+    (5816, "Digital games"),
+    (5817, "Digital applications"),
+    (5818, "Digital goods"),
+    (5912, "Pharmacies"),
+    (5921, "Liquor stores"),
+    (5931, "Used merchandise stores"),
+    (5941, "Sporting goods stores"),
+    (5942, "Book stores"),
+    (5943, "Stationery stores"),
+    (5944, "Jewelry and watch stores"),
+    (5945, "Toy and game stores"),
+    (5946, "Camera and photographic supply stores"),
+    (5947, "Gift and souvenir shops"),
+    (5948, "Luggage and leather goods stores"),
+    (5949, "Sewing and fabric stores"),
+    (5964, "Direct marketing: catalog merchants"),
+    (5965, "Direct marketing: combined catalog and retail"),
+    (5968, "Direct marketing: subscriptions"),
+    (5969, "Direct marketing: other"),
+    (5970, "Artist supply and craft shops"),
+    (5977, "Cosmetic stores"),
+    (5983, "Fuel dealers"),
+    (5992, "Florists"),
+    (5993, "Cigar stores"),
+    (5994, "News dealers and newsstands"),
+    (5995, "Pet shops and pet food"),
+    (5999, "Miscellaneous retail stores"),
+    (6010, "Financial institutions: manual cash disbursements"),
+    (6011, "ATM cash withdrawals"),
+    (6012, "Financial institutions: merchandise and services"),
+    (6051, "Quasi-cash and currency purchases"),
+    (6211, "Securities brokers and dealers"),
+    (6300, "Insurance"),
+    (6513, "Real estate agents and rentals"),
+    (6536, "Money transfers: domestic"),
+    (6537, "Money transfers: cross-border"),
+    (6538, "Money transfers: funding"),
+    (6540, "Stored value card loads"),
+    (7011, "Hotels and lodging"),
+    (7032, "Sporting and recreational camps"),
+    (7210, "Laundry and cleaning services"),
+    (7211, "Laundries"),
+    (7216, "Dry cleaners"),
+    (7221, "Photographic studios"),
+    (7230, "Beauty and barber shops"),
+    (7251, "Shoe repair"),
+    (7261, "Funeral services"),
+    (7298, "Health and beauty spas"),
+    (7299, "Personal services"),
+    (7311, "Advertising services"),
+    (7333, "Commercial photography and graphic design"),
+    (7338, "Quick copy and reproduction services"),
+    (7342, "Pest control"),
+    (7349, "Cleaning and maintenance services"),
+    (7372, "Computer programming and data processing"),
+    (7399, "Business services"),
+    (7512, "Car rental"),
+    (7513, "Truck and trailer rental"),
+    (7523, "Parking lots and garages"),
+    (7531, "Auto body repair shops"),
+    (7538, "Auto service shops"),
+    (7542, "Car washes"),
+    (7549, "Towing services"),
+    (7622, "Electronics repair"),
+    (7629, "Appliance repair"),
+    (7631, "Watch and jewelry repair"),
+    (7699, "Repair shops"),
+    (7832, "Cinemas"),
+    (7841, "Video rental"),
+    (7911, "Dance halls and studios"),
+    (7922, "Theatrical producers and ticket agencies"),
+    (7929, "Bands and entertainers"),
+    (7932, "Billiard and pool halls"),
+    (7933, "Bowling alleys"),
+    (7941, "Sports clubs and promoters"),
+    (7991, "Tourist attractions and exhibits"),
+    (7992, "Golf courses"),
+    (7993, "Video game supplies"),
+    (7994, "Video game arcades"),
+    (7995, "Betting and gambling"),
+    (7996, "Amusement parks"),
+    (7997, "Fitness and recreation clubs"),
+    (7999, "Recreation services"),
+    (8011, "Doctors"),
+    (8021, "Dentists"),
+    (8031, "Osteopaths"),
+    (8041, "Chiropractors"),
+    (8042, "Optometrists"),
+    (8043, "Opticians"),
+    (8049, "Podiatrists"),
+    (8050, "Nursing and personal care facilities"),
+    (8062, "Hospitals"),
+    (8071, "Medical and dental laboratories"),
+    (8099, "Medical services"),
+    (8111, "Legal services"),
+    (8211, "Schools"),
+    (8220, "Colleges and universities"),
+    (8241, "Correspondence schools"),
+    (8244, "Business schools"),
+    (8249, "Vocational schools"),
+    (8299, "Educational services"),
+    (8351, "Child care services"),
+    (8398, "Charitable organizations"),
+    (8641, "Civic and social associations"),
+    (8651, "Political organizations"),
+    (8661, "Religious organizations"),
+    (8699, "Membership organizations"),
+    (8999, "Professional services"),
+    (9211, "Court costs"),
+    (9222, "Fines"),
+    (9311, "Tax payments"),
+    (9399, "Government services"),
+    (9402, "Postal services"),
+];
+
+/// Returns the description of a merchant category code, if known.
+pub(crate) fn mcc_label(code: i32) -> Option<&'static str> {
+    MCC_TABLE
+        .binary_search_by_key(&code, |&(known, _)| known)
+        .ok()
+        .and_then(|index| MCC_TABLE.get(index))
+        .map(|&(_, label)| label)
+}
+
+/// Returns the description of a code, or a generic label for unknown codes.
+pub(crate) fn mcc_label_or_code(code: i32) -> String {
+    mcc_label(code).map_or_else(|| format!("MCC {code}"), str::to_owned)
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::missing_docs_in_private_items,
+    reason = "test functions are self-describing"
+)]
+mod tests {
+    use super::{MCC_TABLE, mcc_label, mcc_label_or_code};
+
+    #[test]
+    fn table_is_sorted_and_unique() {
+        assert!(
+            MCC_TABLE
+                .windows(2)
+                .all(|pair| matches!(pair, &[(left, _), (right, _)] if left < right))
+        );
+    }
+
+    #[test]
+    fn known_and_unknown_codes() {
+        assert_eq!(mcc_label(5411), Some("Grocery stores and supermarkets"));
+        assert_eq!(mcc_label(1), None);
+        assert_eq!(mcc_label_or_code(5812), "Restaurants");
+        assert_eq!(mcc_label_or_code(1234), "MCC 1234");
+    }
+}
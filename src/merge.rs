@@ -0,0 +1,204 @@
+//! Merging a card hold with its settlement for `merge_transactions`.
+//!
+//! A card payment often arrives twice: first as a hold (a pending
+//! authorization) and days later as the posted transaction. Merging keeps
+//! one of them, fills its empty fields from the other and deletes the other.
+//! Pairs are found by matching each hold with a settled transaction on the
+//! same accounts, in the same currencies and with the same amounts, dated on
+//! the hold's day or up to a few days later. The closest date wins; a hold
+//! with two equally close candidates is left for the user to pair.
+
+use std::collections::HashSet;
+
+use zenmoney_rs::models::Transaction;
+
+/// Amounts closer than this are treated as equal.
+const AMOUNT_TOLERANCE: f64 = 0.005;
+
+/// Returns whether `tx` is a hold (pending card authorization).
+const fn is_hold(tx: &Transaction) -> bool {
+    matches!(tx.hold, Some(true))
+}
+
+/// Returns whether two transactions use the same accounts and currencies.
+fn same_accounts(left: &Transaction, right: &Transaction) -> bool {
+    left.income_account.as_inner() == right.income_account.as_inner()
+        && left.outcome_account.as_inner() == right.outcome_account.as_inner()
+        && left.income_instrument.into_inner() == right.income_instrument.into_inner()
+        && left.outcome_instrument.into_inner() == right.outcome_instrument.into_inner()
+}
+
+/// Returns whether two transactions record the same movement of money.
+fn same_entry(left: &Transaction, right: &Transaction) -> bool {
+    same_accounts(left, right)
+        && (left.income - right.income).abs() < AMOUNT_TOLERANCE
+        && (left.outcome - right.outcome).abs() < AMOUNT_TOLERANCE
+}
+
+/// Checks that `remove` can be merged into `keep`.
+///
+/// # Errors
+///
+/// Returns a message if the two are the same transaction, one is deleted,
+/// or they use different accounts or currencies.
+pub(crate) fn check_mergeable(keep: &Transaction, remove: &Transaction) -> Result<(), String> {
+    if keep.id == remove.id {
+        return Err("keep_id and remove_id must be different transactions".to_owned());
+    }
+    if keep.deleted || remove.deleted {
+        return Err("deleted transactions cannot be merged".to_owned());
+    }
+    if !same_accounts(keep, remove) {
+        return Err(format!(
+            "transactions {} and {} use different accounts or currencies",
+            keep.id.as_inner(),
+            remove.id.as_inner()
+        ));
+    }
+    Ok(())
+}
+
+/// Pairs holds with their settlements, returning `(settled, hold)` pairs.
+pub(crate) fn hold_pairs(
+    transactions: &[Transaction],
+    max_days: u32,
+) -> Vec<(&Transaction, &Transaction)> {
+    let window = 0..=i64::from(max_days);
+    let mut claimed: HashSet<&str> = HashSet::new();
+    let mut pairs = Vec::new();
+    for hold in transactions.iter().filter(|tx| !tx.deleted && is_hold(tx)) {
+        let mut candidates: Vec<(i64, &Transaction)> = transactions
+            .iter()
+            .filter(|tx| {
+                !tx.deleted
+                    && !is_hold(tx)
+                    && !claimed.contains(tx.id.as_inner())
+                    && same_entry(hold, tx)
+            })
+            .map(|tx| (tx.date.signed_duration_since(hold.date).num_days(), tx))
+            .filter(|candidate| window.contains(&candidate.0))
+            .collect();
+        candidates.sort_by_key(|candidate| candidate.0);
+        let closest = candidates.first().copied();
+        let tied = candidates
+            .get(1)
+            .zip(closest)
+            .is_some_and(|(second, first)| second.0 == first.0);
+        if let Some((_days, settled)) = closest.filter(|_closest| !tied) {
+            let _claimed = claimed.insert(settled.id.as_inner());
+            pairs.push((settled, hold));
+        }
+    }
+    pairs
+}
+
+/// Fills the fields `keep` lacks from `other`, returning the names of the
+/// fields copied. A kept hold becomes settled if `other` is settled.
+pub(crate) fn fill_missing(keep: &mut Transaction, other: &Transaction) -> Vec<&'static str> {
+    let mut copied = Vec::new();
+    let untagged = |tx: &Transaction| tx.tag.as_ref().is_none_or(Vec::is_empty);
+    if untagged(keep) && !untagged(other) {
+        keep.tag.clone_from(&other.tag);
+        copied.push("tags");
+    }
+    if keep.merchant.is_none() && other.merchant.is_some() {
+        keep.merchant.clone_from(&other.merchant);
+        copied.push("merchant");
+    }
+    let blank = |text: Option<&String>| text.is_none_or(String::is_empty);
+    if blank(keep.payee.as_ref()) && !blank(other.payee.as_ref()) {
+        keep.payee.clone_from(&other.payee);
+        copied.push("payee");
+    }
+    if blank(keep.original_payee.as_ref()) && !blank(other.original_payee.as_ref()) {
+        keep.original_payee.clone_from(&other.original_payee);
+        copied.push("original_payee");
+    }
+    if blank(keep.comment.as_ref()) && !blank(other.comment.as_ref()) {
+        keep.comment.clone_from(&other.comment);
+        copied.push("comment");
+    }
+    if keep.mcc.is_none() && other.mcc.is_some() {
+        keep.mcc = other.mcc;
+        copied.push("mcc");
+    }
+    if keep.latitude.is_none() && other.latitude.is_some() {
+        keep.latitude = other.latitude;
+        keep.longitude = other.longitude;
+        copied.push("location");
+    }
+    if is_hold(keep) && !is_hold(other) {
+        keep.hold = Some(false);
+        copied.push("hold");
+    }
+    copied
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::indexing_slicing,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect and indexing for readability"
+)]
+mod tests {
+    use chrono::{Days, NaiveDate};
+    use zenmoney_rs::models::{Transaction, TransactionId};
+
+    use super::{check_mergeable, fill_missing, hold_pairs};
+
+    fn expense() -> Transaction {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 15).expect("valid date");
+        crate::demo::dataset(today)
+            .transactions
+            .into_iter()
+            .find(|tx| {
+                tx.outcome > 0.0 && tx.income_account.as_inner() == tx.outcome_account.as_inner()
+            })
+            .expect("demo expense")
+    }
+
+    fn copy(tx: &Transaction, id: &str, days_later: u64) -> Transaction {
+        let mut later = tx.clone();
+        later.id = TransactionId::new(id.to_owned());
+        later.date = tx
+            .date
+            .checked_add_days(Days::new(days_later))
+            .expect("valid date");
+        later
+    }
+
+    #[test]
+    fn pairs_hold_with_closest_settlement() {
+        let mut hold = expense();
+        hold.hold = Some(true);
+        hold.comment = Some("coffee".to_owned());
+        let mut settled = copy(&hold, "settled", 2);
+        settled.hold = None;
+        settled.comment = None;
+        let far = copy(&settled, "far", 4);
+        let mut other_amount = copy(&settled, "other", 1);
+        other_amount.outcome += 10.0;
+        let transactions = vec![hold.clone(), far, settled, other_amount];
+
+        let pairs = hold_pairs(&transactions, 5);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].0.id.as_inner(), "settled");
+
+        let mut kept = pairs[0].0.clone();
+        check_mergeable(&kept, &hold).expect("mergeable");
+        assert_eq!(fill_missing(&mut kept, &hold), vec!["comment"]);
+        assert_eq!(kept.comment.as_deref(), Some("coffee"));
+        assert!(check_mergeable(&kept, &kept.clone()).is_err());
+    }
+
+    #[test]
+    fn tied_candidates_are_left_alone() {
+        let mut hold = expense();
+        hold.hold = Some(true);
+        let mut first = copy(&hold, "first", 1);
+        first.hold = Some(false);
+        let second = copy(&first, "second", 0);
+        assert!(hold_pairs(&[hold, first, second], 5).is_empty());
+    }
+}
@@ -0,0 +1,306 @@
+//! OFX bank statement parsing and matching for `import_ofx`.
+//!
+//! OFX 1 (SGML, where element end tags are optional) and OFX 2 (XML)
+//! statements are both read by scanning each `STMTTRN` aggregate for its
+//! fields. Entries are then matched against the transactions already
+//! recorded on the target account: by FITID first, which ZenMoney keeps as
+//! the bank ID of imported transactions and `export_ofx` sets to the
+//! transaction ID, then by signed amount within a few days of the posting
+//! date. An entry with exactly one candidate is matched, one with several
+//! is ambiguous and left to the user, and the rest are new.
+
+use std::collections::HashSet;
+
+use chrono::NaiveDate;
+use rmcp::ErrorData as McpError;
+use zenmoney_rs::models::Transaction;
+
+use crate::export::signed_amount;
+
+/// Amounts closer than this are treated as equal.
+const AMOUNT_TOLERANCE: f64 = 0.005;
+
+/// One transaction of an OFX statement.
+#[derive(Debug, Clone)]
+pub(crate) struct OfxEntry {
+    /// Bank-assigned transaction ID.
+    pub(crate) fitid: String,
+    /// Posting date.
+    pub(crate) date: NaiveDate,
+    /// Signed amount: positive for money in, negative for money out.
+    pub(crate) amount: f64,
+    /// Payee name.
+    pub(crate) name: Option<String>,
+    /// Memo line.
+    pub(crate) memo: Option<String>,
+}
+
+/// Transactions and currency of an OFX statement.
+#[derive(Debug, Clone)]
+pub(crate) struct OfxStatement {
+    /// Statement currency (`CURDEF`).
+    pub(crate) currency: Option<String>,
+    /// Entries in statement order; zero-amount entries are dropped.
+    pub(crate) entries: Vec<OfxEntry>,
+}
+
+/// How a statement entry relates to the recorded transactions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ImportVerdict {
+    /// Already recorded as one transaction.
+    Matched,
+    /// Not recorded yet; staged for creation.
+    New,
+    /// Several recorded transactions could be this entry.
+    Ambiguous,
+}
+
+impl ImportVerdict {
+    /// Returns a human-readable label for responses.
+    pub(crate) const fn label(self) -> &'static str {
+        match self {
+            Self::Matched => "matched",
+            Self::New => "new",
+            Self::Ambiguous => "ambiguous",
+        }
+    }
+}
+
+/// Verdict for one statement entry with the transactions it refers to.
+#[derive(Debug, Clone)]
+pub(crate) struct EntryMatch {
+    /// How the entry relates to recorded transactions.
+    pub(crate) verdict: ImportVerdict,
+    /// The matched transaction, or the candidates of an ambiguous entry.
+    pub(crate) transaction_ids: Vec<String>,
+}
+
+/// Builds the error returned for malformed statements.
+fn invalid_ofx(message: &str) -> McpError {
+    McpError::invalid_params(format!("invalid OFX statement: {message}"), None)
+}
+
+/// Unescapes the XML entities used by OFX 2.
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// Returns the value of element `tag` in `block`: the text after its start
+/// tag up to the next tag, which works with and without end tags.
+fn element(block: &str, tag: &str) -> Option<String> {
+    let (_before, rest) = block.split_once(format!("<{tag}>").as_str())?;
+    let value = rest
+        .split_once('<')
+        .map_or(rest, |(value, _after)| value)
+        .trim();
+    (!value.is_empty()).then(|| unescape(value))
+}
+
+/// Parses one `STMTTRN` aggregate, or `None` for a zero amount.
+fn parse_entry(block: &str) -> Result<Option<OfxEntry>, McpError> {
+    let fitid = element(block, "FITID").ok_or_else(|| invalid_ofx("transaction without FITID"))?;
+    let posted = element(block, "DTPOSTED")
+        .ok_or_else(|| invalid_ofx(&format!("transaction {fitid} has no DTPOSTED")))?;
+    let date = posted
+        .get(..8)
+        .and_then(|digits| NaiveDate::parse_from_str(digits, "%Y%m%d").ok())
+        .ok_or_else(|| invalid_ofx(&format!("bad DTPOSTED '{posted}'")))?;
+    let raw_amount = element(block, "TRNAMT")
+        .ok_or_else(|| invalid_ofx(&format!("transaction {fitid} has no TRNAMT")))?;
+    let amount = raw_amount
+        .replace(',', ".")
+        .parse::<f64>()
+        .ok()
+        .filter(|value| value.is_finite())
+        .ok_or_else(|| invalid_ofx(&format!("bad TRNAMT '{raw_amount}'")))?;
+    if amount.abs() < AMOUNT_TOLERANCE {
+        return Ok(None);
+    }
+    Ok(Some(OfxEntry {
+        fitid,
+        date,
+        amount,
+        name: element(block, "NAME"),
+        memo: element(block, "MEMO"),
+    }))
+}
+
+/// Parses the transactions of an OFX bank or credit card statement.
+///
+/// # Errors
+///
+/// Returns an error if the text has no `STMTTRN` aggregates or one of them
+/// lacks a FITID, a valid posting date or a valid amount.
+pub(crate) fn parse_ofx(text: &str) -> Result<OfxStatement, McpError> {
+    let mut entries = Vec::new();
+    let mut blocks = 0_usize;
+    let mut rest = text;
+    while let Some((_before, after)) = rest.split_once("<STMTTRN>") {
+        let (block, remaining) = after
+            .split_once("</STMTTRN>")
+            .ok_or_else(|| invalid_ofx("unclosed STMTTRN"))?;
+        blocks = blocks.saturating_add(1);
+        if let Some(entry) = parse_entry(block)? {
+            entries.push(entry);
+        }
+        rest = remaining;
+    }
+    if blocks == 0 {
+        return Err(invalid_ofx("no transactions (STMTTRN) found"));
+    }
+    Ok(OfxStatement {
+        currency: element(text, "CURDEF"),
+        entries,
+    })
+}
+
+/// Returns whether `tx` carries `fitid` as its ID or as a bank ID.
+fn has_fitid(tx: &Transaction, fitid: &str) -> bool {
+    tx.id.as_inner() == fitid
+        || tx.income_bank_id.as_deref() == Some(fitid)
+        || tx.outcome_bank_id.as_deref() == Some(fitid)
+}
+
+/// Matches statement entries against the transactions on `account_id`.
+///
+/// FITID matches are found first; amount-and-date matches then only
+/// consider transactions no other entry has matched.
+pub(crate) fn match_entries(
+    entries: &[OfxEntry],
+    account_id: &str,
+    transactions: &[Transaction],
+    match_days: u64,
+) -> Vec<EntryMatch> {
+    let own: Vec<&Transaction> = transactions
+        .iter()
+        .filter(|tx| {
+            !tx.deleted
+                && (tx.income_account.as_inner() == account_id
+                    || tx.outcome_account.as_inner() == account_id)
+        })
+        .collect();
+    let exact: Vec<Option<&str>> = entries
+        .iter()
+        .map(|entry| {
+            own.iter()
+                .find(|tx| has_fitid(tx, &entry.fitid))
+                .map(|tx| tx.id.as_inner())
+        })
+        .collect();
+    let mut claimed: HashSet<&str> = exact.iter().flatten().copied().collect();
+    entries
+        .iter()
+        .zip(exact)
+        .map(|(entry, found)| {
+            if let Some(id) = found {
+                return EntryMatch {
+                    verdict: ImportVerdict::Matched,
+                    transaction_ids: vec![id.to_owned()],
+                };
+            }
+            let candidates: Vec<&str> = own
+                .iter()
+                .filter(|tx| {
+                    !claimed.contains(tx.id.as_inner())
+                        && (signed_amount(tx, account_id) - entry.amount).abs() < AMOUNT_TOLERANCE
+                        && tx
+                            .date
+                            .signed_duration_since(entry.date)
+                            .num_days()
+                            .unsigned_abs()
+                            <= match_days
+                })
+                .map(|tx| tx.id.as_inner())
+                .collect();
+            let verdict = match candidates.len() {
+                0 => ImportVerdict::New,
+                1 => {
+                    claimed.extend(candidates.first().copied());
+                    ImportVerdict::Matched
+                }
+                _ => ImportVerdict::Ambiguous,
+            };
+            EntryMatch {
+                verdict,
+                transaction_ids: candidates.into_iter().map(str::to_owned).collect(),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::indexing_slicing,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect and indexing for readability"
+)]
+mod tests {
+    use chrono::NaiveDate;
+    use zenmoney_rs::models::Transaction;
+
+    use super::{ImportVerdict, match_entries, parse_ofx};
+
+    /// OFX 1 statement in SGML form, without element end tags.
+    const SGML: &str = "OFXHEADER:100\nDATA:OFXSGML\n\n<OFX><BANKMSGSRSV1><STMTTRNRS><STMTRS>\
+        <CURDEF>RUB\n<BANKTRANLIST>\n\
+        <STMTTRN>\n<TRNTYPE>DEBIT\n<DTPOSTED>20240615120000[+3:MSK]\n<TRNAMT>-500.00\n\
+        <FITID>A1\n<NAME>Corner &amp; Co\n</STMTTRN>\n\
+        <STMTTRN>\n<TRNTYPE>CREDIT\n<DTPOSTED>20240616\n<TRNAMT>1000,00\n<FITID>A2\n\
+        <MEMO>Salary\n</STMTTRN>\n\
+        <STMTTRN>\n<TRNTYPE>DEBIT\n<DTPOSTED>20240617\n<TRNAMT>0.00\n<FITID>A3\n</STMTTRN>\n\
+        </BANKTRANLIST></STMTRS></STMTTRNRS></BANKMSGSRSV1></OFX>";
+
+    fn recorded() -> Vec<Transaction> {
+        let today = NaiveDate::from_ymd_opt(2024, 6, 15).expect("valid date");
+        crate::demo::dataset(today).transactions
+    }
+
+    #[test]
+    fn parses_sgml_statement() {
+        let statement = parse_ofx(SGML).expect("should parse");
+        assert_eq!(statement.currency.as_deref(), Some("RUB"));
+        assert_eq!(statement.entries.len(), 2);
+        assert_eq!(statement.entries[0].name.as_deref(), Some("Corner & Co"));
+        assert!((statement.entries[0].amount + 500.0).abs() < f64::EPSILON);
+        assert_eq!(statement.entries[1].date.to_string(), "2024-06-16");
+        assert!((statement.entries[1].amount - 1000.0).abs() < f64::EPSILON);
+        assert!(parse_ofx("<OFX></OFX>").is_err());
+        assert!(parse_ofx("<STMTTRN><TRNAMT>1<FITID>X</STMTTRN>").is_err());
+    }
+
+    #[test]
+    fn matches_by_fitid_then_amount_and_date() {
+        let transactions = recorded();
+        let tx = transactions
+            .iter()
+            .find(|tx| {
+                tx.outcome > 0.0 && tx.income_account.as_inner() == tx.outcome_account.as_inner()
+            })
+            .expect("demo expense");
+        let account = tx.outcome_account.as_inner();
+        let mut statement = parse_ofx(SGML).expect("should parse");
+        statement.entries[0].fitid = tx.id.as_inner().to_owned();
+        statement.entries[1].date = tx.date;
+        statement.entries[1].amount = -tx.outcome;
+        statement.entries[1].fitid = "unknown".to_owned();
+        let mut far = statement.entries[1].clone();
+        far.date = NaiveDate::from_ymd_opt(2000, 1, 1).expect("valid date");
+        statement.entries.push(far);
+
+        let matches = match_entries(&statement.entries, account, &transactions, 3);
+        assert_eq!(matches[0].verdict, ImportVerdict::Matched);
+        assert_eq!(
+            matches[0].transaction_ids,
+            vec![tx.id.as_inner().to_owned()]
+        );
+        assert!(
+            !matches[1]
+                .transaction_ids
+                .contains(&tx.id.as_inner().to_owned())
+        );
+        assert_eq!(matches[2].verdict, ImportVerdict::New);
+    }
+}
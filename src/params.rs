@@ -4,10 +4,10 @@
 //! so that `rmcp` can auto-generate JSON schemas for tool parameters.
 
 use schemars::JsonSchema;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 /// Type of financial transaction.
-#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub(crate) enum TransactionType {
     /// Money spent from an account.
@@ -19,7 +19,7 @@ pub(crate) enum TransactionType {
 }
 
 /// Sort direction for listing results.
-#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Copy, Default, Deserialize, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub(crate) enum SortDirection {
     /// Newest first.
@@ -29,12 +29,92 @@ pub(crate) enum SortDirection {
     Asc,
 }
 
+/// Dimension used to aggregate transactions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum GroupBy {
+    /// First category tag.
+    Tag,
+    /// Payee name.
+    Payee,
+    /// Primary account (outcome account for expenses and transfers).
+    Account,
+    /// Merchant.
+    Merchant,
+    /// Calendar month (`YYYY-MM`).
+    Month,
+    /// Day of the week.
+    Weekday,
+    /// Merchant category code (MCC).
+    Mcc,
+}
+
+/// Geographic circle used to find transactions recorded near a place.
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema)]
+pub(crate) struct NearFilter {
+    /// Latitude of the center, in degrees.
+    pub(crate) latitude: f64,
+    /// Longitude of the center, in degrees.
+    pub(crate) longitude: f64,
+    /// Search radius in kilometers.
+    pub(crate) radius_km: f64,
+}
+
 /// Parameters for the `list_accounts` tool.
-#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
 pub(crate) struct ListAccountsParams {
     /// If `true`, return only non-archived accounts.
     #[serde(default)]
     pub(crate) active_only: bool,
+    /// Base currency instrument ID; adds a `converted_balance` field to each account.
+    pub(crate) convert_to: Option<i32>,
+    /// Only return accounts owned by this user ID (see `list_users`).
+    pub(crate) user_id: Option<i64>,
+    /// Only return accounts in this currency instrument ID.
+    pub(crate) instrument_id: Option<i32>,
+    /// Order of the returned accounts; unset keeps the stored order.
+    pub(crate) sort_by: Option<AccountSort>,
+    /// If `true`, group accounts by type with a subtotal balance per group.
+    #[serde(default)]
+    pub(crate) group_by_type: bool,
+}
+
+/// Order of accounts returned by `list_accounts`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum AccountSort {
+    /// Highest balance first, compared in `convert_to` when given.
+    Balance,
+    /// Alphabetical by title.
+    Title,
+    /// Most recent transaction first; accounts never used come last.
+    LastActivity,
+}
+
+/// Parameters for the `get_account` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct GetAccountParams {
+    /// Account ID.
+    pub(crate) account_id: String,
+}
+
+/// Parameters for the `verify_balances` tool.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub(crate) struct VerifyBalancesParams {
+    /// Only check this account ID (default: every account with a balance).
+    pub(crate) account_id: Option<String>,
+    /// Largest difference still counted as a match (default 0.01).
+    pub(crate) tolerance: Option<f64>,
+}
+
+/// Parameters for the `list_companies` tool.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub(crate) struct ListCompaniesParams {
+    /// Case-insensitive substring matched against the company title.
+    pub(crate) query: Option<String>,
+    /// If `true`, include companies not linked to any account (the full bank catalog).
+    #[serde(default)]
+    pub(crate) include_unused: bool,
 }
 
 /// Parameters for the `list_transactions` tool.
@@ -67,6 +147,174 @@ pub(crate) struct ListTransactionsParams {
     pub(crate) transaction_type: Option<TransactionType>,
     /// Sort direction by date (default: desc = newest first).
     pub(crate) sort: Option<SortDirection>,
+    /// Base currency instrument ID; adds a `converted_amount` field to each transaction.
+    pub(crate) convert_to: Option<i32>,
+    /// Aggregate matching transactions by this dimension instead of returning rows.
+    pub(crate) group_by: Option<GroupBy>,
+    /// Treat child tags as their parent: `tag_id` also matches children and
+    /// `group_by=tag` rolls children up into the parent.
+    pub(crate) include_children: Option<bool>,
+    /// Only return transactions recorded by this user ID (see `list_users`).
+    pub(crate) user_id: Option<i64>,
+    /// If `true`, return only transactions not yet marked as viewed.
+    pub(crate) unviewed_only: Option<bool>,
+    /// If `true`, return only pending (on hold) transactions; if `false`, only settled ones.
+    pub(crate) hold: Option<bool>,
+    /// Only return transactions recorded within this circle (requires stored geolocation).
+    pub(crate) near: Option<NearFilter>,
+    /// Filter by merchant category code (MCC).
+    pub(crate) mcc: Option<i32>,
+    /// Only return transactions changed after this time: an RFC 3339
+    /// timestamp or a `YYYY-MM-DD` date (midnight UTC).
+    pub(crate) changed_since: Option<String>,
+    /// Only return transactions recorded against an occurrence of this
+    /// reminder ID (see `list_reminders`).
+    pub(crate) reminder_id: Option<String>,
+    /// If `true`, return only planned transactions; they are left out otherwise.
+    pub(crate) planned: Option<bool>,
+    /// Filter by origin: `manual` (entered by hand), `imported` (any
+    /// source), or a source name such as a bank import (case-insensitive).
+    pub(crate) source: Option<String>,
+}
+
+/// Transaction filters used by bulk tools to select transactions to change.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub(crate) struct TransactionSelection {
+    /// Start date (inclusive), format `YYYY-MM-DD`.
+    pub(crate) date_from: Option<String>,
+    /// End date (inclusive), format `YYYY-MM-DD`.
+    pub(crate) date_to: Option<String>,
+    /// Filter by account ID.
+    pub(crate) account_id: Option<String>,
+    /// Filter by tag ID.
+    pub(crate) tag_id: Option<String>,
+    /// Make `tag_id` also match child tags.
+    pub(crate) include_children: Option<bool>,
+    /// Filter by payee substring (case-insensitive).
+    pub(crate) payee: Option<String>,
+    /// Filter by merchant ID.
+    pub(crate) merchant_id: Option<String>,
+    /// Minimum amount (income or outcome >= this value).
+    pub(crate) min_amount: Option<f64>,
+    /// Maximum amount (income and outcome <= this value).
+    pub(crate) max_amount: Option<f64>,
+    /// If `true`, select only uncategorized transactions (no tags).
+    pub(crate) uncategorized: Option<bool>,
+    /// Filter by transaction type: expense, income, or transfer.
+    pub(crate) transaction_type: Option<TransactionType>,
+    /// Filter by the user who recorded the transaction.
+    pub(crate) user_id: Option<i64>,
+    /// Filter by origin: `manual`, `imported`, or a source name.
+    pub(crate) source: Option<String>,
+}
+
+impl TransactionSelection {
+    /// Returns `true` if no filter is set (the selection would match everything).
+    pub(crate) const fn is_empty(&self) -> bool {
+        self.date_from.is_none()
+            && self.date_to.is_none()
+            && self.account_id.is_none()
+            && self.tag_id.is_none()
+            && self.payee.is_none()
+            && self.merchant_id.is_none()
+            && self.min_amount.is_none()
+            && self.max_amount.is_none()
+            && self.uncategorized.is_none()
+            && self.transaction_type.is_none()
+            && self.user_id.is_none()
+            && self.source.is_none()
+    }
+
+    /// Converts the selection into `list_transactions` filters.
+    pub(crate) fn to_list_params(&self) -> ListTransactionsParams {
+        ListTransactionsParams {
+            date_from: self.date_from.clone(),
+            date_to: self.date_to.clone(),
+            account_id: self.account_id.clone(),
+            tag_id: self.tag_id.clone(),
+            include_children: self.include_children,
+            payee: self.payee.clone(),
+            merchant_id: self.merchant_id.clone(),
+            min_amount: self.min_amount,
+            max_amount: self.max_amount,
+            uncategorized: self.uncategorized,
+            transaction_type: self.transaction_type.clone(),
+            user_id: self.user_id,
+            source: self.source.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Parameters for the `move_transactions` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct MoveTransactionsParams {
+    /// Filters selecting the transactions to move (must be empty when `transaction_ids` is given).
+    #[serde(flatten)]
+    pub(crate) selection: TransactionSelection,
+    /// Explicit transaction IDs to move, instead of filters.
+    pub(crate) transaction_ids: Option<Vec<String>>,
+    /// Account ID to move the transactions to.
+    pub(crate) target_account_id: String,
+}
+
+/// Parameters for the `tag_transactions` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct TagTransactionsParams {
+    /// Transaction IDs to change.
+    pub(crate) transaction_ids: Vec<String>,
+    /// Tag IDs to add (already present tags are kept once).
+    pub(crate) add_tag_ids: Option<Vec<String>>,
+    /// Tag IDs to remove, applied after `add_tag_ids` (removal wins).
+    pub(crate) remove_tag_ids: Option<Vec<String>>,
+}
+
+/// Parameters for the `merge_transactions` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct MergeTransactionsParams {
+    /// Transaction to keep; give together with `remove_id`.
+    pub(crate) keep_id: Option<String>,
+    /// Duplicate to delete after copying the fields `keep_id` lacks.
+    pub(crate) remove_id: Option<String>,
+    /// Without IDs, only pair holds on this account.
+    pub(crate) account_id: Option<String>,
+    /// Without IDs, days after a hold its settlement may be dated (default 5).
+    pub(crate) max_days: Option<u32>,
+}
+
+/// Parameters for the `mark_viewed` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct MarkViewedParams {
+    /// Filters selecting the transactions to mark (must be empty when `transaction_ids` is given).
+    #[serde(flatten)]
+    pub(crate) selection: TransactionSelection,
+    /// Explicit transaction IDs to mark, instead of filters.
+    pub(crate) transaction_ids: Option<Vec<String>>,
+}
+
+/// Parameters for the `inbox` tool.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub(crate) struct InboxParams {
+    /// Maximum number of transactions to return, newest first (default 50, max 500).
+    pub(crate) limit: Option<usize>,
+}
+
+/// Parameters for the `mark_reviewed` tool.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub(crate) struct MarkReviewedParams {
+    /// New watermark: RFC 3339 timestamp or `YYYY-MM-DD` (default: now).
+    pub(crate) through: Option<String>,
+    /// Also mark unviewed transactions created up to the watermark as viewed
+    /// (default `true`).
+    pub(crate) mark_viewed: Option<bool>,
+}
+
+/// Parameters for the `set_default_account` tool.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub(crate) struct SetDefaultAccountParams {
+    /// Account used when `create_transaction` omits `account_id`; omit to
+    /// clear the saved default.
+    pub(crate) account_id: Option<String>,
 }
 
 /// Parameters for the `list_budgets` tool.
@@ -74,36 +322,680 @@ pub(crate) struct ListTransactionsParams {
 pub(crate) struct ListBudgetsParams {
     /// Filter by month, format `YYYY-MM`.
     pub(crate) month: Option<String>,
+    /// First month of a range (inclusive), format `YYYY-MM`; returns totals per month.
+    pub(crate) month_from: Option<String>,
+    /// Last month of a range (inclusive), format `YYYY-MM`; returns totals per month.
+    pub(crate) month_to: Option<String>,
+    /// Shortcut for the range from January to December of this year.
+    pub(crate) year: Option<i32>,
+}
+
+/// Parameters for the `budget_progress` tool.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub(crate) struct BudgetProgressParams {
+    /// Month to report, format `YYYY-MM` (default: current month).
+    pub(crate) month: Option<String>,
+    /// If `true`, carry unspent (or overspent) amounts of earlier budgeted
+    /// months forward into the month.
+    #[serde(default)]
+    pub(crate) rollover: bool,
+    /// Number of earlier months carried forward with `rollover` (default 12, max 60).
+    pub(crate) rollover_months: Option<u32>,
+    /// Base currency instrument ID for summing spending across currencies.
+    pub(crate) convert_to: Option<i32>,
+}
+
+/// Parameters for the `suggest_budgets` tool.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub(crate) struct SuggestBudgetsParams {
+    /// Month to propose budgets for, format `YYYY-MM` (default: current month).
+    pub(crate) month: Option<String>,
+    /// Number of full months before the current one to learn from (default 6, max 24).
+    pub(crate) months: Option<u32>,
+    /// Monthly statistic used as the budget amount (default: median).
+    #[serde(default)]
+    pub(crate) basis: BudgetBasis,
+    /// Round amounts up to a multiple of this, e.g. 100 (default: no rounding).
+    pub(crate) round_to: Option<f64>,
+}
+
+/// Monthly statistic `suggest_budgets` turns into a budget amount.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum BudgetBasis {
+    /// Middle monthly total; ignores one-off spikes.
+    #[default]
+    Median,
+    /// Mean monthly total.
+    Average,
+}
+
+/// Parameters for the `find_account` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct FindAccountParams {
+    /// Account title or part of it; typos are tolerated.
+    pub(crate) title: String,
+    /// Maximum number of matches (default 5, max 100).
+    pub(crate) limit: Option<usize>,
+}
+
+/// Parameters for the `find_tag` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct FindTagParams {
+    /// Tag title or part of it; typos are tolerated.
+    pub(crate) title: String,
+    /// Maximum number of matches (default 5, max 100).
+    pub(crate) limit: Option<usize>,
+}
+
+/// Parameters for the `find_instrument` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct FindInstrumentParams {
+    /// ISO currency code or part of the name, such as "USD" or "dollar".
+    pub(crate) query: String,
+    /// Maximum number of matches (default 5, max 100).
+    pub(crate) limit: Option<usize>,
+}
+
+/// Parameters for the `find_merchant` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct FindMerchantParams {
+    /// Merchant title or part of it; typos are tolerated.
+    pub(crate) title: String,
+    /// Maximum number of matches (default 5, max 100).
+    pub(crate) limit: Option<usize>,
+}
+
+/// Entity types matched by `search_entities`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SearchKind {
+    /// Accounts.
+    Account,
+    /// Category tags.
+    Tag,
+    /// Merchants.
+    Merchant,
+}
+
+impl SearchKind {
+    /// Every searchable entity type.
+    pub(crate) const ALL: [Self; 3] = [Self::Account, Self::Tag, Self::Merchant];
+}
+
+/// Parameters for the `search_entities` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct SearchEntitiesParams {
+    /// Text to match against titles; typos and partial names are fine.
+    pub(crate) query: String,
+    /// Entity types to search (default: account, tag and merchant).
+    pub(crate) kinds: Option<Vec<SearchKind>>,
+    /// Maximum number of candidates (default 10, max 100).
+    pub(crate) limit: Option<usize>,
+}
+
+/// Parameters for the `list_tags` tool.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub(crate) struct ListTagsParams {
+    /// Include archived tags (default: false).
+    pub(crate) include_archived: Option<bool>,
+    /// Only return direct children of this tag ID.
+    pub(crate) parent_tag_id: Option<String>,
+    /// Only return tags referenced by at least one transaction.
+    pub(crate) used_only: Option<bool>,
+}
+
+/// Parameters for the `tag_tree` tool.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub(crate) struct TagTreeParams {
+    /// Include archived tags (default: false).
+    pub(crate) include_archived: Option<bool>,
+}
+
+/// Parameters for the `get_tag` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct GetTagParams {
+    /// Tag ID.
+    pub(crate) tag_id: String,
+    /// Base currency instrument ID for summing spend across currencies.
+    pub(crate) convert_to: Option<i32>,
+}
+
+/// Parameters for the `suggest_category` tool.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub(crate) struct SuggestCategoryParams {
+    /// Payee name for category suggestion.
+    pub(crate) payee: Option<String>,
+    /// Comment text for category suggestion.
+    pub(crate) comment: Option<String>,
+}
+
+/// Parameters for the `list_instruments` tool.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub(crate) struct ListInstrumentsParams {
+    /// Only return instruments used by an account or transaction.
+    pub(crate) used_only: Option<bool>,
+}
+
+/// Parameters for the `get_instrument` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct GetInstrumentParams {
+    /// Instrument (currency) ID.
+    pub(crate) id: i32,
+}
+
+/// Entity types readable with `get_raw_entity`.
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum RawEntityKind {
+    /// A transaction.
+    Transaction,
+    /// An account.
+    Account,
+    /// A category tag.
+    Tag,
+    /// A recurring transaction reminder.
+    Reminder,
+}
+
+/// Parameters for the `get_raw_entity` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct GetRawEntityParams {
+    /// Entity type: transaction, account, tag or reminder.
+    pub(crate) kind: RawEntityKind,
+    /// Entity ID.
+    pub(crate) id: String,
+}
+
+/// Parameters for the `convert_amount` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct ConvertAmountParams {
+    /// Amount to convert.
+    pub(crate) amount: f64,
+    /// Source currency instrument ID.
+    pub(crate) from_instrument_id: i32,
+    /// Target currency instrument ID.
+    pub(crate) to_instrument_id: i32,
+}
+
+/// Entity whose spending the `top_payees` tool ranks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum PayeeDimension {
+    /// Free-text payee name.
+    #[default]
+    Payee,
+    /// Merchant entity.
+    Merchant,
+}
+
+/// Metric used to rank report entries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum RankBy {
+    /// Total amount spent.
+    #[default]
+    Amount,
+    /// Number of transactions.
+    Count,
+}
+
+/// Parameters for the `top_payees` tool.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub(crate) struct TopPayeesParams {
+    /// Start date (inclusive), format `YYYY-MM-DD`.
+    pub(crate) date_from: Option<String>,
+    /// End date (inclusive), format `YYYY-MM-DD`.
+    pub(crate) date_to: Option<String>,
+    /// Rank payees or merchants (default: payee).
+    pub(crate) by: Option<PayeeDimension>,
+    /// Rank by total amount or transaction count (default: amount).
+    pub(crate) rank_by: Option<RankBy>,
+    /// Number of entries to return (default 10, max 100).
+    pub(crate) limit: Option<usize>,
+    /// Base currency instrument ID for summing amounts across currencies.
+    pub(crate) convert_to: Option<i32>,
+    /// Roll child tags up into their parent in the category breakdown.
+    pub(crate) include_children: Option<bool>,
+}
+
+/// Parameters for the `compare_periods` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct ComparePeriodsParams {
+    /// Start of the base period (inclusive), format `YYYY-MM-DD`.
+    pub(crate) base_from: String,
+    /// End of the base period (inclusive), format `YYYY-MM-DD`.
+    pub(crate) base_to: String,
+    /// Start of the compared period (inclusive), format `YYYY-MM-DD`.
+    pub(crate) compare_from: String,
+    /// End of the compared period (inclusive), format `YYYY-MM-DD`.
+    pub(crate) compare_to: String,
+    /// Compare expense or income totals (default: expense).
+    pub(crate) transaction_type: Option<TransactionType>,
+    /// Base currency instrument ID for summing amounts across currencies.
+    pub(crate) convert_to: Option<i32>,
+    /// Roll child tags up into their parent category.
+    pub(crate) include_children: Option<bool>,
+}
+
+/// Time bucket size for time-series reports.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Granularity {
+    /// One point per day.
+    #[default]
+    Day,
+    /// One point per calendar month.
+    Month,
+}
+
+/// Parameters for the `account_balance_history` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct AccountBalanceHistoryParams {
+    /// Account ID.
+    pub(crate) account_id: String,
+    /// Start date (inclusive), format `YYYY-MM-DD`.
+    pub(crate) date_from: String,
+    /// End date (inclusive), format `YYYY-MM-DD` (default: today).
+    pub(crate) date_to: Option<String>,
+    /// End-of-day or end-of-month balances (default: day).
+    pub(crate) granularity: Option<Granularity>,
+}
+
+/// Parameters for the `detect_anomalies` tool.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub(crate) struct DetectAnomaliesParams {
+    /// Start of the range to scan (inclusive), format `YYYY-MM-DD` (default: 30 days ago).
+    pub(crate) date_from: Option<String>,
+    /// End of the range to scan (inclusive), format `YYYY-MM-DD`.
+    pub(crate) date_to: Option<String>,
+    /// Modified z-score above which a transaction is flagged (default 3.5).
+    pub(crate) threshold: Option<f64>,
+    /// Maximum number of flagged transactions to return (default 10, max 100).
+    pub(crate) limit: Option<usize>,
+    /// Base currency instrument ID for comparing amounts across currencies.
+    pub(crate) convert_to: Option<i32>,
+}
+
+/// Parameters for the `uncategorized_report` tool.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub(crate) struct UncategorizedReportParams {
+    /// Start date (inclusive), format `YYYY-MM-DD`.
+    pub(crate) date_from: Option<String>,
+    /// End date (inclusive), format `YYYY-MM-DD`.
+    pub(crate) date_to: Option<String>,
+    /// Maximum number of payees to return (default 10, max 100).
+    pub(crate) limit: Option<usize>,
+    /// Ask the suggest API for a tag per returned payee (default: true).
+    pub(crate) suggest: Option<bool>,
+    /// Base currency instrument ID for summing amounts across currencies.
+    pub(crate) convert_to: Option<i32>,
+}
+
+/// Parameters for the `debts_report` tool.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub(crate) struct DebtsReportParams {
+    /// Compute balances as of this date (inclusive), format `YYYY-MM-DD`.
+    pub(crate) date_to: Option<String>,
+    /// If `true`, also list counterparties whose balance is zero.
+    pub(crate) include_settled: Option<bool>,
+    /// Base currency instrument ID; balances are converted and totals are added.
+    pub(crate) convert_to: Option<i32>,
+}
+
+/// Parameters for the `loan_schedule` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct LoanScheduleParams {
+    /// ID of a `Loan` or `Deposit` account.
+    pub(crate) account_id: String,
+    /// Maximum number of upcoming periods to list (default: 12, max: 360).
+    pub(crate) limit: Option<usize>,
+}
+
+/// Credit card statement cycle relative to today.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum StatementCycle {
+    /// Cycle that contains today.
+    #[default]
+    Current,
+    /// Last closed cycle.
+    Previous,
+}
+
+/// Parameters for the `card_statement` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct CardStatementParams {
+    /// Credit card account ID.
+    pub(crate) account_id: String,
+    /// Day of month the statement closes (1-31; clamped to short months).
+    pub(crate) closing_day: u32,
+    /// Which cycle to summarize (default: current).
+    pub(crate) cycle: Option<StatementCycle>,
+}
+
+/// Parameters for the `allocate_envelope` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct AllocateEnvelopeParams {
+    /// Envelope name (case-insensitive); the envelope is created if it does not exist.
+    pub(crate) name: String,
+    /// Amount to add to the envelope; negative to take money back.
+    pub(crate) amount: f64,
+    /// Month to allocate for, format `YYYY-MM` (default: current month).
+    pub(crate) month: Option<String>,
+    /// Category tag IDs paid from the envelope. Required for a new envelope;
+    /// replaces the mapping of an existing one.
+    pub(crate) tag_ids: Option<Vec<String>>,
+    /// Move the amount from this envelope instead of allocating new income.
+    pub(crate) from_envelope: Option<String>,
+}
+
+/// Parameters for the `envelope_status` tool.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub(crate) struct EnvelopeStatusParams {
+    /// Month to report, format `YYYY-MM` (default: current month).
+    pub(crate) month: Option<String>,
+    /// Base currency instrument ID; spending and income are converted to it.
+    pub(crate) convert_to: Option<i32>,
+}
+
+/// Length of the period covered by a digest.
+#[derive(Debug, Clone, Copy, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum DigestPeriod {
+    /// Seven days ending on `date_to`.
+    #[default]
+    Week,
+    /// One month ending on `date_to`.
+    Month,
+}
+
+/// Parameters for the `digest` tool.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub(crate) struct DigestParams {
+    /// Period length (default: week).
+    pub(crate) period: Option<DigestPeriod>,
+    /// Last day of the period, format `YYYY-MM-DD` (default: today).
+    pub(crate) date_to: Option<String>,
+    /// Base currency instrument ID; amounts are converted to it.
+    pub(crate) convert_to: Option<i32>,
+}
+
+/// Arguments of a scheduled `export_csv` report.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub(crate) struct CsvReportParams {
+    /// Transaction filters.
+    #[serde(flatten)]
+    pub(crate) selection: TransactionSelection,
+    /// Only include the last this many days up to the run date; replaces
+    /// `date_from` and `date_to`.
+    pub(crate) days: Option<u32>,
+}
+
+/// Parameters for the `spending_patterns` tool.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub(crate) struct SpendingPatternsParams {
+    /// Start date (inclusive), format `YYYY-MM-DD` (default: 90 days before `date_to`).
+    pub(crate) date_from: Option<String>,
+    /// End date (inclusive), format `YYYY-MM-DD` (default: today).
+    pub(crate) date_to: Option<String>,
+    /// Include a per-category breakdown in every bucket.
+    pub(crate) by_category: Option<bool>,
+    /// Roll child tags up into their parent in the category breakdown.
+    pub(crate) include_children: Option<bool>,
+    /// Base currency instrument ID for summing amounts across currencies.
+    pub(crate) convert_to: Option<i32>,
+}
+
+/// Parameters for the `transfer_report` tool.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub(crate) struct TransferReportParams {
+    /// Start date (inclusive), format `YYYY-MM-DD`.
+    pub(crate) date_from: Option<String>,
+    /// End date (inclusive), format `YYYY-MM-DD`.
+    pub(crate) date_to: Option<String>,
+    /// Only include transfers to or from this account.
+    pub(crate) account_id: Option<String>,
+    /// Base currency instrument ID; amounts are converted to it.
+    pub(crate) convert_to: Option<i32>,
+}
+
+/// Parameters for the `savings_rate` tool.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub(crate) struct SavingsRateParams {
+    /// Start date (inclusive), format `YYYY-MM-DD`. Defaults to the start of
+    /// the month eleven months before `date_to`.
+    pub(crate) date_from: Option<String>,
+    /// End date (inclusive), format `YYYY-MM-DD`. Defaults to today.
+    pub(crate) date_to: Option<String>,
+    /// Count transfers into savings accounts as the saved amount.
+    pub(crate) savings_transfers: Option<bool>,
+    /// Base currency instrument ID; amounts are converted to it.
+    pub(crate) convert_to: Option<i32>,
+}
+
+/// Parameters for the `year_review` tool.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub(crate) struct YearReviewParams {
+    /// Calendar year to review. Defaults to the current year.
+    pub(crate) year: Option<i32>,
+    /// Base currency instrument ID; amounts are converted to it.
+    pub(crate) convert_to: Option<i32>,
+}
+
+/// Parameters for the `purge_local_data` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct PurgeLocalDataParams {
+    /// Must be exactly `PURGE LOCAL DATA`; guards against accidental calls.
+    pub(crate) confirm: String,
+}
+
+/// Parameters for the `backup_data` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct BackupDataParams {
+    /// File to write, or a directory to create a timestamped backup file in.
+    pub(crate) path: String,
+}
+
+/// Parameters for the `export_snapshot` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct ExportSnapshotParams {
+    /// File to write, or a directory to create a timestamped snapshot file in.
+    pub(crate) path: String,
+    /// Only include transactions on or after this date, format `YYYY-MM-DD`.
+    pub(crate) date_from: Option<String>,
+    /// Only include transactions on or before this date, format `YYYY-MM-DD`.
+    pub(crate) date_to: Option<String>,
+    /// Also include deleted transactions (default false).
+    pub(crate) include_deleted: Option<bool>,
+}
+
+/// Parameters for the `export_qif` and `export_ofx` tools.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct ExportParams {
+    /// Directory to write one file per account into (created if missing).
+    pub(crate) directory: String,
+    /// Filters selecting the transactions to export; none exports everything.
+    #[serde(flatten)]
+    pub(crate) selection: TransactionSelection,
+}
+
+/// Plain-text accounting syntax written by `export_ledger`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum LedgerDialect {
+    /// hledger journal, also readable by ledger-cli.
+    #[default]
+    Hledger,
+    /// Beancount ledger.
+    Beancount,
+}
+
+impl LedgerDialect {
+    /// Dialect name used in responses.
+    pub(crate) const fn name(self) -> &'static str {
+        match self {
+            Self::Hledger => "hledger",
+            Self::Beancount => "beancount",
+        }
+    }
+
+    /// Extension of a journal file in this dialect.
+    pub(crate) const fn extension(self) -> &'static str {
+        match self {
+            Self::Hledger => "journal",
+            Self::Beancount => "beancount",
+        }
+    }
+}
+
+/// Parameters for the `export_ledger` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct ExportLedgerParams {
+    /// File to write, or a directory to create `zenmoney.journal` or
+    /// `zenmoney.beancount` in.
+    pub(crate) path: String,
+    /// Journal syntax (default hledger).
+    #[serde(default)]
+    pub(crate) dialect: LedgerDialect,
+    /// Filters selecting the transactions to export; none exports everything.
+    #[serde(flatten)]
+    pub(crate) selection: TransactionSelection,
+}
+
+/// How `restore_data` applies a backup.
+#[derive(Debug, Clone, Copy, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum RestoreMode {
+    /// Write the backed-up entities, templates and envelopes into local storage.
+    #[default]
+    Local,
+    /// Stage changes reverting transactions on ZenMoney to their backed-up state.
+    RevertTransactions,
 }
 
-/// Parameters for the `find_account` tool.
+/// Parameters for the `restore_data` tool.
 #[derive(Debug, Clone, Deserialize, JsonSchema)]
-pub(crate) struct FindAccountParams {
-    /// Account title to search for (case-insensitive).
-    pub(crate) title: String,
+pub(crate) struct RestoreDataParams {
+    /// Backup file written by `backup_data`.
+    pub(crate) path: String,
+    /// local (default) or revert_transactions.
+    pub(crate) mode: Option<RestoreMode>,
 }
 
-/// Parameters for the `find_tag` tool.
+/// Entity type stored locally and synced from ZenMoney.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum EntityKind {
+    /// Transactions.
+    Transaction,
+    /// Accounts.
+    Account,
+    /// Category tags.
+    Tag,
+    /// Merchants.
+    Merchant,
+    /// Category budgets.
+    Budget,
+    /// Recurring transaction reminders.
+    Reminder,
+    /// Currency instruments.
+    Instrument,
+    /// Companies (banks).
+    Company,
+    /// Users of the account.
+    User,
+}
+
+impl EntityKind {
+    /// Every entity type.
+    pub(crate) const ALL: [Self; 9] = [
+        Self::Transaction,
+        Self::Account,
+        Self::Tag,
+        Self::Merchant,
+        Self::Budget,
+        Self::Reminder,
+        Self::Instrument,
+        Self::Company,
+        Self::User,
+    ];
+}
+
+/// Parameters for the `sync_entities` tool.
 #[derive(Debug, Clone, Deserialize, JsonSchema)]
-pub(crate) struct FindTagParams {
-    /// Tag title to search for (case-insensitive).
-    pub(crate) title: String,
+pub(crate) struct SyncEntitiesParams {
+    /// Entity types to report changes for, e.g. ["transaction", "account"].
+    pub(crate) entities: Vec<EntityKind>,
 }
 
-/// Parameters for the `suggest_category` tool.
+/// Parameters for the `pending_changes` tool.
 #[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
-pub(crate) struct SuggestCategoryParams {
-    /// Payee name for category suggestion.
-    pub(crate) payee: Option<String>,
-    /// Comment text for category suggestion.
-    pub(crate) comment: Option<String>,
+pub(crate) struct PendingChangesParams {
+    /// ID of a pending change to cancel; its local effect is undone.
+    pub(crate) cancel: Option<String>,
 }
 
-/// Parameters for the `get_instrument` tool.
+/// Group of transaction fields compared and merged by conflict resolution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TransactionField {
+    /// Transaction date.
+    Date,
+    /// Income and outcome amounts.
+    Amounts,
+    /// Income and outcome accounts with their currencies.
+    Accounts,
+    /// Category tags.
+    Tags,
+    /// Linked merchant.
+    Merchant,
+    /// Payee name.
+    Payee,
+    /// User comment.
+    Comment,
+    /// Deleted flag.
+    Deleted,
+}
+
+impl TransactionField {
+    /// Every field group.
+    pub(crate) const ALL: [Self; 8] = [
+        Self::Date,
+        Self::Amounts,
+        Self::Accounts,
+        Self::Tags,
+        Self::Merchant,
+        Self::Payee,
+        Self::Comment,
+        Self::Deleted,
+    ];
+}
+
+/// How `resolve_conflict` settles a conflict.
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ConflictResolution {
+    /// Send the local edit, overwriting the ZenMoney version.
+    KeepLocal,
+    /// Keep the ZenMoney version and drop the local edit.
+    KeepRemote,
+    /// Send the ZenMoney version with `local_fields` taken from the local edit.
+    Merge,
+}
+
+/// Parameters for the `resolve_conflict` tool.
 #[derive(Debug, Clone, Deserialize, JsonSchema)]
-pub(crate) struct GetInstrumentParams {
-    /// Instrument (currency) ID.
-    pub(crate) id: i32,
+pub(crate) struct ResolveConflictParams {
+    /// Conflict ID from `list_conflicts`.
+    pub(crate) id: String,
+    /// keep_local, keep_remote or merge.
+    pub(crate) resolution: ConflictResolution,
+    /// Field groups taken from the local edit when merging, e.g. ["tags", "comment"].
+    #[serde(default)]
+    pub(crate) local_fields: Vec<TransactionField>,
 }
 
 /// Parameters for the `create_transaction` tool.
@@ -114,13 +1006,20 @@ pub(crate) struct CreateTransactionParams {
     /// Transaction date, format `YYYY-MM-DD`.
     pub(crate) date: String,
     /// Primary account ID. For expense: source account. For income: destination account.
-    /// For transfer: source account.
+    /// For transfer: source account. Omit to use the default account (see
+    /// `set_default_account`).
+    #[serde(default)]
     pub(crate) account_id: String,
-    /// Transaction amount (positive number).
+    /// Transaction amount (positive number); strings such as `"1 234,56"` or
+    /// `"1,234.56"` are accepted.
+    #[serde(deserialize_with = "crate::amount::deserialize")]
+    #[schemars(with = "crate::amount::RawAmount")]
     pub(crate) amount: f64,
     /// Destination account ID (required for transfers).
     pub(crate) to_account_id: Option<String>,
     /// Destination amount for transfers with currency conversion (defaults to `amount`).
+    #[serde(default, deserialize_with = "crate::amount::deserialize_option")]
+    #[schemars(with = "Option<crate::amount::RawAmount>")]
     pub(crate) to_amount: Option<f64>,
     /// Override currency instrument ID for the primary account (auto-resolved from account if omitted).
     pub(crate) instrument_id: Option<i32>,
@@ -132,6 +1031,138 @@ pub(crate) struct CreateTransactionParams {
     pub(crate) payee: Option<String>,
     /// User comment.
     pub(crate) comment: Option<String>,
+    /// Merchant ID to link (see `list_merchants`).
+    pub(crate) merchant_id: Option<String>,
+    /// Latitude where the transaction happened (requires `longitude`).
+    pub(crate) latitude: Option<f64>,
+    /// Longitude where the transaction happened (requires `latitude`).
+    pub(crate) longitude: Option<f64>,
+    /// Merchant category code (MCC), 4 digits.
+    pub(crate) mcc: Option<i32>,
+    /// Keep a future-dated transaction locally as planned: not sent to
+    /// ZenMoney and left out of balances, reports and listings until
+    /// `convert_planned` (only `create_transaction` accepts it).
+    pub(crate) planned: Option<bool>,
+    /// Accept a date far from today or before the account's start date.
+    pub(crate) allow_out_of_range: Option<bool>,
+}
+
+/// Parameters for the `log_expense` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct LogExpenseParams {
+    /// Amount spent (positive number), in the default account's currency.
+    #[serde(deserialize_with = "crate::amount::deserialize")]
+    #[schemars(with = "crate::amount::RawAmount")]
+    pub(crate) amount: f64,
+    /// What it was for: a category title ("Groceries") or a payee ("coffee
+    /// shop"); a category is picked from it.
+    pub(crate) text: Option<String>,
+    /// Date, format `YYYY-MM-DD` (default: today).
+    pub(crate) date: Option<String>,
+    /// Accept a date far from today or before the account's start date.
+    pub(crate) allow_out_of_range: Option<bool>,
+}
+
+/// How often a `create_recurring` series repeats.
+#[derive(Debug, Clone, Copy, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum RecurrenceFrequency {
+    /// Every `every` weeks.
+    Weekly,
+    /// Every `every` months, on the same day of the month (or its last day).
+    Monthly,
+}
+
+/// What `create_recurring` creates.
+#[derive(Debug, Clone, Copy, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum RecurringMode {
+    /// Stage one future-dated transaction per occurrence.
+    #[default]
+    Transactions,
+    /// Create a ZenMoney reminder that plans the occurrences.
+    Reminder,
+}
+
+/// Parameters for the `create_recurring` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct CreateRecurringParams {
+    /// The transaction to repeat; its `date` is the first occurrence.
+    #[serde(flatten)]
+    pub(crate) transaction: CreateTransactionParams,
+    /// Weekly or monthly repetition.
+    pub(crate) frequency: RecurrenceFrequency,
+    /// Number of weeks or months between occurrences (default 1).
+    pub(crate) every: Option<u32>,
+    /// Number of occurrences, including the first.
+    pub(crate) count: Option<u32>,
+    /// Last possible occurrence date, format `YYYY-MM-DD`.
+    pub(crate) end_date: Option<String>,
+    /// Stage transactions (default) or create a reminder.
+    #[serde(default)]
+    pub(crate) mode: RecurringMode,
+}
+
+/// Parameters for the `convert_planned` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct ConvertPlannedParams {
+    /// Planned transaction IDs that actually happened.
+    pub(crate) transaction_ids: Vec<String>,
+}
+
+/// Parameters for the `save_template` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct SaveTemplateParams {
+    /// Template name (case-insensitive; saving an existing name replaces it).
+    pub(crate) name: String,
+    /// Type of transaction: expense, income, or transfer.
+    pub(crate) transaction_type: TransactionType,
+    /// Primary account ID.
+    pub(crate) account_id: String,
+    /// Destination account ID (required for transfers).
+    pub(crate) to_account_id: Option<String>,
+    /// Default amount; may be omitted and supplied on each use.
+    #[serde(default, deserialize_with = "crate::amount::deserialize_option")]
+    #[schemars(with = "Option<crate::amount::RawAmount>")]
+    pub(crate) amount: Option<f64>,
+    /// Category tag IDs.
+    pub(crate) tag_ids: Option<Vec<String>>,
+    /// Payee name.
+    pub(crate) payee: Option<String>,
+    /// User comment.
+    pub(crate) comment: Option<String>,
+    /// Merchant ID to link.
+    pub(crate) merchant_id: Option<String>,
+}
+
+/// Parameters for the `duplicate_transaction` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct DuplicateTransactionParams {
+    /// ID of the transaction to copy.
+    pub(crate) transaction_id: String,
+    /// Date of the copy, format `YYYY-MM-DD` (default: today).
+    pub(crate) date: Option<String>,
+    /// Amount override (positive).
+    #[serde(default, deserialize_with = "crate::amount::deserialize_option")]
+    #[schemars(with = "Option<crate::amount::RawAmount>")]
+    pub(crate) amount: Option<f64>,
+    /// Accept a date far from today or before the account's start date.
+    pub(crate) allow_out_of_range: Option<bool>,
+}
+
+/// Parameters for the `create_from_template` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct CreateFromTemplateParams {
+    /// Template name (case-insensitive).
+    pub(crate) name: String,
+    /// Transaction date, format `YYYY-MM-DD` (default: today).
+    pub(crate) date: Option<String>,
+    /// Amount override (required if the template has no amount).
+    #[serde(default, deserialize_with = "crate::amount::deserialize_option")]
+    #[schemars(with = "Option<crate::amount::RawAmount>")]
+    pub(crate) amount: Option<f64>,
+    /// Comment override.
+    pub(crate) comment: Option<String>,
 }
 
 /// Parameters for the `create_tag` and `create_category` tools.
@@ -157,6 +1188,91 @@ pub(crate) struct CreateTagParams {
     pub(crate) required: Option<bool>,
 }
 
+/// Parameters for the `create_merchant` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct CreateMerchantParams {
+    /// Merchant title.
+    pub(crate) title: String,
+}
+
+/// Parameters for the `rename_merchant` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct RenameMerchantParams {
+    /// Merchant ID to rename.
+    pub(crate) merchant_id: String,
+    /// New merchant title.
+    pub(crate) title: String,
+}
+
+/// Parameters for the `merge_merchants` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct MergeMerchantsParams {
+    /// Merchant IDs to merge away (deleted after merging).
+    pub(crate) source_merchant_ids: Vec<String>,
+    /// Merchant ID that absorbs the sources.
+    pub(crate) target_merchant_id: String,
+}
+
+/// Parameters for the `link_merchant` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct LinkMerchantParams {
+    /// Merchant ID to link.
+    pub(crate) merchant_id: String,
+    /// Case-insensitive substring matched against the transaction payee.
+    pub(crate) payee_pattern: String,
+    /// Re-link transactions already linked to a different merchant (default: false).
+    pub(crate) overwrite: Option<bool>,
+    /// Start date (inclusive), format `YYYY-MM-DD`.
+    pub(crate) date_from: Option<String>,
+    /// End date (inclusive), format `YYYY-MM-DD`.
+    pub(crate) date_to: Option<String>,
+}
+
+/// Parameters for the `reconcile_account` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct ReconcileAccountParams {
+    /// Account ID to reconcile.
+    pub(crate) account_id: String,
+    /// Real-world balance of the account, in the account currency.
+    pub(crate) actual_balance: f64,
+    /// Correction date, format `YYYY-MM-DD` (default: today).
+    pub(crate) date: Option<String>,
+    /// Comment for the correction transaction (default: "Balance correction").
+    pub(crate) comment: Option<String>,
+}
+
+/// Parameters for the `import_receipt_qr` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct ImportReceiptQrParams {
+    /// Raw QR string from the receipt, e.g. `t=20240115T1530&s=1234.56&fn=...&i=...&fp=...&n=1`.
+    pub(crate) qr: String,
+    /// Account ID the receipt was paid from (or refunded to).
+    pub(crate) account_id: String,
+    /// Category tag IDs.
+    pub(crate) tag_ids: Option<Vec<String>>,
+    /// Payee name.
+    pub(crate) payee: Option<String>,
+    /// Merchant ID to link (see `list_merchants`).
+    pub(crate) merchant_id: Option<String>,
+    /// User comment.
+    pub(crate) comment: Option<String>,
+}
+
+/// Parameters for the `import_ofx` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct ImportOfxParams {
+    /// OFX file to read; give either this or `content`.
+    pub(crate) path: Option<String>,
+    /// OFX statement text; give either this or `path`.
+    pub(crate) content: Option<String>,
+    /// Account ID the statement belongs to.
+    pub(crate) account_id: String,
+    /// Category tag IDs for the staged new transactions.
+    pub(crate) tag_ids: Option<Vec<String>>,
+    /// Days a recorded transaction's date may differ from the statement's (default 3).
+    pub(crate) match_days: Option<u32>,
+}
+
 /// Parameters for the `update_transaction` tool.
 #[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub(crate) struct UpdateTransactionParams {
@@ -165,19 +1281,39 @@ pub(crate) struct UpdateTransactionParams {
     /// New date, format `YYYY-MM-DD`.
     pub(crate) date: Option<String>,
     /// New amount (applied to the appropriate side based on transaction type).
+    #[serde(default, deserialize_with = "crate::amount::deserialize_option")]
+    #[schemars(with = "Option<crate::amount::RawAmount>")]
     pub(crate) amount: Option<f64>,
     /// New destination amount (for transfers with currency conversion).
+    #[serde(default, deserialize_with = "crate::amount::deserialize_option")]
+    #[schemars(with = "Option<crate::amount::RawAmount>")]
     pub(crate) to_amount: Option<f64>,
     /// New primary account ID.
     pub(crate) account_id: Option<String>,
     /// New destination account ID (for transfers).
     pub(crate) to_account_id: Option<String>,
-    /// New category tag IDs.
+    /// New category tag IDs, replacing the current list.
     pub(crate) tag_ids: Option<Vec<String>>,
+    /// Tag IDs to add, applied after `tag_ids` (already present tags are kept once).
+    pub(crate) add_tag_ids: Option<Vec<String>>,
+    /// Tag IDs to remove, applied last (wins over `tag_ids` and `add_tag_ids`).
+    pub(crate) remove_tag_ids: Option<Vec<String>>,
     /// New payee name (empty string clears it).
     pub(crate) payee: Option<String>,
     /// New comment (empty string clears it).
     pub(crate) comment: Option<String>,
+    /// New merchant ID (empty string clears it).
+    pub(crate) merchant_id: Option<String>,
+    /// Hold status: `true` marks it pending, `false` marks it settled.
+    pub(crate) hold: Option<bool>,
+    /// New latitude (requires `longitude`).
+    pub(crate) latitude: Option<f64>,
+    /// New longitude (requires `latitude`).
+    pub(crate) longitude: Option<f64>,
+    /// New merchant category code (0 clears it).
+    pub(crate) mcc: Option<i32>,
+    /// Accept a new date far from today or before the account's start date.
+    pub(crate) allow_out_of_range: Option<bool>,
 }
 
 /// A single operation within a bulk request.
@@ -206,6 +1342,86 @@ pub(crate) struct DeleteTransactionParams {
     pub(crate) id: String,
 }
 
+/// Parameters for the `list_deleted_transactions` tool.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub(crate) struct ListDeletedTransactionsParams {
+    /// Start date (inclusive), format `YYYY-MM-DD`.
+    pub(crate) date_from: Option<String>,
+    /// End date (inclusive), format `YYYY-MM-DD`.
+    pub(crate) date_to: Option<String>,
+    /// Maximum number of transactions to return (default 100, max 500).
+    pub(crate) limit: Option<usize>,
+    /// Number of transactions to skip (for pagination, default 0).
+    pub(crate) offset: Option<usize>,
+}
+
+/// Parameters for the `restore_transaction` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct RestoreTransactionParams {
+    /// ID of the deleted transaction to restore.
+    pub(crate) id: String,
+}
+
+/// State of a reminder marker, one planned occurrence of a reminder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum MarkerState {
+    /// Still expected; no transaction has been recorded for it.
+    Planned,
+    /// Done, usually with a transaction linked to it.
+    Processed,
+    /// Skipped.
+    Deleted,
+}
+
+/// Parameters for the `list_reminder_markers` tool.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub(crate) struct ListReminderMarkersParams {
+    /// Only markers of this reminder.
+    pub(crate) reminder_id: Option<String>,
+    /// Only markers in this state.
+    pub(crate) state: Option<MarkerState>,
+    /// Start date (inclusive), format `YYYY-MM-DD`.
+    pub(crate) date_from: Option<String>,
+    /// End date (inclusive), format `YYYY-MM-DD`.
+    pub(crate) date_to: Option<String>,
+    /// Maximum number of markers to return (default 100, max 500).
+    pub(crate) limit: Option<usize>,
+}
+
+/// Parameters for the `upcoming_bills` tool.
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub(crate) struct UpcomingBillsParams {
+    /// Month to report, format `YYYY-MM` (default: current month).
+    pub(crate) month: Option<String>,
+    /// Only bills paid from this account ID.
+    pub(crate) account_id: Option<String>,
+    /// Days before or after the due date within which an unlinked
+    /// transaction counts as the payment (default 5).
+    pub(crate) match_days: Option<u32>,
+}
+
+/// What `set_marker_state` does with a reminder marker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum MarkerAction {
+    /// Mark it processed without recording a transaction.
+    Processed,
+    /// Mark it deleted (skip this occurrence).
+    Deleted,
+    /// Record a transaction from it and mark it processed.
+    Convert,
+}
+
+/// Parameters for the `set_marker_state` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub(crate) struct SetMarkerStateParams {
+    /// Reminder marker ID.
+    pub(crate) marker_id: String,
+    /// What to do with the marker.
+    pub(crate) action: MarkerAction,
+}
+
 /// Parameters for the `execute_bulk_operations` tool.
 #[derive(Debug, Clone, Deserialize, JsonSchema)]
 pub(crate) struct ExecuteBulkParams {
@@ -221,10 +1437,15 @@ pub(crate) struct ExecuteBulkParams {
 )]
 mod tests {
     use super::{
-        BulkOperation, BulkOperationsParams, CreateTagParams, CreateTransactionParams,
-        DeleteTransactionParams, ExecuteBulkParams, FindAccountParams, FindTagParams,
-        GetInstrumentParams, ListAccountsParams, ListBudgetsParams, ListTransactionsParams,
-        SuggestCategoryParams, UpdateTransactionParams,
+        AccountBalanceHistoryParams, BulkOperation, BulkOperationsParams, ComparePeriodsParams,
+        ConvertAmountParams, CreateFromTemplateParams, CreateTagParams, CreateTransactionParams,
+        DeleteTransactionParams, DetectAnomaliesParams, ExecuteBulkParams, FindAccountParams,
+        FindTagParams, GetInstrumentParams, GetTagParams, Granularity, GroupBy, LinkMerchantParams,
+        ListAccountsParams, ListBudgetsParams, ListCompaniesParams, ListDeletedTransactionsParams,
+        ListTagsParams, ListTransactionsParams, MarkViewedParams, MergeMerchantsParams,
+        MoveTransactionsParams, PayeeDimension, RankBy, ReconcileAccountParams,
+        RenameMerchantParams, SaveTemplateParams, SuggestCategoryParams, TagTreeParams,
+        TopPayeesParams, TransactionSelection, UncategorizedReportParams, UpdateTransactionParams,
     };
 
     #[test]
@@ -261,6 +1482,206 @@ mod tests {
         assert!(params.uncategorized.is_none());
         assert!(params.transaction_type.is_none());
         assert!(params.sort.is_none());
+        assert!(params.convert_to.is_none());
+        assert!(params.group_by.is_none());
+        assert!(params.include_children.is_none());
+    }
+
+    #[test]
+    fn list_transactions_group_by() {
+        let json = r#"{"group_by": "weekday"}"#;
+        let params: ListTransactionsParams =
+            serde_json::from_str(json).expect("should deserialize group_by");
+        assert_eq!(params.group_by, Some(GroupBy::Weekday));
+    }
+
+    #[test]
+    fn list_transactions_near() {
+        let json = r#"{"near": {"latitude": 55.75, "longitude": 37.62, "radius_km": 3}}"#;
+        let params: ListTransactionsParams =
+            serde_json::from_str(json).expect("should deserialize near");
+        let near = params.near.expect("near should be set");
+        assert!((near.radius_km - 3.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn top_payees_params() {
+        let json = r#"{"by": "merchant", "rank_by": "count", "limit": 5}"#;
+        let params: TopPayeesParams =
+            serde_json::from_str(json).expect("should deserialize top_payees");
+        assert_eq!(params.by, Some(PayeeDimension::Merchant));
+        assert_eq!(params.rank_by, Some(RankBy::Count));
+        assert_eq!(params.limit, Some(5));
+        assert!(params.date_from.is_none());
+    }
+
+    #[test]
+    fn compare_periods_params() {
+        let json = r#"{
+            "base_from": "2024-05-01",
+            "base_to": "2024-05-31",
+            "compare_from": "2024-06-01",
+            "compare_to": "2024-06-30",
+            "transaction_type": "income"
+        }"#;
+        let params: ComparePeriodsParams =
+            serde_json::from_str(json).expect("should deserialize compare_periods");
+        assert_eq!(params.base_from, "2024-05-01");
+        assert_eq!(params.compare_to, "2024-06-30");
+        assert!(matches!(
+            params.transaction_type,
+            Some(super::TransactionType::Income)
+        ));
+        assert!(params.convert_to.is_none());
+    }
+
+    #[test]
+    fn account_balance_history_params() {
+        let json = r#"{"account_id": "acc-1", "date_from": "2024-01-01", "granularity": "month"}"#;
+        let params: AccountBalanceHistoryParams =
+            serde_json::from_str(json).expect("should deserialize balance history");
+        assert_eq!(params.account_id, "acc-1");
+        assert!(params.date_to.is_none());
+        assert_eq!(params.granularity, Some(Granularity::Month));
+    }
+
+    #[test]
+    fn detect_anomalies_defaults() {
+        let params: DetectAnomaliesParams =
+            serde_json::from_str("{}").expect("should deserialize empty");
+        assert!(params.date_from.is_none());
+        assert!(params.threshold.is_none());
+        assert!(params.limit.is_none());
+    }
+
+    #[test]
+    fn uncategorized_report_params() {
+        let json = r#"{"date_from": "2024-01-01", "suggest": false}"#;
+        let params: UncategorizedReportParams =
+            serde_json::from_str(json).expect("should deserialize uncategorized_report");
+        assert_eq!(params.date_from.as_deref(), Some("2024-01-01"));
+        assert_eq!(params.suggest, Some(false));
+        assert!(params.limit.is_none());
+    }
+
+    #[test]
+    fn get_tag_params() {
+        let json = r#"{"tag_id": "tag-001"}"#;
+        let params: GetTagParams = serde_json::from_str(json).expect("should deserialize");
+        assert_eq!(params.tag_id, "tag-001");
+        assert!(params.convert_to.is_none());
+    }
+
+    #[test]
+    fn list_tags_params() {
+        let json = r#"{"parent_tag_id": "tag-1", "used_only": true}"#;
+        let params: ListTagsParams = serde_json::from_str(json).expect("should deserialize");
+        assert!(params.include_archived.is_none());
+        assert_eq!(params.parent_tag_id.as_deref(), Some("tag-1"));
+        assert_eq!(params.used_only, Some(true));
+    }
+
+    #[test]
+    fn tag_tree_defaults_to_active() {
+        let params: TagTreeParams = serde_json::from_str("{}").expect("should deserialize empty");
+        assert!(params.include_archived.is_none());
+    }
+
+    #[test]
+    fn merge_merchants_params() {
+        let json = r#"{"source_merchant_ids": ["m-2", "m-3"], "target_merchant_id": "m-1"}"#;
+        let params: MergeMerchantsParams =
+            serde_json::from_str(json).expect("should deserialize merge_merchants");
+        assert_eq!(params.source_merchant_ids, vec!["m-2", "m-3"]);
+        assert_eq!(params.target_merchant_id, "m-1");
+    }
+
+    #[test]
+    fn list_companies_defaults() {
+        let params: ListCompaniesParams = serde_json::from_str("{}").expect("should deserialize");
+        assert!(params.query.is_none());
+        assert!(!params.include_unused);
+    }
+
+    #[test]
+    fn transaction_selection_empty_and_conversion() {
+        let empty: TransactionSelection = serde_json::from_str("{}").expect("should deserialize");
+        assert!(empty.is_empty());
+        let json = r#"{"payee": "Import", "date_from": "2024-01-01"}"#;
+        let selection: TransactionSelection =
+            serde_json::from_str(json).expect("should deserialize");
+        assert!(!selection.is_empty());
+        let list = selection.to_list_params();
+        assert_eq!(list.payee.as_deref(), Some("Import"));
+        assert!(list.limit.is_none());
+    }
+
+    #[test]
+    fn move_transactions_params_flatten_filters() {
+        let json = r#"{"account_id": "acc-old", "payee": "Shop", "target_account_id": "acc-new"}"#;
+        let params: MoveTransactionsParams =
+            serde_json::from_str(json).expect("should deserialize");
+        assert_eq!(params.selection.account_id.as_deref(), Some("acc-old"));
+        assert_eq!(params.target_account_id, "acc-new");
+        assert!(params.transaction_ids.is_none());
+    }
+
+    #[test]
+    fn list_deleted_transactions_params_defaults() {
+        let params: ListDeletedTransactionsParams =
+            serde_json::from_str("{}").expect("should deserialize");
+        assert!(params.date_from.is_none());
+        assert!(params.limit.is_none());
+        assert!(params.offset.is_none());
+    }
+
+    #[test]
+    fn mark_viewed_params_by_ids() {
+        let json = r#"{"transaction_ids": ["tx-1", "tx-2"]}"#;
+        let params: MarkViewedParams = serde_json::from_str(json).expect("should deserialize");
+        assert!(params.selection.is_empty());
+        assert_eq!(params.transaction_ids.map(|ids| ids.len()), Some(2));
+    }
+
+    #[test]
+    fn save_template_params() {
+        let json = r#"{"name": "Rent", "transaction_type": "expense", "account_id": "acc-1"}"#;
+        let params: SaveTemplateParams = serde_json::from_str(json).expect("should deserialize");
+        assert_eq!(params.name, "Rent");
+        assert!(params.amount.is_none());
+    }
+
+    #[test]
+    fn create_from_template_params() {
+        let json = r#"{"name": "Rent", "amount": 31000}"#;
+        let params: CreateFromTemplateParams =
+            serde_json::from_str(json).expect("should deserialize");
+        assert_eq!(params.amount, Some(31_000.0));
+        assert!(params.date.is_none());
+    }
+
+    #[test]
+    fn reconcile_account_params() {
+        let json = r#"{"account_id": "acc-1", "actual_balance": 1250.5}"#;
+        let params: ReconcileAccountParams =
+            serde_json::from_str(json).expect("should deserialize");
+        assert!((params.actual_balance - 1250.5).abs() < f64::EPSILON);
+        assert!(params.date.is_none());
+    }
+
+    #[test]
+    fn link_merchant_params() {
+        let json = r#"{"merchant_id": "m-1", "payee_pattern": "coffee"}"#;
+        let params: LinkMerchantParams = serde_json::from_str(json).expect("should deserialize");
+        assert_eq!(params.payee_pattern, "coffee");
+        assert!(params.overwrite.is_none());
+    }
+
+    #[test]
+    fn rename_merchant_requires_title() {
+        let json = r#"{"merchant_id": "m-1"}"#;
+        let result: Result<RenameMerchantParams, _> = serde_json::from_str(json);
+        assert!(result.is_err());
     }
 
     #[test]
@@ -347,6 +1768,16 @@ mod tests {
         assert_eq!(params.id, 42);
     }
 
+    #[test]
+    fn convert_amount_params() {
+        let json = r#"{"amount": 100.5, "from_instrument_id": 1, "to_instrument_id": 2}"#;
+        let params: ConvertAmountParams =
+            serde_json::from_str(json).expect("should deserialize conversion");
+        assert!((params.amount - 100.5).abs() < f64::EPSILON);
+        assert_eq!(params.from_instrument_id, 1);
+        assert_eq!(params.to_instrument_id, 2);
+    }
+
     #[test]
     fn create_transaction_expense() {
         let json = r#"{
@@ -387,6 +1818,30 @@ mod tests {
         assert!((params.to_amount.unwrap_or_default() - 15.0).abs() < f64::EPSILON);
     }
 
+    #[test]
+    fn create_transaction_localized_amounts() {
+        let json = r#"{
+            "transaction_type": "transfer",
+            "date": "2024-01-01",
+            "account_id": "acc-001",
+            "amount": "1 234,56",
+            "to_account_id": "acc-002",
+            "to_amount": "1,234.56"
+        }"#;
+        let params: CreateTransactionParams =
+            serde_json::from_str(json).expect("should deserialize localized amounts");
+        assert!((params.amount - 1234.56).abs() < f64::EPSILON);
+        assert!((params.to_amount.unwrap_or_default() - 1234.56).abs() < f64::EPSILON);
+
+        let update: UpdateTransactionParams =
+            serde_json::from_str(r#"{"id": "tx-1", "amount": "99,90"}"#)
+                .expect("should deserialize update");
+        assert!((update.amount.unwrap_or_default() - 99.9).abs() < f64::EPSILON);
+        assert!(update.to_amount.is_none());
+        let invalid = r#"{"id": "tx-1", "amount": "ten"}"#;
+        assert!(serde_json::from_str::<UpdateTransactionParams>(invalid).is_err());
+    }
+
     #[test]
     fn create_transaction_minimal() {
         let json = r#"{
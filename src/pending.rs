@@ -0,0 +1,178 @@
+//! Queue of transaction writes made while ZenMoney was unreachable.
+//!
+//! When pushing a created, updated or restored transaction fails because the
+//! API cannot be reached, the change is saved to local storage (so read tools
+//! see it) and queued here with a `pending` status instead of failing the
+//! call. The queue is flushed after the next successful sync, except for
+//! edits to transactions that sync showed were changed on ZenMoney meanwhile
+//! (see [`crate::conflicts`]), and can be inspected or cancelled with the
+//! `pending_changes` tool. It is stored as a JSON file next to the ZenMoney
+//! storage data, so queued writes survive a restart.
+
+use std::io;
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use zenmoney_rs::models::Transaction;
+
+use crate::persist::{Keyed, KeyedList};
+
+/// File name used for the queue inside the data directory.
+const PENDING_FILE: &str = "pending.json";
+
+/// A queued write of one or more transactions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct PendingChange {
+    /// Queue entry ID, used to cancel it.
+    pub(crate) id: String,
+    /// Tool whose write was queued.
+    pub(crate) tool: String,
+    /// When the write was queued.
+    pub(crate) queued_at: DateTime<Utc>,
+    /// Transactions to push.
+    pub(crate) transactions: Vec<Transaction>,
+    /// Local versions the write replaced, restored if it is cancelled;
+    /// transactions created by the write have none.
+    pub(crate) previous: Vec<Transaction>,
+}
+
+impl Keyed for PendingChange {
+    fn key(&self) -> &str {
+        &self.id
+    }
+}
+
+/// Pending writes in the order they were made, optionally backed by a JSON file.
+#[derive(Debug, Default)]
+pub struct PendingQueue {
+    /// Queued changes, oldest first.
+    changes: KeyedList<PendingChange>,
+}
+
+impl PendingQueue {
+    /// Opens the queue inside `dir`, loading any writes left from a previous run.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read or parsed.
+    #[inline]
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        Ok(Self {
+            changes: KeyedList::open(dir.join(PENDING_FILE))?,
+        })
+    }
+
+    /// Returns the queued changes, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned.
+    pub(crate) fn list(&self) -> io::Result<Vec<PendingChange>> {
+        self.changes.list()
+    }
+
+    /// Appends a change to the queue.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned or the file cannot be written.
+    pub(crate) fn push(&self, change: PendingChange) -> io::Result<()> {
+        self.changes.extend(vec![change])
+    }
+
+    /// Replaces the queued change with the same ID, keeping its place in the queue.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned or the file cannot be written.
+    pub(crate) fn update(&self, change: PendingChange) -> io::Result<bool> {
+        self.changes.replace(change)
+    }
+
+    /// Removes the change with `id`, returning it if it was queued.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned or the file cannot be written.
+    pub(crate) fn remove(&self, id: &str) -> io::Result<Option<PendingChange>> {
+        self.changes.remove(id)
+    }
+
+    /// Drops all queued changes, returning how many there were.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned or the file cannot be written.
+    pub(crate) fn clear(&self) -> io::Result<usize> {
+        self.changes.clear()
+    }
+}
+
+/// Returns whether `err` means the API could not be reached at all, as
+/// opposed to ZenMoney rejecting the request.
+///
+/// Connection failures surface as an I/O error somewhere in the source chain.
+#[inline]
+#[must_use]
+pub fn is_unreachable(err: &(dyn core::error::Error + 'static)) -> bool {
+    let mut current = Some(err);
+    while let Some(error) = current {
+        if error.is::<io::Error>() {
+            return true;
+        }
+        current = error.source();
+    }
+    false
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::indexing_slicing,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect and indexing for readability"
+)]
+mod tests {
+    use std::io;
+
+    use chrono::DateTime;
+
+    use super::{PendingChange, PendingQueue, is_unreachable};
+    use crate::persist::TempDir;
+
+    fn change(id: &str) -> PendingChange {
+        PendingChange {
+            id: id.to_owned(),
+            tool: "create_transaction".to_owned(),
+            queued_at: DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp"),
+            transactions: Vec::new(),
+            previous: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn persists_and_removes_changes() {
+        let dir = TempDir::new();
+        let queue = PendingQueue::open(&dir).expect("open empty");
+        queue.push(change("p-1")).expect("push");
+        queue.push(change("p-2")).expect("push");
+
+        let reopened = PendingQueue::open(&dir).expect("reopen");
+        assert_eq!(reopened.list().expect("lock").len(), 2);
+        let removed = reopened.remove("p-1").expect("remove").expect("queued");
+        assert_eq!(removed.id, "p-1");
+        assert!(reopened.remove("p-1").expect("remove").is_none());
+        assert_eq!(reopened.list().expect("lock")[0].id, "p-2");
+    }
+
+    #[test]
+    fn detects_connection_failures() {
+        let refused = io::Error::new(io::ErrorKind::ConnectionRefused, "refused");
+        assert!(is_unreachable(&refused));
+        let wrapped = io::Error::other(refused);
+        assert!(is_unreachable(&wrapped));
+        let rejected = core::fmt::Error;
+        assert!(!is_unreachable(&rejected));
+    }
+}
@@ -0,0 +1,219 @@
+//! Helpers for small JSON files kept next to the ZenMoney storage data.
+//!
+//! Local-only state such as templates and envelopes is stored as a JSON
+//! array per file and rewritten atomically on every change. [`KeyedList`]
+//! wraps that for ordered queues of items with IDs.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/// Normalizes a user-entered name into its case-insensitive lookup key.
+pub(crate) fn name_key(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+/// Converts a poisoned-lock error into an I/O error.
+pub(crate) fn lock_err<T>(err: &std::sync::PoisonError<T>) -> io::Error {
+    io::Error::other(format!("lock poisoned: {err}"))
+}
+
+/// Reads a JSON array from `path`, returning an empty list if the file is missing.
+///
+/// # Errors
+///
+/// Returns an error if the file exists but cannot be read or parsed.
+pub(crate) fn read_json_list<T: DeserializeOwned>(path: &Path) -> io::Result<Vec<T>> {
+    match std::fs::read(path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).map_err(io::Error::other),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err),
+    }
+}
+
+/// Writes `values` to `path` as a JSON array via a temporary file.
+///
+/// # Errors
+///
+/// Returns an error if the values cannot be serialized or the file cannot be written.
+pub(crate) fn write_json_list<T: Serialize>(path: &Path, values: &[&T]) -> io::Result<()> {
+    let json = serde_json::to_vec_pretty(values).map_err(io::Error::other)?;
+    let tmp_path = path.with_extension("json.tmp");
+    std::fs::write(&tmp_path, json)?;
+    std::fs::rename(&tmp_path, path)
+}
+
+/// Item of a [`KeyedList`], identified by a string ID.
+pub(crate) trait Keyed {
+    /// Returns the ID of the item.
+    fn key(&self) -> &str;
+}
+
+/// Items kept in insertion order, optionally backed by a JSON file.
+#[derive(Debug)]
+pub(crate) struct KeyedList<T> {
+    /// Path of the backing file (`None` keeps the items in memory only).
+    path: Option<PathBuf>,
+    /// Items, oldest first.
+    items: Mutex<Vec<T>>,
+}
+
+impl<T> Default for KeyedList<T> {
+    fn default() -> Self {
+        Self {
+            path: None,
+            items: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+impl<T: Keyed + Clone + Serialize + DeserializeOwned> KeyedList<T> {
+    /// Opens the list stored at `path`, loading any saved items.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read or parsed.
+    pub(crate) fn open(path: PathBuf) -> io::Result<Self> {
+        let items = read_json_list(&path)?;
+        Ok(Self {
+            path: Some(path),
+            items: Mutex::new(items),
+        })
+    }
+
+    /// Returns the items, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned.
+    pub(crate) fn list(&self) -> io::Result<Vec<T>> {
+        let items = self.items.lock().map_err(|err| lock_err(&err))?;
+        Ok(items.clone())
+    }
+
+    /// Appends `new_items` to the list.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned or the file cannot be written.
+    pub(crate) fn extend(&self, new_items: Vec<T>) -> io::Result<()> {
+        let mut items = self.items.lock().map_err(|err| lock_err(&err))?;
+        items.extend(new_items);
+        // Keep the lock while writing so concurrent changes cannot interleave.
+        let persisted = self.persist(&items);
+        drop(items);
+        persisted
+    }
+
+    /// Replaces the item with the same ID as `item`, returning whether it was found.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned or the file cannot be written.
+    pub(crate) fn replace(&self, item: T) -> io::Result<bool> {
+        let mut items = self.items.lock().map_err(|err| lock_err(&err))?;
+        let Some(slot) = items
+            .iter_mut()
+            .find(|existing| existing.key() == item.key())
+        else {
+            return Ok(false);
+        };
+        *slot = item;
+        let persisted = self.persist(&items);
+        drop(items);
+        persisted.map(|()| true)
+    }
+
+    /// Removes the item with `id`, returning it if it was present.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned or the file cannot be written.
+    pub(crate) fn remove(&self, id: &str) -> io::Result<Option<T>> {
+        let mut items = self.items.lock().map_err(|err| lock_err(&err))?;
+        let Some(position) = items.iter().position(|item| item.key() == id) else {
+            return Ok(None);
+        };
+        let removed = items.remove(position);
+        let persisted = self.persist(&items);
+        drop(items);
+        persisted.map(|()| Some(removed))
+    }
+
+    /// Removes all items, returning how many there were.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned or the file cannot be written.
+    pub(crate) fn clear(&self) -> io::Result<usize> {
+        let mut items = self.items.lock().map_err(|err| lock_err(&err))?;
+        let count = items.len();
+        items.clear();
+        let persisted = self.persist(&items);
+        drop(items);
+        persisted.map(|()| count)
+    }
+
+    /// Writes the items to the backing file, if any.
+    fn persist(&self, items: &[T]) -> io::Result<()> {
+        let Some(path) = self.path.as_ref() else {
+            return Ok(());
+        };
+        let values: Vec<&T> = items.iter().collect();
+        write_json_list(path, &values)
+    }
+}
+
+/// Scratch directory for tests, removed with its contents on drop so that a
+/// failed assertion does not leave it behind.
+#[cfg(test)]
+#[derive(Debug)]
+pub(crate) struct TempDir {
+    /// Path of the directory.
+    path: PathBuf,
+}
+
+#[cfg(test)]
+impl TempDir {
+    /// Creates a uniquely named directory under the system temp directory.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the directory cannot be created.
+    #[allow(
+        clippy::expect_used,
+        reason = "test helper fails the test on I/O errors"
+    )]
+    pub(crate) fn new() -> Self {
+        let path = std::env::temp_dir().join(format!("zenmoney-mcp-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&path).expect("create temp dir");
+        Self { path }
+    }
+}
+
+#[cfg(test)]
+impl core::ops::Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        // A test may have removed the directory itself.
+        let _removed = std::fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,155 @@
+//! Planned transactions: future-dated entries that do not count yet.
+//!
+//! ZenMoney has no flag for a transaction that is expected but has not
+//! happened, and every pushed transaction moves its account balance at
+//! once. Transactions created with `planned` are therefore kept in a JSON
+//! file next to the ZenMoney storage data instead of being pushed, so
+//! balances, reports and listings leave them out and a month's spending does
+//! not include bills that are only due later. `convert_planned` pushes them
+//! once the payment actually happened.
+
+use std::io;
+use std::path::Path;
+
+use zenmoney_rs::models::Transaction;
+
+use crate::persist::{Keyed, KeyedList};
+
+/// File name used for planned transactions inside the data directory.
+const PLANNED_FILE: &str = "planned.json";
+
+impl Keyed for Transaction {
+    fn key(&self) -> &str {
+        self.id.as_inner()
+    }
+}
+
+/// Planned transactions not yet pushed, optionally backed by a JSON file.
+#[derive(Debug, Default)]
+pub struct PlannedStore {
+    /// Planned transactions, in the order they were created.
+    transactions: KeyedList<Transaction>,
+}
+
+impl PlannedStore {
+    /// Opens the planned store inside `dir`, loading the saved transactions.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read or parsed.
+    #[inline]
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        Ok(Self {
+            transactions: KeyedList::open(dir.join(PLANNED_FILE))?,
+        })
+    }
+
+    /// Returns the planned transactions.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned.
+    pub(crate) fn list(&self) -> io::Result<Vec<Transaction>> {
+        self.transactions.list()
+    }
+
+    /// Keeps `transaction` as planned.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned or the file cannot be written.
+    pub(crate) fn add(&self, transaction: Transaction) -> io::Result<()> {
+        self.transactions.extend(vec![transaction])
+    }
+
+    /// Removes the planned transaction with `id`, returning it if it was planned.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned or the file cannot be written.
+    pub(crate) fn remove(&self, id: &str) -> io::Result<Option<Transaction>> {
+        self.transactions.remove(id)
+    }
+
+    /// Drops every planned transaction, returning how many there were.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned or the file cannot be written.
+    pub(crate) fn clear(&self) -> io::Result<usize> {
+        self.transactions.clear()
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::indexing_slicing,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect and indexing for readability"
+)]
+mod tests {
+    use chrono::{DateTime, NaiveDate};
+    use zenmoney_rs::models::{AccountId, InstrumentId, Transaction, TransactionId, UserId};
+
+    use super::PlannedStore;
+    use crate::persist::TempDir;
+
+    fn rent(id: &str) -> Transaction {
+        Transaction {
+            id: TransactionId::new(id.to_owned()),
+            changed: DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp"),
+            created: DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp"),
+            user: UserId::new(1),
+            deleted: false,
+            hold: None,
+            income_instrument: InstrumentId::new(1),
+            income_account: AccountId::new("acc-1".to_owned()),
+            income: 0.0,
+            outcome_instrument: InstrumentId::new(1),
+            outcome_account: AccountId::new("acc-1".to_owned()),
+            outcome: 30_000.0,
+            tag: None,
+            merchant: None,
+            payee: Some("Landlord".to_owned()),
+            original_payee: None,
+            comment: None,
+            date: NaiveDate::from_ymd_opt(2030, 1, 1).expect("valid date"),
+            mcc: None,
+            reminder_marker: None,
+            op_income: None,
+            op_income_instrument: None,
+            op_outcome: None,
+            op_outcome_instrument: None,
+            latitude: None,
+            longitude: None,
+            income_bank_id: None,
+            outcome_bank_id: None,
+            qr_code: None,
+            source: None,
+            viewed: None,
+        }
+    }
+
+    #[test]
+    fn planned_transactions_survive_reopen_until_removed() {
+        let dir = TempDir::new();
+        let store = PlannedStore::open(&dir).expect("open empty store");
+        store.add(rent("tx-rent")).expect("plan");
+        store.add(rent("tx-gas")).expect("plan");
+        let reopened = PlannedStore::open(&dir).expect("reopen store");
+        assert_eq!(reopened.list().expect("list").len(), 2);
+        let removed = reopened
+            .remove("tx-rent")
+            .expect("remove")
+            .expect("planned");
+        assert_eq!(removed.id.as_inner(), "tx-rent");
+        assert!(reopened.remove("tx-other").expect("remove").is_none());
+        let planned = PlannedStore::open(&dir)
+            .expect("reopen store")
+            .list()
+            .expect("list");
+        assert_eq!(planned.len(), 1);
+        assert_eq!(planned[0].id.as_inner(), "tx-gas");
+    }
+}
@@ -0,0 +1,219 @@
+//! MCP progress notifications and cancellation for long-running tools.
+//!
+//! Clients opt in by sending a progress token with the tool call. Tools that
+//! may take a while report items processed out of a total, or a periodic
+//! heartbeat when the work cannot be counted, so the call does not look hung.
+//! A client that cancels the call trips the request's cancellation token;
+//! tools check it between batches and stop before sending the next one.
+
+use core::future::Future;
+use core::pin::pin;
+use core::slice::Chunks;
+use core::time::Duration;
+
+use rmcp::model::{ProgressNotificationParam, ProgressToken};
+use rmcp::service::RequestContext;
+use rmcp::{Peer, RoleServer};
+use tokio_util::sync::CancellationToken;
+
+/// Time between heartbeat notifications while waiting on uncountable work.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Client connection and token that progress notifications are sent to.
+#[derive(Clone)]
+struct ProgressTarget {
+    /// Connection to the requesting client.
+    peer: Peer<RoleServer>,
+    /// Token the client attached to the request.
+    token: ProgressToken,
+}
+
+/// Reports progress of one tool call back to the client and tracks whether
+/// the client cancelled it.
+///
+/// Sends nothing when the request carried no progress token.
+#[derive(Clone, Default)]
+pub(crate) struct Progress {
+    /// Notification target, if the client asked for progress.
+    target: Option<ProgressTarget>,
+    /// Token cancelled when the client cancels the request.
+    cancel: CancellationToken,
+}
+
+impl core::fmt::Debug for Progress {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Progress")
+            .field("token", &self.target.as_ref().map(|target| &target.token))
+            .field("cancelled", &self.cancel.is_cancelled())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Converts a count to the floating-point progress value MCP expects.
+fn progress_value(count: usize) -> f64 {
+    u32::try_from(count).map_or(f64::from(u32::MAX), f64::from)
+}
+
+impl Progress {
+    /// Creates a reporter for the request described by `context`.
+    pub(crate) fn from_context(context: &RequestContext<RoleServer>) -> Self {
+        Self {
+            target: context
+                .meta
+                .get_progress_token()
+                .map(|token| ProgressTarget {
+                    peer: context.peer.clone(),
+                    token,
+                }),
+            cancel: context.ct.clone(),
+        }
+    }
+
+    /// Returns a reporter whose request is already cancelled.
+    #[cfg(test)]
+    pub(crate) fn cancelled() -> Self {
+        let progress = Self::default();
+        progress.cancel();
+        progress
+    }
+
+    /// Cancels the request, as a client cancellation notification would.
+    #[cfg(test)]
+    pub(crate) fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    /// Returns whether the client cancelled the request.
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// Sends `done` out of `total` items processed.
+    ///
+    /// Delivery failures are logged and otherwise ignored: progress is
+    /// informational and must not fail the operation itself.
+    pub(crate) async fn report(&self, done: usize, total: Option<usize>, message: String) {
+        let Some(target) = self.target.as_ref() else {
+            return;
+        };
+        let notification = ProgressNotificationParam {
+            progress_token: target.token.clone(),
+            progress: progress_value(done),
+            total: total.map(progress_value),
+            message: Some(message),
+        };
+        if let Err(err) = target.peer.notify_progress(notification).await {
+            tracing::debug!("failed to send progress notification: {err}");
+        }
+    }
+
+    /// Sends `batches` through `send`, reporting `done` out of `total`
+    /// changes after each one.
+    ///
+    /// Stops before the next batch once the request is cancelled. `done` is
+    /// advanced past every batch sent; returns whether all of them were.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error from `send`.
+    pub(crate) async fn send_batches<T, F, Fut, E>(
+        &self,
+        batches: Chunks<'_, T>,
+        total: usize,
+        done: &mut usize,
+        mut send: F,
+    ) -> Result<bool, E>
+    where
+        T: Clone,
+        F: FnMut(Vec<T>) -> Fut,
+        Fut: Future<Output = Result<(), E>>,
+    {
+        for batch in batches {
+            if self.is_cancelled() {
+                return Ok(false);
+            }
+            send(batch.to_vec()).await?;
+            *done = done.saturating_add(batch.len());
+            self.report(
+                *done,
+                Some(total),
+                format!("Saved {done} of {total} changes"),
+            )
+            .await;
+        }
+        Ok(true)
+    }
+
+    /// Awaits `task`, sending a heartbeat with the elapsed seconds every few seconds.
+    pub(crate) async fn heartbeat<F: Future>(&self, task: F, message: &str) -> F::Output {
+        if self.target.is_none() {
+            return task.await;
+        }
+        let mut task = pin!(task);
+        let mut ticks = tokio::time::interval(HEARTBEAT_INTERVAL);
+        let mut elapsed = Duration::ZERO;
+        loop {
+            tokio::select! {
+                output = &mut task => break output,
+                _instant = ticks.tick() => {
+                    let seconds = usize::try_from(elapsed.as_secs()).unwrap_or(usize::MAX);
+                    self.report(seconds, None, format!("{message} ({seconds}s elapsed)"))
+                        .await;
+                    elapsed = elapsed.saturating_add(HEARTBEAT_INTERVAL);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::missing_docs_in_private_items,
+    reason = "test code does not need docs"
+)]
+mod tests {
+    use super::{Progress, progress_value};
+
+    #[test]
+    fn progress_value_saturates() {
+        assert!((progress_value(42) - 42.0).abs() < f64::EPSILON);
+        assert!((progress_value(usize::MAX) - f64::from(u32::MAX)).abs() < f64::EPSILON);
+    }
+
+    #[tokio::test]
+    async fn without_token_reporting_is_a_no_op() {
+        let progress = Progress::default();
+        progress.report(1, Some(2), "halfway".to_owned()).await;
+        assert_eq!(progress.heartbeat(async { 7_i32 }, "waiting").await, 7_i32);
+        assert!(!progress.is_cancelled());
+        assert!(Progress::cancelled().is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn send_batches_stops_after_cancellation() {
+        let progress = Progress::default();
+        let items: Vec<usize> = (0..250).collect();
+        let mut sent = Vec::new();
+        let mut done = 0_usize;
+        let finished = progress
+            .send_batches(items.chunks(100), items.len(), &mut done, |batch| {
+                sent.push(batch.len());
+                progress.cancel();
+                async { Ok::<(), String>(()) }
+            })
+            .await;
+        assert_eq!(finished, Ok(false));
+        assert_eq!((done, sent), (100, vec![100]));
+
+        let mut done_all = 0_usize;
+        let finished_all = Progress::default()
+            .send_batches(
+                items.chunks(100),
+                items.len(),
+                &mut done_all,
+                |_batch| async { Ok::<(), String>(()) },
+            )
+            .await;
+        assert_eq!((finished_all, done_all), (Ok(true), 250));
+    }
+}
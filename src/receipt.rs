@@ -0,0 +1,181 @@
+//! Russian fiscal receipt (FNS) QR code parsing.
+//!
+//! Receipts printed by Russian cash registers carry a QR code with a
+//! URL-style query string such as
+//! `t=20240115T1530&s=1234.56&fn=9289000100123456&i=12345&fp=1234567890&n=1`.
+//! This module extracts the purchase time, sum and fiscal identifiers.
+
+use chrono::NaiveDateTime;
+use rmcp::ErrorData as McpError;
+
+use crate::params::TransactionType;
+
+/// Timestamp formats used by the `t` field, with and without seconds.
+const TIMESTAMP_FORMATS: [&str; 2] = ["%Y%m%dT%H%M%S", "%Y%m%dT%H%M"];
+
+/// Operation type encoded in the `n` field of a receipt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ReceiptOperation {
+    /// Sale to the customer (`n=1`).
+    Sale,
+    /// Refund of a sale (`n=2`).
+    SaleReturn,
+    /// Purchase from the customer (`n=3`).
+    Purchase,
+    /// Refund of a purchase (`n=4`).
+    PurchaseReturn,
+}
+
+impl ReceiptOperation {
+    /// Parses the numeric `n` field.
+    fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "1" => Some(Self::Sale),
+            "2" => Some(Self::SaleReturn),
+            "3" => Some(Self::Purchase),
+            "4" => Some(Self::PurchaseReturn),
+            _ => None,
+        }
+    }
+
+    /// Returns the transaction type from the customer's point of view.
+    pub(crate) const fn transaction_type(self) -> TransactionType {
+        match self {
+            Self::Sale | Self::PurchaseReturn => TransactionType::Expense,
+            Self::SaleReturn | Self::Purchase => TransactionType::Income,
+        }
+    }
+
+    /// Returns a human-readable label for responses.
+    pub(crate) const fn label(self) -> &'static str {
+        match self {
+            Self::Sale => "sale",
+            Self::SaleReturn => "sale_return",
+            Self::Purchase => "purchase",
+            Self::PurchaseReturn => "purchase_return",
+        }
+    }
+}
+
+/// Fields extracted from a fiscal receipt QR code.
+#[derive(Debug, Clone)]
+pub(crate) struct FiscalReceipt {
+    /// Local date and time of the purchase.
+    pub(crate) timestamp: NaiveDateTime,
+    /// Receipt total in rubles.
+    pub(crate) amount: f64,
+    /// Fiscal drive number (`fn`).
+    pub(crate) fiscal_drive: String,
+    /// Fiscal document number (`i`).
+    pub(crate) document_number: String,
+    /// Fiscal document sign (`fp`).
+    pub(crate) fiscal_sign: String,
+    /// Operation type (`n`).
+    pub(crate) operation: ReceiptOperation,
+}
+
+/// Builds the error returned for malformed receipt QR strings.
+fn invalid_qr(message: &str) -> McpError {
+    McpError::invalid_params(format!("invalid receipt QR code: {message}"), None)
+}
+
+/// Parses the query string embedded in a fiscal receipt QR code.
+///
+/// A leading URL (anything up to `?`) is ignored. The `t`, `s`, `fn`,
+/// `i` and `fp` fields are required; `n` defaults to a sale.
+pub(crate) fn parse_receipt_qr(qr: &str) -> Result<FiscalReceipt, McpError> {
+    let query = qr
+        .trim()
+        .rsplit_once('?')
+        .map_or(qr.trim(), |(_, query)| query);
+    let mut timestamp = None;
+    let mut amount = None;
+    let mut fiscal_drive = None;
+    let mut document_number = None;
+    let mut fiscal_sign = None;
+    let mut operation = ReceiptOperation::Sale;
+
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair
+            .split_once('=')
+            .ok_or_else(|| invalid_qr(&format!("field '{pair}' has no value")))?;
+        match key {
+            "t" => {
+                timestamp = TIMESTAMP_FORMATS
+                    .iter()
+                    .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok());
+                if timestamp.is_none() {
+                    return Err(invalid_qr(&format!("bad timestamp '{value}'")));
+                }
+            }
+            "s" => {
+                let sum = value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|sum| sum.is_finite() && *sum > 0.0)
+                    .ok_or_else(|| invalid_qr(&format!("bad sum '{value}'")))?;
+                amount = Some(sum);
+            }
+            "fn" => fiscal_drive = Some(value.to_owned()),
+            "i" => document_number = Some(value.to_owned()),
+            "fp" => fiscal_sign = Some(value.to_owned()),
+            "n" => {
+                operation = ReceiptOperation::from_code(value)
+                    .ok_or_else(|| invalid_qr(&format!("unknown operation type '{value}'")))?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(FiscalReceipt {
+        timestamp: timestamp.ok_or_else(|| invalid_qr("missing 't' (timestamp)"))?,
+        amount: amount.ok_or_else(|| invalid_qr("missing 's' (sum)"))?,
+        fiscal_drive: fiscal_drive.ok_or_else(|| invalid_qr("missing 'fn' (fiscal drive)"))?,
+        document_number: document_number
+            .ok_or_else(|| invalid_qr("missing 'i' (document number)"))?,
+        fiscal_sign: fiscal_sign.ok_or_else(|| invalid_qr("missing 'fp' (fiscal sign)"))?,
+        operation,
+    })
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect for readability"
+)]
+mod tests {
+    use super::{ReceiptOperation, parse_receipt_qr};
+
+    #[test]
+    fn parses_standard_qr() {
+        let receipt = parse_receipt_qr(
+            "t=20240115T1530&s=1234.56&fn=9289000100123456&i=12345&fp=1234567890&n=1",
+        )
+        .expect("should parse");
+        assert_eq!(receipt.timestamp.to_string(), "2024-01-15 15:30:00");
+        assert!((receipt.amount - 1234.56).abs() < f64::EPSILON);
+        assert_eq!(receipt.fiscal_drive, "9289000100123456");
+        assert_eq!(receipt.document_number, "12345");
+        assert_eq!(receipt.fiscal_sign, "1234567890");
+        assert_eq!(receipt.operation, ReceiptOperation::Sale);
+    }
+
+    #[test]
+    fn parses_url_with_seconds_and_refund() {
+        let receipt =
+            parse_receipt_qr("https://check.ofd.ru/?fn=1&i=2&fp=3&n=2&s=99.90&t=20231231T235959")
+                .expect("should parse");
+        assert_eq!(receipt.timestamp.to_string(), "2023-12-31 23:59:59");
+        assert_eq!(receipt.operation, ReceiptOperation::SaleReturn);
+    }
+
+    #[test]
+    fn rejects_malformed_qr() {
+        assert!(parse_receipt_qr("").is_err());
+        assert!(parse_receipt_qr("t=20240115T1530&s=10&fn=1&i=2").is_err());
+        assert!(parse_receipt_qr("t=bad&s=10&fn=1&i=2&fp=3").is_err());
+        assert!(parse_receipt_qr("t=20240115T1530&s=-5&fn=1&i=2&fp=3").is_err());
+        assert!(parse_receipt_qr("t=20240115T1530&s=10&fn=1&i=2&fp=3&n=9").is_err());
+    }
+}
@@ -0,0 +1,128 @@
+//! Record-and-replay of synced data for reproducible offline sessions.
+//!
+//! With `ZENMONEY_REPLAY=record` the binary syncs as usual, then saves
+//! everything the API returned to a replay file. With
+//! `ZENMONEY_REPLAY=replay` it skips the API entirely and serves that file
+//! from memory, so sessions and integration tests of the tool layer see the
+//! same data on every run. The file is the checksummed backup format; the
+//! client does not expose its HTTP exchanges, so the synced entities are
+//! recorded rather than raw responses.
+//!
+//! Only read tools are reproducible. Writes and syncs are not recorded, so
+//! like demo mode, replay refuses every tool that would call the API and
+//! says so in the error.
+
+use std::path::{Path, PathBuf};
+
+use zenmoney_rs::storage::FileStorage;
+
+/// Environment variable selecting record or replay mode.
+pub const REPLAY_ENV: &str = "ZENMONEY_REPLAY";
+
+/// Environment variable overriding the replay file location.
+pub const REPLAY_FILE_ENV: &str = "ZENMONEY_REPLAY_FILE";
+
+/// Why replay refuses sync and write tools, as told to the client.
+pub(crate) const READ_ONLY_NOTE: &str =
+    "a replay reproduces read tools only, since writes and syncs are not recorded";
+
+/// File name of the replay file inside the storage directory.
+const REPLAY_FILE_NAME: &str = "replay.json";
+
+/// What to do with the replay file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayMode {
+    /// Sync from the API, then save the synced data to the replay file.
+    Record,
+    /// Serve the replay file without contacting the API.
+    Replay,
+}
+
+impl ReplayMode {
+    /// Parses a setting value; an unset or blank value turns replay off.
+    ///
+    /// # Errors
+    ///
+    /// Returns a message naming the accepted values if `value` is unknown.
+    #[inline]
+    pub fn from_setting(value: Option<&str>) -> Result<Option<Self>, String> {
+        let setting = value
+            .map(|raw| raw.trim().to_lowercase())
+            .unwrap_or_default();
+        match setting.as_str() {
+            "" | "off" => Ok(None),
+            "record" => Ok(Some(Self::Record)),
+            "replay" => Ok(Some(Self::Replay)),
+            other => Err(format!(
+                "unsupported {REPLAY_ENV} value '{other}'; expected 'record' or 'replay'"
+            )),
+        }
+    }
+}
+
+/// Resolves the replay file: a non-blank override, or `replay.json` in `storage_dir`.
+#[inline]
+#[must_use]
+pub fn replay_path(custom: Option<String>, storage_dir: &Path) -> PathBuf {
+    custom
+        .filter(|path| !path.trim().is_empty())
+        .map_or_else(|| storage_dir.join(REPLAY_FILE_NAME), PathBuf::from)
+}
+
+/// Returns the replay file if `ZENMONEY_REPLAY` selects `mode`.
+///
+/// # Errors
+///
+/// Returns an error if `ZENMONEY_REPLAY` is not a supported mode, or the
+/// default storage directory cannot be located.
+#[inline]
+pub fn configured_file(mode: ReplayMode) -> Result<Option<PathBuf>, Box<dyn core::error::Error>> {
+    if ReplayMode::from_setting(std::env::var(REPLAY_ENV).ok().as_deref())? != Some(mode) {
+        return Ok(None);
+    }
+    let storage_dir = FileStorage::default_dir()?;
+    Ok(Some(replay_path(
+        std::env::var(REPLAY_FILE_ENV).ok(),
+        &storage_dir,
+    )))
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::missing_docs_in_private_items,
+    reason = "test code does not need docs"
+)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{ReplayMode, replay_path};
+
+    #[test]
+    fn parses_settings() {
+        assert_eq!(ReplayMode::from_setting(None), Ok(None));
+        assert_eq!(ReplayMode::from_setting(Some(" ")), Ok(None));
+        assert_eq!(
+            ReplayMode::from_setting(Some("Record")),
+            Ok(Some(ReplayMode::Record))
+        );
+        assert_eq!(
+            ReplayMode::from_setting(Some("replay")),
+            Ok(Some(ReplayMode::Replay))
+        );
+        assert!(ReplayMode::from_setting(Some("rewind")).is_err());
+    }
+
+    #[test]
+    fn replay_path_defaults_to_storage_dir() {
+        let dir = Path::new("/data/zenmoney");
+        assert_eq!(replay_path(None, dir), dir.join("replay.json"));
+        assert_eq!(
+            replay_path(Some(" ".to_owned()), dir),
+            dir.join("replay.json")
+        );
+        assert_eq!(
+            replay_path(Some("/tmp/session.json".to_owned()), dir),
+            PathBuf::from("/tmp/session.json")
+        );
+    }
+}
@@ -0,0 +1,432 @@
+//! MCP resource templates for dereferencing entities by URI.
+//!
+//! Clients can read `zenmoney://transaction/{id}`, `zenmoney://account/{id}`
+//! and `zenmoney://budget/{month}` to fetch an entity mentioned in the
+//! conversation without a tool call. Contents are the same enriched JSON the
+//! list tools return.
+//!
+//! After a sync or write the server compares the `changed` stamps of those
+//! entities with the previous snapshot, then sends `resources/list_changed`
+//! and `resources/updated` for changed URIs the client subscribed to.
+//!
+//! The `zenmoney://inbox` resource lists the transactions that arrived
+//! through syncs since the server started, such as fresh bank imports.
+//! When a sync brings new ones the inbox counts as changed and a log
+//! notification summarizes them, so assistants can offer to categorize
+//! them without being asked.
+
+extern crate alloc;
+
+use alloc::collections::BTreeSet;
+use alloc::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use rmcp::model::{
+    AnnotateAble as _, RawResource, RawResourceTemplate, Resource, ResourceTemplate,
+    ResourceUpdatedNotificationParam,
+};
+use rmcp::{Peer, RoleServer};
+
+/// URI scheme shared by all resources of this server.
+const SCHEME: &str = "zenmoney://";
+
+/// Template URI of a single transaction.
+const TRANSACTION_TEMPLATE: &str = "zenmoney://transaction/{id}";
+
+/// Template URI of a single account.
+const ACCOUNT_TEMPLATE: &str = "zenmoney://account/{id}";
+
+/// Template URI of the budgets of one month.
+const BUDGET_TEMPLATE: &str = "zenmoney://budget/{month}";
+
+/// URI of the transactions that arrived through syncs.
+pub(crate) const INBOX_URI: &str = "zenmoney://inbox";
+
+/// Most transactions kept in the inbox; older arrivals are dropped first.
+const MAX_INBOX_ENTRIES: usize = 200;
+
+/// Entity addressed by a resource URI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EntityUri<'uri> {
+    /// Transaction with the given ID.
+    Transaction(&'uri str),
+    /// Account with the given ID.
+    Account(&'uri str),
+    /// Budgets of the given month (`YYYY-MM`).
+    Budget(&'uri str),
+    /// Transactions that arrived through syncs.
+    Inbox,
+}
+
+impl<'uri> EntityUri<'uri> {
+    /// Parses a resource URI, returning `None` if it matches no template.
+    pub(crate) fn parse(uri: &'uri str) -> Option<Self> {
+        if uri == INBOX_URI {
+            return Some(Self::Inbox);
+        }
+        let (kind, key) = uri.strip_prefix(SCHEME)?.split_once('/')?;
+        if key.is_empty() || key.contains('/') {
+            return None;
+        }
+        match kind {
+            "transaction" => Some(Self::Transaction(key)),
+            "account" => Some(Self::Account(key)),
+            "budget" => Some(Self::Budget(key)),
+            _ => None,
+        }
+    }
+}
+
+/// Resource URI of a transaction.
+pub(crate) fn transaction_uri(id: &str) -> String {
+    format!("{SCHEME}transaction/{id}")
+}
+
+/// Resource URI of an account.
+pub(crate) fn account_uri(id: &str) -> String {
+    format!("{SCHEME}account/{id}")
+}
+
+/// Resource URI of the budgets of the month containing `date`.
+pub(crate) fn budget_uri(date: NaiveDate) -> String {
+    format!("{SCHEME}budget/{}", date.format("%Y-%m"))
+}
+
+/// Version of one resource, derived from the entities behind it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ResourceStamp {
+    /// Latest `changed` time of the entities, in milliseconds.
+    changed: i64,
+    /// Number of entities (budgets of a month share one resource).
+    count: usize,
+}
+
+/// Builds resource versions from `(uri, changed)` pairs, merging pairs with the same URI.
+pub(crate) fn stamp_resources<I>(entries: I) -> HashMap<String, ResourceStamp>
+where
+    I: IntoIterator<Item = (String, DateTime<Utc>)>,
+{
+    let mut stamps: HashMap<String, ResourceStamp> = HashMap::new();
+    for (uri, changed) in entries {
+        let stamp = stamps.entry(uri).or_insert(ResourceStamp {
+            changed: i64::MIN,
+            count: 0,
+        });
+        stamp.changed = stamp.changed.max(changed.timestamp_millis());
+        stamp.count = stamp.count.saturating_add(1);
+    }
+    stamps
+}
+
+/// Returns URIs added, removed or changed between two snapshots.
+fn changed_uris(
+    before: &HashMap<String, ResourceStamp>,
+    after: &HashMap<String, ResourceStamp>,
+) -> BTreeSet<String> {
+    let updated = after
+        .iter()
+        .filter(|entry| before.get(entry.0) != Some(entry.1))
+        .map(|entry| entry.0.clone());
+    let removed = before
+        .keys()
+        .filter(|uri| !after.contains_key(*uri))
+        .cloned();
+    updated.chain(removed).collect()
+}
+
+/// Returns the IDs of transactions present in `after` but not in `before`.
+fn added_transactions(
+    before: &HashMap<String, ResourceStamp>,
+    after: &HashMap<String, ResourceStamp>,
+) -> BTreeSet<String> {
+    let prefix = transaction_uri("");
+    after
+        .keys()
+        .filter(|uri| !before.contains_key(*uri))
+        .filter_map(|uri| uri.strip_prefix(prefix.as_str()))
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Client connection, subscriptions and last seen resource versions.
+#[derive(Default)]
+struct WatchState {
+    /// Connection to notify; `None` until the client initializes.
+    peer: Option<Peer<RoleServer>>,
+    /// URIs the client subscribed to.
+    subscribed: HashSet<String>,
+    /// Versions at the last refresh; `None` before the first snapshot.
+    stamps: Option<HashMap<String, ResourceStamp>>,
+    /// IDs of transactions that arrived through syncs, oldest first.
+    inbox: Vec<String>,
+}
+
+/// Notifications owed to the client after a refresh.
+struct ResourceChanges {
+    /// Connection to notify.
+    peer: Peer<RoleServer>,
+    /// Changed URIs, subscribed or not.
+    changed: BTreeSet<String>,
+    /// Changed URIs the client subscribed to.
+    updated: Vec<String>,
+    /// Number of transactions added to the inbox.
+    arrived: usize,
+}
+
+/// Tracks resource versions and subscriptions to announce changes.
+#[derive(Clone, Default)]
+pub(crate) struct ResourceWatch {
+    /// State shared by all request handlers.
+    state: Arc<Mutex<WatchState>>,
+}
+
+impl core::fmt::Debug for ResourceWatch {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ResourceWatch").finish_non_exhaustive()
+    }
+}
+
+impl ResourceWatch {
+    /// Sets the client connection that change notifications go to.
+    pub(crate) fn set_peer(&self, peer: Peer<RoleServer>) {
+        if let Ok(mut state) = self.state.lock() {
+            state.peer = Some(peer);
+        }
+    }
+
+    /// Returns whether a client is connected to receive notifications.
+    pub(crate) fn is_active(&self) -> bool {
+        self.state.lock().is_ok_and(|state| state.peer.is_some())
+    }
+
+    /// Adds `uri` to the subscriptions of `peer`.
+    pub(crate) fn subscribe(&self, peer: Peer<RoleServer>, uri: String) {
+        if let Ok(mut state) = self.state.lock() {
+            state.peer = Some(peer);
+            let _added = state.subscribed.insert(uri);
+        }
+    }
+
+    /// Removes `uri` from the subscriptions.
+    pub(crate) fn unsubscribe(&self, uri: &str) {
+        if let Ok(mut state) = self.state.lock() {
+            let _removed = state.subscribed.remove(uri);
+        }
+    }
+
+    /// Returns the IDs of the transactions in the inbox, oldest first.
+    pub(crate) fn inbox(&self) -> Vec<String> {
+        self.state
+            .lock()
+            .map(|state| state.inbox.clone())
+            .unwrap_or_default()
+    }
+
+    /// Stores `stamps` and returns the changes since the previous snapshot.
+    ///
+    /// The first snapshot only sets the baseline. After a sync, transactions
+    /// new since the previous snapshot go to the inbox.
+    fn record(
+        &self,
+        stamps: HashMap<String, ResourceStamp>,
+        synced: bool,
+    ) -> Option<ResourceChanges> {
+        let mut state = self.state.lock().ok()?;
+        let previous = state.stamps.replace(stamps)?;
+        let mut changed = changed_uris(&previous, state.stamps.as_ref()?);
+        let arrived = if synced {
+            added_transactions(&previous, state.stamps.as_ref()?)
+        } else {
+            BTreeSet::new()
+        };
+        if !arrived.is_empty() {
+            state.inbox.extend(arrived.iter().cloned());
+            let excess = state.inbox.len().saturating_sub(MAX_INBOX_ENTRIES);
+            drop(state.inbox.drain(..excess));
+            let _added = changed.insert(INBOX_URI.to_owned());
+        }
+        let updated = changed
+            .iter()
+            .filter(|uri| state.subscribed.contains(*uri))
+            .cloned()
+            .collect();
+        Some(ResourceChanges {
+            peer: state.peer.clone()?,
+            changed,
+            updated,
+            arrived: arrived.len(),
+        })
+    }
+
+    /// Stores `stamps` and notifies the client about resources that changed.
+    ///
+    /// `synced` marks snapshots taken right after a sync, whose new
+    /// transactions are announced and added to the inbox. Delivery failures
+    /// are logged and otherwise ignored: notifications are advisory and must
+    /// not fail the write that caused them.
+    pub(crate) async fn publish(&self, stamps: HashMap<String, ResourceStamp>, synced: bool) {
+        let Some(changes) = self.record(stamps, synced) else {
+            return;
+        };
+        if changes.changed.is_empty() {
+            return;
+        }
+        if changes.arrived > 0 {
+            tracing::info!(
+                arrived = changes.arrived,
+                "{} new transactions arrived; read {INBOX_URI} to review and categorize them",
+                changes.arrived
+            );
+        }
+        if let Err(err) = changes.peer.notify_resource_list_changed().await {
+            tracing::debug!("failed to send resource list change: {err}");
+        }
+        for uri in changes.updated {
+            let notification = ResourceUpdatedNotificationParam { uri };
+            if let Err(err) = changes.peer.notify_resource_updated(notification).await {
+                tracing::debug!("failed to send resource update: {err}");
+            }
+        }
+    }
+}
+
+/// Builds one JSON resource template.
+fn template(uri_template: &str, name: &str, description: &str) -> ResourceTemplate {
+    RawResourceTemplate {
+        uri_template: uri_template.to_owned(),
+        name: name.to_owned(),
+        title: None,
+        description: Some(description.to_owned()),
+        mime_type: Some("application/json".to_owned()),
+        icons: None,
+    }
+    .no_annotation()
+}
+
+/// Lists the fixed resources this server can read.
+pub(crate) fn resources() -> Vec<Resource> {
+    let mut inbox = RawResource::new(INBOX_URI, "inbox");
+    inbox.description = Some(
+        "Transactions that arrived through syncs since the server started, newest first, \
+         with an uncategorized count; updated when a sync brings new ones"
+            .to_owned(),
+    );
+    inbox.mime_type = Some("application/json".to_owned());
+    vec![inbox.no_annotation()]
+}
+
+/// Lists the resource templates this server can read.
+pub(crate) fn templates() -> Vec<ResourceTemplate> {
+    vec![
+        template(
+            TRANSACTION_TEMPLATE,
+            "transaction",
+            "Transaction by ID, with account, tag and merchant names resolved",
+        ),
+        template(
+            ACCOUNT_TEMPLATE,
+            "account",
+            "Account by ID, with its balance and currency",
+        ),
+        template(
+            BUDGET_TEMPLATE,
+            "budget",
+            "Category budgets of a month in YYYY-MM format",
+        ),
+    ]
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect for readability"
+)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::{DateTime, NaiveDate};
+
+    use super::{
+        EntityUri, ResourceWatch, account_uri, budget_uri, changed_uris, resources,
+        stamp_resources, templates, transaction_uri,
+    };
+
+    #[test]
+    fn parses_template_uris() {
+        assert_eq!(
+            EntityUri::parse("zenmoney://transaction/tx-1"),
+            Some(EntityUri::Transaction("tx-1"))
+        );
+        assert_eq!(
+            EntityUri::parse("zenmoney://account/acc-1"),
+            Some(EntityUri::Account("acc-1"))
+        );
+        assert_eq!(
+            EntityUri::parse("zenmoney://budget/2024-06"),
+            Some(EntityUri::Budget("2024-06"))
+        );
+        assert_eq!(EntityUri::parse("zenmoney://inbox"), Some(EntityUri::Inbox));
+        assert_eq!(EntityUri::parse("zenmoney://account/"), None);
+        assert_eq!(EntityUri::parse("zenmoney://tag/tag-1"), None);
+        assert_eq!(EntityUri::parse("file://account/acc-1"), None);
+        assert_eq!(EntityUri::parse("zenmoney://account/a/b"), None);
+    }
+
+    #[test]
+    fn lists_every_template() {
+        assert_eq!(templates().len(), 3);
+        assert_eq!(resources().len(), 1);
+    }
+
+    #[test]
+    fn synced_arrivals_fill_the_inbox() {
+        let time = DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp");
+        let stamps =
+            |ids: &[&str]| stamp_resources(ids.iter().map(|id| (transaction_uri(id), time)));
+        let watch = ResourceWatch::default();
+        assert!(watch.record(stamps(&["tx-1"]), true).is_none());
+        // Transactions created by write tools are not arrivals.
+        assert!(watch.record(stamps(&["tx-1", "tx-2"]), false).is_none());
+        assert!(
+            watch
+                .record(stamps(&["tx-1", "tx-2", "tx-3"]), true)
+                .is_none()
+        );
+        assert_eq!(watch.inbox(), vec!["tx-3".to_owned()]);
+    }
+
+    #[test]
+    fn changed_uris_cover_updates_additions_and_removals() {
+        let old_time = DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp");
+        let new_time = DateTime::from_timestamp(1_700_000_100, 0).expect("valid timestamp");
+        let june = NaiveDate::from_ymd_opt(2024, 6, 1).expect("valid date");
+        let before = stamp_resources(vec![
+            (account_uri("acc-1"), old_time),
+            (account_uri("acc-2"), old_time),
+            (transaction_uri("tx-1"), old_time),
+            (budget_uri(june), old_time),
+        ]);
+        let after = stamp_resources(vec![
+            (account_uri("acc-1"), new_time),
+            (transaction_uri("tx-1"), old_time),
+            (transaction_uri("tx-2"), old_time),
+            (budget_uri(june), old_time),
+            (budget_uri(june), old_time),
+        ]);
+        let changed: Vec<String> = changed_uris(&before, &after).into_iter().collect();
+        assert_eq!(
+            changed,
+            vec![
+                "zenmoney://account/acc-1".to_owned(),
+                "zenmoney://account/acc-2".to_owned(),
+                "zenmoney://budget/2024-06".to_owned(),
+                "zenmoney://transaction/tx-2".to_owned(),
+            ]
+        );
+        assert!(changed_uris(&after, &after).is_empty());
+        assert!(changed_uris(&HashMap::new(), &HashMap::new()).is_empty());
+    }
+}
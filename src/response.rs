@@ -1,19 +1,33 @@
 //! Enriched response structs for MCP tool outputs.
 //!
 //! These structs resolve entity IDs to human-readable names, making
-//! tool outputs more useful for LLM assistants.
+//! tool outputs more useful for LLM assistants. They derive
+//! [`schemars::JsonSchema`] so tools can advertise their output schema.
 
-use std::collections::HashMap;
+extern crate alloc;
 
+use alloc::collections::BTreeMap;
+use std::collections::{HashMap, HashSet};
+
+use chrono::Utc;
+use rmcp::ErrorData as McpError;
+use schemars::JsonSchema;
 use serde::Serialize;
 use zenmoney_rs::models::{
-    Account, Budget, Instrument, Interval, Merchant, Reminder, Tag, Transaction,
+    Account, Budget, Company, Instrument, Interval, Merchant, NaiveDate, Reminder, ReminderMarker,
+    ReminderMarkerState, Tag, TagId, Transaction, User, UserId,
 };
 
-use crate::server::account_type_label;
+use crate::date_guard::DateWindow;
+use crate::envelopes::Envelope;
+use crate::mcc::mcc_label_or_code;
+use crate::ofx::{EntryMatch, OfxEntry};
+use crate::params::{EntityKind, SearchKind, TransactionField, TransactionType};
+use crate::server::{account_type_label, classify_transaction};
+use crate::templates::TransactionTemplate;
 
 /// Formats an [`Interval`] variant as a human-readable string.
-fn interval_label(interval: Interval) -> String {
+pub(crate) fn interval_label(interval: Interval) -> String {
     match interval {
         Interval::Day => "Day",
         Interval::Week => "Week",
@@ -23,6 +37,16 @@ fn interval_label(interval: Interval) -> String {
     .to_owned()
 }
 
+/// Formats a [`ReminderMarkerState`] variant as a lowercase label.
+pub(crate) fn marker_state_label(state: ReminderMarkerState) -> String {
+    match state {
+        ReminderMarkerState::Planned => "planned",
+        ReminderMarkerState::Processed => "processed",
+        ReminderMarkerState::Deleted => "deleted",
+    }
+    .to_owned()
+}
+
 /// Lookup maps for resolving entity IDs to display names.
 #[derive(Debug, Default)]
 pub(crate) struct LookupMaps {
@@ -34,11 +58,199 @@ pub(crate) struct LookupMaps {
     instruments: HashMap<i32, String>,
     /// Account ID → instrument ID (for auto-resolving currency from account).
     account_instruments: HashMap<String, i32>,
+    /// Account ID → start date (only for accounts that have one).
+    account_starts: HashMap<String, NaiveDate>,
+    /// Instrument ID → exchange rate relative to the Russian ruble.
+    instrument_rates: HashMap<i32, f64>,
+    /// Merchant ID → title.
+    merchants: HashMap<String, String>,
+    /// Tag ID → parent tag ID (only for nested tags).
+    tag_parents: HashMap<String, String>,
+    /// Company ID → title.
+    companies: HashMap<i32, String>,
+    /// User ID → login.
+    users: HashMap<i64, String>,
+    /// IDs of accounts flagged as savings.
+    savings_accounts: HashSet<String>,
+    /// Reminder marker ID → reminder ID.
+    marker_reminders: HashMap<String, String>,
+    /// Reminder ID → (payee, interval label).
+    reminders: HashMap<String, (Option<String>, Option<String>)>,
+    /// Whether responses leave out the raw IDs next to resolved names.
+    hide_ids: bool,
+    /// Allowed transaction dates for creates and updates.
+    date_window: DateWindow,
+    /// Account ID used when a create omits `account_id`.
+    default_account: Option<String>,
 }
 
 impl LookupMaps {
+    /// Sets whether responses include raw IDs next to resolved names.
+    pub(crate) const fn with_ids(mut self, include: bool) -> Self {
+        self.hide_ids = !include;
+        self
+    }
+
+    /// Returns `id` for a response, unless raw IDs are turned off.
+    pub(crate) fn raw_id(&self, id: &str) -> Option<String> {
+        (!self.hide_ids).then(|| id.to_owned())
+    }
+
+    /// Sets the allowed transaction dates for creates and updates.
+    pub(crate) const fn with_date_window(mut self, window: DateWindow) -> Self {
+        self.date_window = window;
+        self
+    }
+
+    /// Sets the account used when a create omits `account_id`.
+    pub(crate) fn with_default_account(mut self, account_id: Option<String>) -> Self {
+        self.default_account = account_id;
+        self
+    }
+
+    /// Returns the account for a create that omitted `account_id`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an invalid-params error if no default account is set.
+    pub(crate) fn default_account(&self) -> Result<String, McpError> {
+        self.default_account.clone().ok_or_else(|| {
+            McpError::invalid_params(
+                "account_id is required: no default account is set (see set_default_account)",
+                None,
+            )
+        })
+    }
+
+    /// Checks the date of a created or updated transaction against the date
+    /// window and the start dates of its accounts, unless `allow_out_of_range`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an invalid-params error if the date is out of range.
+    pub(crate) fn check_date(
+        &self,
+        tx: &Transaction,
+        allow_out_of_range: Option<bool>,
+    ) -> Result<(), McpError> {
+        if allow_out_of_range == Some(true) {
+            return Ok(());
+        }
+        let account_start = [&tx.income_account, &tx.outcome_account]
+            .into_iter()
+            .filter_map(|account| self.account_starts.get(account.as_inner()).copied())
+            .max();
+        self.date_window
+            .check(tx.date, Utc::now().date_naive(), account_start)
+            .map_err(|message| McpError::invalid_params(message, None))
+    }
+
+    /// Returns tag IDs for a response, unless raw IDs are turned off.
+    pub(crate) fn raw_tag_ids(&self, tags: Option<&[TagId]>) -> Option<Vec<String>> {
+        (!self.hide_ids).then(|| {
+            tags.unwrap_or_default()
+                .iter()
+                .map(ToString::to_string)
+                .collect()
+        })
+    }
+
+    /// Adds merchant titles to the lookup maps.
+    pub(crate) fn with_merchants(mut self, merchants: &[Merchant]) -> Self {
+        for merchant in merchants {
+            let _existed = self
+                .merchants
+                .insert(merchant.id.to_string(), merchant.title.clone());
+        }
+        self
+    }
+
+    /// Adds company (bank) titles to the lookup maps.
+    pub(crate) fn with_companies(mut self, companies: &[Company]) -> Self {
+        for company in companies {
+            let _existed = self
+                .companies
+                .insert(company.id.into_inner(), company.title.clone());
+        }
+        self
+    }
+
+    /// Adds user logins to the lookup maps.
+    pub(crate) fn with_users(mut self, users: &[User]) -> Self {
+        for user in users {
+            if let Some(login) = user.login.as_ref() {
+                let _existed = self.users.insert(user.id.into_inner(), login.clone());
+            }
+        }
+        self
+    }
+
+    /// Adds reminders and their markers to the lookup maps.
+    pub(crate) fn with_reminders(
+        mut self,
+        reminders: &[Reminder],
+        markers: &[ReminderMarker],
+    ) -> Self {
+        for reminder in reminders {
+            let _existed = self.reminders.insert(
+                reminder.id.to_string(),
+                (
+                    reminder.payee.clone(),
+                    reminder.interval.map(interval_label),
+                ),
+            );
+        }
+        for marker in markers {
+            let _existed = self
+                .marker_reminders
+                .insert(marker.id.to_string(), marker.reminder.to_string());
+        }
+        self
+    }
+
+    /// Returns the ID of the reminder a marker belongs to, if the marker is known.
+    pub(crate) fn marker_reminder(&self, marker_id: &str) -> Option<&str> {
+        self.marker_reminders.get(marker_id).map(String::as_str)
+    }
+
+    /// Resolves a reminder marker to the reminder it is an occurrence of.
+    pub(crate) fn reminder_link(&self, marker_id: &str) -> ReminderLink {
+        let reminder_id = self.marker_reminder(marker_id);
+        let details = reminder_id.and_then(|id| self.reminders.get(id));
+        ReminderLink {
+            marker_id: marker_id.to_owned(),
+            reminder_id: reminder_id.map(str::to_owned),
+            payee: details.and_then(|entry| entry.0.clone()),
+            interval: details.and_then(|entry| entry.1.clone()),
+        }
+    }
+
+    /// Resolves a user ID to its login, only in multi-user workspaces.
+    ///
+    /// Single-user workspaces return `None` so responses are not cluttered
+    /// with the same owner on every row.
+    pub(crate) fn user_login(&self, id: i64) -> Option<String> {
+        if self.users.len() < 2 {
+            return None;
+        }
+        self.users.get(&id).cloned()
+    }
+
+    /// Resolves a company ID to its title.
+    pub(crate) fn company_name(&self, id: i32) -> String {
+        self.companies
+            .get(&id)
+            .cloned()
+            .unwrap_or_else(|| id.to_string())
+    }
+
+    /// Returns whether an account is flagged as savings.
+    pub(crate) fn is_savings(&self, account_id: &str) -> bool {
+        self.savings_accounts.contains(account_id)
+    }
+
     /// Resolves an account ID to its title.
-    fn account_name(&self, id: &str) -> String {
+    pub(crate) fn account_name(&self, id: &str) -> String {
         self.accounts
             .get(id)
             .cloned()
@@ -46,12 +258,71 @@ impl LookupMaps {
     }
 
     /// Resolves a tag ID to its title.
-    fn tag_name(&self, id: &str) -> String {
+    pub(crate) fn tag_name(&self, id: &str) -> String {
         self.tags.get(id).cloned().unwrap_or_else(|| id.to_owned())
     }
 
+    /// Returns a tag followed by its ancestors, nearest first.
+    fn tag_lineage<'id>(&'id self, id: &'id str) -> Vec<&'id str> {
+        let mut lineage = vec![id];
+        let mut current = id;
+        // Bounded walk so that a malformed cyclic hierarchy cannot loop forever.
+        for _ in 0..self.tag_parents.len() {
+            match self.tag_parents.get(current) {
+                Some(parent) => {
+                    current = parent;
+                    lineage.push(current);
+                }
+                None => break,
+            }
+        }
+        lineage
+    }
+
+    /// Returns the titles of a tag's ancestors and the tag itself, top-level first.
+    pub(crate) fn tag_path(&self, id: &str) -> Vec<String> {
+        self.tag_lineage(id)
+            .into_iter()
+            .rev()
+            .map(|tag| self.tag_name(tag))
+            .collect()
+    }
+
+    /// Returns the top-level ancestor of a tag (the tag itself if it has no parent).
+    pub(crate) fn root_tag<'id>(&'id self, id: &'id str) -> &'id str {
+        self.tag_lineage(id).last().copied().unwrap_or(id)
+    }
+
+    /// Returns `true` if the tag is `ancestor` or nested under it.
+    pub(crate) fn is_tag_within(&self, id: &str, ancestor: &str) -> bool {
+        self.tag_lineage(id).contains(&ancestor)
+    }
+
+    /// Resolves a merchant ID to its title.
+    pub(crate) fn merchant_name(&self, id: &str) -> String {
+        self.merchants
+            .get(id)
+            .cloned()
+            .unwrap_or_else(|| id.to_owned())
+    }
+
+    /// Returns `true` if the account ID is known.
+    pub(crate) fn has_account(&self, id: &str) -> bool {
+        self.accounts.contains_key(id)
+    }
+
+    /// Returns `true` if the tag ID is known.
+    pub(crate) fn has_tag(&self, id: &str) -> bool {
+        self.tags.contains_key(id)
+    }
+
+    /// Returns `true` if the merchant ID is known.
+    pub(crate) fn has_merchant(&self, id: &str) -> bool {
+        self.merchants.contains_key(id)
+    }
+
     /// Resolves an instrument ID to its currency symbol.
-    fn instrument_symbol(&self, id: i32) -> String {
+    pub(crate) fn instrument_symbol(&self, id: i32) -> String {
         self.instruments
             .get(&id)
             .cloned()
@@ -62,10 +333,24 @@ impl LookupMaps {
     pub(crate) fn account_instrument(&self, id: &str) -> Option<i32> {
         self.account_instruments.get(id).copied()
     }
+
+    /// Returns the stored exchange rate of an instrument, if known.
+    pub(crate) fn instrument_rate(&self, id: i32) -> Option<f64> {
+        self.instrument_rates.get(&id).copied()
+    }
+
+    /// Converts an amount between two instruments using the stored rates.
+    ///
+    /// Returns `None` if either instrument is unknown or the target rate is zero.
+    pub(crate) fn convert(&self, amount: f64, from: i32, to: i32) -> Option<f64> {
+        let from_rate = self.instrument_rate(from)?;
+        let to_rate = self.instrument_rate(to)?;
+        (to_rate > 0.0).then(|| amount * from_rate / to_rate)
+    }
 }
 
 /// Enriched account for display.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub(crate) struct AccountResponse {
     /// Account ID.
     id: String,
@@ -81,6 +366,15 @@ pub(crate) struct AccountResponse {
     archive: bool,
     /// Whether to include in total balance.
     in_balance: bool,
+    /// Bank or company the account belongs to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    company: Option<String>,
+    /// Owner login (multi-user workspaces only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    owner: Option<String>,
+    /// Balance converted to the requested base currency.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    converted_balance: Option<f64>,
 }
 
 impl AccountResponse {
@@ -98,35 +392,138 @@ impl AccountResponse {
             currency,
             archive: account.archive,
             in_balance: account.in_balance,
+            company: account.company.map(|id| maps.company_name(id.into_inner())),
+            owner: maps.user_login(account.user.into_inner()),
+            converted_balance: None,
         }
     }
+
+    /// Sets the balance converted to a base currency.
+    pub(crate) const fn with_converted_balance(mut self, converted: Option<f64>) -> Self {
+        self.converted_balance = converted;
+        self
+    }
+}
+
+/// Accounts of one type with their subtotal balance.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct AccountGroup {
+    /// Account type shared by the group.
+    pub(crate) account_type: String,
+    /// Number of accounts in the group.
+    pub(crate) count: usize,
+    /// Sum of balances, in `convert_to` if given; omitted when the accounts
+    /// hold several currencies and no `convert_to` was given.
+    pub(crate) subtotal: Option<f64>,
+    /// Currency symbol of the subtotal.
+    pub(crate) currency: Option<String>,
+    /// Currencies with no rate to `currency`, whose balances are left out
+    /// of the subtotal.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) unconverted: Vec<String>,
+    /// Accounts of the group, in the requested order.
+    pub(crate) accounts: Vec<AccountResponse>,
+}
+
+/// Result of `list_accounts` with `group_by_type` set.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct AccountGroupsResponse {
+    /// Groups ordered by account type.
+    pub(crate) groups: Vec<AccountGroup>,
+}
+
+/// Recurring reminder a transaction was recorded against.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct ReminderLink {
+    /// Reminder marker (planned occurrence) the transaction fulfils.
+    marker_id: String,
+    /// Reminder ID (absent if the marker is not stored locally).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reminder_id: Option<String>,
+    /// Payee of the reminder.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    payee: Option<String>,
+    /// Recurrence interval of the reminder.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    interval: Option<String>,
 }
 
 /// Enriched transaction for display.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub(crate) struct TransactionResponse {
     /// Transaction ID.
     id: String,
     /// Transaction date.
     date: String,
+    /// Last modification time (RFC 3339), usable as `changed_since` when polling.
+    changed: String,
     /// Income amount.
     income: f64,
     /// Income account name.
     income_account: String,
+    /// Income account ID, for passing back to other tools.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    income_account_id: Option<String>,
     /// Income currency symbol.
     income_currency: String,
     /// Outcome amount.
     outcome: f64,
     /// Outcome account name.
     outcome_account: String,
+    /// Outcome account ID, for passing back to other tools.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    outcome_account_id: Option<String>,
     /// Outcome currency symbol.
     outcome_currency: String,
     /// Category tag names.
     tags: Vec<String>,
+    /// Category tag IDs, in the same order as `tags`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tag_ids: Option<Vec<String>>,
     /// Payee name.
     payee: Option<String>,
+    /// Payee as the bank described it, for imported transactions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    original_payee: Option<String>,
+    /// Linked merchant name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    merchant: Option<String>,
+    /// Linked merchant ID.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    merchant_id: Option<String>,
+    /// Login of the user who recorded it (multi-user workspaces only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    owner: Option<String>,
     /// User comment.
     comment: Option<String>,
+    /// Whether the transaction has been reviewed (absent if unknown).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    viewed: Option<bool>,
+    /// Whether the transaction is a pending authorization (absent if unknown).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hold: Option<bool>,
+    /// Where the transaction came from, such as a bank import (absent if
+    /// entered by hand).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    /// Latitude where the transaction was recorded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    latitude: Option<f64>,
+    /// Longitude where the transaction was recorded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    longitude: Option<f64>,
+    /// Merchant category code (MCC).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mcc: Option<i32>,
+    /// Description of the merchant category code.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mcc_label: Option<String>,
+    /// Recurring reminder the transaction was recorded against.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reminder: Option<ReminderLink>,
+    /// Primary amount converted to the requested base currency.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    converted_amount: Option<f64>,
 }
 
 impl TransactionResponse {
@@ -142,21 +539,53 @@ impl TransactionResponse {
         Self {
             id: tx.id.to_string(),
             date: tx.date.to_string(),
+            changed: tx.changed.to_rfc3339(),
             income: tx.income,
             income_account: maps.account_name(tx.income_account.as_inner()),
+            income_account_id: maps.raw_id(tx.income_account.as_inner()),
             income_currency: maps.instrument_symbol(tx.income_instrument.into_inner()),
             outcome: tx.outcome,
             outcome_account: maps.account_name(tx.outcome_account.as_inner()),
+            outcome_account_id: maps.raw_id(tx.outcome_account.as_inner()),
             outcome_currency: maps.instrument_symbol(tx.outcome_instrument.into_inner()),
             tags,
+            tag_ids: maps.raw_tag_ids(tx.tag.as_deref()),
             payee: tx.payee.clone(),
+            original_payee: tx.original_payee.clone(),
+            merchant: tx
+                .merchant
+                .as_ref()
+                .map(|id| maps.merchant_name(id.as_inner())),
+            merchant_id: tx
+                .merchant
+                .as_ref()
+                .and_then(|id| maps.raw_id(id.as_inner())),
+            owner: maps.user_login(tx.user.into_inner()),
             comment: tx.comment.clone(),
+            viewed: tx.viewed,
+            hold: tx.hold,
+            source: tx.source.clone(),
+            latitude: tx.latitude,
+            longitude: tx.longitude,
+            mcc: tx.mcc,
+            mcc_label: tx.mcc.map(mcc_label_or_code),
+            reminder: tx
+                .reminder_marker
+                .as_ref()
+                .map(|id| maps.reminder_link(id.as_inner())),
+            converted_amount: None,
         }
     }
+
+    /// Sets the primary amount converted to a base currency.
+    pub(crate) const fn with_converted_amount(mut self, converted: Option<f64>) -> Self {
+        self.converted_amount = converted;
+        self
+    }
 }
 
 /// Paginated list of transactions.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub(crate) struct PaginatedTransactions {
     /// Transactions in the current page.
     pub(crate) items: Vec<TransactionResponse>,
@@ -166,10 +595,872 @@ pub(crate) struct PaginatedTransactions {
     pub(crate) offset: usize,
     /// Maximum items in this page.
     pub(crate) limit: usize,
+    /// Totals over all `total` matching transactions, not only this page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) summary: Option<TransactionSummary>,
+}
+
+/// Income and outcome in one currency.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct CurrencyTotals {
+    /// Currency symbol.
+    pub(crate) currency: String,
+    /// Total income.
+    pub(crate) income: f64,
+    /// Total outcome.
+    pub(crate) outcome: f64,
+    /// Income minus outcome.
+    pub(crate) net: f64,
+}
+
+/// Totals over every transaction matching a listing.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct TransactionSummary {
+    /// Number of matching transactions, transfers included.
+    pub(crate) count: usize,
+    /// Number of transfers, which are left out of the totals.
+    pub(crate) transfer_count: usize,
+    /// Currency of `income`, `outcome` and `net`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) currency: Option<String>,
+    /// Total income; absent when amounts are in several currencies and
+    /// neither `convert_to` nor a base currency is known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) income: Option<f64>,
+    /// Total outcome; absent like `income`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) outcome: Option<f64>,
+    /// Income minus outcome; absent like `income`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) net: Option<f64>,
+    /// Totals per currency, in native amounts.
+    pub(crate) by_currency: Vec<CurrencyTotals>,
+    /// Currencies with no rate to `currency`, whose amounts are counted at
+    /// face value in the totals.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) unconverted: Vec<String>,
+}
+
+/// Aggregated totals for one group of transactions.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct TransactionGroup {
+    /// Stable group key (entity ID, `YYYY-MM`, or ISO weekday number).
+    pub(crate) key: String,
+    /// Human-readable group label.
+    pub(crate) label: String,
+    /// Number of transactions in the group.
+    pub(crate) count: usize,
+    /// Sum of income amounts.
+    pub(crate) income: f64,
+    /// Sum of outcome amounts.
+    pub(crate) outcome: f64,
+}
+
+/// Transactions aggregated by a grouping key.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct GroupedTransactions {
+    /// Aggregated groups.
+    pub(crate) groups: Vec<TransactionGroup>,
+    /// Total number of transactions matching the filters.
+    pub(crate) total: usize,
+}
+
+/// Spending on one category within a report entry.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct CategorySpend {
+    /// Category tag name (or "Uncategorized").
+    pub(crate) category: String,
+    /// Number of transactions.
+    pub(crate) count: usize,
+    /// Total amount spent.
+    pub(crate) amount: f64,
+}
+
+/// One payee or merchant in the `top_payees` report.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct PayeeSpend {
+    /// Payee name or merchant ID.
+    pub(crate) key: String,
+    /// Display name.
+    pub(crate) label: String,
+    /// Number of expense transactions.
+    pub(crate) count: usize,
+    /// Total amount spent.
+    pub(crate) total: f64,
+    /// Spending broken down by category, largest first.
+    pub(crate) categories: Vec<CategorySpend>,
+}
+
+/// Result of the `top_payees` report.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct TopPayeesResponse {
+    /// Top entries in ranking order.
+    pub(crate) items: Vec<PayeeSpend>,
+    /// Total spend across all matching expenses.
+    pub(crate) total_spend: f64,
+    /// Number of matching expense transactions.
+    pub(crate) transaction_count: usize,
+}
+
+/// Per-category totals of two periods and their difference.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct CategoryDelta {
+    /// Category tag name (or "Uncategorized").
+    pub(crate) category: String,
+    /// Total in the base period.
+    pub(crate) base: f64,
+    /// Total in the compared period.
+    pub(crate) compare: f64,
+    /// `compare - base`.
+    pub(crate) delta: f64,
+    /// Delta as a percentage of the base total; absent when the base is zero.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) delta_percent: Option<f64>,
+}
+
+/// Result of the `compare_periods` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct ComparePeriodsResponse {
+    /// Per-category comparison, largest increase first.
+    pub(crate) categories: Vec<CategoryDelta>,
+    /// Total in the base period.
+    pub(crate) base_total: f64,
+    /// Total in the compared period.
+    pub(crate) compare_total: f64,
+    /// `compare_total - base_total`.
+    pub(crate) delta: f64,
+    /// Total delta as a percentage of the base total; absent when the base is zero.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) delta_percent: Option<f64>,
+    /// Categories with the largest increases (at most five).
+    pub(crate) largest_increases: Vec<CategoryDelta>,
+}
+
+/// Account balance at the end of a day.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct BalancePoint {
+    /// Date (`YYYY-MM-DD`).
+    pub(crate) date: String,
+    /// Balance at the end of that day.
+    pub(crate) balance: f64,
+}
+
+/// Result of the `account_balance_history` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct BalanceHistoryResponse {
+    /// Account ID.
+    pub(crate) account_id: String,
+    /// Account name.
+    pub(crate) account: String,
+    /// Currency symbol.
+    pub(crate) currency: String,
+    /// Current stored balance the history was reconstructed from.
+    pub(crate) current_balance: f64,
+    /// Balances in chronological order.
+    pub(crate) points: Vec<BalancePoint>,
+    /// Point with the lowest balance in the range.
+    pub(crate) lowest: Option<BalancePoint>,
+}
+
+/// Transaction flagged as unusually large.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct AnomalyResponse {
+    /// Enriched transaction preview.
+    pub(crate) transaction: TransactionResponse,
+    /// Highest modified z-score across the baselines it was compared with.
+    pub(crate) score: f64,
+    /// Human-readable reasons the transaction was flagged.
+    pub(crate) reasons: Vec<String>,
+}
+
+/// Result of the `detect_anomalies` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct AnomaliesResponse {
+    /// Flagged transactions, most unusual first.
+    pub(crate) items: Vec<AnomalyResponse>,
+    /// Number of expense transactions scanned.
+    pub(crate) scanned: usize,
+    /// Total number of flagged transactions (before `limit`).
+    pub(crate) flagged: usize,
+}
+
+/// Untagged transactions of one payee.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct UncategorizedPayee {
+    /// Payee name (or "(no payee)").
+    pub(crate) payee: String,
+    /// Number of untagged transactions.
+    pub(crate) count: usize,
+    /// Sum of income amounts.
+    pub(crate) income: f64,
+    /// Sum of outcome amounts.
+    pub(crate) outcome: f64,
+    /// Tag names suggested by ZenMoney for this payee.
+    pub(crate) suggested_tags: Vec<String>,
+}
+
+/// Result of the `uncategorized_report` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct UncategorizedReportResponse {
+    /// Payees with the largest untagged turnover first.
+    pub(crate) items: Vec<UncategorizedPayee>,
+    /// Number of untagged expense and income transactions.
+    pub(crate) total_transactions: usize,
+    /// Number of distinct payees (before `limit`).
+    pub(crate) total_payees: usize,
+}
+
+/// Debt balance with one counterparty.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct DebtCounterparty {
+    /// Counterparty (payee) name.
+    pub(crate) payee: String,
+    /// Currency symbol of the amounts.
+    pub(crate) currency: String,
+    /// Net balance: positive if they owe you, negative if you owe them.
+    pub(crate) balance: f64,
+    /// Total moved into the debt account (money you lent or repaid).
+    pub(crate) lent: f64,
+    /// Total moved out of the debt account (money you borrowed or got back).
+    pub(crate) received: f64,
+    /// Number of debt transactions with this counterparty.
+    pub(crate) count: usize,
+    /// Date of the latest debt transaction.
+    pub(crate) last_date: String,
+    /// `owes_you`, `you_owe`, or `settled`.
+    pub(crate) status: String,
+}
+
+/// Result of the `debts_report` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct DebtsReportResponse {
+    /// Counterparties with the largest outstanding balance first.
+    pub(crate) items: Vec<DebtCounterparty>,
+    /// Sum of positive balances (only with `convert_to`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) total_owed_to_you: Option<f64>,
+    /// Sum of negative balances as a positive number (only with `convert_to`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) total_you_owe: Option<f64>,
+}
+
+/// One period of a loan or deposit schedule.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct ScheduleRow {
+    /// Period end date, `YYYY-MM-DD`.
+    pub(crate) date: String,
+    /// Loan: total payment due. Deposit: interest paid out (0 when capitalized).
+    pub(crate) payment: f64,
+    /// Interest accrued over the period.
+    pub(crate) interest: f64,
+    /// Principal repaid (always 0 for deposits).
+    pub(crate) principal: f64,
+    /// Outstanding loan principal or deposit balance after the period.
+    pub(crate) balance: f64,
+}
+
+/// Result of the `loan_schedule` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct LoanScheduleResponse {
+    /// Account ID.
+    pub(crate) account_id: String,
+    /// Account name.
+    pub(crate) account: String,
+    /// Account type (`Loan` or `Deposit`).
+    pub(crate) account_type: String,
+    /// Currency symbol.
+    pub(crate) currency: String,
+    /// Outstanding principal (loan) or current balance (deposit).
+    pub(crate) balance: f64,
+    /// Annual interest rate in percent.
+    pub(crate) percent: f64,
+    /// Whether interest is capitalized.
+    pub(crate) capitalization: bool,
+    /// Date the loan or deposit was opened.
+    pub(crate) start_date: String,
+    /// Date the term ends.
+    pub(crate) end_date: String,
+    /// Payoff period, e.g. `1 Month`; absent when paid at the end of the term.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) payoff_period: Option<String>,
+    /// Date of the next payment or interest accrual.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) next_payment_date: Option<String>,
+    /// Amount of the next payment (loan) or interest (deposit).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) next_payment: Option<f64>,
+    /// Number of periods left until the end of the term.
+    pub(crate) remaining_periods: usize,
+    /// Interest accrued over all remaining periods.
+    pub(crate) total_interest: f64,
+    /// Deposit balance at maturity (deposits only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) maturity_amount: Option<f64>,
+    /// Upcoming periods, truncated to the requested limit.
+    pub(crate) schedule: Vec<ScheduleRow>,
+}
+
+/// Result of the `card_statement` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct CardStatementResponse {
+    /// Account ID.
+    pub(crate) account_id: String,
+    /// Account name.
+    pub(crate) account: String,
+    /// Currency symbol.
+    pub(crate) currency: String,
+    /// First day of the statement cycle.
+    pub(crate) cycle_start: String,
+    /// Statement closing date (last day of the cycle).
+    pub(crate) cycle_end: String,
+    /// Days left until the statement closes (current cycle only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) days_left: Option<i64>,
+    /// Money charged to the card in the cycle (purchases, withdrawals, transfers out).
+    pub(crate) spent: f64,
+    /// Money credited to the card in the cycle (payments, refunds, transfers in).
+    pub(crate) credited: f64,
+    /// Number of card transactions in the cycle.
+    pub(crate) transaction_count: usize,
+    /// Categories with the largest spend in the cycle.
+    pub(crate) top_categories: Vec<TransactionGroup>,
+    /// Current account balance (negative when money is owed).
+    pub(crate) balance: f64,
+    /// Credit limit of the card.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) credit_limit: Option<f64>,
+    /// Credit still available: limit plus balance.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) available_credit: Option<f64>,
+    /// Share of the limit in use, in percent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) utilization_percent: Option<f64>,
+}
+
+/// Label and amount pair used in compact summaries.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct DigestAmount {
+    /// Category or other label.
+    pub(crate) label: String,
+    /// Amount.
+    pub(crate) amount: f64,
+}
+
+/// Transaction line in a digest.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct DigestTransaction {
+    /// Transaction date.
+    pub(crate) date: String,
+    /// Payee, merchant, or category.
+    pub(crate) label: String,
+    /// Expense amount.
+    pub(crate) amount: f64,
+}
+
+/// Category whose spending exceeded its monthly budget.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct BudgetOverrun {
+    /// Category name.
+    pub(crate) category: String,
+    /// Budgeted expense.
+    pub(crate) budget: f64,
+    /// Actual spending.
+    pub(crate) spent: f64,
+    /// Amount over budget.
+    pub(crate) over: f64,
+}
+
+/// Net balance change of an account over a period.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct AccountChange {
+    /// Account name.
+    pub(crate) account: String,
+    /// Currency symbol.
+    pub(crate) currency: String,
+    /// Net change (positive when the balance grew).
+    pub(crate) change: f64,
+}
+
+/// Result of the `digest` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct DigestResponse {
+    /// First day of the period.
+    pub(crate) date_from: String,
+    /// Last day of the period.
+    pub(crate) date_to: String,
+    /// Currency of the totals; absent when amounts are summed natively.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) currency: Option<String>,
+    /// Total income.
+    pub(crate) income: f64,
+    /// Total expenses.
+    pub(crate) expense: f64,
+    /// Income minus expenses.
+    pub(crate) net: f64,
+    /// Categories with the largest spending.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) top_categories: Vec<DigestAmount>,
+    /// Largest single expenses.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) largest_expenses: Vec<DigestTransaction>,
+    /// Budgets exceeded in the month of `date_to` (month to date).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) budget_overruns: Vec<BudgetOverrun>,
+    /// Accounts with the largest balance changes, in their own currency.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) account_changes: Vec<AccountChange>,
+    /// Currencies with no rate to `currency`, whose amounts are counted at
+    /// face value.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) unconverted: Vec<String>,
+}
+
+/// Average daily spending in one category.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct CategoryAverage {
+    /// Category tag name (or "Uncategorized").
+    pub(crate) category: String,
+    /// Spending per calendar day.
+    pub(crate) daily_average: f64,
+}
+
+/// Spending for one weekday or day-of-month range.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct PatternBucket {
+    /// Weekday (`Mon`…`Sun`) or day-of-month range (`1-4`).
+    pub(crate) label: String,
+    /// Calendar days of the range that fall into the bucket.
+    pub(crate) days: u32,
+    /// Total spending.
+    pub(crate) total: f64,
+    /// Spending per calendar day.
+    pub(crate) daily_average: f64,
+    /// Per-category daily averages, largest first (only when requested).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) categories: Vec<CategoryAverage>,
+}
+
+/// Result of the `spending_patterns` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct SpendingPatternsResponse {
+    /// First day of the range.
+    pub(crate) date_from: String,
+    /// Last day of the range.
+    pub(crate) date_to: String,
+    /// Average daily spending over the whole range.
+    pub(crate) daily_average: f64,
+    /// Average daily spending Monday to Friday.
+    pub(crate) weekday_daily_average: f64,
+    /// Average daily spending on Saturdays and Sundays.
+    pub(crate) weekend_daily_average: f64,
+    /// Buckets per weekday, Monday first.
+    pub(crate) by_weekday: Vec<PatternBucket>,
+    /// Buckets per day-of-month decile.
+    pub(crate) by_day_of_month: Vec<PatternBucket>,
+}
+
+/// Money moved from one account to another.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct TransferFlow {
+    /// Source account name.
+    pub(crate) from_account: String,
+    /// Destination account name.
+    pub(crate) to_account: String,
+    /// Number of transfers.
+    pub(crate) count: usize,
+    /// Total taken from the source account.
+    pub(crate) sent: f64,
+    /// Currency of `sent`.
+    pub(crate) sent_currency: String,
+    /// Total added to the destination account.
+    pub(crate) received: f64,
+    /// Currency of `received`.
+    pub(crate) received_currency: String,
+}
+
+/// Transfer totals of one account.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct AccountTransfers {
+    /// Account name.
+    pub(crate) account: String,
+    /// Currency of the amounts.
+    pub(crate) currency: String,
+    /// Total received from other accounts.
+    pub(crate) transferred_in: f64,
+    /// Total sent to other accounts.
+    pub(crate) transferred_out: f64,
+    /// Received minus sent.
+    pub(crate) net: f64,
+}
+
+/// Result of the `transfer_report` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct TransferReportResponse {
+    /// Account-to-account flows, busiest first.
+    pub(crate) flows: Vec<TransferFlow>,
+    /// Per-account totals, by account name.
+    pub(crate) accounts: Vec<AccountTransfers>,
+    /// Number of transfers in the range.
+    pub(crate) transfer_count: usize,
+}
+
+/// Income, expenses and savings for one month (or the whole range).
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct SavingsMonth {
+    /// Month (`YYYY-MM`), or `total` for the whole range.
+    pub(crate) month: String,
+    /// Total income.
+    pub(crate) income: f64,
+    /// Total expenses.
+    pub(crate) expense: f64,
+    /// Income minus expenses.
+    pub(crate) net_saved: f64,
+    /// Net amount moved into savings accounts, when requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) to_savings: Option<f64>,
+    /// Saved amount as a percentage of income; absent without income.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) savings_percent: Option<f64>,
+}
+
+/// Result of the `savings_rate` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct SavingsRateResponse {
+    /// First day of the range.
+    pub(crate) date_from: String,
+    /// Last day of the range.
+    pub(crate) date_to: String,
+    /// Per-month figures, oldest first.
+    pub(crate) months: Vec<SavingsMonth>,
+    /// Figures for the whole range.
+    pub(crate) total: SavingsMonth,
+}
+
+/// How well monthly category budgets were kept over a year.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct BudgetAdherence {
+    /// Number of category budgets with a spending limit.
+    pub(crate) budgets: u32,
+    /// Budgets whose category stayed within the limit.
+    pub(crate) kept: u32,
+    /// Share of budgets kept, in percent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) kept_percent: Option<f64>,
+    /// Sum of all limits.
+    pub(crate) budgeted: f64,
+    /// Spending in budgeted categories.
+    pub(crate) spent: f64,
+    /// Largest overruns, labelled with their month.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) overruns: Vec<BudgetOverrun>,
+}
+
+/// Result of the `year_review` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct YearReviewResponse {
+    /// Calendar year reviewed.
+    pub(crate) year: i32,
+    /// Total income.
+    pub(crate) income: f64,
+    /// Total expenses.
+    pub(crate) expense: f64,
+    /// Income minus expenses.
+    pub(crate) net: f64,
+    /// Net as a percentage of income; absent without income.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) savings_percent: Option<f64>,
+    /// Number of transactions in the year.
+    pub(crate) transaction_count: usize,
+    /// Per-month income, expenses and savings.
+    pub(crate) months: Vec<SavingsMonth>,
+    /// Categories with the largest spending.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) top_categories: Vec<DigestAmount>,
+    /// Payees with the largest spending.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) top_payees: Vec<DigestAmount>,
+    /// Largest single expenses.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) largest_expenses: Vec<DigestTransaction>,
+    /// Budget adherence; absent when no budgets were set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) budget_adherence: Option<BudgetAdherence>,
+}
+
+/// Result of writing a backup.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct BackupResponse {
+    /// File the backup was written to.
+    pub(crate) path: String,
+    /// When the backup was taken (RFC 3339).
+    pub(crate) created_at: String,
+    /// Size of the backup file in bytes.
+    pub(crate) bytes: u64,
+    /// Number of entities saved per collection.
+    pub(crate) counts: BTreeMap<String, usize>,
+}
+
+/// Result of the `export_snapshot` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct SnapshotResponse {
+    /// File the snapshot was written to.
+    pub(crate) path: String,
+    /// When the snapshot was taken (RFC 3339).
+    pub(crate) created_at: String,
+    /// Version of the snapshot document layout.
+    pub(crate) schema_version: u32,
+    /// Size of the snapshot file in bytes.
+    pub(crate) bytes: u64,
+    /// Number of entities saved per collection.
+    pub(crate) counts: BTreeMap<String, usize>,
+}
+
+/// One file written by an export.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct ExportedFile {
+    /// Account name.
+    pub(crate) account: String,
+    /// Account ID.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) account_id: Option<String>,
+    /// File the account's transactions were written to.
+    pub(crate) path: String,
+    /// Number of transactions in the file.
+    pub(crate) transactions: usize,
+}
+
+/// Result of the `export_qif` and `export_ofx` tools.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct ExportResponse {
+    /// `qif` or `ofx`.
+    pub(crate) format: String,
+    /// Directory the files were written to.
+    pub(crate) directory: String,
+    /// Number of transactions exported; transfers count once.
+    pub(crate) transactions: usize,
+    /// Files written, one per account.
+    pub(crate) files: Vec<ExportedFile>,
+}
+
+/// Result of the `export_ledger` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct LedgerExportResponse {
+    /// `hledger` or `beancount`.
+    pub(crate) dialect: String,
+    /// Journal file written.
+    pub(crate) path: String,
+    /// Number of transactions exported.
+    pub(crate) transactions: usize,
+    /// Ledger accounts used by the entries.
+    pub(crate) accounts: Vec<String>,
+    /// Commodities declared.
+    pub(crate) commodities: Vec<String>,
+}
+
+/// Result of restoring a backup into local storage.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct RestoreResponse {
+    /// When the restored backup was taken (RFC 3339).
+    pub(crate) created_at: String,
+    /// Number of entities restored per collection.
+    pub(crate) counts: BTreeMap<String, usize>,
+    /// What happens next.
+    pub(crate) message: String,
+}
+
+/// Entities of one type changed by a sync.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, JsonSchema)]
+pub(crate) struct ChangeCounts {
+    /// Entities that were not stored before.
+    pub(crate) added: usize,
+    /// Stored entities that changed.
+    pub(crate) updated: usize,
+    /// Entities removed or flagged as deleted.
+    pub(crate) deleted: usize,
+}
+
+impl ChangeCounts {
+    /// Total number of changed entities.
+    pub(crate) const fn total(&self) -> usize {
+        self.added
+            .saturating_add(self.updated)
+            .saturating_add(self.deleted)
+    }
+}
+
+/// Result of a sync limited to selected entity types.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct SyncEntitiesResponse {
+    /// Changes per requested entity type.
+    pub(crate) changes: BTreeMap<EntityKind, ChangeCounts>,
+    /// Entities changed across the requested types.
+    pub(crate) total_changed: usize,
+}
+
+/// Changes brought in by a sync.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct SyncChangesResponse {
+    /// When the sync finished (RFC 3339).
+    pub(crate) synced_at: String,
+    /// Whether it was a full sync.
+    pub(crate) full: bool,
+    /// Changes per entity type; types without changes are omitted.
+    pub(crate) changes: BTreeMap<EntityKind, ChangeCounts>,
+    /// Entities changed across all types.
+    pub(crate) total_changed: usize,
+}
+
+/// Call statistics of one tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct ToolUsage {
+    /// Tool name.
+    pub(crate) tool: String,
+    /// Number of calls.
+    pub(crate) calls: u64,
+    /// Number of calls that returned an error.
+    pub(crate) errors: u64,
+    /// Median latency of recent calls, in milliseconds.
+    pub(crate) p50_ms: u64,
+    /// 90th percentile latency of recent calls, in milliseconds.
+    pub(crate) p90_ms: u64,
+    /// 99th percentile latency of recent calls, in milliseconds.
+    pub(crate) p99_ms: u64,
+    /// Slowest recent call, in milliseconds.
+    pub(crate) max_ms: u64,
+}
+
+/// Tool call statistics since the server started.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct UsageStatsResponse {
+    /// When counting started (RFC 3339).
+    pub(crate) since: String,
+    /// Calls across all tools.
+    pub(crate) total_calls: u64,
+    /// Failed calls across all tools.
+    pub(crate) total_errors: u64,
+    /// Per-tool statistics, most called first.
+    pub(crate) tools: Vec<ToolUsage>,
+}
+
+/// A write queued while ZenMoney was unreachable.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct PendingChangeResponse {
+    /// Pending change ID, accepted by `cancel`.
+    pub(crate) id: String,
+    /// Tool whose write was queued.
+    pub(crate) tool: String,
+    /// When the write was queued (RFC 3339).
+    pub(crate) queued_at: String,
+    /// Always `pending`: delivered changes leave the queue.
+    pub(crate) status: String,
+    /// Transactions waiting to be sent.
+    pub(crate) transactions: Vec<TransactionResponse>,
+}
+
+/// Result of the `pending_changes` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct PendingChangesResponse {
+    /// ID of the change cancelled by this call, if any.
+    pub(crate) cancelled: Option<String>,
+    /// Writes still waiting to be sent, oldest first.
+    pub(crate) pending: Vec<PendingChangeResponse>,
+}
+
+/// A queued edit held back because ZenMoney changed the same transaction.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct ConflictResponse {
+    /// Conflict ID, accepted by `resolve_conflict`.
+    pub(crate) id: String,
+    /// ID of the transaction both sides changed.
+    pub(crate) transaction_id: String,
+    /// When the conflict was found (RFC 3339).
+    pub(crate) detected_at: String,
+    /// Field groups in which the two versions differ.
+    pub(crate) differing_fields: Vec<TransactionField>,
+    /// The queued local edit.
+    pub(crate) local: TransactionResponse,
+    /// The version currently on ZenMoney.
+    pub(crate) remote: TransactionResponse,
+}
+
+/// Result of the `list_conflicts` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct ConflictsResponse {
+    /// Unresolved conflicts, oldest first.
+    pub(crate) conflicts: Vec<ConflictResponse>,
+}
+
+/// Configured limits reported by `server_info`.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct ServerLimits {
+    /// Minutes after which read tools sync first; absent when disabled.
+    pub(crate) max_data_age_minutes: Option<u64>,
+    /// Minimum spacing of ZenMoney API requests, in milliseconds.
+    pub(crate) min_request_interval_ms: u64,
+    /// Default page size of transaction lists.
+    pub(crate) default_transaction_limit: usize,
+    /// Maximum page size of transaction lists.
+    pub(crate) max_transaction_limit: usize,
+    /// Transactions pushed per request by bulk operations.
+    pub(crate) bulk_batch_size: usize,
+}
+
+/// Build and configuration of the running server.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct ServerInfoResponse {
+    /// Crate name.
+    pub(crate) name: String,
+    /// Crate version.
+    pub(crate) version: String,
+    /// Commit the server was built from, if built from a git checkout.
+    pub(crate) git_hash: Option<String>,
+    /// MCP transports compiled in.
+    pub(crate) transports: Vec<String>,
+    /// Enabled optional features.
+    pub(crate) features: Vec<String>,
+    /// Storage backend type.
+    pub(crate) storage: String,
+    /// Directory of the file storage, if any.
+    pub(crate) data_dir: Option<String>,
+    /// Number of tools the server offers.
+    pub(crate) tool_count: usize,
+    /// Code of the currency converted totals default to, if known.
+    pub(crate) base_currency: Option<String>,
+    /// Configured limits.
+    pub(crate) limits: ServerLimits,
+}
+
+/// Result of wiping locally cached data.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct PurgeResponse {
+    /// Whether local data was purged (`false` when the user declined).
+    pub(crate) purged: bool,
+    /// Files deleted from the storage directory.
+    pub(crate) files_deleted: usize,
+    /// Bytes overwritten with zeros before deletion.
+    pub(crate) bytes_overwritten: u64,
+    /// Saved transaction templates deleted.
+    pub(crate) templates_deleted: usize,
+    /// Budgeting envelopes deleted.
+    pub(crate) envelopes_deleted: usize,
+    /// Staged bulk operations discarded.
+    pub(crate) preparations_discarded: usize,
+    /// Queued offline writes discarded without being sent.
+    pub(crate) pending_discarded: usize,
+    /// Unresolved sync conflicts discarded.
+    pub(crate) conflicts_discarded: usize,
+    /// Planned transactions deleted before being sent to ZenMoney.
+    pub(crate) planned_cleared: usize,
+    /// Whether the inbox review watermark was forgotten.
+    pub(crate) review_cleared: bool,
+    /// Whether the saved default account was forgotten.
+    pub(crate) default_account_cleared: bool,
+    /// What happens next.
+    pub(crate) message: String,
 }
 
 /// Enriched tag for display.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub(crate) struct TagResponse {
     /// Tag ID.
     id: String,
@@ -177,6 +1468,9 @@ pub(crate) struct TagResponse {
     title: String,
     /// Parent tag name (if nested).
     parent: Option<String>,
+    /// Parent tag ID (if nested).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parent_id: Option<String>,
 }
 
 impl TagResponse {
@@ -187,36 +1481,467 @@ impl TagResponse {
             id: tag.id.to_string(),
             title: tag.title.clone(),
             parent,
+            parent_id: tag
+                .parent
+                .as_ref()
+                .and_then(|pid| maps.raw_id(pid.as_inner())),
+        }
+    }
+}
+
+/// Tag with its nested child tags.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct TagNode {
+    /// Tag ID.
+    id: String,
+    /// Display name.
+    title: String,
+    /// Whether the tag is archived.
+    archive: bool,
+    /// Child tags, sorted by title.
+    children: Vec<Self>,
+}
+
+impl TagNode {
+    /// Builds a tree of tags sorted by title.
+    ///
+    /// Tags whose parent is missing from `tags` (e.g. filtered out) become roots.
+    pub(crate) fn tree(tags: &[&Tag]) -> Vec<Self> {
+        let ids: HashSet<&str> = tags.iter().map(|tag| tag.id.as_inner()).collect();
+        let mut children: HashMap<&str, Vec<&Tag>> = HashMap::new();
+        let mut roots: Vec<&Tag> = Vec::new();
+        for tag in tags {
+            match tag.parent.as_ref().map(TagId::as_inner) {
+                Some(parent) if ids.contains(parent) && parent != tag.id.as_inner() => {
+                    children.entry(parent).or_default().push(tag);
+                }
+                _ => roots.push(tag),
+            }
+        }
+        Self::nodes(&roots, &children, tags.len())
+    }
+
+    /// Converts tags to nodes, descending at most `depth` levels to guard against cycles.
+    fn nodes(tags: &[&Tag], children: &HashMap<&str, Vec<&Tag>>, depth: usize) -> Vec<Self> {
+        let mut nodes: Vec<Self> = tags
+            .iter()
+            .map(|tag| Self {
+                id: tag.id.to_string(),
+                title: tag.title.clone(),
+                archive: tag.archive.unwrap_or(false),
+                children: match (children.get(tag.id.as_inner()), depth.checked_sub(1)) {
+                    (Some(kids), Some(remaining)) => Self::nodes(kids, children, remaining),
+                    _ => Vec::new(),
+                },
+            })
+            .collect();
+        nodes.sort_by(|a, b| a.title.cmp(&b.title));
+        nodes
+    }
+}
+
+/// Usage statistics of a tag.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct TagUsage {
+    /// Number of transactions carrying the tag.
+    pub(crate) transaction_count: usize,
+    /// Date of the most recent transaction carrying the tag.
+    pub(crate) last_used: Option<String>,
+    /// Expense total over the last 3 months.
+    pub(crate) spend_last_3_months: f64,
+    /// Expense total over the last 12 months.
+    pub(crate) spend_last_12_months: f64,
+}
+
+/// Totals of the active reminders involving an account.
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub(crate) struct ReminderTotals {
+    /// Number of active reminders involving the account.
+    pub(crate) count: usize,
+    /// Income per occurrence into the account, in its currency.
+    pub(crate) income: f64,
+    /// Outcome per occurrence from the account, in its currency.
+    pub(crate) outcome: f64,
+}
+
+/// Recent activity of an account.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct AccountActivity {
+    /// Money that entered the account this calendar month, transfers included.
+    pub(crate) month_inflow: f64,
+    /// Money that left the account this calendar month, transfers included.
+    pub(crate) month_outflow: f64,
+    /// Date of the most recent transaction involving the account.
+    pub(crate) last_transaction: Option<String>,
+    /// Reminders that have not ended yet.
+    pub(crate) pending_reminders: ReminderTotals,
+}
+
+/// Stored balance of an account compared with one recomputed from history.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct BalanceCheck {
+    /// Account ID.
+    pub(crate) account_id: String,
+    /// Account display name.
+    pub(crate) account: String,
+    /// Currency symbol.
+    pub(crate) currency: String,
+    /// Balance the account was opened with.
+    pub(crate) start_balance: f64,
+    /// Number of non-deleted transactions involving the account.
+    pub(crate) transaction_count: usize,
+    /// Start balance plus all income minus all outcome.
+    pub(crate) computed_balance: f64,
+    /// Balance stored by ZenMoney.
+    pub(crate) stored_balance: f64,
+    /// `stored_balance - computed_balance`.
+    pub(crate) difference: f64,
+}
+
+/// Result of the `verify_balances` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct VerifyBalancesResponse {
+    /// Number of accounts checked.
+    pub(crate) checked: usize,
+    /// Number of accounts whose balances match within the tolerance.
+    pub(crate) matching: usize,
+    /// Accounts whose balances differ, largest difference first.
+    pub(crate) discrepancies: Vec<BalanceCheck>,
+}
+
+/// Full account details with recent activity.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct AccountDetailsResponse {
+    /// Basic account fields.
+    #[serde(flatten)]
+    pub(crate) account: AccountResponse,
+    /// Activity computed from transactions and reminders.
+    pub(crate) activity: AccountActivity,
+}
+
+/// Full tag details with usage statistics.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct TagDetailsResponse {
+    /// Basic tag fields.
+    #[serde(flatten)]
+    pub(crate) tag: TagResponse,
+    /// Icon identifier.
+    pub(crate) icon: Option<String>,
+    /// Color in ARGB format.
+    pub(crate) color: Option<i64>,
+    /// Whether the tag is archived.
+    pub(crate) archive: bool,
+    /// Whether any budget with a non-zero amount exists for the tag.
+    pub(crate) budgeted: bool,
+    /// Direct child tags.
+    pub(crate) children: Vec<TagResponse>,
+    /// Usage statistics.
+    pub(crate) usage: TagUsage,
+}
+
+/// Enriched merchant for display.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct MerchantResponse {
+    /// Merchant ID.
+    id: String,
+    /// Display name.
+    title: String,
+}
+
+impl MerchantResponse {
+    /// Creates a merchant response from a raw merchant.
+    pub(crate) fn from_merchant(merchant: &Merchant) -> Self {
+        Self {
+            id: merchant.id.to_string(),
+            title: merchant.title.clone(),
+        }
+    }
+}
+
+/// An entity found by a `find_*` tool, with how well its title matched.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct RankedMatch<T> {
+    /// Match quality from 0 to 1 (1 is an exact match).
+    pub(crate) score: f64,
+    /// The matched entity.
+    #[serde(flatten)]
+    pub(crate) entity: T,
+}
+
+/// Result of the `find_account`, `find_tag` and `find_merchant` tools.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct FindResponse<T> {
+    /// Whether any title matched.
+    pub(crate) found: bool,
+    /// The searched title.
+    pub(crate) query: String,
+    /// Matches, best first; empty when no title is close.
+    pub(crate) matches: Vec<RankedMatch<T>>,
+}
+
+/// A candidate found by `search_entities`.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct EntityMatch {
+    /// Entity type.
+    pub(crate) kind: SearchKind,
+    /// Entity ID.
+    pub(crate) id: String,
+    /// Entity title.
+    pub(crate) title: String,
+    /// Match quality from 0 to 1 (1 is an exact match).
+    pub(crate) score: f64,
+}
+
+/// Result of the `search_entities` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct EntitySearchResponse {
+    /// Candidates, best match first.
+    pub(crate) matches: Vec<EntityMatch>,
+}
+
+/// Synced user for display.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct UserResponse {
+    /// User ID.
+    id: i64,
+    /// Login (email or username).
+    login: Option<String>,
+    /// Email address.
+    email: Option<String>,
+    /// Preferred currency symbol.
+    currency: String,
+    /// Parent user ID for family members (`None` for the workspace owner).
+    parent_user_id: Option<i64>,
+}
+
+impl UserResponse {
+    /// Creates an enriched user response from a raw user.
+    pub(crate) fn from_user(user: &User, maps: &LookupMaps) -> Self {
+        Self {
+            id: user.id.into_inner(),
+            login: user.login.clone(),
+            email: user.email.clone(),
+            currency: maps.instrument_symbol(user.currency.into_inner()),
+            parent_user_id: user.parent.map(UserId::into_inner),
+        }
+    }
+}
+
+/// Company (bank) for display.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct CompanyResponse {
+    /// Company ID.
+    id: i32,
+    /// Short name.
+    title: String,
+    /// Full legal name.
+    full_title: Option<String>,
+    /// Website URL.
+    www: Option<String>,
+    /// Two-letter country code.
+    country_code: Option<String>,
+}
+
+impl CompanyResponse {
+    /// Creates a company response from a raw company.
+    pub(crate) fn from_company(company: &Company) -> Self {
+        Self {
+            id: company.id.into_inner(),
+            title: company.title.clone(),
+            full_title: company.full_title.clone(),
+            www: company.www.clone(),
+            country_code: company.country_code.clone(),
+        }
+    }
+}
+
+/// Result of the `save_template` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct SavedTemplateResponse {
+    /// Stored template.
+    #[serde(flatten)]
+    pub(crate) template: TransactionTemplate,
+    /// Whether a template with the same name was replaced.
+    pub(crate) replaced: bool,
+}
+
+/// Result of the `allocate_envelope` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct AllocatedEnvelopeResponse {
+    /// Month the allocation was recorded for (`YYYY-MM`).
+    pub(crate) month: String,
+    /// Envelope that received the money.
+    pub(crate) envelope: Envelope,
+    /// Envelope the money was moved from, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) from_envelope: Option<Envelope>,
+    /// Whether the envelope was created by this call.
+    pub(crate) created: bool,
+}
+
+/// Balance of one envelope for a month.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct EnvelopeStatus {
+    /// Envelope name.
+    pub(crate) name: String,
+    /// Names of the category tags paid from the envelope.
+    pub(crate) tags: Vec<String>,
+    /// Unspent money rolled over from earlier months.
+    pub(crate) carried_over: f64,
+    /// Amount allocated in the month.
+    pub(crate) allocated: f64,
+    /// Amount spent in the envelope's categories in the month.
+    pub(crate) spent: f64,
+    /// Money left: carried over plus allocated minus spent.
+    pub(crate) available: f64,
+    /// Whether more was spent than the envelope holds.
+    pub(crate) overspent: bool,
+}
+
+/// Result of the `envelope_status` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct EnvelopeStatusResponse {
+    /// Month reported (`YYYY-MM`).
+    pub(crate) month: String,
+    /// Income received in the month.
+    pub(crate) income: f64,
+    /// Total allocated to envelopes in the month.
+    pub(crate) allocated: f64,
+    /// Income not yet allocated to any envelope (negative when over-allocated).
+    pub(crate) to_be_budgeted: f64,
+    /// Per-envelope balances.
+    pub(crate) envelopes: Vec<EnvelopeStatus>,
+}
+
+/// Result of the `merge_merchants` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct MergeMerchantsResponse {
+    /// Merchant that absorbed the sources.
+    pub(crate) target: MerchantResponse,
+    /// IDs of the merchants merged away and deleted.
+    pub(crate) merged_merchant_ids: Vec<String>,
+    /// Number of transactions re-pointed to the target.
+    pub(crate) transactions_updated: usize,
+    /// Number of reminders re-pointed to the target.
+    pub(crate) reminders_updated: usize,
+}
+
+/// Planned transactions listed by `list_planned`.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct PlannedResponse {
+    /// Number of planned transactions.
+    pub(crate) count: usize,
+    /// Planned transactions dated today or earlier, ready to convert.
+    pub(crate) due: usize,
+    /// Planned transactions, earliest first.
+    pub(crate) transactions: Vec<TransactionResponse>,
+}
+
+impl PlannedResponse {
+    /// Builds the listing, counting transactions due by `today`.
+    pub(crate) fn new(planned: &[Transaction], today: NaiveDate, maps: &LookupMaps) -> Self {
+        Self {
+            count: planned.len(),
+            due: planned.iter().filter(|tx| tx.date <= today).count(),
+            transactions: planned
+                .iter()
+                .map(|tx| TransactionResponse::from_transaction(tx, maps))
+                .collect(),
         }
     }
 }
 
-/// Enriched merchant for display.
-#[derive(Debug, Clone, Serialize)]
-pub(crate) struct MerchantResponse {
-    /// Merchant ID.
-    id: String,
-    /// Display name.
-    title: String,
+/// Transactions that arrived through syncs, read from `zenmoney://inbox`.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct InboxResponse {
+    /// Number of transactions listed.
+    pub(crate) count: usize,
+    /// Listed expenses and incomes without a category tag.
+    pub(crate) uncategorized: usize,
+    /// Arrived transactions, newest arrival first.
+    pub(crate) transactions: Vec<TransactionResponse>,
 }
 
-impl MerchantResponse {
-    /// Creates a merchant response from a raw merchant.
-    pub(crate) fn from_merchant(merchant: &Merchant) -> Self {
+/// Returns whether `tx` is an expense or income without a category tag.
+pub(crate) fn needs_category(tx: &Transaction) -> bool {
+    !matches!(classify_transaction(tx), TransactionType::Transfer)
+        && tx.tag.as_ref().is_none_or(Vec::is_empty)
+}
+
+impl InboxResponse {
+    /// Builds the inbox from the arrived transactions still stored.
+    pub(crate) fn new(arrived: &[&Transaction], maps: &LookupMaps) -> Self {
+        let uncategorized = arrived.iter().filter(|tx| needs_category(tx)).count();
         Self {
-            id: merchant.id.to_string(),
-            title: merchant.title.clone(),
+            count: arrived.len(),
+            uncategorized,
+            transactions: arrived
+                .iter()
+                .map(|tx| TransactionResponse::from_transaction(tx, maps))
+                .collect(),
         }
     }
 }
 
+/// Response of the `inbox` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct ReviewInboxResponse {
+    /// Watermark of the last review (RFC 3339); `None` before the first review.
+    pub(crate) reviewed_through: Option<String>,
+    /// Transactions needing attention.
+    pub(crate) total: usize,
+    /// Of those, transactions not viewed yet.
+    pub(crate) unviewed: usize,
+    /// Of those, transactions created after the watermark.
+    pub(crate) created_since_review: usize,
+    /// Of those, expenses and incomes without a category tag.
+    pub(crate) uncategorized: usize,
+    /// The newest of them, newest first, up to the limit.
+    pub(crate) transactions: Vec<TransactionResponse>,
+}
+
+/// Response of the `mark_reviewed` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct MarkReviewedResponse {
+    /// New watermark (RFC 3339).
+    pub(crate) reviewed_through: String,
+    /// Previous watermark; `None` if this was the first review.
+    pub(crate) previous: Option<String>,
+    /// Number of transactions marked as viewed.
+    pub(crate) marked_viewed: usize,
+}
+
+/// Response of the `log_expense` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct LogExpenseResponse {
+    /// The created expense.
+    pub(crate) transaction: TransactionResponse,
+    /// How the fields not given were filled in, such as
+    /// `tags: Cafe (suggested)`.
+    pub(crate) inferred: Vec<String>,
+}
+
+/// Response of the `set_default_account` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct DefaultAccountResponse {
+    /// Default account ID; `None` if no default is set.
+    pub(crate) account_id: Option<String>,
+    /// Title of the default account.
+    pub(crate) account: Option<String>,
+    /// Where the default comes from: `saved` or `environment`.
+    pub(crate) source: Option<String>,
+}
+
 /// Enriched budget for display.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub(crate) struct BudgetResponse {
     /// Budget month.
     date: String,
     /// Category tag name.
     tag: Option<String>,
+    /// Category tag ID.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tag_id: Option<String>,
     /// Income target.
     income: f64,
     /// Outcome target.
@@ -230,14 +1955,122 @@ impl BudgetResponse {
         Self {
             date: budget.date.to_string(),
             tag,
+            tag_id: budget
+                .tag
+                .as_ref()
+                .and_then(|tid| maps.raw_id(tid.as_inner())),
             income: budget.income,
             outcome: budget.outcome,
         }
     }
 }
 
+/// Budgets of one month with their totals.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct BudgetMonth {
+    /// Month, format `YYYY-MM`.
+    pub(crate) month: String,
+    /// Sum of income targets.
+    pub(crate) income: f64,
+    /// Sum of outcome targets.
+    pub(crate) outcome: f64,
+    /// Budgets of the month.
+    pub(crate) budgets: Vec<BudgetResponse>,
+}
+
+/// Result of `list_budgets` over a range of months.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct BudgetRangeResponse {
+    /// Months that have budgets, oldest first.
+    pub(crate) months: Vec<BudgetMonth>,
+    /// Sum of income targets over all months.
+    pub(crate) income: f64,
+    /// Sum of outcome targets over all months.
+    pub(crate) outcome: f64,
+}
+
+/// Spending of one category against its monthly budget.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct BudgetProgress {
+    /// Category tag ID.
+    pub(crate) tag_id: String,
+    /// Category tag name.
+    pub(crate) tag: String,
+    /// Outcome budget of the month.
+    pub(crate) budget: f64,
+    /// Unspent (positive) or overspent (negative) amount carried over from
+    /// earlier months; 0 without rollover.
+    pub(crate) carried_over: f64,
+    /// `budget + carried_over`.
+    pub(crate) available: f64,
+    /// Spending in the category and its children this month.
+    pub(crate) spent: f64,
+    /// `available - spent`; negative when over budget.
+    pub(crate) remaining: f64,
+    /// Share of `available` spent, in percent; omitted when nothing is available.
+    pub(crate) percent_used: Option<f64>,
+}
+
+/// Result of the `budget_progress` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct BudgetProgressResponse {
+    /// Reported month, format `YYYY-MM`.
+    pub(crate) month: String,
+    /// Earlier months carried forward, when rollover is on.
+    pub(crate) rollover_months: Option<u32>,
+    /// Categories with a budget for the month, least remaining first.
+    pub(crate) categories: Vec<BudgetProgress>,
+    /// Sum of the budgets.
+    pub(crate) budget: f64,
+    /// Sum of the carried-over amounts.
+    pub(crate) carried_over: f64,
+    /// Sum of the spending.
+    pub(crate) spent: f64,
+    /// Sum of the remaining amounts.
+    pub(crate) remaining: f64,
+}
+
+/// Budget proposed for one category by `suggest_budgets`.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct BudgetSuggestion {
+    /// Category tag ID.
+    pub(crate) tag_id: String,
+    /// Category tag name.
+    pub(crate) tag: String,
+    /// Number of months of the history with spending in the category.
+    pub(crate) months_with_spending: usize,
+    /// Median monthly spend over the history.
+    pub(crate) median: f64,
+    /// Average monthly spend over the history.
+    pub(crate) average: f64,
+    /// Proposed outcome budget.
+    pub(crate) amount: f64,
+    /// Outcome budget already set for the month, if any.
+    pub(crate) current: Option<f64>,
+}
+
+/// Result of the `suggest_budgets` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct BudgetSuggestionsResponse {
+    /// Month the budgets are proposed for, format `YYYY-MM`.
+    pub(crate) month: String,
+    /// First day of the spending history.
+    pub(crate) history_from: String,
+    /// Last day of the spending history.
+    pub(crate) history_to: String,
+    /// Currency symbol of the amounts.
+    pub(crate) currency: String,
+    /// Sum of the proposed amounts.
+    pub(crate) total: f64,
+    /// Proposals, largest first.
+    pub(crate) suggestions: Vec<BudgetSuggestion>,
+    /// Pass to `execute_bulk_operations` to save the proposed budgets;
+    /// omitted when there is nothing to propose.
+    pub(crate) preparation_id: Option<String>,
+}
+
 /// Enriched reminder for display.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub(crate) struct ReminderResponse {
     /// Reminder ID.
     id: String,
@@ -245,12 +2078,21 @@ pub(crate) struct ReminderResponse {
     income: f64,
     /// Income account name.
     income_account: String,
+    /// Income account ID.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    income_account_id: Option<String>,
     /// Outcome amount.
     outcome: f64,
     /// Outcome account name.
     outcome_account: String,
+    /// Outcome account ID.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    outcome_account_id: Option<String>,
     /// Category tag names.
     tags: Vec<String>,
+    /// Category tag IDs, in the same order as `tags`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tag_ids: Option<Vec<String>>,
     /// Payee name.
     payee: Option<String>,
     /// Comment.
@@ -277,9 +2119,12 @@ impl ReminderResponse {
             id: reminder.id.to_string(),
             income: reminder.income,
             income_account: maps.account_name(reminder.income_account.as_inner()),
+            income_account_id: maps.raw_id(reminder.income_account.as_inner()),
             outcome: reminder.outcome,
             outcome_account: maps.account_name(reminder.outcome_account.as_inner()),
+            outcome_account_id: maps.raw_id(reminder.outcome_account.as_inner()),
             tags,
+            tag_ids: maps.raw_tag_ids(reminder.tag.as_deref()),
             payee: reminder.payee.clone(),
             comment: reminder.comment.clone(),
             start_date: reminder.start_date.to_string(),
@@ -289,8 +2134,177 @@ impl ReminderResponse {
     }
 }
 
+/// Enriched reminder marker (one planned occurrence of a reminder) for display.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct ReminderMarkerResponse {
+    /// Marker ID.
+    id: String,
+    /// ID of the reminder the marker belongs to.
+    reminder_id: String,
+    /// Date of the occurrence.
+    date: String,
+    /// `planned`, `processed` or `deleted`.
+    state: String,
+    /// Income amount.
+    income: f64,
+    /// Income account name.
+    income_account: String,
+    /// Income account ID.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    income_account_id: Option<String>,
+    /// Outcome amount.
+    outcome: f64,
+    /// Outcome account name.
+    outcome_account: String,
+    /// Outcome account ID.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    outcome_account_id: Option<String>,
+    /// Category tag names.
+    tags: Vec<String>,
+    /// Category tag IDs, in the same order as `tags`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tag_ids: Option<Vec<String>>,
+    /// Payee name.
+    payee: Option<String>,
+    /// Comment.
+    comment: Option<String>,
+}
+
+impl ReminderMarkerResponse {
+    /// Creates an enriched marker response from a raw reminder marker.
+    pub(crate) fn from_marker(marker: &ReminderMarker, maps: &LookupMaps) -> Self {
+        let tags: Vec<String> = marker
+            .tag
+            .as_deref()
+            .unwrap_or_default()
+            .iter()
+            .map(|tid| maps.tag_name(tid.as_inner()))
+            .collect();
+        Self {
+            id: marker.id.to_string(),
+            reminder_id: marker.reminder.to_string(),
+            date: marker.date.to_string(),
+            state: marker_state_label(marker.state),
+            income: marker.income,
+            income_account: maps.account_name(marker.income_account.as_inner()),
+            income_account_id: maps.raw_id(marker.income_account.as_inner()),
+            outcome: marker.outcome,
+            outcome_account: maps.account_name(marker.outcome_account.as_inner()),
+            outcome_account_id: maps.raw_id(marker.outcome_account.as_inner()),
+            tags,
+            tag_ids: maps.raw_tag_ids(marker.tag.as_deref()),
+            payee: marker.payee.clone(),
+            comment: marker.comment.clone(),
+        }
+    }
+}
+
+/// One occurrence of a recurring bill within a period.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct UpcomingBill {
+    /// Reminder ID.
+    pub(crate) reminder_id: String,
+    /// Payee of the reminder.
+    pub(crate) payee: Option<String>,
+    /// Category tag names.
+    pub(crate) tags: Vec<String>,
+    /// Name of the account the bill is paid from.
+    pub(crate) account: String,
+    /// ID of the account the bill is paid from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) account_id: Option<String>,
+    /// Date the occurrence falls due.
+    pub(crate) due_date: String,
+    /// Planned amount.
+    pub(crate) amount: f64,
+    /// Currency symbol of the planned amount.
+    pub(crate) currency: String,
+    /// `paid`, `due`, `overdue` or `skipped`.
+    pub(crate) status: String,
+    /// Date of the payment, when one was found.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) paid_date: Option<String>,
+    /// Amount actually paid, when a payment was found.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) paid_amount: Option<f64>,
+    /// ID of the payment transaction.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) transaction_id: Option<String>,
+}
+
+impl UpcomingBill {
+    /// Creates a bill occurrence of `reminder` due on `due`, paid by `payment` if any.
+    pub(crate) fn new(
+        reminder: &Reminder,
+        due: NaiveDate,
+        status: &str,
+        payment: Option<&Transaction>,
+        maps: &LookupMaps,
+    ) -> Self {
+        Self {
+            reminder_id: reminder.id.to_string(),
+            payee: reminder.payee.clone(),
+            tags: reminder
+                .tag
+                .iter()
+                .flatten()
+                .map(|tag| maps.tag_name(tag.as_inner()))
+                .collect(),
+            account: maps.account_name(reminder.outcome_account.as_inner()),
+            account_id: maps.raw_id(reminder.outcome_account.as_inner()),
+            due_date: due.to_string(),
+            amount: reminder.outcome,
+            currency: maps.instrument_symbol(reminder.outcome_instrument.into_inner()),
+            status: status.to_owned(),
+            paid_date: payment.map(|tx| tx.date.to_string()),
+            paid_amount: payment.map(|tx| tx.outcome),
+            transaction_id: payment.map(|tx| tx.id.to_string()),
+        }
+    }
+}
+
+/// Paid and outstanding bill amounts in one currency.
+#[derive(Debug, Clone, Default, Serialize, JsonSchema)]
+pub(crate) struct BillTotals {
+    /// Currency symbol.
+    pub(crate) currency: String,
+    /// Amount paid so far.
+    pub(crate) paid: f64,
+    /// Planned amount of bills still due or overdue.
+    pub(crate) remaining: f64,
+}
+
+/// Result of the `upcoming_bills` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct UpcomingBillsResponse {
+    /// Month reported, as its first day.
+    pub(crate) month: String,
+    /// Date bills were judged due or overdue against.
+    pub(crate) today: String,
+    /// Number of paid occurrences.
+    pub(crate) paid_count: usize,
+    /// Number of occurrences due today or later.
+    pub(crate) due_count: usize,
+    /// Number of unpaid occurrences whose date has passed.
+    pub(crate) overdue_count: usize,
+    /// Paid and remaining amounts per currency.
+    pub(crate) totals: Vec<BillTotals>,
+    /// Occurrences by due date.
+    pub(crate) bills: Vec<UpcomingBill>,
+}
+
+/// Result of the `set_marker_state` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct MarkerStateResponse {
+    /// The marker in its new state.
+    pub(crate) marker: ReminderMarkerResponse,
+    /// Transaction recorded from the marker, when it was converted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) transaction: Option<TransactionResponse>,
+}
+
 /// Enriched instrument for display.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub(crate) struct InstrumentResponse {
     /// Instrument ID.
     id: i32,
@@ -317,11 +2331,54 @@ impl InstrumentResponse {
     }
 }
 
+/// Result of converting an amount between two currency instruments.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct ConversionResponse {
+    /// Original amount.
+    amount: f64,
+    /// Source currency short code.
+    from_currency: String,
+    /// Target currency short code.
+    to_currency: String,
+    /// Amount expressed in the target currency.
+    converted_amount: f64,
+    /// Rate applied (target units per source unit).
+    rate: f64,
+    /// Timestamp of the oldest rate used in the calculation.
+    rates_as_of: String,
+}
+
+impl ConversionResponse {
+    /// Converts `amount` from one instrument to another using their stored rates.
+    ///
+    /// Returns `None` if the target instrument has a non-positive rate.
+    pub(crate) fn from_instruments(
+        amount: f64,
+        from: &Instrument,
+        to: &Instrument,
+    ) -> Option<Self> {
+        if to.rate <= 0.0_f64 {
+            return None;
+        }
+        let rate = from.rate / to.rate;
+        Some(Self {
+            amount,
+            from_currency: from.short_title.clone(),
+            to_currency: to.short_title.clone(),
+            converted_amount: amount * rate,
+            rate,
+            rates_as_of: from.changed.min(to.changed).to_rfc3339(),
+        })
+    }
+}
+
 /// Response for a deleted transaction, showing what was removed.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub(crate) struct DeletedTransactionResponse {
     /// Status message.
     message: String,
+    /// Whether the transaction was deleted (`false` when the user declined).
+    deleted: bool,
     /// Details of the deleted transaction.
     transaction: TransactionResponse,
 }
@@ -331,13 +2388,86 @@ impl DeletedTransactionResponse {
     pub(crate) const fn new(message: String, transaction: TransactionResponse) -> Self {
         Self {
             message,
+            deleted: true,
+            transaction,
+        }
+    }
+
+    /// Creates a response for a deletion the user declined.
+    pub(crate) const fn declined(message: String, transaction: TransactionResponse) -> Self {
+        Self {
+            message,
+            deleted: false,
             transaction,
         }
     }
 }
 
+impl PurgeResponse {
+    /// Creates a response for a purge the user declined.
+    pub(crate) fn declined() -> Self {
+        Self {
+            purged: false,
+            files_deleted: 0,
+            bytes_overwritten: 0,
+            templates_deleted: 0,
+            envelopes_deleted: 0,
+            preparations_discarded: 0,
+            pending_discarded: 0,
+            conflicts_discarded: 0,
+            planned_cleared: 0,
+            review_cleared: false,
+            default_account_cleared: false,
+            message: "Purge declined by the user; nothing was deleted".to_owned(),
+        }
+    }
+}
+
+/// How a bulk commit stopped before saving everything.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct BulkCancellation {
+    /// `declined` when the user refused the confirmation, `cancelled` when
+    /// the client cancelled the call, `failed` when ZenMoney rejected a batch.
+    pub(crate) reason: String,
+    /// ID of the preparation being executed.
+    pub(crate) preparation_id: String,
+    /// Whether the preparation was kept and can be executed again; only when
+    /// nothing was sent.
+    pub(crate) preparation_kept: bool,
+    /// ID of a new preparation holding the changes not sent, when some were.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) remaining_preparation_id: Option<String>,
+    /// Error that stopped a `failed` commit.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) error: Option<String>,
+    /// Changes sent before stopping: saved transactions first, then
+    /// deletions, then budgets.
+    pub(crate) done: usize,
+    /// Changes in the preparation.
+    pub(crate) total: usize,
+}
+
+impl BulkCancellation {
+    /// Describes a commit of `total` changes from `preparation_id` that the
+    /// user declined; the caller updates it if sending stops part-way.
+    pub(crate) fn declined(preparation_id: &str, total: usize) -> Self {
+        Self {
+            reason: "declined".to_owned(),
+            preparation_id: preparation_id.to_owned(),
+            preparation_kept: false,
+            remaining_preparation_id: None,
+            error: None,
+            done: 0,
+            total,
+        }
+    }
+}
+
 /// Response for bulk operations.
-#[derive(Debug, Clone, Serialize)]
+///
+/// When `cancelled` is set, the counts and previews describe only the
+/// changes sent before the commit stopped.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub(crate) struct BulkOperationsResponse {
     /// Number of transactions created.
     created: usize,
@@ -345,10 +2475,15 @@ pub(crate) struct BulkOperationsResponse {
     updated: usize,
     /// Number of transactions deleted.
     deleted: usize,
+    /// Number of budgets created or updated.
+    budgets: usize,
     /// Details of created and updated transactions.
     transactions: Vec<TransactionResponse>,
     /// Details of deleted transactions.
     deleted_transactions: Vec<TransactionResponse>,
+    /// Set when the commit stopped before saving everything.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cancelled: Option<BulkCancellation>,
 }
 
 impl BulkOperationsResponse {
@@ -364,14 +2499,47 @@ impl BulkOperationsResponse {
             created,
             updated,
             deleted,
+            budgets: 0,
             transactions,
             deleted_transactions,
+            cancelled: None,
         }
     }
+
+    /// Sets the number of budgets saved.
+    pub(crate) const fn with_budgets(mut self, budgets: usize) -> Self {
+        self.budgets = budgets;
+        self
+    }
+
+    /// Narrows the counts and previews to the changes actually sent: the
+    /// first `created + updated` saved transactions, `deleted` deletions and
+    /// `budgets` budgets.
+    pub(crate) fn only_sent(
+        mut self,
+        created: usize,
+        updated: usize,
+        deleted: usize,
+        budgets: usize,
+    ) -> Self {
+        self.created = created;
+        self.updated = updated;
+        self.deleted = deleted;
+        self.budgets = budgets;
+        self.transactions.truncate(created.saturating_add(updated));
+        self.deleted_transactions.truncate(deleted);
+        self
+    }
+
+    /// Marks the commit as stopped before saving everything.
+    pub(crate) fn with_cancellation(mut self, cancellation: BulkCancellation) -> Self {
+        self.cancelled = Some(cancellation);
+        self
+    }
 }
 
 /// Response for `prepare_bulk_operations`, showing a preview of what will happen.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub(crate) struct PrepareResponse {
     /// Opaque ID to pass to `execute_bulk_operations`.
     pub(crate) preparation_id: String,
@@ -387,8 +2555,102 @@ pub(crate) struct PrepareResponse {
     pub(crate) deleted_transactions: Vec<TransactionResponse>,
 }
 
+/// One statement entry in the result of `import_ofx`.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct OfxImportRow {
+    /// Bank-assigned transaction ID.
+    pub(crate) fitid: String,
+    /// Posting date (`YYYY-MM-DD`).
+    pub(crate) date: String,
+    /// Signed amount: positive for money in, negative for money out.
+    pub(crate) amount: f64,
+    /// Payee name from the statement.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) payee: Option<String>,
+    /// Memo from the statement.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) memo: Option<String>,
+    /// `matched`, `new` or `ambiguous`.
+    pub(crate) verdict: String,
+    /// The matched transaction, or the candidates of an ambiguous entry.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) transaction_ids: Vec<String>,
+}
+
+impl OfxImportRow {
+    /// Builds a row from a statement entry and its verdict.
+    pub(crate) fn new(entry: &OfxEntry, found: EntryMatch) -> Self {
+        Self {
+            fitid: entry.fitid.clone(),
+            date: entry.date.format("%Y-%m-%d").to_string(),
+            amount: entry.amount,
+            payee: entry.name.clone(),
+            memo: entry.memo.clone(),
+            verdict: found.verdict.label().to_owned(),
+            transaction_ids: found.transaction_ids,
+        }
+    }
+}
+
+/// Result of the `import_ofx` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct OfxImportResponse {
+    /// Account name.
+    pub(crate) account: String,
+    /// Statement currency (`CURDEF`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) currency: Option<String>,
+    /// Entries already recorded.
+    pub(crate) matched: usize,
+    /// Entries staged as new transactions.
+    pub(crate) new: usize,
+    /// Entries with several candidate transactions, left to the user.
+    pub(crate) ambiguous: usize,
+    /// Every statement entry with its verdict, in statement order.
+    pub(crate) rows: Vec<OfxImportRow>,
+    /// Staged new transactions; absent when there are none.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) preparation: Option<PrepareResponse>,
+}
+
+/// Result of the `reconcile_account` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct ReconcileResponse {
+    /// Account display name.
+    pub(crate) account: String,
+    /// Balance currently stored for the account.
+    pub(crate) stored_balance: f64,
+    /// Real-world balance supplied by the caller.
+    pub(crate) actual_balance: f64,
+    /// Signed correction amount (`actual_balance - stored_balance`).
+    pub(crate) delta: f64,
+    /// Staged correction transaction.
+    #[serde(flatten)]
+    pub(crate) preparation: PrepareResponse,
+}
+
+/// Result of the `import_receipt_qr` tool.
+#[derive(Debug, Clone, Serialize, JsonSchema)]
+pub(crate) struct ReceiptImportResponse {
+    /// Purchase date and time from the receipt.
+    pub(crate) receipt_time: String,
+    /// Receipt total.
+    pub(crate) receipt_amount: f64,
+    /// Receipt operation type (sale, `sale_return`, purchase, `purchase_return`).
+    pub(crate) operation: String,
+    /// Fiscal drive number.
+    pub(crate) fiscal_drive: String,
+    /// Fiscal document number.
+    pub(crate) document_number: String,
+    /// Fiscal document sign.
+    pub(crate) fiscal_sign: String,
+    /// Staged receipt transaction.
+    #[serde(flatten)]
+    pub(crate) preparation: PrepareResponse,
+}
+
 /// Suggestion result for display.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub(crate) struct SuggestResponse {
     /// Normalized payee name.
     payee: Option<String>,
@@ -396,6 +2658,9 @@ pub(crate) struct SuggestResponse {
     merchant: Option<String>,
     /// Suggested category tag names.
     tags: Vec<String>,
+    /// Suggested category tag IDs, in the same order as `tags`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tag_ids: Option<Vec<String>>,
 }
 
 impl SuggestResponse {
@@ -415,6 +2680,7 @@ impl SuggestResponse {
             payee: resp.payee.clone(),
             merchant: resp.merchant.as_ref().map(ToString::to_string),
             tags,
+            tag_ids: maps.raw_tag_ids(resp.tag.as_deref()),
         }
     }
 }
@@ -433,14 +2699,28 @@ pub(crate) fn build_lookup_maps(
                 .account_instruments
                 .insert(acc.id.to_string(), instrument_id.into_inner());
         }
+        if acc.savings == Some(true) {
+            let _existed_savings = maps.savings_accounts.insert(acc.id.to_string());
+        }
+        if let Some(start) = acc.start_date {
+            let _existed_start = maps.account_starts.insert(acc.id.to_string(), start);
+        }
     }
     for tag in tags {
         let _existed = maps.tags.insert(tag.id.to_string(), tag.title.clone());
+        if let Some(parent) = tag.parent.as_ref() {
+            let _existed_parent = maps
+                .tag_parents
+                .insert(tag.id.to_string(), parent.to_string());
+        }
     }
     for instr in instruments {
         let _existed = maps
             .instruments
             .insert(instr.id.into_inner(), instr.symbol.clone());
+        let _existed_rate = maps
+            .instrument_rates
+            .insert(instr.id.into_inner(), instr.rate);
     }
     maps
 }
@@ -462,8 +2742,8 @@ mod tests {
         Transaction, TransactionId, UserId,
     };
 
-    fn sample_maps() -> LookupMaps {
-        let accounts = vec![Account {
+    fn sample_account() -> Account {
+        Account {
             id: AccountId::new("acc-1".to_owned()),
             changed: DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp for test"),
             user: UserId::new(1),
@@ -490,7 +2770,47 @@ mod tests {
             payoff_interval: None,
             balance_correction_type: None,
             private: None,
-        }];
+        }
+    }
+
+    fn sample_transaction() -> Transaction {
+        Transaction {
+            id: TransactionId::new("tx-1".to_owned()),
+            changed: DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp for test"),
+            created: DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp for test"),
+            user: UserId::new(1),
+            deleted: false,
+            hold: None,
+            income_instrument: InstrumentId::new(1),
+            income_account: AccountId::new("acc-1".to_owned()),
+            income: 0.0,
+            outcome_instrument: InstrumentId::new(1),
+            outcome_account: AccountId::new("acc-1".to_owned()),
+            outcome: 500.0,
+            tag: Some(vec![TagId::new("tag-1".to_owned())]),
+            merchant: None,
+            payee: Some("Test Payee".to_owned()),
+            original_payee: None,
+            comment: Some("test comment".to_owned()),
+            date: NaiveDate::from_ymd_opt(2024, 6, 15).expect("valid date for test"),
+            mcc: None,
+            reminder_marker: None,
+            op_income: None,
+            op_income_instrument: None,
+            op_outcome: None,
+            op_outcome_instrument: None,
+            latitude: None,
+            longitude: None,
+            income_bank_id: None,
+            outcome_bank_id: None,
+            qr_code: None,
+            source: None,
+            viewed: None,
+        }
+    }
+
+    fn sample_maps() -> LookupMaps {
+        let accounts = vec![sample_account()];
         let tags = vec![Tag {
             id: TagId::new("tag-1".to_owned()),
             changed: DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp for test"),
@@ -572,6 +2892,17 @@ mod tests {
         assert!(!resp.archive);
     }
 
+    #[test]
+    fn account_response_shows_company() {
+        let maps = sample_maps();
+        let account = Account {
+            company: Some(CompanyId::new(4)),
+            ..sample_account()
+        };
+        let resp = AccountResponse::from_account(&account, &maps);
+        assert_eq!(resp.company.as_deref(), Some("4"));
+    }
+
     #[test]
     fn transaction_response_resolves_names() {
         let maps = sample_maps();
@@ -616,6 +2947,43 @@ mod tests {
         assert_eq!(resp.payee.as_deref(), Some("Test Payee"));
     }
 
+    #[test]
+    fn transaction_response_resolves_merchant() {
+        use zenmoney_rs::models::{Merchant, MerchantId};
+        let merchant = Merchant {
+            id: MerchantId::new("m-1".to_owned()),
+            changed: DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp"),
+            user: UserId::new(1),
+            title: "Coffee Shop".to_owned(),
+        };
+        let maps = sample_maps().with_merchants(&[merchant]);
+        let tx = Transaction {
+            merchant: Some(MerchantId::new("m-1".to_owned())),
+            ..sample_transaction()
+        };
+        let resp = TransactionResponse::from_transaction(&tx, &maps);
+        assert_eq!(resp.merchant.as_deref(), Some("Coffee Shop"));
+    }
+
+    #[test]
+    fn transaction_response_includes_raw_ids() {
+        use zenmoney_rs::models::MerchantId;
+        let maps = sample_maps();
+        let tx = Transaction {
+            merchant: Some(MerchantId::new("m-1".to_owned())),
+            ..sample_transaction()
+        };
+        let resp = TransactionResponse::from_transaction(&tx, &maps);
+        assert_eq!(resp.income_account_id.as_deref(), Some("acc-1"));
+        assert_eq!(resp.tag_ids, Some(vec!["tag-1".to_owned()]));
+        assert_eq!(resp.merchant_id.as_deref(), Some("m-1"));
+
+        let without_ids = TransactionResponse::from_transaction(&tx, &maps.with_ids(false));
+        assert!(without_ids.outcome_account_id.is_none());
+        assert!(without_ids.tag_ids.is_none());
+        assert!(without_ids.merchant_id.is_none());
+    }
+
     // ── interval_label ──────────────────────────────────────────────
 
     #[test]
@@ -696,6 +3064,79 @@ mod tests {
         assert_eq!(resp.title, "Coffee Shop");
     }
 
+    // ── UserResponse / CompanyResponse ──────────────────────────────
+
+    #[test]
+    fn user_response_resolves_currency() {
+        use zenmoney_rs::models::User;
+        let user = User {
+            id: UserId::new(2),
+            changed: DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp"),
+            login: Some("partner".to_owned()),
+            currency: InstrumentId::new(1),
+            parent: Some(UserId::new(1)),
+            country: None,
+            country_code: None,
+            email: None,
+            is_forecast_enabled: None,
+            month_start_day: None,
+            paid_till: None,
+            plan_balance_mode: None,
+            plan_settings: None,
+            subscription: None,
+            subscription_renewal_date: None,
+        };
+        let resp = super::UserResponse::from_user(&user, &sample_maps());
+        assert_eq!(resp.id, 2);
+        assert_eq!(resp.currency, "\u{20bd}");
+        assert_eq!(resp.parent_user_id, Some(1));
+    }
+
+    #[test]
+    fn lookup_maps_user_login_only_for_shared_workspaces() {
+        use zenmoney_rs::models::User;
+        let user = |id: i64, login: &str| User {
+            id: UserId::new(id),
+            changed: DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp"),
+            login: Some(login.to_owned()),
+            currency: InstrumentId::new(1),
+            parent: None,
+            country: None,
+            country_code: None,
+            email: None,
+            is_forecast_enabled: None,
+            month_start_day: None,
+            paid_till: None,
+            plan_balance_mode: None,
+            plan_settings: None,
+            subscription: None,
+            subscription_renewal_date: None,
+        };
+        let single = sample_maps().with_users(&[user(1, "owner")]);
+        assert!(single.user_login(1).is_none());
+        let shared = sample_maps().with_users(&[user(1, "owner"), user(2, "partner")]);
+        assert_eq!(shared.user_login(2).as_deref(), Some("partner"));
+        assert!(shared.user_login(3).is_none());
+    }
+
+    #[test]
+    fn lookup_maps_company_name() {
+        use zenmoney_rs::models::{Company, CompanyId};
+        let company = Company {
+            id: CompanyId::new(4),
+            changed: DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp"),
+            title: "Tinkoff".to_owned(),
+            full_title: None,
+            www: None,
+            country: None,
+            country_code: None,
+            deleted: None,
+        };
+        let maps = sample_maps().with_companies(&[company]);
+        assert_eq!(maps.company_name(4), "Tinkoff");
+        assert_eq!(maps.company_name(5), "5");
+    }
+
     // ── BudgetResponse ──────────────────────────────────────────────
 
     #[test]
@@ -830,6 +3271,57 @@ mod tests {
         assert!((resp.rate - 90.5).abs() < f64::EPSILON);
     }
 
+    // ── Currency conversion ─────────────────────────────────────────
+
+    #[test]
+    fn lookup_convert_uses_rates() {
+        let maps = sample_maps();
+        let same = maps.convert(250.0, 1, 1).expect("known instrument");
+        assert!((same - 250.0).abs() < f64::EPSILON);
+        assert!(maps.convert(250.0, 1, 999).is_none());
+    }
+
+    #[test]
+    fn conversion_response_computes_rate() {
+        let rub = Instrument {
+            id: InstrumentId::new(1),
+            changed: DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp"),
+            title: "Russian Ruble".to_owned(),
+            short_title: "RUB".to_owned(),
+            symbol: "\u{20bd}".to_owned(),
+            rate: 1.0,
+        };
+        let usd = Instrument {
+            id: InstrumentId::new(2),
+            changed: DateTime::from_timestamp(1_600_000_000, 0).expect("valid timestamp"),
+            title: "US Dollar".to_owned(),
+            short_title: "USD".to_owned(),
+            symbol: "$".to_owned(),
+            rate: 90.0,
+        };
+        let resp = super::ConversionResponse::from_instruments(9_000.0, &rub, &usd)
+            .expect("positive target rate");
+        assert!((resp.converted_amount - 100.0).abs() < 1e-9);
+        assert_eq!(resp.from_currency, "RUB");
+        assert_eq!(resp.to_currency, "USD");
+        assert!(resp.rates_as_of.starts_with("2020-09-13"));
+    }
+
+    #[test]
+    fn conversion_response_rejects_zero_rate() {
+        let mut instr = Instrument {
+            id: InstrumentId::new(3),
+            changed: DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp"),
+            title: "Broken".to_owned(),
+            short_title: "BRK".to_owned(),
+            symbol: "B".to_owned(),
+            rate: 1.0,
+        };
+        let source = instr.clone();
+        instr.rate = 0.0;
+        assert!(super::ConversionResponse::from_instruments(1.0, &source, &instr).is_none());
+    }
+
     // ── DeletedTransactionResponse ──────────────────────────────────
 
     #[test]
@@ -942,6 +3434,7 @@ mod tests {
             total: 42,
             offset: 10,
             limit: 20,
+            summary: None,
         };
         let json = serde_json::to_value(&page).expect("should serialize");
         assert_eq!(json["total"], 42);
@@ -0,0 +1,127 @@
+//! Review watermark for the `inbox` tool.
+//!
+//! The inbox lists the transactions that need attention: those not viewed
+//! yet and those created after the last review. `mark_reviewed` moves the
+//! watermark forward, so the next conversation starts from what arrived
+//! since. The watermark is stored as a JSON file next to the ZenMoney
+//! storage data.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::persist::{lock_err, read_json_list, write_json_list};
+
+/// File name used for the review watermark inside the data directory.
+const REVIEW_FILE: &str = "review.json";
+
+/// The last completed review.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct ReviewMark {
+    /// Transactions created at or before this time count as reviewed.
+    pub(crate) reviewed_through: DateTime<Utc>,
+    /// When the review was recorded.
+    pub(crate) marked_at: DateTime<Utc>,
+}
+
+/// Review watermark, optionally backed by a JSON file.
+#[derive(Debug, Default)]
+pub struct ReviewStore {
+    /// Path of the backing file (`None` keeps the watermark in memory only).
+    path: Option<PathBuf>,
+    /// Current watermark; `None` until the first review.
+    mark: Mutex<Option<ReviewMark>>,
+}
+
+impl ReviewStore {
+    /// Opens the review store inside `dir`, loading the saved watermark.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read or parsed.
+    #[inline]
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        let path = dir.join(REVIEW_FILE);
+        let saved: Vec<ReviewMark> = read_json_list(&path)?;
+        Ok(Self {
+            path: Some(path),
+            mark: Mutex::new(saved.last().copied()),
+        })
+    }
+
+    /// Returns the current watermark.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned.
+    pub(crate) fn mark(&self) -> io::Result<Option<ReviewMark>> {
+        self.mark
+            .lock()
+            .map(|mark| *mark)
+            .map_err(|err| lock_err(&err))
+    }
+
+    /// Replaces the watermark.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned or the file cannot be written.
+    pub(crate) fn set(&self, mark: ReviewMark) -> io::Result<()> {
+        let mut current = self.mark.lock().map_err(|err| lock_err(&err))?;
+        *current = Some(mark);
+        // Keep the lock while writing so concurrent reviews cannot interleave.
+        let persisted = self
+            .path
+            .as_deref()
+            .map_or(Ok(()), |path| write_json_list(path, &[&mark]));
+        drop(current);
+        persisted
+    }
+
+    /// Forgets the watermark, returning whether one was set.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned or the file cannot be written.
+    pub(crate) fn clear(&self) -> io::Result<bool> {
+        let mut current = self.mark.lock().map_err(|err| lock_err(&err))?;
+        let cleared = current.take().is_some();
+        let persisted = self
+            .path
+            .as_deref()
+            .map_or(Ok(()), |path| write_json_list::<ReviewMark>(path, &[]));
+        drop(current);
+        persisted.map(|()| cleared)
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect for readability"
+)]
+mod tests {
+    use chrono::DateTime;
+
+    use super::{ReviewMark, ReviewStore};
+    use crate::persist::TempDir;
+
+    #[test]
+    fn watermark_survives_reopen() {
+        let dir = TempDir::new();
+        let store = ReviewStore::open(&dir).expect("open empty store");
+        assert_eq!(store.mark().expect("read mark"), None);
+        let time = DateTime::from_timestamp(1_718_409_600, 0).expect("valid timestamp");
+        let mark = ReviewMark {
+            reviewed_through: time,
+            marked_at: time,
+        };
+        store.set(mark).expect("save mark");
+        let reopened = ReviewStore::open(&dir).expect("reopen store");
+        assert_eq!(reopened.mark().expect("read mark"), Some(mark));
+    }
+}
@@ -0,0 +1,464 @@
+//! Scheduled report generation to files.
+//!
+//! `ZENMONEY_SCHEDULE` names a JSON file of jobs, each pairing a five-field
+//! cron expression (minute, hour, day of month, month, day of week, in UTC)
+//! with a report: `digest`, `budget_progress` or `export_csv`. While the
+//! server runs, every job syncs when it comes due and writes its report
+//! either as a new timestamped file in `directory` or appended to
+//! `append_to`. A stdio server lives only as long as its MCP client, so
+//! `zenmoney-mcp --run-schedule` runs the jobs on their own, without serving
+//! MCP; kept running by a service manager, it produces a weekly summary
+//! even when no chat session is open.
+//!
+//! ```json
+//! {"jobs": [{"name": "weekly", "cron": "0 8 * * MON", "report": "digest",
+//!            "arguments": {"period": "week"}, "directory": "/srv/reports"}]}
+//! ```
+
+use std::io::{self, Write as _};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Datelike as _, NaiveDate, TimeDelta, Timelike as _, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::params::{BudgetProgressParams, CsvReportParams, DigestParams};
+
+/// Environment variable naming the JSON file of scheduled report jobs.
+pub const SCHEDULE_ENV: &str = "ZENMONEY_SCHEDULE";
+
+/// Days searched ahead for the next run before a schedule is considered dead.
+const MAX_SEARCH_DAYS: u32 = 5 * 366;
+
+/// Month names accepted in the month field, January first.
+const MONTH_NAMES: &[&str] = &[
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+/// Day names accepted in the day-of-week field, Sunday first.
+const WEEKDAY_NAMES: &[&str] = &["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// A parsed five-field cron expression, evaluated in UTC.
+///
+/// Each field is a bit set of the values it matches. As in classic cron,
+/// when both day fields are restricted a day matching either one runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CronSchedule {
+    /// Minutes 0-59.
+    minutes: u64,
+    /// Hours 0-23.
+    hours: u64,
+    /// Days of the month 1-31.
+    days: u64,
+    /// Months 1-12.
+    months: u64,
+    /// Days of the week 0-6, Sunday being 0.
+    weekdays: u64,
+    /// Whether both day fields are restricted (neither starts with `*`).
+    either_day: bool,
+}
+
+/// Returns whether bit `value` is set in `bits`.
+const fn has(bits: u64, value: u32) -> bool {
+    value < u64::BITS && (bits >> value) & 1 == 1
+}
+
+/// Parses one value of a field: a number or a name from `names`.
+fn field_value(text: &str, (low, high): (u32, u32), names: &[&str]) -> Result<u32, String> {
+    let named = names
+        .iter()
+        .position(|name| name.eq_ignore_ascii_case(text))
+        .and_then(|index| u32::try_from(index).ok())
+        .map(|index| index.saturating_add(low));
+    named
+        .or_else(|| text.parse().ok())
+        .filter(|value| (low..=high).contains(value))
+        .ok_or_else(|| format!("'{text}' is not a value between {low} and {high}"))
+}
+
+/// Parses a cron field of comma-separated values, ranges and `/` steps.
+fn parse_field(field: &str, range: (u32, u32), names: &[&str]) -> Result<u64, String> {
+    let mut bits = 0_u64;
+    for part in field.split(',') {
+        let (span, step) = match part.split_once('/') {
+            Some((span, step)) => (
+                span,
+                step.parse::<usize>()
+                    .ok()
+                    .filter(|step| *step > 0)
+                    .ok_or_else(|| format!("bad step in '{part}'"))?,
+            ),
+            None => (part, 1),
+        };
+        let (first, last) = if span == "*" {
+            range
+        } else if let Some((start, end)) = span.split_once('-') {
+            (
+                field_value(start, range, names)?,
+                field_value(end, range, names)?,
+            )
+        } else {
+            let single = field_value(span, range, names)?;
+            (single, if step > 1 { range.1 } else { single })
+        };
+        if first > last {
+            return Err(format!("range '{span}' runs backwards"));
+        }
+        for value in (first..=last).step_by(step) {
+            bits |= 1_u64 << value;
+        }
+    }
+    Ok(bits)
+}
+
+impl CronSchedule {
+    /// Parses `minute hour day-of-month month day-of-week`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a message if the expression does not have five fields or a
+    /// field holds a value out of range.
+    pub(crate) fn parse(expression: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = *fields.as_slice() else {
+            return Err(format!(
+                "cron expression '{expression}' must have five fields: \
+                 minute hour day-of-month month day-of-week"
+            ));
+        };
+        let invalid = |message: String| format!("cron expression '{expression}': {message}");
+        let sundays = parse_field(weekday, (0, 7), WEEKDAY_NAMES).map_err(invalid)?;
+        Ok(Self {
+            minutes: parse_field(minute, (0, 59), &[]).map_err(invalid)?,
+            hours: parse_field(hour, (0, 23), &[]).map_err(invalid)?,
+            days: parse_field(day, (1, 31), &[]).map_err(invalid)?,
+            months: parse_field(month, (1, 12), MONTH_NAMES).map_err(invalid)?,
+            weekdays: (sundays | (sundays >> 7_u32)) & 0x7f,
+            either_day: !day.starts_with('*') && !weekday.starts_with('*'),
+        })
+    }
+
+    /// Returns whether the schedule runs on `date`.
+    fn runs_on(&self, date: NaiveDate) -> bool {
+        let day = has(self.days, date.day());
+        let weekday = has(self.weekdays, date.weekday().num_days_from_sunday());
+        let day_matches = if self.either_day {
+            day || weekday
+        } else {
+            day && weekday
+        };
+        has(self.months, date.month()) && day_matches
+    }
+
+    /// Returns the first run strictly after `after`, or `None` if the
+    /// schedule never comes due in the next five years.
+    pub(crate) fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let start = after
+            .naive_utc()
+            .with_second(0)?
+            .with_nanosecond(0)?
+            .checked_add_signed(TimeDelta::minutes(1))?;
+        let mut date = start.date();
+        for _day in 0..MAX_SEARCH_DAYS {
+            if self.runs_on(date) {
+                let hours = (0..24_u32).filter(|hour| has(self.hours, *hour));
+                for hour in hours {
+                    let minutes = (0..60_u32).filter(|minute| has(self.minutes, *minute));
+                    for minute in minutes {
+                        let candidate = date.and_hms_opt(hour, minute, 0)?;
+                        if candidate >= start {
+                            return Some(candidate.and_utc());
+                        }
+                    }
+                }
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+}
+
+/// Report produced by a scheduled job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum ReportKind {
+    /// The `digest` tool's JSON summary.
+    Digest,
+    /// The `budget_progress` tool's JSON report.
+    BudgetProgress,
+    /// Matching transactions as CSV.
+    ExportCsv,
+}
+
+impl ReportKind {
+    /// Extension of the files written for this report.
+    const fn extension(self) -> &'static str {
+        match self {
+            Self::Digest | Self::BudgetProgress => "json",
+            Self::ExportCsv => "csv",
+        }
+    }
+
+    /// Checks that `arguments` are valid parameters of this report.
+    fn check_arguments(self, arguments: &Value) -> Result<(), serde_json::Error> {
+        let arguments = arguments.clone();
+        match self {
+            Self::Digest => serde_json::from_value::<DigestParams>(arguments).map(drop),
+            Self::BudgetProgress => {
+                serde_json::from_value::<BudgetProgressParams>(arguments).map(drop)
+            }
+            Self::ExportCsv => serde_json::from_value::<CsvReportParams>(arguments).map(drop),
+        }
+    }
+}
+
+/// One job of the schedule file, as written.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JobConfig {
+    /// Job name, used in file names and logs.
+    name: String,
+    /// Five-field cron expression in UTC.
+    cron: String,
+    /// Report to run.
+    report: ReportKind,
+    /// Report parameters, as the matching tool takes them.
+    #[serde(default)]
+    arguments: Option<Value>,
+    /// Directory receiving one file per run.
+    directory: Option<PathBuf>,
+    /// File each run is appended to.
+    append_to: Option<PathBuf>,
+}
+
+/// Contents of the schedule file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScheduleConfig {
+    /// Configured jobs.
+    jobs: Vec<JobConfig>,
+}
+
+/// A validated report job.
+#[derive(Debug, Clone)]
+pub struct ScheduledJob {
+    /// Job name, used in file names and logs.
+    pub(crate) name: String,
+    /// When the job runs.
+    pub(crate) schedule: CronSchedule,
+    /// Report to run.
+    pub(crate) report: ReportKind,
+    /// Report parameters.
+    pub(crate) arguments: Value,
+    /// Directory receiving a new `<name>-YYYYMMDD-HHMMSS.<ext>` file per
+    /// run, or with `append` the file every run is appended to.
+    pub(crate) output: PathBuf,
+    /// Whether `output` is a file appended to rather than a directory.
+    pub(crate) append: bool,
+}
+
+impl ScheduledJob {
+    /// Validates one configured job.
+    fn from_config(config: JobConfig) -> Result<Self, String> {
+        let JobConfig {
+            name,
+            cron,
+            report,
+            arguments,
+            directory,
+            append_to,
+        } = config;
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|ch| ch.is_alphanumeric() || "-_.".contains(ch))
+        {
+            return Err(format!(
+                "job name '{name}' must be non-empty letters, digits, '-', '_' or '.'"
+            ));
+        }
+        let schedule = CronSchedule::parse(&cron).map_err(|err| format!("job '{name}': {err}"))?;
+        let arguments = arguments.unwrap_or_else(|| Value::Object(serde_json::Map::new()));
+        report
+            .check_arguments(&arguments)
+            .map_err(|err| format!("job '{name}': invalid arguments: {err}"))?;
+        let (output, append) = match (directory, append_to) {
+            (Some(dir), None) => (dir, false),
+            (None, Some(file)) => (file, true),
+            (Some(_), Some(_)) | (None, None) => {
+                return Err(format!(
+                    "job '{name}' needs exactly one of 'directory' and 'append_to'"
+                ));
+            }
+        };
+        Ok(Self {
+            name,
+            schedule,
+            report,
+            arguments,
+            output,
+            append,
+        })
+    }
+
+    /// Writes the report of the run at `ran_at`, returning the file written.
+    ///
+    /// Appended JSON reports become one `{job, ran_at, report, result}` line;
+    /// appended CSV keeps its header only when the file is new or empty.
+    pub(crate) fn write_report(&self, ran_at: DateTime<Utc>, report: &str) -> io::Result<PathBuf> {
+        if !self.append {
+            std::fs::create_dir_all(&self.output)?;
+            let file = self.output.join(format!(
+                "{}-{}.{}",
+                self.name,
+                ran_at.format("%Y%m%d-%H%M%S"),
+                self.report.extension()
+            ));
+            std::fs::write(&file, report)?;
+            return Ok(file);
+        }
+        let is_new = !std::fs::metadata(&self.output).is_ok_and(|meta| meta.len() > 0);
+        let record = self.appended_record(ran_at, report, is_new)?;
+        if let Some(parent) = self
+            .output
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut out = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.output)?;
+        out.write_all(record.as_bytes())?;
+        Ok(self.output.clone())
+    }
+
+    /// Builds the text appended to the output file for one run.
+    fn appended_record(
+        &self,
+        ran_at: DateTime<Utc>,
+        report: &str,
+        is_new: bool,
+    ) -> io::Result<String> {
+        if self.report == ReportKind::ExportCsv {
+            let rows = if is_new {
+                report
+            } else {
+                report.split_once('\n').map_or("", |(_header, rows)| rows)
+            };
+            return Ok(rows.to_owned());
+        }
+        let result: Value = serde_json::from_str(report).map_err(io::Error::other)?;
+        let line = serde_json::json!({
+            "job": self.name,
+            "ran_at": ran_at.to_rfc3339(),
+            "report": self.report,
+            "result": result,
+        });
+        Ok(format!("{line}\n"))
+    }
+}
+
+/// Reads and validates the scheduled jobs in the file at `path`.
+///
+/// # Errors
+///
+/// Returns a message if the file cannot be read or parsed, or a job has an
+/// invalid name, cron expression, arguments or output.
+#[inline]
+pub fn load_schedule(path: &Path) -> Result<Vec<ScheduledJob>, String> {
+    let text = std::fs::read_to_string(path).map_err(|err| {
+        format!(
+            "cannot read {SCHEDULE_ENV} file '{}': {err}",
+            path.display()
+        )
+    })?;
+    let config: ScheduleConfig = serde_json::from_str(&text)
+        .map_err(|err| format!("invalid {SCHEDULE_ENV} file '{}': {err}", path.display()))?;
+    let jobs = config
+        .jobs
+        .into_iter()
+        .map(ScheduledJob::from_config)
+        .collect::<Result<Vec<ScheduledJob>, String>>()?;
+    for (index, job) in jobs.iter().enumerate() {
+        if jobs
+            .iter()
+            .skip(index.saturating_add(1))
+            .any(|other| other.name == job.name)
+        {
+            return Err(format!("job name '{}' is used twice", job.name));
+        }
+    }
+    Ok(jobs)
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect for readability"
+)]
+mod tests {
+    use chrono::{DateTime, Utc};
+    use serde_json::json;
+
+    use super::{CronSchedule, JobConfig, ReportKind, ScheduledJob};
+
+    fn at(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text)
+            .expect("valid timestamp")
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn finds_next_cron_run() {
+        let weekly = CronSchedule::parse("0 8 * * MON").expect("should parse");
+        // 2024-06-15 is a Saturday.
+        let next = weekly.next_after(at("2024-06-15T10:30:00Z"));
+        assert_eq!(next, Some(at("2024-06-17T08:00:00Z")));
+        assert_eq!(
+            weekly.next_after(at("2024-06-17T08:00:00Z")),
+            Some(at("2024-06-24T08:00:00Z"))
+        );
+
+        let stepped = CronSchedule::parse("*/15 9-10 1,15 * 7").expect("should parse");
+        assert_eq!(
+            stepped.next_after(at("2024-06-15T10:50:00Z")),
+            Some(at("2024-06-16T09:00:00Z"))
+        );
+
+        assert!(CronSchedule::parse("0 8 * *").is_err());
+        assert!(CronSchedule::parse("60 * * * *").is_err());
+        assert!(CronSchedule::parse("0 8 * FOO *").is_err());
+        let never = CronSchedule::parse("0 8 31 2 *").expect("should parse");
+        assert!(never.next_after(at("2024-01-01T00:00:00Z")).is_none());
+    }
+
+    #[test]
+    fn validates_job_config() {
+        let config = |value: serde_json::Value| -> JobConfig {
+            serde_json::from_value(value).expect("valid config shape")
+        };
+        let job = ScheduledJob::from_config(config(json!({
+            "name": "weekly", "cron": "0 8 * * 1", "report": "digest",
+            "arguments": {"period": "week"}, "directory": "/tmp/reports",
+        })))
+        .expect("valid job");
+        assert_eq!(job.report, ReportKind::Digest);
+        assert!(
+            ScheduledJob::from_config(config(json!({
+                "name": "bad", "cron": "0 8 * * 1", "report": "digest",
+                "arguments": {"period": "fortnight"}, "directory": "/tmp/reports",
+            })))
+            .is_err()
+        );
+        assert!(
+            ScheduledJob::from_config(config(json!({
+                "name": "both", "cron": "0 8 * * 1", "report": "export_csv",
+                "directory": "/tmp/reports", "append_to": "/tmp/reports.csv",
+            })))
+            .is_err()
+        );
+    }
+}
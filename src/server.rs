@@ -4,36 +4,141 @@
 
 extern crate alloc;
 
+use alloc::collections::{BTreeMap, BTreeSet};
 use alloc::sync::Arc;
-use std::collections::HashMap;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
-
-use rmcp::handler::server::tool::ToolRouter;
-use rmcp::handler::server::wrapper::Parameters;
-use rmcp::model::{CallToolResult, Content, ServerCapabilities, ServerInfo};
-use rmcp::{ErrorData as McpError, ServerHandler, tool, tool_handler, tool_router};
+use std::time::Instant;
+
+use rmcp::handler::server::common::cached_schema_for_type;
+use rmcp::handler::server::tool::{ToolCallContext, ToolRouter};
+use rmcp::model::{
+    CallToolRequestParam, CallToolResult, CompleteRequestParam, CompleteResult, Content,
+    ListResourceTemplatesResult, ListResourcesResult, ListToolsResult, PaginatedRequestParam,
+    ReadResourceRequestParam, ReadResourceResult, ResourceContents, ServerCapabilities, ServerInfo,
+    SetLevelRequestParam, SubscribeRequestParam, UnsubscribeRequestParam,
+};
+use rmcp::service::{NotificationContext, RequestContext};
+use rmcp::{ErrorData as McpError, RoleServer, ServerHandler, tool, tool_router};
 use zenmoney_rs::models::{
-    AccountId, InstrumentId, MerchantId, NaiveDate, SuggestRequest, Tag, TagId, Transaction,
-    TransactionId, UserId,
+    Account, AccountId, AccountType, Budget, CompanyId, Instrument, InstrumentId, Interval,
+    Merchant, MerchantId, NaiveDate, Reminder, ReminderId, ReminderMarker, ReminderMarkerState,
+    SuggestRequest, Tag, TagId, Transaction, TransactionId, UserId,
 };
 #[cfg(test)]
 use zenmoney_rs::storage::InMemoryStorage;
 use zenmoney_rs::storage::{FileStorage, Storage};
 use zenmoney_rs::zen_money::{TransactionFilter, ZenMoney};
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike as _, Days, Months, NaiveTime, Utc};
 
+use crate::analytics::{
+    self, BillWindow, account_activity, account_changes, balance_history, bill_totals,
+    budget_adherence, budget_months, budget_overruns, budget_progress, check_balances,
+    compare_categories, daily_net_changes, debt_balances, envelope_balances, expense_amount,
+    group_transactions, has_tag_or_child, income_amount, is_near, last_activity_by_account,
+    payee_spend, percent_change, pooled_daily_average, rank_payees, roll_up_tags, round_cents,
+    savings_by_month, statement_period, suggest_budget_amounts, summarize_transactions, tag_usage,
+    transaction_label, transfer_matrix, unconverted_currencies, upcoming_bills,
+};
+use crate::backup::{
+    BackupEntities, SNAPSHOT_SCHEMA_VERSION, SnapshotRange, backup_path, read_backup,
+    snapshot_path, write_backup, write_snapshot,
+};
+use crate::compact;
+use crate::completion::{CompletionSource, complete_ids};
+use crate::confirm::Confirmation;
+use crate::conflicts::{self, Conflict, ConflictStore};
+use crate::date_guard::DateWindow;
+use crate::default_account::DefaultAccountStore;
+use crate::demo;
+use crate::envelopes::{Envelope, EnvelopeStore};
+use crate::export::{ExportFormat, export_file_name, render_csv, render_ofx, render_qif};
+use crate::freshness::{SyncClock, add_skip_sync_param, skip_requested};
+use crate::fuzzy;
+use crate::instructions::{DEFAULT_INSTRUCTIONS, compose as compose_instructions, session_block};
+use crate::ledger::{journal_path, render_journal};
+use crate::lenient::Parameters;
+use crate::loans::{
+    add_intervals, amortization_schedule, deposit_schedule, period_dates, period_rate,
+};
+use crate::logging::ClientLog;
+use crate::merge::{check_mergeable, fill_missing, hold_pairs};
+use crate::ofx::{ImportVerdict, OfxEntry, match_entries, parse_ofx};
 use crate::params::{
-    BulkOperation, BulkOperationsParams, CreateTagParams, CreateTransactionParams,
-    DeleteTransactionParams, ExecuteBulkParams, FindAccountParams, FindTagParams,
-    GetInstrumentParams, ListAccountsParams, ListBudgetsParams, ListTransactionsParams,
-    SortDirection, SuggestCategoryParams, TransactionType, UpdateTransactionParams,
+    AccountBalanceHistoryParams, AccountSort, AllocateEnvelopeParams, BackupDataParams,
+    BudgetProgressParams, BulkOperation, BulkOperationsParams, CardStatementParams,
+    ComparePeriodsParams, ConflictResolution, ConvertAmountParams, ConvertPlannedParams,
+    CreateFromTemplateParams, CreateMerchantParams, CreateRecurringParams, CreateTagParams,
+    CreateTransactionParams, CsvReportParams, DebtsReportParams, DeleteTransactionParams,
+    DetectAnomaliesParams, DigestParams, DigestPeriod, DuplicateTransactionParams, EntityKind,
+    EnvelopeStatusParams, ExecuteBulkParams, ExportLedgerParams, ExportParams,
+    ExportSnapshotParams, FindAccountParams, FindInstrumentParams, FindMerchantParams,
+    FindTagParams, GetAccountParams, GetInstrumentParams, GetRawEntityParams, GetTagParams,
+    Granularity, GroupBy, ImportOfxParams, ImportReceiptQrParams, InboxParams, LinkMerchantParams,
+    ListAccountsParams, ListBudgetsParams, ListCompaniesParams, ListDeletedTransactionsParams,
+    ListInstrumentsParams, ListReminderMarkersParams, ListTagsParams, ListTransactionsParams,
+    LoanScheduleParams, LogExpenseParams, MarkReviewedParams, MarkViewedParams, MarkerAction,
+    MarkerState, MergeMerchantsParams, MergeTransactionsParams, MoveTransactionsParams,
+    PayeeDimension, PendingChangesParams, PurgeLocalDataParams, RankBy, RawEntityKind,
+    ReconcileAccountParams, RecurrenceFrequency, RecurringMode, RenameMerchantParams,
+    ResolveConflictParams, RestoreDataParams, RestoreMode, RestoreTransactionParams,
+    SaveTemplateParams, SavingsRateParams, SearchEntitiesParams, SearchKind,
+    SetDefaultAccountParams, SetMarkerStateParams, SortDirection, SpendingPatternsParams,
+    StatementCycle, SuggestBudgetsParams, SuggestCategoryParams, SyncEntitiesParams,
+    TagTransactionsParams, TagTreeParams, TopPayeesParams, TransactionSelection, TransactionType,
+    TransferReportParams, UncategorizedReportParams, UpcomingBillsParams, UpdateTransactionParams,
+    VerifyBalancesParams, YearReviewParams,
+};
+use crate::pending::{PendingChange, PendingQueue, is_unreachable};
+use crate::planned::PlannedStore;
+use crate::progress::Progress;
+use crate::receipt::{FiscalReceipt, parse_receipt_qr};
+use crate::replay;
+use crate::resources::{
+    self, EntityUri, ResourceStamp, ResourceWatch, account_uri, budget_uri, stamp_resources,
+    transaction_uri,
 };
 use crate::response::{
-    AccountResponse, BudgetResponse, BulkOperationsResponse, DeletedTransactionResponse,
-    InstrumentResponse, LookupMaps, MerchantResponse, PaginatedTransactions, PrepareResponse,
-    ReminderResponse, SuggestResponse, TagResponse, TransactionResponse, build_lookup_maps,
+    AccountChange, AccountDetailsResponse, AccountGroup, AccountGroupsResponse, AccountResponse,
+    AllocatedEnvelopeResponse, AnomaliesResponse, AnomalyResponse, BackupResponse, BalanceCheck,
+    BalanceHistoryResponse, BudgetAdherence, BudgetOverrun, BudgetProgress, BudgetProgressResponse,
+    BudgetRangeResponse, BudgetResponse, BudgetSuggestion, BudgetSuggestionsResponse,
+    BulkCancellation, BulkOperationsResponse, CardStatementResponse, CategoryDelta, ChangeCounts,
+    CompanyResponse, ComparePeriodsResponse, ConflictResponse, ConflictsResponse,
+    ConversionResponse, DebtsReportResponse, DefaultAccountResponse, DeletedTransactionResponse,
+    DigestAmount, DigestResponse, DigestTransaction, EntityMatch, EntitySearchResponse,
+    EnvelopeStatusResponse, ExportResponse, ExportedFile, FindResponse, GroupedTransactions,
+    InboxResponse, InstrumentResponse, LedgerExportResponse, LoanScheduleResponse,
+    LogExpenseResponse, LookupMaps, MarkReviewedResponse, MarkerStateResponse, MerchantResponse,
+    MergeMerchantsResponse, OfxImportResponse, OfxImportRow, PaginatedTransactions,
+    PendingChangeResponse, PendingChangesResponse, PlannedResponse, PrepareResponse, PurgeResponse,
+    RankedMatch, ReceiptImportResponse, ReconcileResponse, ReminderMarkerResponse,
+    ReminderResponse, RestoreResponse, ReviewInboxResponse, SavedTemplateResponse, SavingsMonth,
+    SavingsRateResponse, ServerInfoResponse, ServerLimits, SnapshotResponse,
+    SpendingPatternsResponse, SuggestResponse, SyncChangesResponse, SyncEntitiesResponse,
+    TagDetailsResponse, TagNode, TagResponse, TopPayeesResponse, TransactionResponse,
+    TransferReportResponse, UncategorizedPayee, UncategorizedReportResponse, UpcomingBillsResponse,
+    UsageStatsResponse, UserResponse, VerifyBalancesResponse, YearReviewResponse,
+    build_lookup_maps, interval_label, marker_state_label, needs_category,
 };
+use crate::review::{ReviewMark, ReviewStore};
+use crate::schedule::{ReportKind, ScheduledJob};
+use crate::shutdown::{self, ShutdownHandle};
+use crate::stats::UsageStats;
+use crate::storage::{PurgeStats, purge_dir};
+use crate::sync_diff::{Snapshot, count_changes, stamp_entities};
+use crate::templates::{TemplateStore, TransactionTemplate};
+use crate::throttle::{DEFAULT_MIN_REQUEST_INTERVAL, RateLimiter, SyncCoalescer};
+
+/// Number of transactions pushed per request when merging merchants.
+const MERCHANT_MERGE_BATCH_SIZE: usize = 100;
+
+/// Number of transactions pushed or deleted per request by `execute_bulk_operations`.
+const BULK_EXECUTE_BATCH_SIZE: usize = 100;
 
 /// Maximum number of operations allowed in a single bulk call.
 const MAX_BULK_OPERATIONS: usize = 20;
@@ -44,6 +149,191 @@ const DEFAULT_TRANSACTION_LIMIT: usize = 100;
 /// Hard ceiling for the `limit` parameter on `list_transactions`.
 const MAX_TRANSACTION_LIMIT: usize = 500;
 
+/// Default days after a hold within which `merge_transactions` looks for its settlement.
+const DEFAULT_HOLD_SETTLE_DAYS: u32 = 5;
+
+/// Most occurrences a `create_recurring` series may have.
+const MAX_RECURRING_OCCURRENCES: u32 = 120;
+
+/// Default number of transactions listed by `inbox`.
+const DEFAULT_INBOX_LIMIT: usize = 50;
+
+/// Default number of earlier months `budget_progress` rolls over.
+const DEFAULT_ROLLOVER_MONTHS: u32 = 12;
+
+/// Most earlier months `budget_progress` rolls over.
+const MAX_ROLLOVER_MONTHS: u32 = 60;
+
+/// Default number of past months `suggest_budgets` learns from.
+const DEFAULT_BUDGET_HISTORY_MONTHS: u32 = 6;
+
+/// Most past months `suggest_budgets` learns from.
+const MAX_BUDGET_HISTORY_MONTHS: u32 = 24;
+
+/// Default largest difference `verify_balances` still counts as a match.
+const BALANCE_TOLERANCE: f64 = 0.01;
+
+/// Default number of matches returned by the `find_*` tools.
+const DEFAULT_FIND_LIMIT: usize = 5;
+
+/// Default number of entries returned by ranking reports.
+const DEFAULT_REPORT_LIMIT: usize = 10;
+
+/// Hard ceiling for the `limit` parameter on ranking reports.
+const MAX_REPORT_LIMIT: usize = 100;
+
+/// Number of categories listed under `largest_increases` in `compare_periods`.
+const LARGEST_INCREASES_LIMIT: usize = 5;
+
+/// Number of categories listed in period summaries such as `card_statement`.
+const TOP_CATEGORIES_LIMIT: usize = 5;
+
+/// Number of transactions and accounts listed in a digest.
+const DIGEST_ITEMS_LIMIT: usize = 5;
+
+/// Longest range, in days, allowed for daily balance history.
+const MAX_DAILY_HISTORY_DAYS: i64 = 366;
+
+/// Default modified z-score threshold for `detect_anomalies`.
+const DEFAULT_ANOMALY_THRESHOLD: f64 = 3.5;
+
+/// Default look-back window, in days, scanned by `detect_anomalies`.
+const DEFAULT_ANOMALY_WINDOW_DAYS: u64 = 30;
+
+/// Default number of periods listed by `loan_schedule`.
+const DEFAULT_SCHEDULE_LIMIT: usize = 12;
+
+/// Hard ceiling for the `limit` parameter on `loan_schedule`.
+const MAX_SCHEDULE_LIMIT: usize = 360;
+
+/// Default look-back window, in days, analyzed by `spending_patterns`.
+const DEFAULT_PATTERN_WINDOW_DAYS: u64 = 90;
+
+/// Months before the current one covered by `savings_rate` by default.
+const DEFAULT_SAVINGS_MONTHS: u32 = 11;
+
+/// Days around a bill's due date within which `upcoming_bills` matches a payment.
+const DEFAULT_BILL_MATCH_DAYS: u32 = 5;
+
+/// Days around a statement entry's date within which `import_ofx` matches a transaction.
+const DEFAULT_OFX_MATCH_DAYS: u32 = 3;
+
+/// Number of entries in each ranked list of `year_review`.
+const YEAR_REVIEW_ITEMS_LIMIT: usize = 10;
+
+/// Maximum number of transactions a filter-based bulk tool may stage at once.
+const MAX_FILTER_BULK: usize = 500;
+
+/// Balance differences below this are treated as already reconciled.
+const BALANCE_EPSILON: f64 = 0.005;
+
+/// Phrase `purge_local_data` requires in its `confirm` argument.
+const PURGE_CONFIRMATION: &str = "PURGE LOCAL DATA";
+
+/// Environment variable that leaves raw IDs out of responses when set to a false value.
+pub const INCLUDE_IDS_ENV: &str = "ZENMONEY_INCLUDE_IDS";
+
+/// Environment variable with the currency code (or instrument ID) converted
+/// totals default to.
+pub const BASE_CURRENCY_ENV: &str = "ZENMONEY_BASE_CURRENCY";
+
+/// Tools that answer from local data and sync first when it is stale.
+const READ_TOOLS: &[&str] = &[
+    "inbox",
+    "list_planned",
+    "list_accounts",
+    "get_account",
+    "verify_balances",
+    "list_transactions",
+    "list_deleted_transactions",
+    "list_tags",
+    "tag_tree",
+    "list_merchants",
+    "list_users",
+    "list_companies",
+    "list_budgets",
+    "list_reminders",
+    "list_reminder_markers",
+    "upcoming_bills",
+    "list_instruments",
+    "find_account",
+    "find_tag",
+    "find_merchant",
+    "find_instrument",
+    "search_entities",
+    "get_tag",
+    "get_instrument",
+    "get_raw_entity",
+    "convert_amount",
+    "top_payees",
+    "compare_periods",
+    "account_balance_history",
+    "detect_anomalies",
+    "uncategorized_report",
+    "debts_report",
+    "loan_schedule",
+    "card_statement",
+    "envelope_status",
+    "digest",
+    "spending_patterns",
+    "transfer_report",
+    "savings_rate",
+    "year_review",
+    "export_qif",
+    "export_ofx",
+    "export_ledger",
+    "export_snapshot",
+];
+
+/// Tools that change data and therefore run one at a time.
+const WRITE_TOOLS: &[&str] = &[
+    "create_transaction",
+    "log_expense",
+    "create_from_template",
+    "create_recurring",
+    "convert_planned",
+    "save_template",
+    "allocate_envelope",
+    "create_tag",
+    "create_category",
+    "create_merchant",
+    "rename_merchant",
+    "merge_merchants",
+    "mark_viewed",
+    "mark_reviewed",
+    "set_default_account",
+    "update_transaction",
+    "delete_transaction",
+    "restore_transaction",
+    "set_marker_state",
+    "execute_bulk_operations",
+    "restore_data",
+    "resolve_conflict",
+    "pending_changes",
+    "purge_local_data",
+];
+
+/// Write tools that ask the user first; they take their write turn once the
+/// user has answered, so an open prompt does not hold up other writes.
+const CONFIRMED_WRITE_TOOLS: &[&str] = &[
+    "delete_transaction",
+    "execute_bulk_operations",
+    "purge_local_data",
+];
+
+/// Write tools that only change local state, so they also work offline.
+const LOCAL_WRITE_TOOLS: &[&str] = &[
+    "save_template",
+    "allocate_envelope",
+    "set_default_account",
+    "pending_changes",
+    "purge_local_data",
+];
+
+/// Note appended to the result of every write tool.
+const WRITE_ORDER_NOTE: &str = "Writes run one at a time in the order they arrive, so parallel \
+                                write calls cannot overwrite each other's changes.";
+
 /// Holds the validated, ready-to-execute bulk operations.
 struct PreparedBulk {
     /// Transactions to create or update.
@@ -54,17 +344,116 @@ struct PreparedBulk {
     created_count: usize,
     /// Number of update operations.
     updated_count: usize,
+    /// Budgets to create or update.
+    budgets: Vec<Budget>,
+}
+
+impl PreparedBulk {
+    /// Returns how many changes executing the preparation sends.
+    const fn change_count(&self) -> usize {
+        self.to_push
+            .len()
+            .saturating_add(self.to_delete.len())
+            .saturating_add(self.budgets.len())
+    }
+
+    /// Keeps the first `done` changes, in the order they are sent, and
+    /// returns the rest as a preparation of their own.
+    ///
+    /// Kept transactions whose IDs are not in `existing` count as created.
+    fn split_off(&mut self, done: usize, existing: &HashSet<String>) -> Self {
+        let pushed = done.min(self.to_push.len());
+        let deleted = done.saturating_sub(pushed).min(self.to_delete.len());
+        let budgets_sent = done
+            .saturating_sub(pushed)
+            .saturating_sub(deleted)
+            .min(self.budgets.len());
+        let to_push = self.to_push.split_off(pushed);
+        let to_delete = self.to_delete.split_off(deleted);
+        let budgets = self.budgets.split_off(budgets_sent);
+        let created = self
+            .to_push
+            .iter()
+            .filter(|tx| !existing.contains(tx.id.as_inner()))
+            .count();
+        let updated = pushed.saturating_sub(created);
+        let rest = Self {
+            to_push,
+            to_delete,
+            created_count: self.created_count.saturating_sub(created),
+            updated_count: self.updated_count.saturating_sub(updated),
+            budgets,
+        };
+        self.created_count = created;
+        self.updated_count = updated;
+        rest
+    }
 }
 
 /// MCP server wrapping the ZenMoney personal finance API.
 #[derive(Clone)]
-pub(crate) struct ZenMoneyMcpServer<S: Storage + 'static = FileStorage> {
+pub struct ZenMoneyMcpServer<S: Storage + 'static = FileStorage> {
     /// Inner ZenMoney client (shared via Arc).
     client: Arc<ZenMoney<S>>,
     /// Tool router for dispatching MCP tool calls.
     tool_router: ToolRouter<Self>,
     /// In-memory store of prepared bulk operations awaiting execution.
     preparations: Arc<Mutex<HashMap<String, PreparedBulk>>>,
+    /// Saved transaction templates.
+    templates: Arc<TemplateStore>,
+    /// Locally stored budgeting envelopes.
+    envelopes: Arc<EnvelopeStore>,
+    /// Whether deletions and bulk commits ask the user to confirm first.
+    confirm_destructive: bool,
+    /// Whether responses carry raw IDs next to resolved names.
+    include_ids: bool,
+    /// Whether tool results are compact unless a call asks otherwise.
+    compact_output: bool,
+    /// Destination for log notifications requested by the client.
+    client_log: ClientLog,
+    /// Resource versions and subscriptions for change notifications.
+    resource_watch: ResourceWatch,
+    /// Instructions sent to the client on initialization.
+    instructions: String,
+    /// Directory of the file storage, wiped by `purge_local_data`.
+    data_dir: Option<PathBuf>,
+    /// Changes brought in by the last `sync` or `full_sync` call.
+    last_sync: Arc<Mutex<Option<SyncChangesResponse>>>,
+    /// Age after which read tools sync first; `None` disables the automatic sync.
+    max_data_age: Option<Duration>,
+    /// Time of the last successful sync.
+    sync_clock: Arc<SyncClock>,
+    /// Spacing of ZenMoney API requests.
+    throttle: Arc<RateLimiter>,
+    /// Lets concurrent sync requests share one in-flight sync.
+    sync_gate: Arc<SyncCoalescer>,
+    /// Held for the whole of a write tool call; the fair lock applies
+    /// parallel writes one at a time in the order they arrived.
+    write_lock: Arc<tokio::sync::Mutex<()>>,
+    /// Per-tool call counts and latencies for `usage_stats`.
+    usage: Arc<UsageStats>,
+    /// Transaction writes queued while ZenMoney was unreachable.
+    pending: Arc<PendingQueue>,
+    /// Queued edits held back because ZenMoney changed the same transaction.
+    conflicts: Arc<ConflictStore>,
+    /// Watermark of the last `mark_reviewed`.
+    reviews: Arc<ReviewStore>,
+    /// IDs of planned transactions, left out of reports until converted.
+    planned: Arc<PlannedStore>,
+    /// Account used when a create omits `account_id`.
+    default_account: Arc<DefaultAccountStore>,
+    /// Allowed dates for created and updated transactions.
+    date_window: DateWindow,
+    /// Configured base currency code or instrument ID; `None` uses the
+    /// currency of the account owner.
+    base_currency: Option<String>,
+    /// Set by [`ShutdownHandle::drain`]; later write calls are refused.
+    closing: Arc<AtomicBool>,
+    /// Set when local data was restored from a backup; cleared by the next
+    /// sync, which downloads everything again.
+    restored: Arc<AtomicBool>,
+    /// Offline mode (`demo` or `replay`) in which the server must not call the API.
+    offline_mode: Option<&'static str>,
 }
 
 impl<S: Storage + 'static> core::fmt::Debug for ZenMoneyMcpServer<S> {
@@ -76,15 +465,145 @@ impl<S: Storage + 'static> core::fmt::Debug for ZenMoneyMcpServer<S> {
 /// Converts a [`zenmoney_rs::error::ZenMoneyError`] into an MCP internal error.
 #[allow(clippy::needless_pass_by_value, reason = "map_err passes by value")]
 fn zen_err(err: zenmoney_rs::error::ZenMoneyError) -> McpError {
+    tracing::warn!(%err, "ZenMoney request failed");
     McpError::internal_error(err.to_string(), None)
 }
 
+/// Converts a local (template or envelope) storage I/O error into an MCP internal error.
+#[allow(clippy::needless_pass_by_value, reason = "map_err passes by value")]
+fn io_err(err: std::io::Error) -> McpError {
+    McpError::internal_error(format!("local storage error: {err}"), None)
+}
+
+/// Converts an error from writing to the client's storage into an MCP internal error.
+#[allow(clippy::needless_pass_by_value, reason = "map_err passes by value")]
+fn storage_err<E: core::fmt::Display>(err: E) -> McpError {
+    McpError::internal_error(format!("failed to write local storage: {err}"), None)
+}
+
 /// Parses a date string in `YYYY-MM-DD` format.
 fn parse_date(date_str: &str) -> Result<NaiveDate, McpError> {
     NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
         .map_err(|err| McpError::invalid_params(format!("invalid date '{date_str}': {err}"), None))
 }
 
+/// Parses an RFC 3339 timestamp, or a `YYYY-MM-DD` date as midnight UTC.
+fn parse_timestamp(value: &str) -> Result<DateTime<Utc>, McpError> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_time(NaiveTime::MIN).and_utc());
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .map_err(|err| {
+            McpError::invalid_params(
+                format!("invalid timestamp '{value}': {err}; expected RFC 3339 or YYYY-MM-DD"),
+                None,
+            )
+        })
+}
+
+/// Parses a month in `YYYY-MM` format into its first day, defaulting to the current month.
+fn parse_month(month: Option<&str>) -> Result<NaiveDate, McpError> {
+    match month {
+        Some(value) => parse_date(&format!("{value}-01")),
+        None => Ok(Utc::now()
+            .date_naive()
+            .with_day(1)
+            .unwrap_or(NaiveDate::MIN)),
+    }
+}
+
+/// Resolves the month range requested from `list_budgets`, as the first days
+/// of its first and last months, or `None` when no range was requested.
+fn budget_month_range(
+    params: &ListBudgetsParams,
+) -> Result<Option<(NaiveDate, NaiveDate)>, McpError> {
+    let ranged = params.month_from.is_some() || params.month_to.is_some();
+    if params.month.is_some() && (ranged || params.year.is_some()) {
+        return Err(McpError::invalid_params(
+            "month cannot be combined with month_from, month_to or year",
+            None,
+        ));
+    }
+    if let Some(year) = params.year {
+        if ranged {
+            return Err(McpError::invalid_params(
+                "year cannot be combined with month_from or month_to",
+                None,
+            ));
+        }
+        return NaiveDate::from_ymd_opt(year, 1, 1)
+            .zip(NaiveDate::from_ymd_opt(year, 12, 1))
+            .map(Some)
+            .ok_or_else(|| McpError::invalid_params(format!("invalid year {year}"), None));
+    }
+    if !ranged {
+        return Ok(None);
+    }
+    let first = params
+        .month_from
+        .as_deref()
+        .map(|month| parse_month(Some(month)))
+        .transpose()?
+        .unwrap_or(NaiveDate::MIN);
+    let last = params
+        .month_to
+        .as_deref()
+        .map(|month| parse_month(Some(month)))
+        .transpose()?
+        .unwrap_or(NaiveDate::MAX);
+    if first > last {
+        return Err(McpError::invalid_params(
+            "month_from must not be after month_to",
+            None,
+        ));
+    }
+    Ok(Some((first, last)))
+}
+
+/// Rounds the amounts of `suggestions` up to a multiple of `round_to` and
+/// builds the budgets that save them for `month`.
+///
+/// A budget already set for a category and month keeps its income target
+/// and gets the proposed outcome; `current` is filled in from it.
+fn staged_budgets(
+    suggestions: &mut [BudgetSuggestion],
+    existing: &[Budget],
+    month: NaiveDate,
+    user: UserId,
+    round_to: Option<f64>,
+) -> Vec<Budget> {
+    let now = Utc::now();
+    suggestions
+        .iter_mut()
+        .map(|suggestion| {
+            if let Some(step) = round_to.filter(|step| *step > 0.0_f64) {
+                suggestion.amount = round_cents((suggestion.amount / step).ceil() * step);
+            }
+            let current = existing.iter().find(|budget| {
+                budget.date == month
+                    && budget.tag.as_ref().map(TagId::as_inner) == Some(suggestion.tag_id.as_str())
+            });
+            suggestion.current = current.map(|budget| budget.outcome);
+            let mut budget = current.cloned().unwrap_or_else(|| Budget {
+                changed: now,
+                user,
+                tag: Some(TagId::new(suggestion.tag_id.clone())),
+                date: month,
+                income: 0.0,
+                income_lock: false,
+                outcome: 0.0,
+                outcome_lock: false,
+                is_income_forecast: None,
+                is_outcome_forecast: None,
+            });
+            budget.outcome = suggestion.amount;
+            budget.changed = now;
+            budget
+        })
+        .collect()
+}
+
 /// Serializes a value to a pretty-printed JSON string for tool output.
 fn to_json_text<T: serde::Serialize>(value: &T) -> Result<String, McpError> {
     serde_json::to_string_pretty(value).map_err(|err| {
@@ -93,9 +612,138 @@ fn to_json_text<T: serde::Serialize>(value: &T) -> Result<String, McpError> {
 }
 
 /// Creates a successful tool result containing JSON text.
+///
+/// JSON objects are also returned as structured content for clients that
+/// read typed results. Arrays stay text-only because MCP structured
+/// content must be an object.
 fn json_result<T: serde::Serialize>(value: &T) -> Result<CallToolResult, McpError> {
     let text = to_json_text(value)?;
-    Ok(CallToolResult::success(vec![Content::text(text)]))
+    let structured = serde_json::to_value(value).map_err(|err| {
+        McpError::internal_error(format!("failed to serialize response: {err}"), None)
+    })?;
+    let mut result = CallToolResult::success(vec![Content::text(text)]);
+    if structured.is_object() {
+        result.structured_content = Some(structured);
+    }
+    Ok(result)
+}
+
+/// Serializes the entity in `entities` whose ID renders as `id`, unchanged.
+fn raw_json<T, K, F>(
+    entities: &[T],
+    id: &str,
+    key: F,
+) -> Result<Option<serde_json::Value>, McpError>
+where
+    T: serde::Serialize,
+    K: core::fmt::Display,
+    F: Fn(&T) -> &K,
+{
+    entities
+        .iter()
+        .find(|entity| key(entity).to_string() == id)
+        .map(serde_json::to_value)
+        .transpose()
+        .map_err(|err| McpError::internal_error(format!("failed to serialize entity: {err}"), None))
+}
+
+/// Ranks `entities` by fuzzy match of their title against `query`, best
+/// first, leaving out those scoring below [`fuzzy::MIN_SCORE`].
+fn rank_by_title<T, F>(query: &str, entities: Vec<T>, title: F) -> Vec<(f64, T)>
+where
+    F: Fn(&T) -> &str,
+{
+    let mut ranked: Vec<(f64, T)> = entities
+        .into_iter()
+        .map(|entity| (round_cents(fuzzy::score(query, title(&entity))), entity))
+        .filter(|entry| entry.0 >= fuzzy::MIN_SCORE)
+        .collect();
+    ranked.sort_by(|left, right| right.0.total_cmp(&left.0));
+    ranked
+}
+
+/// Ranks instruments by the better of their code and name match to `query`.
+fn rank_instruments(query: &str, instruments: Vec<Instrument>) -> Vec<(f64, Instrument)> {
+    let mut ranked: Vec<(f64, Instrument)> = instruments
+        .into_iter()
+        .map(|instrument| {
+            let score = fuzzy::score(query, &instrument.short_title)
+                .max(fuzzy::score(query, &instrument.title));
+            (round_cents(score), instrument)
+        })
+        .filter(|entry| entry.0 >= fuzzy::MIN_SCORE)
+        .collect();
+    ranked.sort_by(|left, right| right.0.total_cmp(&left.0));
+    ranked
+}
+
+/// Ranks `candidates` (kind, ID, title) by fuzzy title match against `query`.
+fn rank_matches(
+    query: &str,
+    candidates: Vec<(SearchKind, String, String)>,
+    limit: usize,
+) -> Vec<EntityMatch> {
+    rank_by_title(query, candidates, |candidate| candidate.2.as_str())
+        .into_iter()
+        .take(limit)
+        .map(|(score, (kind, id, title))| EntityMatch {
+            kind,
+            id,
+            title,
+            score,
+        })
+        .collect()
+}
+
+/// Builds the error of a lookup that found nothing.
+///
+/// Its data, `{code: "not_found", kind, query}`, lets clients tell a missing
+/// entity apart from other invalid arguments.
+fn not_found<Q: core::fmt::Display>(kind: &str, query: Q) -> McpError {
+    McpError::invalid_params(
+        format!("{kind} '{query}' not found"),
+        Some(serde_json::json!({
+            "code": "not_found",
+            "kind": kind,
+            "query": query.to_string(),
+        })),
+    )
+}
+
+/// Builds the reply of a `find_*` tool.
+fn find_result<T: serde::Serialize>(
+    query: String,
+    matches: Vec<RankedMatch<T>>,
+) -> Result<CallToolResult, McpError> {
+    json_result(&FindResponse {
+        found: !matches.is_empty(),
+        query,
+        matches,
+    })
+}
+
+/// Clamps the `limit` of a `find_*` tool.
+fn find_limit(limit: Option<usize>) -> usize {
+    limit.unwrap_or(DEFAULT_FIND_LIMIT).min(MAX_REPORT_LIMIT)
+}
+
+/// Appends a note to `result` when its write was queued instead of sent.
+fn with_pending_note(mut result: CallToolResult, pending: Option<&str>) -> CallToolResult {
+    if let Some(id) = pending {
+        result.content.push(Content::text(format!(
+            "ZenMoney is unreachable, so this change was saved locally as pending change {id}. \
+             It is sent after the next sync; use pending_changes to review or cancel it."
+        )));
+    }
+    result
+}
+
+/// Appends the note on write ordering to a successful write result.
+fn with_write_order_note(mut result: CallToolResult) -> CallToolResult {
+    if result.is_error != Some(true) {
+        result.content.push(Content::text(WRITE_ORDER_NOTE));
+    }
+    result
 }
 
 /// Formats an [`AccountType`](zenmoney_rs::models::AccountType) variant as a human-readable string.
@@ -111,6 +759,75 @@ pub(crate) const fn account_type_label(kind: zenmoney_rs::models::AccountType) -
     }
 }
 
+/// Converts the balance of `account` into `convert_to`, if given and known.
+fn converted_balance(account: &Account, maps: &LookupMaps, convert_to: Option<i32>) -> Option<f64> {
+    let to = convert_to?;
+    maps.convert(account.balance?, account.instrument?.into_inner(), to)
+}
+
+/// Groups `accounts` by type, keeping their order within each group.
+///
+/// Subtotals are in `convert_to` when given; otherwise a group gets one only
+/// if all its balances share a currency.
+fn group_accounts(
+    accounts: &[Account],
+    maps: &LookupMaps,
+    convert_to: Option<i32>,
+) -> Vec<AccountGroup> {
+    let mut by_type: BTreeMap<&str, Vec<&Account>> = BTreeMap::new();
+    for account in accounts {
+        by_type
+            .entry(account_type_label(account.kind))
+            .or_default()
+            .push(account);
+    }
+    by_type
+        .into_iter()
+        .map(|(account_type, members)| {
+            let balances: Vec<(i32, f64)> = members
+                .iter()
+                .filter_map(|acc| Some((acc.instrument?.into_inner(), acc.balance?)))
+                .collect();
+            let currencies: BTreeSet<i32> = balances.iter().map(|entry| entry.0).collect();
+            let target = convert_to.or_else(|| {
+                currencies
+                    .first()
+                    .copied()
+                    .filter(|_| currencies.len() == 1)
+            });
+            let subtotal = target.map(|to| {
+                round_cents(
+                    balances
+                        .iter()
+                        .filter_map(|&(from, balance)| maps.convert(balance, from, to))
+                        .sum(),
+                )
+            });
+            let unconverted: BTreeSet<String> = balances
+                .iter()
+                .filter(|&&(from, balance)| {
+                    target.is_some_and(|to| maps.convert(balance, from, to).is_none())
+                })
+                .map(|&(from, _)| maps.instrument_symbol(from))
+                .collect();
+            AccountGroup {
+                account_type: account_type.to_owned(),
+                count: members.len(),
+                subtotal,
+                currency: target.map(|to| maps.instrument_symbol(to)),
+                unconverted: unconverted.into_iter().collect(),
+                accounts: members
+                    .into_iter()
+                    .map(|acc| {
+                        AccountResponse::from_account(acc, maps)
+                            .with_converted_balance(converted_balance(acc, maps, convert_to))
+                    })
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
 /// Resolves the instrument ID for an account, using an explicit override if provided.
 ///
 /// Returns `explicit` if `Some`, otherwise looks up the account's instrument from the maps.
@@ -134,7 +851,7 @@ fn resolve_instrument(
 }
 
 /// Classifies a transaction as expense, income, or transfer based on its amounts and accounts.
-fn classify_transaction(tx: &Transaction) -> TransactionType {
+pub(crate) fn classify_transaction(tx: &Transaction) -> TransactionType {
     let different_accounts = tx.outcome_account.as_inner() != tx.income_account.as_inner();
     if tx.outcome > 0.0 && tx.income > 0.0 && different_accounts {
         TransactionType::Transfer
@@ -145,6 +862,58 @@ fn classify_transaction(tx: &Transaction) -> TransactionType {
     }
 }
 
+/// Returns the amount and instrument that best represent a transaction.
+///
+/// Income uses the income side; expenses and transfers use the outcome side.
+fn primary_amount(tx: &Transaction) -> (f64, i32) {
+    match classify_transaction(tx) {
+        TransactionType::Income => (tx.income, tx.income_instrument.into_inner()),
+        TransactionType::Expense | TransactionType::Transfer => {
+            (tx.outcome, tx.outcome_instrument.into_inner())
+        }
+    }
+}
+
+/// Sums transactions per currency for a confirmation prompt, e.g. `1500.00 ₽, 20.00 $`.
+fn amount_totals<'tx, I>(transactions: I, maps: &LookupMaps) -> String
+where
+    I: IntoIterator<Item = &'tx Transaction>,
+{
+    let mut totals: BTreeMap<String, f64> = BTreeMap::new();
+    for tx in transactions {
+        let (amount, instrument) = primary_amount(tx);
+        *totals
+            .entry(maps.instrument_symbol(instrument))
+            .or_insert(0.0) += amount;
+    }
+    totals
+        .iter()
+        .map(|(symbol, amount)| format!("{amount:.2} {symbol}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Validates that a requested conversion target instrument has a known rate.
+fn validate_convert_to(maps: &LookupMaps, convert_to: Option<i32>) -> Result<(), McpError> {
+    match convert_to {
+        Some(id) if maps.instrument_rate(id).is_none() => Err(not_found("instrument", id)),
+        Some(_) | None => Ok(()),
+    }
+}
+
+/// Builds an enriched transaction response, adding a converted amount when requested.
+fn transaction_response(
+    tx: &Transaction,
+    maps: &LookupMaps,
+    convert_to: Option<i32>,
+) -> TransactionResponse {
+    let converted = convert_to.and_then(|to| {
+        let (amount, from) = primary_amount(tx);
+        maps.convert(amount, from, to)
+    });
+    TransactionResponse::from_transaction(tx, maps).with_converted_amount(converted)
+}
+
 /// Filters transactions in-place by transaction type, if specified.
 fn filter_by_transaction_type(
     transactions: &mut Vec<Transaction>,
@@ -164,11 +933,251 @@ fn filter_by_transaction_type(
     }
 }
 
+/// Validates that a latitude/longitude pair is complete and in range.
+fn validate_coordinates(latitude: Option<f64>, longitude: Option<f64>) -> Result<(), McpError> {
+    match (latitude, longitude) {
+        (None, None) => Ok(()),
+        (Some(lat), Some(lon)) => {
+            if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon) {
+                Ok(())
+            } else {
+                Err(McpError::invalid_params(
+                    "latitude must be within [-90, 90] and longitude within [-180, 180]",
+                    None,
+                ))
+            }
+        }
+        (Some(_), None) | (None, Some(_)) => Err(McpError::invalid_params(
+            "latitude and longitude must be given together",
+            None,
+        )),
+    }
+}
+
+/// Validates that a merchant category code has at most four digits.
+fn validate_mcc(mcc: i32) -> Result<(), McpError> {
+    if (0..=9999).contains(&mcc) {
+        Ok(())
+    } else {
+        Err(McpError::invalid_params(
+            format!("invalid MCC {mcc}: expected a 4-digit code"),
+            None,
+        ))
+    }
+}
+
+/// Builds a storage-level [`TransactionFilter`] from list parameters.
+fn build_transaction_filter(
+    params: &ListTransactionsParams,
+) -> Result<TransactionFilter, McpError> {
+    let mut filter = TransactionFilter::new();
+
+    if let Some(date_from_str) = params.date_from.as_deref() {
+        filter.date_from = Some(parse_date(date_from_str)?);
+    }
+    if let Some(date_to_str) = params.date_to.as_deref() {
+        filter.date_to = Some(parse_date(date_to_str)?);
+    }
+    if let Some(account_id) = params.account_id.as_ref() {
+        filter = filter.account(AccountId::new(account_id.clone()));
+    }
+    // With `include_children` the tag is matched locally against the hierarchy.
+    if let Some(tag_id) = params.tag_id.as_ref()
+        && params.include_children != Some(true)
+    {
+        filter = filter.tag(TagId::new(tag_id.clone()));
+    }
+    if let Some(payee_str) = params.payee.as_ref() {
+        filter = filter.payee(payee_str.clone());
+    }
+    if let Some(merchant_id) = params.merchant_id.as_ref() {
+        filter = filter.merchant(MerchantId::new(merchant_id.clone()));
+    }
+    filter.min_amount = params.min_amount;
+    filter.max_amount = params.max_amount;
+
+    if let Some(near) = params.near {
+        validate_coordinates(Some(near.latitude), Some(near.longitude))?;
+        if near.radius_km.is_nan() || near.radius_km <= 0.0 {
+            return Err(McpError::invalid_params(
+                "near.radius_km must be positive",
+                None,
+            ));
+        }
+    }
+
+    Ok(filter)
+}
+
+/// Returns whether `tx` matches `filter`, for transactions that are not in
+/// the synced storage `filter_transactions` searches.
+fn matches_filter(filter: &TransactionFilter, tx: &Transaction) -> bool {
+    let payee_lower = filter.payee.as_deref().map(str::to_lowercase);
+    filter.date_from.is_none_or(|from| tx.date >= from)
+        && filter.date_to.is_none_or(|to| tx.date <= to)
+        && filter
+            .account
+            .as_ref()
+            .is_none_or(|account| tx.income_account == *account || tx.outcome_account == *account)
+        && filter
+            .tag
+            .as_ref()
+            .is_none_or(|tag| tx.tag.as_ref().is_some_and(|tags| tags.contains(tag)))
+        && payee_lower.is_none_or(|payee| {
+            tx.payee
+                .as_ref()
+                .is_some_and(|name| name.to_lowercase().contains(&payee))
+        })
+        && filter
+            .merchant
+            .as_ref()
+            .is_none_or(|merchant| tx.merchant.as_ref() == Some(merchant))
+        && filter
+            .min_amount
+            .is_none_or(|min| tx.income >= min || tx.outcome >= min)
+        && filter
+            .max_amount
+            .is_none_or(|max| tx.income <= max && tx.outcome <= max)
+}
+
+/// Applies the list filters that [`TransactionFilter`] does not support.
+fn apply_local_filters(
+    transactions: &mut Vec<Transaction>,
+    params: &ListTransactionsParams,
+    maps: &LookupMaps,
+) {
+    // Filter by tag including its children.
+    if let Some(tag_id) = params.tag_id.as_deref()
+        && params.include_children == Some(true)
+    {
+        transactions.retain(|tx| has_tag_or_child(tx, tag_id, maps));
+    }
+
+    // Filter by the user who recorded the transaction.
+    if let Some(user_id) = params.user_id {
+        transactions.retain(|tx| tx.user.into_inner() == user_id);
+    }
+
+    // Filter by uncategorized.
+    if params.uncategorized == Some(true) {
+        transactions.retain(is_uncategorized);
+    }
+
+    // Filter by viewed flag.
+    if params.unviewed_only == Some(true) {
+        transactions.retain(is_unviewed);
+    }
+
+    // Filter by hold status.
+    if let Some(hold) = params.hold {
+        transactions.retain(|tx| is_on_hold(tx) == hold);
+    }
+
+    // Filter by location.
+    if let Some(near) = params.near {
+        transactions.retain(|tx| is_near(tx, near));
+    }
+
+    // Filter by where the transaction came from.
+    if let Some(source) = params.source.as_deref() {
+        transactions.retain(|tx| matches_source(tx, source));
+    }
+
+    // Filter by merchant category code.
+    if let Some(mcc) = params.mcc {
+        transactions.retain(|tx| tx.mcc == Some(mcc));
+    }
+
+    // Filter by the reminder the transaction was recorded against.
+    if let Some(reminder_id) = params.reminder_id.as_deref() {
+        transactions.retain(|tx| {
+            tx.reminder_marker
+                .as_ref()
+                .and_then(|marker| maps.marker_reminder(marker.as_inner()))
+                == Some(reminder_id)
+        });
+    }
+
+    // Filter by transaction type.
+    filter_by_transaction_type(transactions, params.transaction_type.as_ref());
+}
+
+/// Sorts transactions by date in the given direction.
+fn sort_by_date(transactions: &mut [Transaction], sort: SortDirection) {
+    match sort {
+        SortDirection::Desc => transactions.sort_by(|left, right| right.date.cmp(&left.date)),
+        SortDirection::Asc => transactions.sort_by(|left, right| left.date.cmp(&right.date)),
+    }
+}
+
+/// Returns one enriched page of already sorted transactions.
+fn paginate_transactions(
+    transactions: Vec<Transaction>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    maps: &LookupMaps,
+    convert_to: Option<i32>,
+) -> PaginatedTransactions {
+    let total = transactions.len();
+    let offset = offset.unwrap_or(0);
+    let limit = limit
+        .unwrap_or(DEFAULT_TRANSACTION_LIMIT)
+        .min(MAX_TRANSACTION_LIMIT);
+
+    let items: Vec<TransactionResponse> = transactions
+        .into_iter()
+        .skip(offset)
+        .take(limit)
+        .map(|tx| transaction_response(&tx, maps, convert_to))
+        .collect();
+
+    PaginatedTransactions {
+        items,
+        total,
+        offset,
+        limit,
+        summary: None,
+    }
+}
+
 /// Returns `true` if the transaction has no category tags.
 fn is_uncategorized(tx: &Transaction) -> bool {
     tx.tag.as_ref().is_none_or(Vec::is_empty)
 }
 
+/// Returns `true` if the transaction has not been marked as viewed.
+const fn is_unviewed(tx: &Transaction) -> bool {
+    matches!(tx.viewed, Some(false))
+}
+
+/// Returns whether `tx` came from `source`: `manual` matches transactions
+/// entered by hand (no source), `imported` those with any source, and other
+/// values the source name, ignoring case.
+fn matches_source(tx: &Transaction, source: &str) -> bool {
+    let origin = tx.source.as_deref().filter(|found| !found.is_empty());
+    match source.trim().to_lowercase().as_str() {
+        "manual" => origin.is_none(),
+        "imported" => origin.is_some(),
+        wanted => origin.is_some_and(|found| found.to_lowercase() == wanted),
+    }
+}
+
+/// Returns `true` if the transaction is a pending (not yet settled) authorization.
+const fn is_on_hold(tx: &Transaction) -> bool {
+    matches!(tx.hold, Some(true))
+}
+
+/// Category, payee and merchant inferred for a `log_expense` entry.
+#[derive(Default)]
+struct ExpenseGuess {
+    /// Category tag IDs.
+    tag_ids: Option<Vec<String>>,
+    /// Payee name.
+    payee: Option<String>,
+    /// Merchant ID.
+    merchant_id: Option<String>,
+}
+
 /// Resolved account/amount/instrument fields for building a transaction.
 struct ResolvedSides {
     /// Outcome (source) account.
@@ -236,76 +1245,621 @@ fn resolve_sides(
     }
 }
 
-/// Builds a [`Transaction`] from simplified [`CreateTransactionParams`].
-fn build_transaction(
-    params: CreateTransactionParams,
-    maps: &LookupMaps,
-) -> Result<Transaction, McpError> {
-    let date = parse_date(&params.date)?;
-    let now: DateTime<Utc> = Utc::now();
-    let transaction_id = uuid::Uuid::new_v4().to_string();
-
-    let tag_ids: Option<Vec<TagId>> = params
-        .tag_ids
-        .as_ref()
-        .map(|ids| ids.iter().cloned().map(TagId::new).collect());
-
-    let sides = resolve_sides(&params, maps)?;
+/// Validates a merchant ID against the known merchants.
+fn resolve_merchant(maps: &LookupMaps, merchant_id: &str) -> Result<MerchantId, McpError> {
+    if maps.has_merchant(merchant_id) {
+        Ok(MerchantId::new(merchant_id.to_owned()))
+    } else {
+        Err(not_found("merchant", merchant_id))
+    }
+}
 
-    Ok(Transaction {
-        id: TransactionId::new(transaction_id),
-        changed: now,
-        created: now,
-        user: UserId::new(0),
-        deleted: false,
-        hold: None,
-        income_instrument: sides.income_instrument,
-        income_account: sides.income_account,
-        income: sides.income,
-        outcome_instrument: sides.outcome_instrument,
-        outcome_account: sides.outcome_account,
-        outcome: sides.outcome,
-        tag: tag_ids,
-        merchant: None,
-        payee: params.payee,
-        original_payee: None,
-        comment: params.comment,
+/// Builds create params for a balance-correction transaction.
+///
+/// A positive `delta` becomes income on the account, a negative one an
+/// expense. Returns `None` when the delta rounds to zero kopecks/cents.
+fn balance_correction_params(
+    account_id: &str,
+    delta: f64,
+    date: String,
+    comment: String,
+) -> Option<CreateTransactionParams> {
+    if delta.abs() < BALANCE_EPSILON {
+        return None;
+    }
+    let transaction_type = if delta > 0.0_f64 {
+        TransactionType::Income
+    } else {
+        TransactionType::Expense
+    };
+    Some(CreateTransactionParams {
+        transaction_type,
         date,
-        mcc: None,
-        reminder_marker: None,
-        op_income: None,
-        op_income_instrument: None,
-        op_outcome: None,
-        op_outcome_instrument: None,
+        account_id: account_id.to_owned(),
+        amount: delta.abs(),
+        to_account_id: None,
+        to_amount: None,
+        instrument_id: None,
+        to_instrument_id: None,
+        tag_ids: None,
+        payee: None,
+        comment: Some(comment),
+        merchant_id: None,
         latitude: None,
         longitude: None,
-        income_bank_id: None,
-        outcome_bank_id: None,
-        qr_code: None,
-        source: None,
-        viewed: None,
+        mcc: None,
+        planned: None,
+        allow_out_of_range: None,
     })
 }
 
-/// Applies [`UpdateTransactionParams`] to an existing [`Transaction`].
-fn apply_update(
-    tx: &mut Transaction,
-    params: UpdateTransactionParams,
-    maps: &LookupMaps,
-) -> Result<(), McpError> {
-    if let Some(date_str) = params.date.as_deref() {
-        tx.date = parse_date(date_str)?;
-    }
-
-    if let Some(tag_ids) = params.tag_ids {
-        tx.tag = Some(tag_ids.into_iter().map(TagId::new).collect());
+/// Builds create parameters for a transaction recorded from a fiscal receipt.
+fn receipt_create_params(
+    receipt: &FiscalReceipt,
+    params: ImportReceiptQrParams,
+) -> CreateTransactionParams {
+    let comment = params.comment.unwrap_or_else(|| {
+        format!(
+            "Receipt FN {} #{}",
+            receipt.fiscal_drive, receipt.document_number
+        )
+    });
+    CreateTransactionParams {
+        transaction_type: receipt.operation.transaction_type(),
+        date: receipt.timestamp.date().format("%Y-%m-%d").to_string(),
+        account_id: params.account_id,
+        amount: receipt.amount,
+        to_account_id: None,
+        to_amount: None,
+        instrument_id: None,
+        to_instrument_id: None,
+        tag_ids: params.tag_ids,
+        payee: params.payee,
+        comment: Some(comment),
+        merchant_id: params.merchant_id,
+        latitude: None,
+        longitude: None,
+        mcc: None,
+        planned: None,
+        allow_out_of_range: None,
     }
+}
 
-    if let Some(payee) = params.payee {
-        tx.payee = if payee.is_empty() { None } else { Some(payee) };
+/// Returns the statement text given inline or read from a file.
+fn statement_text(path: Option<String>, content: Option<String>) -> Result<String, McpError> {
+    match (path, content) {
+        (Some(file), None) => std::fs::read_to_string(&file)
+            .map_err(|err| McpError::invalid_params(format!("cannot read '{file}': {err}"), None)),
+        (None, Some(text)) => Ok(text),
+        (Some(_), Some(_)) | (None, None) => Err(McpError::invalid_params(
+            "give exactly one of path or content",
+            None,
+        )),
     }
+}
 
-    if let Some(comment) = params.comment {
+/// Builds the transaction staged for a new OFX statement entry.
+///
+/// The FITID is kept as the bank ID, so importing the statement again
+/// matches the transaction instead of staging it twice.
+fn statement_transaction(
+    entry: &OfxEntry,
+    account_id: &str,
+    tag_ids: Option<Vec<String>>,
+    maps: &LookupMaps,
+) -> Result<Transaction, McpError> {
+    let outgoing = entry.amount < 0.0;
+    let mut transaction = build_transaction(
+        CreateTransactionParams {
+            transaction_type: if outgoing {
+                TransactionType::Expense
+            } else {
+                TransactionType::Income
+            },
+            date: entry.date.format("%Y-%m-%d").to_string(),
+            account_id: account_id.to_owned(),
+            amount: entry.amount.abs(),
+            to_account_id: None,
+            to_amount: None,
+            instrument_id: None,
+            to_instrument_id: None,
+            tag_ids,
+            payee: entry.name.clone(),
+            comment: entry.memo.clone(),
+            merchant_id: None,
+            latitude: None,
+            longitude: None,
+            mcc: None,
+            planned: None,
+            allow_out_of_range: None,
+        },
+        maps,
+    )?;
+    if outgoing {
+        transaction.outcome_bank_id = Some(entry.fitid.clone());
+    } else {
+        transaction.income_bank_id = Some(entry.fitid.clone());
+    }
+    Ok(transaction)
+}
+
+/// Validates a template's references and converts it to its stored form.
+fn validate_template(
+    params: SaveTemplateParams,
+    maps: &LookupMaps,
+) -> Result<TransactionTemplate, McpError> {
+    let name = normalize_title(&params.name)?;
+    let _instrument = resolve_instrument(maps, &params.account_id, None)?;
+    match (&params.transaction_type, params.to_account_id.as_deref()) {
+        (&TransactionType::Transfer, None) => {
+            return Err(McpError::invalid_params(
+                "to_account_id is required for transfer templates",
+                None,
+            ));
+        }
+        (_, Some(to_account_id)) => {
+            let _to_instrument = resolve_instrument(maps, to_account_id, None)?;
+        }
+        (_, None) => {}
+    }
+    if let Some(merchant_id) = params.merchant_id.as_deref() {
+        let _merchant = resolve_merchant(maps, merchant_id)?;
+    }
+    Ok(TransactionTemplate {
+        name,
+        transaction_type: params.transaction_type,
+        account_id: params.account_id,
+        to_account_id: params.to_account_id,
+        amount: params.amount,
+        tag_ids: params.tag_ids,
+        payee: params.payee,
+        comment: params.comment,
+        merchant_id: params.merchant_id,
+    })
+}
+
+/// Expands a template into create params, applying per-use overrides.
+fn template_create_params(
+    template: TransactionTemplate,
+    overrides: CreateFromTemplateParams,
+) -> Result<CreateTransactionParams, McpError> {
+    let amount = overrides.amount.or(template.amount).ok_or_else(|| {
+        McpError::invalid_params(
+            format!("template '{}' has no amount; pass amount", template.name),
+            None,
+        )
+    })?;
+    let date = overrides
+        .date
+        .unwrap_or_else(|| Utc::now().date_naive().format("%Y-%m-%d").to_string());
+    Ok(CreateTransactionParams {
+        transaction_type: template.transaction_type,
+        date,
+        account_id: template.account_id,
+        amount,
+        to_account_id: template.to_account_id,
+        to_amount: None,
+        instrument_id: None,
+        to_instrument_id: None,
+        tag_ids: template.tag_ids,
+        payee: template.payee,
+        comment: overrides.comment.or(template.comment),
+        merchant_id: template.merchant_id,
+        latitude: None,
+        longitude: None,
+        mcc: None,
+        planned: None,
+        allow_out_of_range: None,
+    })
+}
+
+/// Validates the `every` and `count` of a series, returning the step.
+fn recurrence_step(every: Option<u32>, count: Option<u32>) -> Result<u32, McpError> {
+    let step = every.unwrap_or(1);
+    if step == 0 {
+        return Err(McpError::invalid_params("every must be at least 1", None));
+    }
+    if count.is_some_and(|occurrences| occurrences == 0 || occurrences > MAX_RECURRING_OCCURRENCES)
+    {
+        return Err(McpError::invalid_params(
+            format!("count must be 1 to {MAX_RECURRING_OCCURRENCES}"),
+            None,
+        ));
+    }
+    Ok(step)
+}
+
+/// Lists the dates of a series starting at `start` and repeating every
+/// `every` intervals, stopping after `count` dates or past `end`.
+///
+/// # Errors
+///
+/// Returns an error if the series is empty or longer than
+/// [`MAX_RECURRING_OCCURRENCES`].
+fn recurrence_dates(
+    start: NaiveDate,
+    interval: Interval,
+    every: u32,
+    count: Option<u32>,
+    end: Option<NaiveDate>,
+) -> Result<Vec<NaiveDate>, McpError> {
+    let limit = count.unwrap_or(MAX_RECURRING_OCCURRENCES);
+    let mut dates = Vec::new();
+    // One step past the limit tells an over-long series from a complete one.
+    for index in 0..=limit {
+        let Some(date) = index
+            .checked_mul(every)
+            .and_then(|steps| add_intervals(start, interval, steps))
+        else {
+            break;
+        };
+        if end.is_some_and(|last| date > last) {
+            break;
+        }
+        if index == limit {
+            if count.is_none() {
+                return Err(McpError::invalid_params(
+                    format!(
+                        "the series has more than {MAX_RECURRING_OCCURRENCES} occurrences; \
+                         pass count or an earlier end_date"
+                    ),
+                    None,
+                ));
+            }
+            break;
+        }
+        dates.push(date);
+    }
+    if dates.is_empty() {
+        return Err(McpError::invalid_params(
+            "end_date is before the first date",
+            None,
+        ));
+    }
+    Ok(dates)
+}
+
+/// Builds a reminder repeating `tx` every `every` intervals from its date.
+fn reminder_from(
+    tx: &Transaction,
+    user_id: i64,
+    interval: Interval,
+    every: u32,
+    end_date: Option<NaiveDate>,
+) -> Reminder {
+    Reminder {
+        id: ReminderId::new(uuid::Uuid::new_v4().to_string()),
+        changed: Utc::now(),
+        user: UserId::new(user_id),
+        income_instrument: tx.income_instrument,
+        income_account: tx.income_account.clone(),
+        income: tx.income,
+        outcome_instrument: tx.outcome_instrument,
+        outcome_account: tx.outcome_account.clone(),
+        outcome: tx.outcome,
+        tag: tx.tag.clone(),
+        merchant: tx.merchant.clone(),
+        payee: tx.payee.clone(),
+        comment: tx.comment.clone(),
+        interval: Some(interval),
+        step: Some(every),
+        points: None,
+        start_date: tx.date,
+        end_date,
+        notify: true,
+    }
+}
+
+/// Builds a [`Transaction`] from simplified [`CreateTransactionParams`].
+fn build_transaction(
+    mut params: CreateTransactionParams,
+    maps: &LookupMaps,
+) -> Result<Transaction, McpError> {
+    if params.planned == Some(true) {
+        return Err(McpError::invalid_params(
+            "planned is only accepted by create_transaction",
+            None,
+        ));
+    }
+    if params.account_id.trim().is_empty() {
+        params.account_id = maps.default_account()?;
+    }
+    let date = parse_date(&params.date)?;
+    let now: DateTime<Utc> = Utc::now();
+    let transaction_id = uuid::Uuid::new_v4().to_string();
+
+    let tag_ids: Option<Vec<TagId>> = params
+        .tag_ids
+        .as_ref()
+        .map(|ids| ids.iter().cloned().map(TagId::new).collect());
+
+    let sides = resolve_sides(&params, maps)?;
+    validate_coordinates(params.latitude, params.longitude)?;
+    if let Some(mcc) = params.mcc {
+        validate_mcc(mcc)?;
+    }
+    let merchant = params
+        .merchant_id
+        .as_deref()
+        .map(|id| resolve_merchant(maps, id))
+        .transpose()?;
+
+    let tx = Transaction {
+        id: TransactionId::new(transaction_id),
+        changed: now,
+        created: now,
+        user: UserId::new(0),
+        deleted: false,
+        hold: None,
+        income_instrument: sides.income_instrument,
+        income_account: sides.income_account,
+        income: sides.income,
+        outcome_instrument: sides.outcome_instrument,
+        outcome_account: sides.outcome_account,
+        outcome: sides.outcome,
+        tag: tag_ids,
+        merchant,
+        payee: params.payee,
+        original_payee: None,
+        comment: params.comment,
+        date,
+        mcc: params.mcc,
+        reminder_marker: None,
+        op_income: None,
+        op_income_instrument: None,
+        op_outcome: None,
+        op_outcome_instrument: None,
+        latitude: params.latitude,
+        longitude: params.longitude,
+        income_bank_id: None,
+        outcome_bank_id: None,
+        qr_code: None,
+        source: None,
+        viewed: None,
+    };
+    maps.check_date(&tx, params.allow_out_of_range)?;
+    Ok(tx)
+}
+
+/// Converts a marker state filter into the ZenMoney state.
+fn marker_state(state: MarkerState) -> ReminderMarkerState {
+    match state {
+        MarkerState::Planned => ReminderMarkerState::Planned,
+        MarkerState::Processed => ReminderMarkerState::Processed,
+        MarkerState::Deleted => ReminderMarkerState::Deleted,
+    }
+}
+
+/// Builds the transaction recording a planned reminder occurrence.
+fn transaction_from_marker(marker: &ReminderMarker) -> Transaction {
+    let now: DateTime<Utc> = Utc::now();
+    Transaction {
+        id: TransactionId::new(uuid::Uuid::new_v4().to_string()),
+        changed: now,
+        created: now,
+        user: marker.user,
+        deleted: false,
+        hold: None,
+        income_instrument: marker.income_instrument,
+        income_account: marker.income_account.clone(),
+        income: marker.income,
+        outcome_instrument: marker.outcome_instrument,
+        outcome_account: marker.outcome_account.clone(),
+        outcome: marker.outcome,
+        tag: marker.tag.clone(),
+        merchant: marker.merchant.clone(),
+        payee: marker.payee.clone(),
+        original_payee: None,
+        comment: marker.comment.clone(),
+        date: marker.date,
+        mcc: None,
+        reminder_marker: Some(marker.id.clone()),
+        op_income: None,
+        op_income_instrument: None,
+        op_outcome: None,
+        op_outcome_instrument: None,
+        latitude: None,
+        longitude: None,
+        income_bank_id: None,
+        outcome_bank_id: None,
+        qr_code: None,
+        source: None,
+        viewed: None,
+    }
+}
+
+/// Builds a copy of `tx` dated `date`, optionally with a new amount.
+///
+/// Bank-assigned fields (hold, bank IDs, receipt QR code, reminder link)
+/// are not copied. A new amount replaces the main side; a transfer's
+/// received amount is scaled by the same ratio.
+fn duplicate_of(tx: &Transaction, date: NaiveDate, amount: Option<f64>) -> Transaction {
+    let now: DateTime<Utc> = Utc::now();
+    let mut copy = Transaction {
+        id: TransactionId::new(uuid::Uuid::new_v4().to_string()),
+        changed: now,
+        created: now,
+        date,
+        hold: None,
+        original_payee: None,
+        reminder_marker: None,
+        income_bank_id: None,
+        outcome_bank_id: None,
+        qr_code: None,
+        source: None,
+        viewed: None,
+        ..tx.clone()
+    };
+    if let Some(value) = amount {
+        match classify_transaction(tx) {
+            TransactionType::Income => copy.income = value,
+            TransactionType::Expense => copy.outcome = value,
+            TransactionType::Transfer => {
+                copy.income = tx.income * value / tx.outcome;
+                copy.outcome = value;
+            }
+        }
+        copy.op_income = None;
+        copy.op_income_instrument = None;
+        copy.op_outcome = None;
+        copy.op_outcome_instrument = None;
+    }
+    copy
+}
+
+/// Renders the export file of one account; OFX needs its ISO currency code.
+fn render_export(
+    account: &Account,
+    transactions: &[&Transaction],
+    format: ExportFormat,
+    currencies: &HashMap<i32, String>,
+    maps: &LookupMaps,
+) -> String {
+    match format {
+        ExportFormat::Qif => render_qif(account, transactions, maps),
+        ExportFormat::Ofx => {
+            let currency = account
+                .instrument
+                .and_then(|instrument| currencies.get(&instrument.into_inner()))
+                .map_or("XXX", String::as_str);
+            render_ofx(account, currency, transactions, maps, Utc::now())
+        }
+    }
+}
+
+/// Moves the primary side of a transaction to another account.
+fn apply_account_change(
+    tx: &mut Transaction,
+    account_id: &str,
+    maps: &LookupMaps,
+) -> Result<(), McpError> {
+    let tx_type = classify_transaction(tx);
+    match tx_type {
+        TransactionType::Expense => {
+            tx.outcome_account = AccountId::new(account_id.to_owned());
+            tx.income_account = AccountId::new(account_id.to_owned());
+            let instrument = resolve_instrument(maps, account_id, None)?;
+            tx.outcome_instrument = instrument;
+            tx.income_instrument = instrument;
+        }
+        TransactionType::Income => {
+            tx.income_account = AccountId::new(account_id.to_owned());
+            tx.outcome_account = AccountId::new(account_id.to_owned());
+            let instrument = resolve_instrument(maps, account_id, None)?;
+            tx.income_instrument = instrument;
+            tx.outcome_instrument = instrument;
+        }
+        TransactionType::Transfer => {
+            tx.outcome_account = AccountId::new(account_id.to_owned());
+            let instrument = resolve_instrument(maps, account_id, None)?;
+            tx.outcome_instrument = instrument;
+        }
+    }
+    Ok(())
+}
+
+/// Moves a transaction to `target` for `move_transactions`.
+///
+/// Expenses and income move entirely. Transfers move the side on
+/// `source_account` when given, otherwise the outcome side. Returns
+/// `false` when the transaction already uses the target account.
+fn move_transaction(
+    tx: &mut Transaction,
+    source_account: Option<&str>,
+    target: &str,
+    maps: &LookupMaps,
+) -> Result<bool, McpError> {
+    if !matches!(classify_transaction(tx), TransactionType::Transfer) {
+        if tx.outcome_account.as_inner() == target {
+            return Ok(false);
+        }
+        apply_account_change(tx, target, maps)?;
+        return Ok(true);
+    }
+    let move_income = source_account.is_some_and(|source| {
+        tx.income_account.as_inner() == source && tx.outcome_account.as_inner() != source
+    });
+    let (moved, other) = if move_income {
+        (&tx.income_account, &tx.outcome_account)
+    } else {
+        (&tx.outcome_account, &tx.income_account)
+    };
+    if moved.as_inner() == target {
+        return Ok(false);
+    }
+    if other.as_inner() == target {
+        return Err(McpError::invalid_params(
+            format!(
+                "moving transfer {} would make it a transfer from {target} to itself",
+                tx.id
+            ),
+            None,
+        ));
+    }
+    let instrument = resolve_instrument(maps, target, None)?;
+    if move_income {
+        tx.income_account = AccountId::new(target.to_owned());
+        tx.income_instrument = instrument;
+    } else {
+        tx.outcome_account = AccountId::new(target.to_owned());
+        tx.outcome_instrument = instrument;
+    }
+    Ok(true)
+}
+
+/// Applies tag replacement, additions and removals, in that order.
+///
+/// An emptied tag list is stored as `None` (uncategorized).
+fn apply_tag_changes(
+    tx: &mut Transaction,
+    replace: Option<Vec<String>>,
+    add: Option<Vec<String>>,
+    remove: Option<Vec<String>>,
+) {
+    if replace.is_none() && add.is_none() && remove.is_none() {
+        return;
+    }
+    let mut tags: Vec<TagId> = match replace {
+        Some(ids) => ids.into_iter().map(TagId::new).collect(),
+        None => tx.tag.take().unwrap_or_default(),
+    };
+    for id in add.unwrap_or_default() {
+        let tag_id = TagId::new(id);
+        if !tags.contains(&tag_id) {
+            tags.push(tag_id);
+        }
+    }
+    if let Some(remove_ids) = remove {
+        tags.retain(|tag_id| !remove_ids.iter().any(|id| id == tag_id.as_inner()));
+    }
+    tx.tag = if tags.is_empty() { None } else { Some(tags) };
+}
+
+/// Applies [`UpdateTransactionParams`] to an existing [`Transaction`].
+fn apply_update(
+    tx: &mut Transaction,
+    params: UpdateTransactionParams,
+    maps: &LookupMaps,
+) -> Result<(), McpError> {
+    tx.date = params
+        .date
+        .as_deref()
+        .map(parse_date)
+        .transpose()?
+        .unwrap_or(tx.date);
+
+    apply_tag_changes(
+        tx,
+        params.tag_ids,
+        params.add_tag_ids,
+        params.remove_tag_ids,
+    );
+
+    if let Some(payee) = params.payee {
+        tx.payee = if payee.is_empty() { None } else { Some(payee) };
+    }
+
+    if let Some(comment) = params.comment {
         tx.comment = if comment.is_empty() {
             None
         } else {
@@ -313,30 +1867,32 @@ fn apply_update(
         };
     }
 
+    if let Some(merchant_id) = params.merchant_id.as_deref() {
+        tx.merchant = if merchant_id.is_empty() {
+            None
+        } else {
+            Some(resolve_merchant(maps, merchant_id)?)
+        };
+    }
+
+    if let Some(hold) = params.hold {
+        tx.hold = Some(hold);
+    }
+
+    if params.latitude.is_some() || params.longitude.is_some() {
+        validate_coordinates(params.latitude, params.longitude)?;
+        tx.latitude = params.latitude;
+        tx.longitude = params.longitude;
+    }
+
+    if let Some(mcc) = params.mcc {
+        validate_mcc(mcc)?;
+        tx.mcc = (mcc != 0).then_some(mcc);
+    }
+
     // Handle account changes.
     if let Some(account_id) = params.account_id {
-        let tx_type = classify_transaction(tx);
-        match tx_type {
-            TransactionType::Expense => {
-                tx.outcome_account = AccountId::new(account_id.clone());
-                tx.income_account = AccountId::new(account_id.clone());
-                let instrument = resolve_instrument(maps, &account_id, None)?;
-                tx.outcome_instrument = instrument;
-                tx.income_instrument = instrument;
-            }
-            TransactionType::Income => {
-                tx.income_account = AccountId::new(account_id.clone());
-                tx.outcome_account = AccountId::new(account_id.clone());
-                let instrument = resolve_instrument(maps, &account_id, None)?;
-                tx.income_instrument = instrument;
-                tx.outcome_instrument = instrument;
-            }
-            TransactionType::Transfer => {
-                tx.outcome_account = AccountId::new(account_id.clone());
-                let instrument = resolve_instrument(maps, &account_id, None)?;
-                tx.outcome_instrument = instrument;
-            }
-        }
+        apply_account_change(tx, &account_id, maps)?;
     }
 
     if let Some(to_account_id) = params.to_account_id {
@@ -354,12 +1910,11 @@ fn apply_update(
         }
     }
 
-    if let Some(to_amount) = params.to_amount {
-        tx.income = to_amount;
-    }
-
+    tx.income = params.to_amount.unwrap_or(tx.income);
     tx.changed = Utc::now();
-
+    if params.date.is_some() {
+        maps.check_date(tx, params.allow_out_of_range)?;
+    }
     Ok(())
 }
 
@@ -387,12 +1942,7 @@ fn process_bulk_operations(
                 let found = all_transactions
                     .iter()
                     .find(|found_tx| found_tx.id.as_inner() == update_params.id)
-                    .ok_or_else(|| {
-                        McpError::invalid_params(
-                            format!("transaction '{}' not found", update_params.id),
-                            None,
-                        )
-                    })?;
+                    .ok_or_else(|| not_found("transaction", &update_params.id))?;
                 let mut updated = found.clone();
                 apply_update(&mut updated, update_params, maps)?;
                 to_push.push(updated);
@@ -403,10 +1953,7 @@ fn process_bulk_operations(
                     .iter()
                     .any(|found_tx| found_tx.id.as_inner() == delete_params.id)
                 {
-                    return Err(McpError::invalid_params(
-                        format!("transaction '{}' not found", delete_params.id),
-                        None,
-                    ));
+                    return Err(not_found("transaction", &delete_params.id));
                 }
                 to_delete.push(TransactionId::new(delete_params.id));
             }
@@ -416,10 +1963,10 @@ fn process_bulk_operations(
     Ok((to_push, to_delete, created_count, updated_count))
 }
 
-/// Validates and normalizes a tag title.
+/// Validates and normalizes a tag or merchant title.
 ///
 /// Trims leading/trailing whitespace and rejects empty/blank titles.
-fn normalize_tag_title(title: &str) -> Result<String, McpError> {
+fn normalize_title(title: &str) -> Result<String, McpError> {
     let trimmed = title.trim();
     if trimmed.is_empty() {
         return Err(McpError::invalid_params(
@@ -442,18 +1989,63 @@ fn find_tag_by_title_case_insensitive<'tag>(tags: &'tag [Tag], title: &str) -> O
         .find(|tag| normalized_title_key(&tag.title) == key)
 }
 
-/// Validates that `parent_tag_id` exists in the current tag list.
-fn validate_parent_tag_exists(tags: &[Tag], parent_tag_id: Option<&str>) -> Result<(), McpError> {
-    if let Some(parent_id) = parent_tag_id {
-        let parent_exists = tags.iter().any(|tag| tag.id.as_inner() == parent_id);
-        if !parent_exists {
+/// Builds a new merchant with a fresh ID.
+fn build_merchant(title: String, user_id: i64) -> Merchant {
+    Merchant {
+        id: MerchantId::new(uuid::Uuid::new_v4().to_string()),
+        changed: Utc::now(),
+        user: UserId::new(user_id),
+        title,
+    }
+}
+
+/// Finds a merchant by ID or returns an `invalid_params` error.
+fn find_merchant<'merchant>(
+    merchants: &'merchant [Merchant],
+    merchant_id: &str,
+) -> Result<&'merchant Merchant, McpError> {
+    merchants
+        .iter()
+        .find(|merchant| merchant.id.as_inner() == merchant_id)
+        .ok_or_else(|| not_found("merchant", merchant_id))
+}
+
+/// Validates merge sources against the target and returns the distinct source IDs.
+fn validate_merge_sources<'id>(
+    merchants: &[Merchant],
+    source_ids: &'id [String],
+    target_id: &str,
+) -> Result<HashSet<&'id str>, McpError> {
+    let _target = find_merchant(merchants, target_id)?;
+    let mut sources: HashSet<&str> = HashSet::new();
+    for source_id in source_ids {
+        if source_id == target_id {
             return Err(McpError::invalid_params(
-                format!("parent_tag_id '{parent_id}' not found"),
+                "target_merchant_id must not be listed in source_merchant_ids",
                 None,
             ));
         }
+        let _source = find_merchant(merchants, source_id)?;
+        let _inserted = sources.insert(source_id.as_str());
     }
-    Ok(())
+    if sources.is_empty() {
+        return Err(McpError::invalid_params(
+            "source_merchant_ids must not be empty",
+            None,
+        ));
+    }
+    Ok(sources)
+}
+
+/// Validates that `parent_tag_id` exists in the current tag list.
+fn validate_parent_tag_exists(tags: &[Tag], parent_tag_id: Option<&str>) -> Result<(), McpError> {
+    if let Some(parent_id) = parent_tag_id {
+        let parent_exists = tags.iter().any(|tag| tag.id.as_inner() == parent_id);
+        if !parent_exists {
+            return Err(not_found("tag", parent_id));
+        }
+    }
+    Ok(())
 }
 
 /// Builds a new [`Tag`] from validated creation parameters.
@@ -480,1440 +2072,7356 @@ fn build_tag(params: CreateTagParams, user_id: i64, title: String) -> Tag {
 #[tool_router]
 impl<S: Storage + 'static> ZenMoneyMcpServer<S> {
     /// Creates a new MCP server with the given ZenMoney client.
-    pub(crate) fn new(client: ZenMoney<S>) -> Self {
+    #[inline]
+    #[must_use]
+    pub fn new(client: ZenMoney<S>) -> Self {
         Self {
             client: Arc::new(client),
             tool_router: Self::tool_router(),
             preparations: Arc::new(Mutex::new(HashMap::new())),
+            templates: Arc::new(TemplateStore::default()),
+            envelopes: Arc::new(EnvelopeStore::default()),
+            confirm_destructive: false,
+            include_ids: true,
+            compact_output: false,
+            client_log: ClientLog::default(),
+            resource_watch: ResourceWatch::default(),
+            instructions: DEFAULT_INSTRUCTIONS.to_owned(),
+            data_dir: None,
+            last_sync: Arc::new(Mutex::new(None)),
+            max_data_age: None,
+            sync_clock: Arc::new(SyncClock::default()),
+            throttle: Arc::new(RateLimiter::new(DEFAULT_MIN_REQUEST_INTERVAL)),
+            sync_gate: Arc::new(SyncCoalescer::default()),
+            write_lock: Arc::new(tokio::sync::Mutex::new(())),
+            usage: Arc::new(UsageStats::default()),
+            pending: Arc::new(PendingQueue::default()),
+            conflicts: Arc::new(ConflictStore::default()),
+            reviews: Arc::new(ReviewStore::default()),
+            planned: Arc::new(PlannedStore::default()),
+            default_account: Arc::new(DefaultAccountStore::default()),
+            date_window: DateWindow::default(),
+            base_currency: None,
+            closing: Arc::new(AtomicBool::new(false)),
+            restored: Arc::new(AtomicBool::new(false)),
+            offline_mode: None,
         }
     }
 
-    /// Builds lookup maps from current storage for enriching responses.
-    async fn lookup_maps(&self) -> Result<LookupMaps, McpError> {
-        let accounts = self.client.accounts().await.map_err(zen_err)?;
-        let tags = self.client.tags().await.map_err(zen_err)?;
-        let instruments = self.client.instruments().await.map_err(zen_err)?;
-        Ok(build_lookup_maps(&accounts, &tags, &instruments))
+    /// Returns a router over all tools of this server.
+    ///
+    /// Lets an embedding application dispatch to these tools from its own
+    /// handler, alongside tools of its own.
+    #[inline]
+    #[must_use]
+    pub fn tools() -> ToolRouter<Self> {
+        Self::tool_router()
     }
 
-    /// Returns the first synced user ID, or `0` when local storage has no users.
-    async fn current_user_id(&self) -> Result<i64, McpError> {
-        let users = self.client.users().await.map_err(zen_err)?;
-        Ok(users.first().map_or(0, |user| user.id.into_inner()))
+    /// Replaces the in-memory template store with a persistent one.
+    #[inline]
+    #[must_use]
+    pub fn with_template_store(mut self, templates: TemplateStore) -> Self {
+        self.templates = Arc::new(templates);
+        self
     }
 
-    /// Shared implementation for `create_tag` and `create_category`.
-    async fn create_tag_internal(
-        &self,
-        params: CreateTagParams,
-    ) -> Result<CallToolResult, McpError> {
-        let normalized_title = normalize_tag_title(&params.title)?;
-        let tags = self.client.tags().await.map_err(zen_err)?;
+    /// Replaces the in-memory envelope store with a persistent one.
+    #[inline]
+    #[must_use]
+    pub fn with_envelope_store(mut self, envelopes: EnvelopeStore) -> Self {
+        self.envelopes = Arc::new(envelopes);
+        self
+    }
 
-        if let Some(existing_tag) = find_tag_by_title_case_insensitive(&tags, &normalized_title) {
-            let maps = self.lookup_maps().await?;
-            let result = TagResponse::from_tag(existing_tag, &maps);
-            return json_result(&result);
+    /// Sets the storage directory that `purge_local_data` wipes.
+    #[inline]
+    #[must_use]
+    pub fn with_data_dir(mut self, dir: PathBuf) -> Self {
+        self.data_dir = Some(dir);
+        self
+    }
+
+    /// Makes read tools sync first when the last sync is older than `max_age`.
+    ///
+    /// Data counts as stale until a sync runs or [`Self::mark_synced`] is
+    /// called. `None` disables the automatic sync, as do demo and replay
+    /// data, which are never synced.
+    #[inline]
+    #[must_use]
+    pub const fn with_max_data_age(mut self, max_age: Option<Duration>) -> Self {
+        if self.offline_mode.is_none() {
+            self.max_data_age = max_age;
         }
+        self
+    }
 
-        validate_parent_tag_exists(&tags, params.parent_tag_id.as_deref())?;
+    /// Seeds local storage with generated demo data and refuses tools that
+    /// would call the ZenMoney API (syncs and writes).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data cannot be written to local storage.
+    #[inline]
+    pub async fn with_demo_data(mut self) -> std::io::Result<Self> {
+        self.store_entities(demo::dataset(Utc::now().date_naive()))
+            .await
+            .map_err(|err| std::io::Error::other(err.message.into_owned()))?;
+        self.offline_mode = Some("demo");
+        self.max_data_age = None;
+        Ok(self)
+    }
 
-        let user_id = self.current_user_id().await?;
-        let new_tag = build_tag(params, user_id, normalized_title);
-        let maps = self.lookup_maps().await?;
-        let preview = TagResponse::from_tag(&new_tag, &maps);
+    /// Seeds local storage from a replay file written by [`Self::record_replay`]
+    /// and refuses tools that would call the ZenMoney API.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, fails its integrity
+    /// checks or cannot be written to local storage.
+    #[inline]
+    pub async fn with_replay(mut self, path: &Path) -> std::io::Result<Self> {
+        let (recorded_at, entities) = read_backup(path)?;
+        self.store_entities(entities)
+            .await
+            .map_err(|err| std::io::Error::other(err.message.into_owned()))?;
+        tracing::info!(%recorded_at, path = %path.display(), "replaying recorded data");
+        self.offline_mode = Some("replay");
+        self.max_data_age = None;
+        Ok(self)
+    }
 
-        let _response = self
-            .client
-            .push_tags(vec![new_tag])
+    /// Saves all locally synced data to a replay file for [`Self::with_replay`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if local data cannot be read or the file cannot be written.
+    #[inline]
+    pub async fn record_replay(&self, path: &Path) -> std::io::Result<()> {
+        let entities = self
+            .backup_entities()
             .await
-            .map_err(zen_err)?;
+            .map_err(|err| std::io::Error::other(err.message.into_owned()))?;
+        let bytes = write_backup(path, &entities, Utc::now())?;
+        tracing::info!(bytes, path = %path.display(), "recorded synced data for replay");
+        Ok(())
+    }
+
+    /// Sets the queue of writes made while ZenMoney was unreachable.
+    #[inline]
+    #[must_use]
+    pub fn with_pending_queue(mut self, pending: PendingQueue) -> Self {
+        self.pending = Arc::new(pending);
+        self
+    }
+
+    /// Sets the store of conflicts between queued edits and server changes.
+    #[inline]
+    #[must_use]
+    pub fn with_conflict_store(mut self, conflicts: ConflictStore) -> Self {
+        self.conflicts = Arc::new(conflicts);
+        self
+    }
+
+    /// Sets the store of the inbox review watermark.
+    #[inline]
+    #[must_use]
+    pub fn with_review_store(mut self, reviews: ReviewStore) -> Self {
+        self.reviews = Arc::new(reviews);
+        self
+    }
+
+    /// Sets the allowed dates for created and updated transactions.
+    #[inline]
+    #[must_use]
+    pub const fn with_date_window(mut self, window: DateWindow) -> Self {
+        self.date_window = window;
+        self
+    }
+
+    /// Sets the currency converted totals default to, as an ISO code such as
+    /// `USD` or an instrument ID. Without one, the currency of the account
+    /// owner is used.
+    #[inline]
+    #[must_use]
+    pub fn with_base_currency(mut self, currency: Option<String>) -> Self {
+        self.base_currency = currency
+            .map(|code| code.trim().to_owned())
+            .filter(|code| !code.is_empty());
+        self
+    }
+
+    /// Sets the store of the default account for quick creates.
+    #[inline]
+    #[must_use]
+    pub fn with_default_account_store(mut self, default_account: DefaultAccountStore) -> Self {
+        self.default_account = Arc::new(default_account);
+        self
+    }
+
+    /// Sets the store of planned transaction IDs.
+    #[inline]
+    #[must_use]
+    pub fn with_planned_store(mut self, planned: PlannedStore) -> Self {
+        self.planned = Arc::new(planned);
+        self
+    }
+
+    /// Starts a background task per scheduled job, each writing its report
+    /// whenever its cron expression comes due until the process exits.
+    #[inline]
+    pub fn spawn_scheduled_jobs(&self, jobs: Vec<ScheduledJob>) {
+        for job in jobs {
+            let server = self.clone();
+            let _task = tokio::spawn(async move { server.run_scheduled_job(job).await });
+        }
+    }
 
-        json_result(&preview)
+    /// Returns a handle that drains in-flight writes and syncs before shutdown.
+    #[inline]
+    #[must_use]
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle::new(
+            Arc::clone(&self.closing),
+            Arc::clone(&self.write_lock),
+            Arc::clone(&self.sync_gate),
+        )
     }
 
-    // ── Sync tools ──────────────────────────────────────────────────
+    /// Records that local data was just synced, as after the initial sync;
+    /// until then, the first read tool syncs when a maximum data age is set.
+    #[inline]
+    pub fn mark_synced(&self) {
+        self.sync_clock.mark();
+    }
 
-    /// Performs an incremental sync with the ZenMoney server.
-    #[tool(
-        description = "Perform an incremental sync with the ZenMoney server, fetching only changes since the last sync"
-    )]
-    async fn sync(&self) -> Result<CallToolResult, McpError> {
-        let _response = self.client.sync().await.map_err(zen_err)?;
-        Ok(CallToolResult::success(vec![Content::text(
-            "Sync completed successfully",
-        )]))
+    /// Sets the minimum interval between ZenMoney API requests (zero disables pacing).
+    #[inline]
+    #[must_use]
+    pub fn with_min_request_interval(mut self, interval: Duration) -> Self {
+        self.throttle = Arc::new(RateLimiter::new(interval));
+        self
     }
 
-    /// Performs a full sync, clearing local data and re-downloading everything.
-    #[tool(
-        description = "Perform a full sync, clearing all local data and re-downloading everything from the ZenMoney server"
-    )]
-    async fn full_sync(&self) -> Result<CallToolResult, McpError> {
-        let _response = self.client.full_sync().await.map_err(zen_err)?;
-        Ok(CallToolResult::success(vec![Content::text(
-            "Full sync completed successfully",
-        )]))
+    /// Enables elicitation prompts before destructive operations.
+    #[inline]
+    #[must_use]
+    pub const fn with_confirm_destructive(mut self, enabled: bool) -> Self {
+        self.confirm_destructive = enabled;
+        self
     }
 
-    // ── Read tools ──────────────────────────────────────────────────
+    /// Sets whether responses carry raw IDs (account, tag, merchant) next to
+    /// resolved names; on by default so results can be fed back into writes.
+    #[inline]
+    #[must_use]
+    pub const fn with_include_ids(mut self, enabled: bool) -> Self {
+        self.include_ids = enabled;
+        self
+    }
 
-    /// Lists all accounts (or only active ones).
-    #[tool(
-        description = "List financial accounts. Set active_only=true to exclude archived accounts"
-    )]
-    async fn list_accounts(
-        &self,
-        params: Parameters<ListAccountsParams>,
-    ) -> Result<CallToolResult, McpError> {
-        let maps = self.lookup_maps().await?;
-        let accounts = if params.0.active_only {
-            self.client.active_accounts().await.map_err(zen_err)?
-        } else {
-            self.client.accounts().await.map_err(zen_err)?
+    /// Makes tool results compact by default; calls can still pass `compact: false`.
+    #[inline]
+    #[must_use]
+    pub const fn with_compact_output(mut self, enabled: bool) -> Self {
+        self.compact_output = enabled;
+        self
+    }
+
+    /// Shares the logging destination used by the tracing layer.
+    #[inline]
+    #[must_use]
+    pub fn with_client_log(mut self, client_log: ClientLog) -> Self {
+        self.client_log = client_log;
+        self
+    }
+
+    /// Sets the instructions to `base` followed by a summary of the user's data.
+    ///
+    /// The summary is left out if local data cannot be read.
+    #[inline]
+    pub async fn with_session_instructions(mut self, base: &str) -> Self {
+        let session = match self.session_context().await {
+            Ok(block) => Some(block),
+            Err(err) => {
+                tracing::warn!("failed to build session context: {}", err.message);
+                None
+            }
         };
-        let result: Vec<AccountResponse> = accounts
+        self.instructions = compose_instructions(base, session.as_deref());
+        self
+    }
+
+    /// Describes the main accounts, base currency and current month.
+    ///
+    /// Active accounts are listed by balance in the base currency, largest first.
+    async fn session_context(&self) -> Result<String, McpError> {
+        let maps = self.lookup_maps().await?;
+        let base = self.base_currency().await?;
+        let accounts = self.client.active_accounts().await.map_err(zen_err)?;
+        let mut ranked: Vec<(f64, String)> = accounts
             .iter()
-            .map(|acc| AccountResponse::from_account(acc, &maps))
+            .map(|account| {
+                let instrument = account.instrument.map(InstrumentId::into_inner);
+                let balance = account.balance.unwrap_or(0.0);
+                let weight = base
+                    .zip(instrument)
+                    .and_then(|pair| maps.convert(balance, pair.1, pair.0))
+                    .unwrap_or(balance)
+                    .abs();
+                let currency =
+                    instrument.map_or_else(|| "?".to_owned(), |id| maps.instrument_symbol(id));
+                (
+                    weight,
+                    format!("{} ({currency}, id {})", account.title, account.id),
+                )
+            })
             .collect();
-        json_result(&result)
+        ranked.sort_by(|left, right| right.0.total_cmp(&left.0));
+        let labels: Vec<String> = ranked.into_iter().map(|entry| entry.1).collect();
+        let base_currency =
+            base.map(|id| format!("{} (instrument {id})", maps.instrument_symbol(id)));
+        Ok(session_block(
+            &labels,
+            base_currency.as_deref(),
+            Utc::now().date_naive(),
+        ))
+    }
+
+    /// Lists `(id, title)` pairs of the entities that complete an argument.
+    async fn completion_candidates(
+        &self,
+        source: CompletionSource,
+    ) -> Result<Vec<(String, String)>, McpError> {
+        Ok(match source {
+            CompletionSource::Account => self
+                .client
+                .accounts()
+                .await
+                .map_err(zen_err)?
+                .into_iter()
+                .filter(|account| !account.archive)
+                .map(|account| (account.id.to_string(), account.title))
+                .collect(),
+            CompletionSource::Tag => self
+                .client
+                .tags()
+                .await
+                .map_err(zen_err)?
+                .into_iter()
+                .filter(|tag| tag.archive != Some(true))
+                .map(|tag| (tag.id.to_string(), tag.title))
+                .collect(),
+            CompletionSource::Merchant => self
+                .client
+                .merchants()
+                .await
+                .map_err(zen_err)?
+                .into_iter()
+                .map(|merchant| (merchant.id.to_string(), merchant.title))
+                .collect(),
+        })
     }
 
-    /// Lists transactions with optional filtering, sorting, pagination, and type/category filters.
-    #[tool(
-        description = "List transactions with optional filters: date range, account, tag, payee, merchant, amount range, transaction_type (expense/income/transfer), uncategorized (true to show only untagged), sort (asc/desc by date, default desc), limit (default 100, max 500), and offset (for pagination). Returns {items, total, offset, limit}."
-    )]
-    async fn list_transactions(
-        &self,
-        params: Parameters<ListTransactionsParams>,
-    ) -> Result<CallToolResult, McpError> {
+    /// Reads the entity behind a resource URI as enriched JSON text.
+    async fn read_entity(&self, uri: &str) -> Result<String, McpError> {
+        let not_found =
+            || McpError::resource_not_found(format!("resource '{uri}' not found"), None);
+        let entity = EntityUri::parse(uri).ok_or_else(not_found)?;
         let maps = self.lookup_maps().await?;
-        let mut filter = TransactionFilter::new();
-
-        if let Some(date_from_str) = params.0.date_from.as_deref() {
-            filter.date_from = Some(parse_date(date_from_str)?);
-        }
-        if let Some(date_to_str) = params.0.date_to.as_deref() {
-            filter.date_to = Some(parse_date(date_to_str)?);
-        }
-        if let Some(account_id) = params.0.account_id.as_ref() {
-            filter = filter.account(AccountId::new(account_id.clone()));
-        }
-        if let Some(tag_id) = params.0.tag_id.as_ref() {
-            filter = filter.tag(TagId::new(tag_id.clone()));
-        }
-        if let Some(payee_str) = params.0.payee.as_ref() {
-            filter = filter.payee(payee_str.clone());
-        }
-        if let Some(merchant_id) = params.0.merchant_id.as_ref() {
-            filter = filter.merchant(MerchantId::new(merchant_id.clone()));
-        }
-        if let Some(min) = params.0.min_amount {
-            filter.min_amount = Some(min);
-        }
-        if let Some(max) = params.0.max_amount {
-            filter.max_amount = Some(max);
+        match entity {
+            EntityUri::Transaction(id) => {
+                let transactions = self.client.transactions().await.map_err(zen_err)?;
+                let found = transactions
+                    .iter()
+                    .find(|tx| tx.id.as_inner() == id)
+                    .ok_or_else(not_found)?;
+                to_json_text(&TransactionResponse::from_transaction(found, &maps))
+            }
+            EntityUri::Account(id) => {
+                let accounts = self.client.accounts().await.map_err(zen_err)?;
+                let found = accounts
+                    .iter()
+                    .find(|account| account.id.as_inner() == id)
+                    .ok_or_else(not_found)?;
+                to_json_text(&AccountResponse::from_account(found, &maps))
+            }
+            EntityUri::Budget(month) => {
+                let month_date = parse_month(Some(month))?;
+                let budgets = self.client.budgets().await.map_err(zen_err)?;
+                let result: Vec<BudgetResponse> = budgets
+                    .iter()
+                    .filter(|budget| budget.date == month_date)
+                    .map(|budget| BudgetResponse::from_budget(budget, &maps))
+                    .collect();
+                to_json_text(&result)
+            }
+            EntityUri::Inbox => {
+                let transactions = self.client.transactions().await.map_err(zen_err)?;
+                let by_id: HashMap<&str, &Transaction> = transactions
+                    .iter()
+                    .filter(|tx| !tx.deleted)
+                    .map(|tx| (tx.id.as_inner(), tx))
+                    .collect();
+                let inbox = self.resource_watch.inbox();
+                let arrived: Vec<&Transaction> = inbox
+                    .iter()
+                    .rev()
+                    .filter_map(|id| by_id.get(id.as_str()).copied())
+                    .collect();
+                to_json_text(&InboxResponse::new(&arrived, &maps))
+            }
         }
+    }
 
-        let mut transactions = self
-            .client
-            .filter_transactions(&filter)
-            .await
-            .map_err(zen_err)?;
+    /// Snapshots the versions of readable resources.
+    async fn resource_stamps(&self) -> Result<HashMap<String, ResourceStamp>, McpError> {
+        let accounts = self.client.accounts().await.map_err(zen_err)?;
+        let transactions = self.client.transactions().await.map_err(zen_err)?;
+        let budgets = self.client.budgets().await.map_err(zen_err)?;
+        let entries = accounts
+            .iter()
+            .map(|account| (account_uri(account.id.as_inner()), account.changed))
+            .chain(
+                transactions
+                    .iter()
+                    .map(|tx| (transaction_uri(tx.id.as_inner()), tx.changed)),
+            )
+            .chain(
+                budgets
+                    .iter()
+                    .map(|budget| (budget_uri(budget.date), budget.changed)),
+            );
+        Ok(stamp_resources(entries))
+    }
 
-        // Filter by uncategorized.
-        if params.0.uncategorized == Some(true) {
-            transactions.retain(is_uncategorized);
-        }
+    /// Notifies the client about resources changed since the last snapshot.
+    ///
+    /// Called after writes; does nothing until a client connects.
+    async fn refresh_resources(&self) {
+        self.publish_resources(false).await;
+    }
 
-        // Filter by transaction type.
-        filter_by_transaction_type(&mut transactions, params.0.transaction_type.as_ref());
+    /// Notifies the client about resources changed by a sync, adding the
+    /// transactions it brought to the inbox.
+    async fn refresh_after_sync(&self) {
+        self.publish_resources(true).await;
+    }
 
-        // Sort by date.
-        let sort_dir = params.0.sort.unwrap_or_default();
-        match sort_dir {
-            SortDirection::Desc => transactions.sort_by(|left, right| right.date.cmp(&left.date)),
-            SortDirection::Asc => transactions.sort_by(|left, right| left.date.cmp(&right.date)),
+    /// Snapshots resources and publishes the changes; `synced` marks a
+    /// snapshot taken right after a sync.
+    async fn publish_resources(&self, synced: bool) {
+        if !self.resource_watch.is_active() {
+            return;
         }
+        match self.resource_stamps().await {
+            Ok(stamps) => self.resource_watch.publish(stamps, synced).await,
+            Err(err) => tracing::debug!("failed to snapshot resources: {}", err.message),
+        }
+    }
 
-        let total = transactions.len();
-        let offset = params.0.offset.unwrap_or(0);
-        let limit = params
-            .0
-            .limit
-            .unwrap_or(DEFAULT_TRANSACTION_LIMIT)
-            .min(MAX_TRANSACTION_LIMIT);
+    /// Runs an incremental sync before a read tool if local data is stale.
+    ///
+    /// Failures are logged and the tool answers from local data.
+    async fn ensure_fresh(&self, request: &CallToolRequestParam) {
+        let Some(max_age) = self.max_data_age else {
+            return;
+        };
+        if !READ_TOOLS.contains(&request.name.as_ref())
+            || skip_requested(request.arguments.as_ref())
+            || !self.sync_clock.is_older_than(max_age)
+        {
+            return;
+        }
+        tracing::info!(tool = %request.name, "local data is stale, syncing before read");
+        match self.run_sync().await {
+            Ok(()) => self.refresh_after_sync().await,
+            Err(err) => tracing::warn!(
+                error = %err.message,
+                "automatic sync failed, answering from local data"
+            ),
+        }
+    }
 
-        let items: Vec<TransactionResponse> = transactions
+    /// Pushes `transactions`, queueing them locally if ZenMoney cannot be reached.
+    ///
+    /// A queued write is saved to local storage so read tools see it, and
+    /// its pending change ID is returned.
+    async fn push_or_queue(
+        &self,
+        tool: &str,
+        transactions: Vec<Transaction>,
+    ) -> Result<Option<String>, McpError> {
+        self.throttle.pace().await;
+        let err = match self.client.push_transactions(transactions.clone()).await {
+            Ok(_response) => return Ok(None),
+            Err(err) if !is_unreachable(&err) => return Err(zen_err(err)),
+            Err(err) => err,
+        };
+        tracing::warn!(%err, tool, "ZenMoney is unreachable, queueing the write");
+        let ids: HashSet<&str> = transactions.iter().map(|tx| tx.id.as_inner()).collect();
+        let previous = self
+            .client
+            .transactions()
+            .await
+            .map_err(zen_err)?
             .into_iter()
-            .skip(offset)
-            .take(limit)
-            .map(|tx| TransactionResponse::from_transaction(&tx, &maps))
+            .filter(|tx| ids.contains(tx.id.as_inner()))
             .collect();
-
-        json_result(&PaginatedTransactions {
-            items,
-            total,
-            offset,
-            limit,
-        })
+        self.client
+            .storage()
+            .upsert_transactions(transactions.clone())
+            .await
+            .map_err(storage_err)?;
+        let id = uuid::Uuid::new_v4().to_string();
+        self.pending
+            .push(PendingChange {
+                id: id.clone(),
+                tool: tool.to_owned(),
+                queued_at: Utc::now(),
+                transactions,
+                previous,
+            })
+            .map_err(io_err)?;
+        Ok(Some(id))
     }
 
-    /// Lists all category tags.
-    #[tool(description = "List all transaction category tags")]
-    async fn list_tags(&self) -> Result<CallToolResult, McpError> {
-        let maps = self.lookup_maps().await?;
-        let tags = self.client.tags().await.map_err(zen_err)?;
-        let result: Vec<TagResponse> = tags
+    /// Sends queued writes in order, holding back edits that conflict.
+    ///
+    /// Runs after a sync. Stops at the first write that still cannot reach
+    /// ZenMoney; writes ZenMoney rejects stay queued for the user to cancel.
+    async fn flush_pending(&self) {
+        // Queued writes are pushed like a write tool's, so they wait for a
+        // write turn as well.
+        let Ok(_turn) = self.write_turn().await else {
+            tracing::info!("server is shutting down; queued writes stay queued");
+            return;
+        };
+        let changes = match self.pending.list() {
+            Ok(changes) => changes,
+            Err(err) => {
+                tracing::warn!(%err, "failed to read pending changes");
+                return;
+            }
+        };
+        let known: HashSet<(String, DateTime<Utc>)> = changes
             .iter()
-            .map(|tag| TagResponse::from_tag(tag, &maps))
+            .flat_map(|change| change.transactions.iter().chain(&change.previous))
+            .map(|tx| (tx.id.as_inner().to_owned(), tx.changed))
             .collect();
-        json_result(&result)
+        for change in changes {
+            let transactions = match self.hold_back_conflicts(change.clone(), &known).await {
+                Ok(transactions) if transactions.is_empty() => continue,
+                Ok(transactions) => transactions,
+                Err(err) => {
+                    tracing::warn!(error = %err.message, id = %change.id, "conflict check failed");
+                    continue;
+                }
+            };
+            self.throttle.pace().await;
+            match self.client.push_transactions(transactions).await {
+                Ok(_response) => {
+                    tracing::info!(id = %change.id, tool = %change.tool, "pending change sent");
+                    if let Err(err) = self.pending.remove(&change.id) {
+                        tracing::warn!(%err, id = %change.id, "failed to dequeue pending change");
+                    }
+                }
+                Err(err) if is_unreachable(&err) => return,
+                Err(err) => tracing::warn!(%err, id = %change.id, "pending change rejected"),
+            }
+        }
     }
 
-    /// Lists all merchants.
-    #[tool(description = "List all merchants/payees")]
-    async fn list_merchants(&self) -> Result<CallToolResult, McpError> {
-        let merchants = self.client.merchants().await.map_err(zen_err)?;
-        let result: Vec<MerchantResponse> = merchants
-            .iter()
-            .map(MerchantResponse::from_merchant)
-            .collect();
-        json_result(&result)
+    /// Waits for a turn to write and returns the guard holding it.
+    ///
+    /// Writes read, modify and push local state, so interleaving two of
+    /// them could push stale copies over each other's changes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the server started shutting down meanwhile.
+    async fn write_turn(&self) -> Result<tokio::sync::MutexGuard<'_, ()>, McpError> {
+        let turn = self.write_lock.lock().await;
+        if shutdown::is_closing(&self.closing) {
+            return Err(McpError::internal_error(
+                "server is shutting down; the write was not applied",
+                None,
+            ));
+        }
+        Ok(turn)
     }
 
-    /// Lists budgets, optionally filtered by month.
-    #[tool(description = "List monthly budgets. Optionally filter by month (format: YYYY-MM)")]
-    async fn list_budgets(
+    /// Moves the transactions of `change` that ZenMoney changed since they
+    /// were queued into the conflict store, returning the rest.
+    ///
+    /// After a sync, local storage holds the server version of each queued
+    /// transaction. If its `changed` time matches neither a queued version
+    /// nor the version the edit replaced, someone else changed it meanwhile.
+    async fn hold_back_conflicts(
         &self,
-        params: Parameters<ListBudgetsParams>,
-    ) -> Result<CallToolResult, McpError> {
-        let maps = self.lookup_maps().await?;
-        let budgets = self.client.budgets().await.map_err(zen_err)?;
-
-        let filtered_budgets: Vec<_> = if let Some(month_str) = params.0.month.as_deref() {
-            let month_prefix = format!("{month_str}-01");
-            let month_date = parse_date(&month_prefix)?;
-            budgets
-                .into_iter()
-                .filter(|budget| budget.date == month_date)
-                .collect()
-        } else {
-            budgets
-        };
-
-        let result: Vec<BudgetResponse> = filtered_budgets
+        mut change: PendingChange,
+        known: &HashSet<(String, DateTime<Utc>)>,
+    ) -> Result<Vec<Transaction>, McpError> {
+        let ids: HashSet<&str> = change
+            .transactions
             .iter()
-            .map(|budget| BudgetResponse::from_budget(budget, &maps))
+            .map(|tx| tx.id.as_inner())
             .collect();
-        json_result(&result)
+        let changed_remotely: HashMap<String, Transaction> = self
+            .client
+            .transactions()
+            .await
+            .map_err(zen_err)?
+            .into_iter()
+            .filter(|tx| ids.contains(tx.id.as_inner()))
+            .filter(|tx| !known.contains(&(tx.id.as_inner().to_owned(), tx.changed)))
+            .map(|tx| (tx.id.as_inner().to_owned(), tx))
+            .collect();
+        if changed_remotely.is_empty() {
+            return Ok(change.transactions);
+        }
+        let (conflicting, clean): (Vec<Transaction>, Vec<Transaction>) = change
+            .transactions
+            .into_iter()
+            .partition(|tx| changed_remotely.contains_key(tx.id.as_inner()));
+        let found: Vec<Conflict> = conflicting
+            .into_iter()
+            .filter_map(|local| {
+                let remote = changed_remotely.get(local.id.as_inner())?.clone();
+                Some(Conflict {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    detected_at: Utc::now(),
+                    local,
+                    remote,
+                })
+            })
+            .collect();
+        tracing::warn!(
+            id = %change.id,
+            count = found.len(),
+            "queued edits conflict with server changes"
+        );
+        self.conflicts.record(found).map_err(io_err)?;
+        change
+            .previous
+            .retain(|tx| !changed_remotely.contains_key(tx.id.as_inner()));
+        change.transactions.clone_from(&clean);
+        if clean.is_empty() {
+            let _removed = self.pending.remove(&change.id).map_err(io_err)?;
+        } else {
+            let _updated = self.pending.update(change).map_err(io_err)?;
+        }
+        Ok(clean)
     }
 
-    /// Lists all reminders.
-    #[tool(description = "List all recurring transaction reminders")]
-    async fn list_reminders(&self) -> Result<CallToolResult, McpError> {
-        let maps = self.lookup_maps().await?;
-        let reminders = self.client.reminders().await.map_err(zen_err)?;
-        let result: Vec<ReminderResponse> = reminders
+    /// Undoes the local effect of a cancelled queued write: replaced
+    /// transactions get their previous version back and created ones are
+    /// marked deleted.
+    async fn revert_pending(&self, change: PendingChange) -> Result<(), McpError> {
+        let replaced: HashSet<&str> = change.previous.iter().map(|tx| tx.id.as_inner()).collect();
+        let created = change
+            .transactions
             .iter()
-            .map(|rem| ReminderResponse::from_reminder(rem, &maps))
-            .collect();
-        json_result(&result)
+            .filter(|tx| !replaced.contains(tx.id.as_inner()))
+            .cloned()
+            .map(|mut tx| {
+                tx.deleted = true;
+                tx
+            });
+        let reverted: Vec<Transaction> = change.previous.iter().cloned().chain(created).collect();
+        self.client
+            .storage()
+            .upsert_transactions(reverted)
+            .await
+            .map_err(storage_err)?;
+        self.refresh_resources().await;
+        Ok(())
     }
 
-    /// Lists all currency instruments.
-    #[tool(description = "List all currency instruments with their exchange rates")]
-    async fn list_instruments(&self) -> Result<CallToolResult, McpError> {
-        let instruments = self.client.instruments().await.map_err(zen_err)?;
-        let result: Vec<InstrumentResponse> = instruments
-            .iter()
-            .map(InstrumentResponse::from_instrument)
-            .collect();
-        json_result(&result)
+    /// Runs an incremental sync, or waits for the one already in flight.
+    ///
+    /// Writes queued while ZenMoney was unreachable are sent once it succeeds.
+    async fn run_sync(&self) -> Result<(), McpError> {
+        let synced = self
+            .sync_gate
+            .run(async {
+                self.throttle.pace().await;
+                let synced = self.client.sync().await;
+                if synced.is_ok() {
+                    self.flush_pending().await;
+                }
+                synced
+            })
+            .await
+            .map_err(zen_err)?;
+        if synced.is_none() {
+            tracing::debug!("joined a sync already in flight");
+        }
+        self.restored.store(false, Ordering::Release);
+        self.sync_clock.mark();
+        Ok(())
     }
 
-    // ── Search tools ────────────────────────────────────────────────
+    /// Counts the changes made since `before` and keeps them for `last_sync_changes`.
+    async fn record_sync(
+        &self,
+        before: &Snapshot,
+        full: bool,
+    ) -> Result<SyncChangesResponse, McpError> {
+        let after = self
+            .entity_snapshot(&BTreeSet::from(EntityKind::ALL))
+            .await?;
+        let mut changes = count_changes(before, &after);
+        changes.retain(|_, counts| counts.total() > 0);
+        let summary = SyncChangesResponse {
+            synced_at: Utc::now().to_rfc3339(),
+            full,
+            total_changed: changes.values().map(ChangeCounts::total).sum(),
+            changes,
+        };
+        let _previous = self
+            .last_sync
+            .lock()
+            .map_err(|err| McpError::internal_error(format!("lock poisoned: {err}"), None))?
+            .replace(summary.clone());
+        Ok(summary)
+    }
+
+    /// Records the versions of stored entities of the given types.
+    async fn entity_snapshot(&self, kinds: &BTreeSet<EntityKind>) -> Result<Snapshot, McpError> {
+        let mut snapshot = Snapshot::new();
+        for kind in kinds.iter().copied() {
+            let stamps = match kind {
+                EntityKind::Transaction => {
+                    let transactions = self.client.transactions().await.map_err(zen_err)?;
+                    stamp_entities(
+                        transactions
+                            .into_iter()
+                            .map(|tx| (tx.id.as_inner().to_owned(), tx.changed, tx.deleted)),
+                    )
+                }
+                EntityKind::Account => {
+                    let accounts = self.client.accounts().await.map_err(zen_err)?;
+                    stamp_entities(
+                        accounts
+                            .into_iter()
+                            .map(|acc| (acc.id.as_inner().to_owned(), acc.changed, false)),
+                    )
+                }
+                EntityKind::Tag => {
+                    let tags = self.client.tags().await.map_err(zen_err)?;
+                    stamp_entities(
+                        tags.into_iter()
+                            .map(|tag| (tag.id.to_string(), tag.changed, false)),
+                    )
+                }
+                EntityKind::Merchant => {
+                    let merchants = self.client.merchants().await.map_err(zen_err)?;
+                    stamp_entities(
+                        merchants
+                            .into_iter()
+                            .map(|merchant| (merchant.id.to_string(), merchant.changed, false)),
+                    )
+                }
+                EntityKind::Budget => {
+                    let budgets = self.client.budgets().await.map_err(zen_err)?;
+                    stamp_entities(budgets.into_iter().map(|budget| {
+                        let tag = budget.tag.as_ref().map(ToString::to_string);
+                        let key = format!("{}/{}", tag.unwrap_or_default(), budget.date);
+                        (key, budget.changed, false)
+                    }))
+                }
+                EntityKind::Reminder => {
+                    let reminders = self.client.reminders().await.map_err(zen_err)?;
+                    stamp_entities(
+                        reminders
+                            .into_iter()
+                            .map(|reminder| (reminder.id.to_string(), reminder.changed, false)),
+                    )
+                }
+                EntityKind::Instrument => {
+                    let instruments = self.client.instruments().await.map_err(zen_err)?;
+                    stamp_entities(instruments.into_iter().map(|instrument| {
+                        (
+                            instrument.id.into_inner().to_string(),
+                            instrument.changed,
+                            false,
+                        )
+                    }))
+                }
+                EntityKind::Company => {
+                    let companies = self.client.companies().await.map_err(zen_err)?;
+                    stamp_entities(companies.into_iter().map(|company| {
+                        (company.id.into_inner().to_string(), company.changed, false)
+                    }))
+                }
+                EntityKind::User => {
+                    let users = self.client.users().await.map_err(zen_err)?;
+                    stamp_entities(
+                        users
+                            .into_iter()
+                            .map(|user| (user.id.into_inner().to_string(), user.changed, false)),
+                    )
+                }
+            };
+            let _previous = snapshot.insert(kind, stamps);
+        }
+        Ok(snapshot)
+    }
+
+    /// Collects everything stored locally for a backup.
+    async fn backup_entities(&self) -> Result<BackupEntities, McpError> {
+        Ok(BackupEntities {
+            accounts: self.client.accounts().await.map_err(zen_err)?,
+            tags: self.client.tags().await.map_err(zen_err)?,
+            instruments: self.client.instruments().await.map_err(zen_err)?,
+            merchants: self.client.merchants().await.map_err(zen_err)?,
+            companies: self.client.companies().await.map_err(zen_err)?,
+            users: self.client.users().await.map_err(zen_err)?,
+            budgets: self.client.budgets().await.map_err(zen_err)?,
+            reminders: self.client.reminders().await.map_err(zen_err)?,
+            transactions: self.client.transactions().await.map_err(zen_err)?,
+            templates: self.templates.list().map_err(io_err)?,
+            envelopes: self.envelopes.list().map_err(io_err)?,
+        })
+    }
 
-    /// Finds an account by title.
-    #[tool(description = "Find an account by title (case-insensitive search)")]
-    async fn find_account(
+    /// Replaces local storage with backed-up entities, templates and envelopes.
+    ///
+    /// The sync timestamp is reset, so the next sync downloads everything
+    /// again and ZenMoney's current data replaces the restored copies.
+    async fn restore_local(
         &self,
-        params: Parameters<FindAccountParams>,
+        created_at: DateTime<Utc>,
+        entities: BackupEntities,
     ) -> Result<CallToolResult, McpError> {
-        let maps = self.lookup_maps().await?;
-        let account = self
-            .client
-            .find_account_by_title(&params.0.title)
+        let counts = entities.counts();
+        // A sync in between would mix server data into the restored copy.
+        self.sync_gate
+            .run_alone(async {
+                let storage = self.client.storage();
+                storage.clear().await.map_err(storage_err)?;
+                self.store_entities(entities).await?;
+                storage
+                    .set_server_timestamp(DateTime::<Utc>::UNIX_EPOCH)
+                    .await
+                    .map_err(storage_err)?;
+                self.restored.store(true, Ordering::Release);
+                Ok::<(), McpError>(())
+            })
+            .await?;
+        tracing::info!(created_at = %created_at, "backup restored into local storage");
+        self.refresh_resources().await;
+        json_result(&RestoreResponse {
+            created_at: created_at.to_rfc3339(),
+            counts,
+            message: "Backup restored into local storage. ZenMoney itself was not changed; \
+                      the next sync downloads everything again and replaces the restored \
+                      data with the server's, and writes sync first"
+                .to_owned(),
+        })
+    }
+
+    /// Syncs before a write if local data was restored from a backup since
+    /// the last sync.
+    ///
+    /// Writes push whole entities, so restored copies older than ZenMoney's
+    /// would otherwise overwrite newer server data.
+    async fn sync_after_restore(&self, tool: &str) -> Result<(), McpError> {
+        if tool == "restore_data"
+            || LOCAL_WRITE_TOOLS.contains(&tool)
+            || !self.restored.load(Ordering::Acquire)
+        {
+            return Ok(());
+        }
+        tracing::info!(
+            tool,
+            "local data was restored from a backup, syncing before the write"
+        );
+        self.run_sync().await.map_err(|err| {
+            McpError::internal_error(
+                format!(
+                    "local data was restored from a backup and could not be synced before \
+                     {tool}: {}",
+                    err.message
+                ),
+                None,
+            )
+        })?;
+        self.refresh_after_sync().await;
+        Ok(())
+    }
+
+    /// Writes entities, templates and envelopes into local storage.
+    async fn store_entities(&self, entities: BackupEntities) -> Result<(), McpError> {
+        let storage = self.client.storage();
+        storage
+            .upsert_instruments(entities.instruments)
             .await
-            .map_err(zen_err)?;
-        if let Some(acc) = account.as_ref() {
-            let result = AccountResponse::from_account(acc, &maps);
-            json_result(&result)
-        } else {
-            Ok(CallToolResult::success(vec![Content::text(format!(
-                "No account found with title '{}'",
-                params.0.title
-            ))]))
+            .map_err(storage_err)?;
+        storage
+            .upsert_companies(entities.companies)
+            .await
+            .map_err(storage_err)?;
+        storage
+            .upsert_users(entities.users)
+            .await
+            .map_err(storage_err)?;
+        storage
+            .upsert_accounts(entities.accounts)
+            .await
+            .map_err(storage_err)?;
+        storage
+            .upsert_tags(entities.tags)
+            .await
+            .map_err(storage_err)?;
+        storage
+            .upsert_merchants(entities.merchants)
+            .await
+            .map_err(storage_err)?;
+        storage
+            .upsert_budgets(entities.budgets)
+            .await
+            .map_err(storage_err)?;
+        storage
+            .upsert_reminders(entities.reminders)
+            .await
+            .map_err(storage_err)?;
+        storage
+            .upsert_transactions(entities.transactions)
+            .await
+            .map_err(storage_err)?;
+        for template in entities.templates {
+            let _replaced = self.templates.save(template).map_err(io_err)?;
         }
+        self.envelopes.save(entities.envelopes).map_err(io_err)
     }
 
-    /// Finds a tag by title.
-    #[tool(description = "Find a category tag by title (case-insensitive search)")]
-    async fn find_tag(
-        &self,
-        params: Parameters<FindTagParams>,
-    ) -> Result<CallToolResult, McpError> {
+    /// Stages changes that return transactions to their state in `backup`.
+    ///
+    /// Transactions changed or removed since the backup are re-saved as they
+    /// were; transactions created since are deleted.
+    async fn stage_revert(&self, backup: Vec<Transaction>) -> Result<CallToolResult, McpError> {
         let maps = self.lookup_maps().await?;
-        let tag = self
-            .client
-            .find_tag_by_title(&params.0.title)
-            .await
-            .map_err(zen_err)?;
-        if let Some(found_tag) = tag.as_ref() {
-            let result = TagResponse::from_tag(found_tag, &maps);
-            json_result(&result)
-        } else {
-            Ok(CallToolResult::success(vec![Content::text(format!(
-                "No tag found with title '{}'",
-                params.0.title
-            ))]))
+        let current = self.client.transactions().await.map_err(zen_err)?;
+        let current_by_id: HashMap<&str, &Transaction> =
+            current.iter().map(|tx| (tx.id.as_inner(), tx)).collect();
+        let backup_ids: HashSet<&str> = backup.iter().map(|tx| tx.id.as_inner()).collect();
+        let created_since: Vec<&Transaction> = current
+            .iter()
+            .filter(|tx| !tx.deleted && !backup_ids.contains(tx.id.as_inner()))
+            .collect();
+        let now = Utc::now();
+        let to_push: Vec<Transaction> = backup
+            .iter()
+            .filter(|tx| {
+                current_by_id
+                    .get(tx.id.as_inner())
+                    .is_none_or(|found| found.changed != tx.changed)
+            })
+            .cloned()
+            .map(|mut tx| {
+                tx.changed = now;
+                tx
+            })
+            .collect();
+        if to_push.is_empty() && created_since.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(
+                "Transactions already match the backup; nothing to revert",
+            )]));
         }
+
+        let preview: Vec<TransactionResponse> = to_push
+            .iter()
+            .map(|tx| TransactionResponse::from_transaction(tx, &maps))
+            .collect();
+        let deleted_preview: Vec<TransactionResponse> = created_since
+            .iter()
+            .map(|tx| TransactionResponse::from_transaction(tx, &maps))
+            .collect();
+        let to_delete: Vec<TransactionId> = created_since.iter().map(|tx| tx.id.clone()).collect();
+        let updated = to_push.len();
+        let deleted = to_delete.len();
+        let preparation_id = self.store_preparation(PreparedBulk {
+            to_push,
+            to_delete,
+            created_count: 0,
+            updated_count: updated,
+            budgets: Vec::new(),
+        })?;
+        json_result(&PrepareResponse {
+            preparation_id,
+            created: 0,
+            updated,
+            deleted,
+            transactions: preview,
+            deleted_transactions: deleted_preview,
+        })
     }
 
-    /// Suggests a category for a transaction.
-    #[tool(
-        description = "Suggest a category tag for a transaction based on payee name and/or comment. Note: the ZenMoney API does not provide confidence scores for suggestions"
-    )]
-    async fn suggest_category(
+    /// Builds lookup maps from current storage for enriching responses.
+    async fn lookup_maps(&self) -> Result<LookupMaps, McpError> {
+        let accounts = self.client.accounts().await.map_err(zen_err)?;
+        let tags = self.client.tags().await.map_err(zen_err)?;
+        let instruments = self.client.instruments().await.map_err(zen_err)?;
+        let merchants = self.client.merchants().await.map_err(zen_err)?;
+        let companies = self.client.companies().await.map_err(zen_err)?;
+        let users = self.client.users().await.map_err(zen_err)?;
+        let reminders = self.client.reminders().await.map_err(zen_err)?;
+        let markers = self.client.reminder_markers().await.map_err(zen_err)?;
+        Ok(build_lookup_maps(&accounts, &tags, &instruments)
+            .with_merchants(&merchants)
+            .with_companies(&companies)
+            .with_users(&users)
+            .with_reminders(&reminders, &markers)
+            .with_ids(self.include_ids)
+            .with_date_window(self.date_window)
+            .with_default_account(
+                self.default_account
+                    .account()
+                    .map_err(io_err)?
+                    .map(|(id, _saved)| id),
+            ))
+    }
+
+    /// Infers the category of a quick expense from `text`: the tag with that
+    /// title, or else ZenMoney's suggestion for `text` as a payee, which also
+    /// gives the payee and merchant. Notes what was inferred in `inferred`.
+    async fn guess_expense(
         &self,
-        params: Parameters<SuggestCategoryParams>,
-    ) -> Result<CallToolResult, McpError> {
-        let maps = self.lookup_maps().await?;
+        text: &str,
+        maps: &LookupMaps,
+        inferred: &mut Vec<String>,
+    ) -> Result<ExpenseGuess, McpError> {
+        let tags = self.client.tags().await.map_err(zen_err)?;
+        let wanted = text.to_lowercase();
+        if let Some(tag) = tags
+            .iter()
+            .find(|tag| !tag.archive.unwrap_or(false) && tag.title.to_lowercase() == wanted)
+        {
+            inferred.push(format!("tags: {} (category title)", tag.title));
+            return Ok(ExpenseGuess {
+                tag_ids: Some(vec![tag.id.to_string()]),
+                ..ExpenseGuess::default()
+            });
+        }
         let request = SuggestRequest {
-            payee: params.0.payee,
-            comment: params.0.comment,
+            payee: Some(text.to_owned()),
+            comment: None,
         };
-        let response = self.client.suggest(&request).await.map_err(zen_err)?;
-        let result = SuggestResponse::from_suggest(&response, &maps);
-        json_result(&result)
+        self.throttle.pace().await;
+        let suggestion = match self.client.suggest(&request).await {
+            Ok(suggestion) => suggestion,
+            Err(err) => {
+                tracing::warn!(%err, "category suggestion failed, logging the expense untagged");
+                inferred.push("tags: none (suggestions unavailable)".to_owned());
+                return Ok(ExpenseGuess {
+                    payee: Some(text.to_owned()),
+                    ..ExpenseGuess::default()
+                });
+            }
+        };
+        let tag_ids: Vec<String> = suggestion
+            .tag
+            .as_deref()
+            .unwrap_or_default()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        let names: Vec<String> = tag_ids.iter().map(|id| maps.tag_name(id)).collect();
+        inferred.push(if names.is_empty() {
+            "tags: none suggested".to_owned()
+        } else {
+            format!("tags: {} (suggested)", names.join(", "))
+        });
+        let payee = suggestion.payee.clone().unwrap_or_else(|| text.to_owned());
+        if payee != text {
+            inferred.push(format!("payee: {payee} (suggested)"));
+        }
+        let merchant_id = suggestion.merchant.as_ref().map(ToString::to_string);
+        if let Some(merchant) = merchant_id.as_deref() {
+            inferred.push(format!(
+                "merchant: {} (suggested)",
+                maps.merchant_name(merchant)
+            ));
+        }
+        Ok(ExpenseGuess {
+            tag_ids: (!tag_ids.is_empty()).then_some(tag_ids),
+            payee: Some(payee),
+            merchant_id,
+        })
     }
 
-    /// Gets a specific instrument by ID.
-    #[tool(description = "Get a specific currency instrument by its numeric ID")]
-    async fn get_instrument(
+    /// Returns the instrument ID of the base currency: the configured one, or
+    /// the currency of the account owner.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configured currency matches no instrument.
+    async fn base_currency(&self) -> Result<Option<i32>, McpError> {
+        let Some(code) = self.base_currency.as_deref() else {
+            let users = self.client.users().await.map_err(zen_err)?;
+            return Ok(users
+                .iter()
+                .find(|user| user.parent.is_none())
+                .map(|user| user.currency.into_inner()));
+        };
+        let instruments = self.client.instruments().await.map_err(zen_err)?;
+        instruments
+            .iter()
+            .find(|instrument| {
+                instrument.short_title.eq_ignore_ascii_case(code)
+                    || instrument.id.into_inner().to_string() == code
+            })
+            .map(|instrument| Some(instrument.id.into_inner()))
+            .ok_or_else(|| {
+                McpError::internal_error(
+                    format!("{BASE_CURRENCY_ENV} '{code}' matches no currency instrument"),
+                    None,
+                )
+            })
+    }
+
+    /// Returns the currency to sum totals in: `convert_to` once validated,
+    /// otherwise the base currency.
+    async fn totals_currency(
         &self,
-        params: Parameters<GetInstrumentParams>,
-    ) -> Result<CallToolResult, McpError> {
-        let instrument = self
-            .client
-            .instrument(InstrumentId::new(params.0.id))
-            .await
-            .map_err(zen_err)?;
-        if let Some(instr) = instrument.as_ref() {
-            let result = InstrumentResponse::from_instrument(instr);
-            json_result(&result)
-        } else {
-            Ok(CallToolResult::success(vec![Content::text(format!(
-                "No instrument found with ID {}",
-                params.0.id
-            ))]))
+        maps: &LookupMaps,
+        convert_to: Option<i32>,
+    ) -> Result<Option<i32>, McpError> {
+        validate_convert_to(maps, convert_to)?;
+        match convert_to {
+            Some(id) => Ok(Some(id)),
+            None => self.base_currency().await,
         }
     }
 
-    // ── Write tools ─────────────────────────────────────────────────
+    /// Loads (kind, ID, title) of every entity of `kinds` for fuzzy search.
+    async fn search_candidates(
+        &self,
+        kinds: &[SearchKind],
+    ) -> Result<Vec<(SearchKind, String, String)>, McpError> {
+        let mut candidates = Vec::new();
+        if kinds.contains(&SearchKind::Account) {
+            let accounts = self.client.accounts().await.map_err(zen_err)?;
+            candidates.extend(
+                accounts
+                    .into_iter()
+                    .map(|account| (SearchKind::Account, account.id.to_string(), account.title)),
+            );
+        }
+        if kinds.contains(&SearchKind::Tag) {
+            let tags = self.client.tags().await.map_err(zen_err)?;
+            candidates.extend(
+                tags.into_iter()
+                    .map(|tag| (SearchKind::Tag, tag.id.to_string(), tag.title)),
+            );
+        }
+        if kinds.contains(&SearchKind::Merchant) {
+            let merchants = self.client.merchants().await.map_err(zen_err)?;
+            candidates.extend(merchants.into_iter().map(|merchant| {
+                (
+                    SearchKind::Merchant,
+                    merchant.id.to_string(),
+                    merchant.title,
+                )
+            }));
+        }
+        Ok(candidates)
+    }
 
-    /// Creates a new transaction with simplified parameters.
-    #[tool(
-        description = "Create a new financial transaction. Specify transaction_type (expense/income/transfer), date, account_id, and amount. For transfers, also provide to_account_id. Currency instruments are auto-resolved from the account unless overridden with instrument_id/to_instrument_id. Optionally specify tag_ids, payee, and comment"
-    )]
-    async fn create_transaction(
+    /// Loads non-deleted transactions matching the `list_transactions` filters.
+    async fn filtered_transactions(
         &self,
-        params: Parameters<CreateTransactionParams>,
-    ) -> Result<CallToolResult, McpError> {
-        let maps = self.lookup_maps().await?;
-        let new_tx = build_transaction(params.0, &maps)?;
-        let preview = TransactionResponse::from_transaction(&new_tx, &maps);
-        let _response = self
-            .client
-            .push_transactions(vec![new_tx])
-            .await
-            .map_err(zen_err)?;
+        params: &ListTransactionsParams,
+        maps: &LookupMaps,
+    ) -> Result<Vec<Transaction>, McpError> {
+        let filter = build_transaction_filter(params)?;
+        let changed_since = params
+            .changed_since
+            .as_deref()
+            .map(parse_timestamp)
+            .transpose()?;
+        // Planned transactions are kept locally until converted, so they
+        // are never among the synced ones.
+        let mut transactions = if params.planned == Some(true) {
+            let mut planned = self.planned.list().map_err(io_err)?;
+            planned.retain(|tx| matches_filter(&filter, tx));
+            planned
+        } else {
+            self.client
+                .filter_transactions(&filter)
+                .await
+                .map_err(zen_err)?
+        };
+        apply_local_filters(&mut transactions, params, maps);
+        if let Some(since) = changed_since {
+            transactions.retain(|tx| tx.changed > since);
+        }
+        Ok(transactions)
+    }
 
-        json_result(&vec![preview])
+    /// Stores prepared changes for `execute_bulk_operations` and returns their ID.
+    fn store_preparation(&self, prepared: PreparedBulk) -> Result<String, McpError> {
+        let preparation_id = uuid::Uuid::new_v4().to_string();
+        let _prev = self
+            .preparations
+            .lock()
+            .map_err(|err| McpError::internal_error(format!("lock poisoned: {err}"), None))?
+            .insert(preparation_id.clone(), prepared);
+        Ok(preparation_id)
     }
 
-    /// Creates a new category tag.
-    #[tool(
-        description = "Create a new category tag. If a tag with the same title already exists (case-insensitive), returns the existing tag instead of creating a duplicate"
-    )]
-    async fn create_tag(
+    /// Stages updated transactions and returns the prepare preview.
+    fn stage_updates(
         &self,
-        params: Parameters<CreateTagParams>,
-    ) -> Result<CallToolResult, McpError> {
-        self.create_tag_internal(params.0).await
+        to_push: Vec<Transaction>,
+        maps: &LookupMaps,
+    ) -> Result<PrepareResponse, McpError> {
+        let preview: Vec<TransactionResponse> = to_push
+            .iter()
+            .map(|tx| TransactionResponse::from_transaction(tx, maps))
+            .collect();
+        let updated_count = to_push.len();
+        let preparation_id = self.store_preparation(PreparedBulk {
+            to_push,
+            to_delete: Vec::new(),
+            created_count: 0,
+            updated_count,
+            budgets: Vec::new(),
+        })?;
+        Ok(PrepareResponse {
+            preparation_id,
+            created: 0,
+            updated: updated_count,
+            deleted: 0,
+            transactions: preview,
+            deleted_transactions: Vec::new(),
+        })
     }
 
-    /// Alias for creating a category tag.
-    #[tool(
-        description = "Alias for create_tag: create a category tag with the same behavior and idempotency guarantees"
-    )]
-    async fn create_category(
+    /// Stages merging `(keep, remove)` pairs: each kept transaction is
+    /// updated with the fields it lacked and the other one is deleted.
+    fn stage_merges(
         &self,
-        params: Parameters<CreateTagParams>,
-    ) -> Result<CallToolResult, McpError> {
-        self.create_tag_internal(params.0).await
+        pairs: Vec<(Transaction, Transaction)>,
+        maps: &LookupMaps,
+    ) -> Result<PrepareResponse, McpError> {
+        let now = Utc::now();
+        let mut to_push = Vec::new();
+        let mut to_delete = Vec::new();
+        let mut deleted_transactions = Vec::new();
+        for (mut kept, removed) in pairs {
+            if !fill_missing(&mut kept, &removed).is_empty() {
+                kept.changed = now;
+                to_push.push(kept);
+            }
+            deleted_transactions.push(TransactionResponse::from_transaction(&removed, maps));
+            to_delete.push(removed.id);
+        }
+        let preview: Vec<TransactionResponse> = to_push
+            .iter()
+            .map(|tx| TransactionResponse::from_transaction(tx, maps))
+            .collect();
+        let updated_count = to_push.len();
+        let deleted_count = to_delete.len();
+        let preparation_id = self.store_preparation(PreparedBulk {
+            to_push,
+            to_delete,
+            created_count: 0,
+            updated_count,
+            budgets: Vec::new(),
+        })?;
+        Ok(PrepareResponse {
+            preparation_id,
+            created: 0,
+            updated: updated_count,
+            deleted: deleted_count,
+            transactions: preview,
+            deleted_transactions,
+        })
     }
 
-    /// Updates an existing transaction.
-    #[tool(
-        description = "Update an existing transaction by ID. All fields except id are optional — only provided fields are changed. Use empty string for payee/comment to clear them. Amount is applied to the correct side (income/outcome) based on the transaction type"
-    )]
-    async fn update_transaction(
+    /// Pushes a new reminder and returns its preview.
+    async fn push_reminder(
         &self,
-        params: Parameters<UpdateTransactionParams>,
-    ) -> Result<CallToolResult, McpError> {
-        let maps = self.lookup_maps().await?;
-        let all_transactions = self.client.transactions().await.map_err(zen_err)?;
-        let mut updated = all_transactions
-            .into_iter()
-            .find(|found_tx| found_tx.id.as_inner() == params.0.id)
-            .ok_or_else(|| {
-                McpError::invalid_params(format!("transaction '{}' not found", params.0.id), None)
-            })?;
-
-        apply_update(&mut updated, params.0, &maps)?;
-
-        let preview = TransactionResponse::from_transaction(&updated, &maps);
+        reminder: Reminder,
+        maps: &LookupMaps,
+    ) -> Result<ReminderResponse, McpError> {
+        let preview = ReminderResponse::from_reminder(&reminder, maps);
+        self.throttle.pace().await;
         let _response = self
             .client
-            .push_transactions(vec![updated])
+            .push_reminders(vec![reminder])
             .await
             .map_err(zen_err)?;
-
-        json_result(&vec![preview])
+        self.refresh_resources().await;
+        Ok(preview)
     }
 
-    /// Deletes a transaction by ID, returning details of the deleted transaction.
-    #[tool(
-        description = "Delete a transaction by its ID. Returns details of the deleted transaction for confirmation"
-    )]
-    async fn delete_transaction(
+    /// Stages new transactions and returns the prepare preview.
+    fn stage_creations(
         &self,
-        params: Parameters<DeleteTransactionParams>,
-    ) -> Result<CallToolResult, McpError> {
-        let maps = self.lookup_maps().await?;
-
-        // Fetch the transaction details before deleting.
-        let all_transactions = self.client.transactions().await.map_err(zen_err)?;
-        let existing = all_transactions
+        to_push: Vec<Transaction>,
+        maps: &LookupMaps,
+    ) -> Result<PrepareResponse, McpError> {
+        let preview: Vec<TransactionResponse> = to_push
             .iter()
-            .find(|found_tx| found_tx.id.as_inner() == params.0.id);
+            .map(|tx| TransactionResponse::from_transaction(tx, maps))
+            .collect();
+        let created_count = to_push.len();
+        let preparation_id = self.store_preparation(PreparedBulk {
+            to_push,
+            to_delete: Vec::new(),
+            created_count,
+            updated_count: 0,
+            budgets: Vec::new(),
+        })?;
+        Ok(PrepareResponse {
+            preparation_id,
+            created: created_count,
+            updated: 0,
+            deleted: 0,
+            transactions: preview,
+            deleted_transactions: Vec::new(),
+        })
+    }
 
-        let delete_id = TransactionId::new(params.0.id.clone());
-        let _response = self
-            .client
-            .delete_transactions(&[delete_id])
-            .await
-            .map_err(zen_err)?;
+    /// Loads non-deleted transactions by ID, failing on the first unknown one.
+    async fn transactions_by_ids(&self, ids: &[String]) -> Result<Vec<Transaction>, McpError> {
+        let all_transactions = self.client.transactions().await.map_err(zen_err)?;
+        ids.iter()
+            .map(|id| {
+                all_transactions
+                    .iter()
+                    .find(|tx| tx.id.as_inner() == id && !tx.deleted)
+                    .cloned()
+                    .ok_or_else(|| not_found("transaction", id))
+            })
+            .collect()
+    }
 
-        if let Some(found_tx) = existing {
-            let tx_response = TransactionResponse::from_transaction(found_tx, &maps);
-            let result = DeletedTransactionResponse::new(
-                format!("Transaction '{}' deleted successfully", params.0.id),
-                tx_response,
-            );
-            json_result(&result)
-        } else {
-            Ok(CallToolResult::success(vec![Content::text(format!(
-                "Transaction '{}' deleted successfully (details not available locally)",
-                params.0.id
-            ))]))
+    /// Loads transactions by explicit IDs or, if none are given, by filters.
+    async fn transactions_by_ids_or_selection(
+        &self,
+        ids: Option<&[String]>,
+        selection: &TransactionSelection,
+        maps: &LookupMaps,
+    ) -> Result<Vec<Transaction>, McpError> {
+        match ids {
+            Some(ids) => {
+                if !selection.is_empty() {
+                    return Err(McpError::invalid_params(
+                        "pass either transaction_ids or filters, not both",
+                        None,
+                    ));
+                }
+                self.transactions_by_ids(ids).await
+            }
+            None => self.selected_transactions(selection, maps).await,
         }
     }
 
-    /// Validates and prepares bulk operations without executing them.
-    ///
-    /// Returns a preview with a `preparation_id` that can be passed to
-    /// `execute_bulk_operations` to commit the changes.
-    #[tool(
-        description = "Validate and preview multiple transaction operations (create, update, delete) without executing them. Returns an enriched preview of all changes and a preparation_id. Pass the preparation_id to execute_bulk_operations to commit the changes. IMPORTANT: limit to 10 operations per call to avoid transport timeouts; split larger batches into multiple prepare calls"
-    )]
-    async fn prepare_bulk_operations(
+    /// Loads the live transactions an export selects, oldest first.
+    async fn export_selection(
         &self,
-        params: Parameters<BulkOperationsParams>,
+        selection: &TransactionSelection,
+        maps: &LookupMaps,
+    ) -> Result<Vec<Transaction>, McpError> {
+        let mut transactions = self
+            .filtered_transactions(&selection.to_list_params(), maps)
+            .await?;
+        transactions.retain(|tx| !tx.deleted);
+        if transactions.is_empty() {
+            return Err(McpError::invalid_params(
+                "no transactions match the filters",
+                None,
+            ));
+        }
+        sort_by_date(&mut transactions, SortDirection::Asc);
+        Ok(transactions)
+    }
+
+    /// Sleeps until each run of `job` and writes its report, logging failures.
+    async fn run_scheduled_job(&self, job: ScheduledJob) {
+        tracing::info!(job = %job.name, "scheduled report job started");
+        while let Some(next) = job.schedule.next_after(Utc::now()) {
+            tracing::info!(job = %job.name, next_run = %next, "next scheduled report");
+            let wait = next
+                .signed_duration_since(Utc::now())
+                .to_std()
+                .unwrap_or_default();
+            tokio::time::sleep(wait).await;
+            let ran_at = Utc::now();
+            let written = match self.scheduled_report(&job).await {
+                Ok(report) => job
+                    .write_report(ran_at, &report)
+                    .map_err(|err| err.to_string()),
+                Err(err) => Err(err.message.into_owned()),
+            };
+            match written {
+                Ok(file) => tracing::info!(
+                    job = %job.name,
+                    file = %file.display(),
+                    "scheduled report written"
+                ),
+                Err(error) => tracing::warn!(job = %job.name, %error, "scheduled report failed"),
+            }
+        }
+        tracing::warn!(job = %job.name, "schedule never comes due again; job stopped");
+    }
+
+    /// Syncs, then produces the report of a scheduled job as text.
+    ///
+    /// A failed sync is logged and the report is built from local data.
+    async fn scheduled_report(&self, job: &ScheduledJob) -> Result<String, McpError> {
+        if self.offline_mode.is_none() {
+            match self.run_sync().await {
+                Ok(()) => self.refresh_after_sync().await,
+                Err(err) => tracing::warn!(
+                    job = %job.name,
+                    error = %err.message,
+                    "sync before scheduled report failed, using local data"
+                ),
+            }
+        }
+        let arguments = job.arguments.clone();
+        let invalid = |err: serde_json::Error| McpError::invalid_params(err.to_string(), None);
+        let result = match job.report {
+            ReportKind::Digest => {
+                self.digest(Parameters(
+                    serde_json::from_value(arguments).map_err(invalid)?,
+                ))
+                .await?
+            }
+            ReportKind::BudgetProgress => {
+                self.budget_progress(Parameters(
+                    serde_json::from_value(arguments).map_err(invalid)?,
+                ))
+                .await?
+            }
+            ReportKind::ExportCsv => {
+                return self
+                    .csv_report(serde_json::from_value(arguments).map_err(invalid)?)
+                    .await;
+            }
+        };
+        let text = result
+            .content
+            .first()
+            .and_then(|content| content.raw.as_text())
+            .map(|content| content.text.clone())
+            .unwrap_or_default();
+        if result.is_error == Some(true) {
+            return Err(McpError::invalid_params(text, None));
+        }
+        Ok(text)
+    }
+
+    /// Renders the transactions selected by a scheduled `export_csv` job.
+    async fn csv_report(&self, params: CsvReportParams) -> Result<String, McpError> {
+        let CsvReportParams {
+            mut selection,
+            days,
+        } = params;
+        if let Some(window) = days {
+            let today = Utc::now().date_naive();
+            let first = today.checked_sub_days(Days::new(u64::from(window.saturating_sub(1))));
+            selection.date_from = first.map(|date| date.to_string());
+            selection.date_to = Some(today.to_string());
+        }
+        let maps = self.lookup_maps().await?;
+        let mut transactions = self
+            .filtered_transactions(&selection.to_list_params(), &maps)
+            .await?;
+        transactions.retain(|tx| !tx.deleted);
+        sort_by_date(&mut transactions, SortDirection::Asc);
+        let currencies = self.currency_codes().await?;
+        Ok(render_csv(&transactions, &currencies, &maps))
+    }
+
+    /// Marks the unviewed `candidates` as viewed on ZenMoney, returning the
+    /// transactions that changed.
+    async fn push_viewed(
+        &self,
+        candidates: Vec<Transaction>,
+    ) -> Result<Vec<Transaction>, McpError> {
+        let now = Utc::now();
+        let to_push: Vec<Transaction> = candidates
+            .into_iter()
+            .filter(|tx| tx.viewed != Some(true))
+            .map(|mut tx| {
+                tx.viewed = Some(true);
+                tx.changed = now;
+                tx
+            })
+            .collect();
+        if !to_push.is_empty() {
+            self.throttle.pace().await;
+            let _response = self
+                .client
+                .push_transactions(to_push.clone())
+                .await
+                .map_err(zen_err)?;
+            self.refresh_resources().await;
+        }
+        Ok(to_push)
+    }
+
+    /// Maps instrument IDs to their currency codes.
+    async fn currency_codes(&self) -> Result<HashMap<i32, String>, McpError> {
+        Ok(self
+            .client
+            .instruments()
+            .await
+            .map_err(zen_err)?
+            .into_iter()
+            .map(|instrument| (instrument.id.into_inner(), instrument.short_title))
+            .collect())
+    }
+
+    /// Writes one QIF or OFX file per account touched by the selected transactions.
+    async fn export_transactions(
+        &self,
+        params: ExportParams,
+        format: ExportFormat,
     ) -> Result<CallToolResult, McpError> {
-        tracing::debug!("prepare_bulk_operations: start");
+        let maps = self.lookup_maps().await?;
+        let transactions = self.export_selection(&params.selection, &maps).await?;
+        let currencies = self.currency_codes().await?;
+        let mut accounts = self.client.accounts().await.map_err(zen_err)?;
+        if let Some(account_id) = params.selection.account_id.as_deref() {
+            accounts.retain(|account| account.id.as_inner() == account_id);
+        }
+        accounts.sort_by(|left, right| left.title.cmp(&right.title));
+        let directory = PathBuf::from(&params.directory);
+        let write_err = |err: std::io::Error| {
+            McpError::invalid_params(
+                format!("cannot write to '{}': {err}", params.directory),
+                None,
+            )
+        };
+        std::fs::create_dir_all(&directory).map_err(write_err)?;
+        let mut files = Vec::new();
+        for account in &accounts {
+            let id = account.id.as_inner();
+            let own: Vec<&Transaction> = transactions
+                .iter()
+                .filter(|tx| {
+                    tx.income_account.as_inner() == id || tx.outcome_account.as_inner() == id
+                })
+                .collect();
+            if own.is_empty() {
+                continue;
+            }
+            let contents = render_export(account, &own, format, &currencies, &maps);
+            let path = directory.join(export_file_name(account, format));
+            std::fs::write(&path, contents).map_err(write_err)?;
+            files.push(ExportedFile {
+                account: account.title.clone(),
+                account_id: maps.raw_id(id),
+                path: path.display().to_string(),
+                transactions: own.len(),
+            });
+        }
+        tracing::info!(
+            format = format.extension(),
+            files = files.len(),
+            "transactions exported"
+        );
+        json_result(&ExportResponse {
+            format: format.extension().to_owned(),
+            directory: directory.display().to_string(),
+            transactions: transactions.len(),
+            files,
+        })
+    }
 
-        if params.0.operations.len() > MAX_BULK_OPERATIONS {
+    /// Loads transactions matched by a bulk selection, enforcing safety limits.
+    async fn selected_transactions(
+        &self,
+        selection: &TransactionSelection,
+        maps: &LookupMaps,
+    ) -> Result<Vec<Transaction>, McpError> {
+        if selection.is_empty() {
+            return Err(McpError::invalid_params(
+                "at least one filter is required",
+                None,
+            ));
+        }
+        let transactions = self
+            .filtered_transactions(&selection.to_list_params(), maps)
+            .await?;
+        if transactions.is_empty() {
+            return Err(McpError::invalid_params(
+                "no transactions match the filters",
+                None,
+            ));
+        }
+        if transactions.len() > MAX_FILTER_BULK {
             return Err(McpError::invalid_params(
                 format!(
-                    "too many operations ({}); limit is {MAX_BULK_OPERATIONS} per call — split into smaller batches",
-                    params.0.operations.len()
+                    "{} transactions match; limit is {MAX_FILTER_BULK} per call — narrow the filters",
+                    transactions.len()
                 ),
                 None,
             ));
         }
+        Ok(transactions)
+    }
 
-        let maps = self.lookup_maps().await?;
-        tracing::debug!("prepare_bulk_operations: lookup_maps done");
+    /// Asks the suggest API for tag names for a payee.
+    ///
+    /// Suggestions are best-effort: failures are logged and yield no tags.
+    async fn suggested_tag_names(&self, payee: &str, maps: &LookupMaps) -> Vec<String> {
+        let request = SuggestRequest {
+            payee: Some(payee.to_owned()),
+            comment: None,
+        };
+        self.throttle.pace().await;
+        match self.client.suggest(&request).await {
+            Ok(response) => response
+                .tag
+                .as_deref()
+                .unwrap_or_default()
+                .iter()
+                .map(|tag_id| maps.tag_name(tag_id.as_inner()))
+                .collect(),
+            Err(err) => {
+                tracing::debug!(%err, payee, "suggest failed");
+                Vec::new()
+            }
+        }
+    }
 
-        let all_transactions = self.client.transactions().await.map_err(zen_err)?;
-        tracing::debug!(
-            count = all_transactions.len(),
-            "prepare_bulk_operations: loaded transactions"
-        );
+    /// Returns the first synced user ID, or `0` when local storage has no users.
+    async fn current_user_id(&self) -> Result<i64, McpError> {
+        let users = self.client.users().await.map_err(zen_err)?;
+        Ok(users.first().map_or(0, |user| user.id.into_inner()))
+    }
 
-        let (to_push, to_delete, created_count, updated_count) =
-            process_bulk_operations(params.0.operations, &all_transactions, &maps)?;
-        tracing::debug!(
-            created_count,
-            updated_count,
-            deleted = to_delete.len(),
-            "prepare_bulk_operations: processed operations"
-        );
+    /// Shared implementation for `create_tag` and `create_category`.
+    async fn create_tag_internal(
+        &self,
+        params: CreateTagParams,
+    ) -> Result<CallToolResult, McpError> {
+        let normalized_title = normalize_title(&params.title)?;
+        let tags = self.client.tags().await.map_err(zen_err)?;
 
-        let preview: Vec<TransactionResponse> = to_push
-            .iter()
-            .map(|tx| TransactionResponse::from_transaction(tx, &maps))
-            .collect();
-        let deleted_preview: Vec<TransactionResponse> = to_delete
-            .iter()
-            .filter_map(|del_id| {
-                all_transactions
-                    .iter()
-                    .find(|tx| tx.id.as_inner() == del_id.as_inner())
-            })
-            .map(|tx| TransactionResponse::from_transaction(tx, &maps))
-            .collect();
+        if let Some(existing_tag) = find_tag_by_title_case_insensitive(&tags, &normalized_title) {
+            let maps = self.lookup_maps().await?;
+            let result = TagResponse::from_tag(existing_tag, &maps);
+            return json_result(&result);
+        }
 
-        let preparation_id = uuid::Uuid::new_v4().to_string();
-        let result = PrepareResponse {
-            preparation_id: preparation_id.clone(),
-            created: created_count,
-            updated: updated_count,
-            deleted: to_delete.len(),
-            transactions: preview,
-            deleted_transactions: deleted_preview,
-        };
+        validate_parent_tag_exists(&tags, params.parent_tag_id.as_deref())?;
 
-        let prepared = PreparedBulk {
-            to_push,
-            to_delete,
-            created_count,
-            updated_count,
-        };
+        let user_id = self.current_user_id().await?;
+        let new_tag = build_tag(params, user_id, normalized_title);
+        let maps = self.lookup_maps().await?;
+        let preview = TagResponse::from_tag(&new_tag, &maps);
 
-        let _prev = self
-            .preparations
-            .lock()
-            .map_err(|err| McpError::internal_error(format!("lock poisoned: {err}"), None))?
-            .insert(preparation_id, prepared);
+        self.throttle.pace().await;
+        let _response = self
+            .client
+            .push_tags(vec![new_tag])
+            .await
+            .map_err(zen_err)?;
 
-        tracing::debug!("prepare_bulk_operations: done");
-        json_result(&result)
+        json_result(&preview)
     }
 
-    /// Executes a previously prepared bulk operation.
-    ///
-    /// Takes the `preparation_id` from `prepare_bulk_operations` and commits
-    /// the changes to ZenMoney.
+    // ── Sync tools ──────────────────────────────────────────────────
+
+    /// Performs an incremental sync with the ZenMoney server.
     #[tool(
-        description = "Execute a previously prepared bulk operation by its preparation_id (obtained from prepare_bulk_operations). Commits the validated changes to ZenMoney and returns a summary of affected transactions"
+        description = "Perform an incremental sync with the ZenMoney server, fetching only changes since the last sync. Reports how many entities of each type were added, updated or deleted (total_changed is 0 when nothing new arrived)",
+        output_schema = cached_schema_for_type::<SyncChangesResponse>()
     )]
-    async fn execute_bulk_operations(
+    async fn sync(&self) -> Result<CallToolResult, McpError> {
+        let before = self
+            .entity_snapshot(&BTreeSet::from(EntityKind::ALL))
+            .await?;
+        self.run_sync().await?;
+        tracing::info!("incremental sync completed");
+        self.refresh_after_sync().await;
+        json_result(&self.record_sync(&before, false).await?)
+    }
+
+    /// Performs an incremental sync and reports changes of selected entity types.
+    #[tool(
+        description = "Perform an incremental sync and report how many entities of the selected types (e.g. [\"transaction\", \"account\"]) were added, updated or deleted. Use it when only fresh data of a few types matters. ZenMoney's sync API cannot filter by type, so other types are still updated locally, but the report covers only the selected ones",
+        output_schema = cached_schema_for_type::<SyncEntitiesResponse>()
+    )]
+    async fn sync_entities(
         &self,
-        params: Parameters<ExecuteBulkParams>,
+        params: Parameters<SyncEntitiesParams>,
     ) -> Result<CallToolResult, McpError> {
-        let maps = self.lookup_maps().await?;
+        let kinds: BTreeSet<EntityKind> = params.0.entities.into_iter().collect();
+        if kinds.is_empty() {
+            return Err(McpError::invalid_params(
+                "entities must name at least one entity type",
+                None,
+            ));
+        }
+        let before = self.entity_snapshot(&kinds).await?;
+        self.run_sync().await?;
+        let after = self.entity_snapshot(&kinds).await?;
+        tracing::info!("incremental sync of selected entity types completed");
+        self.refresh_after_sync().await;
+        let changes = count_changes(&before, &after);
+        let total_changed = changes.values().map(ChangeCounts::total).sum();
+        json_result(&SyncEntitiesResponse {
+            changes,
+            total_changed,
+        })
+    }
 
-        let prepared = self
-            .preparations
+    /// Performs a full sync, clearing local data and re-downloading everything.
+    #[tool(
+        description = "Perform a full sync, clearing all local data and re-downloading everything from the ZenMoney server. Reports how many entities of each type were added, updated or deleted compared with the data stored before",
+        output_schema = cached_schema_for_type::<SyncChangesResponse>()
+    )]
+    async fn full_sync(
+        &self,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let progress = Progress::from_context(&context);
+        let before = self
+            .entity_snapshot(&BTreeSet::from(EntityKind::ALL))
+            .await?;
+        // Always run a fresh download: an incremental sync in flight cannot
+        // stand in for it.
+        self.sync_gate
+            .run_exclusive(async {
+                self.throttle.pace().await;
+                // The download itself is not interrupted: stopping halfway
+                // would leave the local data cleared.
+                let _response = progress
+                    .heartbeat(self.client.full_sync(), "Full sync in progress")
+                    .await
+                    .map_err(zen_err)?;
+                if progress.is_cancelled() {
+                    tracing::warn!(
+                        "full sync cancelled by the client; queued writes were not sent"
+                    );
+                } else {
+                    self.flush_pending().await;
+                }
+                Ok::<(), McpError>(())
+            })
+            .await?;
+        self.restored.store(false, Ordering::Release);
+        self.sync_clock.mark();
+        tracing::info!("full sync completed");
+        self.refresh_after_sync().await;
+        json_result(&self.record_sync(&before, true).await?)
+    }
+
+    /// Returns the changes brought in by the last sync.
+    #[tool(
+        description = "Show what the last sync or full_sync call brought in: added, updated and deleted counts per entity type, and when it ran. Cheaper than syncing again when the assistant only needs to recall whether anything new arrived"
+    )]
+    async fn last_sync_changes(&self) -> Result<CallToolResult, McpError> {
+        let last = self
+            .last_sync
             .lock()
             .map_err(|err| McpError::internal_error(format!("lock poisoned: {err}"), None))?
-            .remove(&params.0.preparation_id)
-            .ok_or_else(|| {
-                McpError::invalid_params(
-                    format!(
-                        "preparation '{}' not found or already executed",
-                        params.0.preparation_id
-                    ),
-                    None,
-                )
-            })?;
+            .clone();
+        match last {
+            Some(summary) => json_result(&summary),
+            None => Ok(CallToolResult::success(vec![Content::text(
+                "No sync has run since the server started; call sync to fetch new data",
+            )])),
+        }
+    }
 
-        // Build previews from local data before consuming prepared transactions.
-        let push_preview: Vec<TransactionResponse> = prepared
-            .to_push
-            .iter()
-            .map(|tx| TransactionResponse::from_transaction(tx, &maps))
+    /// Reports the server build, enabled features and configured limits.
+    #[tool(
+        description = "Show which build of the server the client is talking to: crate version, git commit, compiled transports, enabled features, storage backend and data directory, tool count, base_currency (the currency converted totals default to: ZENMONEY_BASE_CURRENCY, or the account owner's currency) and configured limits. Use it to debug version or configuration mismatches across machines",
+        output_schema = cached_schema_for_type::<ServerInfoResponse>()
+    )]
+    async fn server_info(&self) -> Result<CallToolResult, McpError> {
+        let storage = core::any::type_name::<S>()
+            .rsplit("::")
+            .next()
+            .unwrap_or_default()
+            .to_owned();
+        let mut features: Vec<String> = ["tools", "resources", "completions", "logging"]
+            .into_iter()
+            .map(str::to_owned)
             .collect();
-
-        if !prepared.to_push.is_empty() {
-            let _response = self
-                .client
-                .push_transactions(prepared.to_push)
-                .await
-                .map_err(zen_err)?;
+        if self.confirm_destructive {
+            features.push("confirm_destructive".to_owned());
         }
-
-        // Look up deleted transactions before deleting.
-        let mut deleted_preview: Vec<TransactionResponse> = Vec::new();
-        let deleted_count = prepared.to_delete.len();
-        if !prepared.to_delete.is_empty() {
-            let all_transactions = self.client.transactions().await.map_err(zen_err)?;
-            deleted_preview = prepared
-                .to_delete
-                .iter()
-                .filter_map(|del_id| {
-                    all_transactions
-                        .iter()
-                        .find(|tx| tx.id.as_inner() == del_id.as_inner())
-                })
-                .map(|tx| TransactionResponse::from_transaction(tx, &maps))
-                .collect();
-
-            let _response = self
+        if self.max_data_age.is_some() {
+            features.push("auto_sync".to_owned());
+        }
+        if self.compact_output {
+            features.push("compact_output".to_owned());
+        }
+        if !self.throttle.interval().is_zero() {
+            features.push("request_pacing".to_owned());
+        }
+        if let Some(mode) = self.offline_mode {
+            features.push(mode.to_owned());
+        }
+        let base_currency = match self.base_currency().await? {
+            Some(id) => self
                 .client
-                .delete_transactions(&prepared.to_delete)
+                .instrument(InstrumentId::new(id))
                 .await
-                .map_err(zen_err)?;
-        }
+                .map_err(zen_err)?
+                .map(|instrument| instrument.short_title),
+            None => None,
+        };
+        json_result(&ServerInfoResponse {
+            name: env!("CARGO_PKG_NAME").to_owned(),
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+            git_hash: option_env!("ZENMONEY_MCP_GIT_HASH").map(str::to_owned),
+            transports: vec!["stdio".to_owned()],
+            features,
+            storage,
+            data_dir: self.data_dir.as_ref().map(|dir| dir.display().to_string()),
+            tool_count: self.tool_router.list_all().len(),
+            base_currency,
+            limits: ServerLimits {
+                max_data_age_minutes: self.max_data_age.map(|age| age.as_secs().div_euclid(60)),
+                min_request_interval_ms: u64::try_from(self.throttle.interval().as_millis())
+                    .unwrap_or(u64::MAX),
+                default_transaction_limit: DEFAULT_TRANSACTION_LIMIT,
+                max_transaction_limit: MAX_TRANSACTION_LIMIT,
+                bulk_batch_size: BULK_EXECUTE_BATCH_SIZE,
+            },
+        })
+    }
 
-        let result = BulkOperationsResponse::new(
-            prepared.created_count,
-            prepared.updated_count,
-            deleted_count,
-            push_preview,
-            deleted_preview,
-        );
-        json_result(&result)
+    /// Reports per-tool call counts, errors and latencies.
+    #[tool(
+        description = "Show how the server has been used since it started: calls, errors and latency percentiles (p50/p90/p99/max in ms, over each tool's recent calls) per tool, most called first. Use it to see what an agent actually does and spot loops of repeated calls",
+        output_schema = cached_schema_for_type::<UsageStatsResponse>()
+    )]
+    async fn usage_stats(&self) -> Result<CallToolResult, McpError> {
+        json_result(&self.usage.report())
     }
-}
 
-#[cfg(test)]
-#[allow(
-    clippy::expect_used,
-    clippy::shadow_reuse,
-    clippy::missing_docs_in_private_items,
-    reason = "test code uses expect and shadow reuse for readability"
-)]
-mod tests {
-    use super::*;
-    use chrono::DateTime;
-
-    fn test_timestamp() -> DateTime<Utc> {
-        DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp for test")
+    /// Lists writes queued while ZenMoney was unreachable, optionally cancelling one.
+    #[tool(
+        description = "List writes (created, updated or restored transactions) queued locally with status pending because ZenMoney was unreachable. They are sent after the next successful sync, unless ZenMoney changed the same transaction meanwhile (see list_conflicts). Pass cancel with a pending change ID to drop it and undo its local effect",
+        output_schema = cached_schema_for_type::<PendingChangesResponse>()
+    )]
+    async fn pending_changes(
+        &self,
+        params: Parameters<PendingChangesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut cancelled = None;
+        if let Some(id) = params.0.cancel {
+            let change = self
+                .pending
+                .remove(&id)
+                .map_err(io_err)?
+                .ok_or_else(|| not_found("pending_change", &id))?;
+            self.revert_pending(change).await?;
+            tracing::info!(%id, "pending change cancelled");
+            cancelled = Some(id);
+        }
+        let maps = self.lookup_maps().await?;
+        let pending = self
+            .pending
+            .list()
+            .map_err(io_err)?
+            .into_iter()
+            .map(|change| PendingChangeResponse {
+                id: change.id,
+                tool: change.tool,
+                queued_at: change.queued_at.to_rfc3339(),
+                status: "pending".to_owned(),
+                transactions: change
+                    .transactions
+                    .iter()
+                    .map(|tx| TransactionResponse::from_transaction(tx, &maps))
+                    .collect(),
+            })
+            .collect();
+        json_result(&PendingChangesResponse { cancelled, pending })
     }
 
-    fn test_date() -> NaiveDate {
-        NaiveDate::from_ymd_opt(2024, 6, 15).expect("valid date for test")
+    /// Lists queued edits held back because ZenMoney changed the same transaction.
+    #[tool(
+        description = "List conflicts: queued edits (see pending_changes) that were not sent because the same transaction was changed on ZenMoney after the edit was made. Only edits queued while ZenMoney was unreachable are checked; edits sent directly overwrite the ZenMoney version, so sync first when another device may have changed the transaction. Each shows the local and remote versions and which field groups differ. Resolve them with resolve_conflict",
+        output_schema = cached_schema_for_type::<ConflictsResponse>()
+    )]
+    async fn list_conflicts(&self) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        let conflicts = self
+            .conflicts
+            .list()
+            .map_err(io_err)?
+            .into_iter()
+            .map(|conflict| ConflictResponse {
+                transaction_id: conflict.local.id.as_inner().to_owned(),
+                detected_at: conflict.detected_at.to_rfc3339(),
+                differing_fields: conflicts::differing_fields(&conflict.local, &conflict.remote),
+                local: TransactionResponse::from_transaction(&conflict.local, &maps),
+                remote: TransactionResponse::from_transaction(&conflict.remote, &maps),
+                id: conflict.id,
+            })
+            .collect();
+        json_result(&ConflictsResponse { conflicts })
     }
 
-    fn sample_maps() -> LookupMaps {
-        use zenmoney_rs::models::{Account, AccountType, Instrument, Tag};
-
-        let accounts = vec![
-            Account {
-                id: AccountId::new("acc-1".to_owned()),
-                changed: test_timestamp(),
-                user: UserId::new(1),
-                role: None,
-                instrument: Some(InstrumentId::new(1)),
-                company: None,
-                kind: AccountType::Checking,
-                title: "Main Account".to_owned(),
-                sync_id: None,
-                balance: Some(50_000.0),
-                start_balance: None,
-                credit_limit: None,
-                in_balance: true,
-                savings: None,
-                enable_correction: false,
-                enable_sms: false,
-                archive: false,
-                capitalization: None,
-                percent: None,
-                start_date: None,
-                end_date_offset: None,
-                end_date_offset_interval: None,
-                payoff_step: None,
-                payoff_interval: None,
-                balance_correction_type: None,
-                private: None,
-            },
-            Account {
-                id: AccountId::new("acc-2".to_owned()),
-                changed: test_timestamp(),
-                user: UserId::new(1),
-                role: None,
-                instrument: Some(InstrumentId::new(2)),
-                company: None,
-                kind: AccountType::Cash,
-                title: "USD Account".to_owned(),
-                sync_id: None,
-                balance: Some(1_000.0),
-                start_balance: None,
-                credit_limit: None,
-                in_balance: true,
-                savings: None,
-                enable_correction: false,
-                enable_sms: false,
-                archive: false,
-                capitalization: None,
-                percent: None,
-                start_date: None,
-                end_date_offset: None,
-                end_date_offset_interval: None,
-                payoff_step: None,
-                payoff_interval: None,
-                balance_correction_type: None,
-                private: None,
-            },
-        ];
-        let tags = vec![Tag {
-            id: TagId::new("tag-1".to_owned()),
-            changed: test_timestamp(),
-            user: UserId::new(1),
-            title: "Groceries".to_owned(),
-            parent: None,
-            icon: None,
-            picture: None,
-            color: None,
-            show_income: false,
-            show_outcome: true,
-            budget_income: false,
-            budget_outcome: true,
-            required: None,
-            static_id: None,
-            archive: None,
-        }];
-        let instruments = vec![
-            Instrument {
-                id: InstrumentId::new(1),
-                changed: test_timestamp(),
-                title: "Russian Ruble".to_owned(),
-                short_title: "RUB".to_owned(),
-                symbol: "\u{20bd}".to_owned(),
-                rate: 1.0,
-            },
-            Instrument {
-                id: InstrumentId::new(2),
-                changed: test_timestamp(),
-                title: "US Dollar".to_owned(),
-                short_title: "USD".to_owned(),
-                symbol: "$".to_owned(),
-                rate: 90.0,
-            },
-        ];
-        build_lookup_maps(&accounts, &tags, &instruments)
+    /// Resolves a conflict by keeping one version or merging them.
+    #[tool(
+        description = "Resolve a conflict from list_conflicts. resolution: keep_remote drops the local edit and restores the ZenMoney version locally; keep_local sends the local edit over the ZenMoney version; merge keeps the ZenMoney version but takes the field groups listed in local_fields (date, amounts, accounts, tags, merchant, payee, comment, deleted) from the local edit. Returns the resulting transaction"
+    )]
+    async fn resolve_conflict(
+        &self,
+        params: Parameters<ResolveConflictParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let ResolveConflictParams {
+            id,
+            resolution,
+            local_fields,
+        } = params.0;
+        if !matches!(resolution, ConflictResolution::Merge) && !local_fields.is_empty() {
+            return Err(McpError::invalid_params(
+                "local_fields is only used with resolution=merge",
+                None,
+            ));
+        }
+        // The conflict is only dropped once the resolution was saved, so a
+        // rejected push leaves it to be resolved again.
+        let conflict = self
+            .conflicts
+            .get(&id)
+            .map_err(io_err)?
+            .ok_or_else(|| not_found("conflict", &id))?;
+        let (resolved, push) = match resolution {
+            // Local storage may still hold the edit, so put ZenMoney's version back.
+            ConflictResolution::KeepRemote => {
+                self.client
+                    .storage()
+                    .upsert_transactions(vec![conflict.remote.clone()])
+                    .await
+                    .map_err(storage_err)?;
+                (conflict.remote, false)
+            }
+            ConflictResolution::KeepLocal => {
+                let mut local = conflict.local;
+                local.changed = Utc::now();
+                (local, true)
+            }
+            ConflictResolution::Merge => (
+                conflicts::merge(&conflict.remote, &conflict.local, &local_fields),
+                true,
+            ),
+        };
+        let pending = if push {
+            self.push_or_queue("resolve_conflict", vec![resolved.clone()])
+                .await?
+        } else {
+            None
+        };
+        let _resolved = self.conflicts.remove(&id).map_err(io_err)?;
+        tracing::info!(%id, ?resolution, "conflict resolved");
+        self.refresh_resources().await;
+        let maps = self.lookup_maps().await?;
+        let result = TransactionResponse::from_transaction(&resolved, &maps);
+        Ok(with_pending_note(json_result(&result)?, pending.as_deref()))
     }
 
-    fn sample_transaction(id: &str, outcome: f64, income: f64) -> Transaction {
-        Transaction {
-            id: TransactionId::new(id.to_owned()),
-            changed: test_timestamp(),
-            created: test_timestamp(),
-            user: UserId::new(1),
-            deleted: false,
-            hold: None,
-            income_instrument: InstrumentId::new(1),
-            income_account: AccountId::new("acc-1".to_owned()),
-            income,
-            outcome_instrument: InstrumentId::new(1),
-            outcome_account: AccountId::new("acc-1".to_owned()),
-            outcome,
-            tag: None,
-            merchant: None,
-            payee: None,
-            original_payee: None,
-            comment: None,
-            date: test_date(),
-            mcc: None,
-            reminder_marker: None,
-            op_income: None,
-            op_income_instrument: None,
-            op_outcome: None,
-            op_outcome_instrument: None,
-            latitude: None,
-            longitude: None,
-            income_bank_id: None,
-            outcome_bank_id: None,
-            qr_code: None,
-            source: None,
-            viewed: None,
-        }
+    /// Securely deletes locally cached data after confirmation.
+    #[tool(
+        description = "Securely delete all local ZenMoney data: synced data files (overwritten, then removed), saved templates, envelopes, staged bulk operations, queued offline writes, unresolved sync conflicts, planned transactions, the inbox review watermark and the saved default account. Returns how many of each were deleted. Data on the ZenMoney server is not affected. Requires confirm set to exactly \"PURGE LOCAL DATA\"; clients that support elicitation also ask the user, and a declined prompt returns purged: false. Only call when the user explicitly asks to wipe local data, e.g. before lending the machine or rotating tokens",
+        output_schema = cached_schema_for_type::<PurgeResponse>()
+    )]
+    async fn purge_local_data(
+        &self,
+        params: Parameters<PurgeLocalDataParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        // Always ask, regardless of ZENMONEY_CONFIRM_DESTRUCTIVE.
+        self.purge_local_data_confirmed(params.0, &Confirmation::new(true, &context))
+            .await
     }
 
-    fn sample_transfer(id: &str, outcome: f64, income: f64) -> Transaction {
-        let mut tx = sample_transaction(id, outcome, income);
-        tx.outcome_account = AccountId::new("acc-1".to_owned());
-        tx.income_account = AccountId::new("acc-2".to_owned());
-        tx.income_instrument = InstrumentId::new(2);
-        tx
+    /// Exports filtered transactions as QIF files, one per account.
+    #[tool(
+        description = "Export transactions to QIF (Quicken Interchange Format) files for GnuCash, Quicken and similar tools: one file per account touched by the selected transactions, written to directory (created if missing). Optional filters: date_from/date_to, account_id (export only that account), tag_id, include_children, payee, merchant_id, min_amount/max_amount, uncategorized, transaction_type, user_id; without filters everything is exported. Amounts are signed from each account's side (money in positive, money out negative); the category is the first tag, or [Other account] for transfers, which appear in the files of both accounts. Returns {format, directory, transactions, files: [{account, path, transactions}]}",
+        output_schema = cached_schema_for_type::<ExportResponse>()
+    )]
+    async fn export_qif(
+        &self,
+        params: Parameters<ExportParams>,
+    ) -> Result<CallToolResult, McpError> {
+        self.export_transactions(params.0, ExportFormat::Qif).await
     }
 
-    fn sample_create_params(tx_type: TransactionType) -> CreateTransactionParams {
-        CreateTransactionParams {
-            transaction_type: tx_type,
-            date: "2024-06-15".to_owned(),
-            account_id: "acc-1".to_owned(),
-            amount: 500.0,
-            to_account_id: None,
-            to_amount: None,
-            instrument_id: None,
-            to_instrument_id: None,
-            tag_ids: None,
-            payee: None,
-            comment: None,
-        }
+    /// Exports filtered transactions as OFX statements, one per account.
+    #[tool(
+        description = "Export transactions to OFX 2 (Open Financial Exchange) statement files for GnuCash and other tools: one file per account touched by the selected transactions, written to directory (created if missing). Credit cards get a credit card statement, other accounts a bank statement with the account currency and stored balance. Takes the same filters as export_qif. Amounts are signed from each account's side; transfers are XFER entries in both accounts' files and each entry's FITID is the transaction ID, so re-imports are deduplicated. Returns {format, directory, transactions, files: [{account, path, transactions}]}",
+        output_schema = cached_schema_for_type::<ExportResponse>()
+    )]
+    async fn export_ofx(
+        &self,
+        params: Parameters<ExportParams>,
+    ) -> Result<CallToolResult, McpError> {
+        self.export_transactions(params.0, ExportFormat::Ofx).await
     }
 
-    fn sample_create_tag_params(title: &str) -> CreateTagParams {
-        CreateTagParams {
-            title: title.to_owned(),
-            parent_tag_id: None,
-            icon: None,
-            color: None,
-            show_income: None,
-            show_outcome: None,
-            budget_income: None,
-            budget_outcome: None,
-            required: None,
+    /// Exports filtered transactions as an hledger or beancount journal.
+    #[tool(
+        description = "Export transactions to a plain-text accounting journal for hledger (dialect hledger, default; also read by ledger-cli) or beancount (dialect beancount). path is the file to write, or a directory to create zenmoney.journal / zenmoney.beancount in. Takes the same filters as export_qif. Accounts become Assets:<title> (Liabilities:<title> for credit cards and loans), the first tag of an expense or income becomes Expenses:<tag>:<subtag> or Income:<tag> (Uncategorized without one), and currencies are declared as commodities by code. Transfers between currencies record the outcome amount as the total cost (@@); each entry has the ZenMoney transaction ID as zenmoney_id metadata. Opening balances are not included. Returns {dialect, path, transactions, accounts, commodities}",
+        output_schema = cached_schema_for_type::<LedgerExportResponse>()
+    )]
+    async fn export_ledger(
+        &self,
+        params: Parameters<ExportLedgerParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let ExportLedgerParams {
+            path,
+            dialect,
+            selection,
+        } = params.0;
+        let maps = self.lookup_maps().await?;
+        let transactions = self.export_selection(&selection, &maps).await?;
+        let currencies = self.currency_codes().await?;
+        let accounts = self.client.accounts().await.map_err(zen_err)?;
+        let journal = render_journal(dialect, &accounts, &transactions, &currencies, &maps);
+        let file = journal_path(Path::new(&path), dialect);
+        let write_err = |err: std::io::Error| {
+            McpError::invalid_params(format!("cannot write to '{}': {err}", file.display()), None)
+        };
+        if let Some(parent) = file.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).map_err(write_err)?;
         }
+        std::fs::write(&file, &journal.text).map_err(write_err)?;
+        tracing::info!(
+            dialect = dialect.name(),
+            transactions = transactions.len(),
+            "ledger exported"
+        );
+        json_result(&LedgerExportResponse {
+            dialect: dialect.name().to_owned(),
+            path: file.display().to_string(),
+            transactions: transactions.len(),
+            accounts: journal.accounts,
+            commodities: journal.commodities,
+        })
     }
 
-    // ── parse_date ──────────────────────────────────────────────────
-
-    #[test]
-    fn parse_date_valid() {
-        let date = parse_date("2024-06-15").expect("valid date");
-        assert_eq!(date, NaiveDate::from_ymd_opt(2024, 6, 15).expect("valid"));
+    /// Writes a backup of all locally stored data to a file.
+    #[tool(
+        description = "Write a backup of all locally stored data (accounts, tags, instruments, merchants, companies, users, budgets, reminders, transactions, saved templates and envelopes) to path. If path is a directory, a timestamped zenmoney-backup-YYYYMMDD-HHMMSS.json file is created in it. Take a backup before risky bulk operations or before upgrading the server, and restore it with restore_data",
+        output_schema = cached_schema_for_type::<BackupResponse>()
+    )]
+    async fn backup_data(
+        &self,
+        params: Parameters<BackupDataParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let entities = self.backup_entities().await?;
+        let created_at = Utc::now();
+        let path = backup_path(Path::new(&params.0.path), created_at);
+        let bytes = write_backup(&path, &entities, created_at).map_err(io_err)?;
+        tracing::info!(path = %path.display(), bytes, "backup written");
+        json_result(&BackupResponse {
+            path: path.display().to_string(),
+            created_at: created_at.to_rfc3339(),
+            bytes,
+            counts: entities.counts(),
+        })
     }
 
-    #[test]
-    fn parse_date_invalid_format() {
-        let result = parse_date("15-06-2024");
-        assert!(result.is_err());
+    /// Writes all locally stored data to a JSON snapshot for external tools.
+    #[tool(
+        description = "Export all locally stored data (accounts, tags, instruments, merchants, companies, users, budgets, reminders, transactions, saved templates and envelopes) as one JSON document for external analysis, e.g. notebooks. Transactions can be limited to date_from/date_to; deleted ones are left out unless include_deleted=true. If path is a directory, a timestamped zenmoney-snapshot-YYYYMMDD-HHMMSS.json file is created in it. The document has schema \"zenmoney-mcp/snapshot\", a schema_version, the generating server version, created_at, the transaction bounds, per-collection counts and the entities in ZenMoney's own field layout. Unlike backup_data it has no checksum and cannot be restored with restore_data. Returns {path, created_at, schema_version, bytes, counts}",
+        output_schema = cached_schema_for_type::<SnapshotResponse>()
+    )]
+    async fn export_snapshot(
+        &self,
+        params: Parameters<ExportSnapshotParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let range = SnapshotRange {
+            date_from: params.0.date_from.as_deref().map(parse_date).transpose()?,
+            date_to: params.0.date_to.as_deref().map(parse_date).transpose()?,
+            include_deleted: params.0.include_deleted.unwrap_or(false),
+        };
+        let mut entities = self.backup_entities().await?;
+        entities.transactions.retain(|tx| range.contains(tx));
+        let created_at = Utc::now();
+        let path = snapshot_path(Path::new(&params.0.path), created_at);
+        let bytes = write_snapshot(&path, &entities, range, created_at).map_err(io_err)?;
+        tracing::info!(path = %path.display(), bytes, "snapshot written");
+        json_result(&SnapshotResponse {
+            path: path.display().to_string(),
+            created_at: created_at.to_rfc3339(),
+            schema_version: SNAPSHOT_SCHEMA_VERSION,
+            bytes,
+            counts: entities.counts(),
+        })
     }
 
-    #[test]
-    fn parse_date_invalid_date() {
-        let result = parse_date("2024-13-40");
-        assert!(result.is_err());
+    /// Restores a backup into local storage, or stages a revert of transactions.
+    #[tool(
+        description = "Restore a backup written by backup_data. Its format version, checksum and entity counts are verified first, so a corrupted or edited backup is rejected. mode=local (default) replaces local storage with the backed-up entities, templates and envelopes without contacting ZenMoney; the next sync downloads everything again so the server's current data wins, and write tools sync first. mode=revert_transactions instead stages changes putting transactions on ZenMoney back to their backed-up state (re-saving changed or deleted ones, deleting ones created since) and returns a preview with a preparation_id; nothing is changed until execute_bulk_operations is called"
+    )]
+    async fn restore_data(
+        &self,
+        params: Parameters<RestoreDataParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let (created_at, entities) = read_backup(Path::new(&params.0.path)).map_err(|err| {
+            McpError::invalid_params(format!("cannot restore '{}': {err}", params.0.path), None)
+        })?;
+        match params.0.mode.unwrap_or_default() {
+            RestoreMode::Local => self.restore_local(created_at, entities).await,
+            RestoreMode::RevertTransactions => self.stage_revert(entities.transactions).await,
+        }
     }
 
-    // ── tag helpers ────────────────────────────────────────────────
+    // ── Read tools ──────────────────────────────────────────────────
 
-    #[test]
-    fn normalize_tag_title_trims_text() {
-        let normalized = normalize_tag_title("  Rent an apartment  ").expect("valid title");
-        assert_eq!(normalized, "Rent an apartment");
-    }
-
-    #[test]
-    fn normalize_tag_title_blank_errors() {
-        let result = normalize_tag_title("   ");
-        assert!(result.is_err());
+    /// Lists all accounts (or only active ones).
+    #[tool(
+        description = "List financial accounts. Set active_only=true to exclude archived accounts. Set convert_to to an instrument ID to add converted_balance in that currency. Set user_id to only return accounts owned by that user (see list_users), and instrument_id to only return accounts in that currency. Set sort_by to balance (highest first, compared in convert_to when given), title, or last_activity (most recent transaction first). Set group_by_type=true to return {groups: [{account_type, count, subtotal, currency, accounts}]} instead of a flat list; subtotal sums balances in convert_to, else in the base currency (see server_info), or natively when the group holds a single currency and no base currency is known"
+    )]
+    async fn list_accounts(
+        &self,
+        params: Parameters<ListAccountsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        validate_convert_to(&maps, params.0.convert_to)?;
+        let all_accounts = if params.0.active_only {
+            self.client.active_accounts().await.map_err(zen_err)?
+        } else {
+            self.client.accounts().await.map_err(zen_err)?
+        };
+        let mut accounts: Vec<Account> = all_accounts
+            .into_iter()
+            .filter(|acc| {
+                params
+                    .0
+                    .user_id
+                    .is_none_or(|user_id| acc.user.into_inner() == user_id)
+                    && params.0.instrument_id.is_none_or(|instrument_id| {
+                        acc.instrument.map(InstrumentId::into_inner) == Some(instrument_id)
+                    })
+            })
+            .collect();
+        match params.0.sort_by {
+            Some(AccountSort::Balance) => {
+                let balance = |acc: &Account| {
+                    converted_balance(acc, &maps, params.0.convert_to)
+                        .or(acc.balance)
+                        .unwrap_or(f64::NEG_INFINITY)
+                };
+                accounts.sort_by(|left, right| balance(right).total_cmp(&balance(left)));
+            }
+            Some(AccountSort::Title) => accounts.sort_by_cached_key(|acc| acc.title.to_lowercase()),
+            Some(AccountSort::LastActivity) => {
+                let transactions = self.client.transactions().await.map_err(zen_err)?;
+                let last = last_activity_by_account(&transactions);
+                accounts.sort_by(|left, right| {
+                    last.get(right.id.as_inner())
+                        .cmp(&last.get(left.id.as_inner()))
+                });
+            }
+            None => {}
+        }
+        if params.0.group_by_type {
+            let target = self.totals_currency(&maps, params.0.convert_to).await?;
+            let groups = group_accounts(&accounts, &maps, target);
+            return json_result(&AccountGroupsResponse { groups });
+        }
+        let result: Vec<AccountResponse> =
+            accounts
+                .iter()
+                .map(|acc| {
+                    AccountResponse::from_account(acc, &maps)
+                        .with_converted_balance(converted_balance(acc, &maps, params.0.convert_to))
+                })
+                .collect();
+        json_result(&result)
     }
 
-    #[test]
-    fn find_tag_by_title_case_insensitive_matches_existing() {
-        let tags = vec![Tag {
-            id: TagId::new("tag-1".to_owned()),
-            changed: test_timestamp(),
-            user: UserId::new(1),
-            title: "Groceries".to_owned(),
-            parent: None,
-            icon: None,
-            picture: None,
-            color: None,
-            show_income: false,
-            show_outcome: true,
-            budget_income: false,
-            budget_outcome: true,
-            required: None,
-            static_id: None,
-            archive: None,
-        }];
-        let key = "gRoCeRiEs";
-        let tag = find_tag_by_title_case_insensitive(&tags, key);
-        assert!(tag.is_some());
+    /// Gets an account by ID with its recent activity.
+    #[tool(
+        description = "Get an account by ID with its details plus computed activity: month_inflow and month_outflow for the current calendar month (in the account currency, transfers included), last_transaction date, and pending_reminders {count, income, outcome} totalling per-occurrence amounts of reminders involving it that have not ended. An unknown account_id fails with a not_found error",
+        output_schema = cached_schema_for_type::<AccountDetailsResponse>()
+    )]
+    async fn get_account(
+        &self,
+        params: Parameters<GetAccountParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        let accounts = self.client.accounts().await.map_err(zen_err)?;
+        let Some(account) = accounts
+            .iter()
+            .find(|account| account.id.as_inner() == params.0.account_id)
+        else {
+            return Err(not_found("account", &params.0.account_id));
+        };
+        let transactions = self
+            .filtered_transactions(
+                &ListTransactionsParams {
+                    account_id: Some(params.0.account_id.clone()),
+                    ..Default::default()
+                },
+                &maps,
+            )
+            .await?;
+        let reminders = self.client.reminders().await.map_err(zen_err)?;
+        let activity = account_activity(
+            &params.0.account_id,
+            &transactions,
+            &reminders,
+            Utc::now().date_naive(),
+        );
+        json_result(&AccountDetailsResponse {
+            account: AccountResponse::from_account(account, &maps),
+            activity,
+        })
     }
 
-    #[test]
-    fn build_tag_uses_expense_defaults() {
-        let params = sample_create_tag_params("Utilities");
-        let tag = build_tag(params, 5, "Utilities".to_owned());
-        assert_eq!(tag.title, "Utilities");
-        assert_eq!(tag.user, UserId::new(5));
-        assert!(!tag.show_income);
-        assert!(tag.show_outcome);
-        assert!(!tag.budget_income);
-        assert!(tag.budget_outcome);
-        assert_eq!(tag.archive, Some(false));
+    /// Recomputes account balances from history and reports mismatches.
+    #[tool(
+        description = "Verify account balances: recompute each balance as start_balance plus all income minus all outcome of non-deleted transactions and compare it with the stored balance. Returns {checked, matching, discrepancies: [{account_id, account, currency, start_balance, transaction_count, computed_balance, stored_balance, difference}]}, largest difference first. A discrepancy points at missed transactions or corrupted sync data; fix it with reconcile_account. Set account_id to check one account (an unknown ID fails with a not_found error) and tolerance to change the allowed difference (default 0.01)",
+        output_schema = cached_schema_for_type::<VerifyBalancesResponse>()
+    )]
+    async fn verify_balances(
+        &self,
+        params: Parameters<VerifyBalancesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        let mut accounts = self.client.accounts().await.map_err(zen_err)?;
+        if let Some(account_id) = params.0.account_id.as_deref() {
+            accounts.retain(|account| account.id.as_inner() == account_id);
+            if accounts.is_empty() {
+                return Err(not_found("account", account_id));
+            }
+        }
+        let tolerance = params.0.tolerance.unwrap_or(BALANCE_TOLERANCE).abs();
+        let transactions = self.client.transactions().await.map_err(zen_err)?;
+        let checks = check_balances(&accounts, &transactions, &maps);
+        let checked = checks.len();
+        let mut discrepancies: Vec<BalanceCheck> = checks
+            .into_iter()
+            .filter(|check| check.difference.abs() > tolerance)
+            .collect();
+        discrepancies
+            .sort_by(|left, right| right.difference.abs().total_cmp(&left.difference.abs()));
+        json_result(&VerifyBalancesResponse {
+            checked,
+            matching: checked.saturating_sub(discrepancies.len()),
+            discrepancies,
+        })
     }
 
-    // ── to_json_text / json_result ──────────────────────────────────
+    /// Lists transactions with optional filtering, sorting, pagination, and type/category filters.
+    #[tool(
+        description = "List transactions with optional filters: date range, account, tag, payee, merchant, amount range, transaction_type (expense/income/transfer), uncategorized (true to show only untagged), sort (asc/desc by date, default desc), limit (default 100, max 500), and offset (for pagination). Set user_id to only return transactions recorded by that user (see list_users). Set unviewed_only=true to return only transactions not yet marked as viewed (see mark_viewed). Set hold=true for pending card authorizations only, or hold=false for settled transactions only. Set near={latitude, longitude, radius_km} to return only transactions recorded within that distance. Set convert_to to an instrument ID to add converted_amount in that currency. Returns {items, total, offset, limit, summary}; summary totals every matching transaction, not just the page: count, transfer_count, income, outcome and net (transfers excluded; in convert_to, else in the base currency (see server_info), or omitted when several currencies are mixed and no base currency is known) and by_currency native totals, so use it instead of summing items. Set mcc to filter by merchant category code. Set group_by (tag/payee/account/merchant/month/weekday/mcc) to return aggregated {groups: [{key, label, count, income, outcome}], total} instead of rows. Set include_children=true to make tag_id match child tags and roll child tags up into their parent when grouping by tag. Set changed_since (RFC 3339 timestamp or YYYY-MM-DD) to return only transactions modified after that time; each item carries its changed time, so pass the latest one on the next call to poll for deltas. Set reminder_id to return only payments recorded against that recurring reminder; items recorded from a reminder carry reminder {marker_id, reminder_id, payee, interval}."
+    )]
+    async fn list_transactions(
+        &self,
+        params: Parameters<ListTransactionsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        validate_convert_to(&maps, params.0.convert_to)?;
+        let mut transactions = self.filtered_transactions(&params.0, &maps).await?;
 
-    #[test]
-    fn to_json_text_serializes_pretty() {
-        #[derive(serde::Serialize)]
-        struct Simple {
-            name: String,
+        if let Some(group_by) = params.0.group_by {
+            if params.0.include_children == Some(true) {
+                roll_up_tags(&mut transactions, &maps);
+            }
+            let groups = group_transactions(&transactions, group_by, &maps, params.0.convert_to);
+            return json_result(&GroupedTransactions {
+                groups,
+                total: transactions.len(),
+            });
         }
-        let val = Simple {
-            name: "test".to_owned(),
-        };
-        let text = to_json_text(&val).expect("should serialize");
-        assert!(text.contains("\"name\": \"test\""));
-        // Pretty-printed means it has newlines.
-        assert!(text.contains('\n'));
-    }
 
-    #[test]
-    fn json_result_returns_call_tool_result() {
-        let val = vec![1, 2, 3];
-        let result = json_result(&val).expect("should produce result");
-        assert!(!result.is_error.unwrap_or(false));
-        assert!(!result.content.is_empty());
+        sort_by_date(&mut transactions, params.0.sort.unwrap_or_default());
+        let target = self.totals_currency(&maps, params.0.convert_to).await?;
+        let summary = summarize_transactions(&transactions, &maps, target);
+        let mut page = paginate_transactions(
+            transactions,
+            params.0.offset,
+            params.0.limit,
+            &maps,
+            params.0.convert_to,
+        );
+        page.summary = Some(summary);
+        json_result(&page)
     }
 
-    // ── account_type_label ──────────────────────────────────────────
-
-    #[test]
-    fn account_type_label_all_variants() {
-        use zenmoney_rs::models::AccountType;
-        assert_eq!(account_type_label(AccountType::Cash), "Cash");
-        assert_eq!(account_type_label(AccountType::CreditCard), "CreditCard");
-        assert_eq!(account_type_label(AccountType::Checking), "Checking");
-        assert_eq!(account_type_label(AccountType::Loan), "Loan");
-        assert_eq!(account_type_label(AccountType::Deposit), "Deposit");
-        assert_eq!(account_type_label(AccountType::EMoney), "EMoney");
-        assert_eq!(account_type_label(AccountType::Debt), "Debt");
+    /// Lists transactions marked as deleted in local storage.
+    #[tool(
+        description = "List deleted transactions still present in local storage, newest first. Optional date range (date_from/date_to), limit (default 100, max 500) and offset. Returns {items, total, offset, limit}; pass an item's id to restore_transaction to undo the deletion",
+        output_schema = cached_schema_for_type::<PaginatedTransactions>()
+    )]
+    async fn list_deleted_transactions(
+        &self,
+        params: Parameters<ListDeletedTransactionsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        let date_from = params.0.date_from.as_deref().map(parse_date).transpose()?;
+        let date_to = params.0.date_to.as_deref().map(parse_date).transpose()?;
+        let mut transactions = self.client.transactions().await.map_err(zen_err)?;
+        transactions.retain(|tx| {
+            tx.deleted
+                && date_from.is_none_or(|from| tx.date >= from)
+                && date_to.is_none_or(|to| tx.date <= to)
+        });
+        sort_by_date(&mut transactions, SortDirection::Desc);
+        json_result(&paginate_transactions(
+            transactions,
+            params.0.offset,
+            params.0.limit,
+            &maps,
+            None,
+        ))
     }
 
-    // ── resolve_instrument ──────────────────────────────────────────
+    /// Lists category tags with optional archive, parent, and usage filters.
+    #[tool(
+        description = "List transaction category tags. Archived tags are excluded unless include_archived=true. Set parent_tag_id to list only direct children of a tag, and used_only=true to list only tags referenced by at least one transaction"
+    )]
+    async fn list_tags(
+        &self,
+        params: Parameters<ListTagsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        let mut tags = self.client.tags().await.map_err(zen_err)?;
 
-    #[test]
-    fn resolve_instrument_explicit_overrides() {
-        let maps = sample_maps();
-        let result = resolve_instrument(&maps, "acc-1", Some(42)).expect("should resolve");
-        assert_eq!(result.into_inner(), 42);
-    }
+        if params.0.include_archived != Some(true) {
+            tags.retain(|tag| !tag.archive.unwrap_or(false));
+        }
+        if let Some(parent_id) = params.0.parent_tag_id.as_deref() {
+            tags.retain(|tag| {
+                tag.parent
+                    .as_ref()
+                    .is_some_and(|parent| parent.as_inner() == parent_id)
+            });
+        }
+        if params.0.used_only == Some(true) {
+            let transactions = self.client.transactions().await.map_err(zen_err)?;
+            let used: HashSet<&TagId> = transactions
+                .iter()
+                .filter(|tx| !tx.deleted)
+                .flat_map(|tx| tx.tag.as_deref().unwrap_or_default())
+                .collect();
+            tags.retain(|tag| used.contains(&tag.id));
+        }
 
-    #[test]
-    fn resolve_instrument_from_maps() {
-        let maps = sample_maps();
-        let result = resolve_instrument(&maps, "acc-1", None).expect("should resolve");
-        assert_eq!(result.into_inner(), 1);
+        let result: Vec<TagResponse> = tags
+            .iter()
+            .map(|tag| TagResponse::from_tag(tag, &maps))
+            .collect();
+        json_result(&result)
     }
 
-    #[test]
-    fn resolve_instrument_unknown_account_errors() {
-        let maps = sample_maps();
-        let result = resolve_instrument(&maps, "unknown", None);
-        assert!(result.is_err());
+    /// Lists category tags as a parent/child tree.
+    #[tool(
+        description = "List category tags as a nested tree: root tags with their children, sorted by title. Archived tags are excluded unless include_archived=true; children of excluded parents are shown as roots. Returns [{id, title, archive, children: [...]}]"
+    )]
+    async fn tag_tree(
+        &self,
+        params: Parameters<TagTreeParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let tags = self.client.tags().await.map_err(zen_err)?;
+        let include_archived = params.0.include_archived.unwrap_or(false);
+        let visible: Vec<&Tag> = tags
+            .iter()
+            .filter(|tag| include_archived || !tag.archive.unwrap_or(false))
+            .collect();
+        json_result(&TagNode::tree(&visible))
     }
 
-    // ── classify_transaction ────────────────────────────────────────
-
-    #[test]
-    fn classify_expense() {
-        let tx = sample_transaction("tx-1", 500.0, 0.0);
-        assert!(matches!(
-            classify_transaction(&tx),
-            TransactionType::Expense
-        ));
+    /// Lists all merchants.
+    #[tool(description = "List all merchants/payees")]
+    async fn list_merchants(&self) -> Result<CallToolResult, McpError> {
+        let merchants = self.client.merchants().await.map_err(zen_err)?;
+        let result: Vec<MerchantResponse> = merchants
+            .iter()
+            .map(MerchantResponse::from_merchant)
+            .collect();
+        json_result(&result)
     }
 
-    #[test]
-    fn classify_income() {
-        let tx = sample_transaction("tx-1", 0.0, 1000.0);
-        assert!(matches!(classify_transaction(&tx), TransactionType::Income));
+    /// Lists synced users.
+    #[tool(
+        description = "List users synced into this workspace. Family/shared workspaces have several users; members have a parent_user_id pointing at the owner"
+    )]
+    async fn list_users(&self) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        let users = self.client.users().await.map_err(zen_err)?;
+        let result: Vec<UserResponse> = users
+            .iter()
+            .map(|user| UserResponse::from_user(user, &maps))
+            .collect();
+        json_result(&result)
     }
 
-    #[test]
-    fn classify_transfer() {
-        let tx = sample_transfer("tx-1", 500.0, 500.0);
-        assert!(matches!(
-            classify_transaction(&tx),
-            TransactionType::Transfer
-        ));
+    /// Lists companies (banks), by default only those linked to accounts.
+    #[tool(
+        description = "List companies (banks). By default returns only companies linked to at least one account; set include_unused=true for the full catalog. Optionally filter by query (case-insensitive title substring)"
+    )]
+    async fn list_companies(
+        &self,
+        params: Parameters<ListCompaniesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let companies = self.client.companies().await.map_err(zen_err)?;
+        let used: HashSet<i32> = if params.0.include_unused {
+            HashSet::new()
+        } else {
+            self.client
+                .accounts()
+                .await
+                .map_err(zen_err)?
+                .iter()
+                .filter_map(|account| account.company.map(CompanyId::into_inner))
+                .collect()
+        };
+        let query = params.0.query.as_deref().map(str::to_lowercase);
+        let result: Vec<CompanyResponse> = companies
+            .iter()
+            .filter(|company| company.deleted != Some(true))
+            .filter(|company| params.0.include_unused || used.contains(&company.id.into_inner()))
+            .filter(|company| {
+                query
+                    .as_deref()
+                    .is_none_or(|needle| company.title.to_lowercase().contains(needle))
+            })
+            .map(CompanyResponse::from_company)
+            .collect();
+        json_result(&result)
     }
 
-    #[test]
-    fn classify_same_account_both_positive_is_income() {
-        // Both positive but same account → Income (not Transfer).
-        let tx = sample_transaction("tx-1", 100.0, 200.0);
-        assert!(matches!(classify_transaction(&tx), TransactionType::Income));
-    }
+    /// Lists budgets, optionally filtered by month or a range of months.
+    #[tool(
+        description = "List monthly budgets. Optionally filter by month (format: YYYY-MM). For reviews over several months set month_from and/or month_to (YYYY-MM, inclusive) or year (January to December); the result is then {months: [{month, income, outcome, budgets}], income, outcome} with per-month and grand totals of the targets"
+    )]
+    async fn list_budgets(
+        &self,
+        params: Parameters<ListBudgetsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        let budgets = self.client.budgets().await.map_err(zen_err)?;
+        if let Some((first, last)) = budget_month_range(&params.0)? {
+            let in_range: Vec<Budget> = budgets
+                .into_iter()
+                .filter(|budget| budget.date >= first && budget.date <= last)
+                .collect();
+            let months = budget_months(&in_range, &maps);
+            return json_result(&BudgetRangeResponse {
+                income: round_cents(months.iter().map(|month| month.income).sum()),
+                outcome: round_cents(months.iter().map(|month| month.outcome).sum()),
+                months,
+            });
+        }
 
-    // ── filter_by_transaction_type ──────────────────────────────────
+        let filtered_budgets: Vec<_> = if let Some(month_str) = params.0.month.as_deref() {
+            let month_prefix = format!("{month_str}-01");
+            let month_date = parse_date(&month_prefix)?;
+            budgets
+                .into_iter()
+                .filter(|budget| budget.date == month_date)
+                .collect()
+        } else {
+            budgets
+        };
 
-    #[test]
-    fn filter_expense_retains_only_expenses() {
-        let mut txs = vec![
-            sample_transaction("tx-1", 500.0, 0.0),  // expense
-            sample_transaction("tx-2", 0.0, 1000.0), // income
-            sample_transfer("tx-3", 300.0, 300.0),   // transfer
-        ];
-        filter_by_transaction_type(&mut txs, Some(&TransactionType::Expense));
-        assert_eq!(txs.len(), 1);
-        assert_eq!(txs[0].id.as_inner(), "tx-1");
+        let result: Vec<BudgetResponse> = filtered_budgets
+            .iter()
+            .map(|budget| BudgetResponse::from_budget(budget, &maps))
+            .collect();
+        json_result(&result)
     }
 
-    #[test]
-    fn filter_income_retains_only_income() {
-        let mut txs = vec![
-            sample_transaction("tx-1", 500.0, 0.0),
-            sample_transaction("tx-2", 0.0, 1000.0),
-        ];
-        filter_by_transaction_type(&mut txs, Some(&TransactionType::Income));
-        assert_eq!(txs.len(), 1);
-        assert_eq!(txs[0].id.as_inner(), "tx-2");
+    /// Reports spending against the month's budgets, optionally with rollover.
+    #[tool(
+        description = "Show spending against the category budgets of a month (YYYY-MM, default current): per category budget, spent (the tag and its children), remaining and percent_used, least remaining first, plus totals. Set rollover=true to carry unspent (or overspent) amounts of the category's budgets in earlier months forward: carried_over = sum of (budget - spent) over budgeted months in the last rollover_months months (default 12, max 60), and available = budget + carried_over. ZenMoney itself does not roll budgets over. Set convert_to to an instrument ID to sum spending in one currency",
+        output_schema = cached_schema_for_type::<BudgetProgressResponse>()
+    )]
+    async fn budget_progress(
+        &self,
+        params: Parameters<BudgetProgressParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        validate_convert_to(&maps, params.0.convert_to)?;
+        let month = parse_month(params.0.month.as_deref())?;
+        let month_end = month
+            .checked_add_months(Months::new(1))
+            .and_then(|next| next.pred_opt())
+            .unwrap_or(month);
+        let rollover_months = params.0.rollover.then(|| {
+            params
+                .0
+                .rollover_months
+                .unwrap_or(DEFAULT_ROLLOVER_MONTHS)
+                .min(MAX_ROLLOVER_MONTHS)
+        });
+        let months: Vec<NaiveDate> = (0..=rollover_months.unwrap_or(0))
+            .rev()
+            .filter_map(|back| month.checked_sub_months(Months::new(back)))
+            .collect();
+        let first_day = months.first().copied().unwrap_or(month);
+        let expenses = self
+            .filtered_transactions(
+                &ListTransactionsParams {
+                    date_from: Some(first_day.to_string()),
+                    date_to: Some(month_end.to_string()),
+                    transaction_type: Some(TransactionType::Expense),
+                    ..Default::default()
+                },
+                &maps,
+            )
+            .await?;
+        let budgets = self.client.budgets().await.map_err(zen_err)?;
+        let categories = budget_progress(&budgets, &expenses, &months, &maps, params.0.convert_to);
+        let total = |amount: fn(&BudgetProgress) -> f64| -> f64 {
+            round_cents(categories.iter().map(amount).sum())
+        };
+        json_result(&BudgetProgressResponse {
+            month: month.format("%Y-%m").to_string(),
+            rollover_months,
+            budget: total(|category| category.budget),
+            carried_over: total(|category| category.carried_over),
+            spent: total(|category| category.spent),
+            remaining: total(|category| category.remaining),
+            categories,
+        })
     }
 
-    #[test]
-    fn filter_transfer_retains_only_transfers() {
-        let mut txs = vec![
-            sample_transaction("tx-1", 500.0, 0.0),
-            sample_transfer("tx-2", 300.0, 300.0),
-        ];
-        filter_by_transaction_type(&mut txs, Some(&TransactionType::Transfer));
-        assert_eq!(txs.len(), 1);
-        assert_eq!(txs[0].id.as_inner(), "tx-2");
+    /// Proposes monthly budgets from past spending and stages them.
+    #[tool(
+        description = "Suggest monthly category budgets from spending history. For each category, sums expenses (by first tag, in the account owner's currency) per month over the last months full months (default 6, max 24) and proposes the median (basis=median, default) or average (basis=average) monthly total, optionally rounded up to a multiple of round_to. Returns {month, history_from, history_to, currency, total, suggestions: [{tag_id, tag, months_with_spending, median, average, amount, current}], preparation_id}; pass preparation_id to execute_bulk_operations to save the proposals as budgets for month (YYYY-MM, default current month), replacing current outcome targets",
+        output_schema = cached_schema_for_type::<BudgetSuggestionsResponse>()
+    )]
+    async fn suggest_budgets(
+        &self,
+        params: Parameters<SuggestBudgetsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        let month = parse_month(params.0.month.as_deref())?;
+        let this_month = parse_month(None)?;
+        let lookback = params
+            .0
+            .months
+            .unwrap_or(DEFAULT_BUDGET_HISTORY_MONTHS)
+            .clamp(1, MAX_BUDGET_HISTORY_MONTHS);
+        let history: Vec<NaiveDate> = (1..=lookback)
+            .rev()
+            .filter_map(|back| this_month.checked_sub_months(Months::new(back)))
+            .collect();
+        let history_from = history.first().copied().unwrap_or(this_month);
+        let history_to = this_month.pred_opt().unwrap_or(this_month);
+        let users = self.client.users().await.map_err(zen_err)?;
+        let owner = users
+            .iter()
+            .find(|user| user.parent.is_none())
+            .ok_or_else(|| McpError::internal_error("no account owner found", None))?;
+        let base = self
+            .base_currency()
+            .await?
+            .unwrap_or_else(|| owner.currency.into_inner());
+        let expenses = self
+            .filtered_transactions(
+                &ListTransactionsParams {
+                    date_from: Some(history_from.to_string()),
+                    date_to: Some(history_to.to_string()),
+                    transaction_type: Some(TransactionType::Expense),
+                    ..Default::default()
+                },
+                &maps,
+            )
+            .await?;
+        let mut suggestions =
+            suggest_budget_amounts(&expenses, &history, &maps, params.0.basis, Some(base));
+        let existing = self.client.budgets().await.map_err(zen_err)?;
+        let budgets = staged_budgets(
+            &mut suggestions,
+            &existing,
+            month,
+            owner.id,
+            params.0.round_to,
+        );
+        let preparation_id = (!budgets.is_empty())
+            .then(|| {
+                self.store_preparation(PreparedBulk {
+                    to_push: Vec::new(),
+                    to_delete: Vec::new(),
+                    created_count: 0,
+                    updated_count: 0,
+                    budgets,
+                })
+            })
+            .transpose()?;
+        json_result(&BudgetSuggestionsResponse {
+            month: month.format("%Y-%m").to_string(),
+            history_from: history_from.to_string(),
+            history_to: history_to.to_string(),
+            currency: maps.instrument_symbol(base),
+            total: round_cents(suggestions.iter().map(|suggestion| suggestion.amount).sum()),
+            suggestions,
+            preparation_id,
+        })
     }
 
-    #[test]
-    fn filter_none_keeps_all() {
-        let mut txs = vec![
-            sample_transaction("tx-1", 500.0, 0.0),
-            sample_transaction("tx-2", 0.0, 1000.0),
-        ];
-        filter_by_transaction_type(&mut txs, None);
-        assert_eq!(txs.len(), 2);
+    /// Lists all reminders.
+    #[tool(description = "List all recurring transaction reminders")]
+    async fn list_reminders(&self) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        let reminders = self.client.reminders().await.map_err(zen_err)?;
+        let result: Vec<ReminderResponse> = reminders
+            .iter()
+            .map(|rem| ReminderResponse::from_reminder(rem, &maps))
+            .collect();
+        json_result(&result)
     }
 
-    // ── is_uncategorized ────────────────────────────────────────────
-
-    #[test]
-    fn is_uncategorized_no_tags() {
-        let tx = sample_transaction("tx-1", 500.0, 0.0);
-        assert!(is_uncategorized(&tx));
+    /// Lists reminder markers with optional reminder, state and date filters.
+    #[tool(
+        description = "List reminder markers: the individual planned occurrences of recurring reminders, each with its date, state (planned, processed or deleted), accounts, amounts, tags, payee and comment. Filter by reminder_id, state and date_from/date_to (inclusive). Sorted by date, oldest first; limit defaults to 100 (max 500). Use set_marker_state to process, skip or convert one"
+    )]
+    async fn list_reminder_markers(
+        &self,
+        params: Parameters<ListReminderMarkersParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        let date_from = params.0.date_from.as_deref().map(parse_date).transpose()?;
+        let date_to = params.0.date_to.as_deref().map(parse_date).transpose()?;
+        let state = params.0.state.map(marker_state);
+        let mut markers = self.client.reminder_markers().await.map_err(zen_err)?;
+        markers.retain(|marker| {
+            params
+                .0
+                .reminder_id
+                .as_deref()
+                .is_none_or(|id| marker.reminder.as_inner() == id)
+                && state.is_none_or(|wanted| marker.state == wanted)
+                && date_from.is_none_or(|from| marker.date >= from)
+                && date_to.is_none_or(|to| marker.date <= to)
+        });
+        markers.sort_by_key(|marker| marker.date);
+        let limit = params
+            .0
+            .limit
+            .unwrap_or(DEFAULT_TRANSACTION_LIMIT)
+            .min(MAX_TRANSACTION_LIMIT);
+        let result: Vec<ReminderMarkerResponse> = markers
+            .iter()
+            .take(limit)
+            .map(|marker| ReminderMarkerResponse::from_marker(marker, &maps))
+            .collect();
+        json_result(&result)
     }
 
-    #[test]
-    fn is_uncategorized_empty_vec() {
-        let mut tx = sample_transaction("tx-1", 500.0, 0.0);
-        tx.tag = Some(vec![]);
-        assert!(is_uncategorized(&tx));
+    /// Reconciles a month's bill occurrences with the payments recorded for them.
+    #[tool(
+        description = "Show what is left to pay in a month (default: current): every occurrence of a recurring reminder with an outcome that falls due in the month, with due_date, amount, currency and status paid, due, overdue or skipped. A transaction linked to the occurrence's reminder marker pays it; otherwise an unlinked transaction from the same account within match_days (default 5) of the due date that shares the reminder's payee or a tag does, and its paid_date, paid_amount and transaction_id are shown. Optionally limit to bills paid from account_id. Returns {month, today, paid_count, due_count, overdue_count, totals: [{currency, paid, remaining}], bills}",
+        output_schema = cached_schema_for_type::<UpcomingBillsResponse>()
+    )]
+    async fn upcoming_bills(
+        &self,
+        params: Parameters<UpcomingBillsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        let month = parse_month(params.0.month.as_deref())?;
+        let month_end = month
+            .checked_add_months(Months::new(1))
+            .and_then(|next| next.pred_opt())
+            .unwrap_or(month);
+        let mut reminders = self.client.reminders().await.map_err(zen_err)?;
+        if let Some(account_id) = params.0.account_id.as_deref() {
+            reminders.retain(|reminder| reminder.outcome_account.as_inner() == account_id);
+        }
+        let markers = self.client.reminder_markers().await.map_err(zen_err)?;
+        let transactions = self.client.transactions().await.map_err(zen_err)?;
+        let window = BillWindow {
+            from: month,
+            to: month_end,
+            today: Utc::now().date_naive(),
+            match_days: u64::from(params.0.match_days.unwrap_or(DEFAULT_BILL_MATCH_DAYS)),
+        };
+        let bills = upcoming_bills(&reminders, &markers, &transactions, &window, &maps);
+        let count = |status: &str| bills.iter().filter(|bill| bill.status == status).count();
+        json_result(&UpcomingBillsResponse {
+            month: month.to_string(),
+            today: window.today.to_string(),
+            paid_count: count("paid"),
+            due_count: count("due"),
+            overdue_count: count("overdue"),
+            totals: bill_totals(&bills),
+            bills,
+        })
     }
 
-    #[test]
-    fn is_uncategorized_with_tags() {
-        let mut tx = sample_transaction("tx-1", 500.0, 0.0);
-        tx.tag = Some(vec![TagId::new("tag-1".to_owned())]);
-        assert!(!is_uncategorized(&tx));
+    /// Lists currency instruments, optionally only those in use.
+    #[tool(
+        description = "List currency instruments with their exchange rates. ZenMoney knows hundreds of world currencies; set used_only=true to list only those used by an account or transaction, or use find_instrument to look one up by code or name"
+    )]
+    async fn list_instruments(
+        &self,
+        params: Parameters<ListInstrumentsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut instruments = self.client.instruments().await.map_err(zen_err)?;
+        if params.0.used_only == Some(true) {
+            let accounts = self.client.accounts().await.map_err(zen_err)?;
+            let transactions = self.client.transactions().await.map_err(zen_err)?;
+            let used: HashSet<i32> = accounts
+                .iter()
+                .filter_map(|account| account.instrument)
+                .chain(
+                    transactions
+                        .iter()
+                        .filter(|tx| !tx.deleted)
+                        .flat_map(|tx| [tx.income_instrument, tx.outcome_instrument]),
+                )
+                .map(InstrumentId::into_inner)
+                .collect();
+            instruments.retain(|instrument| used.contains(&instrument.id.into_inner()));
+        }
+        let result: Vec<InstrumentResponse> = instruments
+            .iter()
+            .map(InstrumentResponse::from_instrument)
+            .collect();
+        json_result(&result)
     }
 
-    // ── resolve_sides ───────────────────────────────────────────────
+    // ── Search tools ────────────────────────────────────────────────
 
-    #[test]
-    fn resolve_sides_expense() {
-        let maps = sample_maps();
-        let params = sample_create_params(TransactionType::Expense);
-        let sides = resolve_sides(&params, &maps).expect("should resolve");
-        assert!((sides.outcome - 500.0).abs() < f64::EPSILON);
-        assert!((sides.income - 0.0).abs() < f64::EPSILON);
-        assert_eq!(sides.outcome_account.as_inner(), "acc-1");
+    /// Finds accounts by title, best match first.
+    #[tool(
+        description = "Find accounts by title. Returns {matches: [account fields + score]} ranked best first: exact (case-insensitive) matches score 1, then titles starting with the query, containing it, and close misspellings, so \"Sber\" finds \"Sberbank Card\". limit defaults to 5 (max 100); matches is empty when nothing is close",
+        output_schema = cached_schema_for_type::<FindResponse<AccountResponse>>()
+    )]
+    async fn find_account(
+        &self,
+        params: Parameters<FindAccountParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        let accounts = self.client.accounts().await.map_err(zen_err)?;
+        let matches = rank_by_title(&params.0.title, accounts, |account| &account.title)
+            .into_iter()
+            .take(find_limit(params.0.limit))
+            .map(|(score, account)| RankedMatch {
+                score,
+                entity: AccountResponse::from_account(&account, &maps),
+            })
+            .collect();
+        find_result(params.0.title, matches)
     }
 
-    #[test]
-    fn resolve_sides_income() {
-        let maps = sample_maps();
-        let params = sample_create_params(TransactionType::Income);
-        let sides = resolve_sides(&params, &maps).expect("should resolve");
-        assert!((sides.income - 500.0).abs() < f64::EPSILON);
-        assert!((sides.outcome - 0.0).abs() < f64::EPSILON);
+    /// Finds tags by title, best match first.
+    #[tool(
+        description = "Find category tags by title. Returns {matches: [tag fields + score]} ranked best first: exact (case-insensitive) matches score 1, then titles starting with the query, containing it, and close misspellings. limit defaults to 5 (max 100); matches is empty when nothing is close",
+        output_schema = cached_schema_for_type::<FindResponse<TagResponse>>()
+    )]
+    async fn find_tag(
+        &self,
+        params: Parameters<FindTagParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        let tags = self.client.tags().await.map_err(zen_err)?;
+        let matches = rank_by_title(&params.0.title, tags, |tag| &tag.title)
+            .into_iter()
+            .take(find_limit(params.0.limit))
+            .map(|(score, tag)| RankedMatch {
+                score,
+                entity: TagResponse::from_tag(&tag, &maps),
+            })
+            .collect();
+        find_result(params.0.title, matches)
     }
 
-    #[test]
-    fn resolve_sides_transfer() {
-        let maps = sample_maps();
-        let mut params = sample_create_params(TransactionType::Transfer);
-        params.to_account_id = Some("acc-2".to_owned());
-        params.to_amount = Some(7.0);
-        let sides = resolve_sides(&params, &maps).expect("should resolve");
-        assert!((sides.outcome - 500.0).abs() < f64::EPSILON);
-        assert!((sides.income - 7.0).abs() < f64::EPSILON);
-        assert_eq!(sides.income_account.as_inner(), "acc-2");
-        assert_eq!(sides.income_instrument.into_inner(), 2);
+    /// Finds merchants by title, best match first.
+    #[tool(
+        description = "Find merchants by title. Returns {matches: [merchant fields + score]} ranked best first: exact (case-insensitive) matches score 1, then titles starting with the query, containing it, and close misspellings. limit defaults to 5 (max 100); matches is empty when nothing is close",
+        output_schema = cached_schema_for_type::<FindResponse<MerchantResponse>>()
+    )]
+    async fn find_merchant(
+        &self,
+        params: Parameters<FindMerchantParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let merchants = self.client.merchants().await.map_err(zen_err)?;
+        let matches = rank_by_title(&params.0.title, merchants, |merchant| &merchant.title)
+            .into_iter()
+            .take(find_limit(params.0.limit))
+            .map(|(score, merchant)| RankedMatch {
+                score,
+                entity: MerchantResponse::from_merchant(&merchant),
+            })
+            .collect();
+        find_result(params.0.title, matches)
     }
 
-    #[test]
-    fn resolve_sides_transfer_defaults_to_amount() {
-        let maps = sample_maps();
-        let mut params = sample_create_params(TransactionType::Transfer);
-        params.to_account_id = Some("acc-2".to_owned());
-        // No to_amount — should default to amount.
-        let sides = resolve_sides(&params, &maps).expect("should resolve");
-        assert!((sides.income - 500.0).abs() < f64::EPSILON);
+    /// Finds currency instruments by code or name, best match first.
+    #[tool(
+        description = "Find currency instruments by ISO code or name fragment, such as \"USD\" or \"dollar\". Returns {matches: [instrument fields + score]} ranked best first: an exact code or name (case-insensitive) scores 1, then names starting with the query, containing it, and close misspellings. limit defaults to 5 (max 100); matches is empty when nothing is close",
+        output_schema = cached_schema_for_type::<FindResponse<InstrumentResponse>>()
+    )]
+    async fn find_instrument(
+        &self,
+        params: Parameters<FindInstrumentParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let instruments = self.client.instruments().await.map_err(zen_err)?;
+        let matches = rank_instruments(&params.0.query, instruments)
+            .into_iter()
+            .take(find_limit(params.0.limit))
+            .map(|(score, instrument)| RankedMatch {
+                score,
+                entity: InstrumentResponse::from_instrument(&instrument),
+            })
+            .collect();
+        find_result(params.0.query, matches)
     }
 
-    #[test]
-    fn resolve_sides_transfer_missing_to_account_errors() {
-        let maps = sample_maps();
-        let params = sample_create_params(TransactionType::Transfer);
-        let result = resolve_sides(&params, &maps);
-        assert!(result.is_err());
+    /// Fuzzy-matches a query against account, tag and merchant titles.
+    #[tool(
+        description = "Fuzzy search for accounts, tags and merchants by title. Tolerates typos and partial names, unlike find_account/find_tag/find_merchant. kinds limits the entity types (account, tag, merchant; default all). Returns {matches: [{kind, id, title, score}]} best first, score 1 being an exact match; limit defaults to 10 (max 100)",
+        output_schema = cached_schema_for_type::<EntitySearchResponse>()
+    )]
+    async fn search_entities(
+        &self,
+        params: Parameters<SearchEntitiesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let SearchEntitiesParams {
+            query,
+            kinds,
+            limit,
+        } = params.0;
+        if query.trim().is_empty() {
+            return Err(McpError::invalid_params("query must not be empty", None));
+        }
+        let kinds = kinds.unwrap_or_else(|| SearchKind::ALL.to_vec());
+        let limit = limit.unwrap_or(DEFAULT_REPORT_LIMIT).min(MAX_REPORT_LIMIT);
+        let candidates = self.search_candidates(&kinds).await?;
+        json_result(&EntitySearchResponse {
+            matches: rank_matches(&query, candidates, limit),
+        })
     }
 
-    // ── build_transaction ───────────────────────────────────────────
+    /// Suggests a category for a transaction.
+    #[tool(
+        description = "Suggest a category tag for a transaction based on payee name and/or comment. Note: the ZenMoney API does not provide confidence scores for suggestions",
+        output_schema = cached_schema_for_type::<SuggestResponse>()
+    )]
+    async fn suggest_category(
+        &self,
+        params: Parameters<SuggestCategoryParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        let request = SuggestRequest {
+            payee: params.0.payee,
+            comment: params.0.comment,
+        };
+        self.throttle.pace().await;
+        let response = self.client.suggest(&request).await.map_err(zen_err)?;
+        let result = SuggestResponse::from_suggest(&response, &maps);
+        json_result(&result)
+    }
 
-    #[test]
-    fn build_transaction_expense_with_optional_fields() {
-        let maps = sample_maps();
-        let mut params = sample_create_params(TransactionType::Expense);
-        params.tag_ids = Some(vec!["tag-1".to_owned()]);
-        params.payee = Some("Coffee Shop".to_owned());
-        params.comment = Some("Morning coffee".to_owned());
+    /// Gets a tag's full details with usage statistics.
+    #[tool(
+        description = "Get a category tag by ID with full details (icon, color, archive), direct child tags, whether it has any non-zero budget, and usage stats: transaction_count, last_used, and expense spend over the last 3 and 12 months. Set convert_to to an instrument ID to sum spend in one currency",
+        output_schema = cached_schema_for_type::<TagDetailsResponse>()
+    )]
+    async fn get_tag(&self, params: Parameters<GetTagParams>) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        validate_convert_to(&maps, params.0.convert_to)?;
+        let tags = self.client.tags().await.map_err(zen_err)?;
+        let tag = tags
+            .iter()
+            .find(|tag| tag.id.as_inner() == params.0.tag_id.as_str())
+            .ok_or_else(|| not_found("tag", &params.0.tag_id))?;
+        let children: Vec<TagResponse> = tags
+            .iter()
+            .filter(|child| child.parent.as_ref() == Some(&tag.id))
+            .map(|child| TagResponse::from_tag(child, &maps))
+            .collect();
+        let budgets = self.client.budgets().await.map_err(zen_err)?;
+        let budgeted = budgets.iter().any(|budget| {
+            budget.tag.as_ref() == Some(&tag.id)
+                && (budget.income > 0.0_f64 || budget.outcome > 0.0_f64)
+        });
+        let transactions = self
+            .filtered_transactions(
+                &ListTransactionsParams {
+                    tag_id: Some(params.0.tag_id.clone()),
+                    ..Default::default()
+                },
+                &maps,
+            )
+            .await?;
+        let usage = tag_usage(
+            &transactions,
+            Utc::now().date_naive(),
+            &maps,
+            params.0.convert_to,
+        );
 
-        let tx = build_transaction(params, &maps).expect("should build");
-        assert!((tx.outcome - 500.0).abs() < f64::EPSILON);
-        assert!((tx.income - 0.0).abs() < f64::EPSILON);
-        assert_eq!(tx.tag.as_ref().expect("should have tags").len(), 1);
-        assert_eq!(tx.payee.as_deref(), Some("Coffee Shop"));
-        assert_eq!(tx.comment.as_deref(), Some("Morning coffee"));
-        assert_eq!(tx.date, test_date());
+        json_result(&TagDetailsResponse {
+            tag: TagResponse::from_tag(tag, &maps),
+            icon: tag.icon.clone(),
+            color: tag.color,
+            archive: tag.archive.unwrap_or(false),
+            budgeted,
+            children,
+            usage,
+        })
     }
 
-    #[test]
-    fn build_transaction_income_minimal() {
-        let maps = sample_maps();
-        let params = sample_create_params(TransactionType::Income);
-        let tx = build_transaction(params, &maps).expect("should build");
-        assert!((tx.income - 500.0).abs() < f64::EPSILON);
-        assert!((tx.outcome - 0.0).abs() < f64::EPSILON);
-        assert!(tx.tag.is_none());
-        assert!(tx.payee.is_none());
+    /// Gets a specific instrument by ID.
+    #[tool(
+        description = "Get a specific currency instrument by its numeric ID. An unknown ID fails with an invalid-params error whose data is {code: \"not_found\", kind, query}"
+    )]
+    async fn get_instrument(
+        &self,
+        params: Parameters<GetInstrumentParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let instrument = self
+            .client
+            .instrument(InstrumentId::new(params.0.id))
+            .await
+            .map_err(zen_err)?;
+        let instr = instrument.ok_or_else(|| not_found("instrument", params.0.id))?;
+        json_result(&InstrumentResponse::from_instrument(&instr))
     }
 
-    #[test]
-    fn build_transaction_invalid_date_errors() {
-        let maps = sample_maps();
-        let mut params = sample_create_params(TransactionType::Expense);
-        params.date = "not-a-date".to_owned();
-        let result = build_transaction(params, &maps);
-        assert!(result.is_err());
+    /// Returns an entity exactly as ZenMoney stores it.
+    #[tool(
+        description = "Get the complete, untransformed JSON of a transaction, account, tag or reminder by kind and ID, exactly as stored from ZenMoney. Includes the fields other tools drop (op_income/op_outcome, bank IDs, source, changed timestamps, etc.) and uses ZenMoney's own field names. Meant for debugging sync issues; prefer the regular tools for everyday use"
+    )]
+    async fn get_raw_entity(
+        &self,
+        params: Parameters<GetRawEntityParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let GetRawEntityParams { kind, id } = params.0;
+        let raw = match kind {
+            RawEntityKind::Transaction => {
+                let transactions = self.client.transactions().await.map_err(zen_err)?;
+                raw_json(&transactions, &id, |tx| &tx.id)?
+            }
+            RawEntityKind::Account => {
+                let accounts = self.client.accounts().await.map_err(zen_err)?;
+                raw_json(&accounts, &id, |account| &account.id)?
+            }
+            RawEntityKind::Tag => {
+                let tags = self.client.tags().await.map_err(zen_err)?;
+                raw_json(&tags, &id, |tag| &tag.id)?
+            }
+            RawEntityKind::Reminder => {
+                let reminders = self.client.reminders().await.map_err(zen_err)?;
+                raw_json(&reminders, &id, |reminder| &reminder.id)?
+            }
+        };
+        let entity = raw.ok_or_else(|| not_found("entity", &id))?;
+        json_result(&entity)
     }
 
-    // ── apply_update ────────────────────────────────────────────────
+    /// Converts an amount between two currency instruments.
+    #[tool(
+        description = "Convert an amount between two currency instruments (by ID) using the stored exchange rates. Returns the converted amount, the rate applied, and the timestamp of the rates used",
+        output_schema = cached_schema_for_type::<ConversionResponse>()
+    )]
+    async fn convert_amount(
+        &self,
+        params: Parameters<ConvertAmountParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let from = self
+            .client
+            .instrument(InstrumentId::new(params.0.from_instrument_id))
+            .await
+            .map_err(zen_err)?
+            .ok_or_else(|| not_found("instrument", params.0.from_instrument_id))?;
+        let to = self
+            .client
+            .instrument(InstrumentId::new(params.0.to_instrument_id))
+            .await
+            .map_err(zen_err)?
+            .ok_or_else(|| not_found("instrument", params.0.to_instrument_id))?;
+        let result =
+            ConversionResponse::from_instruments(params.0.amount, &from, &to).ok_or_else(|| {
+                McpError::invalid_params(
+                    format!(
+                        "instrument {} has no usable rate",
+                        params.0.to_instrument_id
+                    ),
+                    None,
+                )
+            })?;
+        json_result(&result)
+    }
 
-    #[test]
-    fn apply_update_date() {
-        let maps = sample_maps();
-        let mut tx = sample_transaction("tx-1", 500.0, 0.0);
-        let params = UpdateTransactionParams {
-            id: "tx-1".to_owned(),
-            date: Some("2025-01-01".to_owned()),
-            amount: None,
-            to_amount: None,
-            account_id: None,
-            to_account_id: None,
-            tag_ids: None,
-            payee: None,
-            comment: None,
-        };
-        apply_update(&mut tx, params, &maps).expect("should update");
-        assert_eq!(tx.date, NaiveDate::from_ymd_opt(2025, 1, 1).expect("valid"));
+    // ── Report tools ────────────────────────────────────────────────
+
+    /// Ranks payees or merchants by total spend or transaction count.
+    #[tool(
+        description = "Report the payees (or merchants, by=merchant) with the highest spending over an optional date range. rank_by: amount (default) or count. limit defaults to 10 (max 100). Each entry includes a per-category breakdown (include_children=true rolls child tags into their parent). Set convert_to to an instrument ID to sum amounts in one currency. Only expenses are counted; transactions without a payee/merchant are skipped. Returns {items, total_spend, transaction_count}",
+        output_schema = cached_schema_for_type::<TopPayeesResponse>()
+    )]
+    async fn top_payees(
+        &self,
+        params: Parameters<TopPayeesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        validate_convert_to(&maps, params.0.convert_to)?;
+        let mut transactions = self
+            .filtered_transactions(
+                &ListTransactionsParams {
+                    date_from: params.0.date_from.clone(),
+                    date_to: params.0.date_to.clone(),
+                    transaction_type: Some(TransactionType::Expense),
+                    ..Default::default()
+                },
+                &maps,
+            )
+            .await?;
+        if params.0.include_children == Some(true) {
+            roll_up_tags(&mut transactions, &maps);
+        }
+
+        let total_spend: f64 = transactions
+            .iter()
+            .map(|tx| expense_amount(tx, &maps, params.0.convert_to))
+            .sum();
+        let mut items = payee_spend(
+            &transactions,
+            params.0.by.unwrap_or_default(),
+            &maps,
+            params.0.convert_to,
+        );
+        rank_payees(&mut items, params.0.rank_by.unwrap_or_default());
+        items.truncate(
+            params
+                .0
+                .limit
+                .unwrap_or(DEFAULT_REPORT_LIMIT)
+                .min(MAX_REPORT_LIMIT),
+        );
+
+        json_result(&TopPayeesResponse {
+            items,
+            total_spend,
+            transaction_count: transactions.len(),
+        })
     }
 
-    #[test]
-    fn apply_update_payee_empty_clears() {
-        let maps = sample_maps();
-        let mut tx = sample_transaction("tx-1", 500.0, 0.0);
-        tx.payee = Some("Old Payee".to_owned());
-        let params = UpdateTransactionParams {
-            id: "tx-1".to_owned(),
-            date: None,
-            amount: None,
-            to_amount: None,
-            account_id: None,
-            to_account_id: None,
-            tag_ids: None,
-            payee: Some(String::new()),
-            comment: None,
-        };
-        apply_update(&mut tx, params, &maps).expect("should update");
-        assert!(tx.payee.is_none());
+    /// Compares per-category totals between two date ranges.
+    #[tool(
+        description = "Compare per-category totals between two date ranges (base_from/base_to vs compare_from/compare_to, YYYY-MM-DD). transaction_type selects expense (default) or income totals. Returns each category's base and compare totals with absolute delta and delta_percent, overall totals, and largest_increases (top 5 categories that grew the most). Set include_children=true to roll child tags up into their parent category. Set convert_to to an instrument ID to sum amounts in one currency",
+        output_schema = cached_schema_for_type::<ComparePeriodsResponse>()
+    )]
+    async fn compare_periods(
+        &self,
+        params: Parameters<ComparePeriodsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        validate_convert_to(&maps, params.0.convert_to)?;
+        let kind = params
+            .0
+            .transaction_type
+            .clone()
+            .unwrap_or(TransactionType::Expense);
+
+        let mut base = self
+            .filtered_transactions(
+                &ListTransactionsParams {
+                    date_from: Some(params.0.base_from.clone()),
+                    date_to: Some(params.0.base_to.clone()),
+                    transaction_type: Some(kind.clone()),
+                    ..Default::default()
+                },
+                &maps,
+            )
+            .await?;
+        let mut compare = self
+            .filtered_transactions(
+                &ListTransactionsParams {
+                    date_from: Some(params.0.compare_from.clone()),
+                    date_to: Some(params.0.compare_to.clone()),
+                    transaction_type: Some(kind.clone()),
+                    ..Default::default()
+                },
+                &maps,
+            )
+            .await?;
+        if params.0.include_children == Some(true) {
+            roll_up_tags(&mut base, &maps);
+            roll_up_tags(&mut compare, &maps);
+        }
+
+        let categories = compare_categories(
+            &group_transactions(&base, GroupBy::Tag, &maps, params.0.convert_to),
+            &group_transactions(&compare, GroupBy::Tag, &maps, params.0.convert_to),
+            &kind,
+        );
+        let base_total: f64 = categories.iter().map(|category| category.base).sum();
+        let compare_total: f64 = categories.iter().map(|category| category.compare).sum();
+        let delta = compare_total - base_total;
+        let largest_increases: Vec<CategoryDelta> = categories
+            .iter()
+            .filter(|category| category.delta > 0.0_f64)
+            .take(LARGEST_INCREASES_LIMIT)
+            .cloned()
+            .collect();
+
+        json_result(&ComparePeriodsResponse {
+            categories,
+            base_total,
+            compare_total,
+            delta,
+            delta_percent: percent_change(base_total, delta),
+            largest_increases,
+        })
     }
 
-    #[test]
-    fn apply_update_comment_empty_clears() {
-        let maps = sample_maps();
-        let mut tx = sample_transaction("tx-1", 500.0, 0.0);
-        tx.comment = Some("Old comment".to_owned());
-        let params = UpdateTransactionParams {
-            id: "tx-1".to_owned(),
-            date: None,
-            amount: None,
-            to_amount: None,
-            account_id: None,
-            to_account_id: None,
-            tag_ids: None,
-            payee: None,
-            comment: Some(String::new()),
+    /// Reconstructs historical balances of an account from its transactions.
+    #[tool(
+        description = "Reconstruct end-of-day (granularity=day, default, max 366 days) or end-of-month (granularity=month) balances for an account between date_from and date_to (default today), walking transactions backwards from the current stored balance. Returns {account_id, account, currency, current_balance, points: [{date, balance}], lowest}",
+        output_schema = cached_schema_for_type::<BalanceHistoryResponse>()
+    )]
+    async fn account_balance_history(
+        &self,
+        params: Parameters<AccountBalanceHistoryParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let from = parse_date(&params.0.date_from)?;
+        let to = match params.0.date_to.as_deref() {
+            Some(date_to) => parse_date(date_to)?,
+            None => Utc::now().date_naive(),
         };
-        apply_update(&mut tx, params, &maps).expect("should update");
-        assert!(tx.comment.is_none());
+        if from > to {
+            return Err(McpError::invalid_params(
+                "date_from must not be after date_to",
+                None,
+            ));
+        }
+        let granularity = params.0.granularity.unwrap_or_default();
+        if granularity == Granularity::Day && (to - from).num_days() >= MAX_DAILY_HISTORY_DAYS {
+            return Err(McpError::invalid_params(
+                format!(
+                    "daily history is limited to {MAX_DAILY_HISTORY_DAYS} days; use granularity=month"
+                ),
+                None,
+            ));
+        }
+
+        let maps = self.lookup_maps().await?;
+        let accounts = self.client.accounts().await.map_err(zen_err)?;
+        let account = accounts
+            .iter()
+            .find(|acc| acc.id.as_inner() == params.0.account_id.as_str())
+            .ok_or_else(|| not_found("account", &params.0.account_id))?;
+        let current_balance = account.balance.unwrap_or_default();
+
+        // Everything from `from` onwards, including transactions after `to`,
+        // is needed to walk back from the current balance.
+        let transactions = self
+            .filtered_transactions(
+                &ListTransactionsParams {
+                    date_from: Some(params.0.date_from.clone()),
+                    account_id: Some(params.0.account_id.clone()),
+                    ..Default::default()
+                },
+                &maps,
+            )
+            .await?;
+        let net = daily_net_changes(&transactions, &params.0.account_id);
+        let points = balance_history(&net, current_balance, from, to, granularity);
+        let lowest = points
+            .iter()
+            .min_by(|left, right| left.balance.total_cmp(&right.balance))
+            .cloned();
+
+        json_result(&BalanceHistoryResponse {
+            account_id: params.0.account_id.clone(),
+            account: account.title.clone(),
+            currency: account
+                .instrument
+                .map(|id| maps.instrument_symbol(id.into_inner()))
+                .unwrap_or_default(),
+            current_balance,
+            points,
+            lowest,
+        })
     }
 
-    #[test]
-    fn apply_update_tag_ids() {
-        let maps = sample_maps();
-        let mut tx = sample_transaction("tx-1", 500.0, 0.0);
-        let params = UpdateTransactionParams {
-            id: "tx-1".to_owned(),
-            date: None,
-            amount: None,
-            to_amount: None,
-            account_id: None,
-            to_account_id: None,
-            tag_ids: Some(vec!["tag-1".to_owned(), "tag-2".to_owned()]),
-            payee: None,
-            comment: None,
+    /// Flags expenses that are unusually large for their category or payee.
+    #[tool(
+        description = "Flag expenses in a date range (default: last 30 days) that are unusually large compared with the full local history of their category and payee, using a robust modified z-score (median/MAD). threshold defaults to 3.5; baselines need at least 5 samples. Returns {items: [{transaction, score, reasons}], scanned, flagged}, most unusual first; limit defaults to 10 (max 100). Set convert_to to compare amounts in one currency",
+        output_schema = cached_schema_for_type::<AnomaliesResponse>()
+    )]
+    async fn detect_anomalies(
+        &self,
+        params: Parameters<DetectAnomaliesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        validate_convert_to(&maps, params.0.convert_to)?;
+        let threshold = params.0.threshold.unwrap_or(DEFAULT_ANOMALY_THRESHOLD);
+        if threshold <= 0.0_f64 {
+            return Err(McpError::invalid_params("threshold must be positive", None));
+        }
+        let date_from = match params.0.date_from.as_deref() {
+            Some(date_from) => parse_date(date_from)?,
+            None => Utc::now()
+                .date_naive()
+                .checked_sub_days(Days::new(DEFAULT_ANOMALY_WINDOW_DAYS))
+                .unwrap_or(NaiveDate::MIN),
         };
-        apply_update(&mut tx, params, &maps).expect("should update");
-        let tags = tx.tag.expect("should have tags");
-        assert_eq!(tags.len(), 2);
+        let date_to = params.0.date_to.as_deref().map(parse_date).transpose()?;
+
+        let history = self
+            .filtered_transactions(
+                &ListTransactionsParams {
+                    transaction_type: Some(TransactionType::Expense),
+                    ..Default::default()
+                },
+                &maps,
+            )
+            .await?;
+        let candidates: Vec<Transaction> = history
+            .iter()
+            .filter(|tx| tx.date >= date_from && date_to.is_none_or(|to| tx.date <= to))
+            .cloned()
+            .collect();
+
+        let anomalies = analytics::detect_anomalies(
+            &history,
+            &candidates,
+            &maps,
+            threshold,
+            params.0.convert_to,
+        );
+        let flagged = anomalies.len();
+        let items: Vec<AnomalyResponse> = anomalies
+            .into_iter()
+            .take(
+                params
+                    .0
+                    .limit
+                    .unwrap_or(DEFAULT_REPORT_LIMIT)
+                    .min(MAX_REPORT_LIMIT),
+            )
+            .map(|anomaly| AnomalyResponse {
+                transaction: transaction_response(anomaly.tx, &maps, params.0.convert_to),
+                score: anomaly.score,
+                reasons: anomaly.reasons,
+            })
+            .collect();
+
+        json_result(&AnomaliesResponse {
+            items,
+            scanned: candidates.len(),
+            flagged,
+        })
     }
 
-    #[test]
-    fn apply_update_amount_on_expense() {
-        let maps = sample_maps();
-        let mut tx = sample_transaction("tx-1", 500.0, 0.0);
-        let params = UpdateTransactionParams {
-            id: "tx-1".to_owned(),
-            date: None,
-            amount: Some(750.0),
-            to_amount: None,
-            account_id: None,
-            to_account_id: None,
-            tag_ids: None,
-            payee: None,
-            comment: None,
-        };
-        apply_update(&mut tx, params, &maps).expect("should update");
-        assert!((tx.outcome - 750.0).abs() < f64::EPSILON);
+    /// Summarizes untagged transactions by payee as a categorization worklist.
+    #[tool(
+        description = "Group untagged expense and income transactions (transfers excluded) by payee, largest turnover first, with count and income/outcome totals per payee. For each returned payee, suggested_tags comes from the ZenMoney suggest API (set suggest=false to skip). limit defaults to 10 (max 100). Returns {items: [{payee, count, income, outcome, suggested_tags}], total_transactions, total_payees}",
+        output_schema = cached_schema_for_type::<UncategorizedReportResponse>()
+    )]
+    async fn uncategorized_report(
+        &self,
+        params: Parameters<UncategorizedReportParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        validate_convert_to(&maps, params.0.convert_to)?;
+        let mut transactions = self
+            .filtered_transactions(
+                &ListTransactionsParams {
+                    date_from: params.0.date_from.clone(),
+                    date_to: params.0.date_to.clone(),
+                    uncategorized: Some(true),
+                    ..Default::default()
+                },
+                &maps,
+            )
+            .await?;
+        transactions.retain(|tx| !matches!(classify_transaction(tx), TransactionType::Transfer));
+
+        let groups = group_transactions(&transactions, GroupBy::Payee, &maps, params.0.convert_to);
+        let total_payees = groups.len();
+        let limit = params
+            .0
+            .limit
+            .unwrap_or(DEFAULT_REPORT_LIMIT)
+            .min(MAX_REPORT_LIMIT);
+        let suggest = params.0.suggest.unwrap_or(true);
+
+        let mut items: Vec<UncategorizedPayee> = Vec::with_capacity(limit.min(total_payees));
+        for group in groups.into_iter().take(limit) {
+            let suggested_tags = if suggest && !group.key.is_empty() {
+                self.suggested_tag_names(&group.key, &maps).await
+            } else {
+                Vec::new()
+            };
+            items.push(UncategorizedPayee {
+                payee: group.label,
+                count: group.count,
+                income: group.income,
+                outcome: group.outcome,
+                suggested_tags,
+            });
+        }
+
+        json_result(&UncategorizedReportResponse {
+            items,
+            total_transactions: transactions.len(),
+            total_payees,
+        })
     }
 
-    #[test]
-    fn apply_update_account_on_transfer() {
-        let maps = sample_maps();
-        let mut tx = sample_transfer("tx-1", 500.0, 500.0);
-        let params = UpdateTransactionParams {
-            id: "tx-1".to_owned(),
-            date: None,
-            amount: None,
-            to_amount: None,
-            account_id: Some("acc-2".to_owned()),
-            to_account_id: None,
-            tag_ids: None,
-            payee: None,
-            comment: None,
+    /// Summarizes personal debts per counterparty from Debt-type accounts.
+    #[tool(
+        description = "Summarize personal debts tracked through Debt-type accounts, grouped by payee (counterparty) and currency. Money moved into a debt account counts as lent, money moved out as received; balance > 0 means they owe you, < 0 means you owe them. Set date_to for balances as of a date, include_settled=true to also list zero balances, and convert_to to an instrument ID to convert amounts and get total_owed_to_you/total_you_owe. Returns {items: [{payee, currency, balance, lent, received, count, last_date, status}]}",
+        output_schema = cached_schema_for_type::<DebtsReportResponse>()
+    )]
+    async fn debts_report(
+        &self,
+        params: Parameters<DebtsReportParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        validate_convert_to(&maps, params.0.convert_to)?;
+        let accounts = self.client.accounts().await.map_err(zen_err)?;
+        let debt_accounts: HashSet<String> = accounts
+            .iter()
+            .filter(|acc| matches!(acc.kind, zenmoney_rs::models::AccountType::Debt))
+            .map(|acc| acc.id.to_string())
+            .collect();
+        let transactions = self
+            .filtered_transactions(
+                &ListTransactionsParams {
+                    date_to: params.0.date_to.clone(),
+                    ..Default::default()
+                },
+                &maps,
+            )
+            .await?;
+
+        let mut items = debt_balances(&transactions, &debt_accounts, &maps, params.0.convert_to);
+        if params.0.include_settled != Some(true) {
+            items.retain(|item| item.status != "settled");
+        }
+        let (total_owed_to_you, total_you_owe) = if params.0.convert_to.is_some() {
+            (
+                Some(items.iter().map(|item| item.balance.max(0.0)).sum()),
+                Some(items.iter().map(|item| (-item.balance).max(0.0)).sum()),
+            )
+        } else {
+            (None, None)
         };
-        apply_update(&mut tx, params, &maps).expect("should update");
-        assert_eq!(tx.outcome_account.as_inner(), "acc-2");
-        assert_eq!(tx.outcome_instrument.into_inner(), 2);
+
+        json_result(&DebtsReportResponse {
+            items,
+            total_owed_to_you,
+            total_you_owe,
+        })
     }
 
-    #[test]
-    fn apply_update_comment_sets_value() {
-        let maps = sample_maps();
-        let mut tx = sample_transaction("tx-1", 500.0, 0.0);
-        let params = UpdateTransactionParams {
-            id: "tx-1".to_owned(),
-            date: None,
-            amount: None,
-            to_amount: None,
-            account_id: None,
-            to_account_id: None,
-            tag_ids: None,
-            payee: None,
-            comment: Some("New comment".to_owned()),
+    /// Projects the amortization or maturity schedule of a loan or deposit.
+    #[tool(
+        description = "Project the payment schedule of a Loan or Deposit account from its stored terms (percent, capitalization, start_date, term, payoff_step/payoff_interval). Loans get an equal-payment amortization of the outstanding balance; deposits get interest accruals, compounded when capitalization is on, plus maturity_amount. Returns next_payment_date, next_payment, remaining_periods, total_interest and schedule: [{date, payment, interest, principal, balance}]; limit defaults to 12 periods (max 360). This is an estimate, not the bank's exact figures",
+        output_schema = cached_schema_for_type::<LoanScheduleResponse>()
+    )]
+    async fn loan_schedule(
+        &self,
+        params: Parameters<LoanScheduleParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let limit = params
+            .0
+            .limit
+            .unwrap_or(DEFAULT_SCHEDULE_LIMIT)
+            .min(MAX_SCHEDULE_LIMIT);
+        let maps = self.lookup_maps().await?;
+        let accounts = self.client.accounts().await.map_err(zen_err)?;
+        let account = accounts
+            .iter()
+            .find(|acc| acc.id.as_inner() == params.0.account_id.as_str())
+            .ok_or_else(|| not_found("account", &params.0.account_id))?;
+        let is_loan = match account.kind {
+            AccountType::Loan => true,
+            AccountType::Deposit => false,
+            AccountType::Cash
+            | AccountType::CreditCard
+            | AccountType::Checking
+            | AccountType::EMoney
+            | AccountType::Debt => {
+                return Err(McpError::invalid_params(
+                    format!(
+                        "account {} is a {} account; loan_schedule needs a Loan or Deposit",
+                        params.0.account_id,
+                        account_type_label(account.kind)
+                    ),
+                    None,
+                ));
+            }
         };
-        apply_update(&mut tx, params, &maps).expect("should update");
-        assert_eq!(tx.comment.as_deref(), Some("New comment"));
-    }
 
-    #[test]
-    fn apply_update_account_on_expense() {
-        let maps = sample_maps();
-        let mut tx = sample_transaction("tx-1", 500.0, 0.0);
-        let params = UpdateTransactionParams {
-            id: "tx-1".to_owned(),
-            date: None,
-            amount: None,
-            to_amount: None,
-            account_id: Some("acc-2".to_owned()),
-            to_account_id: None,
-            tag_ids: None,
-            payee: None,
-            comment: None,
+        let start = account.start_date.ok_or_else(|| {
+            McpError::invalid_params("account has no start_date; set its terms in ZenMoney", None)
+        })?;
+        let (term, term_interval) = account
+            .end_date_offset
+            .zip(account.end_date_offset_interval)
+            .and_then(|(offset, interval)| u32::try_from(offset).ok().map(|term| (term, interval)))
+            .filter(|&(term, _)| term > 0)
+            .ok_or_else(|| {
+                McpError::invalid_params("account has no term; set its end date in ZenMoney", None)
+            })?;
+        let end = add_intervals(start, term_interval, term)
+            .ok_or_else(|| McpError::invalid_params("account term is out of range", None))?;
+        let step = account
+            .payoff_step
+            .zip(account.payoff_interval)
+            .and_then(|(step, interval)| u32::try_from(step).ok().map(|every| (interval, every)))
+            .filter(|&(_, every)| every > 0);
+
+        let percent = account.percent.unwrap_or_default();
+        let capitalization = account.capitalization.unwrap_or(false);
+        // Without a payoff period the whole term is a single period.
+        let rate = step.map_or_else(
+            || period_rate(percent, term_interval, term),
+            |(interval, every)| period_rate(percent, interval, every),
+        );
+        let dates = period_dates(start, end, step, Utc::now().date_naive());
+        let balance = account.balance.unwrap_or_default();
+        let (schedule, maturity_amount) = if is_loan {
+            (amortization_schedule(balance.abs(), rate, &dates), None)
+        } else {
+            let rows = deposit_schedule(balance, rate, capitalization, &dates);
+            let maturity = rows.last().map_or(balance, |row| row.balance);
+            (rows, Some(maturity))
         };
-        apply_update(&mut tx, params, &maps).expect("should update");
-        assert_eq!(tx.outcome_account.as_inner(), "acc-2");
-        assert_eq!(tx.income_account.as_inner(), "acc-2");
-        assert_eq!(tx.outcome_instrument.into_inner(), 2);
-        assert_eq!(tx.income_instrument.into_inner(), 2);
+        let next = schedule.first();
+
+        json_result(&LoanScheduleResponse {
+            account_id: params.0.account_id.clone(),
+            account: account.title.clone(),
+            account_type: account_type_label(account.kind).to_owned(),
+            currency: account
+                .instrument
+                .map(|id| maps.instrument_symbol(id.into_inner()))
+                .unwrap_or_default(),
+            balance: if is_loan { balance.abs() } else { balance },
+            percent,
+            capitalization,
+            start_date: start.to_string(),
+            end_date: end.to_string(),
+            payoff_period: step
+                .map(|(interval, every)| format!("{every} {}", interval_label(interval))),
+            next_payment_date: next.map(|row| row.date.clone()),
+            next_payment: next.map(|row| row.payment),
+            remaining_periods: schedule.len(),
+            total_interest: schedule.iter().map(|row| row.interest).sum(),
+            maturity_amount,
+            schedule: schedule.into_iter().take(limit).collect(),
+        })
     }
 
-    #[test]
-    fn apply_update_account_on_income() {
-        let maps = sample_maps();
-        let mut tx = sample_transaction("tx-1", 0.0, 1000.0);
-        let params = UpdateTransactionParams {
-            id: "tx-1".to_owned(),
-            date: None,
-            amount: None,
-            to_amount: None,
-            account_id: Some("acc-2".to_owned()),
-            to_account_id: None,
-            tag_ids: None,
-            payee: None,
-            comment: None,
-        };
-        apply_update(&mut tx, params, &maps).expect("should update");
-        assert_eq!(tx.income_account.as_inner(), "acc-2");
-        assert_eq!(tx.outcome_account.as_inner(), "acc-2");
-        assert_eq!(tx.income_instrument.into_inner(), 2);
-        assert_eq!(tx.outcome_instrument.into_inner(), 2);
-    }
+    /// Summarizes a credit card statement cycle.
+    #[tool(
+        description = "Summarize a credit card account by statement cycle instead of calendar month. Give account_id and closing_day (1-31, clamped to short months); cycle is current (default, contains today) or previous. Returns {cycle_start, cycle_end, days_left, spent, credited, transaction_count, top_categories, balance, credit_limit, available_credit, utilization_percent}; amounts are in the card's currency",
+        output_schema = cached_schema_for_type::<CardStatementResponse>()
+    )]
+    async fn card_statement(
+        &self,
+        params: Parameters<CardStatementParams>,
+    ) -> Result<CallToolResult, McpError> {
+        if !(1..=31).contains(&params.0.closing_day) {
+            return Err(McpError::invalid_params(
+                "closing_day must be between 1 and 31",
+                None,
+            ));
+        }
+        let cycle = params.0.cycle.unwrap_or_default();
+        let today = Utc::now().date_naive();
+        let (cycle_start, cycle_end) = statement_period(params.0.closing_day, today, cycle)
+            .ok_or_else(|| McpError::internal_error("failed to compute statement cycle", None))?;
 
-    #[test]
-    fn apply_update_to_account_id() {
-        let maps = sample_maps();
-        let mut tx = sample_transfer("tx-1", 500.0, 500.0);
-        let params = UpdateTransactionParams {
-            id: "tx-1".to_owned(),
-            date: None,
-            amount: None,
-            to_amount: None,
-            account_id: None,
-            to_account_id: Some("acc-1".to_owned()),
-            tag_ids: None,
-            payee: None,
-            comment: None,
-        };
-        apply_update(&mut tx, params, &maps).expect("should update");
-        assert_eq!(tx.income_account.as_inner(), "acc-1");
-        assert_eq!(tx.income_instrument.into_inner(), 1);
-    }
+        let maps = self.lookup_maps().await?;
+        let accounts = self.client.accounts().await.map_err(zen_err)?;
+        let account = accounts
+            .iter()
+            .find(|acc| acc.id.as_inner() == params.0.account_id.as_str())
+            .ok_or_else(|| not_found("account", &params.0.account_id))?;
+        if !matches!(account.kind, AccountType::CreditCard) {
+            return Err(McpError::invalid_params(
+                format!(
+                    "account {} is a {} account; card_statement needs a CreditCard account",
+                    params.0.account_id,
+                    account_type_label(account.kind)
+                ),
+                None,
+            ));
+        }
 
-    #[test]
-    fn apply_update_amount_on_income() {
-        let maps = sample_maps();
-        let mut tx = sample_transaction("tx-1", 0.0, 1000.0);
-        let params = UpdateTransactionParams {
-            id: "tx-1".to_owned(),
-            date: None,
-            amount: Some(2000.0),
-            to_amount: None,
-            account_id: None,
-            to_account_id: None,
-            tag_ids: None,
-            payee: None,
-            comment: None,
-        };
-        apply_update(&mut tx, params, &maps).expect("should update");
-        assert!((tx.income - 2000.0).abs() < f64::EPSILON);
+        let transactions = self
+            .filtered_transactions(
+                &ListTransactionsParams {
+                    date_from: Some(cycle_start.to_string()),
+                    date_to: Some(cycle_end.to_string()),
+                    account_id: Some(params.0.account_id.clone()),
+                    ..Default::default()
+                },
+                &maps,
+            )
+            .await?;
+        let account_id = params.0.account_id.as_str();
+        let spent: f64 = transactions
+            .iter()
+            .filter(|tx| tx.outcome_account.as_inner() == account_id)
+            .map(|tx| tx.outcome)
+            .sum();
+        let credited: f64 = transactions
+            .iter()
+            .filter(|tx| tx.income_account.as_inner() == account_id)
+            .map(|tx| tx.income)
+            .sum();
+        let expenses: Vec<Transaction> = transactions
+            .iter()
+            .filter(|tx| matches!(classify_transaction(tx), TransactionType::Expense))
+            .cloned()
+            .collect();
+        let mut top_categories = group_transactions(&expenses, GroupBy::Tag, &maps, None);
+        top_categories.truncate(TOP_CATEGORIES_LIMIT);
+
+        let balance = account.balance.unwrap_or_default();
+        let credit_limit = account.credit_limit.filter(|limit| *limit > 0.0_f64);
+        json_result(&CardStatementResponse {
+            account_id: params.0.account_id.clone(),
+            account: account.title.clone(),
+            currency: account
+                .instrument
+                .map(|id| maps.instrument_symbol(id.into_inner()))
+                .unwrap_or_default(),
+            cycle_start: cycle_start.to_string(),
+            cycle_end: cycle_end.to_string(),
+            days_left: (cycle == StatementCycle::Current).then(|| (cycle_end - today).num_days()),
+            spent,
+            credited,
+            transaction_count: transactions.len(),
+            top_categories,
+            balance,
+            credit_limit,
+            available_credit: credit_limit.map(|limit| limit + balance),
+            utilization_percent: credit_limit
+                .map(|limit| (-balance).max(0.0_f64) / limit * 100.0_f64),
+        })
     }
 
-    #[test]
-    fn apply_update_to_amount() {
-        let maps = sample_maps();
-        let mut tx = sample_transfer("tx-1", 500.0, 500.0);
-        let params = UpdateTransactionParams {
-            id: "tx-1".to_owned(),
-            date: None,
-            amount: None,
-            to_amount: Some(750.0),
-            account_id: None,
-            to_account_id: None,
-            tag_ids: None,
-            payee: None,
-            comment: None,
-        };
-        apply_update(&mut tx, params, &maps).expect("should update");
-        assert!((tx.income - 750.0).abs() < f64::EPSILON);
+    /// Lists budgeting envelopes.
+    #[tool(
+        description = "List envelopes: local budgeting pots mapped to category tags, with their tag_ids and allocations per month (YYYY-MM). Use envelope_status for balances"
+    )]
+    async fn list_envelopes(&self) -> Result<CallToolResult, McpError> {
+        let envelopes = self.envelopes.list().map_err(io_err)?;
+        json_result(&envelopes)
     }
 
-    // ── process_bulk_operations ─────────────────────────────────────
+    /// Reports envelope balances for a month.
+    #[tool(
+        description = "Show envelope balances for a month (YYYY-MM, default current). Unspent money rolls over between months: available = carried_over + allocated - spent, where spent counts expenses in the envelope's tags and their children (each expense is charged to the first matching envelope). Also returns the month's income and to_be_budgeted (income not yet allocated). Set convert_to to an instrument ID to sum amounts in one currency",
+        output_schema = cached_schema_for_type::<EnvelopeStatusResponse>()
+    )]
+    async fn envelope_status(
+        &self,
+        params: Parameters<EnvelopeStatusParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        validate_convert_to(&maps, params.0.convert_to)?;
+        let month = parse_month(params.0.month.as_deref())?;
+        let month_key = month.format("%Y-%m").to_string();
+        let month_end = month
+            .checked_add_months(Months::new(1))
+            .and_then(|next| next.pred_opt())
+            .unwrap_or(month);
+        let envelopes = self.envelopes.list().map_err(io_err)?;
+        // Rollover needs spending since the earliest allocation.
+        let first_month = envelopes
+            .iter()
+            .filter_map(|envelope| envelope.allocations.keys().next())
+            .min()
+            .filter(|first| **first < month_key)
+            .unwrap_or(&month_key);
+
+        let transactions = self
+            .filtered_transactions(
+                &ListTransactionsParams {
+                    date_from: Some(format!("{first_month}-01")),
+                    date_to: Some(month_end.to_string()),
+                    ..Default::default()
+                },
+                &maps,
+            )
+            .await?;
+        let expenses: Vec<Transaction> = transactions
+            .iter()
+            .filter(|tx| matches!(classify_transaction(tx), TransactionType::Expense))
+            .cloned()
+            .collect();
+        let income: f64 = transactions
+            .iter()
+            .filter(|tx| tx.date >= month)
+            .filter(|tx| matches!(classify_transaction(tx), TransactionType::Income))
+            .map(|tx| income_amount(tx, &maps, params.0.convert_to))
+            .sum();
+        let items = envelope_balances(
+            &envelopes,
+            &month_key,
+            &expenses,
+            &maps,
+            params.0.convert_to,
+        );
+        let allocated: f64 = items.iter().map(|item| item.allocated).sum();
 
-    #[test]
-    fn process_bulk_create_update_delete_mix() {
-        let maps = sample_maps();
-        let existing = vec![sample_transaction("tx-existing", 100.0, 0.0)];
-        let operations = vec![
-            BulkOperation::Create(sample_create_params(TransactionType::Expense)),
-            BulkOperation::Update(UpdateTransactionParams {
-                id: "tx-existing".to_owned(),
-                date: None,
-                amount: Some(200.0),
-                to_amount: None,
-                account_id: None,
-                to_account_id: None,
-                tag_ids: None,
-                payee: None,
-                comment: None,
-            }),
-            BulkOperation::Delete(DeleteTransactionParams {
-                id: "tx-existing".to_owned(),
-            }),
-        ];
-        let (to_push, to_delete, created, updated) =
-            process_bulk_operations(operations, &existing, &maps).expect("should process");
-        assert_eq!(created, 1);
-        assert_eq!(updated, 1);
-        assert_eq!(to_push.len(), 2);
-        assert_eq!(to_delete.len(), 1);
+        json_result(&EnvelopeStatusResponse {
+            month: month_key,
+            income,
+            allocated,
+            to_be_budgeted: income - allocated,
+            envelopes: items,
+        })
     }
 
-    #[test]
-    fn process_bulk_update_nonexistent_errors() {
-        let maps = sample_maps();
-        let existing: Vec<Transaction> = vec![];
-        let operations = vec![BulkOperation::Update(UpdateTransactionParams {
-            id: "no-such-tx".to_owned(),
-            date: None,
-            amount: Some(100.0),
-            to_amount: None,
-            account_id: None,
-            to_account_id: None,
-            tag_ids: None,
-            payee: None,
-            comment: None,
-        })];
-        let result = process_bulk_operations(operations, &existing, &maps);
-        assert!(result.is_err());
-    }
+    /// Produces a compact weekly or monthly summary.
+    #[tool(
+        description = "Compact summary of a week (default) or month ending on date_to (default today), meant to be pasted into a chat: income, expense, net, top 5 expense categories, 5 largest expenses, budgets exceeded month-to-date, and the 5 accounts with the largest balance changes (in their own currency). Amounts are rounded to cents and summed in the base currency (see server_info) unless convert_to names another instrument ID. Empty lists are omitted",
+        output_schema = cached_schema_for_type::<DigestResponse>()
+    )]
+    async fn digest(&self, params: Parameters<DigestParams>) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        let convert_to = self.totals_currency(&maps, params.0.convert_to).await?;
+        let date_to = params
+            .0
+            .date_to
+            .as_deref()
+            .map(parse_date)
+            .transpose()?
+            .unwrap_or_else(|| Utc::now().date_naive());
+        let date_from = match params.0.period.unwrap_or_default() {
+            DigestPeriod::Week => date_to.checked_sub_days(Days::new(6)),
+            DigestPeriod::Month => date_to
+                .checked_sub_months(Months::new(1))
+                .and_then(|date| date.succ_opt()),
+        }
+        .unwrap_or(NaiveDate::MIN);
+        let month_start = date_to.with_day(1).unwrap_or(date_to);
+
+        // Budget overruns are month to date, which may start before the period.
+        let transactions = self
+            .filtered_transactions(
+                &ListTransactionsParams {
+                    date_from: Some(date_from.min(month_start).to_string()),
+                    date_to: Some(date_to.to_string()),
+                    ..Default::default()
+                },
+                &maps,
+            )
+            .await?;
+        let is_expense =
+            |tx: &&Transaction| matches!(classify_transaction(tx), TransactionType::Expense);
+        let period: Vec<Transaction> = transactions
+            .iter()
+            .filter(|tx| tx.date >= date_from)
+            .cloned()
+            .collect();
+        let mut expenses: Vec<Transaction> = period.iter().filter(is_expense).cloned().collect();
+        let month_expenses: Vec<Transaction> = transactions
+            .iter()
+            .filter(|tx| tx.date >= month_start)
+            .filter(is_expense)
+            .cloned()
+            .collect();
 
-    #[test]
-    fn process_bulk_delete_nonexistent_errors() {
-        let maps = sample_maps();
-        let existing: Vec<Transaction> = vec![];
-        let operations = vec![BulkOperation::Delete(DeleteTransactionParams {
-            id: "no-such-tx".to_owned(),
-        })];
-        let result = process_bulk_operations(operations, &existing, &maps);
-        assert!(result.is_err());
+        let income: f64 = period
+            .iter()
+            .filter(|tx| matches!(classify_transaction(tx), TransactionType::Income))
+            .map(|tx| income_amount(tx, &maps, convert_to))
+            .sum();
+        let expense: f64 = expenses
+            .iter()
+            .map(|tx| expense_amount(tx, &maps, convert_to))
+            .sum();
+        let top_categories: Vec<DigestAmount> =
+            group_transactions(&expenses, GroupBy::Tag, &maps, convert_to)
+                .into_iter()
+                .take(TOP_CATEGORIES_LIMIT)
+                .map(|group| DigestAmount {
+                    label: group.label,
+                    amount: round_cents(group.outcome),
+                })
+                .collect();
+        expenses.sort_by(|a, b| {
+            expense_amount(b, &maps, convert_to).total_cmp(&expense_amount(a, &maps, convert_to))
+        });
+        let largest_expenses: Vec<DigestTransaction> = expenses
+            .iter()
+            .take(DIGEST_ITEMS_LIMIT)
+            .map(|tx| DigestTransaction {
+                date: tx.date.to_string(),
+                label: transaction_label(tx, &maps),
+                amount: round_cents(expense_amount(tx, &maps, convert_to)),
+            })
+            .collect();
+
+        let budgets = self.client.budgets().await.map_err(zen_err)?;
+        let month_budgets: Vec<Budget> = budgets
+            .into_iter()
+            .filter(|budget| budget.date == month_start)
+            .collect();
+        let overruns: Vec<BudgetOverrun> =
+            budget_overruns(&month_budgets, &month_expenses, &maps, convert_to)
+                .into_iter()
+                .map(|overrun| BudgetOverrun {
+                    budget: round_cents(overrun.budget),
+                    spent: round_cents(overrun.spent),
+                    over: round_cents(overrun.over),
+                    ..overrun
+                })
+                .collect();
+        let changes: Vec<AccountChange> = account_changes(&period, &maps)
+            .into_iter()
+            .take(DIGEST_ITEMS_LIMIT)
+            .map(|change| AccountChange {
+                change: round_cents(change.change),
+                ..change
+            })
+            .collect();
+
+        json_result(&DigestResponse {
+            date_from: date_from.to_string(),
+            date_to: date_to.to_string(),
+            currency: convert_to.map(|id| maps.instrument_symbol(id)),
+            income: round_cents(income),
+            expense: round_cents(expense),
+            net: round_cents(income - expense),
+            top_categories,
+            largest_expenses,
+            budget_overruns: overruns,
+            account_changes: changes,
+            unconverted: unconverted_currencies(&period, &maps, convert_to),
+        })
     }
 
-    #[test]
-    fn process_bulk_empty_operations() {
-        let maps = sample_maps();
-        let existing: Vec<Transaction> = vec![];
-        let (to_push, to_delete, created, updated) =
+    /// Reports average spending by weekday and by day of month.
+    #[tool(
+        description = "Average daily spending by weekday (Mon-Sun) and by day-of-month range (10 buckets, e.g. 1-4) over date_from..date_to (default: last 90 days). Averages divide by every calendar day in the bucket, so quiet days count as zero. Also returns weekday_daily_average vs weekend_daily_average. Set by_category=true for per-category daily averages in each bucket (include_children=true rolls child tags up) and convert_to to sum in one currency",
+        output_schema = cached_schema_for_type::<SpendingPatternsResponse>()
+    )]
+    async fn spending_patterns(
+        &self,
+        params: Parameters<SpendingPatternsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        validate_convert_to(&maps, params.0.convert_to)?;
+        let date_to = params
+            .0
+            .date_to
+            .as_deref()
+            .map(parse_date)
+            .transpose()?
+            .unwrap_or_else(|| Utc::now().date_naive());
+        let date_from = match params.0.date_from.as_deref() {
+            Some(date_from) => parse_date(date_from)?,
+            None => date_to
+                .checked_sub_days(Days::new(DEFAULT_PATTERN_WINDOW_DAYS))
+                .unwrap_or(NaiveDate::MIN),
+        };
+        if date_from > date_to {
+            return Err(McpError::invalid_params(
+                "date_from must not be after date_to",
+                None,
+            ));
+        }
+
+        let mut expenses = self
+            .filtered_transactions(
+                &ListTransactionsParams {
+                    date_from: Some(date_from.to_string()),
+                    date_to: Some(date_to.to_string()),
+                    transaction_type: Some(TransactionType::Expense),
+                    ..Default::default()
+                },
+                &maps,
+            )
+            .await?;
+        if params.0.include_children == Some(true) {
+            roll_up_tags(&mut expenses, &maps);
+        }
+        let (mut by_weekday, mut by_day_of_month) =
+            analytics::spending_patterns(&expenses, date_from, date_to, &maps, params.0.convert_to);
+        let weekday_daily_average = pooled_daily_average(by_weekday.get(..5).unwrap_or_default());
+        let weekend_daily_average = pooled_daily_average(by_weekday.get(5..).unwrap_or_default());
+        let daily_average = pooled_daily_average(&by_weekday);
+        if params.0.by_category != Some(true) {
+            for bucket in by_weekday.iter_mut().chain(by_day_of_month.iter_mut()) {
+                bucket.categories.clear();
+            }
+        }
+
+        json_result(&SpendingPatternsResponse {
+            date_from: date_from.to_string(),
+            date_to: date_to.to_string(),
+            daily_average,
+            weekday_daily_average,
+            weekend_daily_average,
+            by_weekday,
+            by_day_of_month,
+        })
+    }
+
+    /// Aggregates transfers into an account-to-account matrix.
+    #[tool(
+        description = "Summarize money moved between your own accounts (transfers, which expense and income reports exclude) over date_from..date_to, optionally only transfers touching account_id. Returns flows: [{from_account, to_account, count, sent, sent_currency, received, received_currency}] busiest first, per-account totals [{account, currency, transferred_in, transferred_out, net}], and transfer_count. Amounts stay in each account's currency unless convert_to is set",
+        output_schema = cached_schema_for_type::<TransferReportResponse>()
+    )]
+    async fn transfer_report(
+        &self,
+        params: Parameters<TransferReportParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        validate_convert_to(&maps, params.0.convert_to)?;
+        let transfers = self
+            .filtered_transactions(
+                &ListTransactionsParams {
+                    date_from: params.0.date_from.clone(),
+                    date_to: params.0.date_to.clone(),
+                    account_id: params.0.account_id.clone(),
+                    transaction_type: Some(TransactionType::Transfer),
+                    ..Default::default()
+                },
+                &maps,
+            )
+            .await?;
+        let (flows, accounts) = transfer_matrix(&transfers, &maps, params.0.convert_to);
+
+        json_result(&TransferReportResponse {
+            flows,
+            accounts,
+            transfer_count: transfers.len(),
+        })
+    }
+
+    /// Reports income, expenses and the share saved per month.
+    #[tool(
+        description = "Savings rate per calendar month over date_from..date_to (default: the last 12 months including the current one). Each month and the range total report income, expense, net_saved (income minus expense) and savings_percent (saved share of income, absent without income). Set savings_transfers=true to count net transfers into accounts flagged as savings as the saved amount (to_savings) instead of net_saved. Set convert_to to an instrument ID to sum in one currency",
+        output_schema = cached_schema_for_type::<SavingsRateResponse>()
+    )]
+    async fn savings_rate(
+        &self,
+        params: Parameters<SavingsRateParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        validate_convert_to(&maps, params.0.convert_to)?;
+        let date_to = params
+            .0
+            .date_to
+            .as_deref()
+            .map(parse_date)
+            .transpose()?
+            .unwrap_or_else(|| Utc::now().date_naive());
+        let date_from = match params.0.date_from.as_deref() {
+            Some(date_from) => parse_date(date_from)?,
+            None => date_to
+                .with_day(1)
+                .and_then(|date| date.checked_sub_months(Months::new(DEFAULT_SAVINGS_MONTHS)))
+                .unwrap_or(NaiveDate::MIN),
+        };
+        if date_from > date_to {
+            return Err(McpError::invalid_params(
+                "date_from must not be after date_to",
+                None,
+            ));
+        }
+
+        let transactions = self
+            .filtered_transactions(
+                &ListTransactionsParams {
+                    date_from: Some(date_from.to_string()),
+                    date_to: Some(date_to.to_string()),
+                    ..Default::default()
+                },
+                &maps,
+            )
+            .await?;
+        let (months, total) = savings_by_month(
+            &transactions,
+            &maps,
+            params.0.convert_to,
+            params.0.savings_transfers == Some(true),
+        );
+
+        json_result(&SavingsRateResponse {
+            date_from: date_from.to_string(),
+            date_to: date_to.to_string(),
+            months,
+            total,
+        })
+    }
+
+    /// Produces a year-in-review report.
+    #[tool(
+        description = "Year-in-review report for a calendar year (default: current year): income, expense, net and savings_percent for the year, a per-month trend (income, expense, net_saved, savings_percent), the top 10 categories and payees by spending, the 10 largest single expenses, and budget_adherence (category budgets kept vs exceeded, with the largest overruns labelled by month). Amounts are rounded to cents; set convert_to to an instrument ID to sum in one currency",
+        output_schema = cached_schema_for_type::<YearReviewResponse>()
+    )]
+    async fn year_review(
+        &self,
+        params: Parameters<YearReviewParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        let convert_to = params.0.convert_to;
+        validate_convert_to(&maps, convert_to)?;
+        let year = params.0.year.unwrap_or_else(|| Utc::now().year());
+        let (Some(date_from), Some(date_to)) = (
+            NaiveDate::from_ymd_opt(year, 1, 1),
+            NaiveDate::from_ymd_opt(year, 12, 31),
+        ) else {
+            return Err(McpError::invalid_params(
+                format!("invalid year {year}"),
+                None,
+            ));
+        };
+
+        let transactions = self
+            .filtered_transactions(
+                &ListTransactionsParams {
+                    date_from: Some(date_from.to_string()),
+                    date_to: Some(date_to.to_string()),
+                    ..Default::default()
+                },
+                &maps,
+            )
+            .await?;
+        let mut expenses: Vec<Transaction> = transactions
+            .iter()
+            .filter(|tx| matches!(classify_transaction(tx), TransactionType::Expense))
+            .cloned()
+            .collect();
+
+        let round_month = |month: SavingsMonth| SavingsMonth {
+            income: round_cents(month.income),
+            expense: round_cents(month.expense),
+            net_saved: round_cents(month.net_saved),
+            savings_percent: month.savings_percent.map(round_cents),
+            ..month
+        };
+        let (months, total) = savings_by_month(&transactions, &maps, convert_to, false);
+        let top_categories: Vec<DigestAmount> =
+            group_transactions(&expenses, GroupBy::Tag, &maps, convert_to)
+                .into_iter()
+                .take(YEAR_REVIEW_ITEMS_LIMIT)
+                .map(|group| DigestAmount {
+                    label: group.label,
+                    amount: round_cents(group.outcome),
+                })
+                .collect();
+        let mut payees = payee_spend(&expenses, PayeeDimension::Payee, &maps, convert_to);
+        rank_payees(&mut payees, RankBy::Amount);
+        let top_payees: Vec<DigestAmount> = payees
+            .into_iter()
+            .take(YEAR_REVIEW_ITEMS_LIMIT)
+            .map(|payee| DigestAmount {
+                label: payee.label,
+                amount: round_cents(payee.total),
+            })
+            .collect();
+        expenses.sort_by(|a, b| {
+            expense_amount(b, &maps, convert_to).total_cmp(&expense_amount(a, &maps, convert_to))
+        });
+        let largest_expenses: Vec<DigestTransaction> = expenses
+            .iter()
+            .take(YEAR_REVIEW_ITEMS_LIMIT)
+            .map(|tx| DigestTransaction {
+                date: tx.date.to_string(),
+                label: transaction_label(tx, &maps),
+                amount: round_cents(expense_amount(tx, &maps, convert_to)),
+            })
+            .collect();
+
+        let budgets = self.client.budgets().await.map_err(zen_err)?;
+        let year_budgets: Vec<Budget> = budgets
+            .into_iter()
+            .filter(|budget| budget.date.year() == year)
+            .collect();
+        let adherence = budget_adherence(&year_budgets, &expenses, &maps, convert_to);
+        let budget_summary = (adherence.budgets > 0).then(|| BudgetAdherence {
+            kept_percent: adherence.kept_percent.map(round_cents),
+            budgeted: round_cents(adherence.budgeted),
+            spent: round_cents(adherence.spent),
+            overruns: adherence
+                .overruns
+                .iter()
+                .take(YEAR_REVIEW_ITEMS_LIMIT)
+                .map(|overrun| BudgetOverrun {
+                    category: overrun.category.clone(),
+                    budget: round_cents(overrun.budget),
+                    spent: round_cents(overrun.spent),
+                    over: round_cents(overrun.over),
+                })
+                .collect(),
+            ..adherence
+        });
+
+        json_result(&YearReviewResponse {
+            year,
+            income: round_cents(total.income),
+            expense: round_cents(total.expense),
+            net: round_cents(total.net_saved),
+            savings_percent: total.savings_percent.map(round_cents),
+            transaction_count: transactions.len(),
+            months: months.into_iter().map(round_month).collect(),
+            top_categories,
+            top_payees,
+            largest_expenses,
+            budget_adherence: budget_summary,
+        })
+    }
+
+    // ── Write tools ─────────────────────────────────────────────────
+
+    /// Creates a new transaction with simplified parameters.
+    #[tool(
+        description = "Create a new financial transaction. Specify transaction_type (expense/income/transfer), date, account_id, and amount; account_id may be omitted once a default account is set (see set_default_account). For transfers, also provide to_account_id. Currency instruments are auto-resolved from the account unless overridden with instrument_id/to_instrument_id. Optionally specify tag_ids, payee, comment, merchant_id (must be a known merchant), and latitude/longitude where it happened. Set planned=true on a future-dated transaction to keep it locally instead of sending it to ZenMoney, out of balances, reports and listings until convert_planned (see list_planned)"
+    )]
+    async fn create_transaction(
+        &self,
+        params: Parameters<CreateTransactionParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut create_params = params.0;
+        let planned = create_params.planned.take() == Some(true);
+        let maps = self.lookup_maps().await?;
+        let new_tx = build_transaction(create_params, &maps)?;
+        if planned && new_tx.date <= Utc::now().date_naive() {
+            return Err(McpError::invalid_params(
+                "a planned transaction needs a future date",
+                None,
+            ));
+        }
+        let preview = TransactionResponse::from_transaction(&new_tx, &maps);
+        let new_id = new_tx.id.to_string();
+        if planned {
+            self.planned.add(new_tx).map_err(io_err)?;
+            tracing::info!(id = %new_id, "planned transaction saved");
+            return json_result(&vec![preview]);
+        }
+        let pending = self
+            .push_or_queue("create_transaction", vec![new_tx])
+            .await?;
+        tracing::info!(id = %new_id, "transaction created");
+        self.refresh_resources().await;
+
+        Ok(with_pending_note(
+            json_result(&vec![preview])?,
+            pending.as_deref(),
+        ))
+    }
+
+    /// Logs an expense from an amount and a few words.
+    #[tool(
+        description = "Quickly log an expense from one line of chat or voice: just amount, optional text (a category title like \"Groceries\" or a payee like \"coffee shop\") and optional date (YYYY-MM-DD, default today; pass allow_out_of_range=true for a date outside the configured window, such as an old receipt). Uses the default account (see set_default_account), takes the category whose title equals text or else ZenMoney's suggestion for text as a payee, and creates the transaction. Returns {transaction, inferred}, inferred listing what was filled in so it can be corrected with update_transaction",
+        output_schema = cached_schema_for_type::<LogExpenseResponse>()
+    )]
+    async fn log_expense(
+        &self,
+        params: Parameters<LogExpenseParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let LogExpenseParams {
+            amount,
+            text,
+            date,
+            allow_out_of_range,
+        } = params.0;
+        let maps = self.lookup_maps().await?;
+        let account_id = maps.default_account()?;
+        let mut inferred = vec![format!(
+            "account: {} (default account)",
+            maps.account_name(&account_id)
+        )];
+        let date = date.unwrap_or_else(|| {
+            inferred.push("date: today".to_owned());
+            Utc::now().date_naive().format("%Y-%m-%d").to_string()
+        });
+        let guess = match text
+            .as_deref()
+            .map(str::trim)
+            .filter(|text| !text.is_empty())
+        {
+            Some(text) => self.guess_expense(text, &maps, &mut inferred).await?,
+            None => ExpenseGuess::default(),
+        };
+        let new_tx = build_transaction(
+            CreateTransactionParams {
+                transaction_type: TransactionType::Expense,
+                date,
+                account_id,
+                amount,
+                to_account_id: None,
+                to_amount: None,
+                instrument_id: None,
+                to_instrument_id: None,
+                tag_ids: guess.tag_ids,
+                payee: guess.payee,
+                comment: None,
+                merchant_id: guess.merchant_id,
+                latitude: None,
+                longitude: None,
+                mcc: None,
+                planned: None,
+                allow_out_of_range,
+            },
+            &maps,
+        )?;
+        let transaction = TransactionResponse::from_transaction(&new_tx, &maps);
+        let new_id = new_tx.id.to_string();
+        let pending = self.push_or_queue("log_expense", vec![new_tx]).await?;
+        tracing::info!(id = %new_id, "expense logged");
+        self.refresh_resources().await;
+        let result = json_result(&LogExpenseResponse {
+            transaction,
+            inferred,
+        })?;
+        Ok(with_pending_note(result, pending.as_deref()))
+    }
+
+    /// Lists planned transactions, earliest first.
+    #[tool(
+        description = "List planned transactions (created with planned=true): future bills and incomes kept locally, out of balances, reports and list_transactions, until convert_planned sends them to ZenMoney. Returns {count, due, transactions} earliest first; due counts those dated today or earlier, which likely happened and are ready to convert",
+        output_schema = cached_schema_for_type::<PlannedResponse>()
+    )]
+    async fn list_planned(&self) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        let params = ListTransactionsParams {
+            planned: Some(true),
+            ..Default::default()
+        };
+        let mut planned = self.filtered_transactions(&params, &maps).await?;
+        sort_by_date(&mut planned, SortDirection::Asc);
+        json_result(&PlannedResponse::new(
+            &planned,
+            Utc::now().date_naive(),
+            &maps,
+        ))
+    }
+
+    /// Turns planned transactions into actual ones.
+    #[tool(
+        description = "Convert planned transactions into actual ones once they happened: they are sent to ZenMoney as recorded, then count in balances and reports and appear in list_transactions. Use update_transaction afterwards to adjust the date or amount. Returns the converted transactions"
+    )]
+    async fn convert_planned(
+        &self,
+        params: Parameters<ConvertPlannedParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let ids = params.0.transaction_ids;
+        let now = Utc::now();
+        let mut converted: Vec<Transaction> = self
+            .planned
+            .list()
+            .map_err(io_err)?
+            .into_iter()
+            .filter(|tx| ids.iter().any(|id| id == tx.id.as_inner()))
+            .collect();
+        if converted.is_empty() {
+            return Err(McpError::invalid_params(
+                "none of the transaction_ids is planned",
+                None,
+            ));
+        }
+        for tx in &mut converted {
+            tx.changed = now;
+        }
+        let maps = self.lookup_maps().await?;
+        let preview: Vec<TransactionResponse> = converted
+            .iter()
+            .map(|tx| TransactionResponse::from_transaction(tx, &maps))
+            .collect();
+        let converted_ids: Vec<String> = converted.iter().map(|tx| tx.id.to_string()).collect();
+        let pending = self.push_or_queue("convert_planned", converted).await?;
+        for id in &converted_ids {
+            let _removed = self.planned.remove(id).map_err(io_err)?;
+        }
+        tracing::info!(
+            count = converted_ids.len(),
+            "planned transactions converted"
+        );
+        self.refresh_resources().await;
+        Ok(with_pending_note(
+            json_result(&preview)?,
+            pending.as_deref(),
+        ))
+    }
+
+    /// Saves a reusable transaction template.
+    #[tool(
+        description = "Save a transaction template (account, optional amount, tags, payee, comment, merchant) under a name for quick reuse with create_from_template. Saving an existing name (case-insensitive) replaces it. Templates are stored locally next to the sync data",
+        output_schema = cached_schema_for_type::<SavedTemplateResponse>()
+    )]
+    async fn save_template(
+        &self,
+        params: Parameters<SaveTemplateParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        let template = validate_template(params.0, &maps)?;
+        let replaced = self.templates.save(template.clone()).map_err(io_err)?;
+        json_result(&SavedTemplateResponse { template, replaced })
+    }
+
+    /// Lists saved transaction templates.
+    #[tool(description = "List saved transaction templates")]
+    async fn list_templates(&self) -> Result<CallToolResult, McpError> {
+        let templates = self.templates.list().map_err(io_err)?;
+        json_result(&templates)
+    }
+
+    /// Creates a weekly or monthly series as transactions or a reminder.
+    #[tool(
+        description = "Create a recurring transaction from one transaction spec (same fields as create_transaction; date is the first occurrence) repeating weekly or monthly, every N periods (every, default 1). Bound the series with count (occurrences, including the first) and/or end_date; at most 120 occurrences. mode=transactions (default) stages one future-dated transaction per occurrence and returns a preview with a preparation_id for execute_bulk_operations. mode=reminder creates a ZenMoney reminder instead (pushed immediately; count and end_date are optional) so the app plans the occurrences"
+    )]
+    async fn create_recurring(
+        &self,
+        params: Parameters<CreateRecurringParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let CreateRecurringParams {
+            transaction,
+            frequency,
+            every,
+            count,
+            end_date,
+            mode,
+        } = params.0;
+        let interval = match frequency {
+            RecurrenceFrequency::Weekly => Interval::Week,
+            RecurrenceFrequency::Monthly => Interval::Month,
+        };
+        let step = recurrence_step(every, count)?;
+        let end = end_date.as_deref().map(parse_date).transpose()?;
+        let maps = self.lookup_maps().await?;
+        let first = build_transaction(transaction, &maps)?;
+        match mode {
+            RecurringMode::Transactions => {
+                if count.is_none() && end.is_none() {
+                    return Err(McpError::invalid_params(
+                        "pass count or end_date to bound the series",
+                        None,
+                    ));
+                }
+                let series: Vec<Transaction> =
+                    recurrence_dates(first.date, interval, step, count, end)?
+                        .into_iter()
+                        .map(|date| Transaction {
+                            id: TransactionId::new(uuid::Uuid::new_v4().to_string()),
+                            date,
+                            ..first.clone()
+                        })
+                        .collect();
+                json_result(&self.stage_creations(series, &maps)?)
+            }
+            RecurringMode::Reminder => {
+                let last = match count {
+                    Some(_) => recurrence_dates(first.date, interval, step, count, end)?
+                        .last()
+                        .copied(),
+                    None => end,
+                };
+                let user_id = self.current_user_id().await?;
+                let reminder = reminder_from(&first, user_id, interval, step, last);
+                json_result(&self.push_reminder(reminder, &maps).await?)
+            }
+        }
+    }
+
+    /// Stages a copy of an existing transaction on a new date.
+    #[tool(
+        description = "Stage a copy of an existing transaction (accounts, amounts, tags, payee, merchant, comment) dated date (default today), e.g. to add this month's electricity bill like last month's. amount optionally replaces the amount (for a transfer, the received amount is scaled to match). Returns a preview with a preparation_id; pass it to execute_bulk_operations to commit",
+        output_schema = cached_schema_for_type::<PrepareResponse>()
+    )]
+    async fn duplicate_transaction(
+        &self,
+        params: Parameters<DuplicateTransactionParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let DuplicateTransactionParams {
+            transaction_id,
+            date,
+            amount,
+            allow_out_of_range,
+        } = params.0;
+        if amount.is_some_and(|value| !value.is_finite() || value <= 0.0) {
+            return Err(McpError::invalid_params(
+                "amount must be a positive number",
+                None,
+            ));
+        }
+        let day = date
+            .as_deref()
+            .map(parse_date)
+            .transpose()?
+            .unwrap_or_else(|| Utc::now().date_naive());
+        let maps = self.lookup_maps().await?;
+        let copies: Vec<Transaction> = self
+            .transactions_by_ids(&[transaction_id])
+            .await?
+            .iter()
+            .map(|tx| duplicate_of(tx, day, amount))
+            .collect();
+        for copy in &copies {
+            maps.check_date(copy, allow_out_of_range)?;
+        }
+        json_result(&self.stage_creations(copies, &maps)?)
+    }
+
+    /// Creates a transaction from a saved template.
+    #[tool(
+        description = "Create a transaction from a saved template. Optionally override date (default today), amount (required if the template has none), and comment. The transaction is pushed immediately, like create_transaction"
+    )]
+    async fn create_from_template(
+        &self,
+        params: Parameters<CreateFromTemplateParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let template = self
+            .templates
+            .get(&params.0.name)
+            .map_err(io_err)?
+            .ok_or_else(|| not_found("template", &params.0.name))?;
+        let create_params = template_create_params(template, params.0)?;
+        self.create_transaction(Parameters(create_params)).await
+    }
+
+    /// Allocates money to an envelope, optionally moving it from another one.
+    #[tool(
+        description = "Allocate money to a named envelope for a month (YYYY-MM, default current). The envelope is created on first use and then needs tag_ids (category tags paid from it); passing tag_ids later replaces the mapping. A negative amount takes money back. Set from_envelope to move a positive amount from another envelope instead of allocating new income. Envelopes are stored locally next to the sync data",
+        output_schema = cached_schema_for_type::<AllocatedEnvelopeResponse>()
+    )]
+    async fn allocate_envelope(
+        &self,
+        params: Parameters<AllocateEnvelopeParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let amount = params.0.amount;
+        if !amount.is_finite() || amount.abs() < f64::EPSILON {
+            return Err(McpError::invalid_params(
+                "amount must be a non-zero number",
+                None,
+            ));
+        }
+        let month_key = parse_month(params.0.month.as_deref())?
+            .format("%Y-%m")
+            .to_string();
+        let name = normalize_title(&params.0.name)?;
+        let maps = self.lookup_maps().await?;
+        if let Some(tag_ids) = params.0.tag_ids.as_deref() {
+            if tag_ids.is_empty() {
+                return Err(McpError::invalid_params("tag_ids must not be empty", None));
+            }
+            if let Some(unknown) = tag_ids.iter().find(|tag_id| !maps.has_tag(tag_id)) {
+                return Err(not_found("tag", unknown));
+            }
+        }
+
+        let existing = self.envelopes.get(&name).map_err(io_err)?;
+        let created = existing.is_none();
+        let mut envelope = match existing {
+            Some(envelope) => envelope,
+            None => Envelope {
+                tag_ids: params.0.tag_ids.clone().ok_or_else(|| {
+                    McpError::invalid_params(
+                        format!("tag_ids are required to create envelope '{name}'"),
+                        None,
+                    )
+                })?,
+                name,
+                allocations: BTreeMap::new(),
+            },
+        };
+        if let Some(tag_ids) = params.0.tag_ids.clone() {
+            envelope.tag_ids = tag_ids;
+        }
+        envelope.allocate(&month_key, amount);
+
+        let from_envelope = match params.0.from_envelope.as_deref() {
+            Some(from_name) => {
+                if normalized_title_key(from_name) == normalized_title_key(&envelope.name) {
+                    return Err(McpError::invalid_params(
+                        "from_envelope must differ from the target envelope",
+                        None,
+                    ));
+                }
+                if amount < 0.0_f64 {
+                    return Err(McpError::invalid_params(
+                        "amount must be positive when moving between envelopes",
+                        None,
+                    ));
+                }
+                let mut source = self
+                    .envelopes
+                    .get(from_name)
+                    .map_err(io_err)?
+                    .ok_or_else(|| not_found("envelope", from_name))?;
+                source.allocate(&month_key, -amount);
+                Some(source)
+            }
+            None => None,
+        };
+
+        let mut changed = vec![envelope.clone()];
+        changed.extend(from_envelope.clone());
+        self.envelopes.save(changed).map_err(io_err)?;
+        json_result(&AllocatedEnvelopeResponse {
+            month: month_key,
+            envelope,
+            from_envelope,
+            created,
+        })
+    }
+
+    /// Creates a new category tag.
+    #[tool(
+        description = "Create a new category tag. If a tag with the same title already exists (case-insensitive), returns the existing tag instead of creating a duplicate"
+    )]
+    async fn create_tag(
+        &self,
+        params: Parameters<CreateTagParams>,
+    ) -> Result<CallToolResult, McpError> {
+        self.create_tag_internal(params.0).await
+    }
+
+    /// Alias for creating a category tag.
+    #[tool(
+        description = "Alias for create_tag: create a category tag with the same behavior and idempotency guarantees"
+    )]
+    async fn create_category(
+        &self,
+        params: Parameters<CreateTagParams>,
+    ) -> Result<CallToolResult, McpError> {
+        self.create_tag_internal(params.0).await
+    }
+
+    /// Creates a new merchant.
+    #[tool(
+        description = "Create a merchant. If a merchant with the same title already exists (case-insensitive), returns the existing merchant instead of creating a duplicate",
+        output_schema = cached_schema_for_type::<MerchantResponse>()
+    )]
+    async fn create_merchant(
+        &self,
+        params: Parameters<CreateMerchantParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let title = normalize_title(&params.0.title)?;
+        let merchants = self.client.merchants().await.map_err(zen_err)?;
+        let key = normalized_title_key(&title);
+        if let Some(existing) = merchants
+            .iter()
+            .find(|merchant| normalized_title_key(&merchant.title) == key)
+        {
+            return json_result(&MerchantResponse::from_merchant(existing));
+        }
+
+        let user_id = self.current_user_id().await?;
+        let merchant = build_merchant(title, user_id);
+        let preview = MerchantResponse::from_merchant(&merchant);
+        self.throttle.pace().await;
+        let _response = self
+            .client
+            .push_merchants(vec![merchant])
+            .await
+            .map_err(zen_err)?;
+        json_result(&preview)
+    }
+
+    /// Renames an existing merchant.
+    #[tool(
+        description = "Rename a merchant by ID",
+        output_schema = cached_schema_for_type::<MerchantResponse>()
+    )]
+    async fn rename_merchant(
+        &self,
+        params: Parameters<RenameMerchantParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let title = normalize_title(&params.0.title)?;
+        let merchants = self.client.merchants().await.map_err(zen_err)?;
+        let mut merchant = find_merchant(&merchants, &params.0.merchant_id)?.clone();
+        merchant.title = title;
+        merchant.changed = Utc::now();
+
+        let preview = MerchantResponse::from_merchant(&merchant);
+        self.throttle.pace().await;
+        let _response = self
+            .client
+            .push_merchants(vec![merchant])
+            .await
+            .map_err(zen_err)?;
+        json_result(&preview)
+    }
+
+    /// Merges merchants into a target, re-pointing references before deleting the sources.
+    #[tool(
+        description = "Merge one or more source merchants into a target merchant: every transaction and reminder referencing a source is re-pointed to the target (pushed in batches of 100), then the source merchants are deleted. Returns {target, merged_merchant_ids, transactions_updated, reminders_updated}",
+        output_schema = cached_schema_for_type::<MergeMerchantsResponse>()
+    )]
+    async fn merge_merchants(
+        &self,
+        params: Parameters<MergeMerchantsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let merchants = self.client.merchants().await.map_err(zen_err)?;
+        let sources = validate_merge_sources(
+            &merchants,
+            &params.0.source_merchant_ids,
+            &params.0.target_merchant_id,
+        )?;
+        let target = find_merchant(&merchants, &params.0.target_merchant_id)?;
+        let now = Utc::now();
+        let references_source = |merchant: Option<&MerchantId>| {
+            merchant.is_some_and(|id| sources.contains(id.as_inner()))
+        };
+
+        let transactions: Vec<Transaction> = self
+            .client
+            .transactions()
+            .await
+            .map_err(zen_err)?
+            .into_iter()
+            .filter(|tx| !tx.deleted && references_source(tx.merchant.as_ref()))
+            .map(|mut tx| {
+                tx.merchant = Some(target.id.clone());
+                tx.changed = now;
+                tx
+            })
+            .collect();
+        for batch in transactions.chunks(MERCHANT_MERGE_BATCH_SIZE) {
+            self.throttle.pace().await;
+            let _response = self
+                .client
+                .push_transactions(batch.to_vec())
+                .await
+                .map_err(zen_err)?;
+        }
+
+        let reminders: Vec<Reminder> = self
+            .client
+            .reminders()
+            .await
+            .map_err(zen_err)?
+            .into_iter()
+            .filter(|reminder| references_source(reminder.merchant.as_ref()))
+            .map(|mut reminder| {
+                reminder.merchant = Some(target.id.clone());
+                reminder.changed = now;
+                reminder
+            })
+            .collect();
+        let reminders_updated = reminders.len();
+        if !reminders.is_empty() {
+            self.throttle.pace().await;
+            let _response = self
+                .client
+                .push_reminders(reminders)
+                .await
+                .map_err(zen_err)?;
+        }
+
+        let source_ids: Vec<MerchantId> = sources
+            .iter()
+            .map(|id| MerchantId::new((*id).to_owned()))
+            .collect();
+        self.throttle.pace().await;
+        let _response = self
+            .client
+            .delete_merchants(&source_ids)
+            .await
+            .map_err(zen_err)?;
+        self.refresh_resources().await;
+
+        json_result(&MergeMerchantsResponse {
+            target: MerchantResponse::from_merchant(target),
+            merged_merchant_ids: source_ids.iter().map(ToString::to_string).collect(),
+            transactions_updated: transactions.len(),
+            reminders_updated,
+        })
+    }
+
+    /// Stages linking transactions with a matching payee to a merchant.
+    #[tool(
+        description = "Stage setting the merchant on transactions whose payee contains payee_pattern (case-insensitive), optionally within date_from/date_to. Transactions already linked to another merchant are skipped unless overwrite=true. Returns a prepare preview with a preparation_id; pass it to execute_bulk_operations to commit",
+        output_schema = cached_schema_for_type::<PrepareResponse>()
+    )]
+    async fn link_merchant(
+        &self,
+        params: Parameters<LinkMerchantParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let pattern = params.0.payee_pattern.trim();
+        if pattern.is_empty() {
+            return Err(McpError::invalid_params(
+                "payee_pattern must not be empty or blank",
+                None,
+            ));
+        }
+        let merchants = self.client.merchants().await.map_err(zen_err)?;
+        let merchant = find_merchant(&merchants, &params.0.merchant_id)?;
+        let maps = self.lookup_maps().await?;
+        let candidates = self
+            .filtered_transactions(
+                &ListTransactionsParams {
+                    date_from: params.0.date_from.clone(),
+                    date_to: params.0.date_to.clone(),
+                    payee: Some(pattern.to_owned()),
+                    ..Default::default()
+                },
+                &maps,
+            )
+            .await?;
+
+        let overwrite = params.0.overwrite.unwrap_or(false);
+        let now = Utc::now();
+        let to_push: Vec<Transaction> = candidates
+            .into_iter()
+            .filter(|tx| {
+                tx.merchant
+                    .as_ref()
+                    .is_none_or(|current| overwrite && *current != merchant.id)
+            })
+            .map(|mut tx| {
+                tx.merchant = Some(merchant.id.clone());
+                tx.changed = now;
+                tx
+            })
+            .collect();
+        if to_push.is_empty() {
+            return Err(McpError::invalid_params(
+                format!("no transactions with payee matching '{pattern}' need linking"),
+                None,
+            ));
+        }
+
+        json_result(&self.stage_updates(to_push, &maps)?)
+    }
+
+    /// Stages moving transactions to another account.
+    #[tool(
+        description = "Stage moving transactions to target_account_id. Select them either by transaction_ids or by filters (date range, account, tag, include_children, payee, merchant, amount range, uncategorized, transaction_type, user_id), not both. Expenses and income move entirely; transfers move the side on the account_id filter (outcome side if no account filter). The account currency is applied to the moved side. Returns a preview with a preparation_id; pass it to execute_bulk_operations to commit",
+        output_schema = cached_schema_for_type::<PrepareResponse>()
+    )]
+    async fn move_transactions(
+        &self,
+        params: Parameters<MoveTransactionsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        let _target_instrument = resolve_instrument(&maps, &params.0.target_account_id, None)?;
+        let candidates = self
+            .transactions_by_ids_or_selection(
+                params.0.transaction_ids.as_deref(),
+                &params.0.selection,
+                &maps,
+            )
+            .await?;
+
+        let source_account = params.0.selection.account_id.as_deref();
+        let now = Utc::now();
+        let mut to_push = Vec::new();
+        for mut tx in candidates {
+            if move_transaction(&mut tx, source_account, &params.0.target_account_id, &maps)? {
+                tx.changed = now;
+                to_push.push(tx);
+            }
+        }
+        if to_push.is_empty() {
+            return Err(McpError::invalid_params(
+                "all selected transactions already use the target account",
+                None,
+            ));
+        }
+        json_result(&self.stage_updates(to_push, &maps)?)
+    }
+
+    /// Stages adding and removing tags on transactions listed by ID.
+    #[tool(
+        description = "Stage adding and/or removing tags on the transactions in transaction_ids (up to 500), e.g. to categorize a reviewed inbox in one go instead of one update_transaction call each. add_tag_ids are added where missing, then remove_tag_ids are removed (removal wins); other tags are kept. Transactions whose tags would not change are skipped. Returns a preview with a preparation_id; pass it to execute_bulk_operations to commit",
+        output_schema = cached_schema_for_type::<PrepareResponse>()
+    )]
+    async fn tag_transactions(
+        &self,
+        params: Parameters<TagTransactionsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let TagTransactionsParams {
+            mut transaction_ids,
+            add_tag_ids,
+            remove_tag_ids,
+        } = params.0;
+        let changes = add_tag_ids.iter().chain(remove_tag_ids.iter()).flatten();
+        if changes.clone().next().is_none() {
+            return Err(McpError::invalid_params(
+                "add_tag_ids or remove_tag_ids must name at least one tag",
+                None,
+            ));
+        }
+        let maps = self.lookup_maps().await?;
+        if let Some(unknown) = changes.clone().find(|tag_id| !maps.has_tag(tag_id)) {
+            return Err(not_found("tag", unknown));
+        }
+        let mut seen = HashSet::new();
+        transaction_ids.retain(|id| seen.insert(id.clone()));
+        if transaction_ids.is_empty() || transaction_ids.len() > MAX_FILTER_BULK {
+            return Err(McpError::invalid_params(
+                format!("transaction_ids must list 1 to {MAX_FILTER_BULK} transactions"),
+                None,
+            ));
+        }
+        let now = Utc::now();
+        let mut to_push = Vec::new();
+        for mut tx in self.transactions_by_ids(&transaction_ids).await? {
+            let before = tx.tag.clone();
+            apply_tag_changes(&mut tx, None, add_tag_ids.clone(), remove_tag_ids.clone());
+            if tx.tag != before {
+                tx.changed = now;
+                to_push.push(tx);
+            }
+        }
+        if to_push.is_empty() {
+            return Err(McpError::invalid_params(
+                "the selected transactions already have these tags",
+                None,
+            ));
+        }
+        json_result(&self.stage_updates(to_push, &maps)?)
+    }
+
+    /// Stages merging a hold with its settlement, or two given duplicates.
+    #[tool(
+        description = "Stage merging duplicate transactions, typically a card hold and the posted transaction that settled it. With keep_id and remove_id, merges that pair (same accounts and currencies required). Without them, pairs each hold with a settled transaction on the same accounts with the same amounts, dated on the hold's day or up to max_days later (default 5), optionally only on account_id; the closest date wins, ties are skipped, and the settled one is kept. The kept transaction gets the tags, payee, comment, merchant, MCC and location it lacks from the other, which is deleted. Returns a preview with a preparation_id; pass it to execute_bulk_operations to commit",
+        output_schema = cached_schema_for_type::<PrepareResponse>()
+    )]
+    async fn merge_transactions(
+        &self,
+        params: Parameters<MergeTransactionsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let MergeTransactionsParams {
+            keep_id,
+            remove_id,
+            account_id,
+            max_days,
+        } = params.0;
+        let maps = self.lookup_maps().await?;
+        let pairs = match (keep_id, remove_id) {
+            (Some(keep), Some(remove)) => {
+                let mut found = self.transactions_by_ids(&[keep, remove]).await?.into_iter();
+                let (Some(kept), Some(removed)) = (found.next(), found.next()) else {
+                    return Err(McpError::internal_error("merge pair not loaded", None));
+                };
+                check_mergeable(&kept, &removed)
+                    .map_err(|message| McpError::invalid_params(message, None))?;
+                vec![(kept, removed)]
+            }
+            (None, None) => {
+                let transactions = self.client.transactions().await.map_err(zen_err)?;
+                hold_pairs(&transactions, max_days.unwrap_or(DEFAULT_HOLD_SETTLE_DAYS))
+                    .into_iter()
+                    .filter(|&(settled, _hold)| {
+                        account_id.as_deref().is_none_or(|account| {
+                            settled.income_account.as_inner() == account
+                                || settled.outcome_account.as_inner() == account
+                        })
+                    })
+                    .map(|(settled, hold)| (settled.clone(), hold.clone()))
+                    .collect()
+            }
+            (Some(_), None) | (None, Some(_)) => {
+                return Err(McpError::invalid_params(
+                    "give both keep_id and remove_id, or neither to pair holds automatically",
+                    None,
+                ));
+            }
+        };
+        if pairs.is_empty() {
+            return Err(McpError::invalid_params(
+                "no hold has a single matching settled transaction",
+                None,
+            ));
+        }
+        json_result(&self.stage_merges(pairs, &maps)?)
+    }
+
+    /// Marks transactions as viewed.
+    #[tool(
+        description = "Mark transactions as viewed, like reviewing new transactions in the mobile app. Select them either by transaction_ids or by filters (date range, account, tag, include_children, payee, merchant, amount range, uncategorized, transaction_type, user_id), not both. Transactions already viewed are left untouched. Returns the transactions that were marked. Use list_transactions with unviewed_only=true to find new transactions"
+    )]
+    async fn mark_viewed(
+        &self,
+        params: Parameters<MarkViewedParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        let candidates = self
+            .transactions_by_ids_or_selection(
+                params.0.transaction_ids.as_deref(),
+                &params.0.selection,
+                &maps,
+            )
+            .await?;
+
+        let preview: Vec<TransactionResponse> = self
+            .push_viewed(candidates)
+            .await?
+            .iter()
+            .map(|tx| TransactionResponse::from_transaction(tx, &maps))
+            .collect();
+        json_result(&preview)
+    }
+
+    /// Lists transactions needing attention since the last review.
+    #[tool(
+        description = "Inbox of transactions needing attention: those not viewed yet plus those created after the last review watermark (set by mark_reviewed). Returns counts (total, unviewed, created_since_review, uncategorized) and the newest transactions first (limit default 50, max 500). Start a review here, categorize or fix what it lists, then call mark_reviewed",
+        output_schema = cached_schema_for_type::<ReviewInboxResponse>()
+    )]
+    async fn inbox(&self, params: Parameters<InboxParams>) -> Result<CallToolResult, McpError> {
+        let limit = params
+            .0
+            .limit
+            .unwrap_or(DEFAULT_INBOX_LIMIT)
+            .min(MAX_TRANSACTION_LIMIT);
+        let watermark = self
+            .reviews
+            .mark()
+            .map_err(io_err)?
+            .map(|mark| mark.reviewed_through);
+        let maps = self.lookup_maps().await?;
+        let mut pending: Vec<Transaction> = self
+            .client
+            .transactions()
+            .await
+            .map_err(zen_err)?
+            .into_iter()
+            .filter(|tx| {
+                !tx.deleted
+                    && (tx.viewed != Some(true) || watermark.is_some_and(|mark| tx.created > mark))
+            })
+            .collect();
+        pending.sort_by(|left, right| right.created.cmp(&left.created));
+        let created_since_review = watermark.map_or(0, |mark| {
+            pending.iter().filter(|tx| tx.created > mark).count()
+        });
+        json_result(&ReviewInboxResponse {
+            reviewed_through: watermark.map(|mark| mark.to_rfc3339()),
+            total: pending.len(),
+            unviewed: pending.iter().filter(|tx| tx.viewed != Some(true)).count(),
+            created_since_review,
+            uncategorized: pending.iter().filter(|tx| needs_category(tx)).count(),
+            transactions: pending
+                .iter()
+                .take(limit)
+                .map(|tx| TransactionResponse::from_transaction(tx, &maps))
+                .collect(),
+        })
+    }
+
+    /// Saves or clears the default account for quick creates.
+    #[tool(
+        description = "Set the account create_transaction uses when account_id is omitted, so quick expenses like \"I spent 300 on coffee\" need no account. The choice is saved locally; omit account_id to clear it, falling back to ZENMONEY_DEFAULT_ACCOUNT if set. Returns {account_id, account, source} with source saved or environment, or nulls when no default is left",
+        output_schema = cached_schema_for_type::<DefaultAccountResponse>()
+    )]
+    async fn set_default_account(
+        &self,
+        params: Parameters<SetDefaultAccountParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        if let Some(account_id) = params.0.account_id.as_deref() {
+            let accounts = self.client.accounts().await.map_err(zen_err)?;
+            let account = accounts
+                .iter()
+                .find(|account| account.id.as_inner() == account_id)
+                .ok_or_else(|| not_found("account", account_id))?;
+            if account.archive {
+                return Err(McpError::invalid_params(
+                    format!("account {account_id} is archived"),
+                    None,
+                ));
+            }
+        }
+        self.default_account
+            .set(params.0.account_id)
+            .map_err(io_err)?;
+        let current = self.default_account.account().map_err(io_err)?;
+        json_result(&DefaultAccountResponse {
+            account: current.as_ref().map(|(id, _saved)| maps.account_name(id)),
+            source: current
+                .as_ref()
+                .map(|(_id, saved)| if *saved { "saved" } else { "environment" }.to_owned()),
+            account_id: current.map(|(id, _saved)| id),
+        })
+    }
+
+    /// Advances the inbox review watermark.
+    #[tool(
+        description = "Finish an inbox review: move the watermark to through (RFC 3339 timestamp or YYYY-MM-DD, default now) so inbox only lists transactions created after it, and mark unviewed transactions created up to it as viewed (set mark_viewed=false to keep them unviewed). The watermark is stored locally and cannot move backwards",
+        output_schema = cached_schema_for_type::<MarkReviewedResponse>()
+    )]
+    async fn mark_reviewed(
+        &self,
+        params: Parameters<MarkReviewedParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let MarkReviewedParams {
+            through,
+            mark_viewed,
+        } = params.0;
+        let now = Utc::now();
+        let reviewed_through = through
+            .as_deref()
+            .map(parse_timestamp)
+            .transpose()?
+            .unwrap_or(now);
+        let previous = self.reviews.mark().map_err(io_err)?;
+        if let Some(current) = previous.filter(|mark| mark.reviewed_through > reviewed_through) {
+            return Err(McpError::invalid_params(
+                format!(
+                    "through {} is before the current watermark {}",
+                    reviewed_through.to_rfc3339(),
+                    current.reviewed_through.to_rfc3339()
+                ),
+                None,
+            ));
+        }
+        let marked_viewed = if mark_viewed.unwrap_or(true) {
+            let reviewed: Vec<Transaction> = self
+                .client
+                .transactions()
+                .await
+                .map_err(zen_err)?
+                .into_iter()
+                .filter(|tx| !tx.deleted && tx.created <= reviewed_through)
+                .collect();
+            self.push_viewed(reviewed).await?.len()
+        } else {
+            0
+        };
+        self.reviews
+            .set(ReviewMark {
+                reviewed_through,
+                marked_at: now,
+            })
+            .map_err(io_err)?;
+        json_result(&MarkReviewedResponse {
+            reviewed_through: reviewed_through.to_rfc3339(),
+            previous: previous.map(|mark| mark.reviewed_through.to_rfc3339()),
+            marked_viewed,
+        })
+    }
+
+    /// Stages deletion of every transaction matching the filters.
+    #[tool(
+        description = "Stage deletion of all transactions matching the filters (date range, account, tag, include_children, payee, merchant, amount range, uncategorized, transaction_type, user_id). At least one filter is required and at most 500 transactions may match. Returns a preview with a preparation_id; nothing is deleted until execute_bulk_operations is called",
+        output_schema = cached_schema_for_type::<PrepareResponse>()
+    )]
+    async fn delete_transactions_by_filter(
+        &self,
+        params: Parameters<TransactionSelection>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        let transactions = self.selected_transactions(&params.0, &maps).await?;
+        let deleted_preview: Vec<TransactionResponse> = transactions
+            .iter()
+            .map(|tx| TransactionResponse::from_transaction(tx, &maps))
+            .collect();
+        let to_delete: Vec<TransactionId> = transactions.into_iter().map(|tx| tx.id).collect();
+        let deleted = to_delete.len();
+        let preparation_id = self.store_preparation(PreparedBulk {
+            to_push: Vec::new(),
+            to_delete,
+            created_count: 0,
+            updated_count: 0,
+            budgets: Vec::new(),
+        })?;
+        json_result(&PrepareResponse {
+            preparation_id,
+            created: 0,
+            updated: 0,
+            deleted,
+            transactions: Vec::new(),
+            deleted_transactions: deleted_preview,
+        })
+    }
+
+    /// Stages a balance-correction transaction for an account.
+    #[tool(
+        description = "Reconcile an account with its real-world balance. Computes delta = actual_balance - stored balance and stages a correction transaction (income if positive, expense if negative) dated date (default today). Returns the delta and a prepare preview with a preparation_id; pass it to execute_bulk_operations to commit",
+        output_schema = cached_schema_for_type::<ReconcileResponse>()
+    )]
+    async fn reconcile_account(
+        &self,
+        params: Parameters<ReconcileAccountParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        let accounts = self.client.accounts().await.map_err(zen_err)?;
+        let account = accounts
+            .iter()
+            .find(|acc| acc.id.as_inner() == params.0.account_id)
+            .ok_or_else(|| not_found("account", &params.0.account_id))?;
+        let stored_balance = account.balance.unwrap_or_default();
+        let delta = params.0.actual_balance - stored_balance;
+        let date = params
+            .0
+            .date
+            .unwrap_or_else(|| Utc::now().date_naive().format("%Y-%m-%d").to_string());
+        let comment = params
+            .0
+            .comment
+            .unwrap_or_else(|| "Balance correction".to_owned());
+        let create_params = balance_correction_params(&params.0.account_id, delta, date, comment)
+            .ok_or_else(|| {
+            McpError::invalid_params(
+                format!("account '{}' is already reconciled", account.title),
+                None,
+            )
+        })?;
+        let correction = build_transaction(create_params, &maps)?;
+
+        let preview = vec![TransactionResponse::from_transaction(&correction, &maps)];
+        let preparation_id = self.store_preparation(PreparedBulk {
+            to_push: vec![correction],
+            to_delete: Vec::new(),
+            created_count: 1,
+            updated_count: 0,
+            budgets: Vec::new(),
+        })?;
+        json_result(&ReconcileResponse {
+            account: account.title.clone(),
+            stored_balance,
+            actual_balance: params.0.actual_balance,
+            delta,
+            preparation: PrepareResponse {
+                preparation_id,
+                created: 1,
+                updated: 0,
+                deleted: 0,
+                transactions: preview,
+                deleted_transactions: Vec::new(),
+            },
+        })
+    }
+
+    /// Stages a transaction parsed from a Russian fiscal receipt QR code.
+    #[tool(
+        description = "Import a Russian fiscal receipt (FNS) QR code: parses its date, sum and fiscal fields (t, s, fn, i, fp, n) and stages a transaction on account_id with the QR string stored in qr_code. Sales become expenses and refunds become income. Optionally set tag_ids, payee, merchant_id, and comment (default: fiscal drive and document number). Returns the parsed receipt and a preview with a preparation_id; pass it to execute_bulk_operations to commit",
+        output_schema = cached_schema_for_type::<ReceiptImportResponse>()
+    )]
+    async fn import_receipt_qr(
+        &self,
+        params: Parameters<ImportReceiptQrParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        let receipt = parse_receipt_qr(&params.0.qr)?;
+        let qr_code = params.0.qr.trim().to_owned();
+        let mut transaction = build_transaction(receipt_create_params(&receipt, params.0), &maps)?;
+        transaction.qr_code = Some(qr_code);
+
+        let preview = vec![TransactionResponse::from_transaction(&transaction, &maps)];
+        let preparation_id = self.store_preparation(PreparedBulk {
+            to_push: vec![transaction],
+            to_delete: Vec::new(),
+            created_count: 1,
+            updated_count: 0,
+            budgets: Vec::new(),
+        })?;
+        json_result(&ReceiptImportResponse {
+            receipt_time: receipt.timestamp.to_string(),
+            receipt_amount: receipt.amount,
+            operation: receipt.operation.label().to_owned(),
+            fiscal_drive: receipt.fiscal_drive,
+            document_number: receipt.document_number,
+            fiscal_sign: receipt.fiscal_sign,
+            preparation: PrepareResponse {
+                preparation_id,
+                created: 1,
+                updated: 0,
+                deleted: 0,
+                transactions: preview,
+                deleted_transactions: Vec::new(),
+            },
+        })
+    }
+
+    /// Matches an OFX statement against recorded transactions and stages the new ones.
+    #[tool(
+        description = "Import an OFX bank or credit card statement (OFX 1 SGML or OFX 2 XML) into account_id, given as a file path or as content. Each entry is matched against the account's recorded transactions: by FITID (kept as the bank ID of imported transactions, and the transaction ID in export_ofx files), otherwise by signed amount within match_days (default 3) of its date. Verdicts: matched (already recorded, with its transaction_ids), new, or ambiguous (several candidates listed in transaction_ids; not staged, resolve by hand). Only new entries are staged, as expenses or income with the statement name as payee, memo as comment and optional tag_ids. Returns {account, currency, matched, new, ambiguous, rows, preparation}; pass preparation.preparation_id to execute_bulk_operations to commit. An unknown account_id fails with a not_found error",
+        output_schema = cached_schema_for_type::<OfxImportResponse>()
+    )]
+    async fn import_ofx(
+        &self,
+        params: Parameters<ImportOfxParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let ImportOfxParams {
+            path,
+            content,
+            account_id,
+            tag_ids,
+            match_days,
+        } = params.0;
+        let statement = parse_ofx(&statement_text(path, content)?)?;
+        let maps = self.lookup_maps().await?;
+        if !maps.has_account(&account_id) {
+            return Err(not_found("account", &account_id));
+        }
+        let transactions = self.client.transactions().await.map_err(zen_err)?;
+        let window = u64::from(match_days.unwrap_or(DEFAULT_OFX_MATCH_DAYS));
+        let matches = match_entries(&statement.entries, &account_id, &transactions, window);
+        let count = |verdict: ImportVerdict| {
+            matches
+                .iter()
+                .filter(|found| found.verdict == verdict)
+                .count()
+        };
+        let (matched, ambiguous) = (
+            count(ImportVerdict::Matched),
+            count(ImportVerdict::Ambiguous),
+        );
+        let mut to_push = Vec::new();
+        for (entry, found) in statement.entries.iter().zip(&matches) {
+            if found.verdict == ImportVerdict::New {
+                to_push.push(statement_transaction(
+                    entry,
+                    &account_id,
+                    tag_ids.clone(),
+                    &maps,
+                )?);
+            }
+        }
+        let new = to_push.len();
+        let preparation = if to_push.is_empty() {
+            None
+        } else {
+            Some(self.stage_creations(to_push, &maps)?)
+        };
+        json_result(&OfxImportResponse {
+            account: maps.account_name(&account_id),
+            currency: statement.currency,
+            matched,
+            new,
+            ambiguous,
+            rows: statement
+                .entries
+                .iter()
+                .zip(matches)
+                .map(|(entry, found)| OfxImportRow::new(entry, found))
+                .collect(),
+            preparation,
+        })
+    }
+
+    /// Updates an existing transaction.
+    #[tool(
+        description = "Update an existing transaction by ID. All fields except id are optional — only provided fields are changed. Use empty string for payee/comment/merchant_id to clear them. Set hold=true to mark it pending or hold=false to mark it settled. latitude/longitude must be given together. Set mcc to change the merchant category code (0 clears it). tag_ids replaces all tags; add_tag_ids/remove_tag_ids adjust the list instead (applied in that order, so removal wins). Amount is applied to the correct side (income/outcome) based on the transaction type"
+    )]
+    async fn update_transaction(
+        &self,
+        params: Parameters<UpdateTransactionParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        let all_transactions = self.client.transactions().await.map_err(zen_err)?;
+        let mut updated = all_transactions
+            .into_iter()
+            .find(|found_tx| found_tx.id.as_inner() == params.0.id)
+            .ok_or_else(|| not_found("transaction", &params.0.id))?;
+
+        apply_update(&mut updated, params.0, &maps)?;
+
+        let preview = TransactionResponse::from_transaction(&updated, &maps);
+        let updated_id = updated.id.to_string();
+        let pending = self
+            .push_or_queue("update_transaction", vec![updated])
+            .await?;
+        tracing::info!(id = %updated_id, "transaction updated");
+        self.refresh_resources().await;
+
+        Ok(with_pending_note(
+            json_result(&vec![preview])?,
+            pending.as_deref(),
+        ))
+    }
+
+    /// Wipes local data once `params` carries the confirmation phrase and the user agrees.
+    async fn purge_local_data_confirmed(
+        &self,
+        params: PurgeLocalDataParams,
+        confirmation: &Confirmation,
+    ) -> Result<CallToolResult, McpError> {
+        if params.confirm != PURGE_CONFIRMATION {
+            return Err(McpError::invalid_params(
+                format!("set confirm to \"{PURGE_CONFIRMATION}\" to delete local data"),
+                None,
+            ));
+        }
+        let prompt = "Delete all locally cached ZenMoney data, templates, envelopes and \
+                      queued offline writes? Data on the ZenMoney server is not affected."
+            .to_owned();
+        if !confirmation.ask(prompt).await? {
+            return json_result(&PurgeResponse::declined());
+        }
+
+        let _turn = self.write_turn().await?;
+        let cleared = self.clear_local_state()?;
+        let (stats, message) = match self.data_dir.as_deref() {
+            Some(dir) => (
+                purge_dir(dir).map_err(io_err)?,
+                "Local data deleted. Run full_sync to download it again".to_owned(),
+            ),
+            None => (
+                PurgeStats::default(),
+                "Nothing is stored on disk; synced data is discarded when the server exits"
+                    .to_owned(),
+            ),
+        };
+        tracing::warn!(files = stats.files, "local data purged");
+        json_result(&PurgeResponse {
+            files_deleted: stats.files,
+            bytes_overwritten: stats.bytes,
+            message,
+            ..cleared
+        })
+    }
+
+    /// Empties every in-memory store kept next to the ZenMoney data, so
+    /// nothing purged from disk lingers until the server exits.
+    fn clear_local_state(&self) -> Result<PurgeResponse, McpError> {
+        let preparations_discarded = {
+            let mut preparations = self
+                .preparations
+                .lock()
+                .map_err(|err| McpError::internal_error(format!("lock poisoned: {err}"), None))?;
+            let count = preparations.len();
+            preparations.clear();
+            count
+        };
+        Ok(PurgeResponse {
+            purged: true,
+            files_deleted: 0,
+            bytes_overwritten: 0,
+            templates_deleted: self.templates.clear().map_err(io_err)?,
+            envelopes_deleted: self.envelopes.clear().map_err(io_err)?,
+            preparations_discarded,
+            pending_discarded: self.pending.clear().map_err(io_err)?,
+            conflicts_discarded: self.conflicts.clear().map_err(io_err)?,
+            planned_cleared: self.planned.clear().map_err(io_err)?,
+            review_cleared: self.reviews.clear().map_err(io_err)?,
+            default_account_cleared: self.default_account.clear().map_err(io_err)?,
+            message: String::new(),
+        })
+    }
+
+    /// Deletes a transaction by ID, returning details of the deleted transaction.
+    #[tool(
+        description = "Delete a transaction by its ID. Returns {message, deleted, transaction} with details of the deleted transaction (deleted is false when the user declined the confirmation prompt). An unknown ID fails with a not_found error and deletes nothing"
+    )]
+    async fn delete_transaction(
+        &self,
+        params: Parameters<DeleteTransactionParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let confirmation = Confirmation::new(self.confirm_destructive, &context);
+        self.delete_transaction_confirmed(params.0, &confirmation)
+            .await
+    }
+
+    /// Deletes a transaction once the user has confirmed it.
+    async fn delete_transaction_confirmed(
+        &self,
+        params: DeleteTransactionParams,
+        confirmation: &Confirmation,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+
+        // Fetch the transaction details before deleting.
+        let all_transactions = self.client.transactions().await.map_err(zen_err)?;
+        let Some(found_tx) = all_transactions
+            .iter()
+            .find(|found_tx| found_tx.id.as_inner() == params.id)
+        else {
+            return Err(not_found("transaction", &params.id));
+        };
+
+        let prompt = format!(
+            "Delete the transaction of {} on {} ({})? This cannot be undone.",
+            amount_totals([found_tx], &maps),
+            found_tx.date,
+            transaction_label(found_tx, &maps)
+        );
+        let tx_response = TransactionResponse::from_transaction(found_tx, &maps);
+        if !confirmation.ask(prompt).await? {
+            return json_result(&DeletedTransactionResponse::declined(
+                "Deletion declined by the user; nothing was changed".to_owned(),
+                tx_response,
+            ));
+        }
+
+        let _turn = self.write_turn().await?;
+        let delete_id = TransactionId::new(params.id.clone());
+        self.throttle.pace().await;
+        let _response = self
+            .client
+            .delete_transactions(&[delete_id])
+            .await
+            .map_err(zen_err)?;
+        tracing::info!(id = %params.id, "transaction deleted");
+        self.refresh_resources().await;
+
+        let result = DeletedTransactionResponse::new(
+            format!("Transaction '{}' deleted successfully", params.id),
+            tx_response,
+        );
+        json_result(&result)
+    }
+
+    /// Restores a deleted transaction by pushing it back as not deleted.
+    #[tool(
+        description = "Restore a deleted transaction by ID (see list_deleted_transactions). The stored record is pushed back with deleted=false. Returns the restored transaction"
+    )]
+    async fn restore_transaction(
+        &self,
+        params: Parameters<RestoreTransactionParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        let all_transactions = self.client.transactions().await.map_err(zen_err)?;
+        let mut restored = all_transactions
+            .into_iter()
+            .find(|found_tx| found_tx.id.as_inner() == params.0.id)
+            .ok_or_else(|| McpError {
+                message: format!(
+                    "transaction '{}' not found in local storage; run sync first",
+                    params.0.id
+                )
+                .into(),
+                ..not_found("transaction", &params.0.id)
+            })?;
+        if !restored.deleted {
+            return Err(McpError::invalid_params(
+                format!("transaction '{}' is not deleted", params.0.id),
+                None,
+            ));
+        }
+
+        restored.deleted = false;
+        restored.changed = Utc::now();
+        let preview = TransactionResponse::from_transaction(&restored, &maps);
+        let pending = self
+            .push_or_queue("restore_transaction", vec![restored])
+            .await?;
+        tracing::info!(id = %params.0.id, "transaction restored");
+        self.refresh_resources().await;
+
+        Ok(with_pending_note(
+            json_result(&vec![preview])?,
+            pending.as_deref(),
+        ))
+    }
+
+    /// Marks a reminder marker processed or deleted, or converts it into a transaction.
+    #[tool(
+        description = "Change the state of a reminder marker (see list_reminder_markers). action=processed marks the occurrence done without recording anything, action=deleted skips it, and action=convert records a transaction with the marker's date, accounts, amounts, tags, merchant, payee and comment, linked to the marker, then marks it processed. Only planned markers can be converted. Returns {marker, transaction}; an unknown marker ID fails with a not_found error",
+        output_schema = cached_schema_for_type::<MarkerStateResponse>()
+    )]
+    async fn set_marker_state(
+        &self,
+        params: Parameters<SetMarkerStateParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let maps = self.lookup_maps().await?;
+        let markers = self.client.reminder_markers().await.map_err(zen_err)?;
+        let Some(mut marker) = markers
+            .into_iter()
+            .find(|found| found.id.as_inner() == params.0.marker_id)
+        else {
+            return Err(not_found("reminder_marker", &params.0.marker_id));
+        };
+        let convert = params.0.action == MarkerAction::Convert;
+        if convert && marker.state != ReminderMarkerState::Planned {
+            return Err(McpError::invalid_params(
+                format!(
+                    "reminder marker '{}' is {}; only planned markers can be converted",
+                    params.0.marker_id,
+                    marker_state_label(marker.state)
+                ),
+                None,
+            ));
+        }
+
+        let transaction = convert.then(|| transaction_from_marker(&marker));
+        marker.state = if params.0.action == MarkerAction::Deleted {
+            ReminderMarkerState::Deleted
+        } else {
+            ReminderMarkerState::Processed
+        };
+        marker.changed = Utc::now();
+        self.throttle.pace().await;
+        let _response = self
+            .client
+            .push_reminder_markers(vec![marker.clone()])
+            .await
+            .map_err(zen_err)?;
+        let response = MarkerStateResponse {
+            marker: ReminderMarkerResponse::from_marker(&marker, &maps),
+            transaction: transaction
+                .as_ref()
+                .map(|new_tx| TransactionResponse::from_transaction(new_tx, &maps)),
+        };
+        let pending = match transaction {
+            Some(new_tx) => self.push_or_queue("set_marker_state", vec![new_tx]).await?,
+            None => None,
+        };
+        tracing::info!(id = %params.0.marker_id, "reminder marker updated");
+        self.refresh_resources().await;
+
+        Ok(with_pending_note(
+            json_result(&response)?,
+            pending.as_deref(),
+        ))
+    }
+
+    /// Validates and prepares bulk operations without executing them.
+    ///
+    /// Returns a preview with a `preparation_id` that can be passed to
+    /// `execute_bulk_operations` to commit the changes.
+    #[tool(
+        description = "Validate and preview multiple transaction operations (create, update, delete) without executing them. Returns an enriched preview of all changes and a preparation_id. Pass the preparation_id to execute_bulk_operations to commit the changes. IMPORTANT: limit to 10 operations per call to avoid transport timeouts; split larger batches into multiple prepare calls",
+        output_schema = cached_schema_for_type::<PrepareResponse>()
+    )]
+    async fn prepare_bulk_operations(
+        &self,
+        params: Parameters<BulkOperationsParams>,
+    ) -> Result<CallToolResult, McpError> {
+        tracing::debug!("prepare_bulk_operations: start");
+
+        if params.0.operations.len() > MAX_BULK_OPERATIONS {
+            return Err(McpError::invalid_params(
+                format!(
+                    "too many operations ({}); limit is {MAX_BULK_OPERATIONS} per call — split into smaller batches",
+                    params.0.operations.len()
+                ),
+                None,
+            ));
+        }
+
+        let maps = self.lookup_maps().await?;
+        tracing::debug!("prepare_bulk_operations: lookup_maps done");
+
+        let all_transactions = self.client.transactions().await.map_err(zen_err)?;
+        tracing::debug!(
+            count = all_transactions.len(),
+            "prepare_bulk_operations: loaded transactions"
+        );
+
+        let (to_push, to_delete, created_count, updated_count) =
+            process_bulk_operations(params.0.operations, &all_transactions, &maps)?;
+        tracing::debug!(
+            created_count,
+            updated_count,
+            deleted = to_delete.len(),
+            "prepare_bulk_operations: processed operations"
+        );
+
+        let preview: Vec<TransactionResponse> = to_push
+            .iter()
+            .map(|tx| TransactionResponse::from_transaction(tx, &maps))
+            .collect();
+        let deleted_preview: Vec<TransactionResponse> = to_delete
+            .iter()
+            .filter_map(|del_id| {
+                all_transactions
+                    .iter()
+                    .find(|tx| tx.id.as_inner() == del_id.as_inner())
+            })
+            .map(|tx| TransactionResponse::from_transaction(tx, &maps))
+            .collect();
+
+        let deleted = to_delete.len();
+        let preparation_id = self.store_preparation(PreparedBulk {
+            to_push,
+            to_delete,
+            created_count,
+            updated_count,
+            budgets: Vec::new(),
+        })?;
+        let result = PrepareResponse {
+            preparation_id,
+            created: created_count,
+            updated: updated_count,
+            deleted,
+            transactions: preview,
+            deleted_transactions: deleted_preview,
+        };
+
+        tracing::debug!("prepare_bulk_operations: done");
+        json_result(&result)
+    }
+
+    /// Executes a previously prepared bulk operation.
+    ///
+    /// Takes the `preparation_id` from `prepare_bulk_operations` and commits
+    /// the changes to ZenMoney.
+    #[tool(
+        description = "Execute a previously prepared bulk operation by its preparation_id (obtained from prepare_bulk_operations or another staging tool such as suggest_budgets). Commits the validated changes to ZenMoney and returns a summary of affected transactions and the number of budgets saved. If the call is cancelled or ZenMoney rejects a batch, it stops before the next batch of 100 and the summary covers only the changes sent, with cancelled {reason, preparation_id, preparation_kept, remaining_preparation_id, error, done, total}: the preparation is kept when nothing was sent, and otherwise the unsent changes are staged under remaining_preparation_id",
+        output_schema = cached_schema_for_type::<BulkOperationsResponse>()
+    )]
+    async fn execute_bulk_operations(
+        &self,
+        params: Parameters<ExecuteBulkParams>,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let progress = Progress::from_context(&context);
+        let confirmation = Confirmation::new(self.confirm_destructive, &context);
+        self.execute_preparation(&params.0.preparation_id, &progress, &confirmation)
+            .await
+    }
+
+    /// Builds the response of a bulk commit and the confirmation prompt describing it.
+    async fn bulk_summary(
+        &self,
+        prepared: &PreparedBulk,
+    ) -> Result<(BulkOperationsResponse, String), McpError> {
+        let maps = self.lookup_maps().await?;
+
+        // Build previews from local data before consuming prepared transactions.
+        let push_preview: Vec<TransactionResponse> = prepared
+            .to_push
+            .iter()
+            .map(|tx| TransactionResponse::from_transaction(tx, &maps))
+            .collect();
+
+        // Look up deleted transactions before deleting.
+        let mut deleted_preview: Vec<TransactionResponse> = Vec::new();
+        let mut deleted_totals = String::new();
+        let deleted_count = prepared.to_delete.len();
+        if !prepared.to_delete.is_empty() {
+            let all_transactions = self.client.transactions().await.map_err(zen_err)?;
+            let deleted: Vec<&Transaction> = prepared
+                .to_delete
+                .iter()
+                .filter_map(|del_id| {
+                    all_transactions
+                        .iter()
+                        .find(|tx| tx.id.as_inner() == del_id.as_inner())
+                })
+                .collect();
+            deleted_totals = amount_totals(deleted.iter().copied(), &maps);
+            deleted_preview = deleted
+                .into_iter()
+                .map(|tx| TransactionResponse::from_transaction(tx, &maps))
+                .collect();
+        }
+
+        let budget_count = prepared.budgets.len();
+        let mut prompt = format!(
+            "Commit {} created, {} updated and {deleted_count} deleted transactions?",
+            prepared.created_count, prepared.updated_count
+        );
+        if budget_count > 0 {
+            prompt = format!("{prompt} Save {budget_count} budgets?");
+        }
+        if !deleted_totals.is_empty() {
+            prompt = format!("{prompt} Deleted amounts: {deleted_totals}.");
+        }
+        let result = BulkOperationsResponse::new(
+            prepared.created_count,
+            prepared.updated_count,
+            deleted_count,
+            push_preview,
+            deleted_preview,
+        )
+        .with_budgets(budget_count);
+        Ok((result, prompt))
+    }
+
+    /// Commits a prepared bulk operation once confirmed, reporting progress per batch.
+    async fn execute_preparation(
+        &self,
+        preparation_id: &str,
+        progress: &Progress,
+        confirmation: &Confirmation,
+    ) -> Result<CallToolResult, McpError> {
+        let existing = self.transaction_ids().await?;
+        let prepared = self.take_preparation(preparation_id)?;
+        let (result, prompt) = self.bulk_summary(&prepared).await?;
+        let mut cancellation = BulkCancellation::declined(preparation_id, prepared.change_count());
+        if !confirmation.ask(prompt).await? {
+            return self
+                .bulk_stopped(prepared, result, cancellation, &existing)
+                .await;
+        }
+        let _turn = match self.write_turn().await {
+            Ok(turn) => turn,
+            Err(err) => {
+                self.keep_preparation(preparation_id, prepared)?;
+                return Err(err);
+            }
+        };
+
+        match self
+            .send_bulk(&prepared, progress, &mut cancellation.done)
+            .await
+        {
+            Ok(true) => {}
+            Ok(false) => {
+                cancellation.reason = "cancelled".to_owned();
+                return self
+                    .bulk_stopped(prepared, result, cancellation, &existing)
+                    .await;
+            }
+            Err(err) if cancellation.done == 0 => {
+                self.keep_preparation(preparation_id, prepared)?;
+                return Err(err);
+            }
+            Err(err) => {
+                cancellation.reason = "failed".to_owned();
+                cancellation.error = Some(err.message.into_owned());
+                return self
+                    .bulk_stopped(prepared, result, cancellation, &existing)
+                    .await;
+            }
+        }
+
+        tracing::info!(
+            created = prepared.created_count,
+            updated = prepared.updated_count,
+            deleted = prepared.to_delete.len(),
+            budgets = prepared.budgets.len(),
+            "bulk operation committed"
+        );
+        self.refresh_resources().await;
+        json_result(&result)
+    }
+
+    /// Pushes and deletes the transactions of `prepared` in batches, then
+    /// saves its budgets, counting the changes sent in `done`; returns
+    /// `false` if the call was cancelled first.
+    async fn send_bulk(
+        &self,
+        prepared: &PreparedBulk,
+        progress: &Progress,
+        done: &mut usize,
+    ) -> Result<bool, McpError> {
+        let total = prepared.change_count();
+        let pushed = progress
+            .send_batches(
+                prepared.to_push.chunks(BULK_EXECUTE_BATCH_SIZE),
+                total,
+                done,
+                |batch| async move {
+                    self.throttle.pace().await;
+                    self.client
+                        .push_transactions(batch)
+                        .await
+                        .map(drop)
+                        .map_err(zen_err)
+                },
+            )
+            .await?;
+        let sent = pushed
+            && progress
+                .send_batches(
+                    prepared.to_delete.chunks(BULK_EXECUTE_BATCH_SIZE),
+                    total,
+                    done,
+                    |batch| async move {
+                        self.throttle.pace().await;
+                        self.client
+                            .delete_transactions(&batch)
+                            .await
+                            .map(drop)
+                            .map_err(zen_err)
+                    },
+                )
+                .await?;
+        if !sent || prepared.budgets.is_empty() {
+            return Ok(sent);
+        }
+        if progress.is_cancelled() {
+            return Ok(false);
+        }
+        self.throttle.pace().await;
+        let _response = self
+            .client
+            .push_budgets(prepared.budgets.clone())
+            .await
+            .map_err(zen_err)?;
+        *done = total;
+        progress
+            .report(
+                total,
+                Some(total),
+                format!("Saved {total} of {total} changes"),
+            )
+            .await;
+        Ok(true)
+    }
+
+    /// Returns the IDs of all locally synced transactions.
+    async fn transaction_ids(&self) -> Result<HashSet<String>, McpError> {
+        Ok(self
+            .client
+            .transactions()
+            .await
+            .map_err(zen_err)?
+            .into_iter()
+            .map(|tx| tx.id.to_string())
+            .collect())
+    }
+
+    /// Removes the preparation with `preparation_id`, so it runs only once.
+    fn take_preparation(&self, preparation_id: &str) -> Result<PreparedBulk, McpError> {
+        self.preparations
+            .lock()
+            .map_err(|err| McpError::internal_error(format!("lock poisoned: {err}"), None))?
+            .remove(preparation_id)
+            .ok_or_else(|| McpError {
+                message: format!("preparation '{preparation_id}' not found or already executed")
+                    .into(),
+                ..not_found("preparation", preparation_id)
+            })
+    }
+
+    /// Puts `prepared` back under `preparation_id` so it can be executed later.
+    fn keep_preparation(
+        &self,
+        preparation_id: &str,
+        prepared: PreparedBulk,
+    ) -> Result<(), McpError> {
+        let _previous = self
+            .preparations
+            .lock()
+            .map_err(|err| McpError::internal_error(format!("lock poisoned: {err}"), None))?
+            .insert(preparation_id.to_owned(), prepared);
+        Ok(())
+    }
+
+    /// Stops a bulk commit after the first `cancellation.done` changes of
+    /// `prepared` were sent, reporting only those.
+    ///
+    /// When nothing was sent the preparation is kept under its ID, so it can
+    /// be executed again; otherwise the unsent changes are staged as a new
+    /// preparation, whose ID the report carries.
+    async fn bulk_stopped(
+        &self,
+        mut prepared: PreparedBulk,
+        result: BulkOperationsResponse,
+        mut cancellation: BulkCancellation,
+        existing: &HashSet<String>,
+    ) -> Result<CallToolResult, McpError> {
+        tracing::warn!(
+            done = cancellation.done,
+            total = cancellation.total,
+            reason = %cancellation.reason,
+            "bulk operation stopped"
+        );
+        let rest = prepared.split_off(cancellation.done, existing);
+        let result = result.only_sent(
+            prepared.created_count,
+            prepared.updated_count,
+            prepared.to_delete.len(),
+            prepared.budgets.len(),
+        );
+        if cancellation.done == 0 {
+            self.keep_preparation(&cancellation.preparation_id, rest)?;
+            cancellation.preparation_kept = true;
+        } else {
+            if rest.change_count() > 0 {
+                cancellation.remaining_preparation_id = Some(self.store_preparation(rest)?);
+            }
+            self.refresh_resources().await;
+        }
+        json_result(&result.with_cancellation(cancellation))
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::shadow_reuse,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect and shadow reuse for readability"
+)]
+mod tests {
+    use super::*;
+    use crate::params::{LedgerDialect, NearFilter, TransactionField};
+    use crate::persist::TempDir;
+    use chrono::DateTime;
+
+    fn test_timestamp() -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp for test")
+    }
+
+    fn test_date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 6, 15).expect("valid date for test")
+    }
+
+    fn sample_maps() -> LookupMaps {
+        use zenmoney_rs::models::{Account, AccountType, Instrument, Tag};
+
+        let accounts = vec![
+            Account {
+                id: AccountId::new("acc-1".to_owned()),
+                changed: test_timestamp(),
+                user: UserId::new(1),
+                role: None,
+                instrument: Some(InstrumentId::new(1)),
+                company: None,
+                kind: AccountType::Checking,
+                title: "Main Account".to_owned(),
+                sync_id: None,
+                balance: Some(50_000.0),
+                start_balance: None,
+                credit_limit: None,
+                in_balance: true,
+                savings: None,
+                enable_correction: false,
+                enable_sms: false,
+                archive: false,
+                capitalization: None,
+                percent: None,
+                start_date: None,
+                end_date_offset: None,
+                end_date_offset_interval: None,
+                payoff_step: None,
+                payoff_interval: None,
+                balance_correction_type: None,
+                private: None,
+            },
+            Account {
+                id: AccountId::new("acc-2".to_owned()),
+                changed: test_timestamp(),
+                user: UserId::new(1),
+                role: None,
+                instrument: Some(InstrumentId::new(2)),
+                company: None,
+                kind: AccountType::Cash,
+                title: "USD Account".to_owned(),
+                sync_id: None,
+                balance: Some(1_000.0),
+                start_balance: None,
+                credit_limit: None,
+                in_balance: true,
+                savings: None,
+                enable_correction: false,
+                enable_sms: false,
+                archive: false,
+                capitalization: None,
+                percent: None,
+                start_date: None,
+                end_date_offset: None,
+                end_date_offset_interval: None,
+                payoff_step: None,
+                payoff_interval: None,
+                balance_correction_type: None,
+                private: None,
+            },
+        ];
+        let tags = vec![Tag {
+            id: TagId::new("tag-1".to_owned()),
+            changed: test_timestamp(),
+            user: UserId::new(1),
+            title: "Groceries".to_owned(),
+            parent: None,
+            icon: None,
+            picture: None,
+            color: None,
+            show_income: false,
+            show_outcome: true,
+            budget_income: false,
+            budget_outcome: true,
+            required: None,
+            static_id: None,
+            archive: None,
+        }];
+        let instruments = vec![
+            Instrument {
+                id: InstrumentId::new(1),
+                changed: test_timestamp(),
+                title: "Russian Ruble".to_owned(),
+                short_title: "RUB".to_owned(),
+                symbol: "\u{20bd}".to_owned(),
+                rate: 1.0,
+            },
+            Instrument {
+                id: InstrumentId::new(2),
+                changed: test_timestamp(),
+                title: "US Dollar".to_owned(),
+                short_title: "USD".to_owned(),
+                symbol: "$".to_owned(),
+                rate: 90.0,
+            },
+        ];
+        build_lookup_maps(&accounts, &tags, &instruments)
+    }
+
+    fn sample_transaction(id: &str, outcome: f64, income: f64) -> Transaction {
+        Transaction {
+            id: TransactionId::new(id.to_owned()),
+            changed: test_timestamp(),
+            created: test_timestamp(),
+            user: UserId::new(1),
+            deleted: false,
+            hold: None,
+            income_instrument: InstrumentId::new(1),
+            income_account: AccountId::new("acc-1".to_owned()),
+            income,
+            outcome_instrument: InstrumentId::new(1),
+            outcome_account: AccountId::new("acc-1".to_owned()),
+            outcome,
+            tag: None,
+            merchant: None,
+            payee: None,
+            original_payee: None,
+            comment: None,
+            date: test_date(),
+            mcc: None,
+            reminder_marker: None,
+            op_income: None,
+            op_income_instrument: None,
+            op_outcome: None,
+            op_outcome_instrument: None,
+            latitude: None,
+            longitude: None,
+            income_bank_id: None,
+            outcome_bank_id: None,
+            qr_code: None,
+            source: None,
+            viewed: None,
+        }
+    }
+
+    fn sample_marker(id: &str, day: u32, state: ReminderMarkerState) -> ReminderMarker {
+        use zenmoney_rs::models::{ReminderId, ReminderMarkerId};
+        ReminderMarker {
+            id: ReminderMarkerId::new(id.to_owned()),
+            changed: test_timestamp(),
+            user: UserId::new(1),
+            income_instrument: InstrumentId::new(1),
+            income_account: AccountId::new("acc-1".to_owned()),
+            income: 0.0,
+            outcome_instrument: InstrumentId::new(1),
+            outcome_account: AccountId::new("acc-1".to_owned()),
+            outcome: 5000.0,
+            tag: Some(vec![TagId::new("tag-1".to_owned())]),
+            merchant: None,
+            payee: Some("Landlord".to_owned()),
+            comment: None,
+            date: NaiveDate::from_ymd_opt(2024, 7, day).expect("valid date"),
+            reminder: ReminderId::new("rem-1".to_owned()),
+            state,
+            notify: false,
+            is_forecast: false,
+        }
+    }
+
+    fn sample_transfer(id: &str, outcome: f64, income: f64) -> Transaction {
+        let mut tx = sample_transaction(id, outcome, income);
+        tx.outcome_account = AccountId::new("acc-1".to_owned());
+        tx.income_account = AccountId::new("acc-2".to_owned());
+        tx.income_instrument = InstrumentId::new(2);
+        tx
+    }
+
+    fn sample_create_params(tx_type: TransactionType) -> CreateTransactionParams {
+        CreateTransactionParams {
+            transaction_type: tx_type,
+            date: "2024-06-15".to_owned(),
+            account_id: "acc-1".to_owned(),
+            amount: 500.0,
+            to_account_id: None,
+            to_amount: None,
+            instrument_id: None,
+            to_instrument_id: None,
+            tag_ids: None,
+            payee: None,
+            comment: None,
+            merchant_id: None,
+            latitude: None,
+            longitude: None,
+            mcc: None,
+            planned: None,
+            allow_out_of_range: None,
+        }
+    }
+
+    fn sample_create_tag_params(title: &str) -> CreateTagParams {
+        CreateTagParams {
+            title: title.to_owned(),
+            parent_tag_id: None,
+            icon: None,
+            color: None,
+            show_income: None,
+            show_outcome: None,
+            budget_income: None,
+            budget_outcome: None,
+            required: None,
+        }
+    }
+
+    // ── parse_date ──────────────────────────────────────────────────
+
+    #[test]
+    fn parse_date_valid() {
+        let date = parse_date("2024-06-15").expect("valid date");
+        assert_eq!(date, NaiveDate::from_ymd_opt(2024, 6, 15).expect("valid"));
+    }
+
+    #[test]
+    fn parse_date_invalid_format() {
+        let result = parse_date("15-06-2024");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parse_date_invalid_date() {
+        let result = parse_date("2024-13-40");
+        assert!(result.is_err());
+    }
+
+    // ── tag helpers ────────────────────────────────────────────────
+
+    #[test]
+    fn normalize_tag_title_trims_text() {
+        let normalized = normalize_title("  Rent an apartment  ").expect("valid title");
+        assert_eq!(normalized, "Rent an apartment");
+    }
+
+    #[test]
+    fn normalize_tag_title_blank_errors() {
+        let result = normalize_title("   ");
+        assert!(result.is_err());
+    }
+
+    // ── merchant helpers ────────────────────────────────────────────
+
+    fn sample_merchants() -> Vec<Merchant> {
+        ["m-1", "m-2", "m-3"]
+            .iter()
+            .map(|id| Merchant {
+                id: MerchantId::new((*id).to_owned()),
+                changed: test_timestamp(),
+                user: UserId::new(1),
+                title: format!("Merchant {id}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn validate_merge_sources_dedups() {
+        let merchants = sample_merchants();
+        let source_ids = vec!["m-2".to_owned(), "m-3".to_owned(), "m-2".to_owned()];
+        let sources = validate_merge_sources(&merchants, &source_ids, "m-1").expect("valid merge");
+        assert_eq!(sources.len(), 2);
+    }
+
+    #[test]
+    fn validate_merge_sources_rejects_target_in_sources() {
+        let merchants = sample_merchants();
+        let source_ids = vec!["m-1".to_owned()];
+        assert!(validate_merge_sources(&merchants, &source_ids, "m-1").is_err());
+    }
+
+    #[test]
+    fn validate_merge_sources_rejects_unknown_and_empty() {
+        let merchants = sample_merchants();
+        assert!(validate_merge_sources(&merchants, &["missing".to_owned()], "m-1").is_err());
+        assert!(validate_merge_sources(&merchants, &["m-2".to_owned()], "missing").is_err());
+        assert!(validate_merge_sources(&merchants, &[], "m-1").is_err());
+    }
+
+    #[test]
+    fn build_merchant_sets_fields() {
+        let merchant = build_merchant("Bakery".to_owned(), 7);
+        assert_eq!(merchant.title, "Bakery");
+        assert_eq!(merchant.user, UserId::new(7));
+        assert!(!merchant.id.as_inner().is_empty());
+    }
+
+    #[test]
+    fn find_tag_by_title_case_insensitive_matches_existing() {
+        let tags = vec![Tag {
+            id: TagId::new("tag-1".to_owned()),
+            changed: test_timestamp(),
+            user: UserId::new(1),
+            title: "Groceries".to_owned(),
+            parent: None,
+            icon: None,
+            picture: None,
+            color: None,
+            show_income: false,
+            show_outcome: true,
+            budget_income: false,
+            budget_outcome: true,
+            required: None,
+            static_id: None,
+            archive: None,
+        }];
+        let key = "gRoCeRiEs";
+        let tag = find_tag_by_title_case_insensitive(&tags, key);
+        assert!(tag.is_some());
+    }
+
+    #[test]
+    fn build_tag_uses_expense_defaults() {
+        let params = sample_create_tag_params("Utilities");
+        let tag = build_tag(params, 5, "Utilities".to_owned());
+        assert_eq!(tag.title, "Utilities");
+        assert_eq!(tag.user, UserId::new(5));
+        assert!(!tag.show_income);
+        assert!(tag.show_outcome);
+        assert!(!tag.budget_income);
+        assert!(tag.budget_outcome);
+        assert_eq!(tag.archive, Some(false));
+    }
+
+    // ── to_json_text / json_result ──────────────────────────────────
+
+    #[test]
+    fn to_json_text_serializes_pretty() {
+        #[derive(serde::Serialize)]
+        struct Simple {
+            name: String,
+        }
+        let val = Simple {
+            name: "test".to_owned(),
+        };
+        let text = to_json_text(&val).expect("should serialize");
+        assert!(text.contains("\"name\": \"test\""));
+        // Pretty-printed means it has newlines.
+        assert!(text.contains('\n'));
+    }
+
+    #[test]
+    fn json_result_returns_call_tool_result() {
+        let val = vec![1, 2, 3];
+        let result = json_result(&val).expect("should produce result");
+        assert!(!result.is_error.unwrap_or(false));
+        assert!(!result.content.is_empty());
+        assert!(result.structured_content.is_none());
+    }
+
+    #[test]
+    fn json_result_adds_structured_content_for_objects() {
+        let val = DigestAmount {
+            label: "Groceries".to_owned(),
+            amount: 12.5,
+        };
+        let result = json_result(&val).expect("should produce result");
+        let structured = result.structured_content.expect("structured content");
+        assert_eq!(structured["label"], "Groceries");
+        assert_eq!(structured["amount"], 12.5);
+    }
+
+    #[tokio::test]
+    async fn report_tools_advertise_output_schema() {
+        let server = build_test_server().await;
+        let tools = server.tool_router.list_all();
+        let digest = tools
+            .iter()
+            .find(|tool| tool.name == "digest")
+            .expect("digest tool");
+        let schema = digest.output_schema.as_ref().expect("output schema");
+        assert!(schema.contains_key("properties"));
+        let sync = tools
+            .iter()
+            .find(|tool| tool.name == "sync")
+            .expect("sync tool");
+        assert!(sync.output_schema.is_some());
+        let list_tags = tools
+            .iter()
+            .find(|tool| tool.name == "list_tags")
+            .expect("list_tags tool");
+        assert!(list_tags.output_schema.is_none());
+    }
+
+    #[tokio::test]
+    async fn read_tools_exist() {
+        let server = build_test_server().await;
+        let names: HashSet<String> = server
+            .tool_router
+            .list_all()
+            .into_iter()
+            .map(|tool| tool.name.into_owned())
+            .collect();
+        for name in READ_TOOLS {
+            assert!(names.contains(*name), "unknown read tool {name}");
+        }
+    }
+
+    #[tokio::test]
+    async fn write_tools_exist() {
+        let server = build_test_server().await;
+        let names: HashSet<String> = server
+            .tool_router
+            .list_all()
+            .into_iter()
+            .map(|tool| tool.name.into_owned())
+            .collect();
+        for name in WRITE_TOOLS
+            .iter()
+            .chain(CONFIRMED_WRITE_TOOLS)
+            .chain(LOCAL_WRITE_TOOLS)
+        {
+            assert!(names.contains(*name), "unknown write tool {name}");
+        }
+        for name in CONFIRMED_WRITE_TOOLS.iter().chain(LOCAL_WRITE_TOOLS) {
+            assert!(WRITE_TOOLS.contains(name), "{name} is not in WRITE_TOOLS");
+        }
+    }
+
+    #[test]
+    fn tools_that_write_are_write_tools() {
+        const MARKERS: &[&str] = &["push_", ".save(", ".set(", ".remove(", "restore_local("];
+        let source = include_str!("server.rs");
+        let (code, _tests) = source
+            .split_once("#[cfg(test)]\n#[allow(")
+            .expect("test module");
+        for tool in code.split("#[tool(").skip(1) {
+            let name = tool
+                .split_once("async fn ")
+                .and_then(|(_, rest)| rest.split_once('('))
+                .map(|(name, _)| name)
+                .expect("tool function");
+            let body = tool.split_once("\n    }\n").map_or(tool, |(body, _)| body);
+            if let Some(marker) = MARKERS.iter().find(|marker| body.contains(**marker)) {
+                assert!(
+                    WRITE_TOOLS.contains(&name),
+                    "{name} writes ({marker}) but is not in WRITE_TOOLS"
+                );
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn write_results_note_the_ordering() {
+        let server = build_test_server().await;
+        let messages = with_handshake([
+            tool_call(
+                2,
+                "set_default_account",
+                serde_json::json!({ "account_id": "acc-1" }),
+            ),
+            tool_call(3, "list_accounts", serde_json::json!({})),
+        ]);
+        let responses = exchange(server, &messages, &[2, 3]).await;
+
+        let write = &responses[&2]["result"];
+        assert_ne!(write["isError"], true, "{write}");
+        assert_eq!(write["content"][1]["text"], WRITE_ORDER_NOTE);
+        let read = &responses[&3]["result"];
+        assert_eq!(
+            read["content"].as_array().expect("content").len(),
+            1,
+            "{read}"
+        );
+    }
+
+    #[tokio::test]
+    async fn server_info_reports_build_and_limits() {
+        let server = build_test_server()
+            .await
+            .with_max_data_age(Some(Duration::from_secs(600)));
+        let result = server.server_info().await.expect("should report");
+        let info: serde_json::Value =
+            serde_json::from_str(result_text(&result)).expect("valid json");
+        assert_eq!(info["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(info["storage"], "InMemoryStorage");
+        assert_eq!(info["limits"]["max_data_age_minutes"], 10);
+        assert_eq!(info["limits"]["min_request_interval_ms"], 200);
+        assert!(
+            info["features"]
+                .as_array()
+                .expect("features")
+                .contains(&serde_json::json!("auto_sync"))
+        );
+    }
+
+    #[tokio::test]
+    async fn base_currency_sets_default_totals_currency() {
+        let server = build_test_server()
+            .await
+            .with_base_currency(Some(" usd ".to_owned()));
+        let result = server.server_info().await.expect("should report");
+        let info: serde_json::Value =
+            serde_json::from_str(result_text(&result)).expect("valid json");
+        assert_eq!(info["base_currency"], "USD");
+
+        let result = server
+            .list_transactions(Parameters(ListTransactionsParams::default()))
+            .await
+            .expect("should list");
+        let page = parse_paginated(&result);
+        assert_eq!(page["summary"]["currency"], "$");
+        assert!(page["items"][0].get("converted_amount").is_none());
+
+        let unknown = build_test_server()
+            .await
+            .with_base_currency(Some("XYZ".to_owned()));
+        assert!(unknown.server_info().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn demo_data_seeds_local_storage() {
+        let client = ZenMoney::builder()
+            .token("demo")
+            .storage(InMemoryStorage::new())
+            .build()
+            .expect("should build demo client");
+        let server = ZenMoneyMcpServer::new(client)
+            .with_demo_data()
+            .await
+            .expect("should seed demo data");
+        let result = server
+            .list_accounts(Parameters(ListAccountsParams::default()))
+            .await
+            .expect("should list accounts");
+        let accounts: Vec<serde_json::Value> =
+            serde_json::from_str(result_text(&result)).expect("should parse JSON");
+        assert_eq!(accounts.len(), 4);
+        let report = server.server_info().await.expect("should report");
+        let info: serde_json::Value =
+            serde_json::from_str(result_text(&report)).expect("valid json");
+        let features = info["features"].as_array().expect("features");
+        assert!(features.contains(&serde_json::json!("demo")));
+        assert!(!features.contains(&serde_json::json!("auto_sync")));
+    }
+
+    #[tokio::test]
+    async fn recorded_data_replays_offline() {
+        let dir = TempDir::new();
+        let path = dir.join("replay.json");
+        build_test_server()
+            .await
+            .record_replay(&path)
+            .await
+            .expect("should record");
+        let client = ZenMoney::builder()
+            .token("replay")
+            .storage(InMemoryStorage::new())
+            .build()
+            .expect("should build replay client");
+        let server = ZenMoneyMcpServer::new(client)
+            .with_replay(&path)
+            .await
+            .expect("should replay");
+        let result = server
+            .list_accounts(Parameters(ListAccountsParams::default()))
+            .await
+            .expect("should list accounts");
+        let accounts: Vec<serde_json::Value> =
+            serde_json::from_str(result_text(&result)).expect("should parse JSON");
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(server.offline_mode, Some("replay"));
+
+        let messages = with_handshake([tool_call(2, "sync", serde_json::json!({}))]);
+        let responses = exchange(server, &messages, &[2]).await;
+        let message = responses[&2]["error"]["message"]
+            .as_str()
+            .expect("error message");
+        assert!(message.contains("read tools only"), "{message}");
+    }
+
+    #[tokio::test]
+    async fn conflicts_list_and_resolve() {
+        let server = build_test_server().await;
+        let mut local = sample_transaction("tx-expense", 100.0, 0.0);
+        local.comment = Some("local note".to_owned());
+        let remote = sample_transaction("tx-expense", 100.0, 0.0);
+        server
+            .client
+            .storage()
+            .upsert_transactions(vec![local.clone()])
+            .await
+            .expect("should store the edit");
+        server
+            .conflicts
+            .record(vec![Conflict {
+                id: "c-1".to_owned(),
+                detected_at: test_timestamp(),
+                local,
+                remote,
+            }])
+            .expect("should record");
+
+        let listed = server.list_conflicts().await.expect("should list");
+        let report: serde_json::Value =
+            serde_json::from_str(result_text(&listed)).expect("valid json");
+        assert_eq!(report["conflicts"][0]["transaction_id"], "tx-expense");
+        assert_eq!(report["conflicts"][0]["differing_fields"][0], "comment");
+
+        let keep_remote = |fields: Vec<TransactionField>| {
+            Parameters(ResolveConflictParams {
+                id: "c-1".to_owned(),
+                resolution: ConflictResolution::KeepRemote,
+                local_fields: fields,
+            })
+        };
+        assert!(
+            server
+                .resolve_conflict(keep_remote(vec![TransactionField::Comment]))
+                .await
+                .is_err()
+        );
+        let resolved = server
+            .resolve_conflict(keep_remote(Vec::new()))
+            .await
+            .expect("should resolve");
+        let kept: serde_json::Value =
+            serde_json::from_str(result_text(&resolved)).expect("valid json");
+        assert!(kept["comment"].is_null());
+        let stored = server
+            .client
+            .transactions()
+            .await
+            .expect("transactions")
+            .into_iter()
+            .find(|tx| tx.id.as_inner() == "tx-expense")
+            .expect("stored");
+        assert_eq!(stored.comment, None, "the ZenMoney version is stored again");
+        assert!(server.conflicts.list().expect("lock").is_empty());
+        assert!(
+            server
+                .resolve_conflict(keep_remote(Vec::new()))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn rejected_conflict_resolution_keeps_the_conflict() {
+        let zenmoney = FakeZenMoney::start(vec![(400, r#"{"error":"rejected"}"#.to_owned())]);
+        let server = build_test_server_at(Some(&zenmoney.url)).await;
+        let mut local = sample_transaction("tx-expense", 100.0, 0.0);
+        local.comment = Some("local note".to_owned());
+        server
+            .conflicts
+            .record(vec![Conflict {
+                id: "c-1".to_owned(),
+                detected_at: test_timestamp(),
+                local,
+                remote: sample_transaction("tx-expense", 100.0, 0.0),
+            }])
+            .expect("should record");
+
+        let keep_local = Parameters(ResolveConflictParams {
+            id: "c-1".to_owned(),
+            resolution: ConflictResolution::KeepLocal,
+            local_fields: Vec::new(),
+        });
+        assert!(server.resolve_conflict(keep_local).await.is_err());
+        assert_eq!(zenmoney.requests().len(), 1);
+        assert!(server.conflicts.get("c-1").expect("lock").is_some());
+        assert!(server.pending.list().expect("lock").is_empty());
+    }
+
+    #[tokio::test]
+    async fn pending_changes_lists_and_cancels_queued_writes() {
+        let server = build_test_server().await;
+        let queued = sample_transaction("tx-queued", 120.0, 0.0);
+        server
+            .client
+            .storage()
+            .upsert_transactions(vec![queued.clone()])
+            .await
+            .expect("upsert queued transaction");
+        server
+            .pending
+            .push(PendingChange {
+                id: "p-1".to_owned(),
+                tool: "create_transaction".to_owned(),
+                queued_at: test_timestamp(),
+                transactions: vec![queued],
+                previous: Vec::new(),
+            })
+            .expect("should queue");
+
+        let listed = server
+            .pending_changes(Parameters(PendingChangesParams::default()))
+            .await
+            .expect("should list");
+        let report: serde_json::Value =
+            serde_json::from_str(result_text(&listed)).expect("valid json");
+        assert_eq!(report["pending"][0]["status"], "pending");
+        assert_eq!(report["pending"][0]["transactions"][0]["id"], "tx-queued");
+
+        let cancel = PendingChangesParams {
+            cancel: Some("p-1".to_owned()),
+        };
+        let cancelled = server
+            .pending_changes(Parameters(cancel))
+            .await
+            .expect("should cancel");
+        let after: serde_json::Value =
+            serde_json::from_str(result_text(&cancelled)).expect("valid json");
+        assert_eq!(after["cancelled"], "p-1");
+        assert!(after["pending"].as_array().expect("pending").is_empty());
+        let stored = server.client.transactions().await.expect("transactions");
+        assert!(
+            stored
+                .iter()
+                .any(|tx| tx.id.as_inner() == "tx-queued" && tx.deleted)
+        );
+    }
+
+    // ── account_type_label ──────────────────────────────────────────
+
+    #[test]
+    fn account_type_label_all_variants() {
+        use zenmoney_rs::models::AccountType;
+        assert_eq!(account_type_label(AccountType::Cash), "Cash");
+        assert_eq!(account_type_label(AccountType::CreditCard), "CreditCard");
+        assert_eq!(account_type_label(AccountType::Checking), "Checking");
+        assert_eq!(account_type_label(AccountType::Loan), "Loan");
+        assert_eq!(account_type_label(AccountType::Deposit), "Deposit");
+        assert_eq!(account_type_label(AccountType::EMoney), "EMoney");
+        assert_eq!(account_type_label(AccountType::Debt), "Debt");
+    }
+
+    // ── resolve_instrument ──────────────────────────────────────────
+
+    #[test]
+    fn resolve_instrument_explicit_overrides() {
+        let maps = sample_maps();
+        let result = resolve_instrument(&maps, "acc-1", Some(42)).expect("should resolve");
+        assert_eq!(result.into_inner(), 42);
+    }
+
+    #[test]
+    fn resolve_instrument_from_maps() {
+        let maps = sample_maps();
+        let result = resolve_instrument(&maps, "acc-1", None).expect("should resolve");
+        assert_eq!(result.into_inner(), 1);
+    }
+
+    #[test]
+    fn resolve_instrument_unknown_account_errors() {
+        let maps = sample_maps();
+        let result = resolve_instrument(&maps, "unknown", None);
+        assert!(result.is_err());
+    }
+
+    // ── classify_transaction ────────────────────────────────────────
+
+    #[test]
+    fn classify_expense() {
+        let tx = sample_transaction("tx-1", 500.0, 0.0);
+        assert!(matches!(
+            classify_transaction(&tx),
+            TransactionType::Expense
+        ));
+    }
+
+    #[test]
+    fn classify_income() {
+        let tx = sample_transaction("tx-1", 0.0, 1000.0);
+        assert!(matches!(classify_transaction(&tx), TransactionType::Income));
+    }
+
+    #[test]
+    fn classify_transfer() {
+        let tx = sample_transfer("tx-1", 500.0, 500.0);
+        assert!(matches!(
+            classify_transaction(&tx),
+            TransactionType::Transfer
+        ));
+    }
+
+    #[test]
+    fn classify_same_account_both_positive_is_income() {
+        // Both positive but same account → Income (not Transfer).
+        let tx = sample_transaction("tx-1", 100.0, 200.0);
+        assert!(matches!(classify_transaction(&tx), TransactionType::Income));
+    }
+
+    // ── filter_by_transaction_type ──────────────────────────────────
+
+    #[test]
+    fn filter_expense_retains_only_expenses() {
+        let mut txs = vec![
+            sample_transaction("tx-1", 500.0, 0.0),  // expense
+            sample_transaction("tx-2", 0.0, 1000.0), // income
+            sample_transfer("tx-3", 300.0, 300.0),   // transfer
+        ];
+        filter_by_transaction_type(&mut txs, Some(&TransactionType::Expense));
+        assert_eq!(txs.len(), 1);
+        assert_eq!(txs[0].id.as_inner(), "tx-1");
+    }
+
+    #[test]
+    fn filter_income_retains_only_income() {
+        let mut txs = vec![
+            sample_transaction("tx-1", 500.0, 0.0),
+            sample_transaction("tx-2", 0.0, 1000.0),
+        ];
+        filter_by_transaction_type(&mut txs, Some(&TransactionType::Income));
+        assert_eq!(txs.len(), 1);
+        assert_eq!(txs[0].id.as_inner(), "tx-2");
+    }
+
+    #[test]
+    fn filter_transfer_retains_only_transfers() {
+        let mut txs = vec![
+            sample_transaction("tx-1", 500.0, 0.0),
+            sample_transfer("tx-2", 300.0, 300.0),
+        ];
+        filter_by_transaction_type(&mut txs, Some(&TransactionType::Transfer));
+        assert_eq!(txs.len(), 1);
+        assert_eq!(txs[0].id.as_inner(), "tx-2");
+    }
+
+    #[test]
+    fn filter_none_keeps_all() {
+        let mut txs = vec![
+            sample_transaction("tx-1", 500.0, 0.0),
+            sample_transaction("tx-2", 0.0, 1000.0),
+        ];
+        filter_by_transaction_type(&mut txs, None);
+        assert_eq!(txs.len(), 2);
+    }
+
+    // ── is_uncategorized ────────────────────────────────────────────
+
+    #[test]
+    fn is_uncategorized_no_tags() {
+        let tx = sample_transaction("tx-1", 500.0, 0.0);
+        assert!(is_uncategorized(&tx));
+    }
+
+    #[test]
+    fn is_uncategorized_empty_vec() {
+        let mut tx = sample_transaction("tx-1", 500.0, 0.0);
+        tx.tag = Some(vec![]);
+        assert!(is_uncategorized(&tx));
+    }
+
+    #[test]
+    fn is_uncategorized_with_tags() {
+        let mut tx = sample_transaction("tx-1", 500.0, 0.0);
+        tx.tag = Some(vec![TagId::new("tag-1".to_owned())]);
+        assert!(!is_uncategorized(&tx));
+    }
+
+    // ── resolve_sides ───────────────────────────────────────────────
+
+    #[test]
+    fn resolve_sides_expense() {
+        let maps = sample_maps();
+        let params = sample_create_params(TransactionType::Expense);
+        let sides = resolve_sides(&params, &maps).expect("should resolve");
+        assert!((sides.outcome - 500.0).abs() < f64::EPSILON);
+        assert!((sides.income - 0.0).abs() < f64::EPSILON);
+        assert_eq!(sides.outcome_account.as_inner(), "acc-1");
+    }
+
+    #[test]
+    fn resolve_sides_income() {
+        let maps = sample_maps();
+        let params = sample_create_params(TransactionType::Income);
+        let sides = resolve_sides(&params, &maps).expect("should resolve");
+        assert!((sides.income - 500.0).abs() < f64::EPSILON);
+        assert!((sides.outcome - 0.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn resolve_sides_transfer() {
+        let maps = sample_maps();
+        let mut params = sample_create_params(TransactionType::Transfer);
+        params.to_account_id = Some("acc-2".to_owned());
+        params.to_amount = Some(7.0);
+        let sides = resolve_sides(&params, &maps).expect("should resolve");
+        assert!((sides.outcome - 500.0).abs() < f64::EPSILON);
+        assert!((sides.income - 7.0).abs() < f64::EPSILON);
+        assert_eq!(sides.income_account.as_inner(), "acc-2");
+        assert_eq!(sides.income_instrument.into_inner(), 2);
+    }
+
+    #[test]
+    fn resolve_sides_transfer_defaults_to_amount() {
+        let maps = sample_maps();
+        let mut params = sample_create_params(TransactionType::Transfer);
+        params.to_account_id = Some("acc-2".to_owned());
+        // No to_amount — should default to amount.
+        let sides = resolve_sides(&params, &maps).expect("should resolve");
+        assert!((sides.income - 500.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn resolve_sides_transfer_missing_to_account_errors() {
+        let maps = sample_maps();
+        let params = sample_create_params(TransactionType::Transfer);
+        let result = resolve_sides(&params, &maps);
+        assert!(result.is_err());
+    }
+
+    // ── build_transaction ───────────────────────────────────────────
+
+    #[test]
+    fn build_transaction_expense_with_optional_fields() {
+        let maps = sample_maps();
+        let mut params = sample_create_params(TransactionType::Expense);
+        params.tag_ids = Some(vec!["tag-1".to_owned()]);
+        params.payee = Some("Coffee Shop".to_owned());
+        params.comment = Some("Morning coffee".to_owned());
+
+        let tx = build_transaction(params, &maps).expect("should build");
+        assert!((tx.outcome - 500.0).abs() < f64::EPSILON);
+        assert!((tx.income - 0.0).abs() < f64::EPSILON);
+        assert_eq!(tx.tag.as_ref().expect("should have tags").len(), 1);
+        assert_eq!(tx.payee.as_deref(), Some("Coffee Shop"));
+        assert_eq!(tx.comment.as_deref(), Some("Morning coffee"));
+        assert_eq!(tx.date, test_date());
+    }
+
+    #[test]
+    fn build_transaction_sets_location() {
+        let maps = sample_maps();
+        let mut params = sample_create_params(TransactionType::Expense);
+        params.latitude = Some(55.75);
+        params.longitude = Some(37.62);
+        let tx = build_transaction(params, &maps).expect("should build");
+        assert_eq!(tx.latitude, Some(55.75));
+        assert_eq!(tx.longitude, Some(37.62));
+
+        let mut half = sample_create_params(TransactionType::Expense);
+        half.latitude = Some(55.75);
+        assert!(build_transaction(half, &maps).is_err());
+
+        let mut out_of_range = sample_create_params(TransactionType::Expense);
+        out_of_range.latitude = Some(95.0);
+        out_of_range.longitude = Some(37.62);
+        assert!(build_transaction(out_of_range, &maps).is_err());
+    }
+
+    #[test]
+    fn build_transaction_validates_mcc() {
+        let maps = sample_maps();
+        let mut params = sample_create_params(TransactionType::Expense);
+        params.mcc = Some(5411);
+        let tx = build_transaction(params, &maps).expect("should build");
+        assert_eq!(tx.mcc, Some(5411));
+
+        let mut invalid = sample_create_params(TransactionType::Expense);
+        invalid.mcc = Some(12_345);
+        assert!(build_transaction(invalid, &maps).is_err());
+    }
+
+    #[test]
+    fn build_transaction_income_minimal() {
+        let maps = sample_maps();
+        let params = sample_create_params(TransactionType::Income);
+        let tx = build_transaction(params, &maps).expect("should build");
+        assert!((tx.income - 500.0).abs() < f64::EPSILON);
+        assert!((tx.outcome - 0.0).abs() < f64::EPSILON);
+        assert!(tx.tag.is_none());
+        assert!(tx.payee.is_none());
+    }
+
+    #[test]
+    fn build_transaction_links_known_merchant() {
+        let maps = sample_maps().with_merchants(&sample_merchants());
+        let mut params = sample_create_params(TransactionType::Expense);
+        params.merchant_id = Some("m-2".to_owned());
+        let tx = build_transaction(params, &maps).expect("should build");
+        assert_eq!(tx.merchant.as_ref().map(MerchantId::as_inner), Some("m-2"));
+    }
+
+    #[test]
+    fn build_transaction_unknown_merchant_errors() {
+        let maps = sample_maps();
+        let mut params = sample_create_params(TransactionType::Expense);
+        params.merchant_id = Some("m-missing".to_owned());
+        assert!(build_transaction(params, &maps).is_err());
+    }
+
+    #[test]
+    fn validate_template_checks_references() {
+        let maps = sample_maps().with_merchants(&sample_merchants());
+        let params = |account_id: &str, to_account_id: Option<&str>| SaveTemplateParams {
+            name: "  Rent ".to_owned(),
+            transaction_type: if to_account_id.is_some() {
+                TransactionType::Transfer
+            } else {
+                TransactionType::Expense
+            },
+            account_id: account_id.to_owned(),
+            to_account_id: to_account_id.map(str::to_owned),
+            amount: None,
+            tag_ids: None,
+            payee: None,
+            comment: None,
+            merchant_id: Some("m-1".to_owned()),
+        };
+        let template = validate_template(params("acc-1", None), &maps).expect("valid");
+        assert_eq!(template.name, "Rent");
+        assert!(validate_template(params("acc-missing", None), &maps).is_err());
+        assert!(validate_template(params("acc-1", Some("acc-missing")), &maps).is_err());
+    }
+
+    #[test]
+    fn template_create_params_applies_overrides() {
+        let template = TransactionTemplate {
+            name: "Rent".to_owned(),
+            transaction_type: TransactionType::Expense,
+            account_id: "acc-1".to_owned(),
+            to_account_id: None,
+            amount: None,
+            tag_ids: None,
+            payee: Some("Landlord".to_owned()),
+            comment: Some("rent".to_owned()),
+            merchant_id: None,
+        };
+        let overrides = |amount: Option<f64>| CreateFromTemplateParams {
+            name: "rent".to_owned(),
+            date: Some("2024-07-01".to_owned()),
+            amount,
+            comment: None,
+        };
+        assert!(template_create_params(template.clone(), overrides(None)).is_err());
+        let params =
+            template_create_params(template, overrides(Some(30_000.0))).expect("has amount");
+        assert_eq!(params.date, "2024-07-01");
+        assert_eq!(params.payee.as_deref(), Some("Landlord"));
+        assert_eq!(params.comment.as_deref(), Some("rent"));
+    }
+
+    #[test]
+    fn balance_correction_params_sign() {
+        let up =
+            balance_correction_params("acc-1", 150.0, "2024-06-15".to_owned(), "fix".to_owned())
+                .expect("positive delta");
+        assert!(matches!(up.transaction_type, TransactionType::Income));
+        let down =
+            balance_correction_params("acc-1", -42.5, "2024-06-15".to_owned(), "fix".to_owned())
+                .expect("negative delta");
+        assert!(matches!(down.transaction_type, TransactionType::Expense));
+        assert!((down.amount - 42.5).abs() < f64::EPSILON);
+        assert!(
+            balance_correction_params("acc-1", 0.001, "2024-06-15".to_owned(), "fix".to_owned())
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn build_transaction_invalid_date_errors() {
+        let maps = sample_maps();
+        let mut params = sample_create_params(TransactionType::Expense);
+        params.date = "not-a-date".to_owned();
+        let result = build_transaction(params, &maps);
+        assert!(result.is_err());
+    }
+
+    // ── apply_update ────────────────────────────────────────────────
+
+    #[test]
+    fn apply_update_date() {
+        let maps = sample_maps();
+        let mut tx = sample_transaction("tx-1", 500.0, 0.0);
+        let params = UpdateTransactionParams {
+            id: "tx-1".to_owned(),
+            date: Some("2025-01-01".to_owned()),
+            amount: None,
+            to_amount: None,
+            account_id: None,
+            to_account_id: None,
+            tag_ids: None,
+            add_tag_ids: None,
+            remove_tag_ids: None,
+            payee: None,
+            comment: None,
+            merchant_id: None,
+            hold: None,
+            latitude: None,
+            longitude: None,
+            mcc: None,
+            allow_out_of_range: None,
+        };
+        apply_update(&mut tx, params, &maps).expect("should update");
+        assert_eq!(tx.date, NaiveDate::from_ymd_opt(2025, 1, 1).expect("valid"));
+    }
+
+    #[test]
+    fn apply_update_payee_empty_clears() {
+        let maps = sample_maps();
+        let mut tx = sample_transaction("tx-1", 500.0, 0.0);
+        tx.payee = Some("Old Payee".to_owned());
+        let params = UpdateTransactionParams {
+            id: "tx-1".to_owned(),
+            date: None,
+            amount: None,
+            to_amount: None,
+            account_id: None,
+            to_account_id: None,
+            tag_ids: None,
+            add_tag_ids: None,
+            remove_tag_ids: None,
+            payee: Some(String::new()),
+            comment: None,
+            merchant_id: None,
+            hold: None,
+            latitude: None,
+            longitude: None,
+            mcc: None,
+            allow_out_of_range: None,
+        };
+        apply_update(&mut tx, params, &maps).expect("should update");
+        assert!(tx.payee.is_none());
+    }
+
+    #[test]
+    fn apply_update_comment_empty_clears() {
+        let maps = sample_maps();
+        let mut tx = sample_transaction("tx-1", 500.0, 0.0);
+        tx.comment = Some("Old comment".to_owned());
+        let params = UpdateTransactionParams {
+            id: "tx-1".to_owned(),
+            date: None,
+            amount: None,
+            to_amount: None,
+            account_id: None,
+            to_account_id: None,
+            tag_ids: None,
+            add_tag_ids: None,
+            remove_tag_ids: None,
+            payee: None,
+            comment: Some(String::new()),
+            merchant_id: None,
+            hold: None,
+            latitude: None,
+            longitude: None,
+            mcc: None,
+            allow_out_of_range: None,
+        };
+        apply_update(&mut tx, params, &maps).expect("should update");
+        assert!(tx.comment.is_none());
+    }
+
+    #[test]
+    fn move_transaction_expense_moves_both_sides() {
+        let maps = sample_maps();
+        let mut tx = sample_transaction("tx-1", 500.0, 0.0);
+        tx.outcome_account = AccountId::new("acc-old".to_owned());
+        tx.income_account = AccountId::new("acc-old".to_owned());
+        assert!(move_transaction(&mut tx, None, "acc-1", &maps).expect("should move"));
+        assert_eq!(tx.outcome_account.as_inner(), "acc-1");
+        assert_eq!(tx.income_account.as_inner(), "acc-1");
+        assert!(!move_transaction(&mut tx, None, "acc-1", &maps).expect("no-op"));
+    }
+
+    #[test]
+    fn move_transaction_transfer_moves_source_side() {
+        let maps = sample_maps();
+        let mut tx = sample_transaction("tx-1", 500.0, 500.0);
+        tx.outcome_account = AccountId::new("acc-a".to_owned());
+        tx.income_account = AccountId::new("acc-b".to_owned());
+        assert!(move_transaction(&mut tx, Some("acc-b"), "acc-1", &maps).expect("should move"));
+        assert_eq!(tx.outcome_account.as_inner(), "acc-a");
+        assert_eq!(tx.income_account.as_inner(), "acc-1");
+        // Moving the other side onto the same account is rejected.
+        assert!(move_transaction(&mut tx, Some("acc-a"), "acc-1", &maps).is_err());
+    }
+
+    #[test]
+    fn apply_tag_changes_precedence() {
+        let mut tx = sample_transaction("tx-1", 500.0, 0.0);
+        tx.tag = Some(vec![TagId::new("tag-1".to_owned())]);
+        let ids = |list: &[&str]| Some(list.iter().map(|id| (*id).to_owned()).collect());
+
+        apply_tag_changes(&mut tx, None, ids(&["tag-2", "tag-1"]), None);
+        let tags = |tx: &Transaction| {
+            tx.tag
+                .as_deref()
+                .unwrap_or_default()
+                .iter()
+                .map(|id| id.as_inner().to_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(tags(&tx), vec!["tag-1", "tag-2"]);
+
+        apply_tag_changes(&mut tx, ids(&["tag-3"]), ids(&["tag-4"]), ids(&["tag-4"]));
+        assert_eq!(tags(&tx), vec!["tag-3"]);
+
+        apply_tag_changes(&mut tx, None, None, ids(&["tag-3"]));
+        assert!(tx.tag.is_none());
+    }
+
+    #[test]
+    fn apply_update_merchant_empty_clears() {
+        let maps = sample_maps();
+        let mut tx = sample_transaction("tx-1", 500.0, 0.0);
+        tx.merchant = Some(MerchantId::new("m-1".to_owned()));
+        let params = UpdateTransactionParams {
+            id: "tx-1".to_owned(),
+            date: None,
+            amount: None,
+            to_amount: None,
+            account_id: None,
+            to_account_id: None,
+            tag_ids: None,
+            add_tag_ids: None,
+            remove_tag_ids: None,
+            payee: None,
+            comment: None,
+            merchant_id: Some(String::new()),
+            hold: None,
+            latitude: None,
+            longitude: None,
+            mcc: None,
+            allow_out_of_range: None,
+        };
+        apply_update(&mut tx, params, &maps).expect("should update");
+        assert!(tx.merchant.is_none());
+    }
+
+    #[test]
+    fn apply_update_tag_ids() {
+        let maps = sample_maps();
+        let mut tx = sample_transaction("tx-1", 500.0, 0.0);
+        let params = UpdateTransactionParams {
+            id: "tx-1".to_owned(),
+            date: None,
+            amount: None,
+            to_amount: None,
+            account_id: None,
+            to_account_id: None,
+            tag_ids: Some(vec!["tag-1".to_owned(), "tag-2".to_owned()]),
+            add_tag_ids: None,
+            remove_tag_ids: None,
+            payee: None,
+            comment: None,
+            merchant_id: None,
+            hold: None,
+            latitude: None,
+            longitude: None,
+            mcc: None,
+            allow_out_of_range: None,
+        };
+        apply_update(&mut tx, params, &maps).expect("should update");
+        let tags = tx.tag.expect("should have tags");
+        assert_eq!(tags.len(), 2);
+    }
+
+    #[test]
+    fn apply_update_amount_on_expense() {
+        let maps = sample_maps();
+        let mut tx = sample_transaction("tx-1", 500.0, 0.0);
+        let params = UpdateTransactionParams {
+            id: "tx-1".to_owned(),
+            date: None,
+            amount: Some(750.0),
+            to_amount: None,
+            account_id: None,
+            to_account_id: None,
+            tag_ids: None,
+            add_tag_ids: None,
+            remove_tag_ids: None,
+            payee: None,
+            comment: None,
+            merchant_id: None,
+            hold: None,
+            latitude: None,
+            longitude: None,
+            mcc: None,
+            allow_out_of_range: None,
+        };
+        apply_update(&mut tx, params, &maps).expect("should update");
+        assert!((tx.outcome - 750.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn apply_update_account_on_transfer() {
+        let maps = sample_maps();
+        let mut tx = sample_transfer("tx-1", 500.0, 500.0);
+        let params = UpdateTransactionParams {
+            id: "tx-1".to_owned(),
+            date: None,
+            amount: None,
+            to_amount: None,
+            account_id: Some("acc-2".to_owned()),
+            to_account_id: None,
+            tag_ids: None,
+            add_tag_ids: None,
+            remove_tag_ids: None,
+            payee: None,
+            comment: None,
+            merchant_id: None,
+            hold: None,
+            latitude: None,
+            longitude: None,
+            mcc: None,
+            allow_out_of_range: None,
+        };
+        apply_update(&mut tx, params, &maps).expect("should update");
+        assert_eq!(tx.outcome_account.as_inner(), "acc-2");
+        assert_eq!(tx.outcome_instrument.into_inner(), 2);
+    }
+
+    #[test]
+    fn apply_update_comment_sets_value() {
+        let maps = sample_maps();
+        let mut tx = sample_transaction("tx-1", 500.0, 0.0);
+        let params = UpdateTransactionParams {
+            id: "tx-1".to_owned(),
+            date: None,
+            amount: None,
+            to_amount: None,
+            account_id: None,
+            to_account_id: None,
+            tag_ids: None,
+            add_tag_ids: None,
+            remove_tag_ids: None,
+            payee: None,
+            comment: Some("New comment".to_owned()),
+            merchant_id: None,
+            hold: None,
+            latitude: None,
+            longitude: None,
+            mcc: None,
+            allow_out_of_range: None,
+        };
+        apply_update(&mut tx, params, &maps).expect("should update");
+        assert_eq!(tx.comment.as_deref(), Some("New comment"));
+    }
+
+    #[test]
+    fn apply_update_hold_settles() {
+        let maps = sample_maps();
+        let mut tx = sample_transaction("tx-1", 500.0, 0.0);
+        tx.hold = Some(true);
+        let params = UpdateTransactionParams {
+            id: "tx-1".to_owned(),
+            date: None,
+            amount: None,
+            to_amount: None,
+            account_id: None,
+            to_account_id: None,
+            tag_ids: None,
+            add_tag_ids: None,
+            remove_tag_ids: None,
+            payee: None,
+            comment: None,
+            merchant_id: None,
+            hold: Some(false),
+            latitude: None,
+            longitude: None,
+            mcc: None,
+            allow_out_of_range: None,
+        };
+        apply_update(&mut tx, params, &maps).expect("should update");
+        assert_eq!(tx.hold, Some(false));
+    }
+
+    #[test]
+    fn apply_update_account_on_expense() {
+        let maps = sample_maps();
+        let mut tx = sample_transaction("tx-1", 500.0, 0.0);
+        let params = UpdateTransactionParams {
+            id: "tx-1".to_owned(),
+            date: None,
+            amount: None,
+            to_amount: None,
+            account_id: Some("acc-2".to_owned()),
+            to_account_id: None,
+            tag_ids: None,
+            add_tag_ids: None,
+            remove_tag_ids: None,
+            payee: None,
+            comment: None,
+            merchant_id: None,
+            hold: None,
+            latitude: None,
+            longitude: None,
+            mcc: None,
+            allow_out_of_range: None,
+        };
+        apply_update(&mut tx, params, &maps).expect("should update");
+        assert_eq!(tx.outcome_account.as_inner(), "acc-2");
+        assert_eq!(tx.income_account.as_inner(), "acc-2");
+        assert_eq!(tx.outcome_instrument.into_inner(), 2);
+        assert_eq!(tx.income_instrument.into_inner(), 2);
+    }
+
+    #[test]
+    fn apply_update_account_on_income() {
+        let maps = sample_maps();
+        let mut tx = sample_transaction("tx-1", 0.0, 1000.0);
+        let params = UpdateTransactionParams {
+            id: "tx-1".to_owned(),
+            date: None,
+            amount: None,
+            to_amount: None,
+            account_id: Some("acc-2".to_owned()),
+            to_account_id: None,
+            tag_ids: None,
+            add_tag_ids: None,
+            remove_tag_ids: None,
+            payee: None,
+            comment: None,
+            merchant_id: None,
+            hold: None,
+            latitude: None,
+            longitude: None,
+            mcc: None,
+            allow_out_of_range: None,
+        };
+        apply_update(&mut tx, params, &maps).expect("should update");
+        assert_eq!(tx.income_account.as_inner(), "acc-2");
+        assert_eq!(tx.outcome_account.as_inner(), "acc-2");
+        assert_eq!(tx.income_instrument.into_inner(), 2);
+        assert_eq!(tx.outcome_instrument.into_inner(), 2);
+    }
+
+    #[test]
+    fn apply_update_to_account_id() {
+        let maps = sample_maps();
+        let mut tx = sample_transfer("tx-1", 500.0, 500.0);
+        let params = UpdateTransactionParams {
+            id: "tx-1".to_owned(),
+            date: None,
+            amount: None,
+            to_amount: None,
+            account_id: None,
+            to_account_id: Some("acc-1".to_owned()),
+            tag_ids: None,
+            add_tag_ids: None,
+            remove_tag_ids: None,
+            payee: None,
+            comment: None,
+            merchant_id: None,
+            hold: None,
+            latitude: None,
+            longitude: None,
+            mcc: None,
+            allow_out_of_range: None,
+        };
+        apply_update(&mut tx, params, &maps).expect("should update");
+        assert_eq!(tx.income_account.as_inner(), "acc-1");
+        assert_eq!(tx.income_instrument.into_inner(), 1);
+    }
+
+    #[test]
+    fn apply_update_amount_on_income() {
+        let maps = sample_maps();
+        let mut tx = sample_transaction("tx-1", 0.0, 1000.0);
+        let params = UpdateTransactionParams {
+            id: "tx-1".to_owned(),
+            date: None,
+            amount: Some(2000.0),
+            to_amount: None,
+            account_id: None,
+            to_account_id: None,
+            tag_ids: None,
+            add_tag_ids: None,
+            remove_tag_ids: None,
+            payee: None,
+            comment: None,
+            merchant_id: None,
+            hold: None,
+            latitude: None,
+            longitude: None,
+            mcc: None,
+            allow_out_of_range: None,
+        };
+        apply_update(&mut tx, params, &maps).expect("should update");
+        assert!((tx.income - 2000.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn apply_update_to_amount() {
+        let maps = sample_maps();
+        let mut tx = sample_transfer("tx-1", 500.0, 500.0);
+        let params = UpdateTransactionParams {
+            id: "tx-1".to_owned(),
+            date: None,
+            amount: None,
+            to_amount: Some(750.0),
+            account_id: None,
+            to_account_id: None,
+            tag_ids: None,
+            add_tag_ids: None,
+            remove_tag_ids: None,
+            payee: None,
+            comment: None,
+            merchant_id: None,
+            hold: None,
+            latitude: None,
+            longitude: None,
+            mcc: None,
+            allow_out_of_range: None,
+        };
+        apply_update(&mut tx, params, &maps).expect("should update");
+        assert!((tx.income - 750.0).abs() < f64::EPSILON);
+    }
+
+    // ── process_bulk_operations ─────────────────────────────────────
+
+    #[test]
+    fn process_bulk_create_update_delete_mix() {
+        let maps = sample_maps();
+        let existing = vec![sample_transaction("tx-existing", 100.0, 0.0)];
+        let operations = vec![
+            BulkOperation::Create(sample_create_params(TransactionType::Expense)),
+            BulkOperation::Update(UpdateTransactionParams {
+                id: "tx-existing".to_owned(),
+                date: None,
+                amount: Some(200.0),
+                to_amount: None,
+                account_id: None,
+                to_account_id: None,
+                tag_ids: None,
+                add_tag_ids: None,
+                remove_tag_ids: None,
+                payee: None,
+                comment: None,
+                merchant_id: None,
+                hold: None,
+                latitude: None,
+                longitude: None,
+                mcc: None,
+                allow_out_of_range: None,
+            }),
+            BulkOperation::Delete(DeleteTransactionParams {
+                id: "tx-existing".to_owned(),
+            }),
+        ];
+        let (to_push, to_delete, created, updated) =
+            process_bulk_operations(operations, &existing, &maps).expect("should process");
+        assert_eq!(created, 1);
+        assert_eq!(updated, 1);
+        assert_eq!(to_push.len(), 2);
+        assert_eq!(to_delete.len(), 1);
+    }
+
+    #[test]
+    fn process_bulk_update_nonexistent_errors() {
+        let maps = sample_maps();
+        let existing: Vec<Transaction> = vec![];
+        let operations = vec![BulkOperation::Update(UpdateTransactionParams {
+            id: "no-such-tx".to_owned(),
+            date: None,
+            amount: Some(100.0),
+            to_amount: None,
+            account_id: None,
+            to_account_id: None,
+            tag_ids: None,
+            add_tag_ids: None,
+            remove_tag_ids: None,
+            payee: None,
+            comment: None,
+            merchant_id: None,
+            hold: None,
+            latitude: None,
+            longitude: None,
+            mcc: None,
+            allow_out_of_range: None,
+        })];
+        let result = process_bulk_operations(operations, &existing, &maps);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn process_bulk_delete_nonexistent_errors() {
+        let maps = sample_maps();
+        let existing: Vec<Transaction> = vec![];
+        let operations = vec![BulkOperation::Delete(DeleteTransactionParams {
+            id: "no-such-tx".to_owned(),
+        })];
+        let result = process_bulk_operations(operations, &existing, &maps);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn process_bulk_empty_operations() {
+        let maps = sample_maps();
+        let existing: Vec<Transaction> = vec![];
+        let (to_push, to_delete, created, updated) =
             process_bulk_operations(vec![], &existing, &maps).expect("should process");
         assert!(to_push.is_empty());
         assert!(to_delete.is_empty());
@@ -1922,561 +9430,3632 @@ mod tests {
     }
 
     #[test]
-    fn process_bulk_all_deletes() {
+    fn process_bulk_all_deletes() {
+        let maps = sample_maps();
+        let existing = vec![
+            sample_transaction("tx-1", 100.0, 0.0),
+            sample_transaction("tx-2", 200.0, 0.0),
+        ];
+        let operations = vec![
+            BulkOperation::Delete(DeleteTransactionParams {
+                id: "tx-1".to_owned(),
+            }),
+            BulkOperation::Delete(DeleteTransactionParams {
+                id: "tx-2".to_owned(),
+            }),
+        ];
+        let (to_push, to_delete, created, updated) =
+            process_bulk_operations(operations, &existing, &maps).expect("should process");
+        assert!(to_push.is_empty());
+        assert_eq!(to_delete.len(), 2);
+        assert_eq!(created, 0);
+        assert_eq!(updated, 0);
+    }
+
+    // ── Async handler tests (using InMemoryStorage) ─────────────────
+
+    async fn build_test_server() -> ZenMoneyMcpServer<InMemoryStorage> {
+        build_test_server_at(None).await
+    }
+
+    /// Builds the test server, pointing its client at `base_url` if given.
+    async fn build_test_server_at(base_url: Option<&str>) -> ZenMoneyMcpServer<InMemoryStorage> {
+        use zenmoney_rs::models::{
+            Account, AccountType, Budget, Instrument, Merchant, Reminder, ReminderId, Tag,
+        };
+
+        let mut builder = ZenMoney::builder()
+            .token("test-token")
+            .storage(InMemoryStorage::new());
+        if let Some(url) = base_url {
+            builder = builder.base_url(url);
+        }
+        let client = builder.build().expect("should build test client");
+        let accounts = vec![
+            Account {
+                id: AccountId::new("acc-1".to_owned()),
+                changed: test_timestamp(),
+                user: UserId::new(1),
+                role: None,
+                instrument: Some(InstrumentId::new(1)),
+                company: None,
+                kind: AccountType::Checking,
+                title: "Main Account".to_owned(),
+                sync_id: None,
+                balance: Some(50_000.0),
+                start_balance: None,
+                credit_limit: None,
+                in_balance: true,
+                savings: None,
+                enable_correction: false,
+                enable_sms: false,
+                archive: false,
+                capitalization: None,
+                percent: None,
+                start_date: None,
+                end_date_offset: None,
+                end_date_offset_interval: None,
+                payoff_step: None,
+                payoff_interval: None,
+                balance_correction_type: None,
+                private: None,
+            },
+            Account {
+                id: AccountId::new("acc-2".to_owned()),
+                changed: test_timestamp(),
+                user: UserId::new(1),
+                role: None,
+                instrument: Some(InstrumentId::new(2)),
+                company: None,
+                kind: AccountType::Cash,
+                title: "USD Account".to_owned(),
+                sync_id: None,
+                balance: Some(1_000.0),
+                start_balance: None,
+                credit_limit: None,
+                in_balance: true,
+                savings: None,
+                enable_correction: false,
+                enable_sms: false,
+                archive: true,
+                capitalization: None,
+                percent: None,
+                start_date: None,
+                end_date_offset: None,
+                end_date_offset_interval: None,
+                payoff_step: None,
+                payoff_interval: None,
+                balance_correction_type: None,
+                private: None,
+            },
+        ];
+        let tags = vec![Tag {
+            id: TagId::new("tag-1".to_owned()),
+            changed: test_timestamp(),
+            user: UserId::new(1),
+            title: "Groceries".to_owned(),
+            parent: None,
+            icon: None,
+            picture: None,
+            color: None,
+            show_income: false,
+            show_outcome: true,
+            budget_income: false,
+            budget_outcome: true,
+            required: None,
+            static_id: None,
+            archive: None,
+        }];
+        let instruments = vec![
+            Instrument {
+                id: InstrumentId::new(1),
+                changed: test_timestamp(),
+                title: "Russian Ruble".to_owned(),
+                short_title: "RUB".to_owned(),
+                symbol: "\u{20bd}".to_owned(),
+                rate: 1.0,
+            },
+            Instrument {
+                id: InstrumentId::new(2),
+                changed: test_timestamp(),
+                title: "US Dollar".to_owned(),
+                short_title: "USD".to_owned(),
+                symbol: "$".to_owned(),
+                rate: 90.0,
+            },
+        ];
+        let transactions = vec![
+            sample_transaction("tx-expense", 500.0, 0.0),
+            sample_transaction("tx-income", 0.0, 1000.0),
+            sample_transfer("tx-transfer", 300.0, 300.0),
+        ];
+        let merchants = vec![Merchant {
+            id: MerchantId::new("m-1".to_owned()),
+            changed: test_timestamp(),
+            user: UserId::new(1),
+            title: "Coffee Shop".to_owned(),
+        }];
+        let budgets = vec![Budget {
+            changed: test_timestamp(),
+            user: UserId::new(1),
+            tag: Some(TagId::new("tag-1".to_owned())),
+            date: NaiveDate::from_ymd_opt(2024, 6, 1).expect("valid date"),
+            income: 0.0,
+            income_lock: false,
+            outcome: 15_000.0,
+            outcome_lock: false,
+            is_income_forecast: None,
+            is_outcome_forecast: None,
+        }];
+        let reminders = vec![Reminder {
+            id: ReminderId::new("rem-1".to_owned()),
+            changed: test_timestamp(),
+            user: UserId::new(1),
+            income_instrument: InstrumentId::new(1),
+            income_account: AccountId::new("acc-1".to_owned()),
+            income: 0.0,
+            outcome_instrument: InstrumentId::new(1),
+            outcome_account: AccountId::new("acc-1".to_owned()),
+            outcome: 5_000.0,
+            tag: Some(vec![TagId::new("tag-1".to_owned())]),
+            merchant: None,
+            payee: Some("Supermarket".to_owned()),
+            comment: None,
+            interval: None,
+            step: None,
+            points: None,
+            start_date: test_date(),
+            end_date: None,
+            notify: false,
+        }];
+
+        client
+            .storage()
+            .upsert_accounts(accounts)
+            .await
+            .expect("upsert accounts");
+        client
+            .storage()
+            .upsert_tags(tags)
+            .await
+            .expect("upsert tags");
+        client
+            .storage()
+            .upsert_instruments(instruments)
+            .await
+            .expect("upsert instruments");
+        client
+            .storage()
+            .upsert_transactions(transactions)
+            .await
+            .expect("upsert transactions");
+        client
+            .storage()
+            .upsert_merchants(merchants)
+            .await
+            .expect("upsert merchants");
+        client
+            .storage()
+            .upsert_budgets(budgets)
+            .await
+            .expect("upsert budgets");
+        client
+            .storage()
+            .upsert_reminders(reminders)
+            .await
+            .expect("upsert reminders");
+
+        ZenMoneyMcpServer::new(client)
+    }
+
+    /// A stand-in for the ZenMoney API answering each request with the next
+    /// queued `(status, body)` reply and recording the request bodies.
+    struct FakeZenMoney {
+        url: String,
+        requests: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl FakeZenMoney {
+        fn start(replies: Vec<(u16, String)>) -> Self {
+            Self::start_with(replies, |_count| ())
+        }
+
+        /// Like [`Self::start`], calling `on_request` with the number of
+        /// requests seen so far before each reply is sent.
+        fn start_with(
+            replies: Vec<(u16, String)>,
+            on_request: impl Fn(usize) + Send + 'static,
+        ) -> Self {
+            use std::io::Write as _;
+
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("bind");
+            let url = format!("http://{}", listener.local_addr().expect("local address"));
+            let requests = Arc::new(Mutex::new(Vec::new()));
+            let seen = Arc::clone(&requests);
+            let _server = std::thread::spawn(move || {
+                for (status, body) in replies {
+                    let Ok((mut stream, _peer)) = listener.accept() else {
+                        return;
+                    };
+                    let request = read_request_body(&mut stream);
+                    let count = {
+                        let mut seen = seen.lock().expect("lock");
+                        seen.push(request);
+                        seen.len()
+                    };
+                    on_request(count);
+                    let response = format!(
+                        "HTTP/1.1 {status} Fake\r\nContent-Type: application/json\r\n\
+                         Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                        body.len()
+                    );
+                    let _written = stream.write_all(response.as_bytes());
+                }
+            });
+            Self { url, requests }
+        }
+
+        fn requests(&self) -> Vec<String> {
+            self.requests.lock().expect("lock").clone()
+        }
+    }
+
+    /// Reads one HTTP request from `stream` and returns its body.
+    fn read_request_body(stream: &mut std::net::TcpStream) -> String {
+        use std::io::{BufRead as _, Read as _};
+
+        let mut reader = std::io::BufReader::new(stream);
+        let mut length = 0_usize;
+        loop {
+            let mut line = String::new();
+            let read = reader.read_line(&mut line).expect("read header");
+            if read == 0 || line == "\r\n" {
+                break;
+            }
+            if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                length = value.trim().parse().expect("content length");
+            }
+        }
+        let mut body = vec![0_u8; length];
+        reader.read_exact(&mut body).expect("read body");
+        String::from_utf8_lossy(&body).into_owned()
+    }
+
+    /// Asserts that `result` is the `not_found` error for `query` of `kind`.
+    fn assert_not_found(result: Result<CallToolResult, McpError>, kind: &str, query: &str) {
+        let err = result.expect_err("lookup should fail");
+        let expected = serde_json::json!({"code": "not_found", "kind": kind, "query": query});
+        assert_eq!(err.data, Some(expected), "{}", err.message);
+    }
+
+    /// Extracts the text string from a successful `CallToolResult`.
+    fn result_text(result: &CallToolResult) -> &str {
+        assert!(
+            !result.is_error.unwrap_or(false),
+            "result should not be error"
+        );
+        result.content[0]
+            .as_text()
+            .expect("expected text content")
+            .text
+            .as_str()
+    }
+
+    #[tokio::test]
+    async fn handler_list_accounts_all() {
+        let server = build_test_server().await;
+        let params = Parameters(ListAccountsParams::default());
+        let result = server
+            .list_accounts(params)
+            .await
+            .expect("should list accounts");
+        let accounts: Vec<serde_json::Value> =
+            serde_json::from_str(result_text(&result)).expect("should parse JSON");
+        assert_eq!(accounts.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn handler_list_accounts_active_only() {
+        let server = build_test_server().await;
+        let params = Parameters(ListAccountsParams {
+            active_only: true,
+            ..Default::default()
+        });
+        let result = server.list_accounts(params).await.expect("should list");
+        let accounts: Vec<serde_json::Value> =
+            serde_json::from_str(result_text(&result)).expect("should parse");
+        assert_eq!(accounts.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn handler_list_accounts_sorted_and_grouped() {
+        let server = build_test_server().await;
+        let params = Parameters(ListAccountsParams {
+            convert_to: Some(1),
+            sort_by: Some(AccountSort::Balance),
+            ..Default::default()
+        });
+        let result = server.list_accounts(params).await.expect("should list");
+        let accounts: Vec<serde_json::Value> =
+            serde_json::from_str(result_text(&result)).expect("should parse");
+        assert_eq!(accounts[0]["id"], "acc-2");
+
+        let params = Parameters(ListAccountsParams {
+            instrument_id: Some(1),
+            ..Default::default()
+        });
+        let result = server.list_accounts(params).await.expect("should list");
+        let accounts: Vec<serde_json::Value> =
+            serde_json::from_str(result_text(&result)).expect("should parse");
+        assert_eq!(accounts.len(), 1);
+
+        let params = Parameters(ListAccountsParams {
+            group_by_type: true,
+            ..Default::default()
+        });
+        let result = server.list_accounts(params).await.expect("should list");
+        let groups = parse_paginated(&result)["groups"].clone();
+        assert_eq!(groups[0]["account_type"], "Cash");
+        assert_eq!(groups[0]["subtotal"], 1000.0);
+        assert_eq!(groups[1]["account_type"], "Checking");
+        assert_eq!(groups[1]["accounts"][0]["id"], "acc-1");
+    }
+
+    #[tokio::test]
+    async fn handler_list_accounts_by_user() {
+        let server = build_test_server().await;
+        for (user_id, expected) in [(1, 2), (99, 0)] {
+            let params = Parameters(ListAccountsParams {
+                user_id: Some(user_id),
+                ..Default::default()
+            });
+            let result = server.list_accounts(params).await.expect("should list");
+            let accounts: Vec<serde_json::Value> =
+                serde_json::from_str(result_text(&result)).expect("should parse");
+            assert_eq!(accounts.len(), expected, "user {user_id}");
+        }
+    }
+
+    /// Parses a paginated transactions response from a `CallToolResult`.
+    fn parse_paginated(result: &CallToolResult) -> serde_json::Value {
+        serde_json::from_str(result_text(result)).expect("should parse paginated response")
+    }
+
+    #[tokio::test]
+    async fn handler_list_transactions_default() {
+        let server = build_test_server().await;
+        let params = Parameters(ListTransactionsParams::default());
+        let result = server
+            .list_transactions(params)
+            .await
+            .expect("should list transactions");
+        let page = parse_paginated(&result);
+        assert_eq!(page["items"].as_array().expect("items array").len(), 3);
+        assert_eq!(page["total"], 3);
+        assert_eq!(page["offset"], 0);
+        assert_eq!(page["limit"], DEFAULT_TRANSACTION_LIMIT);
+    }
+
+    #[tokio::test]
+    async fn handler_list_transactions_filter_expense() {
+        let server = build_test_server().await;
+        let params = Parameters(ListTransactionsParams {
+            transaction_type: Some(TransactionType::Expense),
+            ..Default::default()
+        });
+        let result = server.list_transactions(params).await.expect("should list");
+        let page = parse_paginated(&result);
+        assert_eq!(page["items"].as_array().expect("items").len(), 1);
+        assert_eq!(page["total"], 1);
+    }
+
+    #[tokio::test]
+    async fn handler_list_transactions_with_limit() {
+        let server = build_test_server().await;
+        let params = Parameters(ListTransactionsParams {
+            limit: Some(1),
+            ..Default::default()
+        });
+        let result = server.list_transactions(params).await.expect("should list");
+        let page = parse_paginated(&result);
+        assert_eq!(page["items"].as_array().expect("items").len(), 1);
+        assert_eq!(page["total"], 3);
+        assert_eq!(page["limit"], 1);
+    }
+
+    #[tokio::test]
+    async fn handler_list_transactions_summary_covers_all_matches() {
+        let server = build_test_server().await;
+        let params = Parameters(ListTransactionsParams {
+            limit: Some(1),
+            ..Default::default()
+        });
+        let result = server.list_transactions(params).await.expect("should list");
+        let page = parse_paginated(&result);
+        let summary = &page["summary"];
+        assert_eq!(summary["count"], 3);
+        assert_eq!(summary["transfer_count"], 1);
+        assert_eq!(summary["income"], 1000.0);
+        assert_eq!(summary["outcome"], 500.0);
+        assert_eq!(summary["net"], 500.0);
+        assert_eq!(
+            summary["by_currency"].as_array().expect("currencies").len(),
+            1
+        );
+    }
+
+    /// Sends `messages` to `server` over an in-memory MCP session and
+    /// returns the responses to the requests with the given ids, by id.
+    async fn exchange(
+        server: ZenMoneyMcpServer<InMemoryStorage>,
+        messages: &[serde_json::Value],
+        ids: &[u64],
+    ) -> BTreeMap<u64, serde_json::Value> {
+        use rmcp::ServiceExt as _;
+        use tokio::io::{AsyncBufReadExt as _, AsyncWriteExt as _, BufReader};
+
+        let (client, transport) = tokio::io::duplex(64 * 1024);
+        let _server = tokio::spawn(async move {
+            let running = server
+                .serve(tokio::io::split(transport))
+                .await
+                .expect("serves");
+            let _reason = running.waiting().await;
+        });
+        let (client_read, mut client_write) = tokio::io::split(client);
+        for message in messages {
+            let line = format!("{message}\n");
+            client_write
+                .write_all(line.as_bytes())
+                .await
+                .expect("writes");
+        }
+        let mut lines = BufReader::new(client_read).lines();
+        let mut responses = BTreeMap::new();
+        while responses.len() < ids.len() {
+            let line = lines
+                .next_line()
+                .await
+                .expect("reads")
+                .expect("session open");
+            let response: serde_json::Value = serde_json::from_str(&line).expect("JSON-RPC");
+            if let Some(id) = response["id"].as_u64().filter(|id| ids.contains(id)) {
+                let _previous = responses.insert(id, response);
+            }
+        }
+        responses
+    }
+
+    /// Prepends the MCP initialization handshake to `calls`.
+    fn with_handshake(
+        calls: impl IntoIterator<Item = serde_json::Value>,
+    ) -> Vec<serde_json::Value> {
+        let handshake = [
+            serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "initialize",
+                "params": {
+                    "protocolVersion": "2025-03-26",
+                    "capabilities": {},
+                    "clientInfo": { "name": "test", "version": "0" }
+                }
+            }),
+            serde_json::json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }),
+        ];
+        handshake.into_iter().chain(calls).collect()
+    }
+
+    /// Builds a `tools/call` request for `name` with `arguments`.
+    fn tool_call(id: u64, name: &str, arguments: serde_json::Value) -> serde_json::Value {
+        serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": "tools/call",
+            "params": { "name": name, "arguments": arguments }
+        })
+    }
+
+    #[tokio::test]
+    async fn handler_reads_lenient_arguments_over_mcp() {
+        let server = build_test_server().await;
+        let call = |id: u64, limit: &str| {
+            tool_call(
+                id,
+                "list_transactions",
+                serde_json::json!({ "limit": limit }),
+            )
+        };
+        let messages = with_handshake([call(2, "20"), call(3, "many")]);
+        let responses = exchange(server, &messages, &[2, 3]).await;
+
+        let coerced = &responses[&2]["result"];
+        assert_ne!(coerced["isError"], true, "{coerced}");
+        let text = coerced["content"][0]["text"]
+            .as_str()
+            .expect("text content");
+        let page: serde_json::Value = serde_json::from_str(text).expect("paginated response");
+        assert_eq!(page["limit"], 20);
+
+        let error = &responses[&3]["error"];
+        let message = error["message"].as_str().expect("message");
+        assert!(
+            message.starts_with("invalid arguments for list_transactions: `limit` got string"),
+            "{message}"
+        );
+        assert!(message.contains("for example 10"), "{message}");
+        let details = error["data"]["details"].as_str().expect("details");
+        assert!(
+            details.starts_with("failed to deserialize parameters"),
+            "{details}"
+        );
+    }
+
+    #[tokio::test]
+    async fn handler_list_transactions_sort_asc() {
+        let server = build_test_server().await;
+        let params = Parameters(ListTransactionsParams {
+            sort: Some(SortDirection::Asc),
+            ..Default::default()
+        });
+        let result = server.list_transactions(params).await.expect("should list");
+        assert!(!result.is_error.unwrap_or(false));
+    }
+
+    #[tokio::test]
+    async fn handler_list_transactions_uncategorized() {
+        let server = build_test_server().await;
+        let params = Parameters(ListTransactionsParams {
+            uncategorized: Some(true),
+            ..Default::default()
+        });
+        let result = server.list_transactions(params).await.expect("should list");
+        let page = parse_paginated(&result);
+        // All sample transactions have no tags.
+        assert_eq!(page["items"].as_array().expect("items").len(), 3);
+        assert_eq!(page["total"], 3);
+    }
+
+    #[tokio::test]
+    async fn handler_list_transactions_by_user() {
+        let server = build_test_server().await;
+        let mut partner = sample_transaction("tx-partner", 120.0, 0.0);
+        partner.user = UserId::new(2);
+        server
+            .client
+            .storage()
+            .upsert_transactions(vec![partner])
+            .await
+            .expect("upsert transactions");
+        let params = Parameters(ListTransactionsParams {
+            user_id: Some(2),
+            ..Default::default()
+        });
+        let result = server.list_transactions(params).await.expect("should list");
+        let page = parse_paginated(&result);
+        assert_eq!(page["total"], 1);
+        assert_eq!(page["items"][0]["id"], "tx-partner");
+    }
+
+    #[tokio::test]
+    async fn handler_list_transactions_changed_since() {
+        let server = build_test_server().await;
+        let mut edited = sample_transaction("tx-edited", 120.0, 0.0);
+        edited.changed = DateTime::from_timestamp(1_800_000_000, 0).expect("valid timestamp");
+        server
+            .client
+            .storage()
+            .upsert_transactions(vec![edited])
+            .await
+            .expect("upsert transactions");
+        let params = Parameters(ListTransactionsParams {
+            changed_since: Some(test_timestamp().to_rfc3339()),
+            ..Default::default()
+        });
+        let result = server.list_transactions(params).await.expect("should list");
+        let page = parse_paginated(&result);
+        assert_eq!(page["total"], 1);
+        assert_eq!(page["items"][0]["id"], "tx-edited");
+        assert_eq!(page["items"][0]["changed"], "2027-01-15T08:00:00+00:00");
+
+        let by_date = Parameters(ListTransactionsParams {
+            changed_since: Some("2023-11-14".to_owned()),
+            ..Default::default()
+        });
+        let dated = server
+            .list_transactions(by_date)
+            .await
+            .expect("should list");
+        assert_eq!(parse_paginated(&dated)["total"], 4);
+
+        let invalid = Parameters(ListTransactionsParams {
+            changed_since: Some("yesterday".to_owned()),
+            ..Default::default()
+        });
+        assert!(server.list_transactions(invalid).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn handler_list_transactions_by_reminder() {
+        let server = build_test_server().await;
+        let marker = sample_marker("mk-1", 1, ReminderMarkerState::Processed);
+        let mut paid = sample_transaction("tx-rent", 5000.0, 0.0);
+        paid.reminder_marker = Some(marker.id.clone());
+        let storage = server.client.storage();
+        storage
+            .upsert_reminder_markers(vec![marker])
+            .await
+            .expect("upsert reminder markers");
+        storage
+            .upsert_transactions(vec![paid])
+            .await
+            .expect("upsert transactions");
+
+        let params = Parameters(ListTransactionsParams {
+            reminder_id: Some("rem-1".to_owned()),
+            ..Default::default()
+        });
+        let result = server.list_transactions(params).await.expect("should list");
+        let page = parse_paginated(&result);
+        assert_eq!(page["total"], 1);
+        assert_eq!(page["items"][0]["id"], "tx-rent");
+        assert_eq!(page["items"][0]["reminder"]["reminder_id"], "rem-1");
+        assert_eq!(page["items"][0]["reminder"]["payee"], "Supermarket");
+    }
+
+    #[tokio::test]
+    async fn handler_list_transactions_with_offset() {
+        let server = build_test_server().await;
+        let params = Parameters(ListTransactionsParams {
+            offset: Some(1),
+            limit: Some(1),
+            ..Default::default()
+        });
+        let result = server.list_transactions(params).await.expect("should list");
+        let page = parse_paginated(&result);
+        assert_eq!(page["items"].as_array().expect("items").len(), 1);
+        assert_eq!(page["total"], 3);
+        assert_eq!(page["offset"], 1);
+        assert_eq!(page["limit"], 1);
+    }
+
+    #[tokio::test]
+    async fn handler_list_transactions_offset_past_end() {
+        let server = build_test_server().await;
+        let params = Parameters(ListTransactionsParams {
+            offset: Some(100),
+            ..Default::default()
+        });
+        let result = server.list_transactions(params).await.expect("should list");
+        let page = parse_paginated(&result);
+        assert!(page["items"].as_array().expect("items").is_empty());
+        assert_eq!(page["total"], 3);
+    }
+
+    #[tokio::test]
+    async fn handler_list_transactions_limit_capped() {
+        let server = build_test_server().await;
+        let params = Parameters(ListTransactionsParams {
+            limit: Some(9999),
+            ..Default::default()
+        });
+        let result = server.list_transactions(params).await.expect("should list");
+        let page = parse_paginated(&result);
+        assert_eq!(page["limit"], MAX_TRANSACTION_LIMIT);
+    }
+
+    #[tokio::test]
+    async fn handler_list_tags() {
+        let server = build_test_server().await;
+        let result = server
+            .list_tags(Parameters(ListTagsParams::default()))
+            .await
+            .expect("should list tags");
+        let tags: Vec<serde_json::Value> =
+            serde_json::from_str(result_text(&result)).expect("should parse");
+        assert_eq!(tags.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn handler_list_tags_filters() {
+        let server = build_test_server().await;
+        let mut child_params = sample_create_tag_params("Vegetables");
+        child_params.parent_tag_id = Some("tag-1".to_owned());
+        let child = build_tag(child_params, 1, "Vegetables".to_owned());
+        let mut archived = build_tag(sample_create_tag_params("Old"), 1, "Old".to_owned());
+        archived.archive = Some(true);
+        let mut veg = sample_transaction("tx-veg", 120.0, 0.0);
+        veg.tag = Some(vec![child.id.clone()]);
+        server
+            .client
+            .storage()
+            .upsert_tags(vec![child, archived])
+            .await
+            .expect("upsert tags");
+        server
+            .client
+            .storage()
+            .upsert_transactions(vec![veg])
+            .await
+            .expect("upsert transaction");
+
+        let count = |result: &CallToolResult| {
+            serde_json::from_str::<Vec<serde_json::Value>>(result_text(result))
+                .expect("should parse")
+                .len()
+        };
+        let all = server
+            .list_tags(Parameters(ListTagsParams {
+                include_archived: Some(true),
+                ..Default::default()
+            }))
+            .await
+            .expect("should list");
+        assert_eq!(count(&all), 3);
+        let children = server
+            .list_tags(Parameters(ListTagsParams {
+                parent_tag_id: Some("tag-1".to_owned()),
+                ..Default::default()
+            }))
+            .await
+            .expect("should list");
+        assert_eq!(count(&children), 1);
+        let used = server
+            .list_tags(Parameters(ListTagsParams {
+                used_only: Some(true),
+                ..Default::default()
+            }))
+            .await
+            .expect("should list");
+        let used_tags: Vec<serde_json::Value> =
+            serde_json::from_str(result_text(&used)).expect("should parse");
+        assert_eq!(used_tags.len(), 1);
+        assert_eq!(used_tags[0]["title"], "Vegetables");
+    }
+
+    #[tokio::test]
+    async fn handler_list_users() {
+        use zenmoney_rs::models::User;
+        let server = build_test_server().await;
+        let user = User {
+            id: UserId::new(1),
+            changed: test_timestamp(),
+            login: Some("owner".to_owned()),
+            currency: InstrumentId::new(1),
+            parent: None,
+            country: None,
+            country_code: None,
+            email: None,
+            is_forecast_enabled: None,
+            month_start_day: None,
+            paid_till: None,
+            plan_balance_mode: None,
+            plan_settings: None,
+            subscription: None,
+            subscription_renewal_date: None,
+        };
+        server
+            .client
+            .storage()
+            .upsert_users(vec![user])
+            .await
+            .expect("upsert users");
+        let result = server.list_users().await.expect("should list users");
+        let users: Vec<serde_json::Value> =
+            serde_json::from_str(result_text(&result)).expect("should parse");
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0]["login"], "owner");
+    }
+
+    #[tokio::test]
+    async fn handler_list_companies_filters() {
+        use zenmoney_rs::models::{Company, CompanyId};
+        let server = build_test_server().await;
+        let companies = ["Tinkoff", "Sberbank"]
+            .iter()
+            .zip(1..)
+            .map(|(title, id)| Company {
+                id: CompanyId::new(id),
+                changed: test_timestamp(),
+                title: (*title).to_owned(),
+                full_title: None,
+                www: None,
+                country: None,
+                country_code: None,
+                deleted: None,
+            })
+            .collect();
+        server
+            .client
+            .storage()
+            .upsert_companies(companies)
+            .await
+            .expect("upsert companies");
+
+        let used = server
+            .list_companies(Parameters(ListCompaniesParams::default()))
+            .await
+            .expect("should list");
+        let used: Vec<serde_json::Value> =
+            serde_json::from_str(result_text(&used)).expect("should parse");
+        assert!(used.is_empty(), "test accounts have no company");
+
+        let params = Parameters(ListCompaniesParams {
+            query: Some("TINK".to_owned()),
+            include_unused: true,
+        });
+        let result = server.list_companies(params).await.expect("should list");
+        let found: Vec<serde_json::Value> =
+            serde_json::from_str(result_text(&result)).expect("should parse");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0]["title"], "Tinkoff");
+    }
+
+    #[tokio::test]
+    async fn handler_list_merchants() {
+        let server = build_test_server().await;
+        let result = server
+            .list_merchants()
+            .await
+            .expect("should list merchants");
+        let merchants: Vec<serde_json::Value> =
+            serde_json::from_str(result_text(&result)).expect("should parse");
+        assert_eq!(merchants.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn handler_list_budgets_all() {
+        let server = build_test_server().await;
+        let params = Parameters(ListBudgetsParams::default());
+        let result = server
+            .list_budgets(params)
+            .await
+            .expect("should list budgets");
+        let budgets: Vec<serde_json::Value> =
+            serde_json::from_str(result_text(&result)).expect("should parse");
+        assert_eq!(budgets.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn handler_list_budgets_filter_month() {
+        let server = build_test_server().await;
+        let params = Parameters(ListBudgetsParams {
+            month: Some("2024-06".to_owned()),
+            ..Default::default()
+        });
+        let result = server.list_budgets(params).await.expect("should list");
+        let budgets: Vec<serde_json::Value> =
+            serde_json::from_str(result_text(&result)).expect("should parse");
+        assert_eq!(budgets.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn handler_list_budgets_filter_no_match() {
+        let server = build_test_server().await;
+        let params = Parameters(ListBudgetsParams {
+            month: Some("2025-01".to_owned()),
+            ..Default::default()
+        });
+        let result = server.list_budgets(params).await.expect("should list");
+        let budgets: Vec<serde_json::Value> =
+            serde_json::from_str(result_text(&result)).expect("should parse");
+        assert!(budgets.is_empty());
+    }
+
+    #[tokio::test]
+    async fn handler_suggest_budgets_stages_median_spend() {
+        let server = build_test_server().await;
+        let this_month = parse_month(None).expect("current month");
+        let mut transactions = Vec::new();
+        for (index, amount) in [(1_u32, 1_000.0), (2, 1_200.0), (3, 5_000.0)] {
+            let mut tx = sample_transaction(&format!("tx-hist-{index}"), amount, 0.0);
+            tx.tag = Some(vec![TagId::new("tag-1".to_owned())]);
+            tx.date = this_month
+                .checked_sub_months(Months::new(index))
+                .expect("valid month");
+            transactions.push(tx);
+        }
+        server
+            .client
+            .storage()
+            .upsert_transactions(transactions)
+            .await
+            .expect("upsert transactions");
+
+        let params = Parameters(SuggestBudgetsParams {
+            months: Some(3),
+            round_to: Some(500.0),
+            ..Default::default()
+        });
+        let result = server
+            .suggest_budgets(params)
+            .await
+            .expect("should suggest");
+        let proposal = parse_paginated(&result);
+        assert_eq!(proposal["suggestions"][0]["tag_id"], "tag-1");
+        assert_eq!(proposal["suggestions"][0]["median"], 1_200.0);
+        assert_eq!(proposal["suggestions"][0]["amount"], 1_500.0);
+        let preparation_id = proposal["preparation_id"].as_str().expect("staged");
+        let prepared = server.preparations.lock().expect("lock");
+        let staged = &prepared.get(preparation_id).expect("prepared").budgets;
+        assert_eq!(staged.len(), 1);
+        assert_eq!(staged[0].date, this_month);
+    }
+
+    #[tokio::test]
+    async fn handler_budget_progress_with_rollover() {
+        let server = build_test_server().await;
+        let july = NaiveDate::from_ymd_opt(2024, 7, 1).expect("valid date");
+        let mut budget = server
+            .client
+            .budgets()
+            .await
+            .expect("budgets")
+            .into_iter()
+            .next()
+            .expect("test budget");
+        budget.date = july;
+        budget.outcome = 1_000.0;
+        server
+            .client
+            .storage()
+            .upsert_budgets(vec![budget])
+            .await
+            .expect("upsert budgets");
+        let mut spending = sample_transaction("tx-july", 400.0, 0.0);
+        spending.date = july;
+        let mut june_spending = sample_transaction("tx-june", 3_000.0, 0.0);
+        for tx in [&mut spending, &mut june_spending] {
+            tx.tag = Some(vec![TagId::new("tag-1".to_owned())]);
+        }
+        server
+            .client
+            .storage()
+            .upsert_transactions(vec![spending, june_spending])
+            .await
+            .expect("upsert transactions");
+
+        let plain = Parameters(BudgetProgressParams {
+            month: Some("2024-07".to_owned()),
+            ..Default::default()
+        });
+        let result = server.budget_progress(plain).await.expect("should report");
+        let report = parse_paginated(&result);
+        assert_eq!(report["categories"][0]["remaining"], 600.0);
+        assert_eq!(report["categories"][0]["carried_over"], 0.0);
+
+        let rolled = Parameters(BudgetProgressParams {
+            month: Some("2024-07".to_owned()),
+            rollover: true,
+            rollover_months: Some(1),
+            convert_to: None,
+        });
+        let result = server.budget_progress(rolled).await.expect("should report");
+        let report = parse_paginated(&result);
+        assert_eq!(report["categories"][0]["carried_over"], 12_000.0);
+        assert_eq!(report["categories"][0]["available"], 13_000.0);
+        assert_eq!(report["rollover_months"], 1);
+    }
+
+    #[tokio::test]
+    async fn handler_list_budgets_over_range() {
+        let server = build_test_server().await;
+        let params = Parameters(ListBudgetsParams {
+            year: Some(2024),
+            ..Default::default()
+        });
+        let result = server.list_budgets(params).await.expect("should list");
+        let review = parse_paginated(&result);
+        assert_eq!(review["months"].as_array().map(Vec::len), Some(1));
+        assert_eq!(review["months"][0]["month"], "2024-06");
+        assert_eq!(review["outcome"], 15_000.0);
+
+        let params = Parameters(ListBudgetsParams {
+            month_from: Some("2024-07".to_owned()),
+            ..Default::default()
+        });
+        let result = server.list_budgets(params).await.expect("should list");
+        assert_eq!(parse_paginated(&result)["outcome"], 0.0);
+
+        let conflicting = Parameters(ListBudgetsParams {
+            month: Some("2024-06".to_owned()),
+            year: Some(2024),
+            ..Default::default()
+        });
+        assert!(server.list_budgets(conflicting).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn handler_list_reminders() {
+        let server = build_test_server().await;
+        let result = server
+            .list_reminders()
+            .await
+            .expect("should list reminders");
+        let reminders: Vec<serde_json::Value> =
+            serde_json::from_str(result_text(&result)).expect("should parse");
+        assert_eq!(reminders.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn handler_reminder_markers() {
+        let server = build_test_server().await;
+        let markers = vec![
+            sample_marker("mk-1", 1, ReminderMarkerState::Processed),
+            sample_marker("mk-2", 15, ReminderMarkerState::Planned),
+        ];
+        server
+            .client
+            .storage()
+            .upsert_reminder_markers(markers)
+            .await
+            .expect("upsert reminder markers");
+
+        let params = ListReminderMarkersParams {
+            state: Some(MarkerState::Planned),
+            ..Default::default()
+        };
+        let result = server
+            .list_reminder_markers(Parameters(params))
+            .await
+            .expect("should list markers");
+        let planned: Vec<serde_json::Value> =
+            serde_json::from_str(result_text(&result)).expect("should parse");
+        assert_eq!(planned.len(), 1);
+        assert_eq!(planned[0]["id"], "mk-2");
+        assert_eq!(planned[0]["state"], "planned");
+        assert_eq!(planned[0]["tags"][0], "Groceries");
+
+        let missing = server
+            .set_marker_state(Parameters(SetMarkerStateParams {
+                marker_id: "mk-missing".to_owned(),
+                action: MarkerAction::Convert,
+            }))
+            .await;
+        assert_not_found(missing, "reminder_marker", "mk-missing");
+        let processed = server
+            .set_marker_state(Parameters(SetMarkerStateParams {
+                marker_id: "mk-1".to_owned(),
+                action: MarkerAction::Convert,
+            }))
+            .await;
+        assert!(processed.is_err(), "only planned markers convert");
+    }
+
+    #[tokio::test]
+    async fn handler_upcoming_bills() {
+        let server = build_test_server().await;
+        let params = || {
+            Parameters(UpcomingBillsParams {
+                month: Some("2024-06".to_owned()),
+                ..Default::default()
+            })
+        };
+        let result = server
+            .upcoming_bills(params())
+            .await
+            .expect("should report bills");
+        let report: serde_json::Value = serde_json::from_str(result_text(&result)).expect("json");
+        assert_eq!(report["overdue_count"], 1);
+        assert_eq!(report["bills"][0]["due_date"], "2024-06-15");
+        assert_eq!(report["bills"][0]["status"], "overdue");
+        assert_eq!(report["totals"][0]["remaining"], 5000.0);
+
+        let mut payment = sample_transaction("tx-shop", 4_800.0, 0.0);
+        payment.payee = Some("SUPERMARKET".to_owned());
+        payment.date = NaiveDate::from_ymd_opt(2024, 6, 17).expect("valid date");
+        server
+            .client
+            .storage()
+            .upsert_transactions(vec![payment])
+            .await
+            .expect("upsert transactions");
+        let paid = server
+            .upcoming_bills(params())
+            .await
+            .expect("should report bills");
+        let paid: serde_json::Value = serde_json::from_str(result_text(&paid)).expect("json");
+        assert_eq!(paid["paid_count"], 1);
+        assert_eq!(paid["bills"][0]["status"], "paid");
+        assert_eq!(paid["bills"][0]["transaction_id"], "tx-shop");
+        assert_eq!(paid["totals"][0]["paid"], 4800.0);
+    }
+
+    #[tokio::test]
+    async fn handler_list_instruments() {
+        let server = build_test_server().await;
+        let result = server
+            .list_instruments(Parameters(ListInstrumentsParams::default()))
+            .await
+            .expect("should list instruments");
+        let instruments: Vec<serde_json::Value> =
+            serde_json::from_str(result_text(&result)).expect("should parse");
+        assert_eq!(instruments.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn handler_list_instruments_used_only() {
+        let server = build_test_server().await;
+        let used = Parameters(ListInstrumentsParams {
+            used_only: Some(true),
+        });
+        let result = server
+            .list_instruments(used)
+            .await
+            .expect("should list instruments");
+        let instruments: Vec<serde_json::Value> =
+            serde_json::from_str(result_text(&result)).expect("should parse");
+        assert_eq!(instruments.len(), 1);
+        assert_eq!(instruments[0]["short_title"], "RUB");
+    }
+
+    #[tokio::test]
+    async fn handler_find_instrument_by_code_and_name() {
+        let server = build_test_server().await;
+        let find = |query: &str| {
+            Parameters(FindInstrumentParams {
+                query: query.to_owned(),
+                limit: None,
+            })
+        };
+        for query in ["usd", "dollar"] {
+            let result = server
+                .find_instrument(find(query))
+                .await
+                .expect("should find");
+            let found: serde_json::Value =
+                serde_json::from_str(result_text(&result)).expect("should parse");
+            assert_eq!(found["matches"][0]["short_title"], "USD", "query {query}");
+        }
+        let result = server
+            .find_instrument(find("zloty"))
+            .await
+            .expect("should find");
+        let found: serde_json::Value =
+            serde_json::from_str(result_text(&result)).expect("should parse");
+        assert_eq!(found["found"], false);
+    }
+
+    #[tokio::test]
+    async fn handler_find_account_found() {
+        let server = build_test_server().await;
+        let params = Parameters(FindAccountParams {
+            title: "main account".to_owned(),
+            limit: None,
+        });
+        let result = server.find_account(params).await.expect("should find");
+        let found: serde_json::Value = serde_json::from_str(result_text(&result)).expect("json");
+        assert_eq!(found["matches"][0]["title"], "Main Account");
+        assert_eq!(found["matches"][0]["score"], 1.0);
+    }
+
+    #[tokio::test]
+    async fn handler_find_account_prefix() {
+        let server = build_test_server().await;
+        let params = Parameters(FindAccountParams {
+            title: "main".to_owned(),
+            limit: None,
+        });
+        let result = server.find_account(params).await.expect("should find");
+        let found: serde_json::Value = serde_json::from_str(result_text(&result)).expect("json");
+        assert_eq!(found["matches"][0]["id"], "acc-1");
+    }
+
+    #[tokio::test]
+    async fn handler_find_account_not_found() {
+        let server = build_test_server().await;
+        let params = Parameters(FindAccountParams {
+            title: "nonexistent".to_owned(),
+            limit: None,
+        });
+        let result = server.find_account(params).await.expect("should respond");
+        let found: serde_json::Value = serde_json::from_str(result_text(&result)).expect("json");
+        assert_eq!(found["found"], false);
+        assert!(found["matches"].as_array().expect("matches").is_empty());
+    }
+
+    #[tokio::test]
+    async fn handler_find_tag_found() {
+        let server = build_test_server().await;
+        let params = Parameters(FindTagParams {
+            title: "groceries".to_owned(),
+            limit: None,
+        });
+        let result = server.find_tag(params).await.expect("should find");
+        assert!(result_text(&result).contains("Groceries"));
+    }
+
+    #[tokio::test]
+    async fn handler_find_tag_not_found() {
+        let server = build_test_server().await;
+        let params = Parameters(FindTagParams {
+            title: "nonexistent".to_owned(),
+            limit: None,
+        });
+        let result = server.find_tag(params).await.expect("should respond");
+        let found: serde_json::Value = serde_json::from_str(result_text(&result)).expect("json");
+        assert_eq!(found["found"], false);
+        assert!(found["matches"].as_array().expect("matches").is_empty());
+    }
+
+    #[tokio::test]
+    async fn handler_create_tag_existing_is_idempotent() {
+        let server = build_test_server().await;
+        let params = Parameters(sample_create_tag_params("gRoCeRiEs"));
+        let result = server
+            .create_tag(params)
+            .await
+            .expect("should return existing");
+        let payload: serde_json::Value =
+            serde_json::from_str(result_text(&result)).expect("should parse");
+        let id = payload
+            .get("id")
+            .and_then(serde_json::Value::as_str)
+            .expect("response should include id");
+        assert_eq!(id, "tag-1");
+
+        let tags = server.client.tags().await.expect("should load tags");
+        assert_eq!(tags.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn handler_create_category_alias_existing_is_idempotent() {
+        let server = build_test_server().await;
+        let params = Parameters(sample_create_tag_params("GROCERIES"));
+        let result = server
+            .create_category(params)
+            .await
+            .expect("should return existing");
+        let payload: serde_json::Value =
+            serde_json::from_str(result_text(&result)).expect("should parse");
+        let title = payload
+            .get("title")
+            .and_then(serde_json::Value::as_str)
+            .expect("response should include title");
+        assert_eq!(title, "Groceries");
+
+        let tags = server.client.tags().await.expect("should load tags");
+        assert_eq!(tags.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn handler_create_merchant_existing_is_idempotent() {
+        let server = build_test_server().await;
+        let params = Parameters(CreateMerchantParams {
+            title: "  coffee SHOP ".to_owned(),
+        });
+        let result = server
+            .create_merchant(params)
+            .await
+            .expect("should return existing");
+        let payload: serde_json::Value =
+            serde_json::from_str(result_text(&result)).expect("should parse");
+        assert_eq!(payload["id"], "m-1");
+    }
+
+    #[tokio::test]
+    async fn handler_rename_merchant_unknown_errors() {
+        let server = build_test_server().await;
+        let params = Parameters(RenameMerchantParams {
+            merchant_id: "missing".to_owned(),
+            title: "New name".to_owned(),
+        });
+        assert!(server.rename_merchant(params).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn handler_create_merchant_blank_title_errors() {
+        let server = build_test_server().await;
+        let params = Parameters(CreateMerchantParams {
+            title: "   ".to_owned(),
+        });
+        assert!(server.create_merchant(params).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn handler_rename_merchant_blank_title_errors() {
+        let server = build_test_server().await;
+        let params = Parameters(RenameMerchantParams {
+            merchant_id: "m-1".to_owned(),
+            title: "  ".to_owned(),
+        });
+        assert!(server.rename_merchant(params).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn handler_merge_merchants_validates_ids() {
+        let server = build_test_server().await;
+        let params = Parameters(MergeMerchantsParams {
+            source_merchant_ids: vec!["m-1".to_owned()],
+            target_merchant_id: "m-1".to_owned(),
+        });
+        assert!(server.merge_merchants(params).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn handler_link_merchant_stages_matches() {
+        let server = build_test_server().await;
+        let mut coffee = sample_transaction("tx-coffee", 250.0, 0.0);
+        coffee.payee = Some("COFFEE SHOP #12".to_owned());
+        let mut linked = sample_transaction("tx-linked", 100.0, 0.0);
+        linked.payee = Some("Coffee shop".to_owned());
+        linked.merchant = Some(MerchantId::new("other".to_owned()));
+        server
+            .client
+            .storage()
+            .upsert_transactions(vec![coffee, linked])
+            .await
+            .expect("upsert transactions");
+
+        let params = Parameters(LinkMerchantParams {
+            merchant_id: "m-1".to_owned(),
+            payee_pattern: "coffee shop".to_owned(),
+            overwrite: None,
+            date_from: None,
+            date_to: None,
+        });
+        let result = server.link_merchant(params).await.expect("should stage");
+        let preview = parse_paginated(&result);
+        assert_eq!(preview["updated"], 1);
+        assert_eq!(preview["transactions"][0]["id"], "tx-coffee");
+        let preparation_id = preview["preparation_id"].as_str().expect("preparation id");
+        let preparations = server.preparations.lock().expect("lock");
+        let prepared = preparations.get(preparation_id).expect("stored");
+        assert_eq!(
+            prepared.to_push[0]
+                .merchant
+                .as_ref()
+                .map(MerchantId::as_inner),
+            Some("m-1")
+        );
+    }
+
+    #[tokio::test]
+    async fn handler_reconcile_account_stages_correction() {
+        let server = build_test_server().await;
+        let params = Parameters(ReconcileAccountParams {
+            account_id: "acc-1".to_owned(),
+            actual_balance: 49_000.0,
+            date: Some("2024-06-30".to_owned()),
+            comment: None,
+        });
+        let result = server
+            .reconcile_account(params)
+            .await
+            .expect("should stage");
+        let response = parse_paginated(&result);
+        assert_eq!(response["delta"], -1000.0);
+        assert_eq!(response["created"], 1);
+        assert_eq!(response["transactions"][0]["outcome"], 1000.0);
+        assert_eq!(response["transactions"][0]["comment"], "Balance correction");
+    }
+
+    #[tokio::test]
+    async fn handler_save_and_list_templates() {
+        let server = build_test_server().await;
+        let params = Parameters(SaveTemplateParams {
+            name: "Rent".to_owned(),
+            transaction_type: TransactionType::Expense,
+            account_id: "acc-1".to_owned(),
+            to_account_id: None,
+            amount: Some(30_000.0),
+            tag_ids: None,
+            payee: Some("Landlord".to_owned()),
+            comment: None,
+            merchant_id: None,
+        });
+        let saved = server.save_template(params).await.expect("should save");
+        let saved: serde_json::Value =
+            serde_json::from_str(result_text(&saved)).expect("should parse");
+        assert_eq!(saved["replaced"], false);
+
+        let listed = server.list_templates().await.expect("should list");
+        let listed: Vec<serde_json::Value> =
+            serde_json::from_str(result_text(&listed)).expect("should parse");
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0]["payee"], "Landlord");
+    }
+
+    #[tokio::test]
+    async fn handler_allocate_and_move_envelopes() {
+        let server = build_test_server().await;
+        let allocate = |name: &str, amount: f64, tag_ids: Option<&str>, from: Option<&str>| {
+            Parameters(AllocateEnvelopeParams {
+                name: name.to_owned(),
+                amount,
+                month: Some("2024-06".to_owned()),
+                tag_ids: tag_ids.map(|tag_id| vec![tag_id.to_owned()]),
+                from_envelope: from.map(str::to_owned),
+            })
+        };
+        let food = server
+            .allocate_envelope(allocate("Food", 600.0, Some("tag-1"), None))
+            .await
+            .expect("should allocate");
+        assert_eq!(parse_paginated(&food)["created"], true);
+        assert!(
+            server
+                .allocate_envelope(allocate("Fun", 100.0, None, None))
+                .await
+                .is_err()
+        );
+        assert!(
+            server
+                .allocate_envelope(allocate("Fun", 100.0, Some("tag-missing"), None))
+                .await
+                .is_err()
+        );
+        let moved = server
+            .allocate_envelope(allocate("Fun", 100.0, Some("tag-1"), Some("food")))
+            .await
+            .expect("should move");
+        let moved = parse_paginated(&moved);
+        assert_eq!(moved["from_envelope"]["allocations"]["2024-06"], 500.0);
+
+        let status = server
+            .envelope_status(Parameters(EnvelopeStatusParams {
+                month: Some("2024-06".to_owned()),
+                convert_to: None,
+            }))
+            .await
+            .expect("should report");
+        let status = parse_paginated(&status);
+        assert_eq!(status["income"], 1_000.0);
+        assert_eq!(status["allocated"], 600.0);
+        assert_eq!(status["to_be_budgeted"], 400.0);
+        assert_eq!(status["envelopes"].as_array().map(Vec::len), Some(2));
+
+        let listed = server.list_envelopes().await.expect("should list");
+        let listed: Vec<serde_json::Value> =
+            serde_json::from_str(result_text(&listed)).expect("should parse");
+        assert_eq!(listed.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn handler_create_from_unknown_template_errors() {
+        let server = build_test_server().await;
+        let params = Parameters(CreateFromTemplateParams {
+            name: "missing".to_owned(),
+            date: None,
+            amount: Some(1.0),
+            comment: None,
+        });
+        assert!(server.create_from_template(params).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn handler_delete_transactions_by_filter_stages_preview() {
+        let server = build_test_server().await;
+        let params = Parameters(TransactionSelection {
+            transaction_type: Some(TransactionType::Expense),
+            ..Default::default()
+        });
+        let result = server
+            .delete_transactions_by_filter(params)
+            .await
+            .expect("should stage");
+        let preview = parse_paginated(&result);
+        let deleted = preview["deleted"].as_u64().expect("deleted count");
+        assert!(deleted > 0);
+        assert_eq!(
+            preview["deleted_transactions"]
+                .as_array()
+                .expect("rows")
+                .len(),
+            usize::try_from(deleted).expect("fits")
+        );
+        // Staging must not touch storage.
+        let remaining = server.client.transactions().await.expect("transactions");
+        assert_eq!(remaining.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn handler_delete_transactions_by_filter_requires_filter() {
+        let server = build_test_server().await;
+        let empty = Parameters(TransactionSelection::default());
+        assert!(server.delete_transactions_by_filter(empty).await.is_err());
+        let none_match = Parameters(TransactionSelection {
+            payee: Some("no such payee".to_owned()),
+            ..Default::default()
+        });
+        assert!(
+            server
+                .delete_transactions_by_filter(none_match)
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn handler_move_transactions_by_ids() {
+        let server = build_test_server().await;
+        let mut moved = sample_transaction("tx-move", 75.0, 0.0);
+        moved.outcome_account = AccountId::new("acc-2".to_owned());
+        moved.income_account = AccountId::new("acc-2".to_owned());
+        server
+            .client
+            .storage()
+            .upsert_transactions(vec![moved])
+            .await
+            .expect("upsert transactions");
+        let params = Parameters(MoveTransactionsParams {
+            selection: TransactionSelection::default(),
+            transaction_ids: Some(vec!["tx-move".to_owned()]),
+            target_account_id: "acc-1".to_owned(),
+        });
+        let result = server
+            .move_transactions(params)
+            .await
+            .expect("should stage");
+        let preview = parse_paginated(&result);
+        assert_eq!(preview["updated"], 1);
+        assert_eq!(
+            preview["transactions"][0]["outcome_account"],
+            "Main Account"
+        );
+    }
+
+    #[tokio::test]
+    async fn handler_tag_transactions_stages_changed_only() {
+        let server = build_test_server().await;
+        let params = |ids: &[&str], add: Option<&str>, remove: Option<&str>| {
+            Parameters(TagTransactionsParams {
+                transaction_ids: ids.iter().map(|id| (*id).to_owned()).collect(),
+                add_tag_ids: add.map(|id| vec![id.to_owned()]),
+                remove_tag_ids: remove.map(|id| vec![id.to_owned()]),
+            })
+        };
+        let result = server
+            .tag_transactions(params(
+                &["tx-expense", "tx-income", "tx-expense"],
+                Some("tag-1"),
+                None,
+            ))
+            .await
+            .expect("should stage");
+        let preview = parse_paginated(&result);
+        assert_eq!(preview["updated"], 2);
+        assert_eq!(preview["transactions"][0]["tags"][0], "Groceries");
+
+        let unknown = params(&["tx-expense"], Some("tag-missing"), None);
+        assert!(server.tag_transactions(unknown).await.is_err());
+        let unchanged = params(&["tx-expense"], None, Some("tag-1"));
+        assert!(server.tag_transactions(unchanged).await.is_err());
+        assert!(
+            server
+                .tag_transactions(params(&["tx-expense"], None, None))
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn recurrence_dates_stop_at_count_or_end() {
+        let date = |month, day| NaiveDate::from_ymd_opt(2024, month, day).expect("valid date");
+        let monthly =
+            recurrence_dates(date(1, 31), Interval::Month, 1, Some(3), None).expect("should list");
+        assert_eq!(monthly, vec![date(1, 31), date(2, 29), date(3, 31)]);
+        let weekly = recurrence_dates(date(6, 1), Interval::Week, 2, None, Some(date(6, 30)))
+            .expect("should list");
+        assert_eq!(weekly, vec![date(6, 1), date(6, 15), date(6, 29)]);
+        assert!(recurrence_dates(date(6, 1), Interval::Week, 1, None, Some(date(5, 1))).is_err());
+        let far = NaiveDate::from_ymd_opt(2030, 1, 1).expect("valid date");
+        assert!(recurrence_dates(date(1, 1), Interval::Week, 1, None, Some(far)).is_err());
+    }
+
+    #[tokio::test]
+    async fn handler_create_recurring_stages_series() {
+        let server = build_test_server().await;
+        let params = |count: Option<u32>| {
+            Parameters(CreateRecurringParams {
+                transaction: CreateTransactionParams {
+                    transaction_type: TransactionType::Expense,
+                    date: "2024-07-05".to_owned(),
+                    account_id: "acc-1".to_owned(),
+                    amount: 3_000.0,
+                    to_account_id: None,
+                    to_amount: None,
+                    instrument_id: None,
+                    to_instrument_id: None,
+                    tag_ids: None,
+                    payee: Some("Electricity".to_owned()),
+                    comment: None,
+                    merchant_id: None,
+                    latitude: None,
+                    longitude: None,
+                    mcc: None,
+                    planned: None,
+                    allow_out_of_range: None,
+                },
+                frequency: RecurrenceFrequency::Monthly,
+                every: None,
+                count,
+                end_date: None,
+                mode: RecurringMode::Transactions,
+            })
+        };
+        let result = server
+            .create_recurring(params(Some(3)))
+            .await
+            .expect("should stage");
+        let preview = parse_paginated(&result);
+        assert_eq!(preview["created"], 3);
+        assert_eq!(preview["transactions"][2]["date"], "2024-09-05");
+        assert!(server.create_recurring(params(None)).await.is_err());
+    }
+
+    #[test]
+    fn duplicate_of_scales_transfer_and_drops_bank_fields() {
+        let mut transfer = sample_transaction("tx-rent", 100.0, 50.0);
+        transfer.income_account = AccountId::new("acc-2".to_owned());
+        transfer.outcome_bank_id = Some("bank-1".to_owned());
+        transfer.op_outcome = Some(1.0);
+        let day = NaiveDate::from_ymd_opt(2024, 7, 1).expect("valid date");
+        let copy = duplicate_of(&transfer, day, Some(300.0));
+        assert_ne!(copy.id.as_inner(), "tx-rent");
+        assert_eq!(copy.date, day);
+        assert!((copy.outcome - 300.0).abs() < f64::EPSILON);
+        assert!((copy.income - 150.0).abs() < f64::EPSILON);
+        assert_eq!(copy.outcome_bank_id, None);
+        assert_eq!(copy.op_outcome, None);
+        assert_eq!(duplicate_of(&transfer, day, None).op_outcome, Some(1.0));
+    }
+
+    #[tokio::test]
+    async fn handler_duplicate_transaction_stages_copy() {
+        let server = build_test_server().await;
+        let params = Parameters(DuplicateTransactionParams {
+            transaction_id: "tx-expense".to_owned(),
+            date: Some("2024-07-01".to_owned()),
+            amount: Some(650.0),
+            allow_out_of_range: None,
+        });
+        let result = server
+            .duplicate_transaction(params)
+            .await
+            .expect("should stage");
+        let preview = parse_paginated(&result);
+        assert_eq!(preview["created"], 1);
+        assert_eq!(preview["transactions"][0]["date"], "2024-07-01");
+        assert_ne!(preview["transactions"][0]["id"], "tx-expense");
+
+        let missing = Parameters(DuplicateTransactionParams {
+            transaction_id: "tx-missing".to_owned(),
+            date: None,
+            amount: None,
+            allow_out_of_range: None,
+        });
+        assert!(server.duplicate_transaction(missing).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn handler_planned_transactions_stay_out_until_converted() {
+        let zenmoney =
+            FakeZenMoney::start(vec![(200, r#"{"serverTimestamp":1900000000}"#.to_owned())]);
+        let server = build_test_server_at(Some(&zenmoney.url)).await;
+        let mut params = sample_create_params(TransactionType::Expense);
+        params.amount = 30_000.0;
+        params.date = Utc::now()
+            .date_naive()
+            .checked_add_days(Days::new(10))
+            .expect("valid date")
+            .format("%Y-%m-%d")
+            .to_string();
+        params.planned = Some(true);
+        let created = server
+            .create_transaction(Parameters(params))
+            .await
+            .expect("should plan");
+        let created: serde_json::Value =
+            serde_json::from_str(result_text(&created)).expect("valid json");
+        let planned_id = created[0]["id"].as_str().expect("ID").to_owned();
+        assert!(zenmoney.requests().is_empty(), "planned stays local");
+
+        let listed = |page: &serde_json::Value| {
+            page["items"]
+                .as_array()
+                .expect("items")
+                .iter()
+                .any(|item| item["id"] == planned_id.as_str())
+        };
+        let all = Parameters(ListTransactionsParams::default());
+        let page = parse_paginated(&server.list_transactions(all).await.expect("should list"));
+        assert!(!listed(&page));
+        let acc_1 = Parameters(VerifyBalancesParams {
+            account_id: Some("acc-1".to_owned()),
+            tolerance: None,
+        });
+        let balances = server.verify_balances(acc_1).await.expect("should verify");
+        let balances = parse_paginated(&balances);
+        assert_eq!(balances["discrepancies"][0]["computed_balance"], 200.0);
+
+        let planned = parse_paginated(&server.list_planned().await.expect("should list"));
+        assert_eq!(planned["count"], 1);
+        assert_eq!(planned["due"], 0);
+
+        let convert = |ids: &[&str]| {
+            Parameters(ConvertPlannedParams {
+                transaction_ids: ids.iter().map(|id| (*id).to_owned()).collect(),
+            })
+        };
+        assert!(
+            server
+                .convert_planned(convert(&[&planned_id]))
+                .await
+                .is_ok()
+        );
+        let requests = zenmoney.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].contains(&planned_id), "converted one is pushed");
+        assert!(
+            server
+                .convert_planned(convert(&[&planned_id]))
+                .await
+                .is_err()
+        );
+        assert!(server.planned.list().expect("list").is_empty());
+    }
+
+    #[test]
+    fn build_transaction_guards_far_dates() {
         let maps = sample_maps();
-        let existing = vec![
-            sample_transaction("tx-1", 100.0, 0.0),
-            sample_transaction("tx-2", 200.0, 0.0),
-        ];
-        let operations = vec![
-            BulkOperation::Delete(DeleteTransactionParams {
-                id: "tx-1".to_owned(),
+        let mut params = sample_create_params(TransactionType::Expense);
+        params.date = Utc::now()
+            .date_naive()
+            .checked_add_days(Days::new(400))
+            .expect("valid date")
+            .format("%Y-%m-%d")
+            .to_string();
+        assert!(build_transaction(params.clone(), &maps).is_err());
+        params.allow_out_of_range = Some(true);
+        assert!(build_transaction(params, &maps).is_ok());
+    }
+
+    #[tokio::test]
+    async fn handler_default_account_fills_missing_account_id() {
+        let server = build_test_server().await;
+        let mut params = sample_create_params(TransactionType::Expense);
+        params.account_id = String::new();
+        let maps = server.lookup_maps().await.expect("maps");
+        assert!(build_transaction(params.clone(), &maps).is_err());
+
+        let set = |account_id: Option<&str>| {
+            Parameters(SetDefaultAccountParams {
+                account_id: account_id.map(str::to_owned),
+            })
+        };
+        assert!(
+            server
+                .set_default_account(set(Some("acc-missing")))
+                .await
+                .is_err()
+        );
+        let result = server
+            .set_default_account(set(Some("acc-1")))
+            .await
+            .expect("should set");
+        let current: serde_json::Value =
+            serde_json::from_str(result_text(&result)).expect("valid json");
+        assert_eq!(current["account_id"], "acc-1");
+        assert_eq!(current["source"], "saved");
+        let maps = server.lookup_maps().await.expect("maps");
+        let tx = build_transaction(params, &maps).expect("should build");
+        assert_eq!(tx.outcome_account.as_inner(), "acc-1");
+
+        let result = server
+            .set_default_account(set(None))
+            .await
+            .expect("should clear");
+        let current: serde_json::Value =
+            serde_json::from_str(result_text(&result)).expect("valid json");
+        assert!(current["account_id"].is_null());
+    }
+
+    #[tokio::test]
+    async fn handler_log_expense_needs_default_account_and_matches_tag_title() {
+        let server = build_test_server().await;
+        let params = Parameters(LogExpenseParams {
+            amount: 300.0,
+            text: Some("coffee".to_owned()),
+            date: None,
+            allow_out_of_range: None,
+        });
+        assert!(server.log_expense(params).await.is_err());
+
+        let maps = server.lookup_maps().await.expect("maps");
+        let mut inferred = Vec::new();
+        let guess = server
+            .guess_expense("groceries", &maps, &mut inferred)
+            .await
+            .expect("should guess");
+        assert_eq!(guess.tag_ids, Some(vec!["tag-1".to_owned()]));
+        assert!(guess.payee.is_none());
+        assert_eq!(
+            inferred,
+            vec!["tags: Groceries (category title)".to_owned()]
+        );
+    }
+
+    #[tokio::test]
+    async fn handler_log_expense_accepts_out_of_range_date_on_request() {
+        let zenmoney =
+            FakeZenMoney::start(vec![(200, r#"{"serverTimestamp":1900000000}"#.to_owned())]);
+        let server = build_test_server_at(Some(&zenmoney.url)).await;
+        server
+            .default_account
+            .set(Some("acc-1".to_owned()))
+            .expect("set default");
+        let params = |allow_out_of_range| {
+            Parameters(LogExpenseParams {
+                amount: 300.0,
+                text: None,
+                date: Some("2099-01-01".to_owned()),
+                allow_out_of_range,
+            })
+        };
+        let err = server
+            .log_expense(params(None))
+            .await
+            .expect_err("date beyond the window");
+        assert!(
+            err.message.contains("allow_out_of_range"),
+            "{}",
+            err.message
+        );
+        assert!(zenmoney.requests().is_empty());
+
+        let result = server
+            .log_expense(params(Some(true)))
+            .await
+            .expect("should log");
+        let logged: serde_json::Value =
+            serde_json::from_str(result_text(&result)).expect("valid json");
+        assert_eq!(logged["transaction"]["date"], "2099-01-01");
+        assert_eq!(zenmoney.requests().len(), 1);
+    }
+
+    #[test]
+    fn build_transaction_rejects_planned() {
+        let maps = sample_maps();
+        let mut params = sample_create_params(TransactionType::Expense);
+        params.planned = Some(true);
+        assert!(build_transaction(params, &maps).is_err());
+    }
+
+    #[tokio::test]
+    async fn handler_merge_transactions_pairs_hold_with_settlement() {
+        let server = build_test_server().await;
+        let mut hold = sample_transaction("tx-hold", 20.0, 0.0);
+        hold.hold = Some(true);
+        hold.comment = Some("Coffee".to_owned());
+        let settled = sample_transaction("tx-settled", 20.0, 0.0);
+        server
+            .client
+            .storage()
+            .upsert_transactions(vec![hold, settled])
+            .await
+            .expect("upsert transactions");
+        let params = |keep: Option<&str>, remove: Option<&str>| {
+            Parameters(MergeTransactionsParams {
+                keep_id: keep.map(str::to_owned),
+                remove_id: remove.map(str::to_owned),
+                account_id: None,
+                max_days: None,
+            })
+        };
+        let result = server
+            .merge_transactions(params(None, None))
+            .await
+            .expect("should stage");
+        let preview = parse_paginated(&result);
+        assert_eq!(preview["updated"], 1);
+        assert_eq!(preview["deleted"], 1);
+        assert_eq!(preview["transactions"][0]["id"], "tx-settled");
+        assert_eq!(preview["transactions"][0]["comment"], "Coffee");
+        assert_eq!(preview["deleted_transactions"][0]["id"], "tx-hold");
+
+        let explicit = params(Some("tx-expense"), Some("tx-settled"));
+        assert!(server.merge_transactions(explicit).await.is_ok());
+        let same = params(Some("tx-expense"), Some("tx-expense"));
+        assert!(server.merge_transactions(same).await.is_err());
+        let half = params(Some("tx-expense"), None);
+        assert!(server.merge_transactions(half).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn handler_move_transactions_rejects_ids_with_filters() {
+        let server = build_test_server().await;
+        let params = Parameters(MoveTransactionsParams {
+            selection: TransactionSelection {
+                payee: Some("x".to_owned()),
+                ..Default::default()
+            },
+            transaction_ids: Some(vec!["tx-1".to_owned()]),
+            target_account_id: "acc-1".to_owned(),
+        });
+        assert!(server.move_transactions(params).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn handler_list_transactions_unviewed_only() {
+        let server = build_test_server().await;
+        let mut fresh = sample_transaction("tx-new", 20.0, 0.0);
+        fresh.viewed = Some(false);
+        server
+            .client
+            .storage()
+            .upsert_transactions(vec![fresh])
+            .await
+            .expect("upsert transactions");
+        let params = Parameters(ListTransactionsParams {
+            unviewed_only: Some(true),
+            ..Default::default()
+        });
+        let result = server.list_transactions(params).await.expect("should list");
+        let page = parse_paginated(&result);
+        assert_eq!(page["total"], 1);
+        assert_eq!(page["items"][0]["id"], "tx-new");
+        assert_eq!(page["items"][0]["viewed"], false);
+    }
+
+    #[tokio::test]
+    async fn handler_list_transactions_hold_filter() {
+        let server = build_test_server().await;
+        let mut pending = sample_transaction("tx-pending", 20.0, 0.0);
+        pending.hold = Some(true);
+        server
+            .client
+            .storage()
+            .upsert_transactions(vec![pending])
+            .await
+            .expect("upsert transactions");
+        let held = Parameters(ListTransactionsParams {
+            hold: Some(true),
+            ..Default::default()
+        });
+        let page = parse_paginated(&server.list_transactions(held).await.expect("should list"));
+        assert_eq!(page["total"], 1);
+        assert_eq!(page["items"][0]["hold"], true);
+
+        let all = parse_paginated(
+            &server
+                .list_transactions(Parameters(ListTransactionsParams::default()))
+                .await
+                .expect("should list"),
+        );
+        let settled = Parameters(ListTransactionsParams {
+            hold: Some(false),
+            ..Default::default()
+        });
+        let page = parse_paginated(
+            &server
+                .list_transactions(settled)
+                .await
+                .expect("should list"),
+        );
+        assert_eq!(page["total"], all["total"].as_u64().expect("total") - 1);
+    }
+
+    #[tokio::test]
+    async fn handler_list_transactions_source_filter() {
+        let server = build_test_server().await;
+        let mut imported = sample_transaction("tx-imported", 20.0, 0.0);
+        imported.source = Some("Bank".to_owned());
+        imported.original_payee = Some("SHOP 42 MOSCOW".to_owned());
+        server
+            .client
+            .storage()
+            .upsert_transactions(vec![imported])
+            .await
+            .expect("upsert transactions");
+        let by_source = |source: &str| {
+            Parameters(ListTransactionsParams {
+                source: Some(source.to_owned()),
+                ..Default::default()
+            })
+        };
+        let page = parse_paginated(
+            &server
+                .list_transactions(by_source("imported"))
+                .await
+                .expect("should list"),
+        );
+        assert_eq!(page["total"], 1);
+        assert_eq!(page["items"][0]["source"], "Bank");
+        assert_eq!(page["items"][0]["original_payee"], "SHOP 42 MOSCOW");
+        let page = parse_paginated(
+            &server
+                .list_transactions(by_source("bank"))
+                .await
+                .expect("should list"),
+        );
+        assert_eq!(page["total"], 1);
+
+        let all = parse_paginated(
+            &server
+                .list_transactions(Parameters(ListTransactionsParams::default()))
+                .await
+                .expect("should list"),
+        );
+        let page = parse_paginated(
+            &server
+                .list_transactions(by_source("manual"))
+                .await
+                .expect("should list"),
+        );
+        assert_eq!(page["total"], all["total"].as_u64().expect("total") - 1);
+        assert!(page["items"][0].get("source").is_none());
+    }
+
+    #[tokio::test]
+    async fn handler_list_deleted_transactions() {
+        let server = build_test_server().await;
+        let mut removed = sample_transaction("tx-removed", 30.0, 0.0);
+        removed.deleted = true;
+        server
+            .client
+            .storage()
+            .upsert_transactions(vec![removed])
+            .await
+            .expect("upsert transactions");
+        let result = server
+            .list_deleted_transactions(Parameters(ListDeletedTransactionsParams::default()))
+            .await
+            .expect("should list");
+        let page = parse_paginated(&result);
+        assert_eq!(page["total"], 1);
+        assert_eq!(page["items"][0]["id"], "tx-removed");
+
+        let out_of_range = Parameters(ListDeletedTransactionsParams {
+            date_from: Some("2030-01-01".to_owned()),
+            ..Default::default()
+        });
+        let page = parse_paginated(
+            &server
+                .list_deleted_transactions(out_of_range)
+                .await
+                .expect("should list"),
+        );
+        assert_eq!(page["total"], 0);
+    }
+
+    #[tokio::test]
+    async fn handler_restore_transaction_errors() {
+        let server = build_test_server().await;
+        let missing = Parameters(RestoreTransactionParams {
+            id: "missing".to_owned(),
+        });
+        assert!(server.restore_transaction(missing).await.is_err());
+        let not_deleted = Parameters(RestoreTransactionParams {
+            id: "tx-expense".to_owned(),
+        });
+        assert!(server.restore_transaction(not_deleted).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn handler_list_transactions_near() {
+        let server = build_test_server().await;
+        let mut located = sample_transaction("tx-located", 20.0, 0.0);
+        located.latitude = Some(55.7601);
+        located.longitude = Some(37.6186);
+        server
+            .client
+            .storage()
+            .upsert_transactions(vec![located])
+            .await
+            .expect("upsert transactions");
+        let params = Parameters(ListTransactionsParams {
+            near: Some(NearFilter {
+                latitude: 55.7539,
+                longitude: 37.6208,
+                radius_km: 2.0,
             }),
-            BulkOperation::Delete(DeleteTransactionParams {
-                id: "tx-2".to_owned(),
+            ..Default::default()
+        });
+        let page = parse_paginated(&server.list_transactions(params).await.expect("should list"));
+        assert_eq!(page["total"], 1);
+        assert_eq!(page["items"][0]["id"], "tx-located");
+
+        let invalid = Parameters(ListTransactionsParams {
+            near: Some(NearFilter {
+                latitude: 55.7539,
+                longitude: 37.6208,
+                radius_km: 0.0,
             }),
-        ];
-        let (to_push, to_delete, created, updated) =
-            process_bulk_operations(operations, &existing, &maps).expect("should process");
-        assert!(to_push.is_empty());
-        assert_eq!(to_delete.len(), 2);
-        assert_eq!(created, 0);
-        assert_eq!(updated, 0);
+            ..Default::default()
+        });
+        assert!(server.list_transactions(invalid).await.is_err());
     }
 
-    // ── Async handler tests (using InMemoryStorage) ─────────────────
+    #[tokio::test]
+    async fn handler_list_transactions_mcc_filter() {
+        let server = build_test_server().await;
+        let mut coffee = sample_transaction("tx-coffee", 5.0, 0.0);
+        coffee.mcc = Some(5814);
+        server
+            .client
+            .storage()
+            .upsert_transactions(vec![coffee])
+            .await
+            .expect("upsert transactions");
+        let params = Parameters(ListTransactionsParams {
+            mcc: Some(5814),
+            ..Default::default()
+        });
+        let page = parse_paginated(&server.list_transactions(params).await.expect("should list"));
+        assert_eq!(page["total"], 1);
+        assert_eq!(page["items"][0]["mcc"], 5814);
+        assert_eq!(page["items"][0]["mcc_label"], "Fast food restaurants");
+    }
+
+    #[tokio::test]
+    async fn handler_mark_viewed_skips_viewed() {
+        let server = build_test_server().await;
+        let mut seen = sample_transaction("tx-seen", 20.0, 0.0);
+        seen.viewed = Some(true);
+        server
+            .client
+            .storage()
+            .upsert_transactions(vec![seen])
+            .await
+            .expect("upsert transactions");
+        let params = Parameters(MarkViewedParams {
+            selection: TransactionSelection::default(),
+            transaction_ids: Some(vec!["tx-seen".to_owned()]),
+        });
+        let result = server.mark_viewed(params).await.expect("should succeed");
+        let marked: serde_json::Value =
+            serde_json::from_str(result_text(&result)).expect("valid JSON");
+        assert_eq!(marked.as_array().map(Vec::len), Some(0));
+    }
+
+    #[tokio::test]
+    async fn handler_mark_viewed_errors() {
+        let server = build_test_server().await;
+        let unknown = Parameters(MarkViewedParams {
+            selection: TransactionSelection::default(),
+            transaction_ids: Some(vec!["missing".to_owned()]),
+        });
+        assert!(server.mark_viewed(unknown).await.is_err());
+        let no_filters = Parameters(MarkViewedParams {
+            selection: TransactionSelection::default(),
+            transaction_ids: None,
+        });
+        assert!(server.mark_viewed(no_filters).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn handler_import_receipt_qr_stages_expense() {
+        let server = build_test_server().await;
+        let params = Parameters(ImportReceiptQrParams {
+            qr: "t=20240115T1530&s=1234.56&fn=9289000100123456&i=12345&fp=1234567890&n=1"
+                .to_owned(),
+            account_id: "acc-1".to_owned(),
+            tag_ids: None,
+            payee: Some("Grocery".to_owned()),
+            merchant_id: None,
+            comment: None,
+        });
+        let result = server
+            .import_receipt_qr(params)
+            .await
+            .expect("should stage");
+        let staged = parse_paginated(&result);
+        assert_eq!(staged["operation"], "sale");
+        assert_eq!(staged["created"], 1);
+        assert_eq!(staged["transactions"][0]["date"], "2024-01-15");
+        assert_eq!(staged["transactions"][0]["outcome"], 1234.56);
+        assert_eq!(
+            staged["transactions"][0]["comment"],
+            "Receipt FN 9289000100123456 #12345"
+        );
+    }
+
+    #[tokio::test]
+    async fn handler_import_receipt_qr_rejects_bad_qr() {
+        let server = build_test_server().await;
+        let params = Parameters(ImportReceiptQrParams {
+            qr: "not a receipt".to_owned(),
+            account_id: "acc-1".to_owned(),
+            tag_ids: None,
+            payee: None,
+            merchant_id: None,
+            comment: None,
+        });
+        assert!(server.import_receipt_qr(params).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn handler_import_ofx_stages_only_new_entries() {
+        let server = build_test_server().await;
+        let content = "<OFX><BANKTRANLIST>\
+            <STMTTRN><DTPOSTED>20240614<TRNAMT>-500.00<FITID>B1<NAME>Shop</STMTTRN>\
+            <STMTTRN><DTPOSTED>20240615<TRNAMT>1000.00<FITID>tx-income</STMTTRN>\
+            <STMTTRN><DTPOSTED>20240620<TRNAMT>-42.50<FITID>B3<NAME>Cafe</STMTTRN>\
+            </BANKTRANLIST></OFX>";
+        let params = || ImportOfxParams {
+            path: None,
+            content: Some(content.to_owned()),
+            account_id: "acc-1".to_owned(),
+            tag_ids: None,
+            match_days: None,
+        };
+        let result = server
+            .import_ofx(Parameters(params()))
+            .await
+            .expect("should import");
+        let import = parse_paginated(&result);
+        assert_eq!(import["matched"], 2);
+        assert_eq!(import["new"], 1);
+        assert_eq!(import["rows"][0]["transaction_ids"][0], "tx-expense");
+        assert_eq!(import["rows"][1]["verdict"], "matched");
+        assert_eq!(import["rows"][2]["verdict"], "new");
+        let preview = &import["preparation"]["transactions"][0];
+        assert_eq!(preview["payee"], "Cafe");
+        assert_eq!(preview["outcome"], 42.5);
+        let preparation_id = import["preparation"]["preparation_id"]
+            .as_str()
+            .expect("staged");
+        {
+            let prepared = server.preparations.lock().expect("lock");
+            let staged = &prepared.get(preparation_id).expect("prepared").to_push;
+            assert_eq!(staged[0].outcome_bank_id.as_deref(), Some("B3"));
+        }
+
+        let mut missing = params();
+        missing.account_id = "acc-unknown".to_owned();
+        assert_not_found(
+            server.import_ofx(Parameters(missing)).await,
+            "account",
+            "acc-unknown",
+        );
+        let mut both = params();
+        both.path = Some("statement.ofx".to_owned());
+        assert!(server.import_ofx(Parameters(both)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn handler_debts_report_without_debt_accounts() {
+        let server = build_test_server().await;
+        let result = server
+            .debts_report(Parameters(DebtsReportParams::default()))
+            .await
+            .expect("should report");
+        let report = parse_paginated(&result);
+        assert_eq!(report["items"].as_array().map(Vec::len), Some(0));
+        assert!(report.get("total_owed_to_you").is_none());
+    }
 
-    async fn build_test_server() -> ZenMoneyMcpServer<InMemoryStorage> {
-        use zenmoney_rs::models::{
-            Account, AccountType, Budget, Instrument, Merchant, Reminder, ReminderId, Tag,
-        };
+    #[tokio::test]
+    async fn handler_loan_schedule_projects_loan() {
+        use zenmoney_rs::models::{Account, Interval};
 
-        let storage = InMemoryStorage::new();
-        let client = ZenMoney::builder()
-            .token("test-token")
-            .storage(storage)
-            .build()
-            .expect("should build test client");
-        let accounts = vec![
-            Account {
-                id: AccountId::new("acc-1".to_owned()),
-                changed: test_timestamp(),
-                user: UserId::new(1),
-                role: None,
-                instrument: Some(InstrumentId::new(1)),
-                company: None,
-                kind: AccountType::Checking,
-                title: "Main Account".to_owned(),
-                sync_id: None,
-                balance: Some(50_000.0),
-                start_balance: None,
-                credit_limit: None,
-                in_balance: true,
-                savings: None,
-                enable_correction: false,
-                enable_sms: false,
-                archive: false,
-                capitalization: None,
-                percent: None,
-                start_date: None,
-                end_date_offset: None,
-                end_date_offset_interval: None,
-                payoff_step: None,
-                payoff_interval: None,
-                balance_correction_type: None,
-                private: None,
-            },
-            Account {
-                id: AccountId::new("acc-2".to_owned()),
-                changed: test_timestamp(),
-                user: UserId::new(1),
-                role: None,
-                instrument: Some(InstrumentId::new(2)),
-                company: None,
-                kind: AccountType::Cash,
-                title: "USD Account".to_owned(),
-                sync_id: None,
-                balance: Some(1_000.0),
-                start_balance: None,
-                credit_limit: None,
-                in_balance: true,
-                savings: None,
-                enable_correction: false,
-                enable_sms: false,
-                archive: true,
-                capitalization: None,
-                percent: None,
-                start_date: None,
-                end_date_offset: None,
-                end_date_offset_interval: None,
-                payoff_step: None,
-                payoff_interval: None,
-                balance_correction_type: None,
-                private: None,
-            },
-        ];
-        let tags = vec![Tag {
-            id: TagId::new("tag-1".to_owned()),
-            changed: test_timestamp(),
-            user: UserId::new(1),
-            title: "Groceries".to_owned(),
-            parent: None,
-            icon: None,
-            picture: None,
-            color: None,
-            show_income: false,
-            show_outcome: true,
-            budget_income: false,
-            budget_outcome: true,
-            required: None,
-            static_id: None,
-            archive: None,
-        }];
-        let instruments = vec![
-            Instrument {
-                id: InstrumentId::new(1),
-                changed: test_timestamp(),
-                title: "Russian Ruble".to_owned(),
-                short_title: "RUB".to_owned(),
-                symbol: "\u{20bd}".to_owned(),
-                rate: 1.0,
-            },
-            Instrument {
-                id: InstrumentId::new(2),
-                changed: test_timestamp(),
-                title: "US Dollar".to_owned(),
-                short_title: "USD".to_owned(),
-                symbol: "$".to_owned(),
-                rate: 90.0,
-            },
-        ];
-        let transactions = vec![
-            sample_transaction("tx-expense", 500.0, 0.0),
-            sample_transaction("tx-income", 0.0, 1000.0),
-            sample_transfer("tx-transfer", 300.0, 300.0),
-        ];
-        let merchants = vec![Merchant {
-            id: MerchantId::new("m-1".to_owned()),
-            changed: test_timestamp(),
-            user: UserId::new(1),
-            title: "Coffee Shop".to_owned(),
-        }];
-        let budgets = vec![Budget {
+        let server = build_test_server().await;
+        let loan = Account {
+            id: AccountId::new("acc-loan".to_owned()),
             changed: test_timestamp(),
             user: UserId::new(1),
-            tag: Some(TagId::new("tag-1".to_owned())),
-            date: NaiveDate::from_ymd_opt(2024, 6, 1).expect("valid date"),
-            income: 0.0,
-            income_lock: false,
-            outcome: 15_000.0,
-            outcome_lock: false,
-            is_income_forecast: None,
-            is_outcome_forecast: None,
-        }];
-        let reminders = vec![Reminder {
-            id: ReminderId::new("rem-1".to_owned()),
+            role: None,
+            instrument: Some(InstrumentId::new(1)),
+            company: None,
+            kind: AccountType::Loan,
+            title: "Mortgage".to_owned(),
+            sync_id: None,
+            balance: Some(-12_000.0),
+            start_balance: None,
+            credit_limit: None,
+            in_balance: true,
+            savings: None,
+            enable_correction: false,
+            enable_sms: false,
+            archive: false,
+            capitalization: Some(false),
+            percent: Some(12.0),
+            start_date: NaiveDate::from_ymd_opt(2090, 1, 1),
+            end_date_offset: Some(12),
+            end_date_offset_interval: Some(Interval::Month),
+            payoff_step: Some(1),
+            payoff_interval: Some(Interval::Month),
+            balance_correction_type: None,
+            private: None,
+        };
+        server
+            .client
+            .storage()
+            .upsert_accounts(vec![loan])
+            .await
+            .expect("upsert loan");
+
+        let result = server
+            .loan_schedule(Parameters(LoanScheduleParams {
+                account_id: "acc-loan".to_owned(),
+                limit: Some(3),
+            }))
+            .await
+            .expect("should project schedule");
+        let schedule = parse_paginated(&result);
+        assert_eq!(schedule["remaining_periods"], 12);
+        assert_eq!(schedule["next_payment_date"], "2090-02-01");
+        assert_eq!(schedule["payoff_period"], "1 Month");
+        assert_eq!(schedule["schedule"].as_array().map(Vec::len), Some(3));
+        assert!(schedule.get("maturity_amount").is_none());
+    }
+
+    #[tokio::test]
+    async fn handler_loan_schedule_rejects_other_accounts() {
+        let server = build_test_server().await;
+        for account_id in ["acc-1", "acc-missing"] {
+            let params = Parameters(LoanScheduleParams {
+                account_id: account_id.to_owned(),
+                limit: None,
+            });
+            assert!(server.loan_schedule(params).await.is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn handler_card_statement_reports_credit() {
+        use zenmoney_rs::models::Account;
+
+        let server = build_test_server().await;
+        let card = Account {
+            id: AccountId::new("acc-card".to_owned()),
             changed: test_timestamp(),
             user: UserId::new(1),
-            income_instrument: InstrumentId::new(1),
-            income_account: AccountId::new("acc-1".to_owned()),
-            income: 0.0,
-            outcome_instrument: InstrumentId::new(1),
-            outcome_account: AccountId::new("acc-1".to_owned()),
-            outcome: 5_000.0,
-            tag: Some(vec![TagId::new("tag-1".to_owned())]),
-            merchant: None,
-            payee: Some("Supermarket".to_owned()),
-            comment: None,
-            interval: None,
-            step: None,
-            points: None,
-            start_date: test_date(),
-            end_date: None,
-            notify: false,
-        }];
+            role: None,
+            instrument: Some(InstrumentId::new(1)),
+            company: None,
+            kind: AccountType::CreditCard,
+            title: "Credit Card".to_owned(),
+            sync_id: None,
+            balance: Some(-2_500.0),
+            start_balance: None,
+            credit_limit: Some(10_000.0),
+            in_balance: true,
+            savings: None,
+            enable_correction: false,
+            enable_sms: false,
+            archive: false,
+            capitalization: None,
+            percent: None,
+            start_date: None,
+            end_date_offset: None,
+            end_date_offset_interval: None,
+            payoff_step: None,
+            payoff_interval: None,
+            balance_correction_type: None,
+            private: None,
+        };
+        server
+            .client
+            .storage()
+            .upsert_accounts(vec![card])
+            .await
+            .expect("upsert card");
 
-        client
+        let result = server
+            .card_statement(Parameters(CardStatementParams {
+                account_id: "acc-card".to_owned(),
+                closing_day: 25,
+                cycle: None,
+            }))
+            .await
+            .expect("should summarize");
+        let statement = parse_paginated(&result);
+        assert_eq!(statement["available_credit"], 7_500.0);
+        assert_eq!(statement["utilization_percent"], 25.0);
+        assert!(statement["days_left"].as_i64().is_some());
+    }
+
+    #[tokio::test]
+    async fn handler_card_statement_errors() {
+        let server = build_test_server().await;
+        for (account_id, closing_day) in [("acc-1", 25), ("acc-missing", 25), ("acc-1", 0)] {
+            let params = Parameters(CardStatementParams {
+                account_id: account_id.to_owned(),
+                closing_day,
+                cycle: Some(StatementCycle::Previous),
+            });
+            assert!(server.card_statement(params).await.is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn handler_digest_summarizes_month() {
+        let server = build_test_server().await;
+        let result = server
+            .digest(Parameters(DigestParams {
+                period: Some(DigestPeriod::Month),
+                date_to: Some("2024-06-30".to_owned()),
+                convert_to: None,
+            }))
+            .await
+            .expect("should summarize");
+        let digest = parse_paginated(&result);
+        assert_eq!(digest["date_from"], "2024-05-31");
+        assert_eq!(digest["income"], 1_000.0);
+        assert_eq!(digest["expense"], 500.0);
+        assert_eq!(digest["net"], 500.0);
+        assert_eq!(digest["largest_expenses"][0]["amount"], 500.0);
+        assert!(digest.get("budget_overruns").is_none());
+
+        let empty = server
+            .digest(Parameters(DigestParams {
+                date_to: Some("2020-01-07".to_owned()),
+                ..Default::default()
+            }))
+            .await
+            .expect("should summarize");
+        let empty = parse_paginated(&empty);
+        assert_eq!(empty["date_from"], "2020-01-01");
+        assert!(empty.get("top_categories").is_none());
+    }
+
+    #[tokio::test]
+    async fn handler_spending_patterns() {
+        let server = build_test_server().await;
+        let result = server
+            .spending_patterns(Parameters(SpendingPatternsParams {
+                date_from: Some("2024-06-01".to_owned()),
+                date_to: Some("2024-06-30".to_owned()),
+                by_category: Some(true),
+                ..Default::default()
+            }))
+            .await
+            .expect("should report");
+        let patterns = parse_paginated(&result);
+        // The sample expense of 500 falls on Saturday 2024-06-15.
+        assert_eq!(patterns["by_weekday"][5]["total"], 500.0);
+        assert_eq!(patterns["weekday_daily_average"], 0.0);
+        assert!(patterns["by_weekday"][5]["categories"].is_array());
+
+        let reversed = Parameters(SpendingPatternsParams {
+            date_from: Some("2024-07-01".to_owned()),
+            date_to: Some("2024-06-01".to_owned()),
+            ..Default::default()
+        });
+        assert!(server.spending_patterns(reversed).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn handler_transfer_report() {
+        let server = build_test_server().await;
+        let result = server
+            .transfer_report(Parameters(TransferReportParams::default()))
+            .await
+            .expect("should report");
+        let report = parse_paginated(&result);
+        assert_eq!(report["transfer_count"], 1);
+        assert_eq!(report["flows"][0]["from_account"], "Main Account");
+        assert_eq!(report["flows"][0]["to_account"], "USD Account");
+        assert_eq!(report["flows"][0]["sent"], 300.0);
+        assert_eq!(report["accounts"][0]["net"], -300.0);
+        assert_eq!(report["accounts"][1]["transferred_in"], 300.0);
+
+        let unknown = Parameters(TransferReportParams {
+            convert_to: Some(999),
+            ..Default::default()
+        });
+        assert!(server.transfer_report(unknown).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn handler_savings_rate() {
+        let server = build_test_server().await;
+        let params = || SavingsRateParams {
+            date_from: Some("2024-06-01".to_owned()),
+            date_to: Some("2024-06-30".to_owned()),
+            ..Default::default()
+        };
+        let result = server
+            .savings_rate(Parameters(params()))
+            .await
+            .expect("should report");
+        let report = parse_paginated(&result);
+        assert_eq!(report["months"][0]["month"], "2024-06");
+        assert_eq!(report["total"]["net_saved"], 500.0);
+        assert_eq!(report["total"]["savings_percent"], 50.0);
+        assert!(report["total"].get("to_savings").is_none());
+
+        let mut accounts = server.client.accounts().await.expect("accounts");
+        for account in &mut accounts {
+            account.savings = Some(account.id.as_inner() == "acc-2");
+        }
+        server
+            .client
             .storage()
             .upsert_accounts(accounts)
             .await
             .expect("upsert accounts");
-        client
-            .storage()
-            .upsert_tags(tags)
+        let result = server
+            .savings_rate(Parameters(SavingsRateParams {
+                savings_transfers: Some(true),
+                ..params()
+            }))
             .await
-            .expect("upsert tags");
-        client
-            .storage()
-            .upsert_instruments(instruments)
+            .expect("should report");
+        let report = parse_paginated(&result);
+        // The 300 transfer from Main Account lands in the savings account.
+        assert_eq!(report["total"]["to_savings"], 300.0);
+        assert_eq!(report["total"]["savings_percent"], 30.0);
+
+        let reversed = Parameters(SavingsRateParams {
+            date_from: Some("2024-07-01".to_owned()),
+            ..params()
+        });
+        assert!(server.savings_rate(reversed).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn handler_year_review() {
+        let server = build_test_server().await;
+        let result = server
+            .year_review(Parameters(YearReviewParams {
+                year: Some(2024),
+                convert_to: None,
+            }))
             .await
-            .expect("upsert instruments");
-        client
+            .expect("should review");
+        let review = parse_paginated(&result);
+        assert_eq!(review["year"], 2024);
+        assert_eq!(review["income"], 1000.0);
+        assert_eq!(review["expense"], 500.0);
+        assert_eq!(review["transaction_count"], 3);
+        assert_eq!(review["months"][0]["month"], "2024-06");
+        assert_eq!(review["largest_expenses"][0]["amount"], 500.0);
+        // The uncategorized 500 expense stays within the Groceries budget.
+        assert_eq!(review["budget_adherence"]["budgets"], 1);
+        assert_eq!(review["budget_adherence"]["kept"], 1);
+
+        let invalid = Parameters(YearReviewParams {
+            year: Some(1_000_000),
+            convert_to: None,
+        });
+        assert!(server.year_review(invalid).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn completion_candidates_match_titles() {
+        let server = build_test_server().await;
+        let accounts = server
+            .completion_candidates(CompletionSource::Account)
+            .await
+            .expect("should list accounts");
+        assert_eq!(
+            complete_ids(&accounts, "main").values,
+            vec!["acc-1".to_owned()]
+        );
+        let tags = server
+            .completion_candidates(CompletionSource::Tag)
+            .await
+            .expect("should list tags");
+        assert_eq!(complete_ids(&tags, "gro").values, vec!["tag-1".to_owned()]);
+        let merchants = server
+            .completion_candidates(CompletionSource::Merchant)
+            .await
+            .expect("should list merchants");
+        assert_eq!(
+            complete_ids(&merchants, "coffee").values,
+            vec!["m-1".to_owned()]
+        );
+    }
+
+    #[tokio::test]
+    async fn read_entity_resolves_template_uris() {
+        let server = build_test_server().await;
+        let transaction: serde_json::Value = serde_json::from_str(
+            &server
+                .read_entity("zenmoney://transaction/tx-expense")
+                .await
+                .expect("should read transaction"),
+        )
+        .expect("valid json");
+        assert_eq!(transaction["id"], "tx-expense");
+        let account: serde_json::Value = serde_json::from_str(
+            &server
+                .read_entity("zenmoney://account/acc-2")
+                .await
+                .expect("should read account"),
+        )
+        .expect("valid json");
+        assert_eq!(account["title"], "USD Account");
+        let budgets: serde_json::Value = serde_json::from_str(
+            &server
+                .read_entity("zenmoney://budget/2024-06")
+                .await
+                .expect("should read budgets"),
+        )
+        .expect("valid json");
+        assert_eq!(budgets.as_array().map(Vec::len), Some(1));
+        let inbox: serde_json::Value = serde_json::from_str(
+            &server
+                .read_entity("zenmoney://inbox")
+                .await
+                .expect("should read inbox"),
+        )
+        .expect("valid json");
+        assert_eq!(inbox["count"], 0);
+        assert!(
+            server
+                .read_entity("zenmoney://account/acc-missing")
+                .await
+                .is_err()
+        );
+        assert!(server.read_entity("zenmoney://budget/june").await.is_err());
+        assert!(server.read_entity("zenmoney://tag/tag-1").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn resource_stamps_cover_readable_entities() {
+        let server = build_test_server().await;
+        let stamps = server
+            .resource_stamps()
+            .await
+            .expect("should snapshot resources");
+        assert!(stamps.contains_key("zenmoney://account/acc-1"));
+        assert!(stamps.contains_key("zenmoney://transaction/tx-expense"));
+        assert!(stamps.contains_key("zenmoney://budget/2024-06"));
+        // Without a connected client there is nothing to notify.
+        server.refresh_resources().await;
+    }
+
+    #[tokio::test]
+    async fn entity_snapshot_records_selected_kinds() {
+        let server = build_test_server().await;
+        let kinds = BTreeSet::from([EntityKind::Transaction, EntityKind::Account]);
+        let before = server
+            .entity_snapshot(&kinds)
+            .await
+            .expect("should snapshot");
+        assert_eq!(before.len(), 2);
+        assert_eq!(before[&EntityKind::Transaction].len(), 3);
+
+        server
+            .client
             .storage()
-            .upsert_transactions(transactions)
+            .upsert_transactions(vec![sample_transaction("tx-new", 10.0, 0.0)])
             .await
             .expect("upsert transactions");
-        client
-            .storage()
-            .upsert_merchants(merchants)
+        let after = server
+            .entity_snapshot(&kinds)
             .await
-            .expect("upsert merchants");
-        client
+            .expect("should snapshot");
+        let changes = count_changes(&before, &after);
+        assert_eq!(changes[&EntityKind::Transaction].added, 1);
+        assert_eq!(changes[&EntityKind::Account].total(), 0);
+    }
+
+    #[tokio::test]
+    async fn inbox_lists_unviewed_and_transactions_after_review() {
+        async fn inbox(server: &ZenMoneyMcpServer<InMemoryStorage>) -> serde_json::Value {
+            let result = server
+                .inbox(Parameters(InboxParams::default()))
+                .await
+                .expect("should list inbox");
+            serde_json::from_str(result_text(&result)).expect("json")
+        }
+
+        let server = build_test_server().await;
+        let first = inbox(&server).await;
+        assert_eq!(first["total"], 3);
+        assert_eq!(first["unviewed"], 3);
+        assert!(first["reviewed_through"].is_null());
+
+        let reviewed = server
+            .mark_reviewed(Parameters(MarkReviewedParams {
+                through: None,
+                mark_viewed: Some(false),
+            }))
+            .await
+            .expect("should mark reviewed");
+        let review: serde_json::Value = serde_json::from_str(result_text(&reviewed)).expect("json");
+        assert_eq!(review["marked_viewed"], 0);
+        let backwards = MarkReviewedParams {
+            through: Some("2000-01-01".to_owned()),
+            mark_viewed: Some(false),
+        };
+        assert!(server.mark_reviewed(Parameters(backwards)).await.is_err());
+
+        let mut arrived = sample_transaction("tx-arrived", 0.0, 42.0);
+        arrived.viewed = Some(true);
+        arrived.created = Utc::now() + chrono::TimeDelta::hours(1);
+        server
+            .client
             .storage()
-            .upsert_budgets(budgets)
+            .upsert_transactions(vec![arrived])
             .await
-            .expect("upsert budgets");
-        client
+            .expect("upsert transactions");
+        let second = inbox(&server).await;
+        assert_eq!(second["total"], 4);
+        assert_eq!(second["created_since_review"], 1);
+        assert_eq!(second["transactions"][0]["id"], "tx-arrived");
+    }
+
+    #[tokio::test]
+    async fn record_sync_keeps_last_changes() {
+        let server = build_test_server().await;
+        let empty = server.last_sync_changes().await.expect("should respond");
+        assert!(result_text(&empty).contains("No sync has run"));
+
+        let before = server
+            .entity_snapshot(&BTreeSet::from(EntityKind::ALL))
+            .await
+            .expect("should snapshot");
+        server
+            .client
             .storage()
-            .upsert_reminders(reminders)
+            .upsert_transactions(vec![sample_transaction("tx-new", 10.0, 0.0)])
             .await
-            .expect("upsert reminders");
+            .expect("upsert transactions");
+        let summary = server
+            .record_sync(&before, false)
+            .await
+            .expect("should record");
+        assert_eq!(summary.total_changed, 1);
+        assert_eq!(summary.changes.len(), 1);
 
-        ZenMoneyMcpServer::new(client)
+        let result = server.last_sync_changes().await.expect("should respond");
+        let last: serde_json::Value =
+            serde_json::from_str(result_text(&result)).expect("valid json");
+        assert_eq!(last["changes"]["transaction"]["added"], 1);
+        assert_eq!(last["full"], false);
     }
 
-    /// Extracts the text string from a successful `CallToolResult`.
-    fn result_text(result: &CallToolResult) -> &str {
+    #[tokio::test]
+    async fn purge_local_data_requires_phrase_and_wipes_dir() {
+        let dir = TempDir::new();
+        std::fs::write(dir.join("transactions.json"), b"[]").expect("write file");
+        let server = build_test_server().await.with_data_dir(dir.to_path_buf());
+        let wrong = PurgeLocalDataParams {
+            confirm: "yes".to_owned(),
+        };
         assert!(
-            !result.is_error.unwrap_or(false),
-            "result should not be error"
+            server
+                .purge_local_data_confirmed(wrong, &Confirmation::default())
+                .await
+                .is_err()
         );
-        result.content[0]
-            .as_text()
-            .expect("expected text content")
-            .text
-            .as_str()
+        assert!(dir.join("transactions.json").exists());
+
+        let params = || PurgeLocalDataParams {
+            confirm: PURGE_CONFIRMATION.to_owned(),
+        };
+        let declined = server
+            .purge_local_data_confirmed(params(), &Confirmation::declined())
+            .await
+            .expect("declining is not an error");
+        let kept: serde_json::Value =
+            serde_json::from_str(result_text(&declined)).expect("valid json");
+        assert_eq!(kept["purged"], false);
+        assert!(dir.join("transactions.json").exists());
+
+        server
+            .pending
+            .push(PendingChange {
+                id: "p-1".to_owned(),
+                tool: "create_transaction".to_owned(),
+                queued_at: test_timestamp(),
+                transactions: vec![sample_transaction("tx-queued", 10.0, 0.0)],
+                previous: Vec::new(),
+            })
+            .expect("queue change");
+        server
+            .planned
+            .add(sample_transaction("tx-planned", 100.0, 0.0))
+            .expect("plan");
+        server
+            .default_account
+            .set(Some("acc-1".to_owned()))
+            .expect("set default");
+        let result = server
+            .purge_local_data_confirmed(params(), &Confirmation::default())
+            .await
+            .expect("should purge");
+        let purged: serde_json::Value =
+            serde_json::from_str(result_text(&result)).expect("valid json");
+        assert_eq!(purged["purged"], true);
+        assert_eq!(purged["files_deleted"], 1);
+        assert_eq!(purged["bytes_overwritten"], 2);
+        assert_eq!(purged["pending_discarded"], 1);
+        assert_eq!(purged["planned_cleared"], 1);
+        assert_eq!(purged["default_account_cleared"], true);
+        assert!(server.pending.list().expect("list").is_empty());
+        assert!(server.planned.list().expect("list").is_empty());
+        assert!(!dir.join("transactions.json").exists());
     }
 
     #[tokio::test]
-    async fn handler_list_accounts_all() {
+    async fn export_writes_one_file_per_account() {
+        let dir = TempDir::new();
         let server = build_test_server().await;
-        let params = Parameters(ListAccountsParams { active_only: false });
+        let params = || ExportParams {
+            directory: dir.display().to_string(),
+            selection: TransactionSelection::default(),
+        };
         let result = server
-            .list_accounts(params)
+            .export_qif(Parameters(params()))
             .await
-            .expect("should list accounts");
-        let accounts: Vec<serde_json::Value> =
-            serde_json::from_str(result_text(&result)).expect("should parse JSON");
-        assert_eq!(accounts.len(), 2);
+            .expect("should export");
+        let export: serde_json::Value = serde_json::from_str(result_text(&result)).expect("json");
+        assert_eq!(export["format"], "qif");
+        assert_eq!(export["transactions"], 3);
+        assert_eq!(export["files"][0]["account"], "Main Account");
+        assert_eq!(export["files"][0]["transactions"], 3);
+        assert_eq!(export["files"][1]["account"], "USD Account");
+        assert_eq!(export["files"][1]["transactions"], 1);
+        let usd = export["files"][1]["path"].as_str().expect("path");
+        let qif = std::fs::read_to_string(usd).expect("read qif");
+        assert!(qif.contains("T300.00\nL[Main Account]"));
+
+        let ofx_result = server
+            .export_ofx(Parameters(params()))
+            .await
+            .expect("should export");
+        let ofx_export: serde_json::Value =
+            serde_json::from_str(result_text(&ofx_result)).expect("json");
+        let main = ofx_export["files"][0]["path"].as_str().expect("path");
+        let ofx = std::fs::read_to_string(main).expect("read ofx");
+        assert!(ofx.contains("<CURDEF>RUB</CURDEF>"));
+        assert!(ofx.contains("<TRNAMT>-500.00</TRNAMT>"));
     }
 
     #[tokio::test]
-    async fn handler_list_accounts_active_only() {
+    async fn export_ledger_writes_beancount_journal() {
+        let dir = TempDir::new();
         let server = build_test_server().await;
-        let params = Parameters(ListAccountsParams { active_only: true });
-        let result = server.list_accounts(params).await.expect("should list");
-        let accounts: Vec<serde_json::Value> =
-            serde_json::from_str(result_text(&result)).expect("should parse");
-        assert_eq!(accounts.len(), 1);
+        let result = server
+            .export_ledger(Parameters(ExportLedgerParams {
+                path: dir.display().to_string(),
+                dialect: LedgerDialect::Beancount,
+                selection: TransactionSelection::default(),
+            }))
+            .await
+            .expect("should export");
+        let export: serde_json::Value = serde_json::from_str(result_text(&result)).expect("json");
+        assert_eq!(export["dialect"], "beancount");
+        assert_eq!(export["transactions"], 3);
+        let path = export["path"].as_str().expect("path");
+        assert!(path.ends_with("zenmoney.beancount"));
+        let journal = std::fs::read_to_string(path).expect("read journal");
+        assert!(journal.contains("2024-06-15 open Assets:Main-Account\n"));
+        assert!(journal.contains("2024-06-15 commodity RUB\n"));
+        assert!(journal.contains("    Expenses:Uncategorized  500.00 RUB\n"));
+        assert!(journal.contains("    Assets:USD-Account  300.00 USD @@ 300.00 RUB\n"));
     }
 
-    /// Parses a paginated transactions response from a `CallToolResult`.
-    fn parse_paginated(result: &CallToolResult) -> serde_json::Value {
-        serde_json::from_str(result_text(result)).expect("should parse paginated response")
+    #[tokio::test]
+    async fn scheduled_csv_report_appends_rows_once_with_header() {
+        let dir = TempDir::new();
+        let output = dir.join("spending.csv");
+        let config = dir.join("schedule.json");
+        let schedule = serde_json::json!({"jobs": [{
+            "name": "daily", "cron": "0 0 * * *", "report": "export_csv",
+            "arguments": {"transaction_type": "expense"}, "append_to": output,
+        }]});
+        std::fs::write(&config, schedule.to_string()).expect("write schedule");
+        let jobs = crate::schedule::load_schedule(&config).expect("valid schedule");
+        let server = build_test_server().await;
+        let report = server
+            .csv_report(serde_json::from_value(jobs[0].arguments.clone()).expect("arguments"))
+            .await
+            .expect("should render");
+        for _run in 0..2 {
+            let _file = jobs[0]
+                .write_report(Utc::now(), &report)
+                .expect("should append");
+        }
+        let text = std::fs::read_to_string(&output).expect("read report");
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("date,id,type,"));
+        assert!(lines[1].starts_with("2024-06-15,tx-expense,expense,500.00,RUB,Main Account,"));
+        assert_eq!(lines[1], lines[2]);
     }
 
     #[tokio::test]
-    async fn handler_list_transactions_default() {
+    async fn export_snapshot_bounds_transactions() {
+        let dir = TempDir::new();
         let server = build_test_server().await;
-        let params = Parameters(ListTransactionsParams::default());
+        let snapshot = |date_from: &str| ExportSnapshotParams {
+            path: dir.display().to_string(),
+            date_from: Some(date_from.to_owned()),
+            date_to: None,
+            include_deleted: None,
+        };
         let result = server
-            .list_transactions(params)
+            .export_snapshot(Parameters(snapshot("2024-06-01")))
             .await
-            .expect("should list transactions");
-        let page = parse_paginated(&result);
-        assert_eq!(page["items"].as_array().expect("items array").len(), 3);
-        assert_eq!(page["total"], 3);
-        assert_eq!(page["offset"], 0);
-        assert_eq!(page["limit"], DEFAULT_TRANSACTION_LIMIT);
+            .expect("should export");
+        let export: serde_json::Value = serde_json::from_str(result_text(&result)).expect("json");
+        assert_eq!(export["schema_version"], 1);
+        assert_eq!(export["counts"]["accounts"], 2);
+        assert_eq!(export["counts"]["transactions"], 3);
+        let text = std::fs::read_to_string(export["path"].as_str().expect("path")).expect("read");
+        let document: serde_json::Value = serde_json::from_str(&text).expect("json");
+        assert_eq!(document["schema"], "zenmoney-mcp/snapshot");
+        assert_eq!(document["transactions"]["date_from"], "2024-06-01");
+
+        let later = server
+            .export_snapshot(Parameters(snapshot("2024-07-01")))
+            .await
+            .expect("should export");
+        let later_export: serde_json::Value =
+            serde_json::from_str(result_text(&later)).expect("json");
+        assert_eq!(later_export["counts"]["transactions"], 0);
+        assert!(
+            server
+                .export_snapshot(Parameters(snapshot("June")))
+                .await
+                .is_err()
+        );
     }
 
     #[tokio::test]
-    async fn handler_list_transactions_filter_expense() {
+    async fn backup_data_round_trips_and_stages_revert() {
+        let dir = TempDir::new();
         let server = build_test_server().await;
-        let params = Parameters(ListTransactionsParams {
-            transaction_type: Some(TransactionType::Expense),
-            ..Default::default()
+        let result = server
+            .backup_data(Parameters(BackupDataParams {
+                path: dir.display().to_string(),
+            }))
+            .await
+            .expect("should back up");
+        let backup: serde_json::Value =
+            serde_json::from_str(result_text(&result)).expect("valid json");
+        assert_eq!(backup["counts"]["accounts"], 2);
+        assert_eq!(backup["counts"]["transactions"], 3);
+        let path = backup["path"].as_str().expect("path").to_owned();
+
+        // Edit one transaction and add another after the backup was taken.
+        let mut edited = sample_transaction("tx-expense", 750.0, 0.0);
+        edited.changed = DateTime::from_timestamp(1_800_000_000, 0).expect("valid timestamp");
+        server
+            .client
+            .storage()
+            .upsert_transactions(vec![edited, sample_transaction("tx-new", 10.0, 0.0)])
+            .await
+            .expect("upsert transactions");
+        let revert = server
+            .restore_data(Parameters(RestoreDataParams {
+                path: path.clone(),
+                mode: Some(RestoreMode::RevertTransactions),
+            }))
+            .await
+            .expect("should stage revert");
+        let preview: serde_json::Value =
+            serde_json::from_str(result_text(&revert)).expect("valid json");
+        assert_eq!(preview["updated"], 1);
+        assert_eq!(preview["deleted"], 1);
+        assert_eq!(preview["deleted_transactions"][0]["id"], "tx-new");
+
+        let restored = server
+            .restore_data(Parameters(RestoreDataParams {
+                path: path.clone(),
+                mode: None,
+            }))
+            .await
+            .expect("should restore");
+        let summary: serde_json::Value =
+            serde_json::from_str(result_text(&restored)).expect("valid json");
+        assert_eq!(summary["counts"]["transactions"], 3);
+
+        std::fs::write(&path, b"{}").expect("corrupt backup");
+        let corrupted = RestoreDataParams { path, mode: None };
+        assert!(server.restore_data(Parameters(corrupted)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn restore_then_sync_lets_server_data_win() {
+        let mut remote = sample_transaction("tx-expense", 900.0, 0.0);
+        remote.changed = DateTime::from_timestamp(1_900_000_000, 0).expect("valid timestamp");
+        let diff = serde_json::json!({
+            "serverTimestamp": 1_900_000_000,
+            "transaction": [remote],
         });
-        let result = server.list_transactions(params).await.expect("should list");
-        let page = parse_paginated(&result);
-        assert_eq!(page["items"].as_array().expect("items").len(), 1);
-        assert_eq!(page["total"], 1);
+        let zenmoney = FakeZenMoney::start(vec![(200, diff.to_string())]);
+        let dir = TempDir::new();
+        let server = build_test_server_at(Some(&zenmoney.url)).await;
+        let result = server
+            .backup_data(Parameters(BackupDataParams {
+                path: dir.display().to_string(),
+            }))
+            .await
+            .expect("should back up");
+        let backup: serde_json::Value =
+            serde_json::from_str(result_text(&result)).expect("valid json");
+        let path = backup["path"].as_str().expect("path").to_owned();
+        server
+            .client
+            .storage()
+            .upsert_transactions(vec![sample_transaction("tx-new", 10.0, 0.0)])
+            .await
+            .expect("upsert transactions");
+
+        let _restored = server
+            .restore_data(Parameters(RestoreDataParams { path, mode: None }))
+            .await
+            .expect("should restore");
+        let ids: Vec<String> = server
+            .client
+            .transactions()
+            .await
+            .expect("transactions")
+            .into_iter()
+            .map(|tx| tx.id.into_inner())
+            .collect();
+        assert!(
+            !ids.contains(&"tx-new".to_owned()),
+            "restore replaces local data"
+        );
+        assert!(server.restored.load(Ordering::Acquire));
+
+        let _synced = server.sync().await.expect("should sync");
+        assert!(!server.restored.load(Ordering::Acquire));
+        let sent: serde_json::Value =
+            serde_json::from_str(&zenmoney.requests()[0]).expect("valid request");
+        assert_eq!(sent["serverTimestamp"], 0);
+        let expense = server
+            .client
+            .transactions()
+            .await
+            .expect("transactions")
+            .into_iter()
+            .find(|tx| tx.id.as_inner() == "tx-expense")
+            .expect("expense");
+        assert!((expense.outcome - 900.0).abs() < f64::EPSILON);
     }
 
     #[tokio::test]
-    async fn handler_list_transactions_with_limit() {
+    async fn handler_reconcile_account_errors() {
         let server = build_test_server().await;
-        let params = Parameters(ListTransactionsParams {
-            limit: Some(1),
-            ..Default::default()
+        let matching = Parameters(ReconcileAccountParams {
+            account_id: "acc-1".to_owned(),
+            actual_balance: 50_000.0,
+            date: None,
+            comment: None,
         });
-        let result = server.list_transactions(params).await.expect("should list");
-        let page = parse_paginated(&result);
-        assert_eq!(page["items"].as_array().expect("items").len(), 1);
-        assert_eq!(page["total"], 3);
-        assert_eq!(page["limit"], 1);
+        assert!(server.reconcile_account(matching).await.is_err());
+        let missing = Parameters(ReconcileAccountParams {
+            account_id: "acc-missing".to_owned(),
+            actual_balance: 1.0,
+            date: None,
+            comment: None,
+        });
+        assert!(server.reconcile_account(missing).await.is_err());
     }
 
     #[tokio::test]
-    async fn handler_list_transactions_sort_asc() {
+    async fn handler_link_merchant_no_matches_errors() {
         let server = build_test_server().await;
-        let params = Parameters(ListTransactionsParams {
-            sort: Some(SortDirection::Asc),
-            ..Default::default()
+        let params = Parameters(LinkMerchantParams {
+            merchant_id: "m-1".to_owned(),
+            payee_pattern: "nothing matches this".to_owned(),
+            overwrite: Some(true),
+            date_from: None,
+            date_to: None,
         });
-        let result = server.list_transactions(params).await.expect("should list");
-        assert!(!result.is_error.unwrap_or(false));
+        assert!(server.link_merchant(params).await.is_err());
     }
 
     #[tokio::test]
-    async fn handler_list_transactions_uncategorized() {
+    async fn handler_create_tag_blank_title_errors() {
         let server = build_test_server().await;
-        let params = Parameters(ListTransactionsParams {
-            uncategorized: Some(true),
-            ..Default::default()
+        let params = Parameters(sample_create_tag_params("   "));
+        let result = server.create_tag(params).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn handler_create_tag_missing_parent_errors() {
+        let server = build_test_server().await;
+        let mut create_params = sample_create_tag_params("New category");
+        create_params.parent_tag_id = Some("missing-parent".to_owned());
+        let params = Parameters(create_params);
+        let result = server.create_tag(params).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn handler_get_instrument_found() {
+        let server = build_test_server().await;
+        let params = Parameters(GetInstrumentParams { id: 1 });
+        let result = server.get_instrument(params).await.expect("should get");
+        assert!(result_text(&result).contains("Russian Ruble"));
+    }
+
+    #[tokio::test]
+    async fn get_raw_entity_returns_stored_fields() {
+        let server = build_test_server().await;
+        let params = Parameters(GetRawEntityParams {
+            kind: RawEntityKind::Transaction,
+            id: "tx-expense".to_owned(),
         });
-        let result = server.list_transactions(params).await.expect("should list");
-        let page = parse_paginated(&result);
-        // All sample transactions have no tags.
-        assert_eq!(page["items"].as_array().expect("items").len(), 3);
-        assert_eq!(page["total"], 3);
+        let result = server.get_raw_entity(params).await.expect("should get");
+        let raw: serde_json::Value = serde_json::from_str(result_text(&result)).expect("json");
+        assert_eq!(raw["id"], "tx-expense");
+        assert_eq!(raw["user"], 1);
+        assert!(raw.get("changed").is_some());
+
+        let missing = Parameters(GetRawEntityParams {
+            kind: RawEntityKind::Reminder,
+            id: "tx-expense".to_owned(),
+        });
+        assert!(server.get_raw_entity(missing).await.is_err());
     }
 
     #[tokio::test]
-    async fn handler_list_transactions_with_offset() {
+    async fn search_entities_tolerates_typos() {
         let server = build_test_server().await;
-        let params = Parameters(ListTransactionsParams {
-            offset: Some(1),
-            limit: Some(1),
-            ..Default::default()
+        let params = Parameters(SearchEntitiesParams {
+            query: "Main Acount".to_owned(),
+            kinds: None,
+            limit: None,
         });
-        let result = server.list_transactions(params).await.expect("should list");
-        let page = parse_paginated(&result);
-        assert_eq!(page["items"].as_array().expect("items").len(), 1);
-        assert_eq!(page["total"], 3);
-        assert_eq!(page["offset"], 1);
-        assert_eq!(page["limit"], 1);
+        let result = server.search_entities(params).await.expect("should search");
+        let found: serde_json::Value = serde_json::from_str(result_text(&result)).expect("json");
+        assert_eq!(found["matches"][0]["id"], "acc-1");
+        assert_eq!(found["matches"][0]["kind"], "account");
     }
 
     #[tokio::test]
-    async fn handler_list_transactions_offset_past_end() {
+    async fn handler_get_instrument_not_found() {
         let server = build_test_server().await;
-        let params = Parameters(ListTransactionsParams {
-            offset: Some(100),
-            ..Default::default()
+        let params = Parameters(GetInstrumentParams { id: 999 });
+        assert_not_found(server.get_instrument(params).await, "instrument", "999");
+    }
+
+    #[tokio::test]
+    async fn handler_delete_transaction_not_found() {
+        let server = build_test_server().await;
+        let params = DeleteTransactionParams {
+            id: "tx-missing".to_owned(),
+        };
+        let result = server
+            .delete_transaction_confirmed(params, &Confirmation::default())
+            .await;
+        assert_not_found(result, "transaction", "tx-missing");
+    }
+
+    #[tokio::test]
+    async fn handler_delete_transaction_declined() {
+        let server = build_test_server().await;
+        let params = || DeleteTransactionParams {
+            id: "tx-expense".to_owned(),
+        };
+        // The prompt is answered while another write holds the turn; only an
+        // accepted deletion waits for it.
+        let other_write = server.write_lock.lock().await;
+        let accepted = server.delete_transaction_confirmed(params(), &Confirmation::default());
+        assert!(
+            tokio::time::timeout(Duration::from_millis(50), accepted)
+                .await
+                .is_err()
+        );
+        let declined = server.delete_transaction_confirmed(params(), &Confirmation::declined());
+        let result = tokio::time::timeout(Duration::from_secs(5), declined)
+            .await
+            .expect("declining does not wait for the write turn")
+            .expect("should respond");
+        drop(other_write);
+        let reply: serde_json::Value = serde_json::from_str(result_text(&result)).expect("json");
+        assert_eq!(reply["deleted"], false);
+        assert_eq!(reply["transaction"]["id"], "tx-expense");
+        let stored = server.client.transactions().await.expect("transactions");
+        assert!(stored.iter().any(|tx| tx.id.as_inner() == "tx-expense"));
+    }
+
+    #[tokio::test]
+    async fn handler_convert_amount() {
+        let server = build_test_server().await;
+        let params = Parameters(ConvertAmountParams {
+            amount: 100.0,
+            from_instrument_id: 2,
+            to_instrument_id: 1,
         });
-        let result = server.list_transactions(params).await.expect("should list");
-        let page = parse_paginated(&result);
-        assert!(page["items"].as_array().expect("items").is_empty());
-        assert_eq!(page["total"], 3);
+        let result = server.convert_amount(params).await.expect("should convert");
+        let payload: serde_json::Value =
+            serde_json::from_str(result_text(&result)).expect("should parse");
+        assert_eq!(payload["converted_amount"], 9_000.0);
+        assert_eq!(payload["rate"], 90.0);
     }
 
     #[tokio::test]
-    async fn handler_list_transactions_limit_capped() {
+    async fn handler_convert_amount_unknown_instrument_errors() {
         let server = build_test_server().await;
-        let params = Parameters(ListTransactionsParams {
-            limit: Some(9999),
-            ..Default::default()
+        let params = Parameters(ConvertAmountParams {
+            amount: 100.0,
+            from_instrument_id: 2,
+            to_instrument_id: 999,
         });
-        let result = server.list_transactions(params).await.expect("should list");
-        let page = parse_paginated(&result);
-        assert_eq!(page["limit"], MAX_TRANSACTION_LIMIT);
+        let result = server.convert_amount(params).await;
+        assert!(result.is_err());
     }
 
     #[tokio::test]
-    async fn handler_list_tags() {
+    async fn handler_list_transactions_convert_to() {
         let server = build_test_server().await;
-        let result = server.list_tags().await.expect("should list tags");
-        let tags: Vec<serde_json::Value> =
-            serde_json::from_str(result_text(&result)).expect("should parse");
-        assert_eq!(tags.len(), 1);
+        let params = Parameters(ListTransactionsParams {
+            transaction_type: Some(TransactionType::Expense),
+            convert_to: Some(2),
+            ..Default::default()
+        });
+        let result = server.list_transactions(params).await.expect("should list");
+        let page = parse_paginated(&result);
+        let converted = page["items"][0]["converted_amount"]
+            .as_f64()
+            .expect("converted amount");
+        assert!((converted - 500.0 / 90.0).abs() < 1e-9);
     }
 
     #[tokio::test]
-    async fn handler_list_merchants() {
+    async fn handler_list_transactions_group_by() {
         let server = build_test_server().await;
+        let params = Parameters(ListTransactionsParams {
+            transaction_type: Some(TransactionType::Expense),
+            group_by: Some(GroupBy::Account),
+            ..Default::default()
+        });
         let result = server
-            .list_merchants()
+            .list_transactions(params)
             .await
-            .expect("should list merchants");
-        let merchants: Vec<serde_json::Value> =
-            serde_json::from_str(result_text(&result)).expect("should parse");
-        assert_eq!(merchants.len(), 1);
+            .expect("should group");
+        let grouped = parse_paginated(&result);
+        assert_eq!(grouped["total"], 1);
+        assert_eq!(grouped["groups"][0]["label"], "Main Account");
+        assert_eq!(grouped["groups"][0]["count"], 1);
+        let outcome = grouped["groups"][0]["outcome"].as_f64().expect("outcome");
+        assert!((outcome - 500.0).abs() < f64::EPSILON);
     }
 
     #[tokio::test]
-    async fn handler_list_budgets_all() {
+    async fn handler_top_payees() {
         let server = build_test_server().await;
-        let params = Parameters(ListBudgetsParams { month: None });
+        let mut coffee = sample_transaction("tx-coffee", 250.0, 0.0);
+        coffee.payee = Some("Coffee".to_owned());
+        coffee.tag = Some(vec![TagId::new("tag-1".to_owned())]);
+        server
+            .client
+            .storage()
+            .upsert_transactions(vec![coffee])
+            .await
+            .expect("upsert coffee");
+
         let result = server
-            .list_budgets(params)
+            .top_payees(Parameters(TopPayeesParams::default()))
             .await
-            .expect("should list budgets");
-        let budgets: Vec<serde_json::Value> =
-            serde_json::from_str(result_text(&result)).expect("should parse");
-        assert_eq!(budgets.len(), 1);
+            .expect("should report");
+        let report = parse_paginated(&result);
+        assert_eq!(report["transaction_count"], 2);
+        assert_eq!(report["items"].as_array().map(Vec::len), Some(1));
+        assert_eq!(report["items"][0]["label"], "Coffee");
+        assert_eq!(report["items"][0]["categories"][0]["category"], "Groceries");
+        let total_spend = report["total_spend"].as_f64().expect("total spend");
+        assert!((total_spend - 750.0).abs() < f64::EPSILON);
     }
 
     #[tokio::test]
-    async fn handler_list_budgets_filter_month() {
+    async fn handler_compare_periods() {
         let server = build_test_server().await;
-        let params = Parameters(ListBudgetsParams {
-            month: Some("2024-06".to_owned()),
+        let params = Parameters(ComparePeriodsParams {
+            base_from: "2024-05-01".to_owned(),
+            base_to: "2024-05-31".to_owned(),
+            compare_from: "2024-06-01".to_owned(),
+            compare_to: "2024-06-30".to_owned(),
+            transaction_type: None,
+            convert_to: None,
+            include_children: None,
         });
-        let result = server.list_budgets(params).await.expect("should list");
-        let budgets: Vec<serde_json::Value> =
-            serde_json::from_str(result_text(&result)).expect("should parse");
-        assert_eq!(budgets.len(), 1);
+        let result = server
+            .compare_periods(params)
+            .await
+            .expect("should compare");
+        let report = parse_paginated(&result);
+        assert_eq!(report["categories"][0]["category"], "Uncategorized");
+        let compare_total = report["compare_total"].as_f64().expect("compare total");
+        assert!((compare_total - 500.0).abs() < f64::EPSILON);
+        assert!(report.get("delta_percent").is_none());
+        assert_eq!(
+            report["largest_increases"].as_array().map(Vec::len),
+            Some(1)
+        );
     }
 
     #[tokio::test]
-    async fn handler_list_budgets_filter_no_match() {
+    async fn handler_compare_periods_invalid_date_errors() {
         let server = build_test_server().await;
-        let params = Parameters(ListBudgetsParams {
-            month: Some("2025-01".to_owned()),
+        let params = Parameters(ComparePeriodsParams {
+            base_from: "May 2024".to_owned(),
+            base_to: "2024-05-31".to_owned(),
+            compare_from: "2024-06-01".to_owned(),
+            compare_to: "2024-06-30".to_owned(),
+            transaction_type: None,
+            convert_to: None,
+            include_children: None,
         });
-        let result = server.list_budgets(params).await.expect("should list");
-        let budgets: Vec<serde_json::Value> =
-            serde_json::from_str(result_text(&result)).expect("should parse");
-        assert!(budgets.is_empty());
+        assert!(server.compare_periods(params).await.is_err());
     }
 
     #[tokio::test]
-    async fn handler_list_reminders() {
+    async fn handler_account_balance_history() {
         let server = build_test_server().await;
+        let params = Parameters(AccountBalanceHistoryParams {
+            account_id: "acc-1".to_owned(),
+            date_from: "2024-06-14".to_owned(),
+            date_to: Some("2024-06-16".to_owned()),
+            granularity: None,
+        });
         let result = server
-            .list_reminders()
+            .account_balance_history(params)
             .await
-            .expect("should list reminders");
-        let reminders: Vec<serde_json::Value> =
-            serde_json::from_str(result_text(&result)).expect("should parse");
-        assert_eq!(reminders.len(), 1);
+            .expect("should reconstruct history");
+        let history = parse_paginated(&result);
+        assert_eq!(history["account"], "Main Account");
+        assert_eq!(history["points"].as_array().map(Vec::len), Some(3));
+        // Expense 500, income 1000 and transfer out 300 on 2024-06-15.
+        let before = history["points"][0]["balance"].as_f64().expect("balance");
+        assert!((before - 49_800.0).abs() < f64::EPSILON);
+        assert_eq!(history["lowest"]["date"], "2024-06-14");
     }
 
     #[tokio::test]
-    async fn handler_list_instruments() {
+    async fn handler_account_balance_history_unknown_account_errors() {
         let server = build_test_server().await;
-        let result = server
-            .list_instruments()
-            .await
-            .expect("should list instruments");
-        let instruments: Vec<serde_json::Value> =
-            serde_json::from_str(result_text(&result)).expect("should parse");
-        assert_eq!(instruments.len(), 2);
+        let params = Parameters(AccountBalanceHistoryParams {
+            account_id: "missing".to_owned(),
+            date_from: "2024-06-14".to_owned(),
+            date_to: Some("2024-06-16".to_owned()),
+            granularity: Some(Granularity::Month),
+        });
+        assert!(server.account_balance_history(params).await.is_err());
     }
 
     #[tokio::test]
-    async fn handler_find_account_found() {
+    async fn handler_detect_anomalies() {
         let server = build_test_server().await;
-        let params = Parameters(FindAccountParams {
-            title: "main account".to_owned(),
+        let mut expenses: Vec<Transaction> = (0..6_u32)
+            .map(|idx| {
+                let mut tx =
+                    sample_transaction(&format!("tx-coffee-{idx}"), 100.0 + f64::from(idx), 0.0);
+                tx.payee = Some("Coffee".to_owned());
+                tx
+            })
+            .collect();
+        let mut big = sample_transaction("tx-coffee-big", 9_000.0, 0.0);
+        big.payee = Some("Coffee".to_owned());
+        expenses.push(big);
+        server
+            .client
+            .storage()
+            .upsert_transactions(expenses)
+            .await
+            .expect("upsert expenses");
+
+        let params = Parameters(DetectAnomaliesParams {
+            date_from: Some("2024-06-01".to_owned()),
+            ..Default::default()
         });
-        let result = server.find_account(params).await.expect("should find");
-        assert!(result_text(&result).contains("Main Account"));
+        let result = server.detect_anomalies(params).await.expect("should scan");
+        let report = parse_paginated(&result);
+        assert_eq!(report["scanned"], 8);
+        // The seeded 500 expense is also unusual for the uncategorized baseline.
+        assert_eq!(report["flagged"], 2);
+        assert_eq!(report["items"][0]["transaction"]["id"], "tx-coffee-big");
+        assert!(
+            report["items"][0]["reasons"]
+                .as_array()
+                .is_some_and(|reasons| !reasons.is_empty())
+        );
     }
 
     #[tokio::test]
-    async fn handler_find_account_not_found() {
+    async fn handler_detect_anomalies_rejects_non_positive_threshold() {
         let server = build_test_server().await;
-        let params = Parameters(FindAccountParams {
-            title: "nonexistent".to_owned(),
+        let params = Parameters(DetectAnomaliesParams {
+            threshold: Some(0.0),
+            ..Default::default()
         });
-        let result = server.find_account(params).await.expect("should respond");
-        assert!(result_text(&result).contains("No account found"));
+        assert!(server.detect_anomalies(params).await.is_err());
     }
 
     #[tokio::test]
-    async fn handler_find_tag_found() {
+    async fn handler_uncategorized_report() {
         let server = build_test_server().await;
-        let params = Parameters(FindTagParams {
-            title: "groceries".to_owned(),
+        let mut coffee = sample_transaction("tx-coffee", 250.0, 0.0);
+        coffee.payee = Some("Coffee".to_owned());
+        let mut tagged = sample_transaction("tx-tagged", 999.0, 0.0);
+        tagged.payee = Some("Coffee".to_owned());
+        tagged.tag = Some(vec![TagId::new("tag-1".to_owned())]);
+        server
+            .client
+            .storage()
+            .upsert_transactions(vec![coffee, tagged])
+            .await
+            .expect("upsert transactions");
+
+        let params = Parameters(UncategorizedReportParams {
+            suggest: Some(false),
+            ..Default::default()
         });
-        let result = server.find_tag(params).await.expect("should find");
-        assert!(result_text(&result).contains("Groceries"));
+        let result = server
+            .uncategorized_report(params)
+            .await
+            .expect("should report");
+        let report = parse_paginated(&result);
+        // tx-expense, tx-income and tx-coffee; the transfer is excluded.
+        assert_eq!(report["total_transactions"], 3);
+        assert_eq!(report["total_payees"], 2);
+        assert_eq!(report["items"][0]["payee"], "(no payee)");
+        assert_eq!(report["items"][1]["payee"], "Coffee");
+        assert_eq!(report["items"][1]["count"], 1);
+        assert!(
+            report["items"][1]["suggested_tags"]
+                .as_array()
+                .is_some_and(Vec::is_empty)
+        );
     }
 
     #[tokio::test]
-    async fn handler_find_tag_not_found() {
+    async fn handler_get_tag() {
         let server = build_test_server().await;
-        let params = Parameters(FindTagParams {
-            title: "nonexistent".to_owned(),
+        let params = Parameters(GetTagParams {
+            tag_id: "tag-1".to_owned(),
+            convert_to: None,
         });
-        let result = server.find_tag(params).await.expect("should respond");
-        assert!(result_text(&result).contains("No tag found"));
+        let result = server.get_tag(params).await.expect("should get tag");
+        let details = parse_paginated(&result);
+        assert_eq!(details["title"], "Groceries");
+        assert_eq!(details["budgeted"], true);
+        assert!(details["children"].as_array().is_some_and(Vec::is_empty));
+        assert_eq!(details["usage"]["transaction_count"], 0);
     }
 
     #[tokio::test]
-    async fn handler_create_tag_existing_is_idempotent() {
+    async fn handler_get_account() {
         let server = build_test_server().await;
-        let params = Parameters(sample_create_tag_params("gRoCeRiEs"));
+        let params = Parameters(GetAccountParams {
+            account_id: "acc-1".to_owned(),
+        });
         let result = server
-            .create_tag(params)
+            .get_account(params)
             .await
-            .expect("should return existing");
-        let payload: serde_json::Value =
-            serde_json::from_str(result_text(&result)).expect("should parse");
-        let id = payload
-            .get("id")
-            .and_then(serde_json::Value::as_str)
-            .expect("response should include id");
-        assert_eq!(id, "tag-1");
-
-        let tags = server.client.tags().await.expect("should load tags");
-        assert_eq!(tags.len(), 1);
+            .expect("should get account");
+        let details = parse_paginated(&result);
+        assert_eq!(details["title"], "Main Account");
+        assert_eq!(details["activity"]["last_transaction"], "2024-06-15");
+        assert_eq!(details["activity"]["pending_reminders"]["count"], 1);
+        assert_eq!(details["activity"]["pending_reminders"]["outcome"], 5000.0);
+
+        let missing = Parameters(GetAccountParams {
+            account_id: "missing".to_owned(),
+        });
+        assert_not_found(server.get_account(missing).await, "account", "missing");
     }
 
     #[tokio::test]
-    async fn handler_create_category_alias_existing_is_idempotent() {
+    async fn handler_verify_balances() {
         let server = build_test_server().await;
-        let params = Parameters(sample_create_tag_params("GROCERIES"));
         let result = server
-            .create_category(params)
+            .verify_balances(Parameters(VerifyBalancesParams::default()))
             .await
-            .expect("should return existing");
-        let payload: serde_json::Value =
-            serde_json::from_str(result_text(&result)).expect("should parse");
-        let title = payload
-            .get("title")
-            .and_then(serde_json::Value::as_str)
-            .expect("response should include title");
-        assert_eq!(title, "Groceries");
-
-        let tags = server.client.tags().await.expect("should load tags");
-        assert_eq!(tags.len(), 1);
+            .expect("should verify");
+        let report = parse_paginated(&result);
+        assert_eq!(report["checked"], 2);
+        assert_eq!(report["matching"], 0);
+        assert_eq!(report["discrepancies"][0]["account_id"], "acc-1");
+        assert_eq!(report["discrepancies"][0]["computed_balance"], 200.0);
+        assert_eq!(report["discrepancies"][0]["difference"], 49_800.0);
+
+        let params = Parameters(VerifyBalancesParams {
+            account_id: Some("acc-2".to_owned()),
+            tolerance: Some(1_000.0),
+        });
+        let result = server.verify_balances(params).await.expect("should verify");
+        let report = parse_paginated(&result);
+        assert_eq!(report["checked"], 1);
+        assert_eq!(report["matching"], 1);
+
+        let missing = Parameters(VerifyBalancesParams {
+            account_id: Some("missing".to_owned()),
+            tolerance: None,
+        });
+        assert_not_found(server.verify_balances(missing).await, "account", "missing");
     }
 
     #[tokio::test]
-    async fn handler_create_tag_blank_title_errors() {
+    async fn handler_get_tag_not_found() {
         let server = build_test_server().await;
-        let params = Parameters(sample_create_tag_params("   "));
-        let result = server.create_tag(params).await;
-        assert!(result.is_err());
+        let params = Parameters(GetTagParams {
+            tag_id: "missing".to_owned(),
+            convert_to: None,
+        });
+        assert_not_found(server.get_tag(params).await, "tag", "missing");
     }
 
     #[tokio::test]
-    async fn handler_create_tag_missing_parent_errors() {
+    async fn handler_tag_tree() {
         let server = build_test_server().await;
-        let mut create_params = sample_create_tag_params("New category");
-        create_params.parent_tag_id = Some("missing-parent".to_owned());
-        let params = Parameters(create_params);
-        let result = server.create_tag(params).await;
-        assert!(result.is_err());
+        let mut child_params = sample_create_tag_params("Vegetables");
+        child_params.parent_tag_id = Some("tag-1".to_owned());
+        let child = build_tag(child_params, 1, "Vegetables".to_owned());
+        let mut archived = build_tag(sample_create_tag_params("Old"), 1, "Old".to_owned());
+        archived.archive = Some(true);
+        server
+            .client
+            .storage()
+            .upsert_tags(vec![child, archived])
+            .await
+            .expect("upsert tags");
+
+        let result = server
+            .tag_tree(Parameters(TagTreeParams::default()))
+            .await
+            .expect("should build tree");
+        let tree = parse_paginated(&result);
+        assert_eq!(tree.as_array().map(Vec::len), Some(1));
+        assert_eq!(tree[0]["title"], "Groceries");
+        assert_eq!(tree[0]["children"][0]["title"], "Vegetables");
+
+        let result = server
+            .tag_tree(Parameters(TagTreeParams {
+                include_archived: Some(true),
+            }))
+            .await
+            .expect("should build tree");
+        assert_eq!(parse_paginated(&result).as_array().map(Vec::len), Some(2));
     }
 
     #[tokio::test]
-    async fn handler_get_instrument_found() {
+    async fn handler_list_transactions_include_children() {
         let server = build_test_server().await;
-        let params = Parameters(GetInstrumentParams { id: 1 });
-        let result = server.get_instrument(params).await.expect("should get");
-        assert!(result_text(&result).contains("Russian Ruble"));
+        let mut child_params = sample_create_tag_params("Vegetables");
+        child_params.parent_tag_id = Some("tag-1".to_owned());
+        let child = build_tag(child_params, 1, "Vegetables".to_owned());
+        let mut veg = sample_transaction("tx-veg", 120.0, 0.0);
+        veg.tag = Some(vec![child.id.clone()]);
+        server
+            .client
+            .storage()
+            .upsert_tags(vec![child])
+            .await
+            .expect("upsert tag");
+        server
+            .client
+            .storage()
+            .upsert_transactions(vec![veg])
+            .await
+            .expect("upsert transaction");
+
+        let strict = Parameters(ListTransactionsParams {
+            tag_id: Some("tag-1".to_owned()),
+            ..Default::default()
+        });
+        let result = server.list_transactions(strict).await.expect("should list");
+        assert_eq!(parse_paginated(&result)["total"], 0);
+
+        let rolled = Parameters(ListTransactionsParams {
+            tag_id: Some("tag-1".to_owned()),
+            include_children: Some(true),
+            group_by: Some(GroupBy::Tag),
+            ..Default::default()
+        });
+        let result = server
+            .list_transactions(rolled)
+            .await
+            .expect("should group");
+        let grouped = parse_paginated(&result);
+        assert_eq!(grouped["total"], 1);
+        assert_eq!(grouped["groups"][0]["label"], "Groceries");
     }
 
     #[tokio::test]
-    async fn handler_get_instrument_not_found() {
+    async fn handler_list_accounts_convert_to_unknown_errors() {
         let server = build_test_server().await;
-        let params = Parameters(GetInstrumentParams { id: 999 });
-        let result = server.get_instrument(params).await.expect("should respond");
-        assert!(result_text(&result).contains("No instrument found"));
+        let params = Parameters(ListAccountsParams {
+            convert_to: Some(999),
+            ..Default::default()
+        });
+        let result = server.list_accounts(params).await;
+        assert!(result.is_err());
     }
 
     #[tokio::test]
@@ -2486,6 +13065,18 @@ mod tests {
         assert!(info.instructions.is_some());
     }
 
+    #[tokio::test]
+    async fn session_instructions_list_accounts() {
+        let server = build_test_server()
+            .await
+            .with_session_instructions("Custom instructions.")
+            .await;
+        let instructions = server.get_info().instructions.expect("instructions set");
+        assert!(instructions.starts_with("Custom instructions.\n\nSession context:"));
+        assert!(instructions.contains("Main Account (₽, id acc-1)"));
+        assert!(instructions.contains("- Current month: "));
+    }
+
     #[tokio::test]
     async fn handler_prepare_bulk_too_many_operations() {
         let server = build_test_server().await;
@@ -2503,6 +13094,12 @@ mod tests {
                     tag_ids: None,
                     payee: None,
                     comment: None,
+                    merchant_id: None,
+                    latitude: None,
+                    longitude: None,
+                    mcc: None,
+                    planned: None,
+                    allow_out_of_range: None,
                 })
             })
             .collect();
@@ -2530,26 +13127,336 @@ mod tests {
     #[tokio::test]
     async fn handler_execute_bulk_not_found() {
         let server = build_test_server().await;
-        let params = Parameters(ExecuteBulkParams {
-            preparation_id: "nonexistent".to_owned(),
-        });
-        let result = server.execute_bulk_operations(params).await;
-        assert!(result.is_err());
+        let messages = with_handshake([tool_call(
+            2,
+            "execute_bulk_operations",
+            serde_json::json!({ "preparation_id": "nonexistent" }),
+        )]);
+        let responses = exchange(server, &messages, &[2]).await;
+        assert!(responses[&2]["error"].is_object(), "{}", responses[&2]);
+    }
+
+    #[tokio::test]
+    async fn handler_execute_bulk_cancelled_keeps_preparation() {
+        let server = build_test_server().await;
+        let operations = vec![BulkOperation::Create(sample_create_params(
+            TransactionType::Expense,
+        ))];
+        let prepared = server
+            .prepare_bulk_operations(Parameters(BulkOperationsParams { operations }))
+            .await
+            .expect("should prepare");
+        let preview: serde_json::Value =
+            serde_json::from_str(result_text(&prepared)).expect("valid json");
+        let preparation_id = preview["preparation_id"].as_str().expect("preparation ID");
+        let result = server
+            .execute_preparation(
+                preparation_id,
+                &Progress::cancelled(),
+                &Confirmation::default(),
+            )
+            .await
+            .expect("cancellation is not an error");
+        let response: serde_json::Value =
+            serde_json::from_str(result_text(&result)).expect("valid json");
+        assert_eq!(response["cancelled"]["preparation_kept"], true);
+        assert_eq!(response["cancelled"]["done"], 0);
+        assert!(
+            server
+                .preparations
+                .lock()
+                .expect("lock")
+                .contains_key(preparation_id)
+        );
+    }
+
+    #[tokio::test]
+    async fn handler_execute_bulk_declined_keeps_preparation() {
+        let server = build_test_server().await;
+        let operations = vec![BulkOperation::Create(sample_create_params(
+            TransactionType::Expense,
+        ))];
+        let prepared = server
+            .prepare_bulk_operations(Parameters(BulkOperationsParams { operations }))
+            .await
+            .expect("should prepare");
+        let preview: serde_json::Value =
+            serde_json::from_str(result_text(&prepared)).expect("valid json");
+        let preparation_id = preview["preparation_id"].as_str().expect("preparation ID");
+        let result = server
+            .execute_preparation(
+                preparation_id,
+                &Progress::default(),
+                &Confirmation::declined(),
+            )
+            .await
+            .expect("declining is not an error");
+        let response: serde_json::Value =
+            serde_json::from_str(result_text(&result)).expect("valid json");
+        assert_eq!(response["cancelled"]["reason"], "declined");
+        assert_eq!(response["cancelled"]["preparation_id"], preparation_id);
+        assert_eq!(response["cancelled"]["preparation_kept"], true);
+        assert_eq!(response["created"], 0, "nothing was sent");
+        assert!(
+            server
+                .preparations
+                .lock()
+                .expect("lock")
+                .contains_key(preparation_id)
+        );
+    }
+
+    /// Stages creating 150 transactions, two batches' worth, and returns
+    /// the preparation ID.
+    fn stage_two_batches(server: &ZenMoneyMcpServer<InMemoryStorage>) -> String {
+        let to_push: Vec<Transaction> = (0..150)
+            .map(|index| sample_transaction(&format!("tx-bulk-{index}"), 10.0, 0.0))
+            .collect();
+        server
+            .store_preparation(PreparedBulk {
+                to_push,
+                to_delete: Vec::new(),
+                created_count: 150,
+                updated_count: 0,
+                budgets: Vec::new(),
+            })
+            .expect("should stage")
+    }
+
+    /// Asserts that `response` reports the first batch of
+    /// [`stage_two_batches`] as sent and that the other 50 changes are
+    /// staged under the returned remaining preparation ID.
+    fn assert_first_batch_sent(
+        server: &ZenMoneyMcpServer<InMemoryStorage>,
+        preparation_id: &str,
+        response: &serde_json::Value,
+    ) {
+        assert_eq!(response["created"], 100);
+        assert_eq!(response["transactions"].as_array().map(Vec::len), Some(100));
+        assert_eq!(response["cancelled"]["done"], 100);
+        assert_eq!(response["cancelled"]["total"], 150);
+        assert_eq!(response["cancelled"]["preparation_kept"], false);
+        let remaining = response["cancelled"]["remaining_preparation_id"]
+            .as_str()
+            .expect("remaining preparation ID");
+        let preparations = server.preparations.lock().expect("lock");
+        assert!(!preparations.contains_key(preparation_id));
+        let rest = preparations.get(remaining).expect("remaining preparation");
+        assert_eq!((rest.to_push.len(), rest.created_count), (50, 50));
+        assert_eq!(rest.to_push[0].id.as_inner(), "tx-bulk-100");
+    }
+
+    #[tokio::test]
+    async fn handler_bulk_cancelled_after_first_batch_reports_partial() {
+        let progress = Progress::default();
+        let cancel = progress.clone();
+        let zenmoney = FakeZenMoney::start_with(
+            vec![(200, r#"{"serverTimestamp":1900000000}"#.to_owned())],
+            move |count| {
+                if count == 1 {
+                    cancel.cancel();
+                }
+            },
+        );
+        let server = build_test_server_at(Some(&zenmoney.url)).await;
+        let preparation_id = stage_two_batches(&server);
+        let result = server
+            .execute_preparation(&preparation_id, &progress, &Confirmation::default())
+            .await
+            .expect("cancellation is not an error");
+        let response: serde_json::Value =
+            serde_json::from_str(result_text(&result)).expect("valid json");
+        assert_eq!(response["cancelled"]["reason"], "cancelled");
+        assert_eq!(zenmoney.requests().len(), 1);
+        assert_first_batch_sent(&server, &preparation_id, &response);
+    }
+
+    #[tokio::test]
+    async fn handler_bulk_failure_after_first_batch_reports_partial() {
+        let zenmoney = FakeZenMoney::start(vec![
+            (200, r#"{"serverTimestamp":1900000000}"#.to_owned()),
+            (400, r#"{"error":"rejected"}"#.to_owned()),
+        ]);
+        let server = build_test_server_at(Some(&zenmoney.url)).await;
+        let preparation_id = stage_two_batches(&server);
+        let result = server
+            .execute_preparation(
+                &preparation_id,
+                &Progress::default(),
+                &Confirmation::default(),
+            )
+            .await
+            .expect("a partial commit is reported, not an error");
+        let response: serde_json::Value =
+            serde_json::from_str(result_text(&result)).expect("valid json");
+        assert_eq!(response["cancelled"]["reason"], "failed");
+        assert!(response["cancelled"]["error"].is_string(), "{response}");
+        assert_eq!(zenmoney.requests().len(), 2);
+        assert_first_batch_sent(&server, &preparation_id, &response);
     }
 }
 
-#[tool_handler]
 impl<S: Storage + 'static> ServerHandler for ZenMoneyMcpServer<S> {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
-            instructions: Some(
-                "ZenMoney personal finance MCP server. \
-                 Use sync/full_sync to fetch data, then query accounts, \
-                 transactions, tags, budgets, and more."
-                    .into(),
-            ),
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            instructions: Some(self.instructions.clone()),
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_logging()
+                .enable_completions()
+                .enable_resources()
+                .enable_resources_subscribe()
+                .enable_resources_list_changed()
+                .build(),
             ..Default::default()
         }
     }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let name = request.name.as_ref();
+        let needs_api = (WRITE_TOOLS.contains(&name) && !LOCAL_WRITE_TOOLS.contains(&name))
+            || demo::ONLINE_TOOLS.contains(&name);
+        if let Some(mode) = self.offline_mode.filter(|_| needs_api) {
+            let mut message =
+                format!("{name} is not available in {mode} mode, which never contacts ZenMoney");
+            if mode == "replay" {
+                message = format!("{message}; {}", replay::READ_ONLY_NOTE);
+            }
+            return Err(McpError::invalid_request(message, None));
+        }
+        self.ensure_fresh(&request).await;
+        if WRITE_TOOLS.contains(&name) {
+            self.sync_after_restore(name).await?;
+        }
+        let write_turn = if WRITE_TOOLS.contains(&name) && !CONFIRMED_WRITE_TOOLS.contains(&name) {
+            Some(self.write_turn().await?)
+        } else {
+            None
+        };
+        let tool = request.name.clone();
+        let compact = compact::requested(request.arguments.as_ref(), self.compact_output);
+        let started = Instant::now();
+        let result = self
+            .tool_router
+            .call(ToolCallContext::new(self, request, context))
+            .await;
+        drop(write_turn);
+        let result = if WRITE_TOOLS.contains(&&*tool) {
+            result.map(with_write_order_note)
+        } else {
+            result
+        };
+        let failed = !result
+            .as_ref()
+            .is_ok_and(|output| output.is_error != Some(true));
+        self.usage.record(&tool, started.elapsed(), failed);
+        if compact {
+            return result.map(compact::compact_result);
+        }
+        result
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        let mut tools = self.tool_router.list_all();
+        if self.max_data_age.is_some() {
+            tools
+                .iter_mut()
+                .filter(|tool| READ_TOOLS.contains(&tool.name.as_ref()))
+                .for_each(add_skip_sync_param);
+        }
+        tools.iter_mut().for_each(compact::add_compact_param);
+        Ok(ListToolsResult::with_all_items(tools))
+    }
+
+    async fn complete(
+        &self,
+        request: CompleteRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<CompleteResult, McpError> {
+        let completion = match CompletionSource::for_argument(&request.argument.name) {
+            Some(source) => complete_ids(
+                &self.completion_candidates(source).await?,
+                &request.argument.value,
+            ),
+            None => complete_ids(&[], ""),
+        };
+        Ok(CompleteResult { completion })
+    }
+
+    async fn list_resource_templates(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourceTemplatesResult, McpError> {
+        Ok(ListResourceTemplatesResult::with_all_items(
+            resources::templates(),
+        ))
+    }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        Ok(ListResourcesResult::with_all_items(resources::resources()))
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        let text = self.read_entity(&request.uri).await?;
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::text(text, request.uri)],
+        })
+    }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        if EntityUri::parse(&request.uri).is_none() {
+            return Err(McpError::resource_not_found(
+                format!("resource '{}' not found", request.uri),
+                None,
+            ));
+        }
+        self.resource_watch.subscribe(context.peer, request.uri);
+        Ok(())
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        self.resource_watch.unsubscribe(&request.uri);
+        Ok(())
+    }
+
+    async fn on_initialized(&self, context: NotificationContext<RoleServer>) {
+        self.resource_watch.set_peer(context.peer);
+        // Takes the baseline that later syncs and writes are compared with.
+        self.refresh_resources().await;
+    }
+
+    async fn set_level(
+        &self,
+        request: SetLevelRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        self.client_log.set_level(context.peer, request.level);
+        tracing::debug!(level = ?request.level, "client logging level set");
+        Ok(())
+    }
 }
@@ -0,0 +1,149 @@
+//! Server settings read from the environment.
+//!
+//! The binary serves synced, demo and replayed data. All three read the
+//! same environment variables through [`Settings`] and apply them with
+//! [`Settings::apply`], so a setting such as `ZENMONEY_DATE_GUARD` behaves
+//! the same whichever data is served. `zenmoney-mcp doctor` describes the
+//! same settings.
+
+use core::time::Duration;
+use std::path::Path;
+
+use zenmoney_rs::storage::Storage;
+
+use crate::compact::COMPACT_OUTPUT_ENV;
+use crate::confirm::{self, CONFIRM_DESTRUCTIVE_ENV};
+use crate::date_guard::{DATE_GUARD_ENV, DateWindow, MAX_FUTURE_DAYS_ENV, MAX_PAST_DAYS_ENV};
+use crate::default_account::DEFAULT_ACCOUNT_ENV;
+use crate::freshness::{self, MAX_DATA_AGE_ENV};
+use crate::instructions::{self, INSTRUCTIONS_ENV};
+use crate::schedule::{SCHEDULE_ENV, ScheduledJob, load_schedule};
+use crate::server::{BASE_CURRENCY_ENV, INCLUDE_IDS_ENV, ZenMoneyMcpServer};
+use crate::storage::LocalState;
+use crate::throttle::{self, MIN_REQUEST_INTERVAL_ENV};
+
+/// Settings applied to every served [`ZenMoneyMcpServer`].
+#[derive(Debug, Clone)]
+pub struct Settings {
+    /// Whether deletions and bulk commits ask the user first.
+    confirm_destructive: bool,
+    /// Whether responses carry raw IDs next to resolved names.
+    include_ids: bool,
+    /// Whether tool results are compact unless a call asks otherwise.
+    compact_output: bool,
+    /// Age after which read tools sync first.
+    max_data_age: Option<Duration>,
+    /// Spacing of ZenMoney API requests.
+    min_request_interval: Duration,
+    /// Transaction dates accepted without confirmation.
+    date_window: DateWindow,
+    /// Currency that totals are converted to.
+    base_currency: Option<String>,
+    /// Account used when neither the call nor the saved default names one.
+    default_account: Option<String>,
+    /// Instructions sent to the client, before the session summary.
+    instructions: String,
+}
+
+impl Settings {
+    /// Reads the settings from the environment.
+    ///
+    /// # Errors
+    ///
+    /// Returns a message if `ZENMONEY_MAX_DATA_AGE`,
+    /// `ZENMONEY_MIN_REQUEST_INTERVAL_MS` or a date guard variable has an
+    /// invalid value.
+    #[inline]
+    pub fn from_env() -> Result<Self, String> {
+        Ok(Self {
+            confirm_destructive: env_flag(CONFIRM_DESTRUCTIVE_ENV).unwrap_or(false),
+            include_ids: env_flag(INCLUDE_IDS_ENV).unwrap_or(true),
+            compact_output: env_flag(COMPACT_OUTPUT_ENV).unwrap_or(false),
+            max_data_age: freshness::parse_max_data_age(env(MAX_DATA_AGE_ENV).as_deref())?,
+            min_request_interval: throttle::parse_min_request_interval(
+                env(MIN_REQUEST_INTERVAL_ENV).as_deref(),
+            )?,
+            date_window: date_window()?,
+            base_currency: env(BASE_CURRENCY_ENV),
+            default_account: env(DEFAULT_ACCOUNT_ENV),
+            instructions: instructions::base_instructions(env(INSTRUCTIONS_ENV)),
+        })
+    }
+
+    /// Appends `notice` to the instructions sent to the client.
+    #[inline]
+    #[must_use]
+    pub fn with_notice(mut self, notice: &str) -> Self {
+        self.instructions = format!("{} {notice}", self.instructions);
+        self
+    }
+
+    /// Configures `server` with these settings and the stores of `local`.
+    ///
+    /// Call it once local data is in place: the instructions summarize it.
+    #[inline]
+    pub async fn apply<S: Storage + 'static>(
+        self,
+        server: ZenMoneyMcpServer<S>,
+        local: LocalState,
+    ) -> ZenMoneyMcpServer<S> {
+        let server = match local.data_dir {
+            Some(dir) => server.with_data_dir(dir),
+            None => server,
+        };
+        server
+            .with_template_store(local.templates)
+            .with_envelope_store(local.envelopes)
+            .with_pending_queue(local.pending)
+            .with_conflict_store(local.conflicts)
+            .with_review_store(local.reviews)
+            .with_planned_store(local.planned)
+            .with_default_account_store(local.default_account.with_fallback(self.default_account))
+            .with_confirm_destructive(self.confirm_destructive)
+            .with_include_ids(self.include_ids)
+            .with_compact_output(self.compact_output)
+            .with_max_data_age(self.max_data_age)
+            .with_date_window(self.date_window)
+            .with_base_currency(self.base_currency)
+            .with_min_request_interval(self.min_request_interval)
+            .with_session_instructions(&self.instructions)
+            .await
+    }
+}
+
+/// Reads the environment variable `name`, if set to valid Unicode.
+fn env(name: &str) -> Option<String> {
+    std::env::var(name).ok()
+}
+
+/// Reads an on/off environment variable; `None` when it is unset.
+fn env_flag(name: &str) -> Option<bool> {
+    env(name).map(|value| confirm::is_enabled(&value))
+}
+
+/// Reads the allowed transaction dates from `ZENMONEY_DATE_GUARD`,
+/// `ZENMONEY_MAX_FUTURE_DAYS` and `ZENMONEY_MAX_PAST_DAYS`.
+///
+/// # Errors
+///
+/// Returns a message if one of the variables has an invalid value.
+pub(crate) fn date_window() -> Result<DateWindow, String> {
+    DateWindow::from_settings(
+        env(DATE_GUARD_ENV).as_deref(),
+        env(MAX_FUTURE_DAYS_ENV).as_deref(),
+        env(MAX_PAST_DAYS_ENV).as_deref(),
+    )
+}
+
+/// Loads the report jobs in the file named by `ZENMONEY_SCHEDULE`, if set.
+///
+/// # Errors
+///
+/// Returns a message if the file cannot be read or a job is invalid.
+#[inline]
+pub fn scheduled_jobs() -> Result<Option<Vec<ScheduledJob>>, String> {
+    std::env::var_os(SCHEDULE_ENV)
+        .filter(|path| !path.is_empty())
+        .map(|path| load_schedule(Path::new(&path)))
+        .transpose()
+}
@@ -0,0 +1,121 @@
+//! Graceful shutdown on SIGINT and SIGTERM.
+//!
+//! Container orchestrators stop the server with a signal, often in the
+//! middle of a session. [`wait_for_signal`] resolves on the first one, and
+//! [`ShutdownHandle::drain`] then refuses new write tool calls and waits for
+//! the write and sync in flight to finish, so the transport is only closed
+//! once the local state matches what was pushed. Templates, envelopes and
+//! synced entities are saved as they change, so finishing in-flight work is
+//! all the flushing they need.
+
+extern crate alloc;
+
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
+use std::io;
+
+use crate::throttle::SyncCoalescer;
+
+/// Lets the binary drain a running server before closing its transport.
+#[derive(Debug, Clone)]
+pub struct ShutdownHandle {
+    /// Set once draining starts; write calls check it after taking their turn.
+    closing: Arc<AtomicBool>,
+    /// The server's write lock, held by every write tool call.
+    write_lock: Arc<tokio::sync::Mutex<()>>,
+    /// The server's sync gate, held while a sync runs.
+    sync_gate: Arc<SyncCoalescer>,
+}
+
+impl ShutdownHandle {
+    /// Creates a handle over the shared state of one server.
+    pub(crate) const fn new(
+        closing: Arc<AtomicBool>,
+        write_lock: Arc<tokio::sync::Mutex<()>>,
+        sync_gate: Arc<SyncCoalescer>,
+    ) -> Self {
+        Self {
+            closing,
+            write_lock,
+            sync_gate,
+        }
+    }
+
+    /// Refuses further writes and waits for the write and sync in flight.
+    ///
+    /// Writes already queued behind the running one are rejected when
+    /// their turn comes rather than applied.
+    #[inline]
+    pub async fn drain(&self) {
+        self.closing.store(true, Ordering::Release);
+        drop(self.write_lock.lock().await);
+        self.sync_gate.wait_idle().await;
+    }
+}
+
+/// Returns whether draining has started.
+pub(crate) fn is_closing(closing: &AtomicBool) -> bool {
+    closing.load(Ordering::Acquire)
+}
+
+/// Waits for SIGINT (Ctrl+C) or, on Unix, SIGTERM.
+///
+/// # Errors
+///
+/// Returns an error if the signal handlers cannot be installed.
+#[inline]
+pub async fn wait_for_signal() -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        let mut terminate =
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
+        tokio::select! {
+            interrupted = tokio::signal::ctrl_c() => interrupted,
+            _terminated = terminate.recv() => Ok(()),
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect for readability"
+)]
+mod tests {
+    extern crate alloc;
+
+    use alloc::sync::Arc;
+    use core::sync::atomic::{AtomicBool, Ordering};
+    use core::time::Duration;
+
+    use super::{ShutdownHandle, is_closing};
+    use crate::throttle::SyncCoalescer;
+
+    #[tokio::test]
+    async fn drain_waits_for_the_write_in_flight() {
+        let closing = Arc::new(AtomicBool::new(false));
+        let write_lock = Arc::new(tokio::sync::Mutex::new(()));
+        let handle = ShutdownHandle::new(
+            Arc::clone(&closing),
+            Arc::clone(&write_lock),
+            Arc::new(SyncCoalescer::default()),
+        );
+        let finished = Arc::new(AtomicBool::new(false));
+        let write_turn = Arc::clone(&write_lock).lock_owned().await;
+        let done = Arc::clone(&finished);
+        let writer = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            done.store(true, Ordering::Release);
+            drop(write_turn);
+        });
+        handle.drain().await;
+        assert!(finished.load(Ordering::Acquire));
+        assert!(is_closing(&closing));
+        writer.await.expect("writer task");
+    }
+}
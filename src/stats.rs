@@ -0,0 +1,150 @@
+//! In-process statistics of tool calls for the `usage_stats` tool.
+//!
+//! Every call through the handler records its latency and whether it
+//! failed, so users can see what their agent actually does and spot
+//! pathological loops such as hundreds of repeated report calls. Only the
+//! most recent latencies of each tool are kept for the percentiles.
+
+extern crate alloc;
+
+use alloc::collections::{BTreeMap, VecDeque};
+use core::time::Duration;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+
+use crate::response::{ToolUsage, UsageStatsResponse};
+
+/// Latency samples kept per tool.
+const MAX_SAMPLES: usize = 1000;
+
+/// Counters of one tool.
+#[derive(Debug, Default)]
+struct ToolStats {
+    /// Number of calls.
+    calls: u64,
+    /// Number of calls that returned an error.
+    errors: u64,
+    /// Latencies of the most recent calls, in milliseconds.
+    latencies_ms: VecDeque<u64>,
+}
+
+/// Per-tool call counters since the server started.
+#[derive(Debug)]
+pub(crate) struct UsageStats {
+    /// When counting started.
+    since: DateTime<Utc>,
+    /// Counters by tool name.
+    tools: Mutex<BTreeMap<String, ToolStats>>,
+}
+
+impl Default for UsageStats {
+    fn default() -> Self {
+        Self {
+            since: Utc::now(),
+            tools: Mutex::new(BTreeMap::new()),
+        }
+    }
+}
+
+impl UsageStats {
+    /// Records one call of `tool`.
+    pub(crate) fn record(&self, tool: &str, elapsed: Duration, failed: bool) {
+        let Ok(mut tools) = self.tools.lock() else {
+            return;
+        };
+        let stats = tools.entry(tool.to_owned()).or_default();
+        stats.calls = stats.calls.saturating_add(1);
+        if failed {
+            stats.errors = stats.errors.saturating_add(1);
+        }
+        if stats.latencies_ms.len() == MAX_SAMPLES {
+            let _oldest = stats.latencies_ms.pop_front();
+        }
+        stats
+            .latencies_ms
+            .push_back(u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX));
+    }
+
+    /// Summarizes the counters, most called tools first.
+    pub(crate) fn report(&self) -> UsageStatsResponse {
+        let mut usage: Vec<ToolUsage> = self
+            .tools
+            .lock()
+            .map(|tools| {
+                tools
+                    .iter()
+                    .map(|(tool, stats)| summarize(tool, stats))
+                    .collect()
+            })
+            .unwrap_or_default();
+        usage.sort_by(|left, right| right.calls.cmp(&left.calls));
+        UsageStatsResponse {
+            since: self.since.to_rfc3339(),
+            total_calls: usage.iter().map(|tool| tool.calls).sum(),
+            total_errors: usage.iter().map(|tool| tool.errors).sum(),
+            tools: usage,
+        }
+    }
+}
+
+/// Builds the report row of one tool.
+fn summarize(tool: &str, stats: &ToolStats) -> ToolUsage {
+    let mut sorted: Vec<u64> = stats.latencies_ms.iter().copied().collect();
+    sorted.sort_unstable();
+    ToolUsage {
+        tool: tool.to_owned(),
+        calls: stats.calls,
+        errors: stats.errors,
+        p50_ms: percentile(&sorted, 50),
+        p90_ms: percentile(&sorted, 90),
+        p99_ms: percentile(&sorted, 99),
+        max_ms: sorted.last().copied().unwrap_or_default(),
+    }
+}
+
+/// Returns the nearest-rank percentile of ascending `sorted` values.
+fn percentile(sorted: &[u64], pct: usize) -> u64 {
+    let rank = sorted.len().saturating_mul(pct).div_ceil(100);
+    sorted
+        .get(rank.saturating_sub(1))
+        .copied()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::indexing_slicing,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses indexing for readability"
+)]
+mod tests {
+    use core::time::Duration;
+
+    use super::{UsageStats, percentile};
+
+    #[test]
+    fn percentiles_use_nearest_rank() {
+        let values: Vec<u64> = (1..=10).collect();
+        assert_eq!(percentile(&values, 50), 5);
+        assert_eq!(percentile(&values, 90), 9);
+        assert_eq!(percentile(&values, 99), 10);
+        assert_eq!(percentile(&[], 50), 0);
+    }
+
+    #[test]
+    fn report_orders_tools_by_calls() {
+        let stats = UsageStats::default();
+        stats.record("list_accounts", Duration::from_millis(5), false);
+        for millis in [10, 20, 30] {
+            stats.record("digest", Duration::from_millis(millis), millis == 30);
+        }
+        let report = stats.report();
+        assert_eq!(report.total_calls, 4);
+        assert_eq!(report.total_errors, 1);
+        assert_eq!(report.tools[0].tool, "digest");
+        assert_eq!(report.tools[0].p50_ms, 20);
+        assert_eq!(report.tools[0].max_ms, 30);
+        assert_eq!(report.tools[1].calls, 1);
+    }
+}
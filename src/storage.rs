@@ -0,0 +1,207 @@
+//! Selection of the local storage backend at startup.
+//!
+//! `ZENMONEY_STORAGE` picks where synced data is kept: `file` (the default)
+//! persists it under the XDG data directory, while `memory` keeps
+//! everything, including templates and envelopes, in memory for ephemeral
+//! analysis sessions that must not write to disk.
+//!
+//! [`LocalState`] opens the local-only stores kept next to the synced data,
+//! and [`purge_dir`] wipes the file storage directory for `--reset` and the
+//! `purge_local_data` tool.
+
+use std::io::{self, Read as _};
+use std::path::{Path, PathBuf};
+
+use crate::conflicts::ConflictStore;
+use crate::default_account::DefaultAccountStore;
+use crate::envelopes::EnvelopeStore;
+use crate::pending::PendingQueue;
+use crate::planned::PlannedStore;
+use crate::review::ReviewStore;
+use crate::templates::TemplateStore;
+
+/// Environment variable selecting the storage backend.
+pub const STORAGE_ENV: &str = "ZENMONEY_STORAGE";
+
+/// Storage backend for synced data and local state.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StorageKind {
+    /// JSON files in the XDG data directory, kept between runs.
+    #[default]
+    File,
+    /// In-memory only; discarded when the server exits.
+    Memory,
+}
+
+impl StorageKind {
+    /// Parses a setting value; an unset or blank value selects file storage.
+    ///
+    /// # Errors
+    ///
+    /// Returns a message naming the accepted values if `value` is unknown.
+    #[inline]
+    pub fn from_setting(value: Option<&str>) -> Result<Self, String> {
+        let setting = value
+            .map(|raw| raw.trim().to_lowercase())
+            .unwrap_or_default();
+        match setting.as_str() {
+            "" | "file" => Ok(Self::File),
+            "memory" | "in-memory" => Ok(Self::Memory),
+            other => Err(format!(
+                "unsupported {STORAGE_ENV} value '{other}'; expected 'file' or 'memory'"
+            )),
+        }
+    }
+}
+
+/// Local-only state kept next to the synced data.
+///
+/// The default keeps everything in memory.
+#[derive(Debug, Default)]
+pub struct LocalState {
+    /// Saved transaction templates.
+    pub(crate) templates: TemplateStore,
+    /// Budgeting envelopes.
+    pub(crate) envelopes: EnvelopeStore,
+    /// Writes queued while ZenMoney was unreachable.
+    pub(crate) pending: PendingQueue,
+    /// Queued edits held back because ZenMoney changed the same transaction.
+    pub(crate) conflicts: ConflictStore,
+    /// Watermark of the last inbox review.
+    pub(crate) reviews: ReviewStore,
+    /// Planned transactions.
+    pub(crate) planned: PlannedStore,
+    /// Account used when a create omits `account_id`.
+    pub(crate) default_account: DefaultAccountStore,
+    /// Directory of the file storage; `None` keeps everything in memory.
+    pub(crate) data_dir: Option<PathBuf>,
+}
+
+impl LocalState {
+    /// Opens the state files inside the file storage directory `dir`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a state file exists but cannot be read or parsed.
+    #[inline]
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        Ok(Self {
+            templates: TemplateStore::open(dir)?,
+            envelopes: EnvelopeStore::open(dir)?,
+            pending: PendingQueue::open(dir)?,
+            conflicts: ConflictStore::open(dir)?,
+            reviews: ReviewStore::open(dir)?,
+            planned: PlannedStore::open(dir)?,
+            default_account: DefaultAccountStore::open(dir)?,
+            data_dir: Some(dir.to_path_buf()),
+        })
+    }
+}
+
+/// Files removed by [`purge_dir`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PurgeStats {
+    /// Number of files deleted.
+    pub files: usize,
+    /// Total size of the deleted files, overwritten with zeros first.
+    pub bytes: u64,
+}
+
+impl PurgeStats {
+    /// Adds the counts of `other`.
+    const fn merge(self, other: Self) -> Self {
+        Self {
+            files: self.files.saturating_add(other.files),
+            bytes: self.bytes.saturating_add(other.bytes),
+        }
+    }
+}
+
+/// Overwrites every file under `dir` with zeros, then deletes it.
+///
+/// Subdirectories are removed as well; `dir` itself is kept, and a missing
+/// `dir` counts as empty. Symbolic links are removed without touching their
+/// targets. Overwriting cannot guarantee erasure on SSDs or copy-on-write
+/// filesystems, but it keeps the data out of reach of ordinary recovery tools.
+///
+/// # Errors
+///
+/// Returns an error if an entry cannot be read, overwritten or removed.
+#[inline]
+pub fn purge_dir(dir: &Path) -> io::Result<PurgeStats> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(PurgeStats::default()),
+        Err(err) => return Err(err),
+    };
+    let mut stats = PurgeStats::default();
+    for entry in entries {
+        let path = entry?.path();
+        let file_type = std::fs::symlink_metadata(&path)?.file_type();
+        if file_type.is_dir() {
+            stats = stats.merge(purge_dir(&path)?);
+            std::fs::remove_dir(&path)?;
+        } else {
+            let bytes = if file_type.is_symlink() {
+                0
+            } else {
+                shred_file(&path)?
+            };
+            std::fs::remove_file(&path)?;
+            stats = stats.merge(PurgeStats { files: 1, bytes });
+        }
+    }
+    Ok(stats)
+}
+
+/// Overwrites a file with zeros and flushes it to disk, returning its size.
+fn shred_file(path: &Path) -> io::Result<u64> {
+    let mut file = std::fs::OpenOptions::new().write(true).open(path)?;
+    let len = file.metadata()?.len();
+    let written = io::copy(&mut io::repeat(0).take(len), &mut file)?;
+    file.sync_all()?;
+    Ok(written)
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect for readability"
+)]
+mod tests {
+    use super::{PurgeStats, StorageKind, purge_dir};
+    use crate::persist::TempDir;
+
+    #[test]
+    fn parses_settings() {
+        assert_eq!(StorageKind::from_setting(None), Ok(StorageKind::File));
+        assert_eq!(StorageKind::from_setting(Some(" ")), Ok(StorageKind::File));
+        assert_eq!(
+            StorageKind::from_setting(Some("FILE")),
+            Ok(StorageKind::File)
+        );
+        assert_eq!(
+            StorageKind::from_setting(Some("memory")),
+            Ok(StorageKind::Memory)
+        );
+        assert_eq!(
+            StorageKind::from_setting(Some("in-memory")),
+            Ok(StorageKind::Memory)
+        );
+        assert!(StorageKind::from_setting(Some("sqlite")).is_err());
+    }
+
+    #[test]
+    fn purge_dir_removes_nested_files() {
+        let dir = TempDir::new();
+        std::fs::create_dir(dir.join("nested")).expect("create dir");
+        std::fs::write(dir.join("accounts.json"), b"[1, 2]").expect("write file");
+        std::fs::write(dir.join("nested").join("tags.json"), b"[]").expect("write file");
+        let stats = purge_dir(&dir).expect("purge");
+        assert_eq!(stats, PurgeStats { files: 2, bytes: 8 });
+        assert_eq!(std::fs::read_dir(&dir).expect("dir kept").count(), 0);
+        std::fs::remove_dir(&dir).expect("remove dir");
+        assert_eq!(purge_dir(&dir).expect("missing dir"), PurgeStats::default());
+    }
+}
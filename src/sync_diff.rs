@@ -0,0 +1,150 @@
+//! Per-type change counts between two snapshots of local storage.
+//!
+//! The ZenMoney diff endpoint has no per-type filter, so a sync always
+//! fetches every changed entity and the client reports nothing about what
+//! arrived. To tell the assistant what a sync brought in, the server records
+//! the `changed` stamps of stored entities before the sync and compares them
+//! with the stored data afterwards.
+
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+
+use crate::params::EntityKind;
+use crate::response::ChangeCounts;
+
+/// Version of one stored entity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct EntityStamp {
+    /// `changed` time of the entity, in milliseconds.
+    changed: i64,
+    /// Whether the entity is flagged as deleted (transactions only).
+    deleted: bool,
+}
+
+/// Entity versions by type and ID.
+pub(crate) type Snapshot = BTreeMap<EntityKind, HashMap<String, EntityStamp>>;
+
+/// Builds entity versions from `(id, changed, deleted)` triples.
+pub(crate) fn stamp_entities<I>(entries: I) -> HashMap<String, EntityStamp>
+where
+    I: IntoIterator<Item = (String, DateTime<Utc>, bool)>,
+{
+    entries
+        .into_iter()
+        .map(|(id, changed, deleted)| {
+            let stamp = EntityStamp {
+                changed: changed.timestamp_millis(),
+                deleted,
+            };
+            (id, stamp)
+        })
+        .collect()
+}
+
+/// Counts entities added, updated and deleted between two snapshots, for
+/// each type recorded in `after`.
+pub(crate) fn count_changes(
+    before: &Snapshot,
+    after: &Snapshot,
+) -> BTreeMap<EntityKind, ChangeCounts> {
+    let empty = HashMap::new();
+    after
+        .iter()
+        .map(|(kind, current)| (*kind, compare(before.get(kind).unwrap_or(&empty), current)))
+        .collect()
+}
+
+/// Compares the versions of one entity type.
+///
+/// An entity counts as deleted when it disappears or gains the deleted flag.
+fn compare(
+    before: &HashMap<String, EntityStamp>,
+    after: &HashMap<String, EntityStamp>,
+) -> ChangeCounts {
+    let mut counts = ChangeCounts::default();
+    for (id, stamp) in after {
+        match before.get(id) {
+            None if stamp.deleted => {}
+            None => counts.added = counts.added.saturating_add(1),
+            Some(old) if old == stamp => {}
+            Some(old) if stamp.deleted && !old.deleted => {
+                counts.deleted = counts.deleted.saturating_add(1);
+            }
+            Some(_) => counts.updated = counts.updated.saturating_add(1),
+        }
+    }
+    let removed = before
+        .iter()
+        .filter(|entry| !entry.1.deleted && !after.contains_key(entry.0))
+        .count();
+    counts.deleted = counts.deleted.saturating_add(removed);
+    counts
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect for readability"
+)]
+mod tests {
+    use chrono::DateTime;
+
+    use super::{ChangeCounts, EntityKind, Snapshot, count_changes, stamp_entities};
+
+    #[test]
+    fn counts_added_updated_and_deleted() {
+        let old_time = DateTime::from_timestamp(1_700_000_000, 0).expect("valid timestamp");
+        let new_time = DateTime::from_timestamp(1_700_000_100, 0).expect("valid timestamp");
+        let before = Snapshot::from([(
+            EntityKind::Transaction,
+            stamp_entities(vec![
+                ("kept".to_owned(), old_time, false),
+                ("edited".to_owned(), old_time, false),
+                ("flagged".to_owned(), old_time, false),
+                ("removed".to_owned(), old_time, false),
+            ]),
+        )]);
+        let after = Snapshot::from([
+            (
+                EntityKind::Transaction,
+                stamp_entities(vec![
+                    ("kept".to_owned(), old_time, false),
+                    ("edited".to_owned(), new_time, false),
+                    ("flagged".to_owned(), new_time, true),
+                    ("new".to_owned(), new_time, false),
+                ]),
+            ),
+            (
+                EntityKind::Account,
+                stamp_entities(vec![("acc".to_owned(), old_time, false)]),
+            ),
+        ]);
+        let changes = count_changes(&before, &after);
+        assert_eq!(
+            changes.get(&EntityKind::Transaction),
+            Some(&ChangeCounts {
+                added: 1,
+                updated: 1,
+                deleted: 2,
+            })
+        );
+        assert_eq!(
+            changes.get(&EntityKind::Account),
+            Some(&ChangeCounts {
+                added: 1,
+                updated: 0,
+                deleted: 0,
+            })
+        );
+        assert!(
+            count_changes(&after, &after)
+                .values()
+                .all(|counts| counts.total() == 0)
+        );
+    }
+}
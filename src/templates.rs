@@ -0,0 +1,185 @@
+//! Persistent transaction templates.
+//!
+//! Templates capture the fields of a frequently repeated transaction
+//! ("monthly rent") so it can be recreated with a single tool call. They
+//! are stored as a JSON file next to the ZenMoney storage data.
+
+extern crate alloc;
+
+use alloc::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::params::TransactionType;
+use crate::persist::{lock_err, name_key, read_json_list, write_json_list};
+
+/// File name used for template storage inside the data directory.
+const TEMPLATES_FILE: &str = "templates.json";
+
+/// A saved transaction template.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub(crate) struct TransactionTemplate {
+    /// Template name as entered by the user.
+    pub(crate) name: String,
+    /// Type of transaction to create.
+    pub(crate) transaction_type: TransactionType,
+    /// Primary account ID.
+    pub(crate) account_id: String,
+    /// Destination account ID (transfers only).
+    pub(crate) to_account_id: Option<String>,
+    /// Default amount (may be overridden on use).
+    pub(crate) amount: Option<f64>,
+    /// Category tag IDs.
+    pub(crate) tag_ids: Option<Vec<String>>,
+    /// Payee name.
+    pub(crate) payee: Option<String>,
+    /// User comment.
+    pub(crate) comment: Option<String>,
+    /// Merchant ID to link.
+    pub(crate) merchant_id: Option<String>,
+}
+
+/// Template collection, optionally backed by a JSON file.
+#[derive(Debug, Default)]
+pub struct TemplateStore {
+    /// Path of the backing file (`None` keeps templates in memory only).
+    path: Option<PathBuf>,
+    /// Templates keyed by lowercased name.
+    templates: Mutex<BTreeMap<String, TransactionTemplate>>,
+}
+
+impl TemplateStore {
+    /// Opens the template store inside `dir`, loading any saved templates.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read or parsed.
+    #[inline]
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        let path = dir.join(TEMPLATES_FILE);
+        let templates: Vec<TransactionTemplate> = read_json_list(&path)?;
+        let map = templates
+            .into_iter()
+            .map(|template| (name_key(&template.name), template))
+            .collect();
+        Ok(Self {
+            path: Some(path),
+            templates: Mutex::new(map),
+        })
+    }
+
+    /// Returns all templates sorted by name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned.
+    pub(crate) fn list(&self) -> io::Result<Vec<TransactionTemplate>> {
+        let templates = self.templates.lock().map_err(|err| lock_err(&err))?;
+        Ok(templates.values().cloned().collect())
+    }
+
+    /// Looks up a template by name (case-insensitive).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned.
+    pub(crate) fn get(&self, name: &str) -> io::Result<Option<TransactionTemplate>> {
+        let templates = self.templates.lock().map_err(|err| lock_err(&err))?;
+        Ok(templates.get(&name_key(name)).cloned())
+    }
+
+    /// Saves a template, replacing any template with the same name.
+    ///
+    /// Returns `true` if an existing template was replaced.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned or the file cannot be written.
+    pub(crate) fn save(&self, template: TransactionTemplate) -> io::Result<bool> {
+        let mut templates = self.templates.lock().map_err(|err| lock_err(&err))?;
+        let replaced = templates
+            .insert(name_key(&template.name), template)
+            .is_some();
+        // Keep the lock while writing so concurrent saves cannot interleave.
+        let persisted = self.persist(&templates);
+        drop(templates);
+        persisted.map(|()| replaced)
+    }
+
+    /// Deletes all templates, returning how many there were.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the lock is poisoned or the file cannot be written.
+    pub(crate) fn clear(&self) -> io::Result<usize> {
+        let mut templates = self.templates.lock().map_err(|err| lock_err(&err))?;
+        let count = templates.len();
+        templates.clear();
+        let persisted = self.persist(&templates);
+        drop(templates);
+        persisted.map(|()| count)
+    }
+
+    /// Writes all templates to the backing file, if any.
+    fn persist(&self, templates: &BTreeMap<String, TransactionTemplate>) -> io::Result<()> {
+        let Some(path) = self.path.as_ref() else {
+            return Ok(());
+        };
+        let values: Vec<&TransactionTemplate> = templates.values().collect();
+        write_json_list(path, &values)
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::indexing_slicing,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect and indexing for readability"
+)]
+mod tests {
+    use super::{TemplateStore, TransactionTemplate};
+    use crate::params::TransactionType;
+    use crate::persist::TempDir;
+
+    fn rent() -> TransactionTemplate {
+        TransactionTemplate {
+            name: "Monthly Rent".to_owned(),
+            transaction_type: TransactionType::Expense,
+            account_id: "acc-1".to_owned(),
+            to_account_id: None,
+            amount: Some(30_000.0),
+            tag_ids: Some(vec!["tag-1".to_owned()]),
+            payee: Some("Landlord".to_owned()),
+            comment: None,
+            merchant_id: None,
+        }
+    }
+
+    #[test]
+    fn in_memory_save_and_get_case_insensitive() {
+        let store = TemplateStore::default();
+        assert!(!store.save(rent()).expect("should save"));
+        let found = store.get("  monthly rent ").expect("lock").expect("found");
+        assert_eq!(found.payee.as_deref(), Some("Landlord"));
+        assert!(store.save(rent()).expect("should save"));
+        assert_eq!(store.list().expect("lock").len(), 1);
+    }
+
+    #[test]
+    fn persists_to_file() {
+        let dir = TempDir::new();
+        let store = TemplateStore::open(&dir).expect("open empty");
+        assert!(store.list().expect("lock").is_empty());
+        let _replaced = store.save(rent()).expect("should save");
+
+        let reopened = TemplateStore::open(&dir).expect("reopen");
+        let templates = reopened.list().expect("lock");
+        assert_eq!(templates.len(), 1);
+        assert_eq!(templates[0].name, "Monthly Rent");
+    }
+}
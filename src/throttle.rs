@@ -0,0 +1,251 @@
+//! Client-side pacing and coalescing of ZenMoney API calls.
+//!
+//! Agents often fan out several tool calls at once. [`RateLimiter`] spaces
+//! API requests at least `ZENMONEY_MIN_REQUEST_INTERVAL_MS` apart (200 ms by
+//! default) so bursts do not trip upstream throttling, and
+//! [`SyncCoalescer`] lets concurrent sync requests share one in-flight sync
+//! instead of each issuing its own.
+
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::time::Duration;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Environment variable setting the minimum interval between API requests, in milliseconds.
+pub const MIN_REQUEST_INTERVAL_ENV: &str = "ZENMONEY_MIN_REQUEST_INTERVAL_MS";
+
+/// Interval used when `ZENMONEY_MIN_REQUEST_INTERVAL_MS` is not set.
+pub const DEFAULT_MIN_REQUEST_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Parses the minimum request interval in milliseconds.
+///
+/// An unset or blank value selects [`DEFAULT_MIN_REQUEST_INTERVAL`]; `0`
+/// turns pacing off.
+///
+/// # Errors
+///
+/// Returns a message if `value` is not a whole number of milliseconds.
+#[inline]
+pub fn parse_min_request_interval(value: Option<&str>) -> Result<Duration, String> {
+    let Some(raw) = value.map(str::trim).filter(|raw| !raw.is_empty()) else {
+        return Ok(DEFAULT_MIN_REQUEST_INTERVAL);
+    };
+    raw.parse().map(Duration::from_millis).map_err(|err| {
+        format!("invalid {MIN_REQUEST_INTERVAL_ENV} value '{raw}': {err}; expected milliseconds")
+    })
+}
+
+/// Spaces requests at least a fixed interval apart.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    /// Minimum time between two requests; zero disables pacing.
+    interval: Duration,
+    /// Earliest time the next request may start.
+    next_slot: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    /// Creates a limiter spacing requests `interval` apart.
+    pub(crate) const fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            next_slot: Mutex::new(None),
+        }
+    }
+
+    /// Returns the minimum time between two requests.
+    pub(crate) const fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Reserves the next request slot and waits until it starts.
+    ///
+    /// Slots are handed out in call order, so concurrent callers are
+    /// released one interval apart.
+    pub(crate) async fn pace(&self) {
+        if self.interval.is_zero() {
+            return;
+        }
+        let wait = {
+            let Ok(mut next_slot) = self.next_slot.lock() else {
+                return;
+            };
+            let now = Instant::now();
+            let slot = next_slot.map_or(now, |at| at.max(now));
+            *next_slot = slot.checked_add(self.interval);
+            slot.saturating_duration_since(now)
+        };
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Lets concurrent callers share one in-flight sync.
+#[derive(Debug, Default)]
+pub(crate) struct SyncCoalescer {
+    /// Held while a sync runs.
+    running: tokio::sync::Mutex<()>,
+    /// Number of syncs completed successfully.
+    completed: AtomicU64,
+}
+
+impl SyncCoalescer {
+    /// Runs `sync`, unless another sync completed while this call waited
+    /// for it; then that sync's result is reused and `Ok(None)` returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `sync` if it ran and failed.
+    pub(crate) async fn run<F, T, E>(&self, sync: F) -> Result<Option<T>, E>
+    where
+        F: Future<Output = Result<T, E>>,
+    {
+        let seen = self.completed.load(Ordering::Acquire);
+        let _running = self.running.lock().await;
+        if self.completed.load(Ordering::Acquire) != seen {
+            return Ok(None);
+        }
+        let value = sync.await?;
+        let _previous = self.completed.fetch_add(1, Ordering::AcqRel);
+        Ok(Some(value))
+    }
+
+    /// Runs `sync` once any sync in flight has finished, never reusing
+    /// another result; syncs waiting meanwhile reuse this one.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of `sync` if it failed.
+    pub(crate) async fn run_exclusive<F, T, E>(&self, sync: F) -> Result<T, E>
+    where
+        F: Future<Output = Result<T, E>>,
+    {
+        let _running = self.running.lock().await;
+        let value = sync.await?;
+        let _previous = self.completed.fetch_add(1, Ordering::AcqRel);
+        Ok(value)
+    }
+
+    /// Runs `work` while no sync runs, without counting it as a sync: syncs
+    /// waiting meanwhile run afterwards instead of reusing it.
+    pub(crate) async fn run_alone<F: Future>(&self, work: F) -> F::Output {
+        let _running = self.running.lock().await;
+        work.await
+    }
+
+    /// Waits until no sync is running.
+    pub(crate) async fn wait_idle(&self) {
+        drop(self.running.lock().await);
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::expect_used,
+    clippy::missing_docs_in_private_items,
+    reason = "test code uses expect for readability"
+)]
+mod tests {
+    extern crate alloc;
+
+    use alloc::sync::Arc;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use core::time::Duration;
+    use std::time::Instant;
+
+    use super::{
+        DEFAULT_MIN_REQUEST_INTERVAL, RateLimiter, SyncCoalescer, parse_min_request_interval,
+    };
+
+    #[test]
+    fn parses_min_request_interval() {
+        assert_eq!(
+            parse_min_request_interval(None),
+            Ok(DEFAULT_MIN_REQUEST_INTERVAL)
+        );
+        assert_eq!(
+            parse_min_request_interval(Some("50")),
+            Ok(Duration::from_millis(50))
+        );
+        assert_eq!(parse_min_request_interval(Some("0")), Ok(Duration::ZERO));
+        assert!(parse_min_request_interval(Some("-1")).is_err());
+    }
+
+    #[tokio::test]
+    async fn limiter_spaces_requests() {
+        let limiter = RateLimiter::new(Duration::from_millis(20));
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.pace().await;
+        }
+        assert!(start.elapsed() >= Duration::from_millis(40));
+    }
+
+    #[tokio::test]
+    async fn concurrent_syncs_share_one_run() {
+        let gate = Arc::new(SyncCoalescer::default());
+        let runs = Arc::new(AtomicUsize::new(0));
+        let sync = |gate: Arc<SyncCoalescer>, runs: Arc<AtomicUsize>| async move {
+            gate.run(async {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                Ok::<usize, ()>(runs.fetch_add(1, Ordering::SeqCst))
+            })
+            .await
+        };
+        let first = tokio::spawn(sync(Arc::clone(&gate), Arc::clone(&runs)));
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        let second = tokio::spawn(sync(Arc::clone(&gate), Arc::clone(&runs)));
+        assert_eq!(first.await.expect("join"), Ok(Some(0)));
+        assert_eq!(second.await.expect("join"), Ok(None));
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn exclusive_sync_waits_and_is_reused() {
+        let gate = Arc::new(SyncCoalescer::default());
+        let runs = Arc::new(AtomicUsize::new(0));
+        let full = |gate: Arc<SyncCoalescer>, runs: Arc<AtomicUsize>| async move {
+            gate.run_exclusive(async {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                Ok::<usize, ()>(runs.fetch_add(1, Ordering::SeqCst))
+            })
+            .await
+        };
+        let first = tokio::spawn(full(Arc::clone(&gate), Arc::clone(&runs)));
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        let second = tokio::spawn(full(Arc::clone(&gate), Arc::clone(&runs)));
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        let incremental = {
+            let (gate, runs) = (Arc::clone(&gate), Arc::clone(&runs));
+            tokio::spawn(async move {
+                gate.run(async { Ok::<usize, ()>(runs.fetch_add(1, Ordering::SeqCst)) })
+                    .await
+            })
+        };
+        assert_eq!(first.await.expect("join"), Ok(0));
+        assert_eq!(second.await.expect("join"), Ok(1));
+        assert_eq!(incremental.await.expect("join"), Ok(None));
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn work_alone_waits_and_is_not_reused() {
+        let gate = Arc::new(SyncCoalescer::default());
+        let runs = Arc::new(AtomicUsize::new(0));
+        let work = {
+            let gate = Arc::clone(&gate);
+            tokio::spawn(async move {
+                gate.run_alone(tokio::time::sleep(Duration::from_millis(20)))
+                    .await;
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        let synced = gate
+            .run(async { Ok::<usize, ()>(runs.fetch_add(1, Ordering::SeqCst)) })
+            .await;
+        work.await.expect("join");
+        assert_eq!(synced, Ok(Some(0)));
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+}